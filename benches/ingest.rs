@@ -0,0 +1,97 @@
+//! Collector throughput on synthetic traffic: `cargo bench --bench ingest`.
+//!
+//! Set `MAVSNARK_MIN_RATE` (messages per second) to fail when any scenario
+//! comes in slower, e.g. in CI.
+
+use std::{
+    process::ExitCode,
+    time::{Duration, Instant},
+};
+
+use mavsnark::{
+    collector::{Collector, Limits},
+    loadgen::LoadGenerator,
+};
+
+/// Messages pushed per scenario, after a warm-up of the same size.
+const MESSAGES: usize = 500_000;
+
+struct Scenario {
+    name: &'static str,
+    /// Simulated arrival rate in Hz, which sets the timestamps.
+    rate: f64,
+    vehicles: u8,
+    event_share: f64,
+}
+
+const SCENARIOS: &[Scenario] = &[
+    Scenario {
+        name: "one vehicle",
+        // Every stream in `loadgen::TELEMETRY` at its own rate.
+        rate: 159.0,
+        vehicles: 1,
+        event_share: 0.0,
+    },
+    Scenario {
+        name: "swarm of 20",
+        rate: 20.0 * 159.0,
+        vehicles: 20,
+        event_share: 0.0,
+    },
+    Scenario {
+        name: "busy link",
+        rate: 100_000.0,
+        vehicles: 4,
+        event_share: 0.0,
+    },
+    Scenario {
+        name: "10% events",
+        rate: 1_000.0,
+        vehicles: 2,
+        event_share: 0.1,
+    },
+];
+
+fn run(scenario: &Scenario) -> f64 {
+    let mut generator = LoadGenerator::new(scenario.rate)
+        .with_vehicles(scenario.vehicles)
+        .with_event_share(scenario.event_share);
+    let mut collector = Collector::new();
+    collector.set_limits(Limits {
+        max_events: Some(10_000),
+        ..Limits::default()
+    });
+    for msg in generator.by_ref().take(MESSAGES) {
+        collector.push(msg);
+    }
+    // Generating is not what is measured.
+    let batch: Vec<_> = generator.take(MESSAGES).collect();
+    let started = Instant::now();
+    for msg in batch {
+        collector.push(msg);
+    }
+    MESSAGES as f64 / started.elapsed().max(Duration::from_nanos(1)).as_secs_f64()
+}
+
+fn main() -> ExitCode {
+    let min_rate: Option<f64> = std::env::var("MAVSNARK_MIN_RATE")
+        .ok()
+        .and_then(|v| v.parse().ok());
+    let mut slow = false;
+    for scenario in SCENARIOS {
+        let rate = run(scenario);
+        let verdict = match min_rate {
+            Some(min) if rate < min => {
+                slow = true;
+                "  below MAVSNARK_MIN_RATE"
+            }
+            _ => "",
+        };
+        println!("{:<14} {:>10.0} msg/s{verdict}", scenario.name, rate);
+    }
+    if slow {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
@@ -0,0 +1,16 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use mavsnark::{collector::Collector, frame::FrameParser};
+
+// The first byte sets the read size, so frames also arrive split.
+fuzz_target!(|data: &[u8]| {
+    let Some((&chunk, bytes)) = data.split_first() else {
+        return;
+    };
+    let mut collector = Collector::new();
+    let mut parser = FrameParser::new();
+    for read in bytes.chunks(usize::from(chunk).max(1)) {
+        collector.ingest(&mut parser, read, 0);
+    }
+});
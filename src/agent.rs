@@ -0,0 +1,307 @@
+use std::{
+    io::{self, BufRead, BufReader, BufWriter, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        Arc, Mutex,
+        mpsc::{self, Receiver as Queue, SyncSender, TrySendError},
+    },
+    thread,
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use tokio::sync::mpsc::{Receiver, Sender};
+
+use crate::{
+    message::Incoming,
+    session::{self, SessionLog, SessionMeta},
+    signing,
+};
+
+/// An attached TUI that stops reading for this long is dropped, as is one
+/// that takes longer to send its token.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Records waiting for an attached TUI; one that falls further behind than
+/// this (about a minute of a busy link) is dropped, so it cannot stall
+/// capture or hold memory for the others.
+const CLIENT_QUEUE: usize = 65_536;
+
+/// Longest token line read from a TUI before it is turned away.
+const MAX_TOKEN_LEN: u64 = 1024;
+
+/// Headless capture for `--agent`: keeps everything received and streams it
+/// to TUIs started with `--attach`, so the radio link is only opened once,
+/// on the machine next to it.
+///
+/// An attached TUI first sends the agent's token on one line (an empty one
+/// without `--agent-token`), then reads a session (see [`session::Session`])
+/// that never ends: the header, the history so far, then each record as it
+/// arrives. Each TUI is written to from its own thread.
+pub struct Agent {
+    addr: SocketAddr,
+    hub: Arc<Mutex<Hub>>,
+}
+
+struct Hub {
+    links: Vec<String>,
+    log: SessionLog,
+    clients: Vec<(SocketAddr, SyncSender<Arc<[u8]>>)>,
+}
+
+impl Agent {
+    /// Listens on `listen` for TUIs; `links` are the capture's URIs as shown
+    /// to them. History beyond `max_memory` bytes, or
+    /// [`session::DEFAULT_LOG_BYTES`] without it, is dropped oldest first.
+    /// Only TUIs that send `token` are served; without one the agent only
+    /// listens on loopback addresses.
+    pub fn bind(
+        listen: &str,
+        links: Vec<String>,
+        max_memory: Option<u64>,
+        token: Option<String>,
+    ) -> io::Result<Self> {
+        let listener = TcpListener::bind(listen)?;
+        let addr = listener.local_addr()?;
+        if token.is_none() && !addr.ip().is_loopback() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "--agent-token is required to listen beyond localhost",
+            ));
+        }
+        let mut log = SessionLog::default();
+        if let Some(max) = max_memory {
+            log.set_max_bytes(max);
+        }
+        let hub = Arc::new(Mutex::new(Hub {
+            links,
+            log,
+            clients: Vec::new(),
+        }));
+        let accepting = Arc::clone(&hub);
+        let token: Arc<str> = token.unwrap_or_default().into();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        tracing::warn!(error = %e, "agent accept failed");
+                        continue;
+                    }
+                };
+                let hub = Arc::clone(&accepting);
+                let token = Arc::clone(&token);
+                thread::spawn(move || serve_client(stream, &hub, &token));
+            }
+        });
+        tracing::info!(%addr, "agent listening");
+        Ok(Self { addr, hub })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Keeps `incoming` and queues it for every attached TUI.
+    pub fn publish(&self, at: DateTime<Utc>, incoming: &Incoming) {
+        let mut record = Vec::new();
+        // Writing to memory cannot fail.
+        let _ = session::write_record(&mut record, at, incoming);
+        let record: Arc<[u8]> = record.into();
+        let mut hub = self.hub.lock().unwrap();
+        hub.log.push(at, incoming);
+        hub.clients
+            .retain(|(peer, queue)| match queue.try_send(Arc::clone(&record)) {
+                Ok(()) => true,
+                Err(TrySendError::Full(_)) => {
+                    tracing::warn!(%peer, "TUI fell behind, dropped");
+                    false
+                }
+                Err(TrySendError::Disconnected(_)) => {
+                    tracing::info!(%peer, "TUI detached");
+                    false
+                }
+            });
+    }
+
+    /// Publishes everything from the link readers until they all end.
+    pub async fn serve(&self, mut rx: Receiver<Incoming>) {
+        while let Some(incoming) = rx.recv().await {
+            self.publish(Utc::now(), &incoming);
+        }
+    }
+}
+
+/// Runs on a TUI's own thread: checks its token, then sends the header,
+/// the history and its queued records until it detaches or falls behind.
+fn serve_client(stream: TcpStream, hub: &Mutex<Hub>, token: &str) {
+    let peer = match stream.peer_addr() {
+        Ok(peer) => peer,
+        Err(e) => {
+            tracing::warn!(error = %e, "agent accept failed");
+            return;
+        }
+    };
+    match read_token(&stream) {
+        Ok(sent) if signing::constant_time_eq(sent.as_bytes(), token.as_bytes()) => {}
+        Ok(_) => {
+            tracing::warn!(%peer, "TUI sent the wrong token");
+            return;
+        }
+        Err(e) => {
+            tracing::warn!(%peer, error = %e, "TUI sent no token");
+            return;
+        }
+    }
+    let (history, links, queue) = {
+        let mut hub = hub.lock().unwrap();
+        let (tx, rx) = mpsc::sync_channel(CLIENT_QUEUE);
+        hub.clients.push((peer, tx));
+        (hub.log.as_bytes().to_vec(), hub.links.clone(), rx)
+    };
+    tracing::info!(%peer, "TUI attached");
+    if let Err(e) = send(stream, &links, &history, &queue) {
+        tracing::info!(%peer, error = %e, "TUI detached");
+    }
+}
+
+fn read_token(stream: &TcpStream) -> io::Result<String> {
+    stream.set_read_timeout(Some(WRITE_TIMEOUT))?;
+    let mut line = String::new();
+    BufReader::new(stream.take(MAX_TOKEN_LEN)).read_line(&mut line)?;
+    if !line.ends_with('\n') {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "token line too long or cut short",
+        ));
+    }
+    line.pop();
+    Ok(line)
+}
+
+fn send(
+    stream: TcpStream,
+    links: &[String],
+    history: &[u8],
+    queue: &Queue<Arc<[u8]>>,
+) -> io::Result<()> {
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+    stream.set_nodelay(true)?;
+    let mut out = BufWriter::new(stream);
+    let meta = SessionMeta {
+        links: links.to_vec(),
+        ..Default::default()
+    };
+    session::write_header(&mut out, &meta)?;
+    out.write_all(history)?;
+    out.flush()?;
+    // Ends once `publish` drops the queue's sender.
+    while let Ok(record) = queue.recv() {
+        out.write_all(&record)?;
+        // Only flush once caught up, so a backlog goes out in large writes.
+        while let Ok(record) = queue.try_recv() {
+            out.write_all(&record)?;
+        }
+        out.flush()?;
+    }
+    Ok(())
+}
+
+/// Connects to an agent at `addr`, sends it `token`, and forwards its
+/// history, then its live traffic, to `tx` from a reader thread. Returns the
+/// agent's link URIs.
+pub fn attach(addr: &str, token: Option<&str>, tx: Sender<Incoming>) -> io::Result<Vec<String>> {
+    let context = |e: io::Error| io::Error::new(e.kind(), format!("{addr}: {e}"));
+    let mut stream = TcpStream::connect(addr).map_err(context)?;
+    writeln!(stream, "{}", token.unwrap_or_default()).map_err(context)?;
+    let mut input = BufReader::new(stream);
+    let meta = session::read_header(&mut input).map_err(|e| match e.kind() {
+        io::ErrorKind::UnexpectedEof => context(io::Error::new(
+            e.kind(),
+            "the agent closed the connection (wrong --agent-token?)",
+        )),
+        _ => context(e),
+    })?;
+    let addr = addr.to_string();
+    thread::spawn(move || {
+        loop {
+            match session::read_record(&mut input) {
+                Ok(Some((_, incoming))) => {
+                    if tx.blocking_send(incoming).is_err() {
+                        return;
+                    }
+                }
+                Ok(None) => {
+                    tracing::info!(addr, "agent closed the connection");
+                    return;
+                }
+                Err(e) => {
+                    tracing::error!(addr, error = %e, "agent stream failed");
+                    return;
+                }
+            }
+        }
+    });
+    Ok(meta.links)
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::{
+        MavHeader,
+        common::{HEARTBEAT_DATA, MavMessage},
+    };
+
+    use super::*;
+    use crate::message::MavMsg;
+
+    fn heartbeat(system_id: u8) -> Incoming {
+        let header = MavHeader {
+            system_id,
+            component_id: 1,
+            sequence: 0,
+        };
+        Incoming::Message(MavMsg::new(
+            header,
+            MavMessage::HEARTBEAT(HEARTBEAT_DATA::default()),
+            0,
+        ))
+    }
+
+    fn system_id(incoming: Incoming) -> u8 {
+        match incoming {
+            Incoming::Message(msg) => msg.header.system_id,
+            Incoming::Diagnostic(diag) => diag.header.system_id,
+        }
+    }
+
+    #[test]
+    fn attached_tui_gets_history_then_live_traffic() {
+        let links = vec!["udpin:0.0.0.0:14550".into()];
+        let agent = Agent::bind("127.0.0.1:0", links, None, Some("hunter2".into())).unwrap();
+        agent.publish(Utc::now(), &heartbeat(1));
+
+        let (tx, mut rx) = tokio::sync::mpsc::channel(16);
+        let links = attach(&agent.local_addr().to_string(), Some("hunter2"), tx).unwrap();
+        assert_eq!(links, ["udpin:0.0.0.0:14550"]);
+        assert_eq!(system_id(rx.blocking_recv().unwrap()), 1);
+
+        agent.publish(Utc::now(), &heartbeat(2));
+        assert_eq!(system_id(rx.blocking_recv().unwrap()), 2);
+    }
+
+    #[test]
+    fn wrong_token_is_turned_away() {
+        let agent = Agent::bind("127.0.0.1:0", Vec::new(), None, Some("hunter2".into())).unwrap();
+        let (tx, _rx) = tokio::sync::mpsc::channel(16);
+        let addr = agent.local_addr().to_string();
+        assert!(attach(&addr, Some("hunter3"), tx.clone()).is_err());
+        assert!(attach(&addr, None, tx).is_err());
+    }
+
+    #[test]
+    fn token_required_beyond_localhost() {
+        assert!(Agent::bind("0.0.0.0:0", Vec::new(), None, None).is_err());
+        assert!(Agent::bind("127.0.0.1:0", Vec::new(), None, None).is_ok());
+    }
+}
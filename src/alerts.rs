@@ -0,0 +1,269 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use ratatui::style::Color;
+
+use crate::{config::Config, entries::StreamEntry};
+
+#[derive(Debug, PartialEq)]
+pub enum RateAlertKind {
+    Deviation,
+    Recovered,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct RateAlert {
+    pub sys_id: u8,
+    pub comp_id: u8,
+    pub name: &'static str,
+    /// Link the stream last arrived on.
+    pub link: usize,
+    pub kind: RateAlertKind,
+    pub expected: f64,
+    pub measured: f64,
+}
+
+impl RateAlert {
+    pub fn event_name(&self) -> &'static str {
+        match self.kind {
+            RateAlertKind::Deviation => "RATE_ANOMALY",
+            RateAlertKind::Recovered => "RATE_RECOVERED",
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "message: {}, expected_hz: {:.1}, measured_hz: {:.1}",
+            self.name, self.expected, self.measured
+        )
+    }
+}
+
+/// A stream row's rate against what is expected of it, shown next to the
+/// row.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RateBadge {
+    Ok,
+    Slow,
+    Fast,
+    /// Gone quiet.
+    Missing,
+}
+
+impl RateBadge {
+    pub fn label(self) -> &'static str {
+        match self {
+            RateBadge::Ok => "\u{2713}",
+            RateBadge::Slow => "slow",
+            RateBadge::Fast => "fast",
+            RateBadge::Missing => "missing",
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            RateBadge::Ok => Color::Green,
+            RateBadge::Slow | RateBadge::Fast => Color::Yellow,
+            RateBadge::Missing => Color::LightRed,
+        }
+    }
+}
+
+struct Deviation {
+    since: DateTime<Utc>,
+    alerted: bool,
+}
+
+/// Compares measured stream rates against the configured expectations.
+pub struct RateMonitor {
+    expected: HashMap<String, f64>,
+    tolerance: f64,
+    hold: TimeDelta,
+    deviations: HashMap<(u8, u8, &'static str), Deviation>,
+}
+
+impl RateMonitor {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            expected: config.expected_rates.clone(),
+            tolerance: config.rate_alert.tolerance,
+            hold: TimeDelta::milliseconds((config.rate_alert.hold_secs * 1000.0) as i64),
+            deviations: HashMap::new(),
+        }
+    }
+
+    pub fn check(&mut self, stream: &[StreamEntry], now: DateTime<Utc>) -> Vec<RateAlert> {
+        let mut alerts = Vec::new();
+        for entry in stream {
+            let Some(&expected) = self.expected.get(entry.name) else {
+                continue;
+            };
+            let Some(measured) = entry.arrivals.recent_hz(now) else {
+                continue;
+            };
+            let key = (entry.sys_id, entry.comp_id, entry.name);
+            let alert = |kind| RateAlert {
+                sys_id: entry.sys_id,
+                comp_id: entry.comp_id,
+                name: entry.name,
+                link: entry.link,
+                kind,
+                expected,
+                measured,
+            };
+
+            if (measured - expected).abs() > expected * self.tolerance {
+                let deviation = self.deviations.entry(key).or_insert(Deviation {
+                    since: now,
+                    alerted: false,
+                });
+                if !deviation.alerted && now.signed_duration_since(deviation.since) >= self.hold {
+                    deviation.alerted = true;
+                    alerts.push(alert(RateAlertKind::Deviation));
+                }
+            } else if let Some(deviation) = self.deviations.remove(&key)
+                && deviation.alerted
+            {
+                alerts.push(alert(RateAlertKind::Recovered));
+            }
+        }
+        alerts
+    }
+
+    /// How `entry` is doing against its `[expected_rates]` entry, or else
+    /// against `configured`, e.g. the rate MESSAGE_INTERVAL reports; `None`
+    /// when nothing is expected of it. Unlike [`RateMonitor::check`] this
+    /// is the rate right now, without the hold time.
+    pub fn badge(
+        &self,
+        entry: &StreamEntry,
+        configured: Option<f64>,
+        now: DateTime<Utc>,
+    ) -> Option<RateBadge> {
+        let expected = self.expected.get(entry.name).copied().or(configured)?;
+        let Some(measured) = entry
+            .arrivals
+            .recent_hz(now)
+            .filter(|_| !entry.is_stale(now))
+        else {
+            return Some(RateBadge::Missing);
+        };
+        Some(if measured < expected * (1.0 - self.tolerance) {
+            RateBadge::Slow
+        } else if measured > expected * (1.0 + self.tolerance) {
+            RateBadge::Fast
+        } else {
+            RateBadge::Ok
+        })
+    }
+
+    pub fn clear(&mut self) {
+        self.deviations.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::style::Color;
+
+    use super::*;
+    use crate::{direction::Direction, rate::ArrivalStats, signing::SignatureStatus};
+
+    fn monitor() -> RateMonitor {
+        let mut config = Config::default();
+        config.expected_rates.insert("ATTITUDE".to_string(), 10.0);
+        RateMonitor::new(&config)
+    }
+
+    fn at(ms: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp_millis(ms).unwrap()
+    }
+
+    fn entry(name: &'static str, interval_ms: i64, until_ms: i64) -> StreamEntry {
+        let mut arrivals = ArrivalStats::default();
+        let mut t = 0;
+        while t <= until_ms {
+            arrivals.record(at(t));
+            t += interval_ms;
+        }
+        StreamEntry {
+            sys_color: Color::Red,
+            comp_color: Color::Red,
+            msg_color: None,
+            sys_id: 1,
+            comp_id: 1,
+            name,
+            fields: String::new(),
+            v1: false,
+            signature: SignatureStatus::Unsigned,
+            timestamp: at(until_ms),
+            link: 0,
+            arrivals,
+            direction: Direction::Downlink,
+            payload: Vec::new(),
+            frame: Vec::new(),
+            history: Default::default(),
+        }
+    }
+
+    #[test]
+    fn on_rate_stream_is_quiet() {
+        let mut m = monitor();
+        let e = [entry("ATTITUDE", 100, 5000)];
+        assert!(m.check(&e, at(5000)).is_empty());
+        assert!(m.check(&e, at(5050)).is_empty());
+    }
+
+    #[test]
+    fn slow_stream_alerts_after_hold() {
+        let mut m = monitor();
+        let e = [entry("ATTITUDE", 250, 10_000)];
+        assert!(m.check(&e, at(10_000)).is_empty());
+        let alerts = m.check(&e, at(13_000));
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].kind, RateAlertKind::Deviation);
+        // Only once per deviation.
+        assert!(m.check(&e, at(13_100)).is_empty());
+    }
+
+    #[test]
+    fn recovery_is_reported() {
+        let mut m = monitor();
+        let slow = [entry("ATTITUDE", 250, 10_000)];
+        m.check(&slow, at(10_000));
+        m.check(&slow, at(13_000));
+        let fine = [entry("ATTITUDE", 100, 13_000)];
+        let alerts = m.check(&fine, at(13_000));
+        assert_eq!(alerts[0].kind, RateAlertKind::Recovered);
+    }
+
+    #[test]
+    fn badges() {
+        let m = monitor();
+        let badge = |e: &StreamEntry, configured, now| m.badge(e, configured, at(now));
+        assert_eq!(
+            badge(&entry("ATTITUDE", 100, 5000), None, 5000),
+            Some(RateBadge::Ok)
+        );
+        assert_eq!(
+            badge(&entry("ATTITUDE", 250, 5000), None, 5000),
+            Some(RateBadge::Slow)
+        );
+        assert_eq!(
+            badge(&entry("ATTITUDE", 100, 5000), None, 9000),
+            Some(RateBadge::Missing)
+        );
+        // MESSAGE_INTERVAL fills in for names the config leaves out.
+        let heartbeat = entry("HEARTBEAT", 100, 5000);
+        assert_eq!(badge(&heartbeat, None, 5000), None);
+        assert_eq!(badge(&heartbeat, Some(1.0), 5000), Some(RateBadge::Fast));
+    }
+
+    #[test]
+    fn unconfigured_streams_are_ignored() {
+        let mut m = monitor();
+        let e = [entry("HEARTBEAT", 5000, 10_000)];
+        m.check(&e, at(10_000));
+        assert!(m.check(&e, at(20_000)).is_empty());
+    }
+}
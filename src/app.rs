@@ -1,22 +1,369 @@
-use std::{io, sync::LazyLock};
+use std::{
+    cell::{Ref, RefCell},
+    collections::{HashMap, VecDeque},
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::PathBuf,
+    sync::{Arc, LazyLock},
+    time::{Duration, Instant},
+};
 
-use crossterm::event::{Event, EventStream, KeyCode, KeyEventKind, KeyModifiers};
+use chrono::{DateTime, Local, TimeDelta, Utc};
+use clap::ValueEnum;
+use crossterm::{
+    event::{Event, EventStream, KeyCode, KeyEventKind, KeyModifiers},
+    execute,
+    terminal::SetTitle,
+};
 use futures::StreamExt;
+use mavlink::{
+    MavHeader, Message,
+    common::{
+        COMMAND_LONG_DATA, CellularNetworkFailedReason, CellularStatusFlag, MavCmd, MavMessage,
+        MavPowerStatus, UavcanNodeHealth,
+    },
+};
 use ratatui::{
     DefaultTerminal, Frame,
-    layout::{Constraint, Layout},
+    layout::{Constraint, Layout, Rect},
     style::{Color, Style, Stylize},
+    symbols,
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState},
+    widgets::{
+        Axis, Bar, BarChart, BarGroup, Block, Borders, Chart, Clear, Dataset, GraphType, Paragraph,
+        Scrollbar, ScrollbarOrientation, ScrollbarState, Tabs, Wrap,
+    },
 };
+use serde::{Deserialize, Serialize};
 use tokio::time::MissedTickBehavior;
 
-use crate::{collector::Collector, message::MavMsg, scroll::ScrollState};
+use crate::{
+    alerts::RateBadge,
+    autosave::Recorder,
+    battery::{self, Battery},
+    bytemap::{FieldSpan, LayoutCache},
+    capabilities::{self, Component},
+    cellular::{self, Modem},
+    clock::{self, ClockEstimate},
+    collector::{Collector, Limits},
+    config::Config,
+    connection::Link,
+    dedup::DedupMode,
+    derived::DerivedFields,
+    direction::Direction,
+    distance::{self, Health},
+    entries::{EventKind, MessageEntry, StreamEntry, parse_fields},
+    export::{self, Record},
+    fallback::Fallback,
+    filter::{self, Filter},
+    flow,
+    follow::{self, Separation},
+    frame::{self, Region},
+    gps::{self, GpsFix},
+    highlight::Highlights,
+    home,
+    http::{self, HttpServer},
+    imu::{self, Agreement, ImuReading},
+    interference::{self, Basis},
+    intervals::{AuditRow, Interval, Status},
+    keymap::{Action, KeyMap},
+    map::MapTracker,
+    message::{self, Incoming, id_color},
+    mirror::EventMirror,
+    modes::{self, ModeScheme},
+    navigation::{NavSample, Navigation},
+    notify::Notifier,
+    palette::Palette,
+    params::{self, ParamOrder},
+    pcap::{self, PcapngWriter},
+    perf::{self, PerfMeter},
+    plot::{self, FieldPlot},
+    power::{self, PowerSample},
+    profile::{self, Profile},
+    radio::{self, RadioSample},
+    rate::ArrivalStats,
+    replay::Replay,
+    safety::{self, AreaStatus, ArmOutcome},
+    sanitize::{self, Sanitizer},
+    scroll::ScrollState,
+    search::{self, Hit, Target},
+    session::{self, Session, SessionLog, SessionMeta},
+    severity::{Classifier, Severity},
+    signing::{self, SignatureStatus},
+    snapshot,
+    state::UiState,
+    statustext, talkers,
+    template::Template,
+    terrain::{Clearance, Terrain},
+    throughput::RateHistory,
+    trigger::RingCapture,
+    uavcan,
+    vibration::{self, VibrationSample},
+    vtol::{self, TransitionOutcome},
+    websocket::WsServer,
+    winch::{self, Winch, WinchSample},
+};
 
-#[derive(Debug, PartialEq)]
-enum Panel {
+/// A top-level view, selected with the number keys.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Tab {
     Stream,
-    Messages,
+    #[default]
+    Events,
+    Stats,
+    Params,
+    Console,
+    Map,
+}
+
+impl Tab {
+    /// Every tab, in display order. `Tab as usize` indexes into this.
+    const ALL: [Tab; 6] = [
+        Tab::Stream,
+        Tab::Events,
+        Tab::Stats,
+        Tab::Params,
+        Tab::Console,
+        Tab::Map,
+    ];
+
+    fn title(self) -> &'static str {
+        match self {
+            Tab::Stream => "Stream",
+            Tab::Events => "Events",
+            Tab::Stats => "Stats",
+            Tab::Params => "Params",
+            Tab::Console => "Console",
+            Tab::Map => "Map",
+        }
+    }
+}
+
+/// Below this many columns `LayoutMode::Auto` stacks the panels.
+const NARROW: u16 = 100;
+
+/// How the list and detail panels share the screen.
+#[derive(Debug, Clone, Copy, PartialEq, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LayoutMode {
+    /// Side by side, stacked when the terminal is narrow
+    #[default]
+    Auto,
+    /// Always side by side
+    Horizontal,
+    /// Always stacked, list above detail
+    Vertical,
+}
+
+impl LayoutMode {
+    fn is_vertical(self, width: u16) -> bool {
+        match self {
+            LayoutMode::Auto => width < NARROW,
+            LayoutMode::Horizontal => false,
+            LayoutMode::Vertical => true,
+        }
+    }
+}
+
+/// How the detail pane fits lines wider than the pane.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Overflow {
+    #[default]
+    Wrap,
+    Truncate,
+    /// Lines are kept whole and the pane scrolls sideways.
+    Scroll,
+}
+
+impl Overflow {
+    fn next(self) -> Self {
+        match self {
+            Overflow::Wrap => Overflow::Truncate,
+            Overflow::Truncate => Overflow::Scroll,
+            Overflow::Scroll => Overflow::Wrap,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Overflow::Wrap => "wrap",
+            Overflow::Truncate => "truncate",
+            Overflow::Scroll => "scroll",
+        }
+    }
+}
+
+/// What the detail pane shows of the selected entry.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+enum DetailMode {
+    #[default]
+    Fields,
+    /// `Ctrl+x`: the payload bytes mapped to fields.
+    ByteMap,
+    /// `x`: the frame as received, as a hex and ASCII dump.
+    Frame,
+}
+
+impl DetailMode {
+    /// `mode`, or back to the fields if it is shown already.
+    fn toggle(self, mode: DetailMode) -> Self {
+        if self == mode {
+            DetailMode::Fields
+        } else {
+            mode
+        }
+    }
+}
+
+/// Per-tab view state, kept while other tabs are shown.
+struct TabView {
+    tab: Tab,
+    scroll: ScrollState,
+    /// Visible list height from the last draw.
+    vh: usize,
+}
+
+impl TabView {
+    fn new(tab: Tab) -> Self {
+        Self {
+            tab,
+            scroll: ScrollState::new(),
+            vh: 0,
+        }
+    }
+}
+
+/// A Ctrl+f search over everything the collector holds, and its results.
+struct Search {
+    query: String,
+    /// Still typing; once done the keys move through the results.
+    editing: bool,
+    hits: Vec<Hit>,
+    scroll: ScrollState,
+    /// Visible result rows from the last draw.
+    vh: usize,
+}
+
+impl Search {
+    fn new() -> Self {
+        Self {
+            query: String::new(),
+            editing: true,
+            hits: Vec::new(),
+            scroll: ScrollState::new(),
+            vh: 0,
+        }
+    }
+}
+
+/// A `/` search through the active panel, kept after Enter for `n`/`N`
+/// and the highlighting.
+struct Find {
+    query: String,
+    /// Still typing; Esc then goes back to `origin`.
+    editing: bool,
+    origin: usize,
+}
+
+/// Search and sort of the Params tab, which shows one component at a time.
+struct ParamsView {
+    /// Index into the components that sent PARAM_VALUE.
+    component: usize,
+    query: String,
+    /// Typing the search; otherwise the keys move through the table.
+    editing: bool,
+    order: ParamOrder,
+}
+
+impl ParamsView {
+    fn new() -> Self {
+        Self {
+            component: 0,
+            query: String::new(),
+            editing: false,
+            order: ParamOrder::default(),
+        }
+    }
+}
+
+/// The `w` prompt for the field of the selected stream row to plot.
+struct PlotPrompt {
+    sys_id: u8,
+    comp_id: u8,
+    name: &'static str,
+    text: String,
+    /// Why the last Enter was refused.
+    error: Option<String>,
+}
+
+/// What arrived since `space` paused the panels, recorded but not yet
+/// collected. Held to the collector's own limits, oldest first.
+#[derive(Default)]
+struct Held {
+    items: VecDeque<(DateTime<Utc>, Incoming)>,
+    bytes: u64,
+    /// Items dropped to stay within the limits.
+    dropped: u64,
+}
+
+impl Held {
+    fn push(&mut self, at: DateTime<Utc>, incoming: Incoming, limits: Limits) {
+        self.bytes += held_size(&incoming);
+        self.items.push_back((at, incoming));
+        while self.items.len() > 1
+            && (limits.max_events.is_some_and(|max| self.items.len() > max)
+                || limits.max_memory.is_some_and(|max| self.bytes > max))
+        {
+            let Some((_, old)) = self.items.pop_front() else {
+                break;
+            };
+            self.bytes -= held_size(&old);
+            self.dropped += 1;
+        }
+    }
+}
+
+fn held_size(incoming: &Incoming) -> u64 {
+    let extra = match incoming {
+        Incoming::Message(msg) => msg.payload.capacity() + msg.frame.capacity(),
+        Incoming::Diagnostic(_) => 0,
+    };
+    (std::mem::size_of::<Incoming>() + extra) as u64
+}
+
+/// The rows the filters show, as indices into the collector's lists, with
+/// the per-severity counts; classifying every event takes too long to
+/// repeat for each key press and frame.
+#[derive(Default)]
+struct Visible {
+    /// What these were worked out for; stale once it differs.
+    key: Option<VisibleKey>,
+    stream: Vec<usize>,
+    messages: Vec<usize>,
+    severities: [usize; 4],
+}
+
+/// The collector revision and the filters the rows depend on.
+#[derive(PartialEq)]
+struct VisibleKey {
+    revision: u64,
+    direction: Option<Direction>,
+    severity: Option<Severity>,
+    sys_id: Option<u8>,
+    name: Option<&'static str>,
+    filter: Option<Filter>,
+    as_of: Option<DateTime<Utc>>,
+}
+
+/// The `f` filter bar while it is being edited.
+struct FilterPrompt {
+    text: String,
+    /// The filter when the bar was opened, restored by Esc.
+    before: Option<Filter>,
+    /// Why `text` does not parse; the last filter that did stays applied.
+    error: Option<String>,
 }
 
 static HEADER: LazyLock<Paragraph<'static>> = LazyLock::new(|| {
@@ -37,347 +384,5204 @@ static HEADER: LazyLock<Paragraph<'static>> = LazyLock::new(|| {
     ])
 });
 
-static FOOTER: LazyLock<Paragraph<'static>> = LazyLock::new(|| {
+/// How often the UI redraws.
+const TICK: Duration = Duration::from_millis(50);
+
+/// Frames the link readers may queue before they block.
+pub const CHANNEL_CAPACITY: usize = 256;
+
+const PERF_WARNING_EVERY: Duration = Duration::from_secs(10);
+
+/// Footer hints in order: the actions whose keys are shown, and what they
+/// do.
+const FOOTER: &[(&[Action], &str)] = &[
+    (&[Action::Quit], "Quit"),
+    (&[Action::NextTab, Action::PrevTab], "Switch Tab"),
+    (&[Action::Up, Action::Down], "Select"),
+    (&[Action::PageUp, Action::PageDown], "Page"),
+    (&[Action::Top, Action::Bottom], "Top/Bottom"),
+    (&[Action::FocusDetail], "Focus Detail"),
+    (&[Action::JumpToName], "Jump to Name"),
+    (&[Action::SearchHistory], "Search History"),
+    (&[Action::OpenDocs], "Docs"),
+    (&[Action::MoveCategory], "Move to Stream/Events"),
+    (&[Action::Direction], "Direction"),
+    (&[Action::Severity], "Severity"),
+    (&[Action::Filter], "Filter"),
+    (&[Action::Pause], "Pause"),
+    (&[Action::Params], "Params"),
+    (&[Action::StatusText], "Status Text"),
+    (&[Action::Plot, Action::ClosePlot], "Plot Field/Close"),
+    (
+        &[Action::ExportRow, Action::ExportEvents],
+        "Export Row/Events",
+    ),
+    (&[Action::Find], "Find"),
+    (&[Action::FindNext, Action::FindPrev], "Next/Previous Match"),
+    (
+        &[Action::OnlySystem, Action::OnlyMessage],
+        "Only System/Message",
+    ),
+    (&[Action::QueryIntervals], "Query Intervals"),
+    (&[Action::WrapMode], "Wrap Mode"),
+    (&[Action::HideDefaults], "Hide Defaults"),
+    (&[Action::SaveSession], "Save Session"),
+    (&[Action::SaveSanitized], "Save Sanitized"),
+    (&[Action::Snapshot], "Snapshot"),
+    (&[Action::SavePcapng], "Save pcapng"),
+    (&[Action::ByteMap], "Byte Map"),
+    (&[Action::RawFrame], "Raw Frame"),
+    (&[Action::Intervals], "Intervals"),
+    (&[Action::Back, Action::Forward], "Time Travel"),
+    (&[Action::Clear], "Clear"),
+];
+
+/// The key help line for the active bindings; hints for unbound actions are
+/// left out.
+fn footer_line(keys: &KeyMap) -> Line<'static> {
     let key = Style::default().fg(Color::Cyan).bold();
-    Paragraph::new(Line::from(vec![
-        Span::styled(" q", key),
-        Span::raw(" Quit  "),
-        Span::styled("Tab/\u{2190}\u{2192}/h/l", key),
-        Span::raw(" Switch Panel  "),
-        Span::styled("\u{2191}\u{2193}/j/k", key),
-        Span::raw(" Select  "),
-        Span::styled("PgUp/PgDn", key),
-        Span::raw(" Page  "),
-        Span::styled("g/G", key),
-        Span::raw(" Top/Bottom  "),
-        Span::styled("Ctrl+o", key),
-        Span::raw(" Docs  "),
-        Span::styled("Ctrl+t", key),
-        Span::raw(" Move to Stream/Messages  "),
-        Span::styled("Ctrl+r", key),
-        Span::raw(" Clear "),
-    ]))
-});
+    let mut spans = vec![Span::raw(" ")];
+    for &(actions, what) in FOOTER {
+        let mut label = keys.label(actions);
+        if label.is_empty() {
+            continue;
+        }
+        // Digits pick a tab whenever no action has them.
+        if actions[0] == Action::NextTab {
+            label = format!("1-9/{label}");
+        }
+        if spans.len() > 1 {
+            spans.push(Span::raw("  "));
+        }
+        spans.push(Span::styled(label, key));
+        spans.push(Span::raw(format!(" {what}")));
+    }
+    Line::from(spans)
+}
 
 pub struct App {
     collector: Collector,
-    stream_scroll: ScrollState,
-    messages_scroll: ScrollState,
-    active_panel: Panel,
-    stream_vh: usize,
-    messages_vh: usize,
+    tabs: Vec<TabView>,
+    active_tab: usize,
+    links: Vec<String>,
+    /// With `--heartbeat`: the system id mavsnark sends as and the open
+    /// links by index, for the commands it sends itself.
+    senders: Option<(u8, Vec<Arc<Link>>)>,
+    /// Only show traffic in this direction; generated events always show.
+    direction_filter: Option<Direction>,
+    /// Lowest severity the Events tab shows; `None` shows all.
+    severity_filter: Option<Severity>,
+    /// `v`: only rows from this system.
+    isolate_sys: Option<u8>,
+    /// `m`: only rows of this message.
+    isolate_name: Option<&'static str>,
+    /// `f`: only rows the display filter matches.
+    filter: Option<Filter>,
+    filter_prompt: Option<FilterPrompt>,
+    classifier: Classifier,
+    visible: RefCell<Visible>,
+    /// Typed prefix while jumping through the stream by name.
+    typeahead: Option<String>,
+    find: Option<Find>,
+    search: Option<Search>,
+    params: ParamsView,
+    plot_prompt: Option<PlotPrompt>,
+    /// `w`: the field shown in the plot panel.
+    plot: Option<FieldPlot>,
+    /// Scroll position of the detail pane; `selected` is the top line.
+    detail_scroll: ScrollState,
+    detail_focused: bool,
+    detail_vh: usize,
+    /// Wrapped line count of the detail pane from the last draw.
+    detail_height: usize,
+    /// `(tab, selected row)` the detail pane was scrolled for.
+    detail_for: (usize, usize),
+    detail_overflow: Overflow,
+    /// First visible column when `detail_overflow` is `Scroll`.
+    detail_column: usize,
+    /// Columns the widest detail line extends past the pane.
+    detail_overhang: usize,
+    /// Hide fields that still hold their default value.
+    hide_defaults: bool,
+    /// Show an inter-arrival histogram below the detail of a stream row.
+    show_intervals: bool,
+    detail_mode: DetailMode,
+    layouts: LayoutCache,
+    palette: Palette,
+    fallback: Fallback,
+    bell: bool,
+    /// From the `[keys]` config table.
+    keys: KeyMap,
+    /// Key help for `keys`, built once.
+    footer: Paragraph<'static>,
+    layout: LayoutMode,
+    /// From `--profile`: how to name HEARTBEAT flight modes.
+    modes: Option<ModeScheme>,
+    docs_url: String,
+    /// Alert count when the Events tab was last looked at.
+    alerts_seen: usize,
+    /// Alert count when the bell was last considered.
+    alerts_rung: usize,
+    /// Window title last sent to the terminal.
+    title: String,
+    /// Every received item, encoded for [`App::save_session`].
+    session_log: SessionLog,
+    /// `--max-memory`, shared between the collector and the session log.
+    max_memory: Option<u64>,
+    /// When a performance warning was last logged.
+    perf_warned: Option<Instant>,
+    /// Time cursor: panels show state as of this moment instead of live.
+    as_of: Option<DateTime<Utc>>,
+    /// `space`: what arrived since pausing, recorded but not yet shown.
+    paused: Option<Held>,
+    /// Showing a loaded session rather than live links.
+    offline: bool,
+    /// `--replay`: recorded traffic still to be played back.
+    replay: Option<Replay>,
+    /// Background capture for `--autosave`.
+    recorder: Option<Recorder>,
+    capture: Option<RingCapture>,
+    /// `--mirror-events`.
+    mirror: Option<EventMirror>,
+    /// `--pcapng`.
+    pcapng: Option<PcapngWriter<BufWriter<File>>>,
+    /// `--serve-ws`.
+    websocket: Option<WsServer>,
+    /// `--serve-http`.
+    http: Option<HttpServer>,
+    /// `[notify]` hooks run for new alerts.
+    notifier: Option<Notifier>,
+    /// Compact row formats from the config, by message name.
+    templates: HashMap<String, Template>,
+    /// From the config's `[[highlight]]` rules.
+    highlights: Highlights,
+    /// Draw as of this moment instead of the wall clock, so renders repeat.
+    clock: Option<DateTime<Utc>>,
+    perf: PerfMeter,
+    /// The hidden performance panel, toggled with F12.
+    show_perf: bool,
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl App {
     pub fn new() -> Self {
         Self {
-            collector: Collector::new(),
-            stream_scroll: ScrollState::new(),
-            messages_scroll: ScrollState::new(),
-            active_panel: Panel::Messages,
-            stream_vh: 0,
-            messages_vh: 0,
+            collector: {
+                let mut collector = Collector::new();
+                collector.keep_evicted();
+                collector
+            },
+            tabs: Tab::ALL.into_iter().map(TabView::new).collect(),
+            active_tab: Tab::Events as usize,
+            links: Vec::new(),
+            senders: None,
+            direction_filter: None,
+            severity_filter: None,
+            isolate_sys: None,
+            isolate_name: None,
+            filter: None,
+            filter_prompt: None,
+            classifier: Classifier::default(),
+            visible: RefCell::default(),
+            typeahead: None,
+            find: None,
+            search: None,
+            params: ParamsView::new(),
+            plot_prompt: None,
+            plot: None,
+            detail_scroll: ScrollState::new(),
+            detail_focused: false,
+            detail_vh: 0,
+            detail_height: 0,
+            detail_for: (0, 0),
+            detail_overflow: Overflow::Wrap,
+            detail_column: 0,
+            detail_overhang: 0,
+            hide_defaults: false,
+            show_intervals: false,
+            detail_mode: DetailMode::Fields,
+            layouts: LayoutCache::new(),
+            palette: Palette::default(),
+            fallback: Fallback::default(),
+            bell: false,
+            keys: KeyMap::default(),
+            footer: Paragraph::new(footer_line(&KeyMap::default())),
+            layout: LayoutMode::Auto,
+            modes: None,
+            docs_url: profile::DEFAULT_DOCS_URL.to_string(),
+            alerts_seen: 0,
+            alerts_rung: 0,
+            title: String::new(),
+            session_log: SessionLog::default(),
+            max_memory: None,
+            perf_warned: None,
+            as_of: None,
+            paused: None,
+            offline: false,
+            replay: None,
+            recorder: None,
+            capture: None,
+            mirror: None,
+            pcapng: None,
+            websocket: None,
+            http: None,
+            notifier: None,
+            templates: HashMap::new(),
+            highlights: Highlights::default(),
+            clock: None,
+            perf: PerfMeter::new(),
+            show_perf: false,
         }
     }
 
-    fn toggle_panel(&mut self) {
-        self.active_panel = match self.active_panel {
-            Panel::Stream => Panel::Messages,
-            Panel::Messages => Panel::Stream,
-        };
+    pub fn with_links(mut self, links: Vec<String>, dedup: DedupMode) -> Self {
+        self.links = links;
+        self.collector.set_dedup_mode(dedup);
+        self
     }
 
-    fn active_scroll(&mut self) -> &mut ScrollState {
-        match self.active_panel {
-            Panel::Stream => &mut self.stream_scroll,
-            Panel::Messages => &mut self.messages_scroll,
-        }
+    pub fn with_senders(mut self, system_id: u8, links: Vec<Arc<Link>>) -> Self {
+        self.senders = Some((system_id, links));
+        self
     }
 
-    fn selected_name(&self) -> Option<&'static str> {
-        match self.active_panel {
-            Panel::Stream => {
-                let stream = self.collector.stream();
-                stream.get(self.stream_scroll.selected).map(|e| e.name)
-            }
-            Panel::Messages => {
-                let messages = self.collector.messages();
-                messages.get(self.messages_scroll.selected).map(|e| e.name)
-            }
-        }
+    pub fn with_config(mut self, config: &Config) -> Self {
+        self.collector.set_config(config);
+        self.palette = config.ui.palette;
+        self.bell = config.ui.bell;
+        self.notifier = Notifier::new(&config.notify);
+        self.classifier = config.classifier();
+        self
     }
 
-    fn open_docs(&self) {
-        if let Some(name) = self.selected_name() {
-            let url = format!("https://mavlink.io/en/messages/common.html#{name}");
-            let _ = open::that(url);
+    /// Restores choices saved by a previous run; see [`App::ui_state`].
+    pub fn with_state(mut self, state: UiState) -> Self {
+        if let Some(names) = &state.stream_types {
+            self.collector
+                .set_stream_types(names.iter().map(String::as_str));
         }
+        self.direction_filter = state.direction;
+        self.severity_filter = state.severity;
+        self.layout = state.layout;
+        self.active_tab = state.tab as usize;
+        self.detail_overflow = state.overflow;
+        self.hide_defaults = state.hide_defaults;
+        self
     }
 
-    fn active_total(&self) -> usize {
-        match self.active_panel {
-            Panel::Stream => self.collector.stream().len(),
-            Panel::Messages => self.collector.messages().len(),
+    pub fn ui_state(&self) -> UiState {
+        UiState {
+            stream_types: Some(
+                self.collector
+                    .stream_types()
+                    .into_iter()
+                    .map(String::from)
+                    .collect(),
+            ),
+            direction: self.direction_filter,
+            severity: self.severity_filter,
+            layout: self.layout,
+            tab: self.tab(),
+            overflow: self.detail_overflow,
+            hide_defaults: self.hide_defaults,
         }
     }
 
-    fn active_vh(&self) -> usize {
-        match self.active_panel {
-            Panel::Stream => self.stream_vh,
-            Panel::Messages => self.messages_vh,
+    pub fn with_profile(mut self, profile: &Profile) -> Self {
+        if let Some(names) = &profile.stream_types {
+            self.collector
+                .set_stream_types(names.iter().map(String::as_str));
         }
+        self.modes = profile.modes;
+        if let Some(url) = &profile.docs_url {
+            self.docs_url = url.clone();
+        }
+        self
     }
 
-    /// Handle a key press. Returns `true` if the app should quit.
-    pub fn handle_key(&mut self, code: KeyCode, modifiers: KeyModifiers) -> bool {
-        let total = self.active_total();
-        let vh = self.active_vh();
-        match (code, modifiers) {
-            (KeyCode::Char('q'), _) | (KeyCode::Esc, _) => return true,
-            (KeyCode::Char('o'), m) if m.contains(KeyModifiers::CONTROL) => self.open_docs(),
-            (KeyCode::Char('r'), m) if m.contains(KeyModifiers::CONTROL) => {
-                self.collector.clear();
-                self.stream_scroll = ScrollState::new();
-                self.messages_scroll = ScrollState::new();
-            }
-            (KeyCode::Char('t'), m) if m.contains(KeyModifiers::CONTROL) => {
-                if let Some(name) = self.selected_name() {
-                    let currently_stream = self.active_panel == Panel::Stream;
-                    self.collector.toggle_category(name, currently_stream);
-                    self.stream_scroll
-                        .clamp(self.collector.stream().len(), self.stream_vh);
-                    self.messages_scroll
-                        .clamp(self.collector.messages().len(), self.messages_vh);
-                }
-            }
-            (KeyCode::Tab, _)
-            | (KeyCode::Left, _)
-            | (KeyCode::Right, _)
-            | (KeyCode::Char('h'), _)
-            | (KeyCode::Char('l'), _) => self.toggle_panel(),
-            (KeyCode::Up, _) | (KeyCode::Char('k'), _) => self.active_scroll().select_up(1),
-            (KeyCode::Down, _) | (KeyCode::Char('j'), _) => {
-                self.active_scroll().select_down(1, total, vh)
-            }
-            (KeyCode::PageUp, _) => self.active_scroll().select_up(vh),
-            (KeyCode::PageDown, _) => self.active_scroll().select_down(vh, total, vh),
-            (KeyCode::Char('g'), _) => self.active_scroll().select_top(),
-            (KeyCode::Char('G'), _) => self.active_scroll().select_bottom(total, vh),
-            _ => {}
-        }
-        false
+    /// Applies `--stream-types` (the full set of stream rows) and
+    /// `--event-types` (taken out of whatever set is in effect).
+    pub fn with_categories(mut self, stream: &[String], events: &[String]) -> Self {
+        let mut names: Vec<String> = if stream.is_empty() {
+            self.collector
+                .stream_types()
+                .into_iter()
+                .map(String::from)
+                .collect()
+        } else {
+            stream.to_vec()
+        };
+        names.retain(|n| !events.contains(n));
+        self.collector
+            .set_stream_types(names.iter().map(String::as_str));
+        self
     }
 
-    pub async fn run(
-        &mut self,
-        terminal: &mut DefaultTerminal,
-        mut mav_rx: tokio::sync::mpsc::Receiver<MavMsg>,
-    ) -> io::Result<()> {
-        let mut event_stream = EventStream::new();
-        let mut tick = tokio::time::interval(std::time::Duration::from_millis(50));
-        tick.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    pub fn with_layout(mut self, layout: LayoutMode) -> Self {
+        self.layout = layout;
+        self
+    }
 
-        loop {
-            tokio::select! {
-                Some(msg) = mav_rx.recv() => {
-                    self.collector.push(msg);
-                }
-                Some(Ok(event)) = event_stream.next() => {
-                    if let Event::Key(key) = event
-                        && key.kind == KeyEventKind::Press
-                        && self.handle_key(key.code, key.modifiers)
-                    {
-                        return Ok(());
-                    }
-                }
-                _ = tick.tick() => {
-                    terminal.draw(|frame| self.draw(frame))?;
-                }
-            }
-        }
+    fn now(&self) -> DateTime<Utc> {
+        self.clock.unwrap_or_else(Utc::now)
     }
 
-    fn draw(&mut self, frame: &mut Frame) {
-        let rows = Layout::vertical([
-            Constraint::Length(3),
-            Constraint::Min(0),
-            Constraint::Length(1),
-        ])
-        .split(frame.area());
+    pub fn with_fallback(mut self, fallback: Fallback) -> Self {
+        self.fallback = fallback;
+        self
+    }
 
-        frame.render_widget(&*HEADER, rows[0]);
+    fn tab(&self) -> Tab {
+        self.tabs[self.active_tab].tab
+    }
 
-        let columns = Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
-            .split(rows[1]);
+    fn view(&self, tab: Tab) -> &TabView {
+        &self.tabs[tab as usize]
+    }
 
-        let right_rows = Layout::vertical([Constraint::Percentage(60), Constraint::Percentage(40)])
-            .split(columns[1]);
+    fn view_mut(&mut self, tab: Tab) -> &mut TabView {
+        &mut self.tabs[tab as usize]
+    }
 
-        self.messages_vh = columns[0].height.saturating_sub(2) as usize;
-        self.stream_vh = right_rows[0].height.saturating_sub(2) as usize;
+    fn select_tab(&mut self, index: usize) {
+        if index < self.tabs.len() {
+            self.active_tab = index;
+            self.detail_focused = false;
+        }
+    }
 
-        // Auto-follow before drawing
-        let stream_total = self.collector.stream().len();
-        self.stream_scroll.auto_follow(stream_total, self.stream_vh);
-        let messages_total = self.collector.messages().len();
-        self.messages_scroll
-            .auto_follow(messages_total, self.messages_vh);
-
-        let (messages_widget, mut messages_sb) = self.build_messages();
-        frame.render_widget(messages_widget, columns[0]);
-        frame.render_stateful_widget(
-            Scrollbar::new(ScrollbarOrientation::VerticalRight),
-            columns[0],
-            &mut messages_sb,
-        );
+    /// Number of distinct scroll positions in the detail pane.
+    fn detail_positions(&self) -> usize {
+        self.detail_height.saturating_sub(self.detail_vh) + 1
+    }
 
-        let (stream_widget, mut stream_sb) = self.build_stream();
-        frame.render_widget(stream_widget, right_rows[0]);
-        frame.render_stateful_widget(
-            Scrollbar::new(ScrollbarOrientation::VerticalRight),
-            right_rows[0],
-            &mut stream_sb,
-        );
+    fn handle_detail_key(&mut self, action: Action) {
+        let positions = self.detail_positions();
+        let page = self.detail_vh.max(1);
+        let scroll = &mut self.detail_scroll;
+        match action {
+            Action::Up => scroll.select_up(1),
+            Action::Down => scroll.select_down(1, positions, 1),
+            Action::PageUp => scroll.select_up(page),
+            Action::PageDown => scroll.select_down(page, positions, 1),
+            Action::Top => scroll.select_top(),
+            Action::Bottom => scroll.select_bottom(positions, 1),
+            Action::PrevTab => {
+                self.detail_column = self.detail_column.saturating_sub(4);
+            }
+            Action::NextTab => {
+                self.detail_column = (self.detail_column + 4).min(self.detail_overhang);
+            }
+            _ => {}
+        }
+    }
 
-        frame.render_widget(self.build_message(), right_rows[1]);
+    fn next_tab(&mut self) {
+        self.select_tab((self.active_tab + 1) % self.tabs.len());
+    }
 
-        frame.render_widget(&*FOOTER, rows[2]);
+    fn prev_tab(&mut self) {
+        self.select_tab((self.active_tab + self.tabs.len() - 1) % self.tabs.len());
     }
 
-    fn build_stream(&self) -> (Paragraph<'_>, ScrollbarState) {
-        let active = self.active_panel == Panel::Stream;
-        let vh = self.stream_vh;
-        let stream = self.collector.stream();
-        let total = stream.len();
+    fn active_scroll(&mut self) -> &mut ScrollState {
+        &mut self.tabs[self.active_tab].scroll
+    }
 
-        let selected_style = Style::default().bg(Color::DarkGray);
+    /// Keeps the list selections in range after entries were removed or hidden.
+    fn clamp_scrolls(&mut self) {
+        let (stream, messages) = (self.stream().len(), self.messages().len());
+        let view = self.view_mut(Tab::Stream);
+        view.scroll.clamp(stream, view.vh);
+        let view = self.view_mut(Tab::Events);
+        view.scroll.clamp(messages, view.vh);
+    }
 
-        let lines: Vec<Line> = stream
-            .iter()
-            .enumerate()
-            .skip(self.stream_scroll.offset)
-            .take(vh)
-            .map(|(i, entry)| {
-                let line = entry.to_line();
-                if active && i == self.stream_scroll.selected {
-                    line.style(selected_style)
-                } else {
-                    line
-                }
-            })
-            .collect();
+    fn shows(&self, direction: Option<Direction>) -> bool {
+        match (self.direction_filter, direction) {
+            (Some(filter), Some(d)) => filter == d,
+            _ => true,
+        }
+    }
 
-        let block = panel_block(
-            "Stream",
-            total,
-            "types",
-            self.stream_scroll.auto_scroll,
-            active,
-        );
+    fn isolates(&self, sys_id: u8, name: &str) -> bool {
+        self.isolate_sys.is_none_or(|s| s == sys_id) && self.isolate_name.is_none_or(|n| n == name)
+    }
 
-        let paragraph = Paragraph::new(lines).block(block);
-        let scrollbar_state =
-            ScrollbarState::new(total.saturating_sub(vh)).position(self.stream_scroll.offset);
+    fn passes_filter(&self, row: filter::Row) -> bool {
+        self.filter.as_ref().is_none_or(|f| f.matches(&row))
+    }
 
-        (paragraph, scrollbar_state)
+    /// Stream rows that pass the active filters and, when time travelling,
+    /// existed at the cursor.
+    fn stream(&self) -> Vec<&StreamEntry> {
+        let stream = self.collector.stream();
+        self.visible().stream.iter().map(|&i| &stream[i]).collect()
     }
 
-    fn build_messages(&self) -> (Paragraph<'_>, ScrollbarState) {
-        let active = self.active_panel == Panel::Messages;
-        let vh = self.messages_vh;
+    /// Message rows that pass the active filters and, when time travelling,
+    /// arrived by the cursor.
+    fn messages(&self) -> Vec<&MessageEntry> {
         let messages = self.collector.messages();
-        let total = messages.len();
-
-        let selected_style = Style::default().bg(Color::DarkGray);
-
-        let lines: Vec<Line> = messages
+        self.visible()
+            .messages
             .iter()
-            .enumerate()
-            .skip(self.messages_scroll.offset)
-            .take(vh)
-            .map(|(i, entry)| {
-                let line = entry.to_line();
-                if active && i == self.messages_scroll.selected {
-                    line.style(selected_style)
-                } else {
-                    line
+            .map(|&i| &messages[i])
+            .collect()
+    }
+
+    /// The visible rows, worked out again only when the collector or a
+    /// filter has changed since last time.
+    fn visible(&self) -> Ref<'_, Visible> {
+        let key = VisibleKey {
+            revision: self.collector.revision(),
+            direction: self.direction_filter,
+            severity: self.severity_filter,
+            sys_id: self.isolate_sys,
+            name: self.isolate_name,
+            filter: self.filter.clone(),
+            as_of: self.as_of,
+        };
+        if self.visible.borrow().key.as_ref() != Some(&key) {
+            let stream = self.collector.stream().iter().enumerate().filter(|(_, e)| {
+                self.shows(Some(e.direction))
+                    && self.isolates(e.sys_id, e.name)
+                    && e.at(self.as_of).is_some_and(|s| {
+                        self.passes_filter(filter::Row {
+                            sys_id: e.sys_id,
+                            comp_id: e.comp_id,
+                            name: e.name,
+                            fields: s.fields,
+                            link: e.link,
+                        })
+                    })
+            });
+            let mut visible = Visible {
+                stream: stream.map(|(i, _)| i).collect(),
+                ..Visible::default()
+            };
+            for (i, entry) in self.collector.messages().iter().enumerate() {
+                if !self.passes_event_filters(entry) {
+                    continue;
                 }
-            })
-            .collect();
+                let severity = self.classifier.classify(entry);
+                visible.severities[severity as usize] += 1;
+                if self.severity_filter.is_none_or(|min| severity >= min) {
+                    visible.messages.push(i);
+                }
+            }
+            visible.key = Some(key);
+            *self.visible.borrow_mut() = visible;
+        }
+        self.visible.borrow()
+    }
 
-        let block = panel_block(
-            "Messages",
-            total,
-            "",
-            self.messages_scroll.auto_scroll,
-            active,
-        );
+    /// Whether `entry` is a row of the Events tab.
+    fn lists_event(&self, entry: &MessageEntry) -> bool {
+        self.passes_event_filters(entry)
+            && self
+                .severity_filter
+                .is_none_or(|min| self.classifier.classify(entry) >= min)
+    }
 
-        let paragraph = Paragraph::new(lines).block(block);
-        let scrollbar_state =
-            ScrollbarState::new(total.saturating_sub(vh)).position(self.messages_scroll.offset);
+    fn passes_event_filters(&self, e: &MessageEntry) -> bool {
+        self.shows(e.direction)
+            && self.isolates(e.sys_id, e.name)
+            && self.as_of.is_none_or(|t| e.timestamp <= t)
+            && self.passes_filter(filter::Row {
+                sys_id: e.sys_id,
+                comp_id: e.comp_id,
+                name: e.name,
+                fields: &e.fields,
+                link: e.link,
+            })
+    }
 
-        (paragraph, scrollbar_state)
+    /// Events per severity, lowest first, whatever the severity filter.
+    fn severity_counts(&self) -> [usize; 4] {
+        self.visible().severities
     }
 
-    fn build_message(&self) -> Paragraph<'_> {
-        let block = Block::default()
-            .title(" Message ")
-            .borders(Borders::ALL)
-            .border_style(Style::default().fg(Color::Gray));
+    /// Oldest and newest moments the collected history covers.
+    fn time_span(&self) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        let stream = self.collector.stream().iter().flat_map(|e| {
+            let oldest = e.history.front().map_or(e.timestamp, |s| s.timestamp);
+            [oldest, e.timestamp]
+        });
+        let events = self.collector.messages().iter().map(|e| e.timestamp);
+        let (mut first, mut last) = (None::<DateTime<Utc>>, None::<DateTime<Utc>>);
+        for t in stream.chain(events) {
+            first = Some(first.map_or(t, |f| f.min(t)));
+            last = Some(last.map_or(t, |l| l.max(t)));
+        }
+        // A live capture goes on to the present even when nothing arrives.
+        let last = if self.offline { last? } else { self.now() };
+        Some((first?, last))
+    }
 
-        let selected = match self.active_panel {
-            Panel::Stream => {
-                let s = self.collector.stream();
-                s.get(self.stream_scroll.selected.min(s.len().saturating_sub(1)))
-                    .map(|e| {
-                        (
-                            e.name,
-                            e.sys_id,
-                            e.comp_id,
-                            e.sys_color,
-                            e.comp_color,
-                            e.parsed_fields(),
-                        )
-                    })
-            }
-            Panel::Messages => {
-                let m = self.collector.messages();
-                m.get(self.messages_scroll.selected.min(m.len().saturating_sub(1)))
-                    .map(|e| {
-                        (
-                            e.name,
-                            e.sys_id,
-                            e.comp_id,
-                            e.sys_color,
-                            e.comp_color,
-                            e.parsed_fields(),
-                        )
-                    })
-            }
+    /// Moves the time cursor; moving it past the newest data goes back to
+    /// live.
+    fn travel(&mut self, delta: TimeDelta) {
+        let Some((first, last)) = self.time_span() else {
+            return;
         };
+        let cursor = self.as_of.unwrap_or(last) + delta;
+        self.as_of = (cursor < last).then(|| cursor.max(first));
+        self.clamp_scrolls();
+    }
 
-        let lines: Vec<Line> = match selected {
-            Some((name, sys_id, comp_id, sys_color, comp_color, fields)) => {
-                message_lines(name, sys_id, comp_id, sys_color, comp_color, fields)
-            }
-            None => vec![Line::from(Span::styled(
-                "No messages",
-                Style::default().fg(Color::DarkGray),
-            ))],
+    fn panel_label(&self, name: &str) -> String {
+        let mut label = match self.direction_filter {
+            Some(d) => format!("{name} {} {}", d.arrow(), d.label()),
+            None => name.to_string(),
         };
+        if let Some(sys_id) = self.isolate_sys {
+            label.push_str(&format!(" sys {sys_id}"));
+        }
+        if let Some(name) = self.isolate_name {
+            label.push_str(&format!(" {name} only"));
+        }
+        if let Some(filter) = &self.filter {
+            label.push_str(&format!(" [{}]", filter.text()));
+        }
+        if let Some(held) = &self.paused {
+            label.push_str(&format!(" paused ({} held", held.items.len()));
+            if held.dropped > 0 {
+                label.push_str(&format!(", {} dropped", held.dropped));
+            }
+            label.push(')');
+        }
+        if let Some(as_of) = self.as_of {
+            label.push_str(&format!(
+                " as of {}",
+                as_of.with_timezone(&Local).format("%H:%M:%S%.3f")
+            ));
+        }
+        label
+    }
 
-        Paragraph::new(lines)
-            .block(block)
-            .wrap(ratatui::widgets::Wrap { trim: false })
+    /// Replaces the footer while time travelling.
+    fn timeline(&self, as_of: DateTime<Utc>, width: u16) -> Paragraph<'static> {
+        let key = Style::default().fg(Color::Cyan).bold();
+        let (first, last) = self.time_span().unwrap_or((as_of, as_of));
+        let behind = last.signed_duration_since(as_of).num_milliseconds() as f64 / 1000.0;
+        let head = format!(
+            " {} (-{behind:.1}s) ",
+            as_of.with_timezone(&Local).format("%H:%M:%S%.3f")
+        );
+        let keys = "  [/] -/+1s  {/} -/+10s  past the end: live ";
+        let bar = (width as usize)
+            .saturating_sub(head.len() + keys.chars().count() + 2)
+            .clamp(10, 60);
+        Paragraph::new(Line::from(vec![
+            Span::styled(head, Style::default().fg(Color::Black).bg(Color::Yellow)),
+            Span::raw(" "),
+            Span::styled(
+                timeline_bar(first, last, as_of, bar),
+                Style::default().fg(Color::Yellow),
+            ),
+            Span::styled(keys, key),
+        ]))
     }
-}
 
-fn panel_block(
-    label: &str,
-    count: usize,
-    count_suffix: &str,
-    auto_scroll: bool,
+    /// Asks each vehicle component for the configured interval of every
+    /// stream it sends, for the Intervals panel.
+    fn query_intervals(&mut self) {
+        let Some((system_id, links)) = &self.senders else {
+            self.collector.push_note(
+                "INTERVAL_QUERY_UNAVAILABLE",
+                "sending commands needs --heartbeat".to_string(),
+            );
+            return;
+        };
+        let header = MavHeader {
+            system_id: *system_id,
+            component_id: 0,
+            sequence: 0,
+        };
+        let mut asked = Vec::new();
+        let mut failed = None;
+        for e in self.collector.stream() {
+            let (Some(id), Some(link)) =
+                (MavMessage::message_id_from_name(e.name), links.get(e.link))
+            else {
+                continue;
+            };
+            if e.direction == Direction::Uplink {
+                continue;
+            }
+            let query = MavMessage::COMMAND_LONG(COMMAND_LONG_DATA {
+                target_system: e.sys_id,
+                target_component: e.comp_id,
+                command: MavCmd::MAV_CMD_GET_MESSAGE_INTERVAL,
+                param1: id as f32,
+                ..Default::default()
+            });
+            if let Err(err) = link.send(&header, &query) {
+                failed = Some(err);
+                break;
+            }
+            asked.push((e.sys_id, e.comp_id, e.name));
+        }
+        let now = self.now();
+        for (sys_id, comp_id, name) in asked {
+            self.collector.interval_asked(sys_id, comp_id, name, now);
+        }
+        if let Some(e) = failed {
+            self.collector
+                .push_note("INTERVAL_QUERY_FAILED", format!("error: {e}"));
+        }
+    }
+
+    fn cycle_severity_filter(&mut self) {
+        self.severity_filter = Severity::next_filter(self.severity_filter);
+        self.clamp_scrolls();
+    }
+
+    fn cycle_direction_filter(&mut self) {
+        self.direction_filter = match self.direction_filter {
+            None => Some(Direction::Downlink),
+            Some(Direction::Downlink) => Some(Direction::Uplink),
+            Some(Direction::Uplink) => Some(Direction::Peer),
+            Some(Direction::Peer) => None,
+        };
+        self.clamp_scrolls();
+    }
+
+    /// Toggles showing only the selected row's system, or with `by_name`
+    /// only its message, keeping that row selected.
+    fn toggle_isolate(&mut self, by_name: bool) {
+        let selected = self.selected_row();
+        if by_name {
+            self.isolate_name = match self.isolate_name {
+                Some(_) => None,
+                None => selected.map(|(_, _, name, _)| name),
+            };
+        } else {
+            self.isolate_sys = match self.isolate_sys {
+                Some(_) => None,
+                None => selected.map(|(sys_id, ..)| sys_id),
+            };
+        }
+        self.clamp_scrolls();
+        let tab = self.tab();
+        let Some((sys_id, comp_id, name, at)) = selected else {
+            return;
+        };
+        if let Some(index) = self.position_of(tab, (sys_id, comp_id, name), at) {
+            let view = self.view_mut(tab);
+            view.scroll.select(index, view.vh);
+        }
+    }
+
+    /// System, component, name and time of the selected list row.
+    fn selected_row(&self) -> Option<(u8, u8, &'static str, DateTime<Utc>)> {
+        let selected = self.tabs[self.active_tab].scroll.selected;
+        match self.tab() {
+            Tab::Stream => self
+                .stream()
+                .get(selected)
+                .map(|e| (e.sys_id, e.comp_id, e.name, e.timestamp)),
+            Tab::Events => self
+                .messages()
+                .get(selected)
+                .map(|e| (e.sys_id, e.comp_id, e.name, e.timestamp)),
+            Tab::Stats | Tab::Params | Tab::Console | Tab::Map => None,
+        }
+    }
+
+    /// Where a row is in its tab's filtered list; events also match on
+    /// their time, stream rows by key alone.
+    fn position_of(&self, tab: Tab, key: (u8, u8, &str), at: DateTime<Utc>) -> Option<usize> {
+        match tab {
+            Tab::Stream => self
+                .stream()
+                .iter()
+                .position(|e| (e.sys_id, e.comp_id, e.name) == key),
+            Tab::Events => self
+                .messages()
+                .iter()
+                .position(|e| (e.sys_id, e.comp_id, e.name) == key && e.timestamp == at),
+            Tab::Stats | Tab::Params | Tab::Console | Tab::Map => None,
+        }
+    }
+
+    fn selected_name(&self) -> Option<&'static str> {
+        let selected = self.tabs[self.active_tab].scroll.selected;
+        match self.tab() {
+            Tab::Stream => self.stream().get(selected).map(|e| e.name),
+            Tab::Events => self
+                .messages()
+                .get(selected)
+                .filter(|e| e.kind == EventKind::Message)
+                .map(|e| e.name),
+            Tab::Stats | Tab::Params | Tab::Console | Tab::Map => None,
+        }
+    }
+
+    fn open_docs(&self) {
+        if let Some(name) = self.selected_name() {
+            let _ = open::that(profile::docs_url(&self.docs_url, name));
+        }
+    }
+
+    fn active_total(&self) -> usize {
+        match self.tab() {
+            Tab::Stream => self.stream().len(),
+            Tab::Events => self.messages().len(),
+            Tab::Stats | Tab::Map => 0,
+            Tab::Params => self.params_total(),
+            Tab::Console => self.collector.statustext().lines().len(),
+        }
+    }
+
+    fn active_vh(&self) -> usize {
+        self.tabs[self.active_tab].vh
+    }
+
+    /// Moves the stream selection to the first row whose name starts with
+    /// the typed prefix. Rows are never hidden.
+    fn jump_to_prefix(&mut self) {
+        let Some(prefix) = &self.typeahead else {
+            return;
+        };
+        let prefix = prefix.to_ascii_uppercase();
+        let hit = self
+            .stream()
+            .iter()
+            .position(|e| e.name.starts_with(&prefix));
+        if let Some(index) = hit {
+            let view = self.view_mut(Tab::Stream);
+            view.scroll.select(index, view.vh);
+        }
+    }
+
+    fn handle_typeahead(&mut self, code: KeyCode) {
+        let Some(prefix) = &mut self.typeahead else {
+            return;
+        };
+        match code {
+            KeyCode::Esc | KeyCode::Enter => self.typeahead = None,
+            KeyCode::Backspace => {
+                prefix.pop();
+            }
+            KeyCode::Char(c) if !c.is_control() => prefix.push(c),
+            _ => {}
+        }
+        self.jump_to_prefix();
+    }
+
+    /// Which rows of the active panel contain the `/` query in their name
+    /// or fields, ignoring case.
+    fn find_hits(&self) -> Vec<bool> {
+        let Some(query) = self.find.as_ref().map(|f| f.query.to_ascii_lowercase()) else {
+            return Vec::new();
+        };
+        let hit = |name: &str, fields: &str| {
+            !query.is_empty()
+                && (name.to_ascii_lowercase().contains(&query)
+                    || fields.to_ascii_lowercase().contains(&query))
+        };
+        match self.tab() {
+            Tab::Stream => self
+                .stream()
+                .iter()
+                .map(|e| hit(e.name, e.at(self.as_of).map_or("", |s| s.fields)))
+                .collect(),
+            Tab::Events => self
+                .messages()
+                .iter()
+                .map(|e| hit(e.name, &e.fields))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Selects the next match after the selection (or from it, with
+    /// `inclusive`), wrapping around the panel like less and vim.
+    fn find_step(&mut self, forward: bool, inclusive: bool) {
+        let hits = self.find_hits();
+        let total = hits.len();
+        let selected = self.active_scroll().selected;
+        let start = if inclusive { 0 } else { 1 };
+        let index = (start..start + total)
+            .map(|k| {
+                if forward {
+                    (selected + k) % total
+                } else {
+                    (selected + total - k % total) % total
+                }
+            })
+            .find(|&i| hits[i]);
+        if let Some(index) = index {
+            let vh = self.active_vh();
+            self.active_scroll().select(index, vh);
+        }
+    }
+
+    fn handle_find(&mut self, code: KeyCode) {
+        let Some(find) = &mut self.find else {
+            return;
+        };
+        match code {
+            KeyCode::Esc => {
+                let origin = find.origin;
+                self.find = None;
+                let vh = self.active_vh();
+                self.active_scroll().select(origin, vh);
+                return;
+            }
+            KeyCode::Enter if find.query.is_empty() => self.find = None,
+            KeyCode::Enter => find.editing = false,
+            KeyCode::Backspace => {
+                find.query.pop();
+            }
+            KeyCode::Char(c) if !c.is_control() => find.query.push(c),
+            _ => {}
+        }
+        if let Some(find) = &self.find
+            && find.editing
+        {
+            let (origin, vh) = (find.origin, self.active_vh());
+            self.active_scroll().select(origin, vh);
+            self.find_step(true, true);
+        }
+    }
+
+    fn open_filter(&mut self) {
+        self.filter_prompt = Some(FilterPrompt {
+            text: self
+                .filter
+                .as_ref()
+                .map_or_else(String::new, |f| f.text().to_string()),
+            before: self.filter.clone(),
+            error: None,
+        });
+    }
+
+    /// Asks which field of the selected stream row to plot, starting from
+    /// the one plotted now or the first numeric one.
+    fn open_plot_prompt(&mut self) {
+        let Some(entry) = self.selected_stream() else {
+            return;
+        };
+        let text = match &self.plot {
+            Some(plot) if plot.is_for(entry) => plot.field.clone(),
+            _ => plot::numeric_fields(&entry.fields)
+                .first()
+                .map_or_else(String::new, |f| f.to_string()),
+        };
+        self.plot_prompt = Some(PlotPrompt {
+            sys_id: entry.sys_id,
+            comp_id: entry.comp_id,
+            name: entry.name,
+            text,
+            error: None,
+        });
+    }
+
+    /// Tab steps through the numeric fields; Enter plots the one typed.
+    fn handle_plot_prompt(&mut self, code: KeyCode) {
+        let Some(prompt) = &mut self.plot_prompt else {
+            return;
+        };
+        let Some(entry) = self
+            .collector
+            .stream_entry(prompt.sys_id, prompt.comp_id, prompt.name)
+        else {
+            self.plot_prompt = None;
+            return;
+        };
+        prompt.error = None;
+        match code {
+            KeyCode::Esc => self.plot_prompt = None,
+            KeyCode::Backspace => {
+                prompt.text.pop();
+            }
+            KeyCode::Char(c) if !c.is_control() => prompt.text.push(c),
+            KeyCode::Tab => {
+                let fields = plot::numeric_fields(&entry.fields);
+                let next = fields
+                    .iter()
+                    .position(|f| *f == prompt.text)
+                    .map_or(0, |i| (i + 1) % fields.len());
+                if let Some(field) = fields.get(next) {
+                    prompt.text = field.to_string();
+                }
+            }
+            KeyCode::Enter => {
+                if plot::value(&entry.fields, &prompt.text).is_some() {
+                    self.plot = Some(FieldPlot::new(entry, &prompt.text));
+                    self.plot_prompt = None;
+                } else {
+                    prompt.error = Some(format!(
+                        "{} has no numeric field {:?}",
+                        entry.name, prompt.text
+                    ));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Applies the filter as it is typed, whenever it parses.
+    fn handle_filter(&mut self, code: KeyCode) {
+        let Some(prompt) = &mut self.filter_prompt else {
+            return;
+        };
+        match code {
+            KeyCode::Esc => {
+                self.filter = prompt.before.take();
+                self.filter_prompt = None;
+            }
+            KeyCode::Enter if prompt.error.is_none() => self.filter_prompt = None,
+            KeyCode::Backspace => {
+                prompt.text.pop();
+            }
+            KeyCode::Char(c) if !c.is_control() => prompt.text.push(c),
+            _ => {}
+        }
+        if let Some(prompt) = &mut self.filter_prompt {
+            if prompt.text.trim().is_empty() {
+                self.filter = None;
+                prompt.error = None;
+            } else {
+                match prompt.text.parse() {
+                    Ok(filter) => {
+                        self.filter = Some(filter);
+                        prompt.error = None;
+                    }
+                    Err(e) => prompt.error = Some(e),
+                }
+            }
+        }
+        self.clamp_scrolls();
+    }
+
+    fn handle_search(&mut self, code: KeyCode, modifiers: KeyModifiers) {
+        let Some(search) = &mut self.search else {
+            return;
+        };
+        if search.editing {
+            match code {
+                KeyCode::Esc => self.search = None,
+                KeyCode::Enter if search.hits.is_empty() => {}
+                KeyCode::Enter => {
+                    // Newest first, as the panels follow the live end.
+                    search.editing = false;
+                    search.scroll.select_bottom(search.hits.len(), search.vh);
+                    self.jump_to_hit();
+                }
+                KeyCode::Backspace => {
+                    search.query.pop();
+                    search.hits = search::search(
+                        self.collector.stream(),
+                        self.collector.messages(),
+                        &search.query,
+                    );
+                    search.scroll = ScrollState::new();
+                }
+                KeyCode::Char(c) if !c.is_control() => {
+                    search.query.push(c);
+                    search.hits = search::search(
+                        self.collector.stream(),
+                        self.collector.messages(),
+                        &search.query,
+                    );
+                    search.scroll = ScrollState::new();
+                }
+                _ => {}
+            }
+            return;
+        }
+        let (total, vh) = (search.hits.len(), search.vh.max(1));
+        match code {
+            KeyCode::Esc | KeyCode::Enter => self.search = None,
+            KeyCode::Backspace => search.editing = true,
+            _ => {
+                let action = self.keys.action(code, modifiers);
+                if action.is_some_and(|a| scroll_by(&mut search.scroll, a, total, vh)) {
+                    self.jump_to_hit();
+                }
+            }
+        }
+    }
+
+    /// Keys the Params tab handles itself: its search, sort order and
+    /// component. Returns `false` for keys left to the usual bindings.
+    fn handle_params(&mut self, code: KeyCode) -> bool {
+        let components = self.collector.params().components().count();
+        let view = &mut self.params;
+        if view.editing {
+            match code {
+                KeyCode::Esc => {
+                    view.query.clear();
+                    view.editing = false;
+                }
+                KeyCode::Enter => view.editing = false,
+                KeyCode::Backspace => {
+                    view.query.pop();
+                }
+                KeyCode::Char(c) if !c.is_control() => view.query.push(c),
+                _ => return true,
+            }
+        } else {
+            match code {
+                KeyCode::Esc if !view.query.is_empty() => view.query.clear(),
+                KeyCode::Char('/') => view.editing = true,
+                KeyCode::Char('o') => {
+                    view.order = view.order.next();
+                    return true;
+                }
+                KeyCode::Char('>') if components > 0 => {
+                    view.component = (view.component + 1) % components;
+                }
+                KeyCode::Char('<') if components > 0 => {
+                    view.component = (view.component + components - 1) % components;
+                }
+                _ => return false,
+            }
+        }
+        self.view_mut(Tab::Params).scroll = ScrollState::new();
+        true
+    }
+
+    /// Rows of the parameter table as searched.
+    fn params_total(&self) -> usize {
+        self.collector
+            .params()
+            .components()
+            .nth(self.params.component)
+            .map_or(0, |(_, set)| {
+                set.matching(&self.params.query, self.params.order).len()
+            })
+    }
+
+    /// Moves the panel holding the selected search hit to it: the Stream
+    /// tab time travels to the hit's value, the Events tab selects the row.
+    /// Filters hiding the hit are cleared.
+    fn jump_to_hit(&mut self) {
+        let Some(hit) = self
+            .search
+            .as_ref()
+            .and_then(|s| s.hits.get(s.scroll.selected))
+            .cloned()
+        else {
+            return;
+        };
+        let tab = match hit.target {
+            Target::Stream { latest } => {
+                self.as_of = (!latest).then_some(hit.timestamp);
+                Tab::Stream
+            }
+            Target::Event => {
+                self.as_of = None;
+                Tab::Events
+            }
+        };
+        let key = (hit.sys_id, hit.comp_id, hit.name);
+        let index = self.position_of(tab, key, hit.timestamp).or_else(|| {
+            self.direction_filter = None;
+            self.severity_filter = None;
+            self.isolate_sys = None;
+            self.isolate_name = None;
+            self.filter = None;
+            self.position_of(tab, key, hit.timestamp)
+        });
+        self.select_tab(tab as usize);
+        self.clamp_scrolls();
+        // Gone since the search, evicted from the events or the history.
+        let Some(index) = index else {
+            return;
+        };
+        let view = self.view_mut(tab);
+        view.scroll.select(index, view.vh);
+    }
+
+    /// Handle a key press. Returns `true` if the app should quit.
+    pub fn handle_key(&mut self, code: KeyCode, modifiers: KeyModifiers) -> bool {
+        if self.typeahead.is_some() {
+            self.handle_typeahead(code);
+            return false;
+        }
+        if self.search.is_some() {
+            self.handle_search(code, modifiers);
+            return false;
+        }
+        if self.filter_prompt.is_some() {
+            self.handle_filter(code);
+            return false;
+        }
+        if self.plot_prompt.is_some() {
+            self.handle_plot_prompt(code);
+            return false;
+        }
+        if self.find.as_ref().is_some_and(|f| f.editing) {
+            self.handle_find(code);
+            return false;
+        }
+        // Clears the `/` highlighting before it quits.
+        if code == KeyCode::Esc && self.find.is_some() {
+            self.find = None;
+            return false;
+        }
+        if self.tab() == Tab::Params && self.handle_params(code) {
+            return false;
+        }
+        let Some(action) = self.keys.action(code, modifiers) else {
+            if let KeyCode::Char(c @ '1'..='9') = code {
+                self.select_tab(c as usize - '1' as usize);
+            }
+            return false;
+        };
+        let total = self.active_total();
+        let vh = self.active_vh();
+        match action {
+            Action::Quit => return true,
+            Action::FocusDetail if matches!(self.tab(), Tab::Stream | Tab::Events) => {
+                self.detail_focused = !self.detail_focused;
+            }
+            Action::Up
+            | Action::Down
+            | Action::PageUp
+            | Action::PageDown
+            | Action::Top
+            | Action::Bottom
+                if self.detail_focused =>
+            {
+                self.handle_detail_key(action)
+            }
+            Action::PrevTab | Action::NextTab
+                if self.detail_focused && self.detail_overflow == Overflow::Scroll =>
+            {
+                self.handle_detail_key(action);
+            }
+            Action::SaveSession => self.save_session(),
+            Action::SaveSanitized => self.save_sanitized(),
+            Action::Snapshot => self.save_snapshot(),
+            Action::SavePcapng => self.save_pcapng(),
+            Action::ByteMap => {
+                self.detail_mode = self.detail_mode.toggle(DetailMode::ByteMap);
+            }
+            Action::HideDefaults => self.hide_defaults = !self.hide_defaults,
+            Action::WrapMode => {
+                self.detail_overflow = self.detail_overflow.next();
+                self.detail_column = 0;
+            }
+            Action::OpenDocs => self.open_docs(),
+            Action::Clear => {
+                self.collector.clear();
+                self.session_log.clear();
+                self.as_of = None;
+                for view in &mut self.tabs {
+                    view.scroll = ScrollState::new();
+                }
+            }
+            Action::QueryIntervals => self.query_intervals(),
+            Action::SearchHistory => self.search = Some(Search::new()),
+            Action::Direction => self.cycle_direction_filter(),
+            Action::MoveCategory => {
+                if let Some(name) = self.selected_name() {
+                    let currently_stream = self.tab() == Tab::Stream;
+                    self.collector.toggle_category(name, currently_stream);
+                    self.clamp_scrolls();
+                }
+            }
+            Action::Intervals => self.show_intervals = !self.show_intervals,
+            Action::Severity => self.cycle_severity_filter(),
+            Action::Faster => {
+                if let Some(replay) = &mut self.replay {
+                    replay.faster();
+                }
+            }
+            Action::Slower => {
+                if let Some(replay) = &mut self.replay {
+                    replay.slower();
+                }
+            }
+            Action::Filter => self.open_filter(),
+            Action::Params => self.select_tab(Tab::Params as usize),
+            Action::Plot => self.open_plot_prompt(),
+            Action::ClosePlot => self.plot = None,
+            Action::RawFrame => {
+                self.detail_mode = self.detail_mode.toggle(DetailMode::Frame);
+            }
+            Action::ExportRow => self.export_json(false),
+            Action::ExportEvents => self.export_json(true),
+            Action::StatusText => self.select_tab(Tab::Console as usize),
+            Action::Pause => self.toggle_pause(),
+            Action::Find if matches!(self.tab(), Tab::Stream | Tab::Events) => {
+                self.find = Some(Find {
+                    query: String::new(),
+                    editing: true,
+                    origin: self.active_scroll().selected,
+                });
+            }
+            Action::FindNext if self.find.is_some() => self.find_step(true, false),
+            Action::FindPrev if self.find.is_some() => self.find_step(false, false),
+            Action::OnlySystem => self.toggle_isolate(false),
+            Action::OnlyMessage => self.toggle_isolate(true),
+            Action::Performance => self.show_perf = !self.show_perf,
+            Action::Back => self.travel(-TimeDelta::seconds(1)),
+            Action::Forward => self.travel(TimeDelta::seconds(1)),
+            Action::BackFar => self.travel(-TimeDelta::seconds(10)),
+            Action::ForwardFar => self.travel(TimeDelta::seconds(10)),
+            Action::JumpToName if self.tab() == Tab::Stream => {
+                self.typeahead = Some(String::new());
+            }
+            Action::NextTab => self.next_tab(),
+            Action::PrevTab => self.prev_tab(),
+            _ => {
+                scroll_by(self.active_scroll(), action, total, vh);
+            }
+        }
+        false
+    }
+
+    pub async fn run(
+        &mut self,
+        terminal: &mut DefaultTerminal,
+        mut mav_rx: tokio::sync::mpsc::Receiver<Incoming>,
+    ) -> io::Result<()> {
+        let mut event_stream = EventStream::new();
+        let mut tick = tokio::time::interval(TICK);
+        tick.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        loop {
+            tokio::select! {
+                Some(incoming) = mav_rx.recv() => self.receive(Utc::now(), incoming),
+                Some(Ok(event)) = event_stream.next() => {
+                    if let Event::Key(key) = event
+                        && key.kind == KeyEventKind::Press
+                        && self.handle_key(key.code, key.modifiers)
+                    {
+                        self.finish_recordings();
+                        return Ok(());
+                    }
+                }
+                _ = tick.tick() => {
+                    // A loaded session is a snapshot; nothing more is coming.
+                    // While paused the collector has not seen what arrived,
+                    // so its timeouts would fire on traffic that is only held.
+                    if !self.offline && self.paused.is_none() {
+                        self.collector.tick(Utc::now());
+                    }
+                    self.advance_replay(Instant::now());
+                    if let Some(result) = self.recorder.as_mut().map(Recorder::flush) {
+                        self.check_recorder(result);
+                    }
+                    if let Some(result) = self.pcapng.as_mut().map(PcapngWriter::flush) {
+                        self.check_pcapng(result);
+                    }
+                    let total = self.collector.event_total();
+                    if let Some(result) = self
+                        .mirror
+                        .as_mut()
+                        .map(|m| m.sync(self.collector.messages(), total))
+                    {
+                        self.check_mirror(result);
+                    }
+                    if let Some(server) = &self.http {
+                        let now = self.now();
+                        for request in server.pending() {
+                            let response = http::route(&self.collector, &request.path, now);
+                            request.respond(response);
+                        }
+                    }
+                    let now = Utc::now();
+                    let alerts = self.collector.alert_count();
+                    if let Some(result) = self.capture.as_mut().map(|c| {
+                        c.flush(now)?;
+                        c.check_alerts(now, alerts)
+                    }) {
+                        self.check_capture(result);
+                    }
+                    let started = Instant::now();
+                    terminal.draw(|frame| self.draw_frame(frame))?;
+                    self.notify_terminal()?;
+                    self.perf.frame(Instant::now(), started.elapsed(), mav_rx.len());
+                    self.check_performance(started.elapsed(), mav_rx.len());
+                }
+            }
+        }
+    }
+
+    /// Logs when drawing cannot keep up with the tick or frames are queueing
+    /// faster than they are handled, at most every [`PERF_WARNING_EVERY`].
+    fn check_performance(&mut self, draw: Duration, backlog: usize) {
+        let slow_draw = draw > TICK;
+        let backed_up = backlog >= CHANNEL_CAPACITY * 3 / 4;
+        if !(slow_draw || backed_up)
+            || self
+                .perf_warned
+                .is_some_and(|at| at.elapsed() < PERF_WARNING_EVERY)
+        {
+            return;
+        }
+        self.perf_warned = Some(Instant::now());
+        if slow_draw {
+            tracing::warn!(draw_ms = draw.as_millis() as u64, "slow redraw");
+        }
+        if backed_up {
+            tracing::warn!(backlog, "receive queue backing up");
+        }
+    }
+
+    /// Takes one item from a link as if it arrived at `at`: recorded, then
+    /// collected.
+    pub fn receive(&mut self, at: DateTime<Utc>, incoming: Incoming) {
+        self.perf.received();
+        self.session_log.push(at, &incoming);
+        if let Some(result) = self.recorder.as_mut().map(|r| r.record(at, &incoming)) {
+            self.check_recorder(result);
+        }
+        if let Some(result) = self.capture.as_mut().map(|c| c.record(at, &incoming)) {
+            self.check_capture(result);
+        }
+        if let Some(result) = self.pcapng.as_mut().map(|p| p.record(at, &incoming)) {
+            self.check_pcapng(result);
+        }
+        if let Some(server) = &self.websocket {
+            server.publish(&incoming);
+        }
+        if let Some(held) = &mut self.paused {
+            held.push(at, incoming, self.collector.limits());
+            return;
+        }
+        self.ingest(at, incoming);
+    }
+
+    /// Hands a received item to the collector and what watches its alerts.
+    fn ingest(&mut self, at: DateTime<Utc>, incoming: Incoming) {
+        match incoming {
+            Incoming::Message(msg) => self.collector.push(msg),
+            Incoming::Diagnostic(diag) => self.collector.push_diagnostic(diag),
+        }
+        if let Some(plot) = &mut self.plot
+            && let Some(entry) = self
+                .collector
+                .stream_entry(plot.sys_id, plot.comp_id, plot.name)
+        {
+            plot.observe(entry);
+        }
+        let alerts = self.collector.alert_count();
+        if let Some(result) = self.capture.as_mut().map(|c| c.check_alerts(at, alerts)) {
+            self.check_capture(result);
+        }
+        // The collector's views take priority; the log gets what is left.
+        if let Some(max) = self.max_memory {
+            let used = self.collector.usage().bytes;
+            self.session_log.trim_to(max.saturating_sub(used));
+        }
+        self.follow_evictions();
+    }
+
+    /// Stops the panels moving so a fast stream can be read; resuming
+    /// applies everything held back in the meantime.
+    fn toggle_pause(&mut self) {
+        match self.paused.take() {
+            Some(held) => {
+                for (at, incoming) in held.items {
+                    self.ingest(at, incoming);
+                }
+            }
+            None => self.paused = Some(Held::default()),
+        }
+    }
+
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.collector.set_limits(limits);
+        self.max_memory = limits.max_memory;
+        if let Some(max) = limits.max_memory {
+            self.session_log.set_max_bytes(max);
+        }
+        self
+    }
+
+    /// Keeps the Events selection on the same entry as old ones are dropped
+    /// from the front; only those the filters showed were rows above it.
+    fn follow_evictions(&mut self) {
+        let dropped = self
+            .collector
+            .take_evicted()
+            .iter()
+            .filter(|e| self.lists_event(e))
+            .count();
+        let scroll = &mut self.view_mut(Tab::Events).scroll;
+        scroll.selected = scroll.selected.saturating_sub(dropped);
+        scroll.offset = scroll.offset.saturating_sub(dropped);
+    }
+
+    pub fn with_templates(mut self, templates: HashMap<String, Template>) -> Self {
+        self.templates = templates;
+        self
+    }
+
+    pub fn with_keymap(mut self, keys: KeyMap) -> Self {
+        self.footer = Paragraph::new(footer_line(&keys));
+        self.keys = keys;
+        self
+    }
+
+    pub fn with_highlights(mut self, highlights: Highlights) -> Self {
+        self.highlights = highlights;
+        self
+    }
+
+    pub fn with_derived(mut self, derived: DerivedFields) -> Self {
+        self.collector.set_derived(derived);
+        self
+    }
+
+    pub fn with_recorder(mut self, recorder: Recorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// Stops autosaving after a write error rather than failing every frame.
+    fn check_recorder(&mut self, result: io::Result<()>) {
+        if let Err(e) = result {
+            self.recorder = None;
+            self.collector
+                .push_note("AUTOSAVE_FAILED", format!("error: {e}"));
+        }
+    }
+
+    /// Closes the autosave and trigger files on exit, so compressed ones
+    /// end cleanly.
+    fn finish_recordings(&mut self) {
+        if let Some(result) = self.recorder.as_mut().map(Recorder::finish) {
+            self.check_recorder(result);
+        }
+        if let Some(result) = self.capture.as_mut().map(RingCapture::finish) {
+            self.check_capture(result.map(|()| None));
+        }
+    }
+
+    pub fn with_mirror(mut self, mirror: EventMirror) -> Self {
+        self.mirror = Some(mirror);
+        self
+    }
+
+    /// Stops mirroring after a write error, like the autosave.
+    fn check_mirror(&mut self, result: io::Result<()>) {
+        if let Err(e) = result {
+            self.mirror = None;
+            self.collector
+                .push_note("MIRROR_FAILED", format!("error: {e}"));
+        }
+    }
+
+    pub fn with_pcapng(mut self, writer: PcapngWriter<BufWriter<File>>) -> Self {
+        self.pcapng = Some(writer);
+        self
+    }
+
+    /// Stops the `--pcapng` capture after a write error, like the autosave.
+    fn check_pcapng(&mut self, result: io::Result<()>) {
+        if let Err(e) = result {
+            self.pcapng = None;
+            self.collector
+                .push_note("PCAPNG_FAILED", format!("error: {e}"));
+        }
+    }
+
+    pub fn with_websocket(mut self, server: WsServer) -> Self {
+        self.websocket = Some(server);
+        self
+    }
+
+    pub fn with_http(mut self, server: HttpServer) -> Self {
+        self.http = Some(server);
+        self
+    }
+
+    pub fn with_capture(mut self, capture: RingCapture) -> Self {
+        self.capture = Some(capture);
+        self
+    }
+
+    fn check_capture(&mut self, result: io::Result<Option<PathBuf>>) {
+        match result {
+            Ok(None) => {}
+            Ok(Some(path)) => self
+                .collector
+                .push_note("CAPTURE_TRIGGERED", format!("path: {}", path.display())),
+            Err(e) => {
+                self.capture = None;
+                self.collector
+                    .push_note("CAPTURE_FAILED", format!("error: {e}"));
+            }
+        }
+    }
+
+    /// Replays a saved session and puts the UI back as it was.
+    pub fn with_session(mut self, session: Session) -> Self {
+        let Session { meta, records } = session;
+        self = self.with_state(meta.ui).with_records(meta.links, records);
+        for (view, &selected) in self.tabs.iter_mut().zip(&meta.selected) {
+            view.scroll.selected = selected;
+            view.scroll.auto_scroll = false;
+        }
+        self.clamp_scrolls();
+        self
+    }
+
+    /// Replays recorded traffic instead of connecting, keeping the current
+    /// UI state (unlike [`Self::with_session`]).
+    pub fn with_records(
+        mut self,
+        links: Vec<String>,
+        records: Vec<(DateTime<Utc>, Incoming)>,
+    ) -> Self {
+        self.links = links;
+        self.collector.set_clock_alignment(true);
+        for (at, incoming) in records {
+            self.receive(at, incoming);
+            self.collector.tick(at);
+        }
+        self.clamp_scrolls();
+        self.offline = true;
+        self
+    }
+
+    /// Plays recorded traffic back at its recorded pace, `+`/`-` changing
+    /// the speed, instead of loading it at once like
+    /// [`Self::with_records`]. Ages and rates follow the recorded time.
+    pub fn with_replay(
+        mut self,
+        links: Vec<String>,
+        records: Vec<(DateTime<Utc>, Incoming)>,
+    ) -> Self {
+        self.links = links;
+        self.collector.set_clock_alignment(true);
+        self.replay = Replay::new(records);
+        self.clock = self.replay.as_ref().map(Replay::position);
+        self.offline = true;
+        self
+    }
+
+    /// Feeds the replay whatever its recorded time has reached.
+    fn advance_replay(&mut self, now: Instant) {
+        let Some(replay) = &mut self.replay else {
+            return;
+        };
+        let due = replay.advance(now);
+        let position = replay.position();
+        for (at, incoming) in due {
+            self.receive(at, incoming);
+            self.collector.tick(at);
+        }
+        self.collector.tick(position);
+        self.clock = Some(position);
+    }
+
+    fn session_meta(&self) -> SessionMeta {
+        SessionMeta {
+            links: self.links.clone(),
+            selected: self.tabs.iter().map(|v| v.scroll.selected).collect(),
+            ui: self.ui_state(),
+        }
+    }
+
+    fn save_session(&mut self) {
+        let path = PathBuf::from(format!(
+            "mavsnark-{}.session",
+            Local::now().format("%Y%m%d-%H%M%S")
+        ));
+        let note = match session::save(&path, &self.session_meta(), self.session_log.as_bytes()) {
+            Ok(()) => ("SESSION_SAVED", format!("path: {}", path.display())),
+            Err(e) => (
+                "SESSION_SAVE_FAILED",
+                format!("path: {}, error: {e}", path.display()),
+            ),
+        };
+        self.collector.push_note(note.0, note.1);
+    }
+
+    /// Like [`Self::save_session`], but safe to share; see [`Sanitizer`].
+    fn save_sanitized(&mut self) {
+        let path = PathBuf::from(format!(
+            "mavsnark-{}-sanitized.session",
+            Local::now().format("%Y%m%d-%H%M%S")
+        ));
+        let meta = self.session_meta();
+        let note = match sanitize::export(
+            &path,
+            meta,
+            self.session_log.as_bytes(),
+            &Sanitizer::random(),
+        ) {
+            Ok(()) => ("SESSION_SAVED", format!("path: {}", path.display())),
+            Err(e) => (
+                "SESSION_SAVE_FAILED",
+                format!("path: {}, error: {e}", path.display()),
+            ),
+        };
+        self.collector.push_note(note.0, note.1);
+    }
+
+    /// Writes the captured frames to a pcapng file for Wireshark.
+    fn save_pcapng(&mut self) {
+        let path = PathBuf::from(format!(
+            "mavsnark-{}.pcapng",
+            Local::now().format("%Y%m%d-%H%M%S")
+        ));
+        let note = match pcap::export(&path, &self.links, self.session_log.as_bytes()) {
+            Ok(frames) => (
+                "PCAPNG_SAVED",
+                format!("path: {}, frames: {frames}", path.display()),
+            ),
+            Err(e) => (
+                "PCAPNG_SAVE_FAILED",
+                format!("path: {}, error: {e}", path.display()),
+            ),
+        };
+        self.collector.push_note(note.0, note.1);
+    }
+
+    /// Writes what is on screen, as of the time cursor, to a markdown file
+    /// for a flight test log.
+    fn save_snapshot(&mut self) {
+        let at = self.as_of.unwrap_or_else(|| self.now());
+        let path = PathBuf::from(format!(
+            "mavsnark-{}.md",
+            at.with_timezone(&Local).format("%Y%m%d-%H%M%S")
+        ));
+        let context = snapshot::Context {
+            links: &self.links,
+            modes: self.modes,
+            at,
+        };
+        let note = match snapshot::save(&path, &self.collector, &context) {
+            Ok(()) => ("SNAPSHOT_SAVED", format!("path: {}", path.display())),
+            Err(e) => (
+                "SNAPSHOT_SAVE_FAILED",
+                format!("path: {}, error: {e}", path.display()),
+            ),
+        };
+        self.collector.push_note(note.0, note.1);
+    }
+
+    /// Writes the selected row, or with `all` every event the Events tab
+    /// shows, to a JSON file for bug reports.
+    fn export_json(&mut self, all: bool) {
+        let at = self.as_of.unwrap_or_else(|| self.now());
+        let selected = self.tabs[self.active_tab].scroll.selected;
+        let records: Vec<Record> = match self.tab() {
+            _ if all => self.messages().into_iter().map(Record::event).collect(),
+            Tab::Stream => self
+                .stream()
+                .get(selected)
+                .copied()
+                .and_then(|e| Record::stream(e, self.as_of))
+                .into_iter()
+                .collect(),
+            Tab::Events => self
+                .messages()
+                .get(selected)
+                .copied()
+                .map(Record::event)
+                .into_iter()
+                .collect(),
+            Tab::Stats | Tab::Params | Tab::Console | Tab::Map => Vec::new(),
+        };
+        let records: Vec<Record> = records
+            .into_iter()
+            .map(|r| {
+                let clock = self.collector.clock(r.sys_id, r.comp_id);
+                r.with_clock(clock)
+            })
+            .collect();
+        if records.is_empty() {
+            return;
+        }
+        let path = PathBuf::from(format!(
+            "mavsnark-{}.json",
+            at.with_timezone(&Local).format("%Y%m%d-%H%M%S%.3f")
+        ));
+        let count = records.len();
+        let note = match export::save(&path, &records, at) {
+            Ok(()) => (
+                "EXPORT_SAVED",
+                format!("path: {}, records: {count}", path.display()),
+            ),
+            Err(e) => (
+                "EXPORT_FAILED",
+                format!("path: {}, error: {e}", path.display()),
+            ),
+        };
+        self.collector.push_note(note.0, note.1);
+    }
+
+    /// Number of alerts raised since the Events tab was last shown.
+    fn unread_alerts(&mut self) -> usize {
+        let count = self.collector.alert_count();
+        if self.tab() == Tab::Events || count < self.alerts_seen {
+            self.alerts_seen = count;
+        }
+        count - self.alerts_seen
+    }
+
+    /// Keeps the window title current and rings the bell on new alerts, so
+    /// mavsnark in a background pane can still get attention.
+    fn notify_terminal(&mut self) -> io::Result<()> {
+        let unread = self.unread_alerts();
+        let title = window_title(&self.links, self.collector.vehicle_count(), unread);
+        let mut out = io::stdout();
+        if title != self.title {
+            execute!(out, SetTitle(&title))?;
+            self.title = title;
+        }
+        let count = self.collector.alert_count();
+        if self.bell && count > self.alerts_rung {
+            out.write_all(b"\x07")?;
+            out.flush()?;
+        }
+        if count > self.alerts_rung {
+            self.run_notify_hooks(count - self.alerts_rung);
+        }
+        self.alerts_rung = count;
+        Ok(())
+    }
+
+    /// Runs the `[notify]` hooks for the `new` latest alerts. A loaded
+    /// session is history, so it pages no one.
+    fn run_notify_hooks(&mut self, new: usize) {
+        let Some(notifier) = self.notifier.as_mut().filter(|_| !self.offline) else {
+            return;
+        };
+        let mut alerts: Vec<&MessageEntry> = self
+            .collector
+            .messages()
+            .iter()
+            .rev()
+            .filter(|e| matches!(e.kind, EventKind::Alert | EventKind::Security))
+            .take(new)
+            .collect();
+        alerts.reverse();
+        let errors: Vec<String> = alerts
+            .into_iter()
+            .filter_map(|entry| notifier.notify(entry).err())
+            .map(|e| format!("error: {e}"))
+            .collect();
+        for error in errors {
+            self.collector.push_note("NOTIFY_FAILED", error);
+        }
+    }
+
+    fn draw_frame(&mut self, frame: &mut Frame) {
+        self.draw(frame);
+        if self.show_perf {
+            let area = centered(frame.area(), 52, 8);
+            frame.render_widget(Clear, area);
+            frame.render_widget(self.build_perf(), area);
+        }
+        self.palette.apply(frame.buffer_mut());
+        self.fallback.apply(frame.buffer_mut());
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let mut rows = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .split(frame.area())
+        .to_vec();
+
+        // Below both panels, whichever tab is shown.
+        if let Some(plot) = &self.plot {
+            let parts =
+                Layout::vertical([Constraint::Min(0), Constraint::Length(12)]).split(rows[2]);
+            rows[2] = parts[0];
+            render_field_plot(frame, parts[1], plot, self.now());
+        }
+
+        // The home readout only takes room once a vehicle has a home.
+        let home_width = if self.collector.home().ranges().next().is_some() {
+            26
+        } else {
+            0
+        };
+        let header = Layout::horizontal([
+            Constraint::Length(38),
+            Constraint::Min(0),
+            Constraint::Length(home_width),
+        ])
+        .split(rows[0]);
+        frame.render_widget(&*HEADER, header[0]);
+        frame.render_widget(self.build_links(), header[1]);
+        frame.render_widget(self.build_home(), header[2]);
+        match &self.replay {
+            Some(replay) => {
+                let status = replay_status(replay);
+                let parts = Layout::horizontal([
+                    Constraint::Min(0),
+                    Constraint::Length(status.width() as u16),
+                ])
+                .split(rows[1]);
+                frame.render_widget(self.build_tabs(), parts[0]);
+                frame.render_widget(status, parts[1]);
+            }
+            None => frame.render_widget(self.build_tabs(), rows[1]),
+        }
+
+        let halves = [Constraint::Percentage(50), Constraint::Percentage(50)];
+        let columns = if self.layout.is_vertical(rows[2].width) {
+            Layout::vertical(halves).split(rows[2])
+        } else {
+            Layout::horizontal(halves).split(rows[2])
+        };
+        let vh = columns[0].height.saturating_sub(2) as usize;
+        for view in &mut self.tabs {
+            view.vh = vh;
+        }
+
+        // Auto-follow before drawing
+        let stream_total = self.stream().len();
+        self.view_mut(Tab::Stream)
+            .scroll
+            .auto_follow(stream_total, vh);
+        let messages_total = self.messages().len();
+        self.view_mut(Tab::Events)
+            .scroll
+            .auto_follow(messages_total, vh);
+
+        let (list, mut scrollbar) = match self.tab() {
+            Tab::Stream => self.build_stream(),
+            Tab::Events => self.build_messages(),
+            Tab::Stats => {
+                let links = self.links.len().clamp(1, 4) as u16;
+                let left = Layout::vertical([
+                    Constraint::Min(0),
+                    Constraint::Length(8),
+                    Constraint::Length(6),
+                    Constraint::Length(links + 2),
+                ])
+                .split(columns[0]);
+                frame.render_widget(self.build_talkers(), left[0]);
+                frame.render_widget(self.build_safety(left[1].height), left[1]);
+                frame.render_widget(self.build_storage(), left[2]);
+                frame.render_widget(self.build_link_errors(), left[3]);
+                let audit = self
+                    .collector
+                    .intervals()
+                    .audit(self.collector.stream(), self.now());
+                let intervals = match audit.len() {
+                    0 => 0,
+                    n => (n as u16 + 2).min(14),
+                };
+                let right = Layout::vertical([
+                    Constraint::Min(0),
+                    Constraint::Length(intervals),
+                    Constraint::Length(12),
+                ])
+                .split(columns[1]);
+                frame.render_widget(self.build_flow(), right[0]);
+                if !audit.is_empty() {
+                    frame.render_widget(build_intervals(&audit), right[1]);
+                }
+                let selected = self
+                    .stream()
+                    .get(self.view(Tab::Stream).scroll.selected)
+                    .map(|e| e.name);
+                render_rate_chart(frame, right[2], self.collector.throughput(), selected);
+                frame.render_widget(&self.footer, rows[3]);
+                return;
+            }
+            // The table is wide, and unrelated to the other panels' rows.
+            Tab::Params => {
+                self.view_mut(Tab::Params).vh = rows[2].height.saturating_sub(3) as usize;
+                frame.render_widget(
+                    build_params(&self.params, self.view(Tab::Params), &self.collector),
+                    rows[2],
+                );
+                frame.render_widget(params_prompt(&self.params), rows[3]);
+                return;
+            }
+            Tab::Console => {
+                let total = self.collector.statustext().lines().len();
+                let view = self.view_mut(Tab::Console);
+                view.vh = rows[2].height.saturating_sub(2) as usize;
+                view.scroll.auto_follow(total, view.vh);
+                frame.render_widget(
+                    build_console(self.view(Tab::Console), &self.collector),
+                    rows[2],
+                );
+                frame.render_widget(&self.footer, rows[3]);
+                return;
+            }
+            Tab::Map => {
+                let parts =
+                    Layout::horizontal([Constraint::Min(0), Constraint::Length(48)]).split(rows[2]);
+                render_map(frame, parts[0], self.collector.map());
+                frame.render_widget(self.build_positions(), parts[1]);
+                frame.render_widget(&self.footer, rows[3]);
+                return;
+            }
+        };
+        frame.render_widget(list, columns[0]);
+        frame.render_stateful_widget(
+            Scrollbar::new(ScrollbarOrientation::VerticalRight),
+            columns[0],
+            &mut scrollbar,
+        );
+
+        if self.selected_radio().is_some() {
+            let detail =
+                Layout::vertical([Constraint::Min(0), Constraint::Length(12)]).split(columns[1]);
+            self.render_detail(frame, detail[0]);
+            if let Some(samples) = self.selected_radio() {
+                render_radio_chart(
+                    frame,
+                    detail[1],
+                    samples,
+                    self.collector.radio().loss(),
+                    self.now(),
+                );
+            }
+        } else if self
+            .selected_stream()
+            .is_some_and(|e| e.name == "FOLLOW_TARGET")
+        {
+            let detail =
+                Layout::vertical([Constraint::Min(0), Constraint::Length(12)]).split(columns[1]);
+            let parts = Layout::horizontal([
+                Constraint::Length(36),
+                Constraint::Min(0),
+                Constraint::Min(0),
+            ])
+            .split(detail[1]);
+            frame.render_widget(self.build_follow(), parts[0]);
+            // Charted for the first vehicle; the table lists them all.
+            if let Some((_, samples)) = self.collector.follow().vehicles().next() {
+                render_separation_charts(frame, [parts[1], parts[2]], samples, self.now());
+            }
+            self.render_detail(frame, detail[0]);
+        } else if let Some(winch) = self.selected_winch() {
+            let detail =
+                Layout::vertical([Constraint::Min(0), Constraint::Length(12)]).split(columns[1]);
+            let parts =
+                Layout::horizontal([Constraint::Length(42), Constraint::Min(0)]).split(detail[1]);
+            frame.render_widget(self.build_winch(winch), parts[0]);
+            render_winch_chart(frame, parts[1], &winch.samples, self.now());
+            self.render_detail(frame, detail[0]);
+        } else if let Some((key, modem)) = self.selected_modem() {
+            let detail =
+                Layout::vertical([Constraint::Min(0), Constraint::Length(12)]).split(columns[1]);
+            let parts =
+                Layout::horizontal([Constraint::Length(42), Constraint::Min(0)]).split(detail[1]);
+            frame.render_widget(self.build_cellular(key, modem), parts[0]);
+            render_quality_chart(frame, parts[1], &modem.quality, self.now());
+            self.render_detail(frame, detail[0]);
+        } else if self
+            .selected_stream()
+            .is_some_and(|e| e.name == "EXTENDED_SYS_STATE")
+        {
+            let detail =
+                Layout::vertical([Constraint::Min(0), Constraint::Length(12)]).split(columns[1]);
+            frame.render_widget(self.build_vtol(), detail[1]);
+            self.render_detail(frame, detail[0]);
+        } else if self.uavcan_selected() {
+            let detail =
+                Layout::vertical([Constraint::Min(0), Constraint::Length(12)]).split(columns[1]);
+            frame.render_widget(self.build_uavcan(), detail[1]);
+            self.render_detail(frame, detail[0]);
+        } else if let Some((id, battery)) = self.selected_battery() {
+            let detail =
+                Layout::vertical([Constraint::Min(0), Constraint::Length(12)]).split(columns[1]);
+            let parts =
+                Layout::horizontal([Constraint::Length(40), Constraint::Min(0)]).split(detail[1]);
+            frame.render_widget(self.build_battery(id, battery), parts[0]);
+            frame.render_widget(self.build_phases(battery), parts[1]);
+            self.render_detail(frame, detail[0]);
+        } else if let Some(terrain) = self.selected_terrain() {
+            let detail =
+                Layout::vertical([Constraint::Min(0), Constraint::Length(12)]).split(columns[1]);
+            let parts =
+                Layout::horizontal([Constraint::Length(34), Constraint::Min(0)]).split(detail[1]);
+            let margin = self.collector.terrain().margin();
+            frame.render_widget(self.build_terrain(terrain), parts[0]);
+            render_clearance_chart(frame, parts[1], &terrain.samples, margin, self.now());
+            self.render_detail(frame, detail[0]);
+        } else if let Some(component) = self.selected_component() {
+            let detail =
+                Layout::vertical([Constraint::Min(0), Constraint::Length(12)]).split(columns[1]);
+            frame.render_widget(self.build_component(component), detail[1]);
+            self.render_detail(frame, detail[0]);
+        } else if let Some(nav) = self.selected_navigation() {
+            let detail =
+                Layout::vertical([Constraint::Min(0), Constraint::Length(12)]).split(columns[1]);
+            let parts = Layout::horizontal([
+                Constraint::Length(32),
+                Constraint::Min(0),
+                Constraint::Min(0),
+            ])
+            .split(detail[1]);
+            frame.render_widget(self.build_navigation(nav), parts[0]);
+            render_navigation_charts(frame, [parts[1], parts[2]], &nav.samples, self.now());
+            self.render_detail(frame, detail[0]);
+        } else if let Some(sys_id) = self.selected_distance() {
+            let detail =
+                Layout::vertical([Constraint::Min(0), Constraint::Length(12)]).split(columns[1]);
+            frame.render_widget(self.build_distance(sys_id), detail[1]);
+            self.render_detail(frame, detail[0]);
+        } else if let Some(power) = self.selected_power() {
+            let detail =
+                Layout::vertical([Constraint::Min(0), Constraint::Length(12)]).split(columns[1]);
+            let parts =
+                Layout::horizontal([Constraint::Length(34), Constraint::Min(0)]).split(detail[1]);
+            frame.render_widget(self.build_power(power), parts[0]);
+            render_power_chart(frame, parts[1], &power.samples, self.now());
+            self.render_detail(frame, detail[0]);
+        } else if let Some(vehicle) = self.selected_vibration() {
+            let detail =
+                Layout::vertical([Constraint::Min(0), Constraint::Length(12)]).split(columns[1]);
+            let parts = Layout::horizontal([
+                Constraint::Length(30),
+                Constraint::Min(0),
+                Constraint::Min(0),
+            ])
+            .split(detail[1]);
+            let warn = self.collector.vibration().warn();
+            frame.render_widget(self.build_vibration(vehicle), parts[0]);
+            render_vibration_charts(
+                frame,
+                [parts[1], parts[2]],
+                &vehicle.samples,
+                warn,
+                self.now(),
+            );
+            self.render_detail(frame, detail[0]);
+        } else if let Some(vehicle) = self.selected_gps() {
+            let detail =
+                Layout::vertical([Constraint::Min(0), Constraint::Length(10)]).split(columns[1]);
+            frame.render_widget(self.build_gps(vehicle), detail[1]);
+            self.render_detail(frame, detail[0]);
+        } else if let Some(readings) = self.selected_imu() {
+            let detail =
+                Layout::vertical([Constraint::Min(0), Constraint::Length(12)]).split(columns[1]);
+            let parts = Layout::horizontal([
+                Constraint::Length(40),
+                Constraint::Length(34),
+                Constraint::Min(0),
+            ])
+            .split(detail[1]);
+            frame.render_widget(self.build_imu(readings), parts[0]);
+            let mag = self
+                .selected_stream()
+                .and_then(|e| self.collector.interference().vehicle(e.sys_id));
+            frame.render_widget(self.build_interference(mag), parts[1]);
+            if let Some(mag) = mag {
+                render_interference_chart(frame, parts[2], mag);
+            }
+            self.render_detail(frame, detail[0]);
+        } else if let Some(arrivals) = self.selected_arrivals() {
+            let detail =
+                Layout::vertical([Constraint::Min(0), Constraint::Length(12)]).split(columns[1]);
+            render_interval_histogram(frame, detail[1], arrivals);
+            self.render_detail(frame, detail[0]);
+        } else {
+            self.render_detail(frame, columns[1]);
+        }
+
+        // Results cover the detail panel, leaving the list it jumps visible.
+        if let Some(search) = &mut self.search {
+            search.vh = columns[1].height.saturating_sub(2) as usize;
+            frame.render_widget(Clear, columns[1]);
+            frame.render_widget(build_search(search), columns[1]);
+        }
+
+        if let Some(prompt) = &self.plot_prompt {
+            frame.render_widget(plot_prompt(prompt), rows[3]);
+            return;
+        }
+
+        let find = self.find.as_ref().filter(|f| f.editing);
+        match (
+            &self.typeahead,
+            &self.search,
+            &self.filter_prompt,
+            find,
+            self.as_of,
+        ) {
+            (Some(prefix), ..) => frame.render_widget(typeahead_prompt(prefix), rows[3]),
+            (None, Some(search), ..) => frame.render_widget(search_prompt(search), rows[3]),
+            (None, None, Some(prompt), ..) => frame.render_widget(filter_prompt(prompt), rows[3]),
+            (None, None, None, Some(find), _) => {
+                let count = self.find_hits().into_iter().filter(|&h| h).count();
+                frame.render_widget(find_prompt(&find.query, count), rows[3]);
+            }
+            (None, None, None, None, Some(as_of)) => {
+                frame.render_widget(self.timeline(as_of, rows[3].width), rows[3])
+            }
+            (None, None, None, None, None) => frame.render_widget(&self.footer, rows[3]),
+        }
+    }
+
+    fn build_tabs(&self) -> Tabs<'_> {
+        let titles = self
+            .tabs
+            .iter()
+            .enumerate()
+            .map(|(i, view)| format!("{} {}", i + 1, view.tab.title()));
+        Tabs::new(titles)
+            .select(self.active_tab)
+            .style(Style::default().fg(Color::Gray))
+            .highlight_style(Style::default().fg(Color::Cyan).bold())
+    }
+
+    fn build_links(&self) -> Paragraph<'_> {
+        let gray = Style::default().fg(Color::DarkGray);
+        let stats = self.collector.link_stats();
+        let now = self.now();
+        let lines: Vec<Line> = self
+            .links
+            .iter()
+            .enumerate()
+            .map(|(i, uri)| {
+                let mut spans = vec![Span::raw(format!(" {i} {uri}"))];
+                if self.links.len() > 1
+                    && let Some(s) = stats.get(i)
+                {
+                    spans.push(Span::styled(
+                        format!("  first {}  dup {}", s.first, s.duplicate),
+                        gray,
+                    ));
+                    let systems = self.collector.routes().systems_on(i, now);
+                    if !systems.is_empty() {
+                        spans.push(Span::styled("  sys", gray));
+                        for id in systems {
+                            spans.push(Span::styled(
+                                format!(" {id}"),
+                                Style::default().fg(id_color(id)),
+                            ));
+                        }
+                    }
+                }
+                Line::from(spans)
+            })
+            .collect();
+        Paragraph::new(lines)
+    }
+
+    /// Distance, bearing and height from home per vehicle, red beyond the
+    /// configured range.
+    fn build_home(&self) -> Paragraph<'_> {
+        let gray = Style::default().fg(Color::DarkGray);
+        let limit = self.collector.home().limit();
+        let lines: Vec<Line> = self
+            .collector
+            .home()
+            .ranges()
+            .map(|(id, range)| {
+                let style = if limit.is_some_and(|l| range.distance > l) {
+                    Style::default().fg(Color::LightRed).bold()
+                } else {
+                    Style::default()
+                };
+                let distance = if range.distance >= 1000.0 {
+                    format!("{:.2} km", range.distance / 1000.0)
+                } else {
+                    format!("{:.0} m", range.distance)
+                };
+                Line::from(vec![
+                    Span::styled(format!("⌂{id:<4}"), Style::default().fg(id_color(id))),
+                    Span::styled(format!("{distance:>8}"), style),
+                    Span::styled(
+                        format!(
+                            " {:>3.0}° {:<2}",
+                            range.bearing,
+                            home::compass_point(range.bearing)
+                        ),
+                        gray,
+                    ),
+                    Span::styled(format!(" {:+.0}m", range.height), gray),
+                ])
+            })
+            .collect();
+        Paragraph::new(lines)
+    }
+
+    /// Latest position of each vehicle, beside the Map tab's chart.
+    fn build_positions(&self) -> Paragraph<'_> {
+        let gray = Style::default().fg(Color::DarkGray);
+        let now = self.now();
+        let mut lines = vec![Line::styled(
+            format!(
+                "{:<4} {:>11} {:>12} {:>8} {:>5}",
+                "sys", "lat", "lon", "alt", "age"
+            ),
+            gray,
+        )];
+        lines.extend(self.collector.map().vehicles().filter_map(|(id, vehicle)| {
+            let (lat, lon) = vehicle.position()?;
+            let age = vehicle
+                .at
+                .map_or(0, |at| now.signed_duration_since(at).num_seconds());
+            Some(Line::from(vec![
+                Span::styled(format!("{id:<4}"), Style::default().fg(id_color(id))),
+                Span::raw(format!(
+                    " {lat:>11.6} {lon:>12.6} {:>7.1}m {age:>4}s",
+                    vehicle.alt
+                )),
+            ]))
+        }));
+        let block = Block::default()
+            .title(" Positions ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Gray));
+        Paragraph::new(lines).block(block)
+    }
+
+    fn build_stream(&self) -> (Paragraph<'_>, ScrollbarState) {
+        let view = self.view(Tab::Stream);
+        let vh = view.vh;
+        let stream = self.stream();
+        let total = stream.len();
+
+        let selected_style = Style::default().bg(Color::DarkGray);
+
+        let badges = self.rate_badges(&stream);
+        let badged = badges.iter().any(Option::is_some);
+        let any_v1 = self.collector.capabilities().any_v1();
+        let checking = signing::key().is_some();
+        let lines: Vec<Line> = stream
+            .iter()
+            .zip(badges)
+            .enumerate()
+            .skip(view.scroll.offset)
+            .take(vh)
+            .map(|(i, (entry, badge))| {
+                let mut line = entry.to_line(
+                    self.templates.get(entry.name),
+                    &self.highlights,
+                    self.as_of.or(self.clock),
+                );
+                // Before the message, so long fields cannot push it out of
+                // view; padded so the rows still line up.
+                if badged {
+                    let badge = match badge {
+                        Some(b) => Span::styled(
+                            format!("{:<8}", b.label()),
+                            Style::default().fg(b.color()),
+                        ),
+                        None => Span::raw(" ".repeat(8)),
+                    };
+                    line.spans.insert(line.spans.len() - 1, badge);
+                }
+                if checking {
+                    line.spans.insert(5, signature_span(Some(entry.signature)));
+                }
+                if any_v1 {
+                    line.spans.insert(5, wire_span(Some(entry.v1)));
+                }
+                if let Some(link) = self.link_span(entry.link) {
+                    line.spans.insert(5, link);
+                }
+                if let Some(find) = &self.find {
+                    line = highlight_matches(line, &find.query);
+                }
+                if i == view.scroll.selected {
+                    line.style(selected_style)
+                } else {
+                    line
+                }
+            })
+            .collect();
+
+        let block = panel_block(
+            &self.panel_label("Stream"),
+            total,
+            "types",
+            view.scroll.auto_scroll,
+            true,
+        );
+
+        let paragraph = Paragraph::new(lines).block(block);
+        let scrollbar_state =
+            ScrollbarState::new(total.saturating_sub(vh)).position(view.scroll.offset);
+
+        (paragraph, scrollbar_state)
+    }
+
+    /// Rate badges for `stream`, expected rates from the config first and
+    /// the MESSAGE_INTERVAL answers second. None while time travelling, as
+    /// the rates are the current ones.
+    fn rate_badges(&self, stream: &[&StreamEntry]) -> Vec<Option<RateBadge>> {
+        if self.as_of.is_some() {
+            return vec![None; stream.len()];
+        }
+        let now = self.now();
+        let configured: HashMap<_, _> = self
+            .collector
+            .intervals()
+            .audit(self.collector.stream(), now)
+            .into_iter()
+            .filter_map(|r| Some(((r.sys_id, r.comp_id, r.name), r.configured?.hz()?)))
+            .collect();
+        let rates = self.collector.rates();
+        stream
+            .iter()
+            .map(|e| {
+                let configured = configured.get(&(e.sys_id, e.comp_id, e.name)).copied();
+                rates.badge(e, configured, now)
+            })
+            .collect()
+    }
+
+    /// Which link a row came from, after its ids; only with more than one.
+    fn link_span(&self, link: usize) -> Option<Span<'static>> {
+        (self.links.len() > 1).then(|| {
+            Span::styled(
+                format!("L{link:<2}"),
+                Style::default().fg(id_color(link as u8)),
+            )
+        })
+    }
+
+    fn build_messages(&self) -> (Paragraph<'_>, ScrollbarState) {
+        let view = self.view(Tab::Events);
+        let vh = view.vh;
+        let messages = self.messages();
+        let total = messages.len();
+
+        let selected_style = Style::default().bg(Color::DarkGray);
+        let any_v1 = self.collector.capabilities().any_v1();
+        let checking = signing::key().is_some();
+
+        let lines: Vec<Line> = messages
+            .iter()
+            .enumerate()
+            .skip(view.scroll.offset)
+            .take(vh)
+            .map(|(i, entry)| {
+                // Templates describe message fields, not generated events.
+                let template = match entry.kind {
+                    EventKind::Message => self.templates.get(entry.name),
+                    _ => None,
+                };
+                let mut line =
+                    entry.to_line(template, &self.highlights, self.classifier.classify(entry));
+                let message = entry.kind == EventKind::Message;
+                if checking {
+                    line.spans
+                        .insert(5, signature_span(message.then_some(entry.signature)));
+                }
+                if any_v1 {
+                    line.spans.insert(5, wire_span(message.then_some(entry.v1)));
+                }
+                if let Some(link) = self.link_span(entry.link) {
+                    line.spans.insert(5, link);
+                }
+                if let Some(find) = &self.find {
+                    line = highlight_matches(line, &find.query);
+                }
+                if i == view.scroll.selected {
+                    line.style(selected_style)
+                } else {
+                    line
+                }
+            })
+            .collect();
+
+        let mut label = self.panel_label("Events");
+        if let Some(min) = self.severity_filter {
+            label.push_str(&format!(" \u{2265}{}", min.label()));
+        }
+        let counts: Vec<String> = Severity::ALL
+            .iter()
+            .zip(self.severity_counts())
+            .map(|(severity, count)| format!("{count} {}", severity.label()))
+            .collect();
+        let block = panel_block(
+            &label,
+            total,
+            &format!("| {}", counts.join(", ")),
+            view.scroll.auto_scroll,
+            true,
+        );
+
+        let paragraph = Paragraph::new(lines).block(block);
+        let scrollbar_state =
+            ScrollbarState::new(total.saturating_sub(vh)).position(view.scroll.offset);
+
+        (paragraph, scrollbar_state)
+    }
+
+    fn build_flow(&self) -> Paragraph<'_> {
+        let block = Block::default()
+            .title(" Flow ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Gray));
+        let gray = Style::default().fg(Color::DarkGray);
+        let node = |(sys, comp): flow::Node| {
+            if (sys, comp) == (0, 0) {
+                Span::styled(format!("{:>7}", "*"), gray)
+            } else {
+                Span::styled(
+                    format!("{sys:>3}:{comp:<3}"),
+                    Style::default().fg(id_color(sys)),
+                )
+            }
+        };
+        let lines: Vec<Line> = self
+            .collector
+            .flow()
+            .edges()
+            .map(|(from, to, counts)| {
+                let total: u64 = counts.iter().map(|(_, c)| c).sum();
+                let names: Vec<String> = counts
+                    .iter()
+                    .take(3)
+                    .map(|(name, c)| format!("{name} \u{00d7}{c}"))
+                    .collect();
+                let more = if counts.len() > 3 { ", \u{2026}" } else { "" };
+                Line::from(vec![
+                    node(from),
+                    Span::raw(" \u{2192} "),
+                    node(to),
+                    Span::styled(format!(" {total:>6}  "), gray),
+                    Span::raw(format!("{}{more}", names.join(", "))),
+                ])
+            })
+            .collect();
+        Paragraph::new(lines).block(block)
+    }
+
+    /// Arm authorization and allowed-area outcomes, newest first.
+    fn build_safety(&self, height: u16) -> Paragraph<'_> {
+        let block = Block::default()
+            .title(" Safety ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Gray));
+        let safety = self.collector.safety();
+        let clock = |t: DateTime<Utc>| t.with_timezone(&Local).format("%H:%M:%S").to_string();
+        let mut rows: Vec<(DateTime<Utc>, Line)> = Vec::new();
+        for r in safety.arm_requests() {
+            let color = match r.outcome {
+                ArmOutcome::Pending => Color::Yellow,
+                ArmOutcome::Granted { .. } => Color::Green,
+                _ => Color::LightRed,
+            };
+            rows.push((
+                r.requested,
+                Line::from(vec![
+                    Span::raw(format!(
+                        "  {} arm auth  {} -> {}:{}  ",
+                        clock(r.requested),
+                        r.vehicle,
+                        r.authorizer.0,
+                        r.authorizer.1
+                    )),
+                    Span::styled(
+                        safety::describe_outcome(r.outcome).to_uppercase(),
+                        Style::default().fg(color).bold(),
+                    ),
+                ]),
+            ));
+        }
+        for r in safety.area_requests() {
+            let (label, color) = match r.status {
+                AreaStatus::Pending => ("PENDING", Color::Yellow),
+                AreaStatus::Confirmed => ("CONFIRMED", Color::Green),
+                AreaStatus::Differs => ("DIFFERS", Color::LightRed),
+            };
+            rows.push((
+                r.sent,
+                Line::from(vec![
+                    Span::raw(format!(
+                        "  {} area  {} -> {}  {}  ",
+                        clock(r.sent),
+                        r.sender,
+                        r.target,
+                        r.area.describe()
+                    )),
+                    Span::styled(label, Style::default().fg(color).bold()),
+                ]),
+            ));
+        }
+        for (sys_id, area, at) in safety.reported_areas() {
+            rows.push((
+                *at,
+                Line::from(format!(
+                    "  {} area  {sys_id} reports {}",
+                    clock(*at),
+                    area.describe()
+                )),
+            ));
+        }
+        rows.sort_by_key(|(at, _)| std::cmp::Reverse(*at));
+        let mut lines: Vec<Line> = rows
+            .into_iter()
+            .take(height.saturating_sub(2) as usize)
+            .map(|(_, line)| line)
+            .collect();
+        if lines.is_empty() {
+            lines.push(Line::styled(
+                "  no arm authorization or allowed-area traffic",
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+        Paragraph::new(lines).block(block)
+    }
+
+    /// What is held in memory against the `--max-*` limits.
+    fn build_storage(&self) -> Paragraph<'_> {
+        let block = Block::default()
+            .title(" Storage ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Gray));
+        let gray = Style::default().fg(Color::DarkGray);
+        let limits = self.collector.limits();
+        let usage = self.collector.usage();
+        let of = |limit: Option<String>| limit.map_or(String::new(), |l| format!(" / {l}"));
+        let dropped = |n: usize| match n {
+            0 => Span::raw(""),
+            n => Span::styled(format!("  {n} dropped"), gray),
+        };
+        let log = self.session_log.byte_len() as u64;
+        let lines = vec![
+            Line::from(vec![
+                Span::raw(format!(
+                    "  {:<13}{}{}",
+                    "events",
+                    usage.events,
+                    of(limits.max_events.map(|n| n.to_string()))
+                )),
+                dropped(usage.evicted),
+            ]),
+            Line::from(format!(
+                "  {:<13}{} values, {} per row",
+                "history", usage.history, limits.max_history
+            )),
+            Line::from(vec![
+                Span::raw(format!(
+                    "  {:<13}{}{}",
+                    "session log",
+                    format_bytes(log),
+                    of(Some(format_bytes(self.session_log.max_bytes())))
+                )),
+                dropped(self.session_log.dropped),
+            ]),
+            Line::from(format!(
+                "  {:<13}{}{}",
+                "memory",
+                format_bytes(usage.bytes + log),
+                of(limits.max_memory.map(format_bytes))
+            )),
+        ];
+        Paragraph::new(lines).block(block)
+    }
+
+    /// Bytes each link delivered that made no message.
+    fn build_link_errors(&self) -> Paragraph<'_> {
+        let errors = self.collector.link_errors();
+        let color = if errors.is_empty() {
+            Color::Gray
+        } else {
+            Color::Yellow
+        };
+        let block = Block::default()
+            .title(" Link Errors ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(color));
+        let gray = Style::default().fg(Color::DarkGray);
+        let links = self.links.len().max(1);
+        let lines: Vec<Line> = (0..links)
+            .map(|link| {
+                let e = errors.get(&link).copied().unwrap_or_default();
+                let count = |n: usize, what: &str| {
+                    let style = if n == 0 { gray } else { Style::default() };
+                    Span::styled(format!("  {n} {what}"), style)
+                };
+                Line::from(vec![
+                    Span::raw(format!("  link {link:<7}")),
+                    count(e.crc, "crc"),
+                    count(e.length, "length"),
+                    count(e.framing, "framing"),
+                    count(e.decode, "decode"),
+                ])
+            })
+            .collect();
+        Paragraph::new(lines).block(block)
+    }
+
+    fn build_perf(&self) -> Paragraph<'_> {
+        let block = Block::default()
+            .title(" Performance ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Yellow));
+        let gray = Style::default().fg(Color::DarkGray);
+        let ms = |d: Option<Duration>| {
+            d.map_or("-".to_string(), |d| {
+                format!("{:.1} ms", d.as_secs_f64() * 1000.0)
+            })
+        };
+        let row = |label: &str, value: String| Line::from(format!("  {label:<13}{value}"));
+        let allocations = match (perf::allocations(), self.perf.allocations_per_sec) {
+            (Some((_, live)), Some(rate)) => row(
+                "allocations",
+                format!("{rate:.0}/s, {} live", format_bytes(live)),
+            ),
+            (Some((_, live)), None) => row("allocations", format!("{} live", format_bytes(live))),
+            (None, _) => Line::from(vec![
+                Span::raw(format!("  {:<13}", "allocations")),
+                Span::styled("not counted", gray),
+            ]),
+        };
+        let usage = self.collector.usage();
+        let lines = vec![
+            row(
+                "queue",
+                format!("{} / {CHANNEL_CAPACITY}", self.perf.backlog),
+            ),
+            row(
+                "processed",
+                format!(
+                    "{:.0} msg/s, {} total",
+                    self.perf.received_per_sec,
+                    self.perf.total_received()
+                ),
+            ),
+            row(
+                "render",
+                format!(
+                    "{}, slowest {}",
+                    ms(self.perf.last_frame()),
+                    ms(self.perf.slowest_frame())
+                ),
+            ),
+            allocations,
+            row(
+                "collector",
+                format!(
+                    "{}, {} rows, {} events",
+                    format_bytes(usage.bytes),
+                    self.collector.stream().len(),
+                    usage.events
+                ),
+            ),
+            row(
+                "session log",
+                format_bytes(self.session_log.byte_len() as u64),
+            ),
+        ];
+        Paragraph::new(lines).block(block)
+    }
+
+    fn build_talkers(&self) -> Paragraph<'_> {
+        let block = Block::default()
+            .title(format!(
+                " Top talkers (last {}s) ",
+                talkers::WINDOW.num_seconds()
+            ))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Gray));
+        let now = self.now();
+        let heading = Style::default().fg(Color::Cyan).bold();
+        let row = |label: Span<'static>, t_hz: f64, bps: f64| {
+            Line::from(vec![
+                label,
+                Span::raw(format!("{t_hz:>8.1} Hz {:>10}", format_rate(bps))),
+            ])
+        };
+        let talkers = self.collector.talkers();
+        let mut lines = vec![Line::from(Span::styled("Sources", heading))];
+        lines.extend(talkers.by_source(now).into_iter().take(10).map(|t| {
+            let (sys, comp) = t.key;
+            row(
+                Span::styled(
+                    format!("  {sys:>3}:{comp:<3}{:<18}", ""),
+                    Style::default().fg(id_color(sys)),
+                ),
+                t.hz,
+                t.bytes_per_sec,
+            )
+        }));
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled("Message types", heading)));
+        lines.extend(
+            talkers
+                .by_type(now)
+                .into_iter()
+                .take(10)
+                .map(|t| row(Span::raw(format!("  {:<25}", t.key)), t.hz, t.bytes_per_sec)),
+        );
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled("Wire versions", heading)));
+        for (sys, wire) in self.collector.capabilities().wire_by_system() {
+            let style = if wire.0 > 0 {
+                Style::default().fg(Color::Yellow)
+            } else {
+                Style::default()
+            };
+            lines.push(Line::from(vec![
+                Span::styled(
+                    format!("  {sys:>3}    "),
+                    Style::default().fg(id_color(sys)),
+                ),
+                Span::styled(format!("{:<14}", capabilities::wire_label(wire)), style),
+                Span::raw(format!("{:>8} v1 {:>8} v2", wire.0, wire.1)),
+            ]));
+        }
+        Paragraph::new(lines).block(block)
+    }
+
+    /// The highlighted row while the Stream tab is showing.
+    fn selected_stream(&self) -> Option<&StreamEntry> {
+        if self.tab() != Tab::Stream {
+            return None;
+        }
+        let s = self.stream();
+        let selected = self.view(Tab::Stream).scroll.selected;
+        s.get(selected.min(s.len().saturating_sub(1))).copied()
+    }
+
+    /// RSSI history for the selected stream row, if it is a RADIO_STATUS.
+    fn selected_radio(&self) -> Option<&VecDeque<RadioSample>> {
+        let entry = self.selected_stream()?;
+        if entry.name != "RADIO_STATUS" {
+            return None;
+        }
+        self.collector.radio().history(entry.sys_id, entry.comp_id)
+    }
+
+    /// Arrival timing of the selected stream row while `i` is toggled on.
+    fn selected_arrivals(&self) -> Option<&ArrivalStats> {
+        if !self.show_intervals {
+            return None;
+        }
+        self.selected_stream().map(|entry| &entry.arrivals)
+    }
+
+    /// Whether the selected stream row is one of the UAVCAN node messages.
+    fn uavcan_selected(&self) -> bool {
+        self.selected_stream()
+            .is_some_and(|e| e.name.starts_with("UAVCAN_NODE_"))
+    }
+
+    /// The modem behind the selected CELLULAR_* row: that component if it
+    /// reports CELLULAR_STATUS (a CELLULAR_CONFIG row may be the ground
+    /// station's request), otherwise the first modem seen.
+    fn selected_modem(&self) -> Option<((u8, u8), &Modem)> {
+        let entry = self.selected_stream()?;
+        if !entry.name.starts_with("CELLULAR_") {
+            return None;
+        }
+        let modems: Vec<_> = self
+            .collector
+            .cellular()
+            .modems()
+            .filter(|(_, m)| m.registration.is_some())
+            .collect();
+        let (key, modem) = modems
+            .iter()
+            .find(|(key, _)| **key == (entry.sys_id, entry.comp_id))
+            .or(modems.first())?;
+        Some((**key, *modem))
+    }
+
+    /// Registration and the latest configuration exchange of one modem.
+    fn build_cellular(&self, key: (u8, u8), modem: &Modem) -> Paragraph<'_> {
+        let block = Block::default()
+            .title(format!(" Cellular {}:{} ", key.0, key.1))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Gray));
+        let gray = Style::default().fg(Color::DarkGray);
+        let row = |label: &str, value: Span<'static>| {
+            Line::from(vec![Span::styled(format!("  {label:<10}"), gray), value])
+        };
+        let mut lines = Vec::new();
+        if let Some(r) = modem.registration {
+            let color = match r.status {
+                CellularStatusFlag::CELLULAR_STATUS_FLAG_CONNECTED => Color::Green,
+                CellularStatusFlag::CELLULAR_STATUS_FLAG_FAILED => Color::LightRed,
+                _ => Color::Yellow,
+            };
+            lines.push(row(
+                "status",
+                Span::styled(
+                    cellular::status_name(r.status),
+                    Style::default().fg(color).bold(),
+                ),
+            ));
+            if r.failure != CellularNetworkFailedReason::CELLULAR_NETWORK_FAILED_REASON_NONE {
+                lines.push(row(
+                    "failure",
+                    Span::styled(
+                        cellular::failure_name(r.failure),
+                        Style::default().fg(Color::LightRed),
+                    ),
+                ));
+            }
+            lines.push(row("radio", Span::raw(cellular::radio_name(r.radio))));
+            lines.push(row(
+                "quality",
+                Span::raw(r.quality.map_or("unknown".to_string(), |q| format!("{q}%"))),
+            ));
+            lines.push(row(
+                "network",
+                Span::raw(format!("MCC {} MNC {} LAC {}", r.mcc, r.mnc, r.lac)),
+            ));
+        }
+        if let Some(config) = self
+            .collector
+            .cellular()
+            .modems()
+            .filter_map(|(_, m)| m.config.as_ref())
+            .max_by_key(|c| c.at)
+        {
+            let on = |b: bool| if b { "on" } else { "off" };
+            lines.push(row("apn", Span::raw(config.apn.clone())));
+            lines.push(row(
+                "config",
+                Span::raw(format!(
+                    "LTE {}, roaming {}, {}",
+                    on(config.lte),
+                    on(config.roaming),
+                    cellular::response_name(config.response)
+                )),
+            ));
+        }
+        Paragraph::new(lines).block(block)
+    }
+
+    /// Latest separation of each vehicle from the follow target.
+    fn build_follow(&self) -> Paragraph<'_> {
+        let block = Block::default()
+            .title(" Follow target ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Gray));
+        let gray = Style::default().fg(Color::DarkGray);
+        let follow = self.collector.follow();
+        let mut lines = Vec::new();
+        if let Some(target) = follow.target() {
+            let age = self.now().signed_duration_since(target.at).num_seconds();
+            lines.push(Line::styled(
+                format!("  from {}, {age}s ago", target.sender),
+                gray,
+            ));
+        }
+        for (sys, samples) in follow.vehicles() {
+            let Some(s) = samples.back() else {
+                continue;
+            };
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {sys:>3}  "), Style::default().fg(id_color(*sys))),
+                Span::raw(format!(
+                    "{:.1} m  {:.0}\u{00b0} {:<2}  {:+.1} m",
+                    s.distance,
+                    s.bearing,
+                    follow::compass(s.bearing),
+                    s.alt_diff
+                )),
+            ]));
+        }
+        if lines.is_empty() {
+            lines.push(Line::styled("  no FOLLOW_TARGET position", gray));
+        }
+        Paragraph::new(lines).block(block)
+    }
+
+    /// The winch behind the selected WINCH_STATUS row.
+    fn selected_winch(&self) -> Option<&Winch> {
+        let entry = self.selected_stream()?;
+        if entry.name != "WINCH_STATUS" {
+            return None;
+        }
+        self.collector
+            .winch()
+            .winches()
+            .find(|(key, _)| **key == (entry.sys_id, entry.comp_id))
+            .map(|(_, winch)| winch)
+    }
+
+    /// Latest WINCH_STATUS readings with units and its set flags.
+    fn build_winch(&self, winch: &Winch) -> Paragraph<'_> {
+        let block = Block::default()
+            .title(" Winch ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Gray));
+        let gray = Style::default().fg(Color::DarkGray);
+        let Some(s) = winch.latest() else {
+            return Paragraph::new("").block(block);
+        };
+        let reading = |value: f32, unit: &str| {
+            if value.is_nan() {
+                "-".to_string()
+            } else {
+                format!("{value:.2} {unit}")
+            }
+        };
+        let row = |label: &str, value: String| {
+            Line::from(vec![
+                Span::styled(format!("  {label:<9}"), gray),
+                Span::raw(value),
+            ])
+        };
+        let mut lines = vec![
+            row("line", reading(s.line_length, "m")),
+            row("speed", reading(s.speed, "m/s")),
+            row("tension", reading(s.tension, "kg")),
+            row(
+                "power",
+                format!("{}, {}", reading(s.voltage, "V"), reading(s.current, "A")),
+            ),
+            row(
+                "temp",
+                s.temperature
+                    .map_or("-".to_string(), |t| format!("{t} \u{00b0}C")),
+            ),
+        ];
+        let color = if winch::in_trouble(s.status) {
+            Color::LightRed
+        } else {
+            Color::Green
+        };
+        lines.push(Line::from(vec![
+            Span::styled(format!("  {:<9}", "status"), gray),
+            Span::styled(
+                winch::flag_names(s.status).join(", "),
+                Style::default().fg(color),
+            ),
+        ]));
+        Paragraph::new(lines)
+            .block(block)
+            .wrap(Wrap { trim: false })
+    }
+
+    /// Current VTOL configuration per vehicle and its recent transitions,
+    /// newest first.
+    fn build_vtol(&self) -> Paragraph<'_> {
+        let block = Block::default()
+            .title(" VTOL ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Gray));
+        let gray = Style::default().fg(Color::DarkGray);
+        let now = self.now();
+        let mut lines = Vec::new();
+        for (sys, vehicle) in self.collector.vtol().vehicles() {
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {sys:>3}  "), Style::default().fg(id_color(*sys))),
+                Span::styled(
+                    vtol::state_name(vehicle.state),
+                    Style::default().fg(Color::Cyan).bold(),
+                ),
+                Span::styled(
+                    format!(
+                        " for {}s",
+                        now.signed_duration_since(vehicle.since)
+                            .num_seconds()
+                            .max(0)
+                    ),
+                    gray,
+                ),
+            ]));
+            for t in vehicle.transitions.iter().rev() {
+                let (label, color) = match t.outcome {
+                    TransitionOutcome::InProgress => ("IN PROGRESS", Color::Yellow),
+                    TransitionOutcome::Completed => ("DONE", Color::Green),
+                    TransitionOutcome::Aborted { .. } => ("ABORTED", Color::LightRed),
+                    TransitionOutcome::Rejected(_) => ("REJECTED", Color::LightRed),
+                };
+                let took = t.duration().map_or(String::new(), |d| {
+                    format!("{:.1}s", d.num_milliseconds() as f64 / 1000.0)
+                });
+                lines.push(Line::from(vec![
+                    Span::raw(format!(
+                        "       {} to {:<3}{:<11}{took:>7}  ",
+                        t.started.with_timezone(&Local).format("%H:%M:%S"),
+                        vtol::state_name(t.to),
+                        if t.commanded { " commanded" } else { "" },
+                    )),
+                    Span::styled(label, Style::default().fg(color).bold()),
+                ]));
+            }
+        }
+        if lines.is_empty() {
+            lines.push(Line::styled("  no vtol_state reported", gray));
+        }
+        Paragraph::new(lines).block(block)
+    }
+
+    /// The IMUs of the vehicle whose RAW_IMU or SCALED_IMU* row is selected.
+    fn selected_imu(&self) -> Option<&[Option<ImuReading>; imu::IMUS]> {
+        let entry = self.selected_stream()?;
+        if !matches!(
+            entry.name,
+            "RAW_IMU" | "SCALED_IMU" | "SCALED_IMU2" | "SCALED_IMU3"
+        ) {
+            return None;
+        }
+        self.collector.imu().readings(entry.sys_id)
+    }
+
+    /// Each axis of every IMU side by side, values off from the others in
+    /// red.
+    fn build_imu(&self, readings: &[Option<ImuReading>; imu::IMUS]) -> Paragraph<'_> {
+        let block = Block::default()
+            .title(" IMU comparison (mG, mrad/s, mgauss) ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Gray));
+        let gray = Style::default().fg(Color::DarkGray);
+        let now = self.now();
+        let compared = imu::compare(readings, now);
+        let mut header = vec![Span::styled(format!("  {:<8}", ""), gray)];
+        for (i, reading) in readings.iter().enumerate() {
+            let (label, style) = match reading {
+                Some(r) if imu::is_stale(r, now) => (
+                    "stale".to_string(),
+                    Style::default().fg(Color::LightRed).bold(),
+                ),
+                Some(_) => (format!("IMU{}", i + 1), Style::default().bold()),
+                None => (format!("IMU{}", i + 1), gray),
+            };
+            header.push(Span::styled(format!("{label:>9}"), style));
+        }
+        let mut lines = vec![Line::from(header)];
+        for (axis, name) in imu::AXES.iter().enumerate() {
+            let mut spans = vec![Span::raw(format!("  {name:<8}"))];
+            for (reading, agreement) in readings.iter().zip(compared[axis]) {
+                let text = reading.map_or("-".to_string(), |r| format!("{:.0}", r.values[axis]));
+                let style = match agreement {
+                    Some(Agreement::Differs) => Style::default().fg(Color::LightRed).bold(),
+                    Some(Agreement::Agrees) => Style::default(),
+                    None => gray,
+                };
+                spans.push(Span::styled(format!("{text:>9}"), style));
+            }
+            lines.push(Line::from(spans));
+        }
+        Paragraph::new(lines).block(block)
+    }
+
+    /// The compass/motor interference estimate next to the IMU comparison.
+    fn build_interference(&self, mag: Option<&interference::Vehicle>) -> Paragraph<'_> {
+        let block = Block::default()
+            .title(" Mag interference ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Gray));
+        let gray = Style::default().fg(Color::DarkGray);
+        let Some(mag) = mag.filter(|m| !m.samples.is_empty()) else {
+            return Paragraph::new(Line::styled("  no SCALED_IMU compass yet", gray)).block(block);
+        };
+        let field = mag.samples.back().map_or(0.0, |s| s.field);
+        let mut lines = vec![Line::from(vec![
+            Span::raw("  field     "),
+            Span::raw(format!("{field:>7.0}")),
+            Span::styled(" mgauss", gray),
+        ])];
+        let Some(estimate) = mag.estimate() else {
+            lines.push(Line::raw(""));
+            lines.push(Line::styled("  vary throttle to estimate", gray));
+            lines.push(Line::styled(
+                format!("  {} samples", mag.samples.len()),
+                gray,
+            ));
+            return Paragraph::new(lines).block(block);
+        };
+        let unit = estimate.basis.unit();
+        let (fine, bad) = interference::RATING;
+        let (rating, style) = if estimate.interference < fine {
+            ("ok", Style::default().fg(Color::Green))
+        } else if estimate.interference < bad {
+            ("marginal", Style::default().fg(Color::Yellow).bold())
+        } else {
+            ("bad", Style::default().fg(Color::LightRed).bold())
+        };
+        lines.extend([
+            Line::from(vec![
+                Span::raw("  at rest   "),
+                Span::raw(format!("{:>7.0}", estimate.baseline)),
+                Span::styled(" mgauss", gray),
+            ]),
+            Line::from(vec![
+                Span::raw("  slope     "),
+                Span::raw(format!("{:>7.1}", estimate.slope)),
+                Span::styled(format!(" mgauss/{unit}"), gray),
+            ]),
+            Line::from(vec![
+                Span::raw("  max load  "),
+                Span::raw(format!("{:>7.1}", estimate.max_load)),
+                Span::styled(format!(" {unit}"), gray),
+            ]),
+            Line::from(vec![
+                Span::raw("  corr      "),
+                Span::raw(format!("{:>7.2}", estimate.correlation)),
+            ]),
+            Line::raw(""),
+            Line::from(vec![
+                Span::raw("  interf    "),
+                Span::styled(format!("{:>6.0}% {rating}", estimate.interference), style),
+            ]),
+            Line::styled(
+                format!(
+                    "  {} samples vs {}",
+                    estimate.samples,
+                    match estimate.basis {
+                        Basis::Current => "current",
+                        Basis::Throttle => "throttle",
+                    }
+                ),
+                gray,
+            ),
+        ]);
+        Paragraph::new(lines).block(block)
+    }
+
+    /// The lowest-numbered battery of the vehicle whose BATTERY_STATUS row
+    /// is selected.
+    fn selected_battery(&self) -> Option<(u8, &Battery)> {
+        let entry = self.selected_stream()?;
+        if entry.name != "BATTERY_STATUS" {
+            return None;
+        }
+        self.collector.battery().vehicle(entry.sys_id).next()
+    }
+
+    /// Present readings, average and recent draw and the time left.
+    fn build_battery(&self, id: u8, battery: &Battery) -> Paragraph<'_> {
+        let block = Block::default()
+            .title(format!(" Battery {id} "))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Gray));
+        let gray = Style::default().fg(Color::DarkGray);
+        let Some(latest) = battery.latest() else {
+            return Paragraph::new(Line::styled("  no BATTERY_STATUS yet", gray)).block(block);
+        };
+        let row = |label: &'static str, value: Option<String>| {
+            Line::from(vec![
+                Span::raw(format!("  {label:<11}")),
+                match value {
+                    Some(v) => Span::raw(v),
+                    None => Span::styled("-", gray),
+                },
+            ])
+        };
+        let endurance = battery.endurance();
+        let low = endurance.is_some_and(|t| t < TimeDelta::minutes(3));
+        Paragraph::new(vec![
+            row("voltage", latest.voltage.map(|v| format!("{v:.2} V"))),
+            row("current", latest.current.map(|a| format!("{a:.1} A"))),
+            row("consumed", latest.consumed.map(|c| format!("{c:.0} mAh"))),
+            row("remaining", latest.remaining.map(|p| format!("{p}%"))),
+            Line::raw(""),
+            row(
+                "avg draw",
+                battery.average_draw().map(|a| format!("{a:.1} A")),
+            ),
+            row(
+                "last 60s",
+                battery.recent_draw().map(|a| format!("{a:.1} A")),
+            ),
+            Line::from(vec![
+                Span::raw(format!("  {:<11}", "time left")),
+                match endurance {
+                    Some(t) if low => Span::styled(
+                        battery::format_duration(t),
+                        Style::default().fg(Color::LightRed).bold(),
+                    ),
+                    Some(t) => Span::styled(
+                        battery::format_duration(t),
+                        Style::default().fg(Color::Green),
+                    ),
+                    None => Span::styled("-", gray),
+                },
+            ]),
+            row(
+                "autopilot",
+                latest.time_remaining.map(battery::format_duration),
+            ),
+        ])
+        .block(block)
+    }
+
+    /// Consumption per flight mode phase, newest last.
+    fn build_phases(&self, battery: &Battery) -> Paragraph<'_> {
+        let block = Block::default()
+            .title(" Consumption by phase ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Gray));
+        let gray = Style::default().fg(Color::DarkGray);
+        if battery.phases.is_empty() {
+            return Paragraph::new(Line::styled(
+                "  needs HEARTBEAT and a consumed counter",
+                gray,
+            ))
+            .block(block);
+        }
+        let mut lines = vec![Line::styled(
+            format!("  {:<24}{:>8}{:>9}{:>8}", "mode", "time", "mAh", "avg A"),
+            gray,
+        )];
+        // Header and borders take three of the twelve rows.
+        let shown = battery.phases.len().saturating_sub(9);
+        for phase in battery.phases.iter().skip(shown) {
+            let hours = phase.duration().num_milliseconds() as f64 / 3_600_000.0;
+            let average = if hours > 0.0 {
+                format!("{:.1}", phase.consumed / 1000.0 / hours)
+            } else {
+                "-".to_string()
+            };
+            lines.push(Line::raw(format!(
+                "  {:<24}{:>8}{:>9.0}{average:>8}",
+                phase.label(self.modes),
+                battery::format_duration(phase.duration()),
+                phase.consumed
+            )));
+        }
+        Paragraph::new(lines).block(block)
+    }
+
+    fn selected_terrain(&self) -> Option<&Terrain> {
+        let entry = self.selected_stream()?;
+        if !matches!(entry.name, "ALTITUDE" | "TERRAIN_REPORT") {
+            return None;
+        }
+        self.collector.terrain().vehicle(entry.sys_id)
+    }
+
+    /// Clearance now and at its lowest against the margin, with the terrain
+    /// and vehicle altitudes it comes from.
+    fn build_terrain(&self, terrain: &Terrain) -> Paragraph<'_> {
+        let block = Block::default()
+            .title(" Terrain clearance ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Gray));
+        let gray = Style::default().fg(Color::DarkGray);
+        let margin = self.collector.terrain().margin();
+        let value = |v: Option<f32>| v.map_or("-".to_string(), |v| format!("{v:.1}"));
+        let row = |label: &str, value: String, style: Style| {
+            Line::from(vec![
+                Span::raw(format!("  {label:<11}")),
+                Span::styled(format!("{value:>8}"), style),
+                Span::styled(" m", gray),
+            ])
+        };
+        let style = |height: Option<f32>| match (height, margin) {
+            (Some(h), Some(m)) if h < m => Style::default().fg(Color::LightRed).bold(),
+            (Some(_), _) => Style::default().fg(Color::Green),
+            (None, _) => gray,
+        };
+        let latest = terrain.latest();
+        let lowest = terrain.lowest();
+        let mut lines = vec![
+            row(
+                "clearance",
+                value(latest.map(|c| c.height)),
+                style(latest.map(|c| c.height)),
+            ),
+            row("lowest", value(lowest), style(lowest)),
+            row("margin", value(margin), gray),
+            Line::raw(""),
+            row("terrain", value(terrain.terrain_height), Style::default()),
+            row("vehicle", value(terrain.amsl), Style::default()),
+        ];
+        if let Some((pending, loaded)) = terrain.tiles {
+            lines.push(Line::styled(
+                format!("  tiles {loaded} loaded, {pending} pending"),
+                if pending > 0 {
+                    Style::default().fg(Color::Yellow)
+                } else {
+                    gray
+                },
+            ));
+        }
+        if let Some(latest) = latest {
+            lines.push(Line::styled(
+                format!("  from {}", latest.source.name()),
+                gray,
+            ));
+        }
+        Paragraph::new(lines).block(block)
+    }
+
+    fn selected_navigation(&self) -> Option<&Navigation> {
+        let entry = self.selected_stream()?;
+        if entry.name != "NAV_CONTROLLER_OUTPUT" {
+            return None;
+        }
+        self.collector.navigation().vehicle(entry.sys_id)
+    }
+
+    /// Desired against actual bearing, crosstrack error and waypoint
+    /// distance.
+    fn build_navigation(&self, nav: &Navigation) -> Paragraph<'_> {
+        let block = Block::default()
+            .title(" Navigation ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Gray));
+        let gray = Style::default().fg(Color::DarkGray);
+        let Some(latest) = nav.latest() else {
+            return Paragraph::new(Line::styled("  no NAV_CONTROLLER_OUTPUT yet", gray))
+                .block(block);
+        };
+        let row = |label: &str, value: String, unit: &'static str| {
+            Line::from(vec![
+                Span::raw(format!("  {label:<11}")),
+                Span::raw(format!("{value:>8}")),
+                Span::styled(unit, gray),
+            ])
+        };
+        let error = latest.bearing_error();
+        let error_style = if error.is_some_and(|e| e.abs() > 30.0) {
+            Style::default().fg(Color::LightRed).bold()
+        } else {
+            Style::default()
+        };
+        Paragraph::new(vec![
+            row("desired", format!("{:.0}", latest.desired), "°"),
+            row(
+                "actual",
+                latest.actual.map_or("-".to_string(), |h| format!("{h:.0}")),
+                "°",
+            ),
+            Line::from(vec![
+                Span::raw(format!("  {:<11}", "error")),
+                Span::styled(
+                    format!(
+                        "{:>8}",
+                        error.map_or("-".to_string(), |e| format!("{e:+.0}"))
+                    ),
+                    error_style,
+                ),
+                Span::styled("°", gray),
+            ]),
+            row("to wp", format!("{:.0}", latest.target), "°"),
+            Line::raw(""),
+            row("crosstrack", format!("{:+.1}", latest.crosstrack), " m"),
+            row(
+                "worst",
+                format!("{:.1}", nav.worst_crosstrack().unwrap_or(0.0)),
+                " m",
+            ),
+            row("wp dist", format!("{:.0}", latest.wp_dist), " m"),
+            row("alt error", format!("{:+.1}", latest.alt_error), " m"),
+        ])
+        .block(block)
+    }
+
+    /// The component behind a selected HEARTBEAT, AUTOPILOT_VERSION or
+    /// PROTOCOL_VERSION row.
+    fn selected_component(&self) -> Option<&Component> {
+        let entry = self.selected_stream()?;
+        if !matches!(
+            entry.name,
+            "HEARTBEAT" | "AUTOPILOT_VERSION" | "PROTOCOL_VERSION"
+        ) {
+            return None;
+        }
+        self.collector
+            .capabilities()
+            .component(entry.sys_id, entry.comp_id)
+    }
+
+    /// Protocol version, firmware and board, declared capabilities and the
+    /// microservices seen in use.
+    fn build_component(&self, component: &Component) -> Paragraph<'_> {
+        let block = Block::default()
+            .title(" Component ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Gray));
+        let gray = Style::default().fg(Color::DarkGray);
+        let row = |label: &str, value: String| {
+            Line::from(vec![
+                Span::styled(format!("  {label:<12}"), gray),
+                Span::raw(value),
+            ])
+        };
+        let dash = || "-".to_string();
+        let mut lines = vec![
+            row(
+                "type",
+                format!(
+                    "{} / {}",
+                    component.mavtype.as_deref().unwrap_or("-"),
+                    component.autopilot.as_deref().unwrap_or("-")
+                ),
+            ),
+            row(
+                "wire",
+                match component.protocol {
+                    Some((version, min, max)) => {
+                        format!("{} (v{version}, {min}-{max})", component.wire_label())
+                    }
+                    None => component.wire_label(),
+                },
+            ),
+        ];
+        match &component.version {
+            Some(version) => {
+                lines.push(row(
+                    "firmware",
+                    capabilities::format_version(version.flight_sw),
+                ));
+                lines.push(row(
+                    "middleware",
+                    capabilities::format_version(version.middleware_sw),
+                ));
+                lines.push(row("os", capabilities::format_version(version.os_sw)));
+                lines.push(row(
+                    "board",
+                    format!(
+                        "{:#x}, vendor {:#06x} product {:#06x}",
+                        version.board, version.vendor_id, version.product_id
+                    ),
+                ));
+            }
+            None => lines.push(Line::styled("  no AUTOPILOT_VERSION yet", gray)),
+        }
+        let names = component.capability_names();
+        lines.push(row(
+            "capabilities",
+            if names.is_empty() {
+                dash()
+            } else {
+                names.join(", ")
+            },
+        ));
+        lines.push(row(
+            "services",
+            if component.services.is_empty() {
+                dash()
+            } else {
+                component
+                    .services
+                    .iter()
+                    .copied()
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            },
+        ));
+        Paragraph::new(lines)
+            .block(block)
+            .wrap(Wrap { trim: false })
+    }
+
+    /// The vehicle whose DISTANCE_SENSOR row is selected.
+    fn selected_distance(&self) -> Option<u8> {
+        let entry = self.selected_stream()?;
+        (entry.name == "DISTANCE_SENSOR").then_some(entry.sys_id)
+    }
+
+    /// Each rangefinder by where it points, with its reading, the extremes
+    /// seen and whether it looks healthy.
+    fn build_distance(&self, sys_id: u8) -> Paragraph<'_> {
+        let block = Block::default()
+            .title(" Rangefinders (m) ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Gray));
+        let gray = Style::default().fg(Color::DarkGray);
+        let mut lines = vec![Line::styled(
+            format!(
+                "  {:<3}{:<15}{:<14}{:>8}{:>8}{:>8}{:>13}  {}",
+                "id", "facing", "type", "now", "min", "max", "range", "health"
+            ),
+            gray,
+        )];
+        for (id, sensor, health) in self.collector.distance().vehicle(sys_id, self.now()) {
+            let (min, max) = sensor
+                .seen
+                .map_or(("-".to_string(), "-".to_string()), |(lo, hi)| {
+                    (format!("{lo:.2}"), format!("{hi:.2}"))
+                });
+            let style = match health {
+                Health::Ok => Style::default().fg(Color::Green),
+                Health::Stale => gray,
+                _ => Style::default().fg(Color::LightRed).bold(),
+            };
+            lines.push(Line::from(vec![
+                Span::raw(format!(
+                    "  {id:<3}{:<15}{:<14}{:>8.2}{min:>8}{max:>8}{:>13}  ",
+                    distance::direction(sensor.orientation),
+                    distance::kind_name(sensor.kind),
+                    sensor.current,
+                    format!("{:.2}-{:.2}", sensor.range.0, sensor.range.1),
+                )),
+                Span::styled(health.label(), style),
+            ]));
+        }
+        Paragraph::new(lines).block(block)
+    }
+
+    fn selected_power(&self) -> Option<&power::Power> {
+        let entry = self.selected_stream()?;
+        if entry.name != "POWER_STATUS" {
+            return None;
+        }
+        self.collector.power().vehicle(entry.sys_id)
+    }
+
+    /// Rail voltages and one indicator per POWER_STATUS flag.
+    fn build_power(&self, power: &power::Power) -> Paragraph<'_> {
+        let block = Block::default()
+            .title(" Power ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Gray));
+        let gray = Style::default().fg(Color::DarkGray);
+        let Some(latest) = power.latest() else {
+            return Paragraph::new(Line::styled("  no POWER_STATUS yet", gray)).block(block);
+        };
+        let red = Style::default().fg(Color::LightRed).bold();
+        let green = Style::default().fg(Color::Green);
+        let (low, high) = power::VCC_RANGE;
+        let (min, max) = power
+            .samples
+            .iter()
+            .fold((f32::MAX, f32::MIN), |(lo, hi), s| {
+                (lo.min(s.vcc), hi.max(s.vcc))
+            });
+        let mut lines = vec![
+            Line::from(vec![
+                Span::raw("  5V    "),
+                Span::styled(
+                    format!("{:>5.2} V", latest.vcc),
+                    if power::vcc_ok(latest.vcc) {
+                        green
+                    } else {
+                        red
+                    },
+                ),
+                Span::styled(format!("  {min:.2}..{max:.2}"), gray),
+            ]),
+            Line::from(vec![
+                Span::raw("  servo "),
+                if latest.vservo > 0.0 {
+                    Span::raw(format!("{:>5.2} V", latest.vservo))
+                } else {
+                    Span::styled("    - V", gray)
+                },
+            ]),
+            Line::styled(format!("  5V ok {low:.1}..{high:.1} V"), gray),
+            Line::raw(""),
+        ];
+        for (flag, label) in power::INDICATORS {
+            let on = latest.flags.contains(flag);
+            // Valid rails should be on, overcurrent should be off.
+            let good = if power::OVERCURRENT.contains(flag) {
+                !on
+            } else {
+                on || flag != MavPowerStatus::MAV_POWER_STATUS_BRICK_VALID
+            };
+            let style = match (on, good) {
+                (_, false) => red,
+                (true, true) => green,
+                (false, true) => gray,
+            };
+            lines.push(Line::from(vec![
+                Span::styled(if on { "  ● " } else { "  ○ " }, style),
+                Span::styled(label, style),
+            ]));
+        }
+        Paragraph::new(lines).block(block)
+    }
+
+    fn selected_vibration(&self) -> Option<&vibration::Vehicle> {
+        let entry = self.selected_stream()?;
+        if entry.name != "VIBRATION" {
+            return None;
+        }
+        self.collector.vibration().vehicle(entry.sys_id)
+    }
+
+    /// Latest levels against the warning level, and clipping counts.
+    fn build_vibration(&self, vehicle: &vibration::Vehicle) -> Paragraph<'_> {
+        let block = Block::default()
+            .title(" Vibration ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Gray));
+        let gray = Style::default().fg(Color::DarkGray);
+        let warn = self.collector.vibration().warn();
+        let Some(latest) = vehicle.latest() else {
+            return Paragraph::new(Line::styled("  no VIBRATION yet", gray)).block(block);
+        };
+        let mut lines = Vec::new();
+        for (axis, level) in ["x", "y", "z"].iter().zip(latest.levels) {
+            let style = if level > warn {
+                Style::default().fg(Color::LightRed).bold()
+            } else {
+                Style::default().fg(Color::Green)
+            };
+            lines.push(Line::from(vec![
+                Span::raw(format!("  {axis}  ")),
+                Span::styled(format!("{level:>6.1}"), style),
+                Span::styled(" m/s²", gray),
+            ]));
+        }
+        lines.push(Line::styled(format!("  warn at {warn:.1} m/s²"), gray));
+        lines.push(Line::raw(""));
+        let first = vehicle.samples.front().map_or([0; 3], |s| s.clipping);
+        for (imu, total) in latest.clipping.iter().enumerate() {
+            let recent = total.saturating_sub(first[imu]);
+            let style = if recent > 0 {
+                Style::default().fg(Color::LightRed).bold()
+            } else {
+                Style::default()
+            };
+            lines.push(Line::from(vec![
+                Span::raw(format!("  clip{imu} ")),
+                Span::styled(format!("{total:>8}"), style),
+                Span::styled(format!("  +{recent}"), gray),
+            ]));
+        }
+        Paragraph::new(lines).block(block)
+    }
+
+    /// The receivers of the vehicle whose GPS_RAW_INT or GPS2_RAW row is
+    /// selected.
+    fn selected_gps(&self) -> Option<&gps::Vehicle> {
+        let entry = self.selected_stream()?;
+        if !matches!(entry.name, "GPS_RAW_INT" | "GPS2_RAW") {
+            return None;
+        }
+        self.collector.gps().vehicle(entry.sys_id)
+    }
+
+    /// Both receivers side by side and how far apart they are.
+    fn build_gps(&self, vehicle: &gps::Vehicle) -> Paragraph<'_> {
+        let block = Block::default()
+            .title(" GPS comparison ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Gray));
+        let gray = Style::default().fg(Color::DarkGray);
+        type Cell = fn(&GpsFix) -> String;
+        let rows: [(&str, Cell); 6] = [
+            ("fix", |f| gps::fix_name(f.fix_type)),
+            ("sats", |f| {
+                f.satellites.map_or("-".to_string(), |s| s.to_string())
+            }),
+            ("hdop", |f| {
+                f.hdop.map_or("-".to_string(), |h| format!("{h:.2}"))
+            }),
+            ("lat", |f| format!("{:.7}", f64::from(f.lat) / 1e7)),
+            ("lon", |f| format!("{:.7}", f64::from(f.lon) / 1e7)),
+            ("alt", |f| format!("{:.1} m", f.alt)),
+        ];
+        let mut lines = vec![Line::from(vec![
+            Span::raw(format!("  {:<6}", "")),
+            Span::styled(
+                format!("{:>14}{:>14}", "GPS_RAW_INT", "GPS2_RAW"),
+                Style::default().bold(),
+            ),
+        ])];
+        for (label, cell) in rows {
+            let mut text = format!("  {label:<6}");
+            for receiver in &vehicle.receivers {
+                let value = receiver.as_ref().map_or("-".to_string(), cell);
+                text.push_str(&format!("{value:>14}"));
+            }
+            lines.push(Line::raw(text));
+        }
+        let threshold = self.collector.gps().threshold();
+        lines.push(match vehicle.delta() {
+            Some(delta) => {
+                let style = if delta.horizontal > threshold {
+                    Style::default().fg(Color::LightRed).bold()
+                } else {
+                    Style::default().fg(Color::Green)
+                };
+                Line::from(vec![
+                    Span::raw(format!("  {:<6}", "delta")),
+                    Span::styled(
+                        format!(
+                            "{:.2} m horizontal, {:.2} m vertical",
+                            delta.horizontal, delta.vertical
+                        ),
+                        style,
+                    ),
+                    Span::styled(format!("  (limit {threshold:.1} m)"), gray),
+                ])
+            }
+            None => Line::styled("  delta   needs a 3D fix on both receivers", gray),
+        });
+        Paragraph::new(lines).block(block)
+    }
+
+    /// One row per DroneCAN/UAVCAN node, worst health first.
+    fn build_uavcan(&self) -> Paragraph<'_> {
+        let block = Block::default()
+            .title(" UAVCAN nodes ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Gray));
+        let gray = Style::default().fg(Color::DarkGray);
+        let now = self.now();
+        let mut nodes: Vec<_> = self.collector.uavcan().nodes().collect();
+        nodes.sort_by_key(|(key, node)| (std::cmp::Reverse(node.health as u8), **key));
+        let mut lines = vec![Line::styled(
+            format!(
+                "  {:<8}{:<22}{:<10}{:<16}{:>10}  {:<9}{}",
+                "node", "name", "health", "mode", "uptime", "seen", "version"
+            ),
+            Style::default().fg(Color::Cyan).bold(),
+        )];
+        lines.extend(nodes.into_iter().map(|((sys, comp), node)| {
+            let color = match node.health {
+                UavcanNodeHealth::UAVCAN_NODE_HEALTH_OK => Color::Green,
+                UavcanNodeHealth::UAVCAN_NODE_HEALTH_WARNING => Color::Yellow,
+                _ => Color::LightRed,
+            };
+            let name = if node.name.is_empty() {
+                "?"
+            } else {
+                &node.name
+            };
+            let seen = node.last_status.map_or("-".to_string(), |t| {
+                format!("{}s ago", (now - t).num_seconds().max(0))
+            });
+            Line::from(vec![
+                Span::styled(
+                    format!("  {:<8}", format!("{sys}:{comp}")),
+                    Style::default().fg(id_color(*sys)),
+                ),
+                Span::raw(format!("{:<22}", name.chars().take(21).collect::<String>())),
+                Span::styled(
+                    format!("{:<10}", uavcan::health_name(node.health)),
+                    Style::default().fg(color).bold(),
+                ),
+                Span::raw(format!("{:<16}", uavcan::mode_name(node.mode))),
+                Span::raw(format!("{:>10}", format_uptime(node.uptime_sec))),
+                Span::styled(
+                    format!("  {seen:<9}{}", node.version.as_deref().unwrap_or("")),
+                    gray,
+                ),
+            ])
+        }));
+        Paragraph::new(lines).block(block)
+    }
+
+    fn render_detail(&mut self, frame: &mut Frame, area: Rect) {
+        let showing = (self.active_tab, self.tabs[self.active_tab].scroll.selected);
+        if showing != self.detail_for {
+            self.detail_for = showing;
+            self.detail_scroll = ScrollState::new();
+            self.detail_column = 0;
+        }
+        let mut lines = self.detail_lines();
+        let inner_width = area.width.saturating_sub(2);
+        self.detail_vh = area.height.saturating_sub(2) as usize;
+        self.detail_height = match self.detail_overflow {
+            Overflow::Wrap => wrapped_height(&lines, inner_width),
+            Overflow::Truncate | Overflow::Scroll => lines.len(),
+        };
+        let widest = lines.iter().map(Line::width).max().unwrap_or(0);
+        self.detail_overhang = widest.saturating_sub(inner_width as usize);
+        self.detail_column = self.detail_column.min(self.detail_overhang);
+        if self.detail_overflow == Overflow::Truncate {
+            lines = lines
+                .into_iter()
+                .map(|l| truncate_line(l, inner_width as usize))
+                .collect();
+        }
+        let positions = self.detail_positions();
+        self.detail_scroll.clamp(positions, 1);
+        let offset = self.detail_scroll.selected;
+
+        let border_style = if self.detail_focused {
+            Style::default().fg(Color::Cyan).bold()
+        } else {
+            Style::default().fg(Color::Gray)
+        };
+        let block = Block::default()
+            .title(format!(
+                " Message ({}{}) ",
+                self.detail_overflow.label(),
+                if self.hide_defaults {
+                    ", non-default"
+                } else {
+                    ""
+                }
+            ))
+            .borders(Borders::ALL)
+            .border_style(border_style);
+        let mut paragraph = Paragraph::new(lines)
+            .block(block)
+            .scroll((offset as u16, self.detail_column as u16));
+        if self.detail_overflow == Overflow::Wrap {
+            paragraph = paragraph.wrap(ratatui::widgets::Wrap { trim: false });
+        }
+        frame.render_widget(paragraph, area);
+        if positions > 1 {
+            let mut scrollbar = ScrollbarState::new(positions - 1).position(offset);
+            frame.render_stateful_widget(
+                Scrollbar::new(ScrollbarOrientation::VerticalRight),
+                area,
+                &mut scrollbar,
+            );
+        }
+    }
+
+    fn detail_lines(&self) -> Vec<Line<'static>> {
+        let selected_index = self.tabs[self.active_tab].scroll.selected;
+        let selected = match self.tab() {
+            Tab::Stream => {
+                let s = self.stream();
+                s.get(selected_index.min(s.len().saturating_sub(1)))
+                    .and_then(|e| Some((e, e.at(self.as_of)?)))
+                    .map(|(e, snapshot)| Detail {
+                        name: e.name,
+                        sys_id: e.sys_id,
+                        comp_id: e.comp_id,
+                        sys_color: e.sys_color,
+                        comp_color: e.comp_color,
+                        link: e.link,
+                        fields: snapshot.parsed_fields(),
+                        hidden: 0,
+                        payload: snapshot.payload,
+                        frame: snapshot.frame,
+                        arrivals: Some(&e.arrivals),
+                    })
+            }
+            Tab::Events => {
+                let m = self.messages();
+                m.get(selected_index.min(m.len().saturating_sub(1)))
+                    .map(|e| Detail {
+                        name: e.name,
+                        sys_id: e.sys_id,
+                        comp_id: e.comp_id,
+                        sys_color: e.sys_color,
+                        comp_color: e.comp_color,
+                        link: e.link,
+                        fields: e.parsed_fields(),
+                        hidden: 0,
+                        payload: &e.payload,
+                        frame: &e.frame,
+                        arrivals: None,
+                    })
+            }
+            Tab::Stats | Tab::Params | Tab::Console | Tab::Map => None,
+        };
+
+        match selected {
+            Some(detail) if self.detail_mode == DetailMode::Frame => {
+                frame_dump_lines(detail.name, detail.frame)
+            }
+            Some(detail) if self.detail_mode == DetailMode::ByteMap => {
+                let layout = MavMessage::message_id_from_name(detail.name)
+                    .map(|id| self.layouts.get(id))
+                    .unwrap_or_default();
+                byte_map_lines(detail.name, detail.payload, &layout)
+            }
+            Some(mut detail) => {
+                if self.hide_defaults {
+                    detail.hidden = hide_default_fields(detail.name, &mut detail.fields);
+                }
+                let clock = self.collector.clock(detail.sys_id, detail.comp_id);
+                let mode = self.modes.and_then(|scheme| flight_mode(scheme, &detail));
+                message_lines(detail, &self.links, clock, mode)
+            }
+            None => vec![Line::from(Span::styled(
+                "No messages",
+                Style::default().fg(Color::DarkGray),
+            ))],
+        }
+    }
+}
+
+/// Moves a list selection for the navigation actions; `false` for any
+/// other action.
+fn scroll_by(scroll: &mut ScrollState, action: Action, total: usize, vh: usize) -> bool {
+    match action {
+        Action::Up => scroll.select_up(1),
+        Action::Down => scroll.select_down(1, total, vh),
+        Action::PageUp => scroll.select_up(vh),
+        Action::PageDown => scroll.select_down(vh, total, vh),
+        Action::Top => scroll.select_top(),
+        Action::Bottom => scroll.select_bottom(total, vh),
+        _ => return false,
+    }
+    true
+}
+
+/// A `width` x `height` rectangle in the middle of `area`, clipped to it.
+fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+fn window_title(links: &[String], vehicles: usize, unread: usize) -> String {
+    let mut title = String::from("mavsnark");
+    if let Some(first) = links.first() {
+        title.push_str(&format!(": {first}"));
+        if links.len() > 1 {
+            title.push_str(&format!(" (+{})", links.len() - 1));
+        }
+    }
+    let plural = |n: usize| if n == 1 { "" } else { "s" };
+    title.push_str(&format!(" | {vehicles} vehicle{}", plural(vehicles)));
+    if unread > 0 {
+        title.push_str(&format!(" | {unread} alert{}", plural(unread)));
+    }
+    title
+}
+
+/// Rows `lines` occupy when wrapped to `width` columns.
+fn wrapped_height(lines: &[Line], width: u16) -> usize {
+    let width = width.max(1) as usize;
+    lines.iter().map(|l| l.width().div_ceil(width).max(1)).sum()
+}
+
+/// Cuts `line` to `width` columns, marking the cut with an ellipsis.
+fn truncate_line(line: Line<'static>, width: usize) -> Line<'static> {
+    if line.width() <= width || width == 0 {
+        return line;
+    }
+    let mut room = width - 1;
+    let mut spans = Vec::new();
+    for span in line.spans {
+        if room == 0 {
+            break;
+        }
+        let kept: String = span.content.chars().take(room).collect();
+        room -= kept.chars().count();
+        spans.push(Span::styled(kept, span.style));
+    }
+    spans.push(Span::styled(
+        "\u{2026}",
+        Style::default().fg(Color::DarkGray),
+    ));
+    Line::from(spans)
+}
+
+/// Where the time cursor sits within the collected history.
+fn timeline_bar(
+    first: DateTime<Utc>,
+    last: DateTime<Utc>,
+    at: DateTime<Utc>,
+    width: usize,
+) -> String {
+    let span = last.signed_duration_since(first).num_milliseconds().max(1) as f64;
+    let offset = at.signed_duration_since(first).num_milliseconds() as f64;
+    let pos = ((offset / span) * (width.saturating_sub(1)) as f64).round() as usize;
+    (0..width)
+        .map(|i| {
+            if i == pos.min(width.saturating_sub(1)) {
+                '\u{2588}'
+            } else {
+                '\u{2500}'
+            }
+        })
+        .collect()
+}
+
+fn typeahead_prompt(prefix: &str) -> Paragraph<'static> {
+    let key = Style::default().fg(Color::Cyan).bold();
+    Paragraph::new(Line::from(vec![
+        Span::styled(" jump to: ", key),
+        Span::raw(format!("{}\u{258f}", prefix.to_ascii_uppercase())),
+        Span::styled("  Enter/Esc", key),
+        Span::raw(" Done "),
+    ]))
+}
+
+fn find_prompt(query: &str, count: usize) -> Paragraph<'static> {
+    let key = Style::default().fg(Color::Cyan).bold();
+    Paragraph::new(Line::from(vec![
+        Span::styled(" /", key),
+        Span::raw(format!("{query}\u{258f}")),
+        Span::styled(
+            format!("  {count} matches"),
+            Style::default().fg(Color::DarkGray),
+        ),
+        Span::styled("  Enter", key),
+        Span::raw(" Done, then "),
+        Span::styled("n/N", key),
+        Span::raw(" Next/Previous  "),
+        Span::styled("Esc", key),
+        Span::raw(" Cancel "),
+    ]))
+}
+
+/// `v1`/`v2` column, shown once any MAVLink 1 frame has arrived so rows
+/// downgraded to MAVLink 1 stand out; blank for generated events.
+fn wire_span(v1: Option<bool>) -> Span<'static> {
+    match v1 {
+        Some(true) => Span::styled("v1 ", Style::default().fg(Color::Yellow)),
+        Some(false) => Span::styled("v2 ", Style::default().fg(Color::DarkGray)),
+        None => Span::raw("   "),
+    }
+}
+
+/// Signature column, shown with `--signing-key`; blank for generated events.
+fn signature_span(status: Option<SignatureStatus>) -> Span<'static> {
+    match status {
+        Some(status) => Span::styled(
+            format!("{:<8} ", status.label()),
+            Style::default().fg(status.color()),
+        ),
+        None => Span::raw(" ".repeat(9)),
+    }
+}
+
+/// Marks each occurrence of `query` in the line's spans, ignoring case.
+/// Occurrences split across two spans are not marked.
+fn highlight_matches<'a>(line: Line<'a>, query: &str) -> Line<'a> {
+    if query.is_empty() {
+        return line;
+    }
+    let query = query.to_ascii_lowercase();
+    let marked = Style::default().fg(Color::Black).bg(Color::Yellow);
+    let mut spans = Vec::with_capacity(line.spans.len());
+    for span in line.spans {
+        let lower = span.content.to_ascii_lowercase();
+        if !lower.contains(&query) {
+            spans.push(span);
+            continue;
+        }
+        let mut start = 0;
+        for (at, _) in lower.match_indices(&query) {
+            if at > start {
+                spans.push(Span::styled(
+                    span.content[start..at].to_string(),
+                    span.style,
+                ));
+            }
+            let end = at + query.len();
+            spans.push(Span::styled(
+                span.content[at..end].to_string(),
+                span.style.patch(marked),
+            ));
+            start = end;
+        }
+        if start < span.content.len() {
+            spans.push(Span::styled(span.content[start..].to_string(), span.style));
+        }
+    }
+    Line { spans, ..line }
+}
+
+fn filter_prompt(prompt: &FilterPrompt) -> Paragraph<'static> {
+    let key = Style::default().fg(Color::Cyan).bold();
+    let mut spans = vec![
+        Span::styled(" filter: ", key),
+        Span::raw(format!("{}\u{258f}", prompt.text)),
+    ];
+    match &prompt.error {
+        Some(error) => spans.push(Span::styled(
+            format!("  {error}"),
+            Style::default().fg(Color::Red),
+        )),
+        None => {
+            spans.push(Span::styled("  Enter", key));
+            spans.push(Span::raw(" Apply  "));
+        }
+    }
+    spans.push(Span::styled("  Esc", key));
+    spans.push(Span::raw(" Cancel "));
+    Paragraph::new(Line::from(spans))
+}
+
+fn search_prompt(search: &Search) -> Paragraph<'static> {
+    let key = Style::default().fg(Color::Cyan).bold();
+    let gray = Style::default().fg(Color::DarkGray);
+    let mut spans = vec![
+        Span::styled(" search: ", key),
+        Span::raw(search.query.clone()),
+    ];
+    if search.editing {
+        spans.push(Span::raw("\u{258f}"));
+        spans.push(Span::styled(format!("  {} hits", search.hits.len()), gray));
+        spans.push(Span::styled("  Enter", key));
+        spans.push(Span::raw(" Browse  "));
+        spans.push(Span::styled("Esc", key));
+        spans.push(Span::raw(" Close "));
+    } else {
+        spans.push(Span::styled(
+            format!("  {}/{}", search.scroll.selected + 1, search.hits.len()),
+            gray,
+        ));
+        spans.push(Span::styled("  \u{2191}\u{2193}/j/k", key));
+        spans.push(Span::raw(" Jump  "));
+        spans.push(Span::styled("Backspace", key));
+        spans.push(Span::raw(" Edit  "));
+        spans.push(Span::styled("Enter/Esc", key));
+        spans.push(Span::raw(" Done "));
+    }
+    Paragraph::new(Line::from(spans))
+}
+
+fn plot_prompt(prompt: &PlotPrompt) -> Paragraph<'static> {
+    let key = Style::default().fg(Color::Cyan).bold();
+    let mut spans = vec![
+        Span::styled(format!(" plot {}.", prompt.name), key),
+        Span::raw(format!("{}\u{258f}", prompt.text)),
+    ];
+    match &prompt.error {
+        Some(error) => spans.push(Span::styled(
+            format!("  {error}"),
+            Style::default().fg(Color::Red),
+        )),
+        None => {
+            spans.push(Span::styled("  Tab", key));
+            spans.push(Span::raw(" Next Field  "));
+            spans.push(Span::styled("Enter", key));
+            spans.push(Span::raw(" Plot  "));
+        }
+    }
+    spans.push(Span::styled("  Esc", key));
+    spans.push(Span::raw(" Cancel "));
+    Paragraph::new(Line::from(spans))
+}
+
+fn params_prompt(view: &ParamsView) -> Paragraph<'static> {
+    let key = Style::default().fg(Color::Cyan).bold();
+    let mut spans = vec![
+        Span::styled(" params: ", key),
+        Span::raw(view.query.clone()),
+    ];
+    if view.editing {
+        spans.push(Span::raw("\u{258f}"));
+        spans.push(Span::styled("  Enter", key));
+        spans.push(Span::raw(" Browse  "));
+        spans.push(Span::styled("Esc", key));
+        spans.push(Span::raw(" Clear "));
+    } else {
+        spans.push(Span::styled("  /", key));
+        spans.push(Span::raw(" Search  "));
+        spans.push(Span::styled("o", key));
+        spans.push(Span::raw(format!(" Sort ({})  ", view.order.label())));
+        spans.push(Span::styled("</>", key));
+        spans.push(Span::raw(" Component "));
+    }
+    Paragraph::new(Line::from(spans))
+}
+
+/// The PARAM_VALUEs of the component `view` is on, with how much of the
+/// download has arrived.
+fn build_params(view: &ParamsView, tab: &TabView, collector: &Collector) -> Paragraph<'static> {
+    let gray = Style::default().fg(Color::DarkGray);
+    let selected_style = Style::default().bg(Color::DarkGray);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan).bold());
+    let Some((&(sys_id, comp_id), set)) = collector.params().components().nth(view.component)
+    else {
+        return Paragraph::new(Line::styled(" No PARAM_VALUE received yet", gray))
+            .block(block.title(" Parameters "));
+    };
+    let rows = set.matching(&view.query, view.order);
+    let mut lines = vec![Line::styled(
+        format!(
+            "{:<16} {:>14} {:<7} {:>11}",
+            "name", "value", "type", "index"
+        ),
+        gray,
+    )];
+    lines.extend(
+        rows.iter()
+            .enumerate()
+            .skip(tab.scroll.offset)
+            .take(tab.vh)
+            .map(|(i, (name, param))| {
+                let line = Line::from(format!(
+                    "{name:<16} {:>14} {:<7} {:>5}/{:<5}",
+                    param.display(),
+                    params::kind_name(param.kind),
+                    param.index,
+                    set.count
+                ));
+                if i == tab.scroll.selected {
+                    line.style(selected_style)
+                } else {
+                    line
+                }
+            }),
+    );
+    let mut title = format!(
+        " Parameters [{sys_id}:{comp_id}] {}/{} received",
+        set.len(),
+        set.count
+    );
+    if !view.query.is_empty() {
+        title.push_str(&format!(", {} matching \"{}\"", rows.len(), view.query));
+    }
+    title.push_str(&format!(" by {} ", view.order.label()));
+    Paragraph::new(lines).block(block.title(title))
+}
+
+/// Every STATUSTEXT so far, chunked ones joined, coloured by severity.
+fn build_console(console: &TabView, collector: &Collector) -> Paragraph<'static> {
+    let gray = Style::default().fg(Color::DarkGray);
+    let selected_style = Style::default().bg(Color::DarkGray);
+    let lines = collector.statustext().lines();
+    let rows: Vec<Line> = lines
+        .iter()
+        .enumerate()
+        .skip(console.scroll.offset)
+        .take(console.vh)
+        .map(|(i, line)| {
+            let severity = statustext::severity(line.severity);
+            let mut style = severity
+                .color()
+                .map_or(Style::default(), |c| Style::default().fg(c));
+            if severity == Severity::Critical {
+                style = style.bold();
+            }
+            let row = Line::from(vec![
+                Span::styled(
+                    format!("{} ", line.at.with_timezone(&Local).format("%H:%M:%S%.3f")),
+                    gray,
+                ),
+                Span::styled(format!("[{:>3}:{:>3}] ", line.sys_id, line.comp_id), gray),
+                Span::styled(format!("{:<9} ", line.severity_name()), style),
+                Span::styled(line.text.clone(), style),
+            ]);
+            if i == console.scroll.selected {
+                row.style(selected_style)
+            } else {
+                row
+            }
+        })
+        .collect();
+    let block = Block::default()
+        .title(format!(" Status text [{}] ", lines.len()))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan).bold());
+    Paragraph::new(rows).block(block)
+}
+
+/// Search hits, oldest first, with the panel each is in.
+fn build_search(search: &Search) -> Paragraph<'static> {
+    let gray = Style::default().fg(Color::DarkGray);
+    let selected_style = Style::default().bg(Color::DarkGray);
+    let lines: Vec<Line> = search
+        .hits
+        .iter()
+        .enumerate()
+        .skip(search.scroll.offset)
+        .take(search.vh)
+        .map(|(i, hit)| {
+            let panel = match hit.target {
+                Target::Stream { .. } => "stream",
+                Target::Event => "event ",
+            };
+            let line = Line::from(vec![
+                Span::styled(
+                    format!(
+                        "{} {panel} ",
+                        hit.timestamp.with_timezone(&Local).format("%H:%M:%S%.3f")
+                    ),
+                    gray,
+                ),
+                Span::raw(format!(
+                    "[{:>3}:{:>3}] {} {}",
+                    hit.sys_id, hit.comp_id, hit.name, hit.fields
+                )),
+            ]);
+            if i == search.scroll.selected {
+                line.style(selected_style)
+            } else {
+                line
+            }
+        })
+        .collect();
+    let block = Block::default()
+        .title(format!(
+            " Search \"{}\" [{}] ",
+            search.query,
+            search.hits.len()
+        ))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan).bold());
+    Paragraph::new(lines).block(block)
+}
+
+fn format_bytes(bytes: u64) -> String {
+    match bytes {
+        b if b >= 1024 * 1024 => format!("{:.1} MB", b as f64 / (1024.0 * 1024.0)),
+        b if b >= 1024 => format!("{:.1} KB", b as f64 / 1024.0),
+        b => format!("{b} B"),
+    }
+}
+
+fn format_rate(bytes_per_sec: f64) -> String {
+    if bytes_per_sec >= 1000.0 {
+        format!("{:.1} kB/s", bytes_per_sec / 1000.0)
+    } else {
+        format!("{bytes_per_sec:.0} B/s")
+    }
+}
+
+/// Distance to the follow target and the bearing it lies on, over the
+/// last few minutes.
+/// Each vehicle's track, latest position and home, longitude across and
+/// latitude up.
+fn render_map(frame: &mut Frame, area: Rect, map: &MapTracker) {
+    let gray = Style::default().fg(Color::Gray);
+    let block = Block::default()
+        .title(" Map ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan).bold());
+    let Some(((south, west), (north, east))) = map.bounds() else {
+        let empty = Line::styled(
+            " No GLOBAL_POSITION_INT received yet",
+            Style::default().fg(Color::DarkGray),
+        );
+        frame.render_widget(Paragraph::new(empty).block(block), area);
+        return;
+    };
+    // A margin around the extent, and at least ~100 m across so a vehicle
+    // sitting still is not drawn at the very edge.
+    let pad = |low: f64, high: f64| {
+        let margin = ((high - low) * 0.1).max(0.0005);
+        [low - margin, high + margin]
+    };
+    let (x, y) = (pad(west, east), pad(south, north));
+    let xy = |&(lat, lon): &(f64, f64)| (lon, lat);
+    let points: Vec<_> = map
+        .vehicles()
+        .map(|(id, v)| {
+            let track: Vec<_> = v.track.iter().map(xy).collect();
+            let latest: Vec<_> = v.position().iter().map(xy).collect();
+            let home: Vec<_> = v.home.iter().map(xy).collect();
+            (id, track, latest, home)
+        })
+        .collect();
+    let mut datasets = Vec::new();
+    for (id, track, latest, home) in &points {
+        let style = Style::default().fg(id_color(*id));
+        datasets.push(
+            Dataset::default()
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(style)
+                .data(track),
+        );
+        datasets.push(
+            Dataset::default()
+                .name(format!("sys {id}"))
+                .marker(symbols::Marker::Block)
+                .graph_type(GraphType::Scatter)
+                .style(style.bold())
+                .data(latest),
+        );
+        if !home.is_empty() {
+            datasets.push(
+                Dataset::default()
+                    .name(format!("home {id}"))
+                    .marker(symbols::Marker::Dot)
+                    .graph_type(GraphType::Scatter)
+                    .style(style)
+                    .data(home),
+            );
+        }
+    }
+    let chart = Chart::new(datasets)
+        .block(block)
+        .x_axis(
+            Axis::default()
+                .bounds(x)
+                .labels([format!("{:.5}", x[0]), format!("{:.5}", x[1])])
+                .style(gray),
+        )
+        .y_axis(
+            Axis::default()
+                .bounds(y)
+                .labels([format!("{:.5}", y[0]), format!("{:.5}", y[1])])
+                .style(gray),
+        );
+    frame.render_widget(chart, area);
+}
+
+fn render_separation_charts(
+    frame: &mut Frame,
+    areas: [Rect; 2],
+    samples: &VecDeque<Separation>,
+    now: DateTime<Utc>,
+) {
+    let series = |f: fn(&Separation) -> f64| -> Vec<(f64, f64)> {
+        samples
+            .iter()
+            .map(|s| {
+                let ago = now.signed_duration_since(s.at).num_milliseconds() as f64 / 1000.0;
+                (-ago, f(s))
+            })
+            .collect()
+    };
+    let distance = series(|s| s.distance);
+    let bearing = series(|s| s.bearing);
+    let far = distance.iter().map(|&(_, d)| d).fold(1.0, f64::max);
+    let gray = Style::default().fg(Color::Gray);
+    let window = radio::HISTORY.num_seconds() as f64;
+    let mut draw =
+        |area: Rect, title: &'static str, color: Color, data: &[(f64, f64)], top: f64| {
+            let chart = Chart::new(vec![
+                Dataset::default()
+                    .marker(symbols::Marker::Braille)
+                    .graph_type(GraphType::Scatter)
+                    .style(Style::default().fg(color))
+                    .data(data),
+            ])
+            .block(
+                Block::default()
+                    .title(title)
+                    .borders(Borders::ALL)
+                    .border_style(gray),
+            )
+            .x_axis(
+                Axis::default()
+                    .bounds([-window, 0.0])
+                    .labels([format!("-{window:.0}s"), "now".to_string()])
+                    .style(gray),
+            )
+            .y_axis(
+                Axis::default()
+                    .bounds([0.0, top])
+                    .labels(["0".to_string(), format!("{top:.0}")])
+                    .style(gray),
+            );
+            frame.render_widget(chart, area);
+        };
+    draw(areas[0], " Separation m ", Color::Cyan, &distance, far);
+    draw(areas[1], " Bearing ", Color::Yellow, &bearing, 360.0);
+}
+
+/// Compass field strength against current (or throttle) with the fitted
+/// line, so interference shows as a slope.
+fn render_interference_chart(frame: &mut Frame, area: Rect, mag: &interference::Vehicle) {
+    let estimate = mag.estimate();
+    let basis = estimate.map_or(Basis::Current, |e| e.basis);
+    let points: Vec<(f64, f64)> = mag
+        .samples
+        .iter()
+        .filter_map(|s| Some((s.load(basis)?, s.field)))
+        .collect();
+    let right = points.iter().map(|&(x, _)| x).fold(1.0, f64::max);
+    let top = points.iter().map(|&(_, y)| y).fold(1.0, f64::max) * 1.1;
+    let fit: Vec<(f64, f64)> = estimate.map_or_else(Vec::new, |e| {
+        vec![(0.0, e.baseline), (right, e.baseline + e.slope * right)]
+    });
+    let datasets = vec![
+        Dataset::default()
+            .name("field")
+            .marker(symbols::Marker::Dot)
+            .graph_type(GraphType::Scatter)
+            .style(Style::default().fg(Color::Cyan))
+            .data(&points),
+        Dataset::default()
+            .name("fit")
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(Color::Yellow))
+            .data(&fit),
+    ];
+    let gray = Style::default().fg(Color::Gray);
+    let unit = basis.unit();
+    let chart = Chart::new(datasets)
+        .block(
+            Block::default()
+                .title(" Field mgauss vs load ")
+                .borders(Borders::ALL)
+                .border_style(gray),
+        )
+        .x_axis(
+            Axis::default()
+                .bounds([0.0, right])
+                .labels([format!("0 {unit}"), format!("{right:.0} {unit}")])
+                .style(gray),
+        )
+        .y_axis(
+            Axis::default()
+                .bounds([0.0, top])
+                .labels(["0".to_string(), format!("{top:.0}")])
+                .style(gray),
+        );
+    frame.render_widget(chart, area);
+}
+
+/// The 5 V and servo rails over the last few minutes, with the healthy 5 V
+/// band.
+fn render_power_chart(
+    frame: &mut Frame,
+    area: Rect,
+    samples: &VecDeque<PowerSample>,
+    now: DateTime<Utc>,
+) {
+    let window = radio::HISTORY.num_seconds() as f64;
+    let series = |f: fn(&PowerSample) -> f32| -> Vec<(f64, f64)> {
+        samples
+            .iter()
+            .filter(|s| f(s) > 0.0)
+            .map(|s| {
+                let ago = now.signed_duration_since(s.at).num_milliseconds() as f64 / 1000.0;
+                (-ago, f64::from(f(s)))
+            })
+            .collect()
+    };
+    let vcc = series(|s| s.vcc);
+    let vservo = series(|s| s.vservo);
+    let (low, high) = power::VCC_RANGE;
+    let low_line = vec![(-window, f64::from(low)), (0.0, f64::from(low))];
+    let high_line = vec![(-window, f64::from(high)), (0.0, f64::from(high))];
+    let top = vcc
+        .iter()
+        .chain(&vservo)
+        .map(|&(_, v)| v)
+        .fold(f64::from(high) + 0.5, f64::max);
+    let line = |name: &'static str, color: Color, data| {
+        Dataset::default()
+            .name(name)
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(color))
+            .data(data)
+    };
+    let datasets = vec![
+        line("5V", Color::Cyan, &vcc),
+        line("servo", Color::Yellow, &vservo),
+        line("", Color::DarkGray, &low_line),
+        line("", Color::DarkGray, &high_line),
+    ];
+    let gray = Style::default().fg(Color::Gray);
+    let chart = Chart::new(datasets)
+        .block(
+            Block::default()
+                .title(" Rails V ")
+                .borders(Borders::ALL)
+                .border_style(gray),
+        )
+        .x_axis(
+            Axis::default()
+                .bounds([-window, 0.0])
+                .labels([format!("-{window:.0}s"), "now".to_string()])
+                .style(gray),
+        )
+        .y_axis(
+            Axis::default()
+                .bounds([0.0, top])
+                .labels(["0".to_string(), format!("{top:.1}")])
+                .style(gray),
+        );
+    frame.render_widget(chart, area);
+}
+
+/// Terrain clearance over the last few minutes against the margin.
+fn render_clearance_chart(
+    frame: &mut Frame,
+    area: Rect,
+    samples: &VecDeque<Clearance>,
+    margin: Option<f32>,
+    now: DateTime<Utc>,
+) {
+    let window = radio::HISTORY.num_seconds() as f64;
+    let data: Vec<(f64, f64)> = samples
+        .iter()
+        .map(|s| {
+            let ago = now.signed_duration_since(s.at).num_milliseconds() as f64 / 1000.0;
+            (-ago, f64::from(s.height))
+        })
+        .collect();
+    let margin_line: Vec<(f64, f64)> = margin.map_or_else(Vec::new, |m| {
+        vec![(-window, f64::from(m)), (0.0, f64::from(m))]
+    });
+    let top = data
+        .iter()
+        .map(|&(_, v)| v)
+        .fold(margin.map_or(1.0, |m| f64::from(m) * 1.5), f64::max);
+    let line = |name: &'static str, color: Color, data| {
+        Dataset::default()
+            .name(name)
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(color))
+            .data(data)
+    };
+    let datasets = vec![
+        line("clearance", Color::Cyan, &data),
+        line("margin", Color::LightRed, &margin_line),
+    ];
+    let gray = Style::default().fg(Color::Gray);
+    let chart = Chart::new(datasets)
+        .block(
+            Block::default()
+                .title(" Clearance m ")
+                .borders(Borders::ALL)
+                .border_style(gray),
+        )
+        .x_axis(
+            Axis::default()
+                .bounds([-window, 0.0])
+                .labels([format!("-{window:.0}s"), "now".to_string()])
+                .style(gray),
+        )
+        .y_axis(
+            Axis::default()
+                .bounds([0.0, top])
+                .labels(["0".to_string(), format!("{top:.0}")])
+                .style(gray),
+        );
+    frame.render_widget(chart, area);
+}
+
+/// Crosstrack error, and desired against actual bearing, over the last few
+/// minutes.
+fn render_navigation_charts(
+    frame: &mut Frame,
+    areas: [Rect; 2],
+    samples: &VecDeque<NavSample>,
+    now: DateTime<Utc>,
+) {
+    let window = radio::HISTORY.num_seconds() as f64;
+    let series = |f: &dyn Fn(&NavSample) -> Option<f64>| -> Vec<(f64, f64)> {
+        samples
+            .iter()
+            .filter_map(|s| {
+                let ago = now.signed_duration_since(s.at).num_milliseconds() as f64 / 1000.0;
+                Some((-ago, f(s)?))
+            })
+            .collect()
+    };
+    let crosstrack = series(&|s| Some(s.crosstrack));
+    let desired = series(&|s| Some(s.desired));
+    let actual = series(&|s| s.actual);
+    let line = |name: &'static str, color: Color, data| {
+        Dataset::default()
+            .name(name)
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(color))
+            .data(data)
+    };
+    let gray = Style::default().fg(Color::Gray);
+    let chart = |title: &'static str, datasets, bounds: [f64; 2]| {
+        Chart::new(datasets)
+            .block(
+                Block::default()
+                    .title(title)
+                    .borders(Borders::ALL)
+                    .border_style(gray),
+            )
+            .x_axis(
+                Axis::default()
+                    .bounds([-window, 0.0])
+                    .labels([format!("-{window:.0}s"), "now".to_string()])
+                    .style(gray),
+            )
+            .y_axis(
+                Axis::default()
+                    .bounds(bounds)
+                    .labels([format!("{:.0}", bounds[0]), format!("{:.0}", bounds[1])])
+                    .style(gray),
+            )
+    };
+
+    // Symmetric about the track so left and right read alike.
+    let reach = crosstrack.iter().map(|&(_, v)| v.abs()).fold(1.0, f64::max);
+    let datasets = vec![line("xtrack", Color::Cyan, &crosstrack)];
+    frame.render_widget(chart(" Crosstrack m ", datasets, [-reach, reach]), areas[0]);
+    let datasets = vec![
+        line("desired", Color::Yellow, &desired),
+        line("actual", Color::Cyan, &actual),
+    ];
+    frame.render_widget(chart(" Bearing ° ", datasets, [0.0, 360.0]), areas[1]);
+}
+
+/// Vibration levels against the warning level, and the clipping counters,
+/// over the last few minutes.
+fn render_vibration_charts(
+    frame: &mut Frame,
+    areas: [Rect; 2],
+    samples: &VecDeque<VibrationSample>,
+    warn: f32,
+    now: DateTime<Utc>,
+) {
+    let window = radio::HISTORY.num_seconds() as f64;
+    let series = |f: &dyn Fn(&VibrationSample) -> f64| -> Vec<(f64, f64)> {
+        samples
+            .iter()
+            .map(|s| {
+                let ago = now.signed_duration_since(s.at).num_milliseconds() as f64 / 1000.0;
+                (-ago, f(s))
+            })
+            .collect()
+    };
+    let levels: Vec<Vec<(f64, f64)>> = (0..3)
+        .map(|axis| series(&|s| f64::from(s.levels[axis])))
+        .collect();
+    let clipping: Vec<Vec<(f64, f64)>> = (0..3)
+        .map(|imu| series(&|s| f64::from(s.clipping[imu])))
+        .collect();
+    let warn_line = vec![(-window, f64::from(warn)), (0.0, f64::from(warn))];
+    let line = |name: String, color: Color, data| {
+        Dataset::default()
+            .name(name)
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(color))
+            .data(data)
+    };
+    let colors = [Color::Cyan, Color::Yellow, Color::Magenta];
+    let gray = Style::default().fg(Color::Gray);
+    let chart = |title: &'static str, datasets, top: f64| {
+        Chart::new(datasets)
+            .block(
+                Block::default()
+                    .title(title)
+                    .borders(Borders::ALL)
+                    .border_style(gray),
+            )
+            .x_axis(
+                Axis::default()
+                    .bounds([-window, 0.0])
+                    .labels([format!("-{window:.0}s"), "now".to_string()])
+                    .style(gray),
+            )
+            .y_axis(
+                Axis::default()
+                    .bounds([0.0, top])
+                    .labels(["0".to_string(), format!("{top:.0}")])
+                    .style(gray),
+            )
+    };
+
+    let top = levels
+        .iter()
+        .flatten()
+        .map(|&(_, v)| v)
+        .fold(f64::from(warn) * 1.2, f64::max);
+    let mut datasets: Vec<Dataset> = levels
+        .iter()
+        .zip(["x", "y", "z"])
+        .zip(colors)
+        .map(|((data, axis), color)| line(axis.to_string(), color, data))
+        .collect();
+    datasets.push(line("warn".to_string(), Color::LightRed, &warn_line));
+    frame.render_widget(chart(" Vibration m/s² ", datasets, top), areas[0]);
+
+    let top = clipping
+        .iter()
+        .flatten()
+        .map(|&(_, v)| v)
+        .fold(1.0, f64::max);
+    let datasets = clipping
+        .iter()
+        .enumerate()
+        .zip(colors)
+        .map(|((imu, data), color)| line(format!("clip{imu}"), color, data))
+        .collect();
+    frame.render_widget(chart(" Clipping ", datasets, top), areas[1]);
+}
+
+/// Line paid out and tension over the last few minutes, sharing one axis.
+fn render_winch_chart(
+    frame: &mut Frame,
+    area: Rect,
+    samples: &VecDeque<WinchSample>,
+    now: DateTime<Utc>,
+) {
+    let series = |f: fn(&WinchSample) -> f32| -> Vec<(f64, f64)> {
+        samples
+            .iter()
+            .filter(|s| !f(s).is_nan())
+            .map(|s| {
+                let ago = now.signed_duration_since(s.at).num_milliseconds() as f64 / 1000.0;
+                (-ago, f64::from(f(s)))
+            })
+            .collect()
+    };
+    let length = series(|s| s.line_length);
+    let tension = series(|s| s.tension);
+    let top = length
+        .iter()
+        .chain(&tension)
+        .map(|&(_, v)| v)
+        .fold(1.0, f64::max);
+    let line = |name: &'static str, color: Color, data| {
+        Dataset::default()
+            .name(name)
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(color))
+            .data(data)
+    };
+    let datasets = vec![
+        line("line m", Color::Cyan, &length),
+        line("tension kg", Color::Yellow, &tension),
+    ];
+    let gray = Style::default().fg(Color::Gray);
+    let window = radio::HISTORY.num_seconds() as f64;
+    let chart = Chart::new(datasets)
+        .block(
+            Block::default()
+                .title(" Line / tension ")
+                .borders(Borders::ALL)
+                .border_style(gray),
+        )
+        .x_axis(
+            Axis::default()
+                .bounds([-window, 0.0])
+                .labels([format!("-{window:.0}s"), "now".to_string()])
+                .style(gray),
+        )
+        .y_axis(
+            Axis::default()
+                .bounds([0.0, top])
+                .labels(["0".to_string(), format!("{top:.1}")])
+                .style(gray),
+        );
+    frame.render_widget(chart, area);
+}
+
+/// The `w` field over the last minute, scaled to what it did in that time.
+fn render_field_plot(frame: &mut Frame, area: Rect, plot: &FieldPlot, now: DateTime<Utc>) {
+    let data: Vec<(f64, f64)> = plot
+        .samples()
+        .iter()
+        .map(|&(t, v)| {
+            let ago = now.signed_duration_since(t).num_milliseconds() as f64 / 1000.0;
+            (-ago, v)
+        })
+        .collect();
+    let (lo, hi) = plot.range().unwrap_or((0.0, 1.0));
+    // A flat line still needs some height.
+    let pad = ((hi - lo) * 0.05).max(hi.abs().max(1.0) * 1e-3);
+    let (lo, hi) = (lo - pad, hi + pad);
+    let latest = plot
+        .samples()
+        .back()
+        .map_or_else(|| "-".to_string(), |&(_, v)| format!("{v}"));
+    let gray = Style::default().fg(Color::Gray);
+    let window = plot::WINDOW.num_seconds() as f64;
+    let dataset = Dataset::default()
+        .marker(symbols::Marker::Braille)
+        .graph_type(GraphType::Line)
+        .style(Style::default().fg(Color::Cyan))
+        .data(&data);
+    let chart = Chart::new(vec![dataset])
+        .block(
+            Block::default()
+                .title(format!(" {} = {latest} ", plot.label()))
+                .borders(Borders::ALL)
+                .border_style(gray),
+        )
+        .x_axis(
+            Axis::default()
+                .bounds([-window, 0.0])
+                .labels([format!("-{window:.0}s"), "now".to_string()])
+                .style(gray),
+        )
+        .y_axis(
+            Axis::default()
+                .bounds([lo, hi])
+                .labels([format!("{lo:.3}"), format!("{hi:.3}")])
+                .style(gray),
+        );
+    frame.render_widget(chart, area);
+}
+
+/// Cellular signal quality over the last few minutes.
+fn render_quality_chart(
+    frame: &mut Frame,
+    area: Rect,
+    samples: &VecDeque<(DateTime<Utc>, u8)>,
+    now: DateTime<Utc>,
+) {
+    let data: Vec<(f64, f64)> = samples
+        .iter()
+        .map(|&(t, q)| {
+            let ago = now.signed_duration_since(t).num_milliseconds() as f64 / 1000.0;
+            (-ago, f64::from(q))
+        })
+        .collect();
+    let datasets = vec![
+        Dataset::default()
+            .name("quality %")
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(Color::Green))
+            .data(&data),
+    ];
+    let gray = Style::default().fg(Color::Gray);
+    let window = radio::HISTORY.num_seconds() as f64;
+    let chart = Chart::new(datasets)
+        .block(
+            Block::default()
+                .title(" Signal quality ")
+                .borders(Borders::ALL)
+                .border_style(gray),
+        )
+        .x_axis(
+            Axis::default()
+                .bounds([-window, 0.0])
+                .labels([format!("-{window:.0}s"), "now".to_string()])
+                .style(gray),
+        )
+        .y_axis(
+            Axis::default()
+                .bounds([0.0, 100.0])
+                .labels(["0", "100"])
+                .style(gray),
+        );
+    frame.render_widget(chart, area);
+}
+
+/// Replay speed and how far it has got, next to the tabs.
+fn replay_status(replay: &Replay) -> Line<'static> {
+    let key = Style::default().fg(Color::Cyan).bold();
+    let gray = Style::default().fg(Color::DarkGray);
+    let (played, total) = replay.progress();
+    let secs = |t: TimeDelta| t.num_seconds().max(0) as u32;
+    let state = if replay.finished() { "done" } else { "replay" };
+    Line::from(vec![
+        Span::styled(format!("{state} {}x ", replay.speed()), key),
+        Span::styled(
+            format!(
+                "{} / {} ",
+                format_uptime(secs(played)),
+                format_uptime(secs(total))
+            ),
+            gray,
+        ),
+        Span::styled("+/-", key),
+        Span::raw(" Speed "),
+    ])
+}
+
+/// `3d 04:05:06`, or without the days under a day.
+fn format_uptime(secs: u32) -> String {
+    let (days, rest) = (secs / 86_400, secs % 86_400);
+    let clock = format!("{:02}:{:02}:{:02}", rest / 3600, rest / 60 % 60, rest % 60);
+    if days > 0 {
+        format!("{days}d {clock}")
+    } else {
+        clock
+    }
+}
+
+/// Local/remote RSSI and noise over the last few minutes, with packet loss
+/// drawn as bars scaled to its own peak.
+fn render_radio_chart(
+    frame: &mut Frame,
+    area: Rect,
+    samples: &VecDeque<RadioSample>,
+    loss: &VecDeque<(i64, u32)>,
+    now: DateTime<Utc>,
+) {
+    let ago = |t: DateTime<Utc>| -(now.signed_duration_since(t).num_milliseconds() as f64) / 1000.0;
+    let series = |f: fn(&RadioSample) -> u8| -> Vec<(f64, f64)> {
+        samples
+            .iter()
+            .map(|s| (ago(s.timestamp), f(s) as f64))
+            .collect()
+    };
+    let rssi = series(|s| s.rssi);
+    let remrssi = series(|s| s.remrssi);
+    let noise = series(|s| s.noise);
+    let remnoise = series(|s| s.remnoise);
+
+    let peak = loss.iter().map(|&(_, n)| n).max().unwrap_or(0);
+    let lost: Vec<(f64, f64)> = loss
+        .iter()
+        .filter_map(|&(second, n)| {
+            let t = DateTime::from_timestamp(second, 0)?;
+            Some((ago(t), n as f64 * 255.0 / peak.max(1) as f64))
+        })
+        .collect();
+    let loss_label = format!("loss (peak {peak}/s)");
+
+    let line = |name: &'static str, color: Color, data| {
+        Dataset::default()
+            .name(name)
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(color))
+            .data(data)
+    };
+    let datasets = vec![
+        Dataset::default()
+            .name(loss_label)
+            .marker(symbols::Marker::Bar)
+            .graph_type(GraphType::Bar)
+            .style(Style::default().fg(Color::DarkGray))
+            .data(&lost),
+        line("rssi", Color::Green, &rssi),
+        line("remrssi", Color::Cyan, &remrssi),
+        line("noise", Color::Red, &noise),
+        line("remnoise", Color::Magenta, &remnoise),
+    ];
+    let gray = Style::default().fg(Color::Gray);
+    let window = radio::HISTORY.num_seconds() as f64;
+    let chart = Chart::new(datasets)
+        .block(
+            Block::default()
+                .title(" RSSI / noise ")
+                .borders(Borders::ALL)
+                .border_style(gray),
+        )
+        .x_axis(
+            Axis::default()
+                .bounds([-window, 0.0])
+                .labels([format!("-{window:.0}s"), "now".to_string()])
+                .style(gray),
+        )
+        .y_axis(
+            Axis::default()
+                .bounds([0.0, 255.0])
+                .labels(["0", "255"])
+                .style(gray),
+        );
+    frame.render_widget(chart, area);
+}
+
+/// Total messages per second over the whole session, and those of the type
+/// selected in the Stream tab.
+fn render_rate_chart(
+    frame: &mut Frame,
+    area: Rect,
+    history: &RateHistory,
+    selected: Option<&'static str>,
+) {
+    // Braille packs two points per cell; leave room for the y labels.
+    let points = (area.width.saturating_sub(8) as usize * 2).max(1);
+    let total = history.series(None, points);
+    let one = selected.map(|name| history.series(Some(name), points));
+    let peak = total.iter().map(|&(_, hz)| hz).fold(1.0, f64::max);
+
+    let line = |name: String, color: Color, data| {
+        Dataset::default()
+            .name(name)
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(color))
+            .data(data)
+    };
+    let mut datasets = vec![line("total".to_string(), Color::Cyan, &total)];
+    if let (Some(name), Some(one)) = (selected, &one) {
+        datasets.push(line(name.to_string(), Color::Yellow, one));
+    }
+    let gray = Style::default().fg(Color::Gray);
+    let span = history.span().max(1) as f64;
+    let clock = |offset: i64| {
+        history
+            .start()
+            .map(|start| {
+                (start + TimeDelta::seconds(offset))
+                    .with_timezone(&Local)
+                    .format("%H:%M:%S")
+                    .to_string()
+            })
+            .unwrap_or_default()
+    };
+    let chart = Chart::new(datasets)
+        .block(
+            Block::default()
+                .title(" Message rate (msg/s) ")
+                .borders(Borders::ALL)
+                .border_style(gray),
+        )
+        .x_axis(
+            Axis::default()
+                .bounds([0.0, span])
+                .labels([clock(0), clock(span as i64)])
+                .style(gray),
+        )
+        .y_axis(
+            Axis::default()
+                .bounds([0.0, peak])
+                .labels(["0".to_string(), format!("{peak:.0}")])
+                .style(gray),
+        );
+    frame.render_widget(chart, area);
+}
+
+/// How a stream's recent inter-arrival intervals are spread: one tall bar
+/// for steady scheduling, several clusters for bursts, a long tail for
+/// starvation.
+fn render_interval_histogram(frame: &mut Frame, area: Rect, arrivals: &ArrivalStats) {
+    const BAR_WIDTH: u16 = 4;
+    let bins = (area.width.saturating_sub(2) / (BAR_WIDTH + 1)).max(1) as usize;
+    let histogram = arrivals.histogram(bins);
+    let bars: Vec<Bar> = histogram
+        .iter()
+        .map(|&(from_ms, count)| {
+            Bar::default()
+                .value(count)
+                .label(Line::from(format!("{from_ms:.0}")))
+        })
+        .collect();
+    let gray = Style::default().fg(Color::Gray);
+    let chart = BarChart::default()
+        .block(
+            Block::default()
+                .title(" Inter-arrival (ms, last 100) ")
+                .borders(Borders::ALL)
+                .border_style(gray),
+        )
+        .bar_width(BAR_WIDTH)
+        .bar_gap(1)
+        .bar_style(Style::default().fg(Color::Cyan))
+        .value_style(Style::default().fg(Color::Black).bg(Color::Cyan))
+        .label_style(gray)
+        .data(BarGroup::default().bars(&bars));
+    frame.render_widget(chart, area);
+}
+
+/// MESSAGE_INTERVAL answers against observed rates, problems first.
+fn build_intervals(audit: &[AuditRow]) -> Paragraph<'static> {
+    let gray = Style::default().fg(Color::DarkGray);
+    let problems = audit.iter().filter(|r| r.status.is_problem()).count();
+    let title = match problems {
+        0 => " Intervals ".to_string(),
+        n => format!(" Intervals [{n} flagged] "),
+    };
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Gray));
+    let mut rows: Vec<&AuditRow> = audit.iter().collect();
+    rows.sort_by_key(|r| !r.status.is_problem());
+    let lines: Vec<Line> = rows
+        .into_iter()
+        .map(|r| {
+            let configured = match (r.configured, r.configured.and_then(Interval::hz)) {
+                (_, Some(hz)) => format!("{hz:.1} Hz"),
+                (Some(Interval::Disabled), _) => "off".to_string(),
+                (Some(_), _) => "n/a".to_string(),
+                (None, _) => "?".to_string(),
+            };
+            let observed = r
+                .observed
+                .map_or("-".to_string(), |hz| format!("{hz:.1} Hz"));
+            let color = match r.status {
+                Status::Ok => Color::Green,
+                Status::Pending | Status::Disabled | Status::Unavailable => Color::Gray,
+                _ => Color::LightRed,
+            };
+            Line::from(vec![
+                Span::styled(
+                    format!(" {:>3}:{:<3} ", r.sys_id, r.comp_id),
+                    Style::default().fg(id_color(r.sys_id)),
+                ),
+                Span::raw(format!("{:<28}", r.name)),
+                Span::styled("set ", gray),
+                Span::raw(format!("{configured:>9}")),
+                Span::styled("  seen ", gray),
+                Span::raw(format!("{observed:>9}  ")),
+                Span::styled(r.status.label(), Style::default().fg(color)),
+            ])
+        })
+        .collect();
+    Paragraph::new(lines).block(block)
+}
+
+fn panel_block(
+    label: &str,
+    count: usize,
+    count_suffix: &str,
+    auto_scroll: bool,
     active: bool,
 ) -> Block<'static> {
     let count_label = if count_suffix.is_empty() {
@@ -400,14 +5604,200 @@ fn panel_block(
         .border_style(border_style)
 }
 
-fn message_lines(
+/// The selected entry, flattened for the detail panel.
+struct Detail<'a> {
     name: &'static str,
     sys_id: u8,
     comp_id: u8,
     sys_color: Color,
     comp_color: Color,
-    fields: Vec<(&str, &str)>,
+    link: usize,
+    fields: Vec<(&'a str, &'a str)>,
+    /// Fields left out because they hold their default value.
+    hidden: usize,
+    payload: &'a [u8],
+    frame: &'a [u8],
+    arrivals: Option<&'a ArrivalStats>,
+}
+
+/// Bytes per row of the byte map.
+const BYTE_MAP_ROW: usize = 8;
+
+/// Payload bytes coloured by the field they encode, followed by a legend of
+/// each field's offset and length.
+fn byte_map_lines(name: &'static str, payload: &[u8], layout: &[FieldSpan]) -> Vec<Line<'static>> {
+    let gray = Style::default().fg(Color::DarkGray);
+    let mut lines = vec![
+        Line::from(Span::styled(name, Style::default().fg(Color::Cyan).bold())),
+        Line::from(""),
+    ];
+    let full_len = layout
+        .last()
+        .map_or(0, |s| s.offset + s.len)
+        .max(payload.len());
+    if full_len == 0 {
+        lines.push(Line::from(Span::styled("No payload", gray)));
+        return lines;
+    }
+    let field_of = |offset: usize| {
+        layout
+            .iter()
+            .position(|s| (s.offset..s.offset + s.len).contains(&offset))
+    };
+
+    for row in (0..full_len).step_by(BYTE_MAP_ROW) {
+        let mut spans = vec![Span::styled(format!("{row:04x} "), gray)];
+        for offset in row..(row + BYTE_MAP_ROW).min(full_len) {
+            let span = match (payload.get(offset), field_of(offset)) {
+                (None, _) => Span::styled(" 00", gray),
+                (Some(b), Some(i)) => {
+                    Span::styled(format!(" {b:02x}"), Style::default().fg(id_color(i as u8)))
+                }
+                (Some(b), None) => Span::raw(format!(" {b:02x}")),
+            };
+            spans.push(span);
+        }
+        lines.push(Line::from(spans));
+    }
+
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled("offset len  field", gray)));
+    for (i, span) in layout.iter().enumerate() {
+        lines.push(Line::from(vec![
+            Span::raw(format!("{:>6} {:>3}  ", span.offset, span.len)),
+            Span::styled(span.field.clone(), Style::default().fg(id_color(i as u8))),
+        ]));
+    }
+    if payload.len() < full_len {
+        lines.push(Line::from(Span::styled(
+            format!(
+                "({} trailing zero bytes truncated on the wire)",
+                full_len - payload.len()
+            ),
+            gray,
+        )));
+    }
+    lines
+}
+
+/// Bytes per row of the frame dump.
+const FRAME_DUMP_ROW: usize = 16;
+
+fn region_color(region: Region) -> Color {
+    match region {
+        Region::Header => Color::Magenta,
+        Region::Payload => Color::Green,
+        Region::Checksum => Color::Yellow,
+        Region::Signature => Color::Blue,
+    }
+}
+
+/// The frame as offset, hex and ASCII columns, each byte coloured by the
+/// region it belongs to, followed by a legend of the regions.
+fn frame_dump_lines(name: &'static str, frame: &[u8]) -> Vec<Line<'static>> {
+    let gray = Style::default().fg(Color::DarkGray);
+    let mut lines = vec![
+        Line::from(Span::styled(name, Style::default().fg(Color::Cyan).bold())),
+        Line::from(""),
+    ];
+    if frame.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "Raw frame not kept (generated, or loaded from a session)",
+            gray,
+        )));
+        return lines;
+    }
+    let regions = frame::regions(frame);
+    let style_of = |offset: usize| {
+        regions
+            .iter()
+            .find(|(_, range)| range.contains(&offset))
+            .map_or(Style::default(), |(region, _)| {
+                Style::default().fg(region_color(*region))
+            })
+    };
+
+    for row in (0..frame.len()).step_by(FRAME_DUMP_ROW) {
+        let bytes = &frame[row..(row + FRAME_DUMP_ROW).min(frame.len())];
+        let mut spans = vec![Span::styled(format!("{row:04x} "), gray)];
+        for (i, b) in bytes.iter().enumerate() {
+            spans.push(Span::styled(format!(" {b:02x}"), style_of(row + i)));
+        }
+        spans.push(Span::raw(
+            " ".repeat(3 * (FRAME_DUMP_ROW - bytes.len()) + 2),
+        ));
+        for (i, &b) in bytes.iter().enumerate() {
+            let c = if b.is_ascii_graphic() || b == b' ' {
+                b as char
+            } else {
+                '.'
+            };
+            spans.push(Span::styled(c.to_string(), style_of(row + i)));
+        }
+        lines.push(Line::from(spans));
+    }
+
+    lines.push(Line::from(""));
+    let mut legend = Vec::new();
+    for (region, range) in &regions {
+        legend.push(Span::styled(
+            format!("{} {}", region.name(), range.len()),
+            Style::default().fg(region_color(*region)),
+        ));
+        legend.push(Span::raw("  "));
+    }
+    legend.push(Span::styled(format!("({} bytes)", frame.len()), gray));
+    lines.push(Line::from(legend));
+    lines
+}
+
+/// The decoded flight mode of a HEARTBEAT detail.
+fn flight_mode(scheme: ModeScheme, detail: &Detail) -> Option<String> {
+    if detail.name != "HEARTBEAT" {
+        return None;
+    }
+    let field = |key| {
+        detail
+            .fields
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| *v)
+    };
+    let custom_mode = field("custom_mode")?.parse().ok()?;
+    modes::decode(scheme, field("mavtype")?, custom_mode)
+}
+
+/// Drops fields equal to their value in a default `name` message and
+/// returns how many were dropped.
+fn hide_default_fields(name: &str, fields: &mut Vec<(&str, &str)>) -> usize {
+    let Some(defaults) = message::default_fields(name) else {
+        return 0;
+    };
+    let defaults = parse_fields(&defaults);
+    let before = fields.len();
+    fields.retain(|field| !defaults.contains(field));
+    before - fields.len()
+}
+
+fn message_lines(
+    detail: Detail,
+    links: &[String],
+    clock: Option<&ClockEstimate>,
+    mode: Option<String>,
 ) -> Vec<Line<'static>> {
+    let Detail {
+        name,
+        sys_id,
+        comp_id,
+        sys_color,
+        comp_color,
+        link,
+        fields,
+        hidden,
+        payload: _,
+        frame: _,
+        arrivals,
+    } = detail;
     let label = Style::default().fg(Color::Gray);
     let mut lines = vec![
         Line::from(Span::styled(name, Style::default().fg(Color::Cyan).bold())),
@@ -420,94 +5810,1213 @@ fn message_lines(
             Span::styled("comp_id ", label),
             Span::styled(format!("{}", comp_id), Style::default().fg(comp_color)),
         ]),
-        Line::from(""),
     ];
+    if links.len() > 1 {
+        let uri = links.get(link).map(String::as_str).unwrap_or("?");
+        lines.push(Line::from(vec![
+            Span::styled("link    ", label),
+            Span::raw(format!("{link} {uri}")),
+        ]));
+    }
+    if let Some(rate) = arrivals.and_then(ArrivalStats::summary) {
+        lines.push(Line::from(vec![
+            Span::styled("rate    ", label),
+            Span::raw(rate),
+        ]));
+    }
+    if let Some(mode) = mode {
+        lines.push(Line::from(vec![
+            Span::styled("mode    ", label),
+            Span::raw(mode),
+        ]));
+    }
+    if let Some(est) = clock {
+        lines.push(Line::from(vec![
+            Span::styled("clock   ", label),
+            Span::raw(clock::summary(est)),
+        ]));
+        let aligned = fields
+            .iter()
+            .find(|(key, _)| *key == "time_boot_ms")
+            .and_then(|(_, value)| value.parse().ok())
+            .and_then(|boot_ms| est.align(boot_ms));
+        if let Some(ts) = aligned {
+            lines.push(Line::from(vec![
+                Span::styled("sent    ", label),
+                Span::raw(clock::format_aligned(ts)),
+            ]));
+        }
+    }
+    lines.push(Line::from(""));
     for (key, value) in fields {
         lines.push(Line::from(vec![
             Span::styled(format!("{key}: "), label),
             Span::raw(value.to_string()),
         ]));
     }
-    lines
-}
+    if hidden > 0 {
+        lines.push(Line::from(Span::styled(
+            format!("({hidden} default fields hidden)"),
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::{MavHeader, common::MavMessage};
+    use ratatui::{Terminal, backend::TestBackend, buffer::Buffer};
+
+    use super::*;
+    use crate::message::MavMsg;
+
+    impl App {
+        /// Freezes the clock used for ages, charts and staleness at `now`.
+        fn with_clock(mut self, now: DateTime<Utc>) -> Self {
+            self.clock = Some(now);
+            self
+        }
+
+        /// Draws one frame into an off-screen buffer of `width` x `height`, as
+        /// the terminal would show it.
+        fn render(&mut self, width: u16, height: u16) -> Buffer {
+            let mut terminal =
+                Terminal::new(TestBackend::new(width, height)).expect("test backend cannot fail");
+            terminal
+                .draw(|frame| self.draw_frame(frame))
+                .expect("test backend cannot fail");
+            terminal.backend().buffer().clone()
+        }
+
+        /// [`App::render`] as plain text, one line per row with trailing blanks
+        /// trimmed.
+        fn render_to_string(&mut self, width: u16, height: u16) -> String {
+            buffer_text(&self.render(width, height))
+        }
+    }
+
+    /// The symbols of `buffer`, without styling.
+    fn buffer_text(buffer: &Buffer) -> String {
+        let area = buffer.area;
+        let mut text = String::new();
+        for y in area.top()..area.bottom() {
+            let row: String = (area.left()..area.right())
+                .map(|x| buffer[(x, y)].symbol())
+                .collect();
+            text.push_str(row.trim_end());
+            text.push('\n');
+        }
+        text
+    }
+
+    /// The inside of the bordered panel whose title starts with `title`, one
+    /// line per row with trailing blanks trimmed.
+    fn panel_text(buffer: &Buffer, title: &str) -> Option<String> {
+        let area = buffer.area;
+        let symbol = |x: u16, y: u16| buffer[(x, y)].symbol();
+        let heading = format!(" {title}");
+        let (left, top) = (area.top()..area.bottom()).find_map(|y| {
+            (area.left()..area.right()).find_map(|x| {
+                let after: String = (x + 1..area.right()).map(|x| symbol(x, y)).collect();
+                (symbol(x, y) == "┌" && after.starts_with(&heading)).then_some((x, y))
+            })
+        })?;
+        let right = (left + 1..area.right()).find(|&x| symbol(x, top) == "┐")?;
+        let bottom = (top + 1..area.bottom()).find(|&y| symbol(left, y) == "└")?;
+        let mut text = String::new();
+        for y in top + 1..bottom {
+            let row: String = (left + 1..right).map(|x| symbol(x, y)).collect();
+            text.push_str(row.trim_end());
+            text.push('\n');
+        }
+        Some(text)
+    }
+
+    fn make_app_with_stream_entries(n: usize) -> App {
+        let mut app = App::new();
+        app.select_tab(Tab::Stream as usize);
+        for view in &mut app.tabs {
+            view.vh = 10;
+        }
+        for i in 0..n {
+            let header = MavHeader {
+                system_id: i as u8,
+                component_id: 1,
+                sequence: 0,
+            };
+            let msg = MavMessage::HEARTBEAT(mavlink::common::HEARTBEAT_DATA::default());
+            app.collector.push(MavMsg::new(header, msg, 0));
+        }
+        app
+    }
+
+    // --- handle_key tests ---
+
+    #[test]
+    fn quit_on_q() {
+        let mut app = App::new();
+        assert!(app.handle_key(KeyCode::Char('q'), KeyModifiers::NONE));
+    }
+
+    #[test]
+    fn quit_on_esc() {
+        let mut app = App::new();
+        assert!(app.handle_key(KeyCode::Esc, KeyModifiers::NONE));
+    }
+
+    #[test]
+    fn j_moves_down() {
+        let mut app = make_app_with_stream_entries(5);
+        app.handle_key(KeyCode::Char('j'), KeyModifiers::NONE);
+        assert_eq!(app.view(Tab::Stream).scroll.selected, 1);
+    }
+
+    #[test]
+    fn k_moves_up() {
+        let mut app = make_app_with_stream_entries(5);
+        app.handle_key(KeyCode::Char('j'), KeyModifiers::NONE);
+        app.handle_key(KeyCode::Char('j'), KeyModifiers::NONE);
+        app.handle_key(KeyCode::Char('k'), KeyModifiers::NONE);
+        assert_eq!(app.view(Tab::Stream).scroll.selected, 1);
+    }
+
+    #[test]
+    fn tab_cycles_tabs() {
+        let mut app = App::new();
+        assert_eq!(app.tab(), Tab::Events);
+        app.handle_key(KeyCode::Tab, KeyModifiers::NONE);
+        assert_eq!(app.tab(), Tab::Stats);
+        app.handle_key(KeyCode::BackTab, KeyModifiers::NONE);
+        assert_eq!(app.tab(), Tab::Events);
+        app.select_tab(Tab::Stream as usize);
+        app.handle_key(KeyCode::BackTab, KeyModifiers::NONE);
+        assert_eq!(app.tab(), Tab::ALL[Tab::ALL.len() - 1]);
+        app.handle_key(KeyCode::Tab, KeyModifiers::NONE);
+        assert_eq!(app.tab(), Tab::Stream);
+    }
+
+    #[test]
+    fn g_selects_top() {
+        let mut app = make_app_with_stream_entries(5);
+        app.handle_key(KeyCode::Char('j'), KeyModifiers::NONE);
+        app.handle_key(KeyCode::Char('j'), KeyModifiers::NONE);
+        app.handle_key(KeyCode::Char('g'), KeyModifiers::NONE);
+        assert_eq!(app.view(Tab::Stream).scroll.selected, 0);
+    }
+
+    #[test]
+    fn big_g_selects_bottom() {
+        let mut app = make_app_with_stream_entries(5);
+        app.handle_key(KeyCode::Char('G'), KeyModifiers::NONE);
+        assert_eq!(app.view(Tab::Stream).scroll.selected, 4);
+    }
+
+    #[test]
+    fn s_filters_events_by_severity() {
+        use mavlink::common::{MavSeverity, STATUSTEXT_DATA};
+
+        let mut app = App::new();
+        app.collector
+            .push_note("AUTOSAVE_FAILED", "error: full".to_string());
+        app.collector.push(MavMsg::new(
+            MavHeader::default(),
+            MavMessage::STATUSTEXT(STATUSTEXT_DATA {
+                severity: MavSeverity::MAV_SEVERITY_WARNING,
+                ..Default::default()
+            }),
+            0,
+        ));
+        assert_eq!(app.messages().len(), 2);
+        assert!(
+            app.render_to_string(200, 20)
+                .contains("Events [2 | 1 info, 1 warning, 0 error, 0 critical]")
+        );
+
+        app.handle_key(KeyCode::Char('s'), KeyModifiers::NONE);
+        assert_eq!(app.severity_filter, Some(Severity::Warning));
+        assert_eq!(app.messages()[0].name, "STATUSTEXT");
+        app.handle_key(KeyCode::Char('s'), KeyModifiers::NONE);
+        assert!(app.messages().is_empty());
+        // The counts still cover what the filter hides.
+        assert_eq!(app.severity_counts(), [1, 1, 0, 0]);
+    }
+
+    #[test]
+    fn visible_rows_follow_new_and_dropped_events() {
+        let mut app = App::new().with_limits(Limits {
+            max_events: Some(2),
+            ..Limits::default()
+        });
+        app.collector.push_note("FIRST", String::new());
+        assert_eq!(app.messages().len(), 1);
+        app.collector.push_note("SECOND", String::new());
+        app.collector.push_note("THIRD", String::new());
+        let names: Vec<_> = app.messages().iter().map(|e| e.name).collect();
+        assert_eq!(names, ["SECOND", "THIRD"]);
+        assert_eq!(app.severity_counts()[0], 2);
+
+        app.isolate_name = Some("THIRD");
+        assert_eq!(app.messages().len(), 1);
+        assert_eq!(app.severity_counts()[0], 1);
+    }
+
+    #[test]
+    fn interval_query_needs_a_sender() {
+        let mut app = App::new();
+        app.handle_key(KeyCode::Char('g'), KeyModifiers::CONTROL);
+        assert_eq!(
+            app.collector.messages().back().unwrap().name,
+            "INTERVAL_QUERY_UNAVAILABLE"
+        );
+    }
+
+    #[test]
+    fn ctrl_d_filters_by_direction() {
+        let mut app = make_app_with_stream_entries(5);
+        app.handle_key(KeyCode::Char('d'), KeyModifiers::CONTROL);
+        assert_eq!(app.direction_filter, Some(Direction::Downlink));
+        assert_eq!(app.stream().len(), 5);
+        app.handle_key(KeyCode::Char('d'), KeyModifiers::CONTROL);
+        assert_eq!(app.direction_filter, Some(Direction::Uplink));
+        assert!(app.stream().is_empty());
+        assert_eq!(app.view(Tab::Stream).scroll.selected, 0);
+    }
+
+    #[test]
+    fn number_keys_select_tabs() {
+        let mut app = App::new();
+        app.handle_key(KeyCode::Char('3'), KeyModifiers::NONE);
+        assert_eq!(app.tab(), Tab::Stats);
+        app.handle_key(KeyCode::Char('1'), KeyModifiers::NONE);
+        assert_eq!(app.tab(), Tab::Stream);
+        app.handle_key(KeyCode::Char('9'), KeyModifiers::NONE);
+        assert_eq!(app.tab(), Tab::Stream);
+    }
 
-#[cfg(test)]
-mod tests {
-    use mavlink::{MavHeader, common::MavMessage};
+    #[test]
+    fn scroll_is_kept_per_tab() {
+        let mut app = make_app_with_stream_entries(5);
+        app.handle_key(KeyCode::Char('j'), KeyModifiers::NONE);
+        app.handle_key(KeyCode::Char('2'), KeyModifiers::NONE);
+        app.handle_key(KeyCode::Char('1'), KeyModifiers::NONE);
+        assert_eq!(app.view(Tab::Stream).scroll.selected, 1);
+    }
 
-    use super::*;
-    use crate::message::MavMsg;
+    #[test]
+    fn rates_are_human_readable() {
+        assert_eq!(format_rate(512.0), "512 B/s");
+        assert_eq!(format_rate(2500.0), "2.5 kB/s");
+    }
 
-    fn make_app_with_stream_entries(n: usize) -> App {
+    #[test]
+    fn typeahead_jumps_to_matching_row() {
         let mut app = App::new();
-        app.active_panel = Panel::Stream;
-        app.stream_vh = 10;
-        app.messages_vh = 10;
-        for i in 0..n {
+        app.select_tab(Tab::Stream as usize);
+        for (i, msg) in [
+            MavMessage::HEARTBEAT(Default::default()),
+            MavMessage::ATTITUDE(Default::default()),
+            MavMessage::VFR_HUD(Default::default()),
+        ]
+        .into_iter()
+        .enumerate()
+        {
             let header = MavHeader {
-                system_id: i as u8,
+                system_id: 1,
+                component_id: 1,
+                sequence: i as u8,
+            };
+            app.collector.push(MavMsg::new(header, msg, 0));
+        }
+        app.handle_key(KeyCode::Char('\''), KeyModifiers::NONE);
+        app.handle_key(KeyCode::Char('v'), KeyModifiers::NONE);
+        assert_eq!(app.view(Tab::Stream).scroll.selected, 2);
+        app.handle_key(KeyCode::Backspace, KeyModifiers::NONE);
+        app.handle_key(KeyCode::Char('a'), KeyModifiers::NONE);
+        assert_eq!(app.view(Tab::Stream).scroll.selected, 1);
+        // No match keeps the current selection.
+        app.handle_key(KeyCode::Char('x'), KeyModifiers::NONE);
+        assert_eq!(app.view(Tab::Stream).scroll.selected, 1);
+        // Keys go to the prompt until it is closed, so `q` doesn't quit.
+        assert!(!app.handle_key(KeyCode::Char('q'), KeyModifiers::NONE));
+        app.handle_key(KeyCode::Enter, KeyModifiers::NONE);
+        assert!(app.typeahead.is_none());
+        assert_eq!(app.stream().len(), 3);
+    }
+
+    #[test]
+    fn search_jumps_to_hits() {
+        let mut app = App::new();
+        app.select_tab(Tab::Stream as usize);
+        for msg in [
+            MavMessage::HEARTBEAT(Default::default()),
+            MavMessage::COMMAND_ACK(Default::default()),
+            MavMessage::ATTITUDE(Default::default()),
+        ] {
+            app.collector
+                .push(MavMsg::new(MavHeader::default(), msg, 0));
+        }
+        let search = |app: &mut App, query: &str| {
+            app.handle_key(KeyCode::Char('f'), KeyModifiers::CONTROL);
+            for c in query.chars() {
+                app.handle_key(KeyCode::Char(c), KeyModifiers::NONE);
+            }
+            app.handle_key(KeyCode::Enter, KeyModifiers::NONE);
+        };
+        search(&mut app, "command_ack");
+        assert_eq!(app.search.as_ref().unwrap().hits.len(), 1);
+        assert_eq!(app.tab(), Tab::Events);
+        assert_eq!(app.selected_name(), Some("COMMAND_ACK"));
+        // Keys go to the results until they are closed.
+        assert!(!app.handle_key(KeyCode::Char('q'), KeyModifiers::NONE));
+        app.handle_key(KeyCode::Esc, KeyModifiers::NONE);
+        assert!(app.search.is_none());
+
+        search(&mut app, "attitude");
+        assert_eq!(app.tab(), Tab::Stream);
+        assert_eq!(app.selected_name(), Some("ATTITUDE"));
+        assert_eq!(app.as_of, None);
+    }
+
+    #[test]
+    fn isolates_selected_system_and_message() {
+        let mut app = App::new();
+        app.select_tab(Tab::Stream as usize);
+        for (system_id, msg) in [
+            (1, MavMessage::HEARTBEAT(Default::default())),
+            (1, MavMessage::ATTITUDE(Default::default())),
+            (2, MavMessage::HEARTBEAT(Default::default())),
+            (2, MavMessage::ATTITUDE(Default::default())),
+        ] {
+            let header = MavHeader {
+                system_id,
+                component_id: 1,
+                sequence: 0,
+            };
+            app.collector.push(MavMsg::new(header, msg, 0));
+        }
+        let rows = |app: &App| {
+            app.stream()
+                .iter()
+                .map(|e| (e.sys_id, e.name))
+                .collect::<Vec<_>>()
+        };
+        app.view_mut(Tab::Stream).scroll.select(3, 10);
+        app.handle_key(KeyCode::Char('v'), KeyModifiers::NONE);
+        assert_eq!(rows(&app), [(2, "HEARTBEAT"), (2, "ATTITUDE")]);
+        assert_eq!(app.selected_row().unwrap().2, "ATTITUDE");
+        // Stacks with the system.
+        app.handle_key(KeyCode::Char('m'), KeyModifiers::NONE);
+        assert_eq!(rows(&app), [(2, "ATTITUDE")]);
+        app.handle_key(KeyCode::Char('v'), KeyModifiers::NONE);
+        assert_eq!(rows(&app), [(1, "ATTITUDE"), (2, "ATTITUDE")]);
+        assert_eq!(app.selected_row().unwrap().0, 2);
+        app.handle_key(KeyCode::Char('m'), KeyModifiers::NONE);
+        assert_eq!(rows(&app).len(), 4);
+    }
+
+    #[test]
+    fn space_pauses_and_resumes_the_panels() {
+        let mut app = make_app_with_stream_entries(1);
+        let heartbeat = |system_id| {
+            let header = MavHeader {
+                system_id,
+                component_id: 1,
+                sequence: 0,
+            };
+            let msg = MavMessage::HEARTBEAT(mavlink::common::HEARTBEAT_DATA::default());
+            Incoming::Message(MavMsg::new(header, msg, 0))
+        };
+        app.handle_key(KeyCode::Char(' '), KeyModifiers::NONE);
+        app.receive(Utc::now(), heartbeat(7));
+        app.receive(Utc::now(), heartbeat(8));
+        assert_eq!(app.collector.stream().len(), 1);
+        assert!(app.session_log.byte_len() > 0);
+        assert!(app.render_to_string(120, 20).contains("paused (2 held)"));
+
+        app.handle_key(KeyCode::Char(' '), KeyModifiers::NONE);
+        assert_eq!(app.collector.stream().len(), 3);
+        assert!(!app.render_to_string(120, 20).contains("paused"));
+    }
+
+    #[test]
+    fn pause_holds_no_more_than_the_limits() {
+        let mut app = make_app_with_stream_entries(1).with_limits(Limits {
+            max_events: Some(2),
+            ..Limits::default()
+        });
+        app.handle_key(KeyCode::Char(' '), KeyModifiers::NONE);
+        for system_id in 7..10 {
+            let header = MavHeader {
+                system_id,
                 component_id: 1,
                 sequence: 0,
             };
             let msg = MavMessage::HEARTBEAT(mavlink::common::HEARTBEAT_DATA::default());
-            app.collector.push(MavMsg::new(header, msg));
+            app.receive(Utc::now(), Incoming::Message(MavMsg::new(header, msg, 0)));
         }
-        app
+        assert!(
+            app.render_to_string(120, 20)
+                .contains("paused (2 held, 1 dropped)")
+        );
+
+        app.handle_key(KeyCode::Char(' '), KeyModifiers::NONE);
+        let systems: Vec<_> = app.collector.stream().iter().map(|e| e.sys_id).collect();
+        assert!(!systems.contains(&7));
+        assert!(systems.contains(&9));
     }
 
-    // --- handle_key tests ---
+    #[test]
+    fn parameter_tab_searches_and_clears() {
+        use mavlink::common::{MavParamType, PARAM_VALUE_DATA};
+
+        let mut app = App::new();
+        for (index, name) in ["ARMING_CHECK", "BATT_CAPACITY", "BATT_MONITOR"]
+            .into_iter()
+            .enumerate()
+        {
+            let mut id = [0u8; 16];
+            id[..name.len()].copy_from_slice(name.as_bytes());
+            let header = MavHeader {
+                system_id: 1,
+                component_id: 1,
+                sequence: 0,
+            };
+            let msg = MavMessage::PARAM_VALUE(PARAM_VALUE_DATA {
+                param_value: 5200.0,
+                param_count: 900,
+                param_index: index as u16,
+                param_id: id.into(),
+                param_type: MavParamType::MAV_PARAM_TYPE_INT32,
+            });
+            app.collector.push(MavMsg::new(header, msg, 0));
+        }
+        app.handle_key(KeyCode::Char('p'), KeyModifiers::NONE);
+        let screen = app.render_to_string(120, 20);
+        assert!(screen.contains("Parameters [1:1] 3/900 received"));
+        assert!(screen.contains("BATT_CAPACITY              5200 INT32"));
+
+        app.handle_key(KeyCode::Char('/'), KeyModifiers::NONE);
+        for c in "batt".chars() {
+            app.handle_key(KeyCode::Char(c), KeyModifiers::NONE);
+        }
+        app.handle_key(KeyCode::Enter, KeyModifiers::NONE);
+        let screen = app.render_to_string(120, 20);
+        assert!(screen.contains("2 matching \"batt\""));
+        assert!(!screen.contains("ARMING_CHECK"));
+
+        // Esc clears the search rather than quitting.
+        assert!(!app.handle_key(KeyCode::Esc, KeyModifiers::NONE));
+        assert!(app.render_to_string(120, 20).contains("ARMING_CHECK"));
+
+        app.handle_key(KeyCode::Char('1'), KeyModifiers::NONE);
+        assert!(!app.render_to_string(120, 20).contains("Parameters ["));
+        app.handle_key(KeyCode::Char('4'), KeyModifiers::NONE);
+        assert_eq!(app.tab(), Tab::Params);
+    }
 
     #[test]
-    fn quit_on_q() {
+    fn map_tab_lists_vehicle_positions() {
+        use mavlink::common::GLOBAL_POSITION_INT_DATA;
+
         let mut app = App::new();
-        assert!(app.handle_key(KeyCode::Char('q'), KeyModifiers::NONE));
+        let header = MavHeader {
+            system_id: 1,
+            component_id: 1,
+            sequence: 0,
+        };
+        let msg = MavMessage::GLOBAL_POSITION_INT(GLOBAL_POSITION_INT_DATA {
+            lat: 473_977_420,
+            lon: 85_455_940,
+            alt: 488_000,
+            ..Default::default()
+        });
+        app.handle_key(KeyCode::Char('6'), KeyModifiers::NONE);
+        assert_eq!(app.tab(), Tab::Map);
+        assert!(
+            app.render_to_string(140, 30)
+                .contains("No GLOBAL_POSITION_INT")
+        );
+        app.collector.push(MavMsg::new(header, msg, 0));
+        let screen = app.render_to_string(140, 30);
+        assert!(screen.contains("47.397742     8.545594   488.0m"));
+        assert!(screen.contains("sys 1"));
     }
 
     #[test]
-    fn quit_on_esc() {
+    fn console_joins_status_text_chunks() {
+        use mavlink::common::{MavSeverity, STATUSTEXT_DATA};
+
         let mut app = App::new();
-        assert!(app.handle_key(KeyCode::Esc, KeyModifiers::NONE));
+        let first = "PreArm: Battery 1 voltage 10.2 below the minimum o";
+        for (chunk_seq, text) in [(0, first), (1, "f 10.5")] {
+            let mut bytes = [0u8; 50];
+            bytes[..text.len()].copy_from_slice(text.as_bytes());
+            let msg = MavMessage::STATUSTEXT(STATUSTEXT_DATA {
+                severity: MavSeverity::MAV_SEVERITY_CRITICAL,
+                text: bytes.into(),
+                id: 1,
+                chunk_seq,
+            });
+            app.collector
+                .push(MavMsg::new(MavHeader::default(), msg, 0));
+        }
+        app.handle_key(KeyCode::Char('c'), KeyModifiers::NONE);
+        assert_eq!(app.tab(), Tab::Console);
+        let screen = app.render_to_string(140, 20);
+        assert!(screen.contains("Status text [1]"));
+        assert!(screen.contains(&format!("CRITICAL  {first}f 10.5")));
+
+        app.handle_key(KeyCode::Char('2'), KeyModifiers::NONE);
+        assert!(!app.render_to_string(140, 20).contains("Status text"));
+        app.handle_key(KeyCode::Char('5'), KeyModifiers::NONE);
+        assert_eq!(app.tab(), Tab::Console);
     }
 
     #[test]
-    fn j_moves_down() {
+    fn w_plots_a_field_of_the_selected_row() {
+        let mut app = App::new();
+        app.select_tab(Tab::Stream as usize);
+        let attitude = |roll| {
+            let header = MavHeader {
+                system_id: 1,
+                component_id: 1,
+                sequence: 0,
+            };
+            let msg = MavMessage::ATTITUDE(mavlink::common::ATTITUDE_DATA {
+                roll,
+                ..Default::default()
+            });
+            Incoming::Message(MavMsg::new(header, msg, 0))
+        };
+        app.receive(Utc::now(), attitude(0.5));
+
+        app.handle_key(KeyCode::Char('w'), KeyModifiers::NONE);
+        assert!(
+            app.render_to_string(120, 30)
+                .contains("plot ATTITUDE.time_boot_ms")
+        );
+        app.handle_key(KeyCode::Char('x'), KeyModifiers::NONE);
+        app.handle_key(KeyCode::Enter, KeyModifiers::NONE);
+        assert!(app.render_to_string(120, 30).contains("no numeric field"));
+        app.handle_key(KeyCode::Tab, KeyModifiers::NONE);
+        app.handle_key(KeyCode::Tab, KeyModifiers::NONE);
+        app.handle_key(KeyCode::Enter, KeyModifiers::NONE);
+
+        app.receive(Utc::now() + TimeDelta::milliseconds(100), attitude(0.25));
+        let plot = app.plot.as_ref().unwrap();
+        assert_eq!(plot.samples().len(), 2);
+        assert!(
+            app.render_to_string(120, 30)
+                .contains("ATTITUDE.roll [1:1] = 0.25")
+        );
+
+        app.handle_key(KeyCode::Char('W'), KeyModifiers::NONE);
+        assert!(app.plot.is_none());
+    }
+
+    #[test]
+    fn filter_bar_filters_both_panels() {
+        let mut app = App::new();
+        for (system_id, msg) in [
+            (1, MavMessage::HEARTBEAT(Default::default())),
+            (
+                1,
+                MavMessage::ATTITUDE(mavlink::common::ATTITUDE_DATA {
+                    roll: 0.6,
+                    ..Default::default()
+                }),
+            ),
+            (2, MavMessage::ATTITUDE(Default::default())),
+            (1, MavMessage::STATUSTEXT(Default::default())),
+            (2, MavMessage::STATUSTEXT(Default::default())),
+        ] {
+            let header = MavHeader {
+                system_id,
+                component_id: 1,
+                sequence: 0,
+            };
+            app.collector.push(MavMsg::new(header, msg, 0));
+        }
+        let type_text = |app: &mut App, text: &str| {
+            for c in text.chars() {
+                app.handle_key(KeyCode::Char(c), KeyModifiers::NONE);
+            }
+        };
+        app.handle_key(KeyCode::Char('f'), KeyModifiers::NONE);
+        type_text(&mut app, "sysid==1 && roll > 0.5");
+        let names: Vec<_> = app.stream().iter().map(|e| (e.sys_id, e.name)).collect();
+        assert_eq!(names, [(1, "ATTITUDE")]);
+        assert!(app.messages().is_empty());
+        app.handle_key(KeyCode::Enter, KeyModifiers::NONE);
+        assert!(app.filter_prompt.is_none());
+        assert!(
+            app.panel_label("Stream")
+                .ends_with("[sysid==1 && roll > 0.5]")
+        );
+
+        // A broken edit keeps the last good filter and blocks Enter.
+        app.handle_key(KeyCode::Char('f'), KeyModifiers::NONE);
+        type_text(&mut app, " &&");
+        assert!(app.filter_prompt.as_ref().unwrap().error.is_some());
+        app.handle_key(KeyCode::Enter, KeyModifiers::NONE);
+        assert!(app.filter_prompt.is_some());
+        assert_eq!(app.stream().len(), 1);
+        for _ in 0..25 {
+            app.handle_key(KeyCode::Backspace, KeyModifiers::NONE);
+        }
+        type_text(&mut app, "sys == 2");
+        assert_eq!(app.messages().len(), 1);
+        // Esc goes back to the filter from before the edit.
+        app.handle_key(KeyCode::Esc, KeyModifiers::NONE);
+        assert_eq!(app.stream().len(), 1);
+        assert!(app.messages().is_empty());
+    }
+
+    #[test]
+    fn slash_finds_and_highlights_in_the_active_panel() {
+        let mut app = App::new();
+        app.select_tab(Tab::Stream as usize);
+        for view in &mut app.tabs {
+            view.vh = 10;
+        }
+        for msg in [
+            MavMessage::HEARTBEAT(Default::default()),
+            MavMessage::GPS_RAW_INT(Default::default()),
+            MavMessage::ATTITUDE(Default::default()),
+            MavMessage::GPS2_RAW(Default::default()),
+        ] {
+            app.collector
+                .push(MavMsg::new(MavHeader::default(), msg, 0));
+        }
+        let selected = |app: &App| app.stream()[app.view(Tab::Stream).scroll.selected].name;
+        app.handle_key(KeyCode::Char('/'), KeyModifiers::NONE);
+        for c in "gps".chars() {
+            app.handle_key(KeyCode::Char(c), KeyModifiers::NONE);
+        }
+        assert_eq!(selected(&app), "GPS_RAW_INT");
+        app.handle_key(KeyCode::Enter, KeyModifiers::NONE);
+        let buffer = app.render(120, 30);
+        assert!(
+            buffer.content().iter().any(|cell| cell.bg == Color::Yellow),
+            "matches highlighted"
+        );
+
+        app.handle_key(KeyCode::Char('n'), KeyModifiers::NONE);
+        assert_eq!(selected(&app), "GPS2_RAW");
+        // Wraps round.
+        app.handle_key(KeyCode::Char('n'), KeyModifiers::NONE);
+        assert_eq!(selected(&app), "GPS_RAW_INT");
+        app.handle_key(KeyCode::Char('N'), KeyModifiers::NONE);
+        assert_eq!(selected(&app), "GPS2_RAW");
+        // Esc clears the search before it would quit.
+        assert!(!app.handle_key(KeyCode::Esc, KeyModifiers::NONE));
+        assert!(app.find.is_none());
+
+        // Esc while typing goes back to where the search started.
+        app.handle_key(KeyCode::Char('g'), KeyModifiers::NONE);
+        app.handle_key(KeyCode::Char('/'), KeyModifiers::NONE);
+        for c in "att".chars() {
+            app.handle_key(KeyCode::Char(c), KeyModifiers::NONE);
+        }
+        assert_eq!(selected(&app), "ATTITUDE");
+        app.handle_key(KeyCode::Esc, KeyModifiers::NONE);
+        assert_eq!(selected(&app), "HEARTBEAT");
+    }
+
+    #[test]
+    fn rows_show_their_link_with_several() {
+        let links = vec![
+            "udpin:0.0.0.0:14445".to_string(),
+            "serial:/dev/ttyUSB0:57600".to_string(),
+        ];
+        let mut app = App::new().with_links(links, DedupMode::Off);
+        app.select_tab(Tab::Stream as usize);
+        let header = MavHeader {
+            system_id: 1,
+            component_id: 1,
+            sequence: 0,
+        };
+        let msg = MavMessage::HEARTBEAT(mavlink::common::HEARTBEAT_DATA::default());
+        app.collector.push(MavMsg::new(header, msg, 1));
+        assert!(app.render_to_string(100, 20).contains("[  1:  1] L1 "));
+
+        app.links.pop();
+        assert!(!app.render_to_string(100, 20).contains("] L1"));
+    }
+
+    #[test]
+    fn rows_show_their_wire_version_once_v1_arrives() {
+        let mut app = App::new();
+        app.select_tab(Tab::Stream as usize);
+        let header = |system_id| MavHeader {
+            system_id,
+            component_id: 1,
+            sequence: 0,
+        };
+        let heartbeat = || MavMessage::HEARTBEAT(mavlink::common::HEARTBEAT_DATA::default());
+        app.collector.push(MavMsg::new(header(1), heartbeat(), 0));
+        assert!(!app.render_to_string(100, 20).contains("] v2 "));
+
+        let mut v1 = MavMsg::new(header(2), heartbeat(), 0);
+        v1.len = v1.payload.len() + 8;
+        app.collector.push(v1);
+        let screen = app.render_to_string(100, 20);
+        assert!(screen.contains("[  1:  1] v2 "), "{screen}");
+        assert!(screen.contains("[  2:  1] v1 "), "{screen}");
+
+        app.select_tab(Tab::Stats as usize);
+        assert!(app.render_to_string(120, 40).contains("MAVLink 1"));
+    }
+
+    #[test]
+    fn enter_focuses_detail_scrolling() {
         let mut app = make_app_with_stream_entries(5);
+        app.detail_vh = 4;
+        app.detail_height = 10;
+        app.handle_key(KeyCode::Enter, KeyModifiers::NONE);
+        assert!(app.detail_focused);
+        app.handle_key(KeyCode::PageDown, KeyModifiers::NONE);
+        assert_eq!(app.detail_scroll.selected, 4);
+        app.handle_key(KeyCode::Char('G'), KeyModifiers::NONE);
+        assert_eq!(app.detail_scroll.selected, 6);
+        // The list selection stays put while the detail pane has focus.
+        assert_eq!(app.view(Tab::Stream).scroll.selected, 0);
+        app.handle_key(KeyCode::Enter, KeyModifiers::NONE);
         app.handle_key(KeyCode::Char('j'), KeyModifiers::NONE);
-        assert_eq!(app.stream_scroll.selected, 1);
+        assert_eq!(app.view(Tab::Stream).scroll.selected, 1);
     }
 
     #[test]
-    fn k_moves_up() {
-        let mut app = make_app_with_stream_entries(5);
+    fn wrapped_height_counts_wrapped_rows() {
+        let lines = [Line::from("a".repeat(25)), Line::from("")];
+        assert_eq!(wrapped_height(&lines, 10), 4);
+    }
+
+    #[test]
+    fn ctrl_w_cycles_overflow_modes() {
+        let mut app = make_app_with_stream_entries(1);
+        assert_eq!(app.detail_overflow, Overflow::Wrap);
+        app.handle_key(KeyCode::Char('w'), KeyModifiers::CONTROL);
+        assert_eq!(app.detail_overflow, Overflow::Truncate);
+        app.handle_key(KeyCode::Char('w'), KeyModifiers::CONTROL);
+        assert_eq!(app.detail_overflow, Overflow::Scroll);
+        app.detail_overhang = 6;
+        app.handle_key(KeyCode::Enter, KeyModifiers::NONE);
+        app.handle_key(KeyCode::Right, KeyModifiers::NONE);
+        app.handle_key(KeyCode::Right, KeyModifiers::NONE);
+        assert_eq!(app.detail_column, 6);
+        assert_eq!(app.tab(), Tab::Stream);
+        app.handle_key(KeyCode::Char('w'), KeyModifiers::CONTROL);
+        assert_eq!(app.detail_overflow, Overflow::Wrap);
+        assert_eq!(app.detail_column, 0);
+    }
+
+    #[test]
+    fn truncate_line_adds_ellipsis() {
+        let line = Line::from(vec![Span::raw("text: "), Span::raw("hello world")]);
+        let cut = truncate_line(line, 10);
+        assert_eq!(cut.width(), 10);
+        assert_eq!(cut.to_string(), "text: hel\u{2026}");
+        assert_eq!(truncate_line(Line::from("short"), 10).to_string(), "short");
+    }
+
+    #[test]
+    fn hide_default_fields_keeps_set_values() {
+        let mut fields = vec![
+            ("command", "MAV_CMD_COMPONENT_ARM_DISARM"),
+            ("result", "MAV_RESULT_ACCEPTED"),
+        ];
+        assert_eq!(hide_default_fields("COMMAND_ACK", &mut fields), 1);
+        assert_eq!(fields, vec![("command", "MAV_CMD_COMPONENT_ARM_DISARM")]);
+
+        let mut fields = vec![("from_link", "0")];
+        assert_eq!(hide_default_fields("LINK_MIGRATION", &mut fields), 0);
+        assert_eq!(fields.len(), 1);
+    }
+
+    #[test]
+    fn byte_map_labels_fields_and_truncation() {
+        let layout = [
+            FieldSpan {
+                offset: 0,
+                len: 2,
+                field: "command".to_string(),
+            },
+            FieldSpan {
+                offset: 2,
+                len: 1,
+                field: "result".to_string(),
+            },
+        ];
+        let lines: Vec<String> = byte_map_lines("COMMAND_ACK", &[0x90, 0x01], &layout)
+            .iter()
+            .map(Line::to_string)
+            .collect();
+        assert_eq!(lines[2], "0000  90 01 00");
+        assert_eq!(lines[5], "     0   2  command");
+        assert_eq!(lines[6], "     2   1  result");
+        assert_eq!(lines[7], "(1 trailing zero bytes truncated on the wire)");
+    }
+
+    #[test]
+    fn footer_follows_the_bindings() {
+        let default = footer_line(&KeyMap::default()).to_string();
+        assert!(
+            default.starts_with(" q/Esc Quit  1-9/Tab/\u{2192}/l/Shift+Tab/\u{2190}/h Switch Tab")
+        );
+        assert!(default.contains("  Ctrl+d Direction  "));
+
+        let config =
+            crate::config::parse("[keys]\nseverity = [\"ctrl+d\"]\nup = [\"up\"]").unwrap();
+        let mut app = App::new().with_keymap(config.keymap().unwrap());
+        let rebound = footer_line(&app.keys).to_string();
+        assert!(rebound.contains("  Ctrl+d Severity  "));
+        assert!(rebound.contains("  \u{2191}/\u{2193}/j Select  "));
+        // The direction filter lost its only key, so has no hint.
+        assert!(!rebound.contains("Direction"));
+        app.handle_key(KeyCode::Char('d'), KeyModifiers::CONTROL);
+        assert_eq!(app.direction_filter, None);
+        assert_eq!(app.severity_filter, Some(Severity::Warning));
+        app.handle_key(KeyCode::Char('s'), KeyModifiers::NONE);
+        assert_eq!(app.severity_filter, Some(Severity::Warning));
+    }
+
+    #[test]
+    fn frame_dump_marks_regions() {
+        // MAVLink 1 HEARTBEAT-sized header, one payload byte, checksum.
+        let frame = [0xfe, 0x01, 0x00, 0x01, 0x01, 0x00, 0x41, 0x12, 0x34];
+        let lines = frame_dump_lines("HEARTBEAT", &frame);
+        let text: Vec<String> = lines.iter().map(Line::to_string).collect();
+        assert_eq!(
+            text[2],
+            format!(
+                "0000  fe 01 00 01 01 00 41 12 34{}......A.4",
+                " ".repeat(23)
+            )
+        );
+        assert_eq!(text[4], "header 6  payload 1  crc 2  (9 bytes)");
+        // The payload byte is coloured as payload in both columns.
+        let payload = Style::default().fg(region_color(Region::Payload));
+        assert_eq!(lines[2].spans[7].style, payload);
+        assert_eq!(lines[2].spans[17].style, payload);
+
+        assert!(
+            frame_dump_lines("HEARTBEAT", &[])[2]
+                .to_string()
+                .contains("not kept")
+        );
+    }
+
+    #[test]
+    fn window_title_summarises_state() {
+        let links = vec!["udpin:0.0.0.0:14445".to_string()];
+        assert_eq!(
+            window_title(&links, 1, 0),
+            "mavsnark: udpin:0.0.0.0:14445 | 1 vehicle"
+        );
+        let links = vec![
+            "udpin:0.0.0.0:14445".to_string(),
+            "serial:/dev/ttyUSB0".into(),
+        ];
+        assert_eq!(
+            window_title(&links, 2, 3),
+            "mavsnark: udpin:0.0.0.0:14445 (+1) | 2 vehicles | 3 alerts"
+        );
+    }
+
+    #[test]
+    fn viewing_events_marks_alerts_read() {
+        let mut app = make_app_with_stream_entries(0);
+        app.collector.push(MavMsg::new(
+            MavHeader {
+                system_id: 255,
+                component_id: 190,
+                sequence: 0,
+            },
+            MavMessage::COMMAND_LONG(mavlink::common::COMMAND_LONG_DATA {
+                target_system: 1,
+                ..Default::default()
+            }),
+            0,
+        ));
+        app.collector
+            .tick(Utc::now() + chrono::TimeDelta::seconds(10));
+        assert_eq!(app.unread_alerts(), 1);
+        app.select_tab(Tab::Events as usize);
+        assert_eq!(app.unread_alerts(), 0);
+    }
+
+    #[test]
+    fn auto_layout_stacks_on_narrow_terminals() {
+        assert!(LayoutMode::Auto.is_vertical(80));
+        assert!(!LayoutMode::Auto.is_vertical(160));
+        assert!(LayoutMode::Vertical.is_vertical(160));
+        assert!(!LayoutMode::Horizontal.is_vertical(80));
+    }
+
+    #[test]
+    fn ui_state_round_trips_through_app() {
+        let mut app = make_app_with_stream_entries(0);
+        app.handle_key(KeyCode::Char('d'), KeyModifiers::CONTROL);
+        app.handle_key(KeyCode::Char('w'), KeyModifiers::CONTROL);
+        app.collector
+            .set_stream_types(["HEARTBEAT", "ATTITUDE", "NOT_A_MESSAGE"]);
+        let state = app.ui_state();
+        assert_eq!(
+            state.stream_types,
+            Some(vec!["ATTITUDE".to_string(), "HEARTBEAT".to_string()])
+        );
+
+        let restored = App::new().with_state(state);
+        assert_eq!(restored.tab(), Tab::Stream);
+        assert_eq!(restored.direction_filter, Some(Direction::Downlink));
+        assert_eq!(restored.detail_overflow, Overflow::Truncate);
+        assert_eq!(
+            restored.collector.stream_types(),
+            vec!["ATTITUDE", "HEARTBEAT"]
+        );
+    }
+
+    #[test]
+    fn profile_decodes_heartbeat_mode() {
+        let detail = Detail {
+            name: "HEARTBEAT",
+            sys_id: 1,
+            comp_id: 1,
+            sys_color: Color::Red,
+            comp_color: Color::Red,
+            link: 0,
+            fields: vec![("custom_mode", "6"), ("mavtype", "MAV_TYPE_HEXAROTOR")],
+            hidden: 0,
+            payload: &[],
+            frame: &[],
+            arrivals: None,
+        };
+        assert_eq!(
+            flight_mode(ModeScheme::Ardupilot, &detail).as_deref(),
+            Some("RTL")
+        );
+        let lines = message_lines(detail, &[], None, Some("RTL".to_string()));
+        assert!(lines.iter().any(|l| l.to_string() == "mode    RTL"));
+    }
+
+    #[test]
+    fn cli_categories_override_defaults() {
+        let strings = |names: &[&str]| names.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let app = App::new().with_categories(&[], &strings(&["ATTITUDE"]));
+        let types = app.collector.stream_types();
+        assert!(types.contains(&"HEARTBEAT"));
+        assert!(!types.contains(&"ATTITUDE"));
+
+        let app = App::new().with_categories(
+            &strings(&["HEARTBEAT", "ATTITUDE", "COMMAND_LONG"]),
+            &strings(&["ATTITUDE"]),
+        );
+        assert_eq!(
+            app.collector.stream_types(),
+            vec!["COMMAND_LONG", "HEARTBEAT"]
+        );
+    }
+
+    #[test]
+    fn session_restores_data_and_selection() {
+        let mut app = make_app_with_stream_entries(0);
+        for i in 0..4 {
+            let header = MavHeader {
+                system_id: i,
+                component_id: 1,
+                sequence: 0,
+            };
+            let msg = MavMessage::HEARTBEAT(mavlink::common::HEARTBEAT_DATA::default());
+            app.receive(Utc::now(), Incoming::Message(MavMsg::new(header, msg, 0)));
+        }
         app.handle_key(KeyCode::Char('j'), KeyModifiers::NONE);
         app.handle_key(KeyCode::Char('j'), KeyModifiers::NONE);
-        app.handle_key(KeyCode::Char('k'), KeyModifiers::NONE);
-        assert_eq!(app.stream_scroll.selected, 1);
+        let meta = app.session_meta();
+
+        let mut input = app.session_log.as_bytes();
+        let mut records = Vec::new();
+        while let Some(record) = session::read_record(&mut input).unwrap() {
+            records.push(record);
+        }
+        let restored = App::new().with_session(Session { meta, records });
+        assert!(restored.offline);
+        assert_eq!(restored.tab(), Tab::Stream);
+        assert_eq!(restored.stream().len(), 4);
+        assert_eq!(restored.view(Tab::Stream).scroll.selected, 2);
     }
 
     #[test]
-    fn tab_toggles_panel() {
+    fn selection_follows_dropped_events() {
+        let mut app = App::new().with_limits(Limits {
+            max_events: Some(5),
+            ..Limits::default()
+        });
+        let command = |sys| {
+            let header = MavHeader {
+                system_id: sys,
+                component_id: 1,
+                sequence: 0,
+            };
+            let msg = MavMessage::COMMAND_LONG(mavlink::common::COMMAND_LONG_DATA::default());
+            Incoming::Message(MavMsg::new(header, msg, 0))
+        };
+        for sys in 0..5 {
+            app.receive(Utc::now(), command(sys));
+        }
+        let view = app.view_mut(Tab::Events);
+        view.scroll.selected = 3;
+        view.scroll.auto_scroll = false;
+        app.receive(Utc::now(), command(5));
+        app.receive(Utc::now(), command(6));
+        let selected = app.view(Tab::Events).scroll.selected;
+        assert_eq!(selected, 1);
+        assert_eq!(app.messages()[selected].sys_id, 3);
+
+        // Dropped rows the filter hid were never above the selection.
+        app.filter = Some("sys >= 4".parse().unwrap());
+        app.view_mut(Tab::Events).scroll.selected = 2;
+        for sys in 7..10 {
+            app.receive(Utc::now(), command(sys));
+            let selected = app.view(Tab::Events).scroll.selected;
+            assert_eq!(app.messages()[selected].sys_id, 6);
+        }
+        assert_eq!(app.view(Tab::Events).scroll.selected, 1);
+    }
+
+    #[test]
+    fn time_travel_shows_earlier_values() {
         let mut app = App::new();
-        assert_eq!(app.active_panel, Panel::Messages);
-        app.handle_key(KeyCode::Tab, KeyModifiers::NONE);
-        assert_eq!(app.active_panel, Panel::Stream);
-        app.handle_key(KeyCode::Tab, KeyModifiers::NONE);
-        assert_eq!(app.active_panel, Panel::Messages);
+        app.select_tab(Tab::Stream as usize);
+        let t0 = Utc::now() - TimeDelta::seconds(10);
+        let attitude = |sys, roll| {
+            let header = MavHeader {
+                system_id: sys,
+                component_id: 1,
+                sequence: 0,
+            };
+            let msg = MavMessage::ATTITUDE(mavlink::common::ATTITUDE_DATA {
+                roll,
+                ..Default::default()
+            });
+            MavMsg {
+                timestamp: t0 + TimeDelta::seconds(roll as i64),
+                ..MavMsg::new(header, msg, 0)
+            }
+        };
+        for roll in 0..4 {
+            app.collector.push(attitude(1, roll as f32));
+        }
+        // A second vehicle that only appears later.
+        app.collector.push(attitude(2, 3.0));
+        // Offline, so the newest data rather than the clock ends the span.
+        app.offline = true;
+
+        app.as_of = Some(t0 + TimeDelta::milliseconds(1500));
+        let stream = app.stream();
+        assert_eq!(stream.len(), 1);
+        let line = stream[0]
+            .to_line(None, &Highlights::default(), app.as_of)
+            .to_string();
+        assert!(line.contains("roll: 1.0"), "{line}");
+
+        // Stepping forward past the newest data goes back to live.
+        app.handle_key(KeyCode::Char('}'), KeyModifiers::NONE);
+        assert_eq!(app.as_of, None);
+        assert_eq!(app.stream().len(), 2);
+
+        app.handle_key(KeyCode::Char('{'), KeyModifiers::NONE);
+        assert_eq!(app.as_of, Some(t0));
     }
 
     #[test]
-    fn g_selects_top() {
-        let mut app = make_app_with_stream_entries(5);
-        app.handle_key(KeyCode::Char('j'), KeyModifiers::NONE);
-        app.handle_key(KeyCode::Char('j'), KeyModifiers::NONE);
-        app.handle_key(KeyCode::Char('g'), KeyModifiers::NONE);
-        assert_eq!(app.stream_scroll.selected, 0);
+    fn f12_toggles_the_performance_panel() {
+        let mut app = make_app_with_stream_entries(3);
+        assert!(!app.render_to_string(100, 30).contains("Performance"));
+        app.handle_key(KeyCode::F(12), KeyModifiers::NONE);
+        let screen = app.render_to_string(100, 30);
+        assert!(screen.contains("Performance"));
+        assert!(screen.contains("queue        0 / 256"));
+        assert!(screen.contains("3 rows"));
+    }
+
+    fn t0() -> DateTime<Utc> {
+        DateTime::from_timestamp(1_700_000_000, 0).unwrap()
+    }
+
+    fn from(system_id: u8, msg: MavMessage, secs: i64) -> Incoming {
+        let header = MavHeader {
+            system_id,
+            component_id: 1,
+            sequence: 0,
+        };
+        Incoming::Message(MavMsg {
+            timestamp: t0() + TimeDelta::seconds(secs),
+            ..MavMsg::new(header, msg, 0)
+        })
+    }
+
+    /// Two vehicles heartbeating for three seconds, and one status text.
+    fn two_vehicles(stream_types: &[&str]) -> App {
+        let stream: Vec<String> = stream_types.iter().map(|s| s.to_string()).collect();
+        let mut app = App::new()
+            .with_categories(&stream, &[])
+            .with_clock(t0() + TimeDelta::seconds(3));
+        for secs in 0..3 {
+            let heartbeat = MavMessage::HEARTBEAT(mavlink::common::HEARTBEAT_DATA::default());
+            app.receive(t0(), from(1, heartbeat.clone(), secs));
+            app.receive(t0(), from(2, heartbeat, secs));
+        }
+        let text = MavMessage::STATUSTEXT(mavlink::common::STATUSTEXT_DATA {
+            text: "Preflight fail".into(),
+            ..Default::default()
+        });
+        app.receive(t0(), from(1, text, 2));
+        app
+    }
+
+    fn panel_rows(app: &mut App, title: &str) -> Vec<String> {
+        let buffer = app.render(120, 20);
+        let text = panel_text(&buffer, title).expect("panel not drawn");
+        text.lines()
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect()
     }
 
     #[test]
-    fn big_g_selects_bottom() {
-        let mut app = make_app_with_stream_entries(5);
-        app.handle_key(KeyCode::Char('G'), KeyModifiers::NONE);
-        assert_eq!(app.stream_scroll.selected, 4);
+    fn stream_groups_repeats_into_one_row_per_source() {
+        let mut app = two_vehicles(&[]);
+        app.handle_key(KeyCode::Char('1'), KeyModifiers::NONE);
+        let rows = panel_rows(&mut app, "Stream");
+        assert_eq!(rows.len(), 2);
+        assert!(rows[0].starts_with("[  1:  1] ↓     1.0s HEARTBEAT"));
+        assert!(rows[1].starts_with("[  2:  1] ↓     1.0s HEARTBEAT"));
+        // Same input, same clock: the same screen.
+        assert_eq!(app.render_to_string(120, 20), app.render_to_string(120, 20));
+    }
+
+    #[test]
+    fn stream_types_decide_where_messages_are_listed() {
+        let mut app = two_vehicles(&["STATUSTEXT"]);
+        app.handle_key(KeyCode::Char('1'), KeyModifiers::NONE);
+        let stream = panel_rows(&mut app, "Stream");
+        assert_eq!(stream.len(), 1);
+        assert!(stream[0].contains("STATUSTEXT"));
+        app.handle_key(KeyCode::Char('2'), KeyModifiers::NONE);
+        let events = panel_rows(&mut app, "Events");
+        assert_eq!(events.len(), 6);
+        assert!(events.iter().all(|row| row.contains("HEARTBEAT")));
+    }
+
+    #[test]
+    fn direction_filter_empties_the_list() {
+        let mut app = two_vehicles(&[]);
+        app.handle_key(KeyCode::Char('1'), KeyModifiers::NONE);
+        app.handle_key(KeyCode::Char('d'), KeyModifiers::CONTROL);
+        assert_eq!(panel_rows(&mut app, "Stream ↓ downlink").len(), 2);
+        app.handle_key(KeyCode::Char('d'), KeyModifiers::CONTROL);
+        assert!(panel_rows(&mut app, "Stream ↑ uplink").is_empty());
     }
 }
@@ -0,0 +1,238 @@
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::PathBuf,
+    str::FromStr,
+};
+
+use chrono::{DateTime, Local, TimeDelta, Utc};
+
+use crate::{
+    compress::{self, Output},
+    message::Incoming,
+    session::{self, SessionMeta},
+};
+
+/// When `--autosave` starts a new file.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rotate {
+    /// Once the file reaches this many bytes.
+    Size(u64),
+    /// Once the file covers this much time.
+    Time(TimeDelta),
+}
+
+/// Parses a byte size such as `512KB`, `100MB` or `2GB`.
+pub fn parse_size(s: &str) -> Result<u64, String> {
+    match s.parse::<Rotate>()? {
+        Rotate::Size(bytes) => Ok(bytes),
+        Rotate::Time(_) => Err(format!("{s:?}: expected a size such as 100MB")),
+    }
+}
+
+/// Parses a duration such as `30s`, `15m` or `1h`.
+pub fn parse_duration(s: &str) -> Result<TimeDelta, String> {
+    match s.parse::<Rotate>()? {
+        Rotate::Time(span) => Ok(span),
+        Rotate::Size(_) => Err(format!("{s:?}: expected a duration such as 30s")),
+    }
+}
+
+impl FromStr for Rotate {
+    type Err = String;
+
+    /// Parses `100MB`, `512KB`, `2GB`, `30s`, `15m` or `1h`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let split = s
+            .find(|c: char| !c.is_ascii_digit())
+            .ok_or_else(|| format!("{s:?}: missing unit (e.g. 100MB or 1h)"))?;
+        let (value, unit) = s.split_at(split);
+        let value: u64 = value
+            .parse()
+            .map_err(|_| format!("{s:?}: expected a number before the unit"))?;
+        if value == 0 {
+            return Err(format!("{s:?}: must be greater than zero"));
+        }
+        let size = |scale: u64| Ok(Rotate::Size(value * scale));
+        let time = |secs: u64| Ok(Rotate::Time(TimeDelta::seconds((value * secs) as i64)));
+        match unit.to_ascii_uppercase().as_str() {
+            "B" => size(1),
+            "KB" => size(1024),
+            "MB" => size(1024 * 1024),
+            "GB" => size(1024 * 1024 * 1024),
+            "S" => time(1),
+            "M" => time(60),
+            "H" => time(3600),
+            _ => Err(format!("{s:?}: unknown unit {unit:?}")),
+        }
+    }
+}
+
+struct Segment {
+    out: Output,
+    started: DateTime<Utc>,
+    bytes: u64,
+}
+
+/// Records everything received to a directory of session files, starting a
+/// new one per [`Rotate`], zstd-compressed if asked to. Each file opens with
+/// `mavsnark --session`.
+pub struct Recorder {
+    dir: PathBuf,
+    rotate: Rotate,
+    compress: bool,
+    links: Vec<String>,
+    segment: Option<Segment>,
+    /// Reused for encoding so the size of each record is known.
+    buf: Vec<u8>,
+}
+
+impl Recorder {
+    pub fn new(
+        dir: PathBuf,
+        rotate: Rotate,
+        compress: bool,
+        links: Vec<String>,
+    ) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            rotate,
+            compress,
+            links,
+            segment: None,
+            buf: Vec::new(),
+        })
+    }
+
+    /// A [`Rotate::Size`] limit counts bytes before compression.
+    fn due(&self, at: DateTime<Utc>) -> bool {
+        match (&self.segment, self.rotate) {
+            (None, _) => true,
+            (Some(s), Rotate::Size(max)) => s.bytes >= max,
+            (Some(s), Rotate::Time(span)) => at.signed_duration_since(s.started) >= span,
+        }
+    }
+
+    fn open(&mut self, at: DateTime<Utc>) -> io::Result<()> {
+        self.finish()?;
+        let stamp = at.with_timezone(&Local).format("%Y%m%d-%H%M%S%.3f");
+        let suffix = compress::suffix(self.compress);
+        let path = self.dir.join(format!("mavsnark-{stamp}.session{suffix}"));
+        let mut out = Output::new(File::create_new(path)?, self.compress)?;
+        let meta = SessionMeta {
+            links: self.links.clone(),
+            ..Default::default()
+        };
+        let mut header = Vec::new();
+        session::write_header(&mut header, &meta)?;
+        out.write_all(&header)?;
+        self.segment = Some(Segment {
+            out,
+            started: at,
+            bytes: header.len() as u64,
+        });
+        Ok(())
+    }
+
+    pub fn record(&mut self, at: DateTime<Utc>, incoming: &Incoming) -> io::Result<()> {
+        if self.due(at) {
+            self.open(at)?;
+        }
+        self.buf.clear();
+        session::write_record(&mut self.buf, at, incoming)?;
+        let segment = self.segment.as_mut().expect("opened above");
+        segment.out.write_all(&self.buf)?;
+        segment.bytes += self.buf.len() as u64;
+        Ok(())
+    }
+
+    /// Pushes buffered records to disk; called every UI tick so a crash
+    /// loses at most a tick's worth.
+    pub fn flush(&mut self) -> io::Result<()> {
+        match &mut self.segment {
+            Some(segment) => segment.out.flush(),
+            None => Ok(()),
+        }
+    }
+
+    /// Closes the current file; the next record starts a new one.
+    pub fn finish(&mut self) -> io::Result<()> {
+        match self.segment.take() {
+            Some(segment) => segment.out.finish(),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::{
+        MavHeader,
+        common::{HEARTBEAT_DATA, MavMessage},
+    };
+
+    use super::*;
+    use crate::message::MavMsg;
+
+    #[test]
+    fn parses_rotation_specs() {
+        assert_eq!("100MB".parse(), Ok(Rotate::Size(100 * 1024 * 1024)));
+        assert_eq!("512kb".parse(), Ok(Rotate::Size(512 * 1024)));
+        assert_eq!("1h".parse(), Ok(Rotate::Time(TimeDelta::hours(1))));
+        assert_eq!("15m".parse(), Ok(Rotate::Time(TimeDelta::minutes(15))));
+        assert!("100".parse::<Rotate>().is_err());
+        assert!("0MB".parse::<Rotate>().is_err());
+        assert!("3 parsecs".parse::<Rotate>().is_err());
+        assert_eq!(parse_size("2GB"), Ok(2 * 1024 * 1024 * 1024));
+        assert!(parse_size("1h").is_err());
+        assert_eq!(parse_duration("30s"), Ok(TimeDelta::seconds(30)));
+        assert!(parse_duration("30MB").is_err());
+    }
+
+    #[test]
+    fn rotates_into_readable_sessions() {
+        let dir = std::env::temp_dir().join(format!("mavsnark-autosave-{}", std::process::id()));
+        let mut recorder = Recorder::new(
+            dir.clone(),
+            Rotate::Time(TimeDelta::seconds(10)),
+            true,
+            vec![],
+        )
+        .unwrap();
+        let heartbeat = || {
+            Incoming::Message(MavMsg::new(
+                MavHeader::default(),
+                MavMessage::HEARTBEAT(HEARTBEAT_DATA::default()),
+                0,
+            ))
+        };
+        let t0 = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        recorder.record(t0, &heartbeat()).unwrap();
+        recorder
+            .record(t0 + TimeDelta::seconds(5), &heartbeat())
+            .unwrap();
+        recorder
+            .record(t0 + TimeDelta::seconds(12), &heartbeat())
+            .unwrap();
+        recorder.finish().unwrap();
+
+        let mut files: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .collect();
+        files.sort();
+        let counts: Vec<usize> = files
+            .iter()
+            .map(|f| session::load(f).unwrap().records.len())
+            .collect();
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(
+            files
+                .iter()
+                .all(|f| f.to_string_lossy().ends_with(".session.zst"))
+        );
+        assert_eq!(counts, vec![2, 1]);
+    }
+}
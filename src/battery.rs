@@ -0,0 +1,335 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use mavlink::common::{MavMessage, MavModeFlag};
+
+use crate::{
+    message::MavMsg,
+    modes::{self, ModeScheme},
+    radio::HISTORY,
+};
+
+/// Current averaged over this long for the remaining time, so it follows
+/// the vehicle's present work rather than the whole flight.
+const RECENT: TimeDelta = TimeDelta::seconds(60);
+
+/// Phases kept per battery, oldest dropped first.
+const MAX_PHASES: usize = 50;
+
+/// One BATTERY_STATUS.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BatterySample {
+    pub at: DateTime<Utc>,
+    /// Sum of the reported cells, V.
+    pub voltage: Option<f64>,
+    pub current: Option<f64>,
+    /// mAh since boot.
+    pub consumed: Option<f64>,
+    /// Percent.
+    pub remaining: Option<u8>,
+    /// The autopilot's own estimate.
+    pub time_remaining: Option<TimeDelta>,
+}
+
+/// A stretch of the flight in one flight mode, armed or not.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Phase {
+    pub mavtype: String,
+    pub custom_mode: u32,
+    pub armed: bool,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// mAh consumed during the phase.
+    pub consumed: f64,
+}
+
+impl Phase {
+    pub fn duration(&self) -> TimeDelta {
+        self.end - self.start
+    }
+
+    /// The decoded flight mode when a scheme is known, else the raw
+    /// custom_mode.
+    pub fn label(&self, scheme: Option<ModeScheme>) -> String {
+        let mode = scheme
+            .and_then(|s| modes::decode(s, &self.mavtype, self.custom_mode))
+            .unwrap_or_else(|| format!("mode {}", self.custom_mode));
+        if self.armed {
+            mode
+        } else {
+            format!("{mode} (disarmed)")
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Battery {
+    /// Over the last [`HISTORY`], oldest first.
+    pub samples: VecDeque<BatterySample>,
+    /// The first sample that reported consumption, for the flight average.
+    first: Option<BatterySample>,
+    /// The first sample that reported both consumption and remaining.
+    first_remaining: Option<(f64, u8)>,
+    pub phases: VecDeque<Phase>,
+}
+
+impl Battery {
+    pub fn latest(&self) -> Option<&BatterySample> {
+        self.samples.back()
+    }
+
+    /// Mean current in A since the battery was first seen, from the
+    /// consumed counter.
+    pub fn average_draw(&self) -> Option<f64> {
+        let first = self.first?;
+        let latest = self.latest()?;
+        let hours = (latest.at - first.at).num_milliseconds() as f64 / 3_600_000.0;
+        (hours > 0.0).then(|| (latest.consumed? - first.consumed?) / 1000.0 / hours)
+    }
+
+    /// Mean of the reported current over the last minute.
+    pub fn recent_draw(&self) -> Option<f64> {
+        let latest = self.latest()?;
+        let currents: Vec<f64> = self
+            .samples
+            .iter()
+            .filter(|s| latest.at - s.at <= RECENT)
+            .filter_map(|s| s.current)
+            .collect();
+        (!currents.is_empty()).then(|| currents.iter().sum::<f64>() / currents.len() as f64)
+    }
+
+    /// mAh left, scaled from how much was used per percent so far.
+    pub fn remaining_capacity(&self) -> Option<f64> {
+        let (first_consumed, first_pct) = self.first_remaining?;
+        let latest = self.latest()?;
+        let (consumed, pct) = (latest.consumed?, latest.remaining?);
+        let used_pct = f64::from(first_pct) - f64::from(pct);
+        // Too little drop for the ratio to mean anything yet.
+        if used_pct < 2.0 {
+            return None;
+        }
+        Some((consumed - first_consumed) / used_pct * f64::from(pct))
+    }
+
+    /// Time left at the recent draw, falling back to the flight average.
+    pub fn endurance(&self) -> Option<TimeDelta> {
+        let draw = self
+            .recent_draw()
+            .or_else(|| self.average_draw())
+            .filter(|&a| a > 0.1)?;
+        let hours = self.remaining_capacity()? / 1000.0 / draw;
+        Some(TimeDelta::seconds((hours * 3600.0).round() as i64))
+    }
+}
+
+/// BATTERY_STATUS history per vehicle and battery, split into phases by the
+/// vehicle's HEARTBEAT flight mode and arming.
+#[derive(Default)]
+pub struct BatteryTracker {
+    batteries: BTreeMap<(u8, u8), Battery>,
+    /// mavtype, custom_mode and armed from the last autopilot HEARTBEAT.
+    modes: BTreeMap<u8, (String, u32, bool)>,
+}
+
+impl BatteryTracker {
+    pub fn observe(&mut self, msg: &MavMsg) {
+        let sys_id = msg.header.system_id;
+        let d = match &msg.msg {
+            MavMessage::HEARTBEAT(d) if msg.header.component_id == 1 => {
+                let armed = d
+                    .base_mode
+                    .contains(MavModeFlag::MAV_MODE_FLAG_SAFETY_ARMED);
+                self.modes
+                    .insert(sys_id, (format!("{:?}", d.mavtype), d.custom_mode, armed));
+                return;
+            }
+            MavMessage::BATTERY_STATUS(d) => d,
+            _ => return,
+        };
+        let cells: Vec<f64> = d
+            .voltages
+            .iter()
+            .chain(&d.voltages_ext)
+            .filter(|&&mv| mv != u16::MAX && mv != 0)
+            .map(|&mv| f64::from(mv) / 1000.0)
+            .collect();
+        let sample = BatterySample {
+            at: msg.timestamp,
+            voltage: (!cells.is_empty()).then(|| cells.iter().sum()),
+            current: (d.current_battery >= 0).then(|| f64::from(d.current_battery) / 100.0),
+            consumed: (d.current_consumed >= 0).then(|| f64::from(d.current_consumed)),
+            remaining: u8::try_from(d.battery_remaining).ok(),
+            time_remaining: (d.time_remaining > 0)
+                .then(|| TimeDelta::seconds(i64::from(d.time_remaining))),
+        };
+        let battery = self.batteries.entry((sys_id, d.id)).or_default();
+        if battery.first.is_none() && sample.consumed.is_some() {
+            battery.first = Some(sample);
+        }
+        if battery.first_remaining.is_none() {
+            battery.first_remaining = sample.consumed.zip(sample.remaining);
+        }
+
+        if let (Some(consumed), Some((mavtype, custom_mode, armed))) =
+            (sample.consumed, self.modes.get(&sys_id))
+        {
+            // What was used since the previous sample goes to the mode the
+            // vehicle is in now, and phases tile without gaps.
+            let previous = battery.latest().filter(|s| s.consumed.is_some()).copied();
+            let used = previous.map_or(0.0, |s| consumed - s.consumed.unwrap_or(consumed));
+            match battery.phases.back_mut() {
+                Some(phase) if phase.custom_mode == *custom_mode && phase.armed == *armed => {
+                    phase.consumed += used;
+                    phase.end = sample.at;
+                }
+                _ => {
+                    battery.phases.push_back(Phase {
+                        mavtype: mavtype.clone(),
+                        custom_mode: *custom_mode,
+                        armed: *armed,
+                        start: previous.map_or(sample.at, |s| s.at),
+                        end: sample.at,
+                        consumed: used,
+                    });
+                    if battery.phases.len() > MAX_PHASES {
+                        battery.phases.pop_front();
+                    }
+                }
+            }
+        }
+
+        battery.samples.push_back(sample);
+        while battery
+            .samples
+            .front()
+            .is_some_and(|s| sample.at - s.at > HISTORY)
+        {
+            battery.samples.pop_front();
+        }
+    }
+
+    /// Batteries of one vehicle by battery id.
+    pub fn vehicle(&self, sys_id: u8) -> impl Iterator<Item = (u8, &Battery)> {
+        self.batteries
+            .range((sys_id, 0)..=(sys_id, u8::MAX))
+            .map(|(&(_, id), battery)| (id, battery))
+    }
+
+    /// All batteries by vehicle and battery id.
+    pub fn batteries(&self) -> impl Iterator<Item = (&(u8, u8), &Battery)> {
+        self.batteries.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.batteries.clear();
+        self.modes.clear();
+    }
+}
+
+/// `1h02m` or `12m05s`.
+pub fn format_duration(duration: TimeDelta) -> String {
+    let secs = duration.num_seconds().max(0);
+    if secs >= 3600 {
+        format!("{}h{:02}m", secs / 3600, secs % 3600 / 60)
+    } else {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::{
+        MavHeader,
+        common::{BATTERY_STATUS_DATA, HEARTBEAT_DATA},
+    };
+
+    use super::*;
+
+    fn push(tracker: &mut BatteryTracker, msg: MavMessage, at: DateTime<Utc>) {
+        let header = MavHeader {
+            system_id: 1,
+            component_id: 1,
+            sequence: 0,
+        };
+        let mut msg = MavMsg::new(header, msg, 0);
+        msg.timestamp = at;
+        tracker.observe(&msg);
+    }
+
+    fn status(centiamps: i16, consumed: i32, remaining: i8) -> MavMessage {
+        let mut voltages = [u16::MAX; 10];
+        voltages[..4].fill(3_900);
+        MavMessage::BATTERY_STATUS(BATTERY_STATUS_DATA {
+            voltages,
+            current_battery: centiamps,
+            current_consumed: consumed,
+            battery_remaining: remaining,
+            ..Default::default()
+        })
+    }
+
+    fn heartbeat(custom_mode: u32, armed: bool) -> MavMessage {
+        MavMessage::HEARTBEAT(HEARTBEAT_DATA {
+            custom_mode,
+            base_mode: if armed {
+                MavModeFlag::MAV_MODE_FLAG_SAFETY_ARMED
+            } else {
+                MavModeFlag::empty()
+            },
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn projects_endurance_from_consumption() {
+        let mut tracker = BatteryTracker::default();
+        let t0 = Utc::now();
+        // 18 A for two minutes: 600 mAh, 10% of the pack.
+        for i in 0..=120 {
+            let at = t0 + TimeDelta::seconds(i);
+            let consumed = (i * 5) as i32;
+            let remaining = 90 - (i / 12) as i8;
+            push(&mut tracker, status(1_800, consumed, remaining), at);
+        }
+        let (id, battery) = tracker.vehicle(1).next().unwrap();
+        assert_eq!(id, 0);
+        let latest = battery.latest().unwrap();
+        assert!((latest.voltage.unwrap() - 15.6).abs() < 1e-9);
+        assert!((battery.average_draw().unwrap() - 18.0).abs() < 1e-6);
+        assert!((battery.recent_draw().unwrap() - 18.0).abs() < 1e-6);
+        // 60 mAh per percent, 80% left: 4800 mAh at 18 A is 16 minutes.
+        assert!((battery.remaining_capacity().unwrap() - 4_800.0).abs() < 1e-6);
+        assert_eq!(battery.endurance().unwrap().num_seconds(), 960);
+        assert_eq!(format_duration(TimeDelta::seconds(960)), "16m00s");
+        assert_eq!(format_duration(TimeDelta::seconds(3_725)), "1h02m");
+    }
+
+    #[test]
+    fn splits_consumption_by_phase() {
+        let mut tracker = BatteryTracker::default();
+        let t0 = Utc::now();
+        let at = |s| t0 + TimeDelta::seconds(s);
+        push(&mut tracker, heartbeat(0, false), at(0));
+        push(&mut tracker, status(50, 100, 99), at(0));
+        push(&mut tracker, status(50, 101, 99), at(10));
+        push(&mut tracker, heartbeat(5, true), at(11));
+        push(&mut tracker, status(2_000, 150, 98), at(20));
+        push(&mut tracker, status(2_000, 300, 95), at(40));
+
+        let (_, battery) = tracker.vehicle(1).next().unwrap();
+        let phases: Vec<(String, f64)> = battery
+            .phases
+            .iter()
+            .map(|p| (p.label(None), p.consumed))
+            .collect();
+        assert_eq!(
+            phases,
+            vec![
+                ("mode 0 (disarmed)".to_string(), 1.0),
+                ("mode 5".to_string(), 199.0),
+            ]
+        );
+        assert_eq!(battery.phases[1].duration(), TimeDelta::seconds(30));
+    }
+}
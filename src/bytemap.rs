@@ -0,0 +1,145 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use mavlink::{MavlinkVersion, Message, common::MavMessage};
+
+use crate::{entries::parse_fields, message::debug_fields};
+
+/// Bytes `offset..offset + len` of a payload encode `field`.
+#[derive(Debug, PartialEq)]
+pub struct FieldSpan {
+    pub offset: usize,
+    pub len: usize,
+    pub field: String,
+}
+
+/// Works out which payload bytes encode which field of message `id`.
+///
+/// The generated bindings do not expose wire offsets, so each byte of a
+/// default-encoded payload is flipped in turn and the decoded message is
+/// compared against the default to see which field changed. Bytes whose
+/// every value is rejected by the decoder (e.g. an enum with a single
+/// variant) are left out.
+pub fn field_layout(id: u32) -> Vec<FieldSpan> {
+    let Some(default) = MavMessage::default_message_from_id(id) else {
+        return Vec::new();
+    };
+    let mut base = [0u8; 255];
+    // MAVLink 1 does not truncate trailing zeros, so this is the full length.
+    let len = default.ser(MavlinkVersion::V1, &mut base);
+    let base = &base[..len];
+    let base_debug = debug_fields(&default);
+    let base_fields = parse_fields(&base_debug);
+
+    let mut spans: Vec<FieldSpan> = Vec::new();
+    for offset in 0..len {
+        let Some(field) = field_at(id, base, offset, &base_fields) else {
+            continue;
+        };
+        match spans.last_mut() {
+            Some(last) if last.field == field && last.offset + last.len == offset => {
+                last.len += 1;
+            }
+            _ => spans.push(FieldSpan {
+                offset,
+                len: 1,
+                field,
+            }),
+        }
+    }
+    spans
+}
+
+fn field_at(id: u32, base: &[u8], offset: usize, base_fields: &[(&str, &str)]) -> Option<String> {
+    let mut buf = base.to_vec();
+    for flip in 1..=u8::MAX {
+        buf[offset] = base[offset] ^ flip;
+        let Ok(msg) = MavMessage::parse(MavlinkVersion::V1, id, &buf) else {
+            continue;
+        };
+        let debug = debug_fields(&msg);
+        let changed = parse_fields(&debug)
+            .into_iter()
+            .zip(base_fields)
+            .find(|(now, before)| now != *before);
+        if let Some(((key, _), _)) = changed {
+            return Some(key.to_string());
+        }
+    }
+    None
+}
+
+/// [`field_layout`] results per message id; working one out takes a few
+/// hundred decodes, too many to repeat every frame.
+pub struct LayoutCache {
+    layouts: RefCell<HashMap<u32, Rc<Vec<FieldSpan>>>>,
+}
+
+impl LayoutCache {
+    pub fn new() -> Self {
+        Self {
+            layouts: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn get(&self, id: u32) -> Rc<Vec<FieldSpan>> {
+        self.layouts
+            .borrow_mut()
+            .entry(id)
+            .or_insert_with(|| Rc::new(field_layout(id)))
+            .clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::{
+        MessageData,
+        common::{ATTITUDE_DATA, COMMAND_ACK_DATA, HEARTBEAT_DATA},
+    };
+
+    use super::*;
+
+    fn span(offset: usize, len: usize, field: &str) -> FieldSpan {
+        FieldSpan {
+            offset,
+            len,
+            field: field.to_string(),
+        }
+    }
+
+    #[test]
+    fn heartbeat_layout_follows_wire_order() {
+        assert_eq!(
+            field_layout(HEARTBEAT_DATA::ID),
+            vec![
+                span(0, 4, "custom_mode"),
+                span(4, 1, "mavtype"),
+                span(5, 1, "autopilot"),
+                span(6, 1, "base_mode"),
+                span(7, 1, "system_status"),
+                span(8, 1, "mavlink_version"),
+            ]
+        );
+    }
+
+    #[test]
+    fn layout_covers_every_attitude_byte() {
+        let layout = field_layout(ATTITUDE_DATA::ID);
+        assert_eq!(layout.len(), 7);
+        assert_eq!(layout[0], span(0, 4, "time_boot_ms"));
+        assert_eq!(layout.iter().map(|s| s.len).sum::<usize>(), 28);
+    }
+
+    #[test]
+    fn enum_fields_are_found() {
+        assert_eq!(
+            field_layout(COMMAND_ACK_DATA::ID),
+            vec![span(0, 2, "command"), span(2, 1, "result")]
+        );
+    }
+
+    #[test]
+    fn unknown_ids_have_no_layout() {
+        assert!(field_layout(u32::MAX).is_empty());
+    }
+}
@@ -0,0 +1,277 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use mavlink::{
+    Message,
+    common::{MavMessage, MavProtocolCapability},
+};
+
+use crate::message::MavMsg;
+
+/// Microservices and the messages whose presence shows a component takes
+/// part in them; a trailing `_` matches a prefix.
+const SERVICES: &[(&str, &[&str])] = &[
+    ("commands", &["COMMAND_ACK"]),
+    ("parameters", &["PARAM_VALUE", "PARAM_EXT_VALUE"]),
+    ("mission", &["MISSION_"]),
+    ("time sync", &["TIMESYNC", "SYSTEM_TIME"]),
+    ("ftp", &["FILE_TRANSFER_PROTOCOL"]),
+    (
+        "component info",
+        &["COMPONENT_INFORMATION", "COMPONENT_METADATA"],
+    ),
+    ("log download", &["LOG_ENTRY", "LOG_DATA"]),
+    ("geofence", &["FENCE_STATUS"]),
+    ("terrain", &["TERRAIN_REQUEST", "TERRAIN_REPORT"]),
+    ("camera", &["CAMERA_"]),
+    ("gimbal", &["GIMBAL_", "MOUNT_"]),
+    ("battery", &["BATTERY_STATUS"]),
+    ("remote id", &["OPEN_DRONE_ID_"]),
+    ("adsb", &["ADSB_VEHICLE"]),
+    ("high latency", &["HIGH_LATENCY"]),
+    ("tunnel", &["TUNNEL"]),
+];
+
+fn service(name: &str) -> Option<&'static str> {
+    SERVICES.iter().find_map(|(service, messages)| {
+        messages
+            .iter()
+            .any(|m| match m.strip_suffix('_') {
+                Some(prefix) => name.starts_with(prefix),
+                None => name == *m,
+            })
+            .then_some(*service)
+    })
+}
+
+/// Firmware and board details from AUTOPILOT_VERSION.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Version {
+    pub capabilities: MavProtocolCapability,
+    pub flight_sw: u32,
+    pub middleware_sw: u32,
+    pub os_sw: u32,
+    pub board: u32,
+    pub vendor_id: u16,
+    pub product_id: u16,
+}
+
+/// What one component has shown of itself.
+#[derive(Debug, Default)]
+pub struct Component {
+    /// HEARTBEAT type and autopilot, without their enum prefixes.
+    pub mavtype: Option<String>,
+    pub autopilot: Option<String>,
+    /// MAVLink 1 frames seen, MAVLink 2 frames seen.
+    pub wire: (u64, u64),
+    /// Any MAVLink 2 frame was signed.
+    pub signed: bool,
+    /// PROTOCOL_VERSION version, min and max, e.g. 200.
+    pub protocol: Option<(u16, u16, u16)>,
+    pub version: Option<Version>,
+    /// Inferred from the messages the component sends.
+    pub services: BTreeSet<&'static str>,
+}
+
+impl Component {
+    /// `MAVLink 2 (signed)`, `MAVLink 1`, or both.
+    pub fn wire_label(&self) -> String {
+        let mut label = wire_label(self.wire).to_string();
+        if self.signed {
+            label.push_str(" (signed)");
+        }
+        label
+    }
+
+    pub fn capability_names(&self) -> Vec<String> {
+        self.version.as_ref().map_or_else(Vec::new, |v| {
+            v.capabilities
+                .iter_names()
+                .map(|(name, _)| {
+                    name.trim_start_matches("MAV_PROTOCOL_CAPABILITY_")
+                        .to_lowercase()
+                        .replace('_', " ")
+                })
+                .collect()
+        })
+    }
+}
+
+/// Protocol version, capabilities, firmware and the microservices in use,
+/// per component.
+#[derive(Default)]
+pub struct CapabilityTracker {
+    components: BTreeMap<(u8, u8), Component>,
+}
+
+impl CapabilityTracker {
+    pub fn observe(&mut self, msg: &MavMsg) {
+        let component = self
+            .components
+            .entry((msg.header.system_id, msg.header.component_id))
+            .or_default();
+        if msg.is_v1() {
+            component.wire.0 += 1;
+        } else {
+            component.wire.1 += 1;
+        }
+        component.signed |= msg.signature.is_some();
+        match &msg.msg {
+            MavMessage::HEARTBEAT(d) => {
+                component.mavtype = Some(short(&format!("{:?}", d.mavtype), "MAV_TYPE_"));
+                component.autopilot = Some(short(&format!("{:?}", d.autopilot), "MAV_AUTOPILOT_"));
+            }
+            MavMessage::PROTOCOL_VERSION(d) => {
+                component.protocol = Some((d.version, d.min_version, d.max_version));
+            }
+            MavMessage::AUTOPILOT_VERSION(d) => {
+                component.version = Some(Version {
+                    capabilities: d.capabilities,
+                    flight_sw: d.flight_sw_version,
+                    middleware_sw: d.middleware_sw_version,
+                    os_sw: d.os_sw_version,
+                    board: d.board_version,
+                    vendor_id: d.vendor_id,
+                    product_id: d.product_id,
+                });
+            }
+            _ => {}
+        }
+        if let Some(service) = service(msg.msg.message_name()) {
+            component.services.insert(service);
+        }
+    }
+
+    pub fn component(&self, sys_id: u8, comp_id: u8) -> Option<&Component> {
+        self.components.get(&(sys_id, comp_id))
+    }
+
+    pub fn components(&self) -> impl Iterator<Item = ((u8, u8), &Component)> {
+        self.components.iter().map(|(&key, c)| (key, c))
+    }
+
+    /// MAVLink 1 and MAVLink 2 frames per system, over all its components.
+    pub fn wire_by_system(&self) -> BTreeMap<u8, (u64, u64)> {
+        let mut systems = BTreeMap::new();
+        for (&(sys_id, _), component) in &self.components {
+            let wire: &mut (u64, u64) = systems.entry(sys_id).or_default();
+            wire.0 += component.wire.0;
+            wire.1 += component.wire.1;
+        }
+        systems
+    }
+
+    /// Whether any MAVLink 1 frame has been seen.
+    pub fn any_v1(&self) -> bool {
+        self.components.values().any(|c| c.wire.0 > 0)
+    }
+
+    pub fn clear(&mut self) {
+        self.components.clear();
+    }
+}
+
+/// `MAVLink 1`, `MAVLink 2` or `MAVLink 1+2` for frame counts per version.
+pub fn wire_label((v1, v2): (u64, u64)) -> &'static str {
+    match (v1 > 0, v2 > 0) {
+        (true, true) => "MAVLink 1+2",
+        (true, false) => "MAVLink 1",
+        _ => "MAVLink 2",
+    }
+}
+
+fn short(value: &str, prefix: &str) -> String {
+    value.strip_prefix(prefix).unwrap_or(value).to_string()
+}
+
+/// `4.5.1 official` from a semantic version packed as major, minor, patch
+/// and FIRMWARE_VERSION_TYPE bytes; `-` for 0, which means not given.
+pub fn format_version(version: u32) -> String {
+    if version == 0 {
+        return "-".to_string();
+    }
+    let [major, minor, patch, kind] = version.to_be_bytes();
+    let kind = match kind {
+        0 => " dev",
+        64 => " alpha",
+        128 => " beta",
+        192 => " rc",
+        255 => " official",
+        _ => "",
+    };
+    format!("{major}.{minor}.{patch}{kind}")
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::{
+        MavHeader,
+        common::{AUTOPILOT_VERSION_DATA, MISSION_CURRENT_DATA, PARAM_VALUE_DATA},
+    };
+
+    use super::*;
+
+    #[test]
+    fn builds_a_component_overview() {
+        let mut tracker = CapabilityTracker::default();
+        for msg in [
+            MavMessage::AUTOPILOT_VERSION(AUTOPILOT_VERSION_DATA {
+                capabilities: MavProtocolCapability::MAV_PROTOCOL_CAPABILITY_MAVLINK2
+                    | MavProtocolCapability::MAV_PROTOCOL_CAPABILITY_FTP,
+                flight_sw_version: 0x0405_01ff,
+                ..Default::default()
+            }),
+            MavMessage::PARAM_VALUE(PARAM_VALUE_DATA::default()),
+            MavMessage::MISSION_CURRENT(MISSION_CURRENT_DATA::default()),
+        ] {
+            tracker.observe(&MavMsg::new(MavHeader::default(), msg, 0));
+        }
+        let header = MavHeader::default();
+        let component = tracker
+            .component(header.system_id, header.component_id)
+            .unwrap();
+        assert_eq!(component.capability_names(), ["ftp", "mavlink2"]);
+        assert_eq!(
+            component.services.iter().copied().collect::<Vec<_>>(),
+            ["mission", "parameters"]
+        );
+        assert_eq!(component.wire_label(), "MAVLink 2");
+        assert_eq!(
+            format_version(component.version.as_ref().unwrap().flight_sw),
+            "4.5.1 official"
+        );
+    }
+
+    #[test]
+    fn counts_wire_versions_per_system() {
+        let mut tracker = CapabilityTracker::default();
+        let frame = |sys_id, comp_id, v1: bool| {
+            let header = MavHeader {
+                system_id: sys_id,
+                component_id: comp_id,
+                sequence: 0,
+            };
+            let mut msg = MavMsg::new(header, MavMessage::PARAM_VALUE(Default::default()), 0);
+            if v1 {
+                msg.len = msg.payload.len() + 8;
+            }
+            msg
+        };
+        tracker.observe(&frame(1, 1, false));
+        assert!(!tracker.any_v1());
+        tracker.observe(&frame(1, 1, true));
+        tracker.observe(&frame(1, 2, true));
+        tracker.observe(&frame(2, 1, false));
+        assert!(tracker.any_v1());
+        let systems = tracker.wire_by_system();
+        assert_eq!(systems[&1], (2, 1));
+        assert_eq!(wire_label(systems[&1]), "MAVLink 1+2");
+        assert_eq!(wire_label(systems[&2]), "MAVLink 2");
+        assert_eq!(tracker.component(1, 2).unwrap().wire_label(), "MAVLink 1");
+    }
+
+    #[test]
+    fn versions() {
+        assert_eq!(format_version(0), "-");
+        assert_eq!(format_version(0x010e_0040), "1.14.0 alpha");
+    }
+}
@@ -0,0 +1,274 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use chrono::{DateTime, Utc};
+use mavlink::common::{
+    CellularConfigResponse, CellularNetworkFailedReason, CellularNetworkRadioType,
+    CellularStatusFlag, MavMessage,
+};
+
+use crate::{entries::Report, message::MavMsg, radio::HISTORY};
+
+/// The last CELLULAR_STATUS of one modem.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Registration {
+    pub status: CellularStatusFlag,
+    pub failure: CellularNetworkFailedReason,
+    pub radio: CellularNetworkRadioType,
+    /// Signal quality in percent, `None` if the modem does not know.
+    pub quality: Option<u8>,
+    pub mcc: u16,
+    pub mnc: u16,
+    pub lac: u16,
+}
+
+/// CELLULAR_CONFIG as last seen from one component: a request from a
+/// ground station or the vehicle's answer. PIN and PUK are never kept.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModemConfig {
+    pub apn: String,
+    pub lte: bool,
+    pub roaming: bool,
+    pub response: CellularConfigResponse,
+    pub at: DateTime<Utc>,
+}
+
+#[derive(Debug, Default)]
+pub struct Modem {
+    pub registration: Option<Registration>,
+    /// Signal quality over the last [`HISTORY`].
+    pub quality: VecDeque<(DateTime<Utc>, u8)>,
+    pub config: Option<ModemConfig>,
+}
+
+/// A registration change or a refused configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CellularReport {
+    Status {
+        modem: (u8, u8),
+        from: CellularStatusFlag,
+        to: Registration,
+    },
+    ConfigRefused {
+        modem: (u8, u8),
+        response: CellularConfigResponse,
+    },
+}
+
+impl Report for CellularReport {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Status { .. } => "CELLULAR_STATE",
+            Self::ConfigRefused { .. } => "CELLULAR_CONFIG_REFUSED",
+        }
+    }
+
+    /// Losing the data connection, a failed modem or a refused config.
+    fn is_alert(&self) -> bool {
+        match self {
+            Self::Status { from, to, .. } => {
+                to.status == CellularStatusFlag::CELLULAR_STATUS_FLAG_FAILED
+                    || *from == CellularStatusFlag::CELLULAR_STATUS_FLAG_CONNECTED
+            }
+            Self::ConfigRefused { .. } => true,
+        }
+    }
+
+    fn source(&self) -> (u8, u8) {
+        match self {
+            Self::Status { modem, .. } | Self::ConfigRefused { modem, .. } => *modem,
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Self::Status { from, to, .. } => {
+                let mut text = format!(
+                    "status: {} -> {}",
+                    status_name(*from),
+                    status_name(to.status)
+                );
+                if to.failure != CellularNetworkFailedReason::CELLULAR_NETWORK_FAILED_REASON_NONE {
+                    text.push_str(&format!(", failure: {}", failure_name(to.failure)));
+                }
+                text
+            }
+            Self::ConfigRefused { response, .. } => {
+                format!("response: {}", response_name(*response))
+            }
+        }
+    }
+}
+
+/// CELLULAR_STATUS and CELLULAR_CONFIG per sending component.
+#[derive(Default)]
+pub struct CellularTracker {
+    modems: BTreeMap<(u8, u8), Modem>,
+}
+
+impl CellularTracker {
+    pub fn observe(&mut self, msg: &MavMsg) -> Option<CellularReport> {
+        let key = (msg.header.system_id, msg.header.component_id);
+        match &msg.msg {
+            MavMessage::CELLULAR_STATUS(d) => {
+                let modem = self.modems.entry(key).or_default();
+                let quality = (d.quality != u8::MAX).then_some(d.quality);
+                let now = Registration {
+                    status: d.status,
+                    failure: d.failure_reason,
+                    radio: d.mavtype,
+                    quality,
+                    mcc: d.mcc,
+                    mnc: d.mnc,
+                    lac: d.lac,
+                };
+                if let Some(q) = quality {
+                    modem.quality.push_back((msg.timestamp, q));
+                }
+                while modem
+                    .quality
+                    .front()
+                    .is_some_and(|&(t, _)| msg.timestamp - t > HISTORY)
+                {
+                    modem.quality.pop_front();
+                }
+                let previous = modem.registration.replace(now)?;
+                (previous.status != now.status).then_some(CellularReport::Status {
+                    modem: key,
+                    from: previous.status,
+                    to: now,
+                })
+            }
+            MavMessage::CELLULAR_CONFIG(d) => {
+                let response = d.response;
+                self.modems.entry(key).or_default().config = Some(ModemConfig {
+                    apn: d.apn.to_str().unwrap_or_default().to_string(),
+                    lte: d.enable_lte != 0,
+                    roaming: d.roaming != 0,
+                    response,
+                    at: msg.timestamp,
+                });
+                (response != CellularConfigResponse::CELLULAR_CONFIG_RESPONSE_ACCEPTED).then_some(
+                    CellularReport::ConfigRefused {
+                        modem: key,
+                        response,
+                    },
+                )
+            }
+            _ => None,
+        }
+    }
+
+    pub fn modems(&self) -> impl Iterator<Item = (&(u8, u8), &Modem)> {
+        self.modems.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.modems.clear();
+    }
+}
+
+/// `CELLULAR_STATUS_FLAG_CONNECTED` as `connected`.
+fn short(debug: String, prefix: &str) -> String {
+    debug
+        .strip_prefix(prefix)
+        .unwrap_or(&debug)
+        .to_lowercase()
+        .replace('_', " ")
+}
+
+pub fn status_name(status: CellularStatusFlag) -> String {
+    short(format!("{status:?}"), "CELLULAR_STATUS_FLAG_")
+}
+
+pub fn failure_name(reason: CellularNetworkFailedReason) -> String {
+    short(format!("{reason:?}"), "CELLULAR_NETWORK_FAILED_REASON_")
+}
+
+pub fn radio_name(radio: CellularNetworkRadioType) -> String {
+    short(format!("{radio:?}"), "CELLULAR_NETWORK_RADIO_TYPE_").to_uppercase()
+}
+
+pub fn response_name(response: CellularConfigResponse) -> String {
+    let debug = format!("{response:?}");
+    let debug = debug.replace("_RESPONSE", "");
+    short(debug, "CELLULAR_CONFIG_")
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeDelta;
+    use mavlink::{
+        MavHeader,
+        common::{CELLULAR_CONFIG_DATA, CELLULAR_STATUS_DATA},
+    };
+
+    use super::*;
+
+    fn at(msg: MavMessage, t: DateTime<Utc>) -> MavMsg {
+        MavMsg {
+            timestamp: t,
+            ..MavMsg::new(MavHeader::default(), msg, 0)
+        }
+    }
+
+    fn status(status: CellularStatusFlag, quality: u8) -> MavMessage {
+        MavMessage::CELLULAR_STATUS(CELLULAR_STATUS_DATA {
+            status,
+            quality,
+            mavtype: CellularNetworkRadioType::CELLULAR_NETWORK_RADIO_TYPE_LTE,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn tracks_registration_and_quality() {
+        let t0 = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let mut cellular = CellularTracker::default();
+        let registered = CellularStatusFlag::CELLULAR_STATUS_FLAG_REGISTERED;
+        let connected = CellularStatusFlag::CELLULAR_STATUS_FLAG_CONNECTED;
+        assert_eq!(cellular.observe(&at(status(registered, 40), t0)), None);
+        let up = cellular
+            .observe(&at(status(connected, 55), t0 + TimeDelta::seconds(1)))
+            .unwrap();
+        assert!(!up.is_alert());
+        assert_eq!(up.describe(), "status: registered -> connected");
+        // Unknown quality is not charted.
+        cellular.observe(&at(status(connected, u8::MAX), t0 + TimeDelta::seconds(2)));
+        let down = cellular
+            .observe(&at(status(registered, 10), t0 + TimeDelta::minutes(10)))
+            .unwrap();
+        assert!(down.is_alert());
+
+        let (_, modem) = cellular.modems().next().unwrap();
+        assert_eq!(
+            modem.quality.iter().map(|&(_, q)| q).collect::<Vec<_>>(),
+            [10]
+        );
+        assert_eq!(
+            radio_name(modem.registration.unwrap().radio),
+            "LTE".to_string()
+        );
+    }
+
+    #[test]
+    fn refused_config_is_reported_without_secrets() {
+        let t0 = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let mut cellular = CellularTracker::default();
+        let mut apn = [0u8; 32];
+        apn[..8].copy_from_slice(b"internet");
+        let report = cellular.observe(&at(
+            MavMessage::CELLULAR_CONFIG(CELLULAR_CONFIG_DATA {
+                apn: apn.into(),
+                pin: [b'1'; 16].into(),
+                enable_lte: 1,
+                response: CellularConfigResponse::CELLULAR_CONFIG_RESPONSE_PIN_ERROR,
+                ..Default::default()
+            }),
+            t0,
+        ));
+        assert_eq!(report.unwrap().describe(), "response: pin error");
+        let (_, modem) = cellular.modems().next().unwrap();
+        let config = modem.config.as_ref().unwrap();
+        assert_eq!((config.apn.as_str(), config.lte), ("internet", true));
+    }
+}
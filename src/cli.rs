@@ -0,0 +1,676 @@
+use std::{
+    io::{self, Write},
+    path::PathBuf,
+    sync::Arc,
+};
+
+use clap::Parser;
+
+use crate::{
+    agent, app, autosave, collector, config, connection,
+    dedup::DedupMode,
+    definitions::{self, Definitions},
+    export, fallback, frame, http, index, logging,
+    message::{self, Incoming},
+    mirror, pcap, profile, session, signing, state, tlog, trigger, watchdog, websocket, wizard,
+};
+
+#[derive(Parser)]
+#[command(
+    name = "mavsnark",
+    about = "wireshark for mavlink",
+    group = clap::ArgGroup::new("recorded").args(["session", "tlog"]).multiple(true)
+)]
+struct Args {
+    /// MAVLink connection URI (repeat to merge several links; omit to pick one interactively)
+    #[arg(short, long)]
+    uri: Vec<String>,
+
+    /// How to handle the same frame arriving on more than one link
+    #[arg(long, value_enum, default_value_t = DedupMode::Merge)]
+    dedup: DedupMode,
+
+    /// Also decode messages only this dialect has, e.g. ArduPilot's MEMINFO and AHRS (common, ardupilotmega)
+    #[arg(long, default_value = "common", value_parser = frame::parse_dialect)]
+    dialect: &'static frame::Dialect,
+
+    /// Check MAVLink 2 signatures with this key: 64 hex digits, or a passphrase hashed with SHA-256
+    #[arg(long, value_parser = signing::parse_key)]
+    signing_key: Option<[u8; 32]>,
+
+    /// Sign everything mavsnark sends (heartbeats, requests) with --signing-key under this link ID
+    #[arg(long, requires = "signing_key")]
+    signing_link_id: Option<u8>,
+
+    /// Decode messages defined in this dialect XML file (mavgen format), e.g. a private dialect
+    #[arg(long)]
+    definitions: Option<PathBuf>,
+
+    /// Send heartbeat with this system ID to enable mavlink-routerd sniffer mode
+    #[arg(long)]
+    heartbeat: Option<u8>,
+
+    /// Config file (default: $XDG_CONFIG_HOME/mavsnark/config.toml)
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// How to arrange the list and detail panels [default: auto, or as last saved]
+    #[arg(long, value_enum)]
+    layout: Option<app::LayoutMode>,
+
+    /// Message types shown as stream rows, replacing the defaults (comma separated)
+    #[arg(long, value_delimiter = ',')]
+    stream_types: Vec<String>,
+
+    /// Message types shown as events rather than stream rows (comma separated)
+    #[arg(long, value_delimiter = ',')]
+    event_types: Vec<String>,
+
+    /// Autopilot profile (px4, ardupilot, or one from the config)
+    #[arg(long)]
+    profile: Option<String>,
+
+    /// Open a session saved with Ctrl+s instead of connecting
+    #[arg(long, conflicts_with_all = ["uri", "heartbeat"])]
+    session: Option<PathBuf>,
+
+    /// Run headless: capture the --uri links and serve them to TUIs started
+    /// with --attach on this address (e.g. 0.0.0.0:5790)
+    #[arg(long, requires = "uri", conflicts_with_all = ["session", "tlog", "attach", "autosave", "trigger"])]
+    agent: Option<String>,
+
+    /// Run headless until every condition has been met on the --uri links,
+    /// then exit 0; 1 on --timeout, 2 if the links end. Prints a JSON
+    /// summary. NAME, or NAME.field OP value (e.g. GPS_RAW_INT.satellites_visible>=6)
+    #[arg(long, requires = "uri", conflicts_with_all = ["session", "tlog", "attach", "agent"])]
+    watch: Vec<watchdog::Condition>,
+
+    /// Run without the TUI, printing every message on the --uri links to
+    /// stdout as one JSON object per line
+    #[arg(long, requires = "uri", conflicts_with_all = ["session", "tlog", "attach", "agent", "watch"])]
+    headless: bool,
+
+    /// Push every decoded message as JSON to WebSocket clients on this
+    /// address (e.g. 0.0.0.0:8088) while the TUI runs
+    #[arg(long, conflicts_with_all = ["agent", "watch", "headless"])]
+    serve_ws: Option<String>,
+
+    /// Answer GET /stats, /vehicles and /messages/<type> with JSON, and
+    /// /metrics for Prometheus, on this address (e.g. 127.0.0.1:8089) while
+    /// the TUI runs
+    #[arg(long, conflicts_with_all = ["agent", "watch", "headless"])]
+    serve_http: Option<String>,
+
+    /// How long --watch waits for its conditions
+    #[arg(long, default_value = "60s", value_parser = autosave::parse_duration, requires = "watch")]
+    timeout: chrono::TimeDelta,
+
+    /// Sit between two links, e.g. QGroundControl and the autopilot, passing
+    /// everything from each on to the other unchanged while capturing both
+    #[arg(long, num_args = 2, value_names = ["GCS_URI", "VEHICLE_URI"], conflicts_with_all = ["uri", "heartbeat", "session", "tlog", "attach", "agent", "watch"])]
+    proxy: Vec<String>,
+
+    /// Browse the capture of an --agent at this address instead of connecting
+    #[arg(long, conflicts_with_all = ["uri", "heartbeat", "session", "tlog"])]
+    attach: Option<String>,
+
+    /// Shared secret an --attach TUI must send the --agent; required for an
+    /// agent listening beyond localhost
+    #[arg(long)]
+    agent_token: Option<String>,
+
+    /// Open telemetry logs (.tlog) instead of connecting; repeat to merge
+    /// captures of the same flight, aligned by SYSTEM_TIME
+    #[arg(long, conflicts_with_all = ["uri", "heartbeat", "session"])]
+    tlog: Vec<PathBuf>,
+
+    /// Start this far into each --tlog (e.g. 90s, 20m), seeking with its index
+    #[arg(long, value_parser = autosave::parse_duration, requires = "tlog")]
+    from: Option<chrono::TimeDelta>,
+
+    /// Play --tlog or --session back at the recorded pace (+/- to change the
+    /// speed) instead of loading it all at once
+    #[arg(long, requires = "recorded")]
+    replay: bool,
+
+    /// Print the duration and frames per message type of each --tlog and exit
+    #[arg(long, requires = "tlog")]
+    summary: bool,
+
+    /// Record everything received into session files in this directory
+    #[arg(long, conflicts_with_all = ["session", "tlog"])]
+    autosave: Option<PathBuf>,
+
+    /// Start a new autosave file after this size or time (e.g. 100MB, 1h)
+    #[arg(long, requires = "autosave", default_value = "100MB")]
+    rotate: autosave::Rotate,
+
+    /// Compress --autosave and --trigger files with zstd (.session.zst)
+    #[arg(long)]
+    compress: bool,
+
+    /// Save the traffic around each occurrence of a message type, or of any
+    /// alert with `alert`, to a session file (comma separated)
+    #[arg(long, value_delimiter = ',', conflicts_with_all = ["session", "tlog"])]
+    trigger: Vec<trigger::Trigger>,
+
+    /// Directory for --trigger captures
+    #[arg(long, default_value = ".", requires = "trigger")]
+    trigger_dir: PathBuf,
+
+    /// Traffic kept from before a trigger
+    #[arg(long, default_value = "30s", value_parser = autosave::parse_duration, requires = "trigger")]
+    pre_trigger: chrono::TimeDelta,
+
+    /// Traffic captured after a trigger
+    #[arg(long, default_value = "10s", value_parser = autosave::parse_duration, requires = "trigger")]
+    post_trigger: chrono::TimeDelta,
+
+    /// Append every event row to this file as it arrives
+    #[arg(long)]
+    mirror_events: Option<PathBuf>,
+
+    /// How --mirror-events writes each row
+    #[arg(long, value_enum, default_value_t = mirror::MirrorFormat::Text, requires = "mirror_events")]
+    mirror_format: mirror::MirrorFormat,
+
+    /// Write every frame received to this pcapng file, for Wireshark
+    #[arg(long)]
+    pcapng: Option<PathBuf>,
+
+    /// Events kept before the oldest are dropped; 0 keeps all
+    #[arg(long, default_value_t = 100_000)]
+    max_events: usize,
+
+    /// Earlier values kept per stream row
+    #[arg(long, default_value_t = 100)]
+    max_history_per_stream: usize,
+
+    /// Approximate memory for events, stream history and the session log (e.g. 512MB)
+    #[arg(long, value_parser = autosave::parse_size)]
+    max_memory: Option<u64>,
+
+    /// Write internal diagnostics to this file
+    #[arg(long)]
+    log_file: Option<PathBuf>,
+
+    /// Most detailed diagnostics written to --log-file (error, warn, info, debug, trace)
+    #[arg(long, default_value = "info", requires = "log_file")]
+    log_level: tracing::Level,
+
+    /// Draw with ASCII characters and the basic 8-colour palette
+    #[arg(long)]
+    ascii: bool,
+}
+
+/// The `mavsnark` command: parses the arguments and runs the TUI or one of
+/// the headless modes until it ends.
+#[tokio::main(flavor = "current_thread")]
+pub async fn run() -> io::Result<()> {
+    let args = Args::parse();
+    frame::extend(args.dialect);
+    if let Some(key) = args.signing_key {
+        signing::install(key);
+        if let Some(link_id) = args.signing_link_id {
+            signing::sign_outgoing(key, link_id);
+        }
+    }
+    if let Some(path) = &args.definitions {
+        let definitions = Definitions::load(path).map_err(|e| {
+            eprintln!("error: {e}");
+            io::Error::other(e)
+        })?;
+        if definitions.is_empty() {
+            eprintln!("error: {}: no <message> definitions", path.display());
+            return Err(io::Error::other("no message definitions"));
+        }
+        definitions::install(definitions);
+    }
+    if let Some(path) = &args.log_file {
+        logging::init(path, args.log_level).map_err(|e| {
+            eprintln!("error: {}: {e}", path.display());
+            e
+        })?;
+    }
+    let mut config = config::load(args.config).map_err(|e| {
+        eprintln!("error: {e}");
+        e
+    })?;
+    if let Some(name) = args
+        .stream_types
+        .iter()
+        .chain(&args.event_types)
+        .find(|name| !message::is_message_name(name))
+    {
+        eprintln!("error: unknown message type {name:?}");
+        return Err(io::Error::other("unknown message type"));
+    }
+    let templates = config.templates().map_err(|e| {
+        eprintln!("error: {e}");
+        io::Error::other(e)
+    })?;
+    let derived = config.derived().map_err(|e| {
+        eprintln!("error: {e}");
+        io::Error::other(e)
+    })?;
+    let highlights = config.highlights().map_err(|e| {
+        eprintln!("error: {e}");
+        io::Error::other(e)
+    })?;
+    let keymap = config.keymap().map_err(|e| {
+        eprintln!("error: {e}");
+        io::Error::other(e)
+    })?;
+    let profile = match &args.profile {
+        Some(name) => {
+            let Some(profile) = config.profile(name) else {
+                let known = config.profile_names().join(", ");
+                eprintln!("error: unknown profile {name:?} (available: {known})");
+                return Err(io::Error::other("unknown profile"));
+            };
+            config.apply_profile(&profile);
+            profile
+        }
+        None => profile::Profile::default(),
+    };
+
+    let session = match &args.session {
+        Some(path) => Some(session::load(path).map_err(|e| {
+            eprintln!("error: {e}");
+            e
+        })?),
+        None => None,
+    };
+    if args.summary {
+        return summarize(&args.tlog);
+    }
+    let tlogs = if args.tlog.is_empty() {
+        None
+    } else {
+        let skip = args.from.unwrap_or_default();
+        Some(tlog::merge(&args.tlog, skip).map_err(|e| {
+            eprintln!("error: {e}");
+            e
+        })?)
+    };
+
+    let (tx, rx) = tokio::sync::mpsc::channel::<Incoming>(app::CHANNEL_CAPACITY);
+    if let Some(listen) = &args.agent {
+        return run_agent(
+            listen,
+            args.uri,
+            args.heartbeat,
+            args.max_memory,
+            args.agent_token,
+            tx,
+            rx,
+        )
+        .await;
+    }
+    if !args.watch.is_empty() {
+        return run_watchdog(args.watch, args.timeout, args.uri, args.heartbeat, tx, rx).await;
+    }
+    if args.headless {
+        return run_headless(args.uri, args.heartbeat, tx, rx).await;
+    }
+    let attached = match &args.attach {
+        Some(addr) => Some(
+            agent::attach(addr, args.agent_token.as_deref(), tx.clone()).map_err(|e| {
+                eprintln!("error: {e}");
+                e
+            })?,
+        ),
+        None => None,
+    };
+    let fallback = fallback::Fallback::detect(args.ascii);
+    let mut terminal = ratatui::init();
+
+    let live = session.is_none() && tlogs.is_none();
+    let mut senders = None;
+    let uris = if let Some(links) = attached {
+        links
+    } else if !args.proxy.is_empty() {
+        connect_proxy(&args.proxy, &tx).inspect_err(|e| {
+            ratatui::restore();
+            eprintln!("error: {e}");
+        })?
+    } else if live {
+        match connect_all(&mut terminal, args.uri, fallback, &tx).await {
+            Ok(Some(links)) => {
+                let (uris, links): (Vec<_>, Vec<_>) = links.into_iter().unzip();
+                if let Some(system_id) = args.heartbeat {
+                    for link in &links {
+                        connection::spawn_heartbeat(link, system_id);
+                    }
+                    senders = Some((system_id, links));
+                }
+                uris
+            }
+            result => {
+                ratatui::restore();
+                return result.map(|_| ());
+            }
+        }
+    } else {
+        Vec::new()
+    };
+    drop(tx);
+
+    let recorder = match &args.autosave {
+        Some(dir) => {
+            match autosave::Recorder::new(dir.clone(), args.rotate, args.compress, uris.clone()) {
+                Ok(recorder) => Some(recorder),
+                Err(e) => {
+                    ratatui::restore();
+                    eprintln!("error: {}: {e}", dir.display());
+                    return Err(e);
+                }
+            }
+        }
+        None => None,
+    };
+
+    let capture = if args.trigger.is_empty() {
+        None
+    } else {
+        match trigger::RingCapture::new(
+            args.trigger_dir.clone(),
+            args.pre_trigger,
+            args.post_trigger,
+            args.trigger,
+            uris.clone(),
+            args.compress,
+        ) {
+            Ok(capture) => Some(capture),
+            Err(e) => {
+                ratatui::restore();
+                eprintln!("error: {}: {e}", args.trigger_dir.display());
+                return Err(e);
+            }
+        }
+    };
+
+    let mirror = match &args.mirror_events {
+        Some(path) => match mirror::EventMirror::new(path, args.mirror_format) {
+            Ok(mirror) => Some(mirror),
+            Err(e) => {
+                ratatui::restore();
+                eprintln!("error: {}: {e}", path.display());
+                return Err(e);
+            }
+        },
+        None => None,
+    };
+
+    let pcapng = match &args.pcapng {
+        Some(path) => match pcap::create(path, &uris) {
+            Ok(writer) => Some(writer),
+            Err(e) => {
+                ratatui::restore();
+                eprintln!("error: {}: {e}", path.display());
+                return Err(e);
+            }
+        },
+        None => None,
+    };
+
+    let websocket = match &args.serve_ws {
+        Some(listen) => match websocket::WsServer::bind(listen) {
+            Ok(server) => Some(server),
+            Err(e) => {
+                ratatui::restore();
+                eprintln!("error: {listen}: {e}");
+                return Err(e);
+            }
+        },
+        None => None,
+    };
+
+    let http = match &args.serve_http {
+        Some(listen) => match http::HttpServer::bind(listen) {
+            Ok(server) => Some(server),
+            Err(e) => {
+                ratatui::restore();
+                eprintln!("error: {listen}: {e}");
+                return Err(e);
+            }
+        },
+        None => None,
+    };
+
+    let preset = state::preset_key(&uris);
+    let mut app = app::App::new()
+        .with_links(uris, args.dedup)
+        .with_config(&config)
+        .with_templates(templates)
+        .with_derived(derived)
+        .with_highlights(highlights)
+        .with_keymap(keymap)
+        .with_limits(collector::Limits {
+            max_events: (args.max_events > 0).then_some(args.max_events),
+            max_history: args.max_history_per_stream,
+            max_memory: args.max_memory,
+        })
+        .with_state(state::load(&preset))
+        .with_profile(&profile)
+        .with_categories(&args.stream_types, &args.event_types)
+        .with_fallback(fallback);
+    if let Some(session) = session {
+        app = if args.replay {
+            app.with_state(session.meta.ui)
+                .with_replay(session.meta.links, session.records)
+        } else {
+            app.with_session(session)
+        };
+    }
+    if let Some(tlogs) = tlogs {
+        app = if args.replay {
+            app.with_replay(tlogs.meta.links, tlogs.records)
+        } else {
+            app.with_records(tlogs.meta.links, tlogs.records)
+        };
+    }
+    if let Some(recorder) = recorder {
+        app = app.with_recorder(recorder);
+    }
+    if let Some(capture) = capture {
+        app = app.with_capture(capture);
+    }
+    if let Some(mirror) = mirror {
+        app = app.with_mirror(mirror);
+    }
+    if let Some(pcapng) = pcapng {
+        app = app.with_pcapng(pcapng);
+    }
+    if let Some(server) = websocket {
+        app = app.with_websocket(server);
+    }
+    if let Some(server) = http {
+        app = app.with_http(server);
+    }
+    if let Some((system_id, links)) = senders {
+        app = app.with_senders(system_id, links);
+    }
+    if let Some(layout) = args.layout {
+        app = app.with_layout(layout);
+    }
+    let result = app.run(&mut terminal, rx).await;
+    ratatui::restore();
+    if live && let Err(e) = state::save(&preset, app.ui_state()) {
+        eprintln!("warning: could not save UI state: {e}");
+    }
+    result
+}
+
+/// `--summary`: what is in each log, from its index.
+fn summarize(paths: &[PathBuf]) -> io::Result<()> {
+    for path in paths {
+        let index = index::load_or_build(path).inspect_err(|e| eprintln!("error: {e}"))?;
+        println!("{}", path.display());
+        if let (Some(first), Some(last)) = (index.first(), index.last()) {
+            let secs = (last - first).num_milliseconds() as f64 / 1e3;
+            println!("  {first} .. {last} ({secs:.1}s)");
+        }
+        println!("  {} frames", index.frames());
+        for (name, count) in index.type_counts() {
+            println!("  {count:>10}  {name}");
+        }
+    }
+    Ok(())
+}
+
+/// `--agent`: no terminal, links given up front, everything received served
+/// to attached TUIs until the links end.
+async fn run_agent(
+    listen: &str,
+    uris: Vec<String>,
+    heartbeat: Option<u8>,
+    max_memory: Option<u64>,
+    token: Option<String>,
+    tx: tokio::sync::mpsc::Sender<Incoming>,
+    rx: tokio::sync::mpsc::Receiver<Incoming>,
+) -> io::Result<()> {
+    for (index, uri) in uris.iter().enumerate() {
+        let link = connection::connect(uri).inspect_err(|e| eprintln!("error: {e}"))?;
+        if let Some(system_id) = heartbeat {
+            connection::spawn_heartbeat(&link, system_id);
+        }
+        connection::spawn_reader(link, index, tx.clone());
+    }
+    drop(tx);
+    let agent = agent::Agent::bind(listen, uris, max_memory, token)
+        .inspect_err(|e| eprintln!("error: {listen}: {e}"))?;
+    eprintln!("serving on {}", agent.local_addr());
+    agent.serve(rx).await;
+    Ok(())
+}
+
+/// `--watch`: no terminal, checks every frame against the conditions and
+/// exits with the outcome's status.
+async fn run_watchdog(
+    conditions: Vec<watchdog::Condition>,
+    timeout: chrono::TimeDelta,
+    uris: Vec<String>,
+    heartbeat: Option<u8>,
+    tx: tokio::sync::mpsc::Sender<Incoming>,
+    mut rx: tokio::sync::mpsc::Receiver<Incoming>,
+) -> io::Result<()> {
+    for (index, uri) in uris.iter().enumerate() {
+        let link = connection::connect(uri).inspect_err(|e| eprintln!("error: {e}"))?;
+        if let Some(system_id) = heartbeat {
+            connection::spawn_heartbeat(&link, system_id);
+        }
+        connection::spawn_reader(link, index, tx.clone());
+    }
+    drop(tx);
+    let mut watchdog = watchdog::Watchdog::new(conditions, chrono::Utc::now());
+    let deadline = tokio::time::sleep(timeout.to_std().unwrap_or_default());
+    let mut deadline = std::pin::pin!(deadline);
+    let outcome = loop {
+        tokio::select! {
+            incoming = rx.recv() => match incoming {
+                Some(Incoming::Message(msg)) => {
+                    if watchdog.observe(&msg) {
+                        break watchdog::Outcome::Met;
+                    }
+                }
+                Some(Incoming::Diagnostic(_)) => {}
+                None => break watchdog::Outcome::Closed,
+            },
+            () = &mut deadline => break watchdog::Outcome::TimedOut,
+        }
+    };
+    println!("{}", watchdog.summary(outcome, chrono::Utc::now()));
+    std::process::exit(outcome.code());
+}
+
+/// `--headless`: no terminal, every message printed as a line of JSON until
+/// the links end or stdout is closed.
+async fn run_headless(
+    uris: Vec<String>,
+    heartbeat: Option<u8>,
+    tx: tokio::sync::mpsc::Sender<Incoming>,
+    mut rx: tokio::sync::mpsc::Receiver<Incoming>,
+) -> io::Result<()> {
+    for (index, uri) in uris.iter().enumerate() {
+        let link = connection::connect(uri).inspect_err(|e| eprintln!("error: {e}"))?;
+        if let Some(system_id) = heartbeat {
+            connection::spawn_heartbeat(&link, system_id);
+        }
+        connection::spawn_reader(link, index, tx.clone());
+    }
+    drop(tx);
+    let mut out = io::stdout().lock();
+    while let Some(incoming) = rx.recv().await {
+        let Some(line) = export::ndjson(&incoming) else {
+            continue;
+        };
+        // Stdout is line buffered, so each message goes out as it arrives.
+        match writeln!(out, "{line}") {
+            Ok(()) => {}
+            // `| head` and the like closing the pipe is a normal end.
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+/// `--proxy`: both links opened up front, each forwarding to the other.
+fn connect_proxy(
+    uris: &[String],
+    tx: &tokio::sync::mpsc::Sender<Incoming>,
+) -> io::Result<Vec<String>> {
+    let links = uris
+        .iter()
+        .map(|uri| connection::connect(uri))
+        .collect::<io::Result<Vec<_>>>()?;
+    for (index, link) in links.iter().enumerate() {
+        let peer = Arc::clone(&links[1 - index]);
+        connection::spawn_forwarder(Arc::clone(link), index, peer, tx.clone());
+    }
+    Ok(uris.to_vec())
+}
+
+/// Connects each of `uris` and starts its reader. When none were given or
+/// one fails, the wizard picks a replacement; `None` means the user quit.
+async fn connect_all(
+    terminal: &mut ratatui::DefaultTerminal,
+    uris: Vec<String>,
+    fallback: fallback::Fallback,
+    tx: &tokio::sync::mpsc::Sender<Incoming>,
+) -> io::Result<Option<Vec<(String, Arc<connection::Link>)>>> {
+    let mut pending: Vec<Option<String>> = uris.into_iter().map(Some).collect();
+    if pending.is_empty() {
+        pending.push(None);
+    }
+    let mut links = Vec::new();
+    for (index, uri) in pending.into_iter().enumerate() {
+        let error = match &uri {
+            Some(uri) => match connection::connect(uri) {
+                Ok(link) => {
+                    connection::spawn_reader(Arc::clone(&link), index, tx.clone());
+                    links.push((uri.clone(), link));
+                    continue;
+                }
+                Err(e) => Some(e.to_string()),
+            },
+            None => None,
+        };
+        let wizard = wizard::Wizard::new(uri.as_deref(), error, index);
+        let Some(mut chosen) = wizard.run(terminal, fallback).await? else {
+            return Ok(None);
+        };
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            while let Some(incoming) = chosen.rx.recv().await {
+                if tx.send(incoming).await.is_err() {
+                    break;
+                }
+            }
+        });
+        tracing::info!(uri = chosen.uri, "link chosen in wizard");
+        links.push((chosen.uri, chosen.link));
+    }
+    Ok(Some(links))
+}
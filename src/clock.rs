@@ -0,0 +1,401 @@
+use std::collections::{HashMap, VecDeque};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use mavlink::common::MavMessage;
+
+use crate::{entries::parse_fields, message::MavMsg};
+
+const MAX_SAMPLES: usize = 64;
+
+/// Minimum vehicle-time span (ms) before a drift estimate is trusted.
+const MIN_DRIFT_SPAN_MS: f64 = 2000.0;
+
+/// Largest drift a running clock is assumed to have (1%, far beyond any
+/// crystal), so a boot clock falling further behind the local one means
+/// the vehicle restarted.
+const MAX_RATE_ERROR: f64 = 0.01;
+
+/// How much later a sample may arrive than the previous one, beyond what the
+/// vehicle clock advanced, before it counts as a restart: link latency and
+/// buffering.
+const MAX_LATENCY_MS: f64 = 1000.0;
+
+/// `TIMESYNC.tc1` at or above this (2001 in ns since 1970) is a Unix epoch
+/// time; below it, time since boot. Both are in use by different stacks.
+const EPOCH_NS: i64 = 1_000_000_000_000_000_000;
+
+/// Per-component estimate of how the vehicle clock relates to the local
+/// clock.
+///
+/// Boot-relative samples (`SYSTEM_TIME.time_boot_ms`, `TIMESYNC.tc1`) are fit
+/// with a least-squares line `local = boot_epoch + rate * vehicle`, which
+/// gives both the local instant the vehicle booted and its drift. Wall-clock
+/// readings (`SYSTEM_TIME.time_unix_usec`, epoch `TIMESYNC.tc1`) give the
+/// offset between the two wall clocks instead.
+pub struct ClockEstimate {
+    samples: VecDeque<(f64, f64)>,
+    unix_offset_ms: Option<f64>,
+    /// Local minus vehicle wall clock from epoch `TIMESYNC`s, in ms.
+    epoch_offsets: VecDeque<f64>,
+}
+
+impl ClockEstimate {
+    fn new() -> Self {
+        Self {
+            samples: VecDeque::with_capacity(MAX_SAMPLES),
+            unix_offset_ms: None,
+            epoch_offsets: VecDeque::with_capacity(MAX_SAMPLES),
+        }
+    }
+
+    /// Adds a reading of the vehicle's boot clock taken at `local`. Both
+    /// sides are kept in milliseconds from here on.
+    fn add_boot_sample(&mut self, vehicle: TimeDelta, local: DateTime<Utc>) {
+        let vehicle_ms = vehicle.num_microseconds().unwrap_or(i64::MAX) as f64 / 1000.0;
+        let local_ms = local.timestamp_micros() as f64 / 1000.0;
+        // A vehicle reboot restarts its boot clock; old samples no longer
+        // apply. It shows as the boot clock going back, or, when the log has
+        // a gap longer than the old uptime, as it advancing far less than
+        // the local clock did.
+        if let Some(&(last_vehicle, last_local)) = self.samples.back() {
+            let advanced = vehicle_ms - last_vehicle;
+            let elapsed = local_ms - last_local;
+            if advanced < 0.0 || advanced < elapsed * (1.0 - MAX_RATE_ERROR) - MAX_LATENCY_MS {
+                self.samples.clear();
+            }
+        }
+        if self.samples.len() == MAX_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((vehicle_ms, local_ms));
+    }
+
+    /// Adds a reading of the vehicle's wall clock taken at `local`.
+    fn add_epoch_sample(&mut self, vehicle_ns: i64, local: DateTime<Utc>) {
+        let offset_ms = local.timestamp_micros() as f64 / 1000.0 - vehicle_ns as f64 / 1e6;
+        if self.epoch_offsets.len() == MAX_SAMPLES {
+            self.epoch_offsets.pop_front();
+        }
+        self.epoch_offsets.push_back(offset_ms);
+    }
+
+    /// Local wall clock minus vehicle wall clock, in ms: from `SYSTEM_TIME`,
+    /// or else averaged over the epoch `TIMESYNC`s.
+    pub fn unix_offset_ms(&self) -> Option<f64> {
+        self.unix_offset_ms.or_else(|| {
+            let n = self.epoch_offsets.len();
+            (n > 0).then(|| self.epoch_offsets.iter().sum::<f64>() / n as f64)
+        })
+    }
+
+    /// Returns `(boot_epoch_ms, rate)` of the fitted line.
+    fn fit(&self) -> Option<(f64, f64)> {
+        let (&(v0, _), &(v1, _)) = (self.samples.front()?, self.samples.back()?);
+        let n = self.samples.len() as f64;
+        let mean_v = self.samples.iter().map(|s| s.0).sum::<f64>() / n;
+        let mean_l = self.samples.iter().map(|s| s.1).sum::<f64>() / n;
+        let rate = if v1 - v0 >= MIN_DRIFT_SPAN_MS {
+            let (mut cov, mut var) = (0.0, 0.0);
+            for &(v, l) in &self.samples {
+                cov += (v - mean_v) * (l - mean_l);
+                var += (v - mean_v) * (v - mean_v);
+            }
+            cov / var
+        } else {
+            1.0
+        };
+        Some((mean_l - rate * mean_v, rate))
+    }
+
+    /// Vehicle clock drift relative to the local clock in parts per million.
+    /// Positive means the vehicle clock runs slow.
+    pub fn drift_ppm(&self) -> Option<f64> {
+        let (&(v0, _), &(v1, _)) = (self.samples.front()?, self.samples.back()?);
+        if v1 - v0 < MIN_DRIFT_SPAN_MS {
+            return None;
+        }
+        self.fit().map(|(_, rate)| (rate - 1.0) * 1e6)
+    }
+
+    /// Maps a vehicle boot-relative timestamp onto the local clock.
+    pub fn align(&self, time_boot_ms: u32) -> Option<DateTime<Utc>> {
+        let (boot_epoch, rate) = self.fit()?;
+        let local_ms = boot_epoch + rate * f64::from(time_boot_ms);
+        DateTime::from_timestamp_millis(local_ms.round() as i64)
+    }
+
+    /// When a message with these fields left the vehicle, on the local
+    /// clock; `None` unless it carries `time_boot_ms`.
+    pub fn sent(&self, fields: &str) -> Option<DateTime<Utc>> {
+        self.align(boot_ms(fields)?)
+    }
+}
+
+/// The `time_boot_ms` field of a message's rendered fields.
+pub fn boot_ms(fields: &str) -> Option<u32> {
+    parse_fields(fields)
+        .into_iter()
+        .find(|(key, _)| *key == "time_boot_ms")
+        .and_then(|(_, value)| value.parse().ok())
+}
+
+/// Clock estimates by `(system, component)`: a companion computer keeps a
+/// clock of its own next to the autopilot's.
+pub struct ClockTracker {
+    components: HashMap<(u8, u8), ClockEstimate>,
+}
+
+impl Default for ClockTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ClockTracker {
+    pub fn new() -> Self {
+        Self {
+            components: HashMap::new(),
+        }
+    }
+
+    pub fn observe(&mut self, msg: &MavMsg) {
+        let local = msg.timestamp;
+        let key = (msg.header.system_id, msg.header.component_id);
+        match &msg.msg {
+            MavMessage::SYSTEM_TIME(data) => {
+                let est = self.entry(key);
+                est.add_boot_sample(TimeDelta::milliseconds(data.time_boot_ms.into()), local);
+                if data.time_unix_usec != 0 {
+                    let offset_us = local.timestamp_micros() - data.time_unix_usec as i64;
+                    est.unix_offset_ms = Some(offset_us as f64 / 1000.0);
+                }
+            }
+            // Only responses carry the responder's clock in tc1, in ns.
+            MavMessage::TIMESYNC(data) if data.tc1 >= EPOCH_NS => {
+                self.entry(key).add_epoch_sample(data.tc1, local);
+            }
+            MavMessage::TIMESYNC(data) if data.tc1 > 0 => {
+                let est = self.entry(key);
+                est.add_boot_sample(TimeDelta::nanoseconds(data.tc1), local);
+            }
+            _ => {}
+        }
+    }
+
+    fn entry(&mut self, key: (u8, u8)) -> &mut ClockEstimate {
+        self.components
+            .entry(key)
+            .or_insert_with(ClockEstimate::new)
+    }
+
+    pub fn get(&self, sys_id: u8, comp_id: u8) -> Option<&ClockEstimate> {
+        self.components.get(&(sys_id, comp_id))
+    }
+
+    /// [`ClockEstimate::sent`] for a message from `sys_id`:`comp_id`.
+    pub fn sent(&self, sys_id: u8, comp_id: u8, fields: &str) -> Option<DateTime<Utc>> {
+        self.get(sys_id, comp_id)?.sent(fields)
+    }
+
+    pub fn clear(&mut self) {
+        self.components.clear();
+    }
+}
+
+/// Formats an aligned timestamp with millisecond precision.
+pub fn format_aligned(ts: DateTime<Utc>) -> String {
+    ts.format("%H:%M:%S%.3f").to_string()
+}
+
+/// Human readable offset/drift summary for the detail panel.
+pub fn summary(est: &ClockEstimate) -> String {
+    let offset = match est.unix_offset_ms() {
+        Some(ms) => format!("{:+.1} ms", ms),
+        None => "n/a".to_string(),
+    };
+    let drift = match est.drift_ppm() {
+        Some(ppm) => format!("{:+.1} ppm", ppm),
+        None => "n/a".to_string(),
+    };
+    format!("offset {offset}, drift {drift}")
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::{
+        MavHeader,
+        common::{SYSTEM_TIME_DATA, TIMESYNC_DATA},
+    };
+
+    use super::*;
+
+    fn make(msg: MavMessage, local_ms: i64) -> MavMsg {
+        make_from(1, msg, local_ms)
+    }
+
+    fn make_from(comp_id: u8, msg: MavMessage, local_ms: i64) -> MavMsg {
+        MavMsg {
+            header: MavHeader {
+                system_id: 1,
+                component_id: comp_id,
+                sequence: 0,
+            },
+            msg,
+            timestamp: DateTime::from_timestamp_millis(local_ms).unwrap(),
+            link: 0,
+            signature: None,
+            len: 0,
+            payload: Vec::new(),
+            frame: Vec::new(),
+        }
+    }
+
+    fn system_time(boot_ms: u32, unix_usec: u64, local_ms: i64) -> MavMsg {
+        make(
+            MavMessage::SYSTEM_TIME(SYSTEM_TIME_DATA {
+                time_unix_usec: unix_usec,
+                time_boot_ms: boot_ms,
+            }),
+            local_ms,
+        )
+    }
+
+    #[test]
+    fn unix_offset_from_system_time() {
+        let mut t = ClockTracker::new();
+        t.observe(&system_time(1000, 1_700_000_000_000_000, 1_700_000_000_250));
+        let est = t.get(1, 1).unwrap();
+        assert!((est.unix_offset_ms().unwrap() - 250.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn zero_unix_time_is_ignored() {
+        let mut t = ClockTracker::new();
+        t.observe(&system_time(1000, 0, 1_700_000_000_000));
+        assert!(t.get(1, 1).unwrap().unix_offset_ms().is_none());
+    }
+
+    #[test]
+    fn drift_needs_span() {
+        let mut t = ClockTracker::new();
+        t.observe(&system_time(1000, 0, 1_000_000));
+        t.observe(&system_time(1500, 0, 1_000_500));
+        assert!(t.get(1, 1).unwrap().drift_ppm().is_none());
+    }
+
+    #[test]
+    fn drift_detects_slow_vehicle_clock() {
+        let mut t = ClockTracker::new();
+        // Vehicle advances 999 ms for every 1000 local ms: ~1001 ppm slow.
+        for i in 0..10 {
+            t.observe(&system_time(i * 999, 0, 1_000_000 + i as i64 * 1000));
+        }
+        let ppm = t.get(1, 1).unwrap().drift_ppm().unwrap();
+        assert!((ppm - 1001.0).abs() < 1.0, "ppm = {ppm}");
+    }
+
+    #[test]
+    fn align_maps_boot_time_to_local() {
+        let mut t = ClockTracker::new();
+        t.observe(&system_time(5000, 0, 1_000_000));
+        let aligned = t.get(1, 1).unwrap().align(6000).unwrap();
+        assert_eq!(aligned.timestamp_millis(), 1_001_000);
+    }
+
+    #[test]
+    fn timesync_response_adds_sample() {
+        let mut t = ClockTracker::new();
+        t.observe(&make(
+            MavMessage::TIMESYNC(TIMESYNC_DATA {
+                tc1: 2_000_000_000,
+                ts1: 1,
+            }),
+            10_000,
+        ));
+        let aligned = t.get(1, 1).unwrap().align(2000).unwrap();
+        assert_eq!(aligned.timestamp_millis(), 10_000);
+    }
+
+    #[test]
+    fn epoch_timesync_gives_wall_clock_offset() {
+        let mut t = ClockTracker::new();
+        // 2023-11-14 22:13:20 UTC on the vehicle, seen 250 ms later locally.
+        t.observe(&make(
+            MavMessage::TIMESYNC(TIMESYNC_DATA {
+                tc1: 1_700_000_000_000_000_000,
+                ts1: 1,
+            }),
+            1_700_000_000_250,
+        ));
+        let est = t.get(1, 1).unwrap();
+        assert!((est.unix_offset_ms().unwrap() - 250.0).abs() < 1e-3);
+        // Not taken for a boot time 54 years in.
+        assert!(est.align(1000).is_none());
+    }
+
+    #[test]
+    fn components_keep_their_own_clocks() {
+        let mut t = ClockTracker::new();
+        t.observe(&system_time(5000, 0, 1_000_000));
+        t.observe(&make_from(
+            191,
+            MavMessage::SYSTEM_TIME(SYSTEM_TIME_DATA {
+                time_unix_usec: 0,
+                time_boot_ms: 90_000,
+            }),
+            1_000_000,
+        ));
+        assert_eq!(
+            t.sent(1, 1, "time_boot_ms: 5000")
+                .unwrap()
+                .timestamp_millis(),
+            1_000_000
+        );
+        assert_eq!(
+            t.sent(1, 191, "time_boot_ms: 90000")
+                .unwrap()
+                .timestamp_millis(),
+            1_000_000
+        );
+        assert!(t.get(1, 2).is_none());
+    }
+
+    #[test]
+    fn timesync_request_ignored() {
+        let mut t = ClockTracker::new();
+        t.observe(&make(
+            MavMessage::TIMESYNC(TIMESYNC_DATA { tc1: 0, ts1: 1 }),
+            10_000,
+        ));
+        assert!(t.get(1, 1).is_none());
+    }
+
+    #[test]
+    fn reboot_resets_samples() {
+        let mut t = ClockTracker::new();
+        t.observe(&system_time(100_000, 0, 1_000_000));
+        t.observe(&system_time(500, 0, 1_200_000));
+        let aligned = t.get(1, 1).unwrap().align(500).unwrap();
+        assert_eq!(aligned.timestamp_millis(), 1_200_000);
+    }
+
+    #[test]
+    fn reboot_during_a_gap_resets_samples() {
+        let mut t = ClockTracker::new();
+        t.observe(&system_time(100_000, 0, 1_000_000));
+        // An hour later the vehicle has been up for 200 s since a restart.
+        t.observe(&system_time(200_000, 0, 4_600_000));
+        let aligned = t.get(1, 1).unwrap().align(200_000).unwrap();
+        assert_eq!(aligned.timestamp_millis(), 4_600_000);
+    }
+
+    #[test]
+    fn sent_reads_time_boot_ms() {
+        let mut t = ClockTracker::new();
+        t.observe(&system_time(5000, 0, 1_000_000));
+        let sent = t.sent(1, 1, "time_boot_ms: 5250, roll: 0.1").unwrap();
+        assert_eq!(sent.timestamp_millis(), 1_000_250);
+        assert!(t.sent(1, 1, "roll: 0.1").is_none());
+        assert!(t.sent(2, 1, "time_boot_ms: 5250").is_none());
+    }
+}
@@ -1,12 +1,132 @@
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    mem,
+};
+
+use chrono::{DateTime, Utc};
+use mavlink::{Message, common::MavMessage};
+use ratatui::style::Color;
 
 use crate::{
-    entries::{MessageEntry, StreamEntry},
-    message::MavMsg,
+    alerts::{RateAlertKind, RateMonitor},
+    battery::BatteryTracker,
+    capabilities::CapabilityTracker,
+    cellular::CellularTracker,
+    clock::{ClockEstimate, ClockTracker},
+    commands::CommandTracker,
+    config::Config,
+    connection,
+    counts::MessageCounts,
+    dedup::{DedupMode, Deduplicator, Delivery, LinkStats},
+    definitions,
+    derived::DerivedFields,
+    direction::{Direction, DirectionClassifier},
+    distance::DistanceTracker,
+    entries::{Report, Sample},
+    flow::FlowGraph,
+    follow::FollowTracker,
+    frame::{self, FrameParser},
+    gps::GpsTracker,
+    home::HomeTracker,
+    imu::ImuTracker,
+    interference::InterferenceTracker,
+    intervals::IntervalTracker,
+    map::MapTracker,
+    message::{Diagnostic, Incoming, MavMsg, id_color},
+    mission::{MissionReport, MissionTracker, MissionUpdate},
+    navigation::NavigationTracker,
+    params::ParamTracker,
+    power::PowerTracker,
+    radio::RadioTracker,
+    rate::ArrivalStats,
+    routing::RouteTracker,
+    safety::SafetyTracker,
+    security::SigningMonitor,
+    signing::SignatureStatus,
+    statustext::StatusConsole,
+    talkers::TopTalkers,
+    terrain::TerrainTracker,
+    throughput::RateHistory,
+    uavcan::UavcanNodes,
+    vibration::VibrationTracker,
+    vtol::VtolTracker,
+    winch::WinchTracker,
 };
 
+// The rows traffic is sorted into, part of the `Collector` API.
+pub use crate::entries::{EventKind, MessageEntry, StreamEntry};
+
 type StreamKey = (u8, u8, &'static str);
 
+/// Caps on what the collector keeps, from `--max-events`,
+/// `--max-history-per-stream` and `--max-memory`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limits {
+    /// Events kept before the oldest are dropped; `None` keeps all.
+    pub max_events: Option<usize>,
+    /// Earlier values kept per stream row.
+    pub max_history: usize,
+    /// Approximate bytes for events and stream history together. Events go
+    /// first, oldest first, then history.
+    pub max_memory: Option<u64>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_events: None,
+            max_history: 100,
+            max_memory: None,
+        }
+    }
+}
+
+/// What the collector currently holds, for the Stats tab.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Usage {
+    pub events: usize,
+    /// Events dropped to stay within the limits since the last clear.
+    pub evicted: usize,
+    pub history: usize,
+    /// Approximate bytes used by events and history.
+    pub bytes: u64,
+}
+
+/// Errors of one link since the last clear, by
+/// [`FrameErrorKind`](crate::frame::FrameErrorKind).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LinkErrors {
+    pub crc: usize,
+    pub length: usize,
+    /// Runs of bytes skipped between frames.
+    pub framing: usize,
+    pub decode: usize,
+}
+
+impl LinkErrors {
+    fn count(&mut self, name: &str) {
+        match name {
+            "CRC_ERROR" => self.crc += 1,
+            "LENGTH_ERROR" => self.length += 1,
+            "FRAMING_ERROR" => self.framing += 1,
+            "DECODE_ERROR" => self.decode += 1,
+            _ => {}
+        }
+    }
+}
+
+fn event_size(entry: &MessageEntry) -> u64 {
+    (mem::size_of::<MessageEntry>()
+        + entry.fields.capacity()
+        + entry.payload.capacity()
+        + entry.frame.capacity()) as u64
+}
+
+fn history_size(sample: &Sample) -> u64 {
+    (mem::size_of::<Sample>() + sample.fields.len() + sample.payload.len() + sample.frame.len())
+        as u64
+}
+
 const DEFAULT_STREAM_TYPES: &[&str] = &[
     "HEARTBEAT",
     "SYS_STATUS",
@@ -53,15 +173,94 @@ const DEFAULT_STREAM_TYPES: &[&str] = &[
     "TIMESYNC",
     "PING",
     "LINK_NODE_STATUS",
+    "RADIO_STATUS",
     "ACTUATOR_OUTPUT_STATUS",
     "FLIGHT_INFORMATION",
+    "UAVCAN_NODE_STATUS",
+    "UAVCAN_NODE_INFO",
+    "CELLULAR_STATUS",
+    "CELLULAR_CONFIG",
+    "WINCH_STATUS",
+    "FOLLOW_TARGET",
+    // ArduPilot telemetry, decoded with `--dialect ardupilotmega`.
+    "AHRS",
+    "AHRS2",
+    "EKF_STATUS_REPORT",
+    "HWSTATUS",
+    "MEMINFO",
+    "RANGEFINDER",
+    "WIND",
 ];
 
+/// Everything received, sorted into stream rows (latest value per
+/// `(system, component, message type)`) and events, with the per-message
+/// trackers fed along the way. Feed it with [`Collector::push`] and
+/// [`Collector::push_diagnostic`] and call [`Collector::tick`] regularly
+/// so time-based checks (stalled rates, command timeouts) can fire.
 pub struct Collector {
     stream: Vec<StreamEntry>,
     stream_index: HashMap<StreamKey, usize>,
-    messages: Vec<MessageEntry>,
+    messages: VecDeque<MessageEntry>,
     stream_types: HashSet<&'static str>,
+    clock: ClockTracker,
+    /// Stamp entries with when the vehicle sent them rather than when they
+    /// arrived; see [`Collector::set_clock_alignment`].
+    align_clock: bool,
+    dedup: Deduplicator,
+    dedup_mode: DedupMode,
+    signing: SigningMonitor,
+    rates: RateMonitor,
+    commands: CommandTracker,
+    mission: MissionTracker,
+    radio: RadioTracker,
+    flow: FlowGraph,
+    routes: RouteTracker,
+    directions: DirectionClassifier,
+    talkers: TopTalkers,
+    throughput: RateHistory,
+    safety: SafetyTracker,
+    uavcan: UavcanNodes,
+    cellular: CellularTracker,
+    vtol: VtolTracker,
+    winch: WinchTracker,
+    follow: FollowTracker,
+    imu: ImuTracker,
+    interference: InterferenceTracker,
+    gps: GpsTracker,
+    vibration: VibrationTracker,
+    power: PowerTracker,
+    battery: BatteryTracker,
+    distance: DistanceTracker,
+    params: ParamTracker,
+    statustext: StatusConsole,
+    counts: MessageCounts,
+    /// By link.
+    link_errors: BTreeMap<usize, LinkErrors>,
+    home: HomeTracker,
+    map: MapTracker,
+    navigation: NavigationTracker,
+    terrain: TerrainTracker,
+    intervals: IntervalTracker,
+    capabilities: CapabilityTracker,
+    derived: DerivedFields,
+    /// Alert and security events pushed since the last clear.
+    alerts: usize,
+    limits: Limits,
+    event_bytes: u64,
+    history_bytes: u64,
+    evicted: usize,
+    /// Events dropped for the limits since [`Collector::take_evicted`], once
+    /// [`Collector::keep_evicted`] asked for them.
+    kept_evicted: Option<Vec<MessageEntry>>,
+    /// Bumped whenever stream rows or events change; see
+    /// [`Collector::revision`].
+    revision: u64,
+}
+
+impl Default for Collector {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Collector {
@@ -69,46 +268,260 @@ impl Collector {
         Self {
             stream: Vec::new(),
             stream_index: HashMap::new(),
-            messages: Vec::new(),
+            messages: VecDeque::new(),
             stream_types: DEFAULT_STREAM_TYPES.iter().copied().collect(),
+            clock: ClockTracker::new(),
+            align_clock: false,
+            dedup: Deduplicator::new(),
+            dedup_mode: DedupMode::Merge,
+            signing: SigningMonitor::new(),
+            rates: RateMonitor::new(&Config::default()),
+            commands: CommandTracker::new(Config::default().command_timeout()),
+            mission: MissionTracker::new(),
+            radio: RadioTracker::new(),
+            flow: FlowGraph::new(),
+            routes: RouteTracker::new(),
+            directions: DirectionClassifier::new(),
+            talkers: TopTalkers::new(),
+            throughput: RateHistory::new(),
+            safety: SafetyTracker::default(),
+            uavcan: UavcanNodes::default(),
+            cellular: CellularTracker::default(),
+            vtol: VtolTracker::default(),
+            winch: WinchTracker::default(),
+            follow: FollowTracker::default(),
+            imu: ImuTracker::default(),
+            interference: InterferenceTracker::default(),
+            gps: GpsTracker::default(),
+            vibration: VibrationTracker::default(),
+            power: PowerTracker::default(),
+            battery: BatteryTracker::default(),
+            distance: DistanceTracker::default(),
+            params: ParamTracker::default(),
+            statustext: StatusConsole::default(),
+            counts: MessageCounts::default(),
+            link_errors: BTreeMap::new(),
+            home: HomeTracker::default(),
+            map: MapTracker::default(),
+            navigation: NavigationTracker::default(),
+            terrain: TerrainTracker::default(),
+            intervals: IntervalTracker::default(),
+            capabilities: CapabilityTracker::default(),
+            derived: DerivedFields::default(),
+            alerts: 0,
+            limits: Limits::default(),
+            event_bytes: 0,
+            history_bytes: 0,
+            evicted: 0,
+            kept_evicted: None,
+            revision: 0,
         }
     }
 
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+        for entry in &mut self.stream {
+            while entry.history.len() > limits.max_history {
+                if let Some(old) = entry.history.pop_front() {
+                    self.history_bytes -= history_size(&old);
+                }
+            }
+        }
+        self.enforce_limits();
+    }
+
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    pub fn usage(&self) -> Usage {
+        Usage {
+            events: self.messages.len(),
+            evicted: self.evicted,
+            history: self.stream.iter().map(|e| e.history.len()).sum(),
+            bytes: self.event_bytes + self.history_bytes,
+        }
+    }
+
+    /// Drops the oldest events, then the oldest history, until within the
+    /// limits.
+    fn enforce_limits(&mut self) {
+        self.revision += 1;
+        let max_events = self.limits.max_events.unwrap_or(usize::MAX);
+        let max_memory = self.limits.max_memory.unwrap_or(u64::MAX);
+        while self.messages.len() > max_events
+            || (self.event_bytes + self.history_bytes > max_memory && !self.messages.is_empty())
+        {
+            let Some(entry) = self.messages.pop_front() else {
+                break;
+            };
+            self.event_bytes -= event_size(&entry);
+            self.evicted += 1;
+            if let Some(kept) = &mut self.kept_evicted {
+                kept.push(entry);
+            }
+        }
+        while self.event_bytes + self.history_bytes > max_memory {
+            let Some(entry) = self
+                .stream
+                .iter_mut()
+                .filter(|e| !e.history.is_empty())
+                .min_by_key(|e| e.history[0].timestamp)
+            else {
+                break;
+            };
+            if let Some(old) = entry.history.pop_front() {
+                self.history_bytes -= history_size(&old);
+            }
+        }
+    }
+
+    /// Recomputes the byte counts after entries were removed in bulk.
+    fn recount(&mut self) {
+        self.revision += 1;
+        self.event_bytes = self.messages.iter().map(event_size).sum();
+        self.history_bytes = self
+            .stream
+            .iter()
+            .flat_map(|e| &e.history)
+            .map(history_size)
+            .sum();
+    }
+
+    /// For recorded traffic: stamps messages that carry `time_boot_ms` with
+    /// when the vehicle sent them, by its clock estimate, instead of when
+    /// the capturing machine received them, so logs recorded on different
+    /// machines line up.
+    pub fn set_clock_alignment(&mut self, on: bool) {
+        self.align_clock = on;
+    }
+
+    pub fn set_dedup_mode(&mut self, mode: DedupMode) {
+        self.dedup_mode = mode;
+    }
+
+    pub fn set_derived(&mut self, derived: DerivedFields) {
+        self.derived = derived;
+    }
+
+    pub fn set_config(&mut self, config: &Config) {
+        self.rates = RateMonitor::new(config);
+        self.commands = CommandTracker::new(config.command_timeout());
+        self.gps = GpsTracker::new(config.gps.divergence_m);
+        self.vibration = VibrationTracker::new(config.vibration.warn_level);
+        self.home = HomeTracker::new(config.home.max_range_m);
+        self.terrain = TerrainTracker::new(config.terrain.min_clearance_m);
+    }
+
     pub fn push(&mut self, msg: MavMsg) {
+        let duplicate = match self.dedup_mode {
+            DedupMode::Off => false,
+            _ => self.dedup.observe(&msg) != Delivery::First,
+        };
+        // Duplicates still cost bandwidth on the link they arrived on.
+        self.counts.observe_link(&msg);
+        self.talkers.observe(&msg);
+        self.throughput.observe(&msg);
+        if let Some(migration) = self.routes.observe(&msg) {
+            self.push_event(MessageEntry {
+                msg_color: Some(Color::Yellow),
+                ..MessageEntry::event(
+                    EventKind::Alert,
+                    migration.name(),
+                    migration.describe(),
+                    (migration.sys_id, 0),
+                    migration.to,
+                    msg.timestamp,
+                )
+            });
+        }
+        if duplicate && self.dedup_mode == DedupMode::Merge {
+            return;
+        }
+        let mut reports: Vec<Box<dyn Report>> = Vec::new();
+        let (anomaly, mission) = if duplicate {
+            (None, MissionUpdate::None)
+        } else {
+            self.counts.observe(&msg);
+            self.clock.observe(&msg);
+            self.commands.observe(&msg);
+            self.radio.observe(&msg);
+            self.flow.observe(&msg);
+            self.follow.observe(&msg);
+            self.imu.observe(&msg);
+            self.interference.observe(&msg);
+            self.battery.observe(&msg);
+            self.distance.observe(&msg);
+            self.params.observe(&msg);
+            self.statustext.observe(&msg);
+            self.navigation.observe(&msg);
+            self.map.observe(&msg);
+            self.intervals.observe(&msg);
+            self.capabilities.observe(&msg);
+            fn boxed(r: impl Report + 'static) -> Box<dyn Report> {
+                Box::new(r)
+            }
+            reports.extend(self.safety.observe(&msg).map(boxed));
+            reports.extend(self.uavcan.observe(&msg).map(boxed));
+            reports.extend(self.cellular.observe(&msg).map(boxed));
+            reports.extend(self.vtol.observe(&msg).map(boxed));
+            reports.extend(self.winch.observe(&msg).map(boxed));
+            reports.extend(self.gps.observe(&msg).map(boxed));
+            reports.extend(self.vibration.observe(&msg).map(boxed));
+            reports.extend(self.power.observe(&msg).map(boxed));
+            reports.extend(self.home.observe(&msg).map(boxed));
+            reports.extend(self.terrain.observe(&msg).map(boxed));
+            (self.signing.observe(&msg), self.mission.observe(&msg))
+        };
+
         let sys_color = msg.sys_color();
         let comp_color = msg.comp_color();
         let msg_color = msg.msg_color();
         let sys_id = msg.header.system_id;
         let comp_id = msg.header.component_id;
         let name = msg.msg_type();
-        let fields = msg.fields();
-        let timestamp = msg.timestamp;
+        let fields = self.derived.apply(name, msg.fields());
+        let timestamp = if self.align_clock {
+            self.clock
+                .sent(sys_id, comp_id, &fields)
+                .unwrap_or(msg.timestamp)
+        } else {
+            msg.timestamp
+        };
+        let link = msg.link;
+        let v1 = msg.is_v1();
+        let signature = msg.signature_status();
+        let trailer = msg.signature;
+        let direction = self.directions.classify(&msg);
+        let payload = msg.payload;
+        let frame = msg.frame;
 
         if self.stream_types.contains(name) {
-            let key = (sys_id, comp_id, name);
-            if let Some(&idx) = self.stream_index.get(&key) {
-                let entry = &mut self.stream[idx];
-                entry.sys_color = sys_color;
-                entry.comp_color = comp_color;
-                entry.msg_color = msg_color;
-                entry.fields = fields;
-                entry.timestamp = timestamp;
-            } else {
-                let idx = self.stream.len();
-                self.stream_index.insert(key, idx);
-                self.stream.push(StreamEntry {
-                    sys_color,
-                    comp_color,
-                    msg_color,
-                    sys_id,
-                    comp_id,
-                    name,
-                    fields,
-                    timestamp,
-                });
+            // A late copy must not overwrite the row the first link already updated.
+            if duplicate {
+                return;
             }
-        } else {
-            self.messages.push(MessageEntry {
+            self.upsert_stream(StreamEntry {
+                sys_color,
+                comp_color,
+                msg_color,
+                sys_id,
+                comp_id,
+                name,
+                fields,
+                timestamp,
+                link,
+                v1,
+                signature,
+                arrivals: ArrivalStats::default(),
+                direction,
+                payload,
+                frame,
+                history: VecDeque::new(),
+            });
+        } else if mission != MissionUpdate::Retry {
+            // Repeated mission requests are summarised once the transfer ends.
+            self.push_event(MessageEntry {
                 sys_color,
                 comp_color,
                 msg_color,
@@ -116,18 +529,493 @@ impl Collector {
                 comp_id,
                 name,
                 fields,
+                link,
+                v1,
+                signature,
+                duplicate,
+                kind: EventKind::Message,
+                direction: Some(direction),
+                payload,
+                frame,
+                timestamp,
             });
         }
+
+        if let MissionUpdate::Report(report) = mission {
+            self.push_mission_report(report, link, timestamp);
+        }
+
+        for report in reports {
+            self.push_report(report.as_ref(), link, timestamp);
+        }
+
+        if let Some(anomaly) = anomaly {
+            self.push_event(MessageEntry {
+                msg_color: Some(Color::Red),
+                ..MessageEntry::event(
+                    EventKind::Security,
+                    anomaly.name(),
+                    anomaly.describe(),
+                    (sys_id, comp_id),
+                    link,
+                    timestamp,
+                )
+            });
+        }
+
+        if signature == SignatureStatus::Invalid
+            && let Some(trailer) = trailer
+        {
+            self.push_event(MessageEntry {
+                msg_color: Some(Color::Red),
+                signature,
+                ..MessageEntry::event(
+                    EventKind::Security,
+                    "SIGNATURE_INVALID",
+                    format!(
+                        "msg: {name}, link_id: {}, timestamp: {}",
+                        trailer.link_id, trailer.timestamp
+                    ),
+                    (sys_id, comp_id),
+                    link,
+                    timestamp,
+                )
+            });
+        }
+    }
+
+    fn push_mission_report(&mut self, report: MissionReport, link: usize, at: DateTime<Utc>) {
+        let (requester, color) = match report {
+            MissionReport::Retried { requester, .. } => (requester, Color::Yellow),
+            MissionReport::Stalled { requester, .. } => (requester, Color::LightRed),
+        };
+        self.push_event(MessageEntry {
+            msg_color: Some(color),
+            ..MessageEntry::event(
+                EventKind::Diagnostic,
+                report.name(),
+                report.describe(),
+                (requester, 0),
+                link,
+                at,
+            )
+        });
+    }
+
+    /// An event raised by one of the trackers: an alert, or a green note
+    /// when things are as they should be.
+    fn push_report(&mut self, report: &dyn Report, link: usize, at: DateTime<Utc>) {
+        let (sys_id, comp_id) = report.source();
+        let (color, kind) = if report.is_alert() {
+            (Color::LightRed, EventKind::Alert)
+        } else {
+            (Color::Green, EventKind::Diagnostic)
+        };
+        self.push_event(MessageEntry {
+            msg_color: Some(color),
+            ..MessageEntry::event(
+                kind,
+                report.name(),
+                report.describe(),
+                (sys_id, comp_id),
+                link,
+                at,
+            )
+        });
+    }
+
+    pub fn push_diagnostic(&mut self, diag: Diagnostic) {
+        if !diag.is_hint() {
+            return self.push_extension(diag);
+        }
+        if diag.is_error() {
+            self.link_errors
+                .entry(diag.link)
+                .or_default()
+                .count(diag.name);
+        }
+        self.push_event(MessageEntry {
+            msg_color: Some(Color::Yellow),
+            frame: diag.frame,
+            ..MessageEntry::event(
+                EventKind::Diagnostic,
+                diag.name,
+                diag.fields,
+                (diag.header.system_id, diag.header.component_id),
+                diag.link,
+                diag.timestamp,
+            )
+        });
+    }
+
+    /// A message decoded with `--dialect` or `--definitions`: a stream row or
+    /// event like any other, but without the trackers, which only know
+    /// `common`.
+    fn push_extension(&mut self, diag: Diagnostic) {
+        let sys_color = id_color(diag.header.system_id);
+        let comp_color = id_color(diag.header.component_id);
+        let direction = self.directions.classify_sender(&diag.header);
+        if self.stream_types.contains(diag.name) {
+            self.upsert_stream(StreamEntry {
+                sys_color,
+                comp_color,
+                msg_color: None,
+                sys_id: diag.header.system_id,
+                comp_id: diag.header.component_id,
+                name: diag.name,
+                fields: diag.fields,
+                v1: false,
+                signature: SignatureStatus::Unsigned,
+                timestamp: diag.timestamp,
+                link: diag.link,
+                arrivals: ArrivalStats::default(),
+                direction,
+                payload: Vec::new(),
+                frame: diag.frame,
+                history: VecDeque::new(),
+            });
+        } else {
+            self.push_event(MessageEntry {
+                direction: Some(direction),
+                frame: diag.frame,
+                ..MessageEntry::event(
+                    EventKind::Message,
+                    diag.name,
+                    diag.fields,
+                    (diag.header.system_id, diag.header.component_id),
+                    diag.link,
+                    diag.timestamp,
+                )
+            });
+        }
+    }
+
+    /// Replaces the fields of `row`'s `(system, component, message)` row,
+    /// keeping the old ones as history, or adds it as a new row.
+    fn upsert_stream(&mut self, mut row: StreamEntry) {
+        let key = (row.sys_id, row.comp_id, row.name);
+        if let Some(&idx) = self.stream_index.get(&key) {
+            let entry = &mut self.stream[idx];
+            entry.sys_color = row.sys_color;
+            entry.comp_color = row.comp_color;
+            entry.msg_color = row.msg_color;
+            let previous = Sample {
+                timestamp: entry.timestamp,
+                fields: mem::replace(&mut entry.fields, row.fields),
+                payload: mem::replace(&mut entry.payload, row.payload),
+                frame: mem::replace(&mut entry.frame, row.frame),
+            };
+            if self.limits.max_history > 0 {
+                self.history_bytes += history_size(&previous);
+                entry.history.push_back(previous);
+                if entry.history.len() > self.limits.max_history
+                    && let Some(old) = entry.history.pop_front()
+                {
+                    self.history_bytes -= history_size(&old);
+                }
+            }
+            entry.timestamp = row.timestamp;
+            entry.v1 = row.v1;
+            entry.signature = row.signature;
+            entry.arrivals.record(row.timestamp);
+            entry.direction = row.direction;
+        } else {
+            self.stream_index.insert(key, self.stream.len());
+            row.arrivals.record(row.timestamp);
+            self.stream.push(row);
+        }
+        self.enforce_limits();
+    }
+
+    /// Runs bytes read from link `link` through the whole pipeline: framing
+    /// in `parser`, decoding, then [`Collector::push`] or
+    /// [`Collector::push_diagnostic`]. A frame split across calls is picked
+    /// up once the rest arrives. Any input is accepted, so this is the entry
+    /// point for fuzzing.
+    pub fn ingest(&mut self, parser: &mut FrameParser, bytes: &[u8], link: usize) {
+        parser.push(bytes);
+        while let Some(parsed) = parser.next_frame() {
+            match connection::decode(parsed, link) {
+                Incoming::Message(msg) => self.push(msg),
+                Incoming::Diagnostic(diag) => self.push_diagnostic(diag),
+            }
+        }
+    }
+
+    /// Logs something mavsnark itself did, such as saving a file.
+    pub fn push_note(&mut self, name: &'static str, fields: String) {
+        tracing::info!(name, fields, "note");
+        self.push_event(MessageEntry {
+            msg_color: Some(Color::Gray),
+            ..MessageEntry::event(EventKind::Diagnostic, name, fields, (0, 0), 0, Utc::now())
+        });
+    }
+
+    /// Raises the time-based alerts: rate deviations, unanswered commands and
+    /// stalled mission transfers.
+    pub fn tick(&mut self, now: DateTime<Utc>) {
+        for alert in self.rates.check(&self.stream, now) {
+            let color = match alert.kind {
+                RateAlertKind::Deviation => Color::LightRed,
+                RateAlertKind::Recovered => Color::Green,
+            };
+            self.push_event(MessageEntry {
+                msg_color: Some(color),
+                ..MessageEntry::event(
+                    EventKind::Alert,
+                    alert.event_name(),
+                    alert.describe(),
+                    (alert.sys_id, alert.comp_id),
+                    alert.link,
+                    now,
+                )
+            });
+        }
+        for (report, link) in self.mission.stalled(now) {
+            self.push_mission_report(report, link, now);
+        }
+        for unanswered in self.commands.expire(now) {
+            self.push_event(MessageEntry {
+                msg_color: Some(Color::LightRed),
+                ..MessageEntry::event(
+                    EventKind::Alert,
+                    unanswered.name(),
+                    unanswered.describe(),
+                    (unanswered.sys_id, unanswered.comp_id),
+                    unanswered.link,
+                    now,
+                )
+            });
+        }
+    }
+
+    fn push_event(&mut self, entry: MessageEntry) {
+        if matches!(entry.kind, EventKind::Alert | EventKind::Security) {
+            self.alerts += 1;
+        }
+        self.event_bytes += event_size(&entry);
+        self.messages.push_back(entry);
+        self.enforce_limits();
+    }
+
+    /// Holds on to events dropped for the limits until
+    /// [`Collector::take_evicted`], so a view can tell which of its rows went.
+    pub fn keep_evicted(&mut self) {
+        self.kept_evicted.get_or_insert_default();
+    }
+
+    /// Events dropped for the limits since the last call, oldest first.
+    pub fn take_evicted(&mut self) -> Vec<MessageEntry> {
+        self.kept_evicted
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// Events held so far, including those dropped for the limits; starts
+    /// again from zero on [`Collector::clear`].
+    pub fn event_total(&self) -> usize {
+        self.messages.len() + self.evicted
+    }
+
+    pub fn alert_count(&self) -> usize {
+        self.alerts
+    }
+
+    /// Systems sending heartbeats that are not ground stations.
+    pub fn vehicle_count(&self) -> usize {
+        self.stream
+            .iter()
+            .filter(|e| e.name == "HEARTBEAT" && e.direction != Direction::Uplink)
+            .map(|e| e.sys_id)
+            .collect::<HashSet<_>>()
+            .len()
     }
 
     pub fn stream(&self) -> &[StreamEntry] {
         &self.stream
     }
 
-    pub fn messages(&self) -> &[MessageEntry] {
+    /// The stream row of `name` from `sys_id`:`comp_id`, if there is one.
+    pub fn stream_entry(&self, sys_id: u8, comp_id: u8, name: &str) -> Option<&StreamEntry> {
+        let &index = self.stream_index.get(&(sys_id, comp_id, name))?;
+        self.stream.get(index)
+    }
+
+    pub fn messages(&self) -> &VecDeque<MessageEntry> {
         &self.messages
     }
 
+    /// Changes whenever [`Collector::stream`] or [`Collector::messages`] may
+    /// have, so views can keep what they derive from them until it does.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    pub fn clock(&self, sys_id: u8, comp_id: u8) -> Option<&ClockEstimate> {
+        self.clock.get(sys_id, comp_id)
+    }
+
+    pub fn radio(&self) -> &RadioTracker {
+        &self.radio
+    }
+
+    pub fn flow(&self) -> &FlowGraph {
+        &self.flow
+    }
+
+    pub fn talkers(&self) -> &TopTalkers {
+        &self.talkers
+    }
+
+    pub fn throughput(&self) -> &RateHistory {
+        &self.throughput
+    }
+
+    pub fn safety(&self) -> &SafetyTracker {
+        &self.safety
+    }
+
+    pub fn uavcan(&self) -> &UavcanNodes {
+        &self.uavcan
+    }
+
+    pub fn cellular(&self) -> &CellularTracker {
+        &self.cellular
+    }
+
+    pub fn vtol(&self) -> &VtolTracker {
+        &self.vtol
+    }
+
+    pub fn winch(&self) -> &WinchTracker {
+        &self.winch
+    }
+
+    pub fn follow(&self) -> &FollowTracker {
+        &self.follow
+    }
+
+    pub fn imu(&self) -> &ImuTracker {
+        &self.imu
+    }
+
+    pub fn interference(&self) -> &InterferenceTracker {
+        &self.interference
+    }
+
+    pub fn gps(&self) -> &GpsTracker {
+        &self.gps
+    }
+
+    pub fn vibration(&self) -> &VibrationTracker {
+        &self.vibration
+    }
+
+    pub fn power(&self) -> &PowerTracker {
+        &self.power
+    }
+
+    pub fn battery(&self) -> &BatteryTracker {
+        &self.battery
+    }
+
+    pub fn distance(&self) -> &DistanceTracker {
+        &self.distance
+    }
+
+    pub fn params(&self) -> &ParamTracker {
+        &self.params
+    }
+
+    pub fn statustext(&self) -> &StatusConsole {
+        &self.statustext
+    }
+
+    pub fn counts(&self) -> &MessageCounts {
+        &self.counts
+    }
+
+    /// Links that delivered bytes which made no message, by link.
+    pub fn link_errors(&self) -> &BTreeMap<usize, LinkErrors> {
+        &self.link_errors
+    }
+
+    pub fn home(&self) -> &HomeTracker {
+        &self.home
+    }
+
+    pub fn map(&self) -> &MapTracker {
+        &self.map
+    }
+
+    pub fn navigation(&self) -> &NavigationTracker {
+        &self.navigation
+    }
+
+    pub fn terrain(&self) -> &TerrainTracker {
+        &self.terrain
+    }
+
+    pub fn rates(&self) -> &RateMonitor {
+        &self.rates
+    }
+
+    pub fn intervals(&self) -> &IntervalTracker {
+        &self.intervals
+    }
+
+    pub fn capabilities(&self) -> &CapabilityTracker {
+        &self.capabilities
+    }
+
+    /// Records a MAV_CMD_GET_MESSAGE_INTERVAL mavsnark sent itself, which
+    /// never comes back through the links.
+    pub fn interval_asked(
+        &mut self,
+        sys_id: u8,
+        comp_id: u8,
+        name: &'static str,
+        at: DateTime<Utc>,
+    ) {
+        self.intervals.asked(sys_id, comp_id, name, at);
+    }
+
+    pub fn routes(&self) -> &RouteTracker {
+        &self.routes
+    }
+
+    pub fn link_stats(&self) -> &[LinkStats] {
+        self.dedup.link_stats()
+    }
+
+    /// Stream-row message names, sorted.
+    pub fn stream_types(&self) -> Vec<&'static str> {
+        let mut names: Vec<_> = self.stream_types.iter().copied().collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Replaces which message types are stream rows. Names that are not
+    /// messages in `common`, the `--dialect` extension or `--definitions`
+    /// are ignored.
+    pub fn set_stream_types<'a>(&mut self, names: impl IntoIterator<Item = &'a str>) {
+        self.stream_types = names
+            .into_iter()
+            .filter_map(|name| {
+                MavMessage::message_id_from_name(name)
+                    .and_then(MavMessage::default_message_from_id)
+                    .map(|msg| msg.message_name())
+                    .or_else(|| frame::extension()?.message_name(name))
+                    .or_else(|| definitions::installed()?.message_name(name))
+            })
+            .collect();
+        let stream_types = &self.stream_types;
+        self.stream.retain(|e| stream_types.contains(e.name));
+        self.rebuild_stream_index();
+        self.recount();
+    }
+
     pub fn toggle_category(&mut self, name: &'static str, currently_stream: bool) {
         if currently_stream {
             self.stream_types.remove(name);
@@ -137,6 +1025,7 @@ impl Collector {
             self.stream_types.insert(name);
             self.messages.retain(|e| e.name != name);
         }
+        self.recount();
     }
 
     fn rebuild_stream_index(&mut self) {
@@ -151,12 +1040,54 @@ impl Collector {
         self.stream.clear();
         self.stream_index.clear();
         self.messages.clear();
+        self.clock.clear();
+        self.dedup.clear();
+        self.signing.clear();
+        self.rates.clear();
+        self.commands.clear();
+        self.mission.clear();
+        self.radio.clear();
+        self.flow.clear();
+        self.routes.clear();
+        self.directions.clear();
+        self.talkers.clear();
+        self.throughput.clear();
+        self.safety.clear();
+        self.uavcan.clear();
+        self.cellular.clear();
+        self.vtol.clear();
+        self.winch.clear();
+        self.follow.clear();
+        self.imu.clear();
+        self.interference.clear();
+        self.gps.clear();
+        self.vibration.clear();
+        self.power.clear();
+        self.battery.clear();
+        self.distance.clear();
+        self.params.clear();
+        self.statustext.clear();
+        self.counts.clear();
+        self.link_errors.clear();
+        self.home.clear();
+        self.map.clear();
+        self.navigation.clear();
+        self.intervals.clear();
+        self.capabilities.clear();
+        self.terrain.clear();
+        self.alerts = 0;
+        self.event_bytes = 0;
+        self.history_bytes = 0;
+        self.evicted = 0;
+        if let Some(kept) = &mut self.kept_evicted {
+            kept.clear();
+        }
+        self.revision += 1;
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use chrono::Utc;
     use mavlink::{MavHeader, common::MavMessage};
 
     use super::*;
@@ -170,6 +1101,11 @@ mod tests {
             },
             msg,
             timestamp: Utc::now(),
+            link: 0,
+            signature: None,
+            len: 0,
+            payload: Vec::new(),
+            frame: Vec::new(),
         }
     }
 
@@ -382,4 +1318,415 @@ mod tests {
         assert_eq!(c.messages().len(), 1);
         assert_eq!(c.messages()[0].name, "COMMAND_LONG");
     }
+
+    #[test]
+    fn merge_drops_copy_from_other_link() {
+        let mut c = Collector::new();
+        let first = make_msg(
+            MavMessage::COMMAND_LONG(mavlink::common::COMMAND_LONG_DATA::default()),
+            1,
+            1,
+        );
+        let mut copy = make_msg(
+            MavMessage::COMMAND_LONG(mavlink::common::COMMAND_LONG_DATA::default()),
+            1,
+            1,
+        );
+        copy.link = 1;
+        c.push(first);
+        c.push(copy);
+        assert_eq!(c.messages().len(), 1);
+        assert_eq!(c.messages()[0].link, 0);
+        assert_eq!(c.link_stats()[1].duplicate, 1);
+    }
+
+    #[test]
+    fn tag_keeps_copy_marked_duplicate() {
+        let mut c = Collector::new();
+        c.set_dedup_mode(DedupMode::Tag);
+        let first = make_msg(
+            MavMessage::COMMAND_LONG(mavlink::common::COMMAND_LONG_DATA::default()),
+            1,
+            1,
+        );
+        let mut copy = make_msg(
+            MavMessage::COMMAND_LONG(mavlink::common::COMMAND_LONG_DATA::default()),
+            1,
+            1,
+        );
+        copy.link = 1;
+        c.push(first);
+        c.push(copy);
+        assert_eq!(c.messages().len(), 2);
+        assert!(!c.messages()[0].duplicate);
+        assert!(c.messages()[1].duplicate);
+    }
+
+    #[test]
+    fn signing_regression_adds_security_event() {
+        let mut c = Collector::new();
+        for seq in 0..2 {
+            let mut msg = make_msg(
+                MavMessage::HEARTBEAT(mavlink::common::HEARTBEAT_DATA::default()),
+                1,
+                1,
+            );
+            msg.header.sequence = seq;
+            msg.signature = Some(crate::message::Signature {
+                link_id: 0,
+                timestamp: 1000,
+                verified: None,
+            });
+            c.push(msg);
+        }
+        assert_eq!(c.messages().len(), 1);
+        assert_eq!(c.messages()[0].kind, EventKind::Security);
+        assert_eq!(c.messages()[0].name, "SIGNING_TIMESTAMP_REGRESSION");
+    }
+
+    #[test]
+    fn bad_signature_is_marked_and_reported() {
+        let mut c = Collector::new();
+        let mut msg = make_msg(
+            MavMessage::HEARTBEAT(mavlink::common::HEARTBEAT_DATA::default()),
+            1,
+            1,
+        );
+        msg.signature = Some(crate::message::Signature {
+            link_id: 2,
+            timestamp: 1000,
+            verified: Some(false),
+        });
+        c.push(msg);
+        assert_eq!(c.stream()[0].signature, SignatureStatus::Invalid);
+        assert_eq!(c.messages()[0].kind, EventKind::Security);
+        assert_eq!(c.messages()[0].name, "SIGNATURE_INVALID");
+        assert!(
+            c.messages()[0]
+                .fields
+                .contains("msg: HEARTBEAT, link_id: 2")
+        );
+    }
+
+    #[test]
+    fn diagnostic_goes_to_messages() {
+        let mut c = Collector::new();
+        c.push_diagnostic(crate::message::Diagnostic {
+            header: MavHeader {
+                system_id: 1,
+                component_id: 1,
+                sequence: 0,
+            },
+            name: "DIALECT_MISMATCH",
+            fields: "msg_id: 0".to_string(),
+            link: 0,
+            timestamp: Utc::now(),
+            frame: Vec::new(),
+        });
+        assert!(c.stream().is_empty());
+        assert_eq!(c.messages()[0].kind, EventKind::Diagnostic);
+    }
+
+    #[test]
+    fn dialect_extension_messages_are_rows_or_events() {
+        let mut c = Collector::new();
+        let decoded = |name, fields: &str| crate::message::Diagnostic {
+            header: MavHeader {
+                system_id: 1,
+                component_id: 1,
+                sequence: 0,
+            },
+            name,
+            fields: fields.to_string(),
+            link: 0,
+            timestamp: Utc::now(),
+            frame: Vec::new(),
+        };
+        c.push_diagnostic(decoded("MEMINFO", "brkval: 0, freemem: 100"));
+        c.push_diagnostic(decoded("MEMINFO", "brkval: 0, freemem: 90"));
+        c.push_diagnostic(decoded("RALLY_POINT", "idx: 0"));
+        assert_eq!(c.stream().len(), 1);
+        assert_eq!(c.stream()[0].fields, "brkval: 0, freemem: 90");
+        assert_eq!(c.messages()[0].name, "RALLY_POINT");
+        assert_eq!(c.messages()[0].kind, EventKind::Message);
+    }
+
+    #[test]
+    fn stalled_stream_raises_alert() {
+        let mut config = Config::default();
+        config.expected_rates.insert("HEARTBEAT".to_string(), 1.0);
+        let mut c = Collector::new();
+        c.set_config(&config);
+        c.push(make_msg(
+            MavMessage::HEARTBEAT(mavlink::common::HEARTBEAT_DATA::default()),
+            1,
+            1,
+        ));
+        let later = Utc::now() + chrono::TimeDelta::seconds(5);
+        c.tick(later);
+        c.tick(later + chrono::TimeDelta::seconds(4));
+        let alert = c.messages().back().unwrap();
+        assert_eq!(alert.kind, EventKind::Alert);
+        assert_eq!(alert.name, "RATE_ANOMALY");
+    }
+
+    #[test]
+    fn unanswered_command_raises_alert() {
+        let mut c = Collector::new();
+        let mut command = make_msg(
+            MavMessage::COMMAND_LONG(mavlink::common::COMMAND_LONG_DATA {
+                target_system: 1,
+                target_component: 1,
+                command: mavlink::common::MavCmd::MAV_CMD_COMPONENT_ARM_DISARM,
+                ..Default::default()
+            }),
+            255,
+            190,
+        );
+        command.link = 1;
+        c.push(command);
+        c.tick(Utc::now() + chrono::TimeDelta::seconds(10));
+        let alert = c.messages().back().unwrap();
+        assert_eq!(alert.kind, EventKind::Alert);
+        assert_eq!(alert.name, "COMMAND_UNANSWERED");
+        // Credited to the link the command came in on.
+        assert_eq!(alert.link, 1);
+        assert_eq!(c.alert_count(), 1);
+    }
+
+    #[test]
+    fn vehicle_count_ignores_ground_stations() {
+        use mavlink::common::{HEARTBEAT_DATA, MavType};
+
+        let mut c = Collector::new();
+        let heartbeat = |mavtype| {
+            MavMessage::HEARTBEAT(HEARTBEAT_DATA {
+                mavtype,
+                ..Default::default()
+            })
+        };
+        c.push(make_msg(heartbeat(MavType::MAV_TYPE_QUADROTOR), 1, 1));
+        c.push(make_msg(heartbeat(MavType::MAV_TYPE_QUADROTOR), 1, 2));
+        c.push(make_msg(heartbeat(MavType::MAV_TYPE_FIXED_WING), 2, 1));
+        c.push(make_msg(heartbeat(MavType::MAV_TYPE_GCS), 255, 190));
+        assert_eq!(c.vehicle_count(), 2);
+    }
+
+    #[test]
+    fn mission_retries_are_summarised() {
+        use mavlink::common::{MISSION_ACK_DATA, MISSION_COUNT_DATA, MISSION_REQUEST_INT_DATA};
+
+        let mut c = Collector::new();
+        c.push(make_msg(
+            MavMessage::MISSION_COUNT(MISSION_COUNT_DATA {
+                count: 20,
+                target_system: 1,
+                target_component: 1,
+            }),
+            255,
+            190,
+        ));
+        for _ in 0..6 {
+            c.push(make_msg(
+                MavMessage::MISSION_REQUEST_INT(MISSION_REQUEST_INT_DATA {
+                    seq: 17,
+                    target_system: 255,
+                    target_component: 190,
+                }),
+                1,
+                1,
+            ));
+        }
+        c.push(make_msg(
+            MavMessage::MISSION_ACK(MISSION_ACK_DATA {
+                target_system: 255,
+                target_component: 190,
+                ..Default::default()
+            }),
+            1,
+            1,
+        ));
+        let names: Vec<_> = c.messages().iter().map(|m| m.name).collect();
+        assert_eq!(
+            names,
+            [
+                "MISSION_COUNT",
+                "MISSION_REQUEST_INT",
+                "MISSION_ACK",
+                "MISSION_RETRIES"
+            ]
+        );
+    }
+
+    #[test]
+    fn link_migration_is_logged() {
+        let mut c = Collector::new();
+        let mut first = make_msg(MavMessage::HEARTBEAT(Default::default()), 1, 1);
+        first.timestamp -= chrono::TimeDelta::seconds(10);
+        c.push(first);
+        let mut second = make_msg(MavMessage::HEARTBEAT(Default::default()), 1, 1);
+        second.link = 1;
+        c.push(second);
+        let last = c.messages().back().unwrap();
+        assert_eq!(last.name, "LINK_MIGRATION");
+        assert_eq!(last.kind, EventKind::Alert);
+    }
+
+    #[test]
+    fn oldest_events_are_dropped_past_the_limit() {
+        let mut c = Collector::new();
+        c.set_limits(Limits {
+            max_events: Some(2),
+            ..Limits::default()
+        });
+        for sys in 1..=3 {
+            c.push(make_msg(
+                MavMessage::COMMAND_LONG(mavlink::common::COMMAND_LONG_DATA::default()),
+                sys,
+                1,
+            ));
+        }
+        let systems: Vec<_> = c.messages().iter().map(|m| m.sys_id).collect();
+        assert_eq!(systems, vec![2, 3]);
+        assert_eq!(c.usage().evicted, 1);
+    }
+
+    #[test]
+    fn stream_rows_keep_bounded_history() {
+        let mut c = Collector::new();
+        c.set_limits(Limits {
+            max_history: 2,
+            ..Limits::default()
+        });
+        for roll in 0..4 {
+            c.push(make_msg(
+                MavMessage::ATTITUDE(mavlink::common::ATTITUDE_DATA {
+                    roll: roll as f32,
+                    ..Default::default()
+                }),
+                1,
+                1,
+            ));
+        }
+        let entry = &c.stream()[0];
+        assert!(entry.fields.contains("roll: 3.0"));
+        let history: Vec<_> = entry.history.iter().map(|s| s.fields.as_str()).collect();
+        assert_eq!(history.len(), 2);
+        assert!(history[0].contains("roll: 1.0"));
+        assert!(history[1].contains("roll: 2.0"));
+        assert_eq!(c.usage().history, 2);
+    }
+
+    #[test]
+    fn memory_limit_drops_events_before_history() {
+        let mut c = Collector::new();
+        for i in 0..3 {
+            c.push(make_msg(
+                MavMessage::ATTITUDE(mavlink::common::ATTITUDE_DATA {
+                    roll: i as f32,
+                    ..Default::default()
+                }),
+                1,
+                1,
+            ));
+        }
+        for _ in 0..50 {
+            c.push(make_msg(
+                MavMessage::COMMAND_LONG(mavlink::common::COMMAND_LONG_DATA::default()),
+                1,
+                1,
+            ));
+        }
+        let history_bytes = c.history_bytes;
+        c.set_limits(Limits {
+            max_memory: Some(history_bytes + 1),
+            ..Limits::default()
+        });
+        assert!(c.messages().is_empty());
+        assert_eq!(c.usage().history, 2);
+        assert!(c.usage().bytes <= history_bytes + 1);
+
+        c.set_limits(Limits {
+            max_memory: Some(0),
+            ..Limits::default()
+        });
+        assert_eq!(
+            c.usage(),
+            Usage {
+                evicted: 50,
+                ..Usage::default()
+            }
+        );
+    }
+
+    #[test]
+    fn ingest_survives_noise_and_split_frames() {
+        let mut raw = mavlink::MAVLinkV2MessageRaw::new();
+        raw.serialize_message(
+            MavHeader::default(),
+            &MavMessage::HEARTBEAT(mavlink::common::HEARTBEAT_DATA::default()),
+        );
+        let frame = raw.raw_bytes();
+        let mut bytes = vec![0x00, 0xFE, 0x03, 0x55, 0xFD, 0x02, 0x07, 0x11];
+        bytes.extend_from_slice(frame);
+        // A header whose payload never arrives.
+        bytes.extend_from_slice(&[0xFD, 0xFF, 0x01]);
+
+        let mut c = Collector::new();
+        let mut parser = FrameParser::new();
+        for chunk in bytes.chunks(5) {
+            c.ingest(&mut parser, chunk, 0);
+        }
+        assert_eq!(c.stream().len(), 1);
+        assert_eq!(c.stream()[0].name, "HEARTBEAT");
+        // The noise is reported with its bytes and counted.
+        assert!(!c.messages().is_empty());
+        assert!(c.messages().iter().all(|m| m.name == "FRAMING_ERROR"));
+        assert_eq!(c.messages()[0].frame, [0x00]);
+        let framing = c.link_errors()[&0].framing;
+        assert_eq!(framing, c.messages().len());
+    }
+
+    #[test]
+    fn recorded_timestamps_follow_the_vehicle_clock_across_a_reboot() {
+        use mavlink::common::{ATTITUDE_DATA, SYSTEM_TIME_DATA};
+
+        let t0 = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let at = |ms: i64, msg: MavMessage| MavMsg {
+            timestamp: t0 + chrono::TimeDelta::milliseconds(ms),
+            ..make_msg(msg, 1, 1)
+        };
+        let system_time = |boot_ms: u32| {
+            MavMessage::SYSTEM_TIME(SYSTEM_TIME_DATA {
+                time_unix_usec: 0,
+                time_boot_ms: boot_ms,
+            })
+        };
+        let attitude = |boot_ms: u32| {
+            MavMessage::ATTITUDE(ATTITUDE_DATA {
+                time_boot_ms: boot_ms,
+                ..Default::default()
+            })
+        };
+        let sent = |c: &Collector| {
+            let entry = c.stream_entry(1, 1, "ATTITUDE").unwrap();
+            (entry.timestamp - t0).num_milliseconds()
+        };
+
+        let mut c = Collector::new();
+        c.set_clock_alignment(true);
+        c.push(at(0, system_time(10_000)));
+        // Sent 500 ms after the sample, delayed 400 ms on the way.
+        c.push(at(900, attitude(10_500)));
+        assert_eq!(sent(&c), 500);
+        // The vehicle restarts; its boot clock begins again.
+        c.push(at(60_000, system_time(2_000)));
+        c.push(at(60_400, attitude(2_250)));
+        assert_eq!(sent(&c), 60_250);
+
+        // Live traffic keeps the time it arrived.
+        c.set_clock_alignment(false);
+        c.push(at(61_000, attitude(2_300)));
+        assert_eq!(sent(&c), 61_000);
+    }
 }
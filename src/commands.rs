@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use mavlink::common::{MavCmd, MavMessage};
+
+use crate::message::MavMsg;
+
+/// A command nobody acknowledged in time.
+#[derive(Debug, PartialEq)]
+pub struct Unanswered {
+    pub sys_id: u8,
+    pub comp_id: u8,
+    /// Link the command arrived on.
+    pub link: usize,
+    pub target_system: u8,
+    pub target_component: u8,
+    pub command: MavCmd,
+    pub waited: TimeDelta,
+}
+
+impl Unanswered {
+    pub fn name(&self) -> &'static str {
+        "COMMAND_UNANSWERED"
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "command: {:?}, target: {}:{}, waited_s: {:.1}",
+            self.command,
+            self.target_system,
+            self.target_component,
+            self.waited.num_milliseconds() as f64 / 1000.0
+        )
+    }
+}
+
+struct Pending {
+    command: MavCmd,
+    sys_id: u8,
+    comp_id: u8,
+    link: usize,
+    sent: DateTime<Utc>,
+}
+
+/// Matches COMMAND_LONG/COMMAND_INT against COMMAND_ACK and reports the ones
+/// left unanswered past the timeout.
+pub struct CommandTracker {
+    timeout: TimeDelta,
+    /// Keyed by `(target_system, target_component, command)`; a retry
+    /// refreshes the send time instead of counting as a second command.
+    /// `MavCmd` isn't `Hash`, so the key holds its numeric id.
+    pending: HashMap<(u8, u8, u32), Pending>,
+}
+
+impl CommandTracker {
+    pub fn new(timeout: TimeDelta) -> Self {
+        Self {
+            timeout,
+            pending: HashMap::new(),
+        }
+    }
+
+    pub fn observe(&mut self, msg: &MavMsg) {
+        let (target_system, target_component, command) = match &msg.msg {
+            MavMessage::COMMAND_LONG(d) => (d.target_system, d.target_component, d.command),
+            MavMessage::COMMAND_INT(d) => (d.target_system, d.target_component, d.command),
+            MavMessage::COMMAND_ACK(d) => {
+                let (sys, comp) = (msg.header.system_id, msg.header.component_id);
+                // Broadcast targets (0) are answered by whoever replies.
+                self.pending.retain(|&(ts, tc, cmd), _| {
+                    !(cmd == d.command as u32 && (ts == 0 || ts == sys) && (tc == 0 || tc == comp))
+                });
+                return;
+            }
+            _ => return,
+        };
+        self.pending.insert(
+            (target_system, target_component, command as u32),
+            Pending {
+                command,
+                sys_id: msg.header.system_id,
+                comp_id: msg.header.component_id,
+                link: msg.link,
+                sent: msg.timestamp,
+            },
+        );
+    }
+
+    /// Removes and returns the commands that have waited longer than the timeout.
+    pub fn expire(&mut self, now: DateTime<Utc>) -> Vec<Unanswered> {
+        let mut expired = Vec::new();
+        self.pending
+            .retain(|&(target_system, target_component, _), p| {
+                let waited = now.signed_duration_since(p.sent);
+                if waited < self.timeout {
+                    return true;
+                }
+                expired.push(Unanswered {
+                    sys_id: p.sys_id,
+                    comp_id: p.comp_id,
+                    link: p.link,
+                    target_system,
+                    target_component,
+                    command: p.command,
+                    waited,
+                });
+                false
+            });
+        expired
+    }
+
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::{
+        MavHeader,
+        common::{COMMAND_ACK_DATA, COMMAND_LONG_DATA, MavResult},
+    };
+
+    use super::*;
+
+    fn msg(sys_id: u8, msg: MavMessage, ms: i64) -> MavMsg {
+        let mut m = MavMsg::new(
+            MavHeader {
+                system_id: sys_id,
+                component_id: 1,
+                sequence: 0,
+            },
+            msg,
+            0,
+        );
+        m.timestamp = DateTime::from_timestamp_millis(ms).unwrap();
+        m
+    }
+
+    fn arm(ms: i64) -> MavMsg {
+        msg(
+            255,
+            MavMessage::COMMAND_LONG(COMMAND_LONG_DATA {
+                target_system: 1,
+                target_component: 1,
+                command: MavCmd::MAV_CMD_COMPONENT_ARM_DISARM,
+                ..Default::default()
+            }),
+            ms,
+        )
+    }
+
+    fn ack(ms: i64) -> MavMsg {
+        msg(
+            1,
+            MavMessage::COMMAND_ACK(COMMAND_ACK_DATA {
+                command: MavCmd::MAV_CMD_COMPONENT_ARM_DISARM,
+                result: MavResult::MAV_RESULT_ACCEPTED,
+            }),
+            ms,
+        )
+    }
+
+    fn at(ms: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp_millis(ms).unwrap()
+    }
+
+    #[test]
+    fn acked_command_is_not_reported() {
+        let mut t = CommandTracker::new(TimeDelta::seconds(3));
+        t.observe(&arm(0));
+        t.observe(&ack(200));
+        assert!(t.expire(at(5000)).is_empty());
+    }
+
+    #[test]
+    fn unanswered_command_is_reported_once() {
+        let mut t = CommandTracker::new(TimeDelta::seconds(3));
+        t.observe(&arm(0));
+        assert!(t.expire(at(2000)).is_empty());
+        let expired = t.expire(at(3000));
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].sys_id, 255);
+        assert_eq!(expired[0].target_system, 1);
+        assert!(
+            expired[0]
+                .describe()
+                .contains("MAV_CMD_COMPONENT_ARM_DISARM")
+        );
+        assert!(t.expire(at(9000)).is_empty());
+    }
+
+    #[test]
+    fn retry_restarts_the_timeout() {
+        let mut t = CommandTracker::new(TimeDelta::seconds(3));
+        t.observe(&arm(0));
+        t.observe(&arm(2000));
+        assert!(t.expire(at(4000)).is_empty());
+        assert_eq!(t.expire(at(5000)).len(), 1);
+    }
+
+    #[test]
+    fn ack_from_other_system_does_not_match() {
+        let mut t = CommandTracker::new(TimeDelta::seconds(3));
+        t.observe(&arm(0));
+        let mut other = ack(200);
+        other.header.system_id = 2;
+        t.observe(&other);
+        assert_eq!(t.expire(at(3000)).len(), 1);
+    }
+}
@@ -0,0 +1,147 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+/// Every zstd frame starts with these bytes.
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
+/// Opens a capture (session, tlog or raw dump) for reading, decompressing it
+/// on the fly if it is zstd. Recognised by content, so the file name does
+/// not matter.
+pub fn open(path: &Path) -> io::Result<Box<dyn Read + Send>> {
+    reader(BufReader::new(File::open(path)?))
+}
+
+/// Like [`open`], but starting `offset` bytes into the uncompressed content:
+/// a seek for a plain file, decompressing and dropping what comes before for
+/// a compressed one.
+pub fn open_at(path: &Path, offset: u64) -> io::Result<Box<dyn Read + Send>> {
+    let mut file = BufReader::new(File::open(path)?);
+    if file.fill_buf()?.starts_with(&ZSTD_MAGIC) {
+        let mut input = reader(file)?;
+        io::copy(&mut input.by_ref().take(offset), &mut io::sink())?;
+        Ok(input)
+    } else {
+        file.seek(SeekFrom::Start(offset))?;
+        Ok(Box::new(file))
+    }
+}
+
+fn reader<R: BufRead + Send + 'static>(mut input: R) -> io::Result<Box<dyn Read + Send>> {
+    if input.fill_buf()?.starts_with(&ZSTD_MAGIC) {
+        Ok(Box::new(Truncated(zstd::Decoder::with_buffer(input)?)))
+    } else {
+        Ok(Box::new(input))
+    }
+}
+
+/// Reads a compressed file that may end mid-frame, as one does when the
+/// recording was killed: what was flushed before is returned, then a plain
+/// end of input.
+struct Truncated<R>(R);
+
+impl<R: Read> Read for Truncated<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.0.read(buf) {
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                tracing::warn!("compressed capture ends mid-frame; reading up to there");
+                Ok(0)
+            }
+            result => result,
+        }
+    }
+}
+
+/// Where a recording goes: the file as is, or zstd-compressed. Call
+/// [`Output::finish`] when done, or a compressed file is cut short.
+pub enum Output {
+    Plain(BufWriter<File>),
+    Zstd(zstd::Encoder<'static, BufWriter<File>>),
+}
+
+impl Output {
+    pub fn new(file: File, compress: bool) -> io::Result<Self> {
+        let out = BufWriter::new(file);
+        if compress {
+            Ok(Self::Zstd(zstd::Encoder::new(
+                out,
+                zstd::DEFAULT_COMPRESSION_LEVEL,
+            )?))
+        } else {
+            Ok(Self::Plain(out))
+        }
+    }
+
+    /// Ends the compressed stream and pushes everything to disk.
+    pub fn finish(self) -> io::Result<()> {
+        match self {
+            Self::Plain(mut out) => out.flush(),
+            Self::Zstd(encoder) => encoder.finish()?.flush(),
+        }
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Plain(out) => out.write(buf),
+            Self::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    /// For zstd this ends the current block, so everything written so far
+    /// can be decompressed even if the file is never finished.
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Plain(out) => out.flush(),
+            Self::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
+/// File name suffix for a recording: `.zst` when compressed.
+pub fn suffix(compress: bool) -> &'static str {
+    if compress { ".zst" } else { "" }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_plain_and_compressed_alike() {
+        let data = b"MAVSNARK and then some records".repeat(100);
+        let compressed = zstd::encode_all(data.as_slice(), 0).unwrap();
+        assert!(compressed.len() < data.len());
+        for input in [data.clone(), compressed] {
+            let mut out = Vec::new();
+            reader(io::Cursor::new(input))
+                .unwrap()
+                .read_to_end(&mut out)
+                .unwrap();
+            assert_eq!(out, data);
+        }
+    }
+
+    #[test]
+    fn flushed_output_is_readable_before_finish() {
+        let path = std::env::temp_dir().join(format!("mavsnark-zst-{}", std::process::id()));
+        let mut out = Output::new(File::create(&path).unwrap(), true).unwrap();
+        out.write_all(b"first").unwrap();
+        out.flush().unwrap();
+
+        // As if mavsnark were killed here.
+        let mut partial = String::new();
+        open(&path).unwrap().read_to_string(&mut partial).unwrap();
+        assert_eq!(partial, "first");
+
+        out.write_all(b" second").unwrap();
+        out.finish().unwrap();
+        let mut all = String::new();
+        open(&path).unwrap().read_to_string(&mut all).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(all, "first second");
+    }
+}
@@ -0,0 +1,553 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    env, fs, io,
+    path::PathBuf,
+};
+
+use chrono::TimeDelta;
+use serde::Deserialize;
+
+use crate::{
+    derived::{DerivedFields, Formula},
+    entries::parse_fields,
+    highlight::{Highlights, Rule},
+    keymap::{Action, Key, KeyMap},
+    message,
+    palette::Palette,
+    profile::{self, Profile},
+    severity::{Classifier, Severity},
+    template::Template,
+};
+
+/// User configuration, read from `--config` or
+/// `$XDG_CONFIG_HOME/mavsnark/config.toml` (falling back to `~/.config`).
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Expected rate in Hz per message name, e.g. `ATTITUDE = 10`.
+    pub expected_rates: HashMap<String, f64>,
+    pub rate_alert: RateAlertConfig,
+    pub command_ack: CommandAckConfig,
+    pub gps: GpsConfig,
+    pub vibration: VibrationConfig,
+    pub home: HomeConfig,
+    pub terrain: TerrainConfig,
+    pub notify: NotifyConfig,
+    pub ui: UiConfig,
+    /// User profiles, selected with `--profile` alongside the built-in ones.
+    pub profiles: HashMap<String, Profile>,
+    /// Compact row format per message name, e.g.
+    /// `GPS_RAW_INT = "{lat} {lon} alt {alt} fix {fix_type}"`.
+    pub templates: HashMap<String, String>,
+    /// Extra fields computed per message name, e.g.
+    /// `[derived.VFR_HUD] kmh = "groundspeed * 3.6"`.
+    pub derived: HashMap<String, BTreeMap<String, String>>,
+    /// Row colours, e.g. `[[highlight]] comp = 191, color = "yellow"`.
+    pub highlight: Vec<HighlightRule>,
+    /// Event severity by event or message name, overriding the built-in
+    /// rules, e.g. `GPS_DIVERGED = "critical"`.
+    pub severity: HashMap<String, Severity>,
+    /// Keys per action, replacing its default ones, e.g.
+    /// `open_docs = ["ctrl+d"]`; see [`Config::keymap`].
+    pub keys: HashMap<String, Vec<String>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UiConfig {
+    pub palette: Palette,
+    /// Ring the terminal bell when an alert or security event arrives.
+    pub bell: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateAlertConfig {
+    /// Allowed relative deviation from the expected rate (0.2 = ±20%).
+    pub tolerance: f64,
+    /// How long a deviation must persist before an alert is raised.
+    pub hold_secs: f64,
+}
+
+impl Default for RateAlertConfig {
+    fn default() -> Self {
+        Self {
+            tolerance: 0.2,
+            hold_secs: 3.0,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CommandAckConfig {
+    /// How long a command may go without a COMMAND_ACK.
+    pub timeout_secs: f64,
+}
+
+impl Default for CommandAckConfig {
+    fn default() -> Self {
+        Self { timeout_secs: 3.0 }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GpsConfig {
+    /// Horizontal distance in metres between GPS_RAW_INT and GPS2_RAW
+    /// beyond which the receivers are flagged as diverged.
+    pub divergence_m: f64,
+}
+
+impl Default for GpsConfig {
+    fn default() -> Self {
+        Self { divergence_m: 5.0 }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VibrationConfig {
+    /// VIBRATION level in m/s² on any axis that raises VIBRATION_HIGH.
+    pub warn_level: f32,
+}
+
+impl Default for VibrationConfig {
+    fn default() -> Self {
+        Self { warn_level: 30.0 }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HomeConfig {
+    /// Distance in metres from HOME_POSITION beyond which a vehicle raises
+    /// HOME_RANGE_EXCEEDED; no alert when unset.
+    pub max_range_m: Option<f64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TerrainConfig {
+    /// Clearance in metres above terrain below which an armed vehicle
+    /// raises TERRAIN_CLEARANCE_LOW; no alert when unset.
+    pub min_clearance_m: Option<f32>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotifyConfig {
+    /// Run with `sh -c` (`cmd /C` on Windows) for each alert, with the
+    /// event in `MAVSNARK_*` variables and as JSON on stdin.
+    pub command: Option<String>,
+    /// Show a desktop notification (`notify-send`, or `osascript` on macOS).
+    pub desktop: bool,
+    /// Alert names to notify for; every alert and security event when empty.
+    pub events: Vec<String>,
+    /// Minimum time between notifications for the same alert name.
+    pub min_interval_secs: f64,
+}
+
+impl Default for NotifyConfig {
+    fn default() -> Self {
+        Self {
+            command: None,
+            desktop: false,
+            events: Vec::new(),
+            min_interval_secs: 60.0,
+        }
+    }
+}
+
+/// A `[[highlight]]` rule as written; see [`Config::highlights`].
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HighlightRule {
+    pub message: Option<String>,
+    pub sys: Option<u8>,
+    pub comp: Option<u8>,
+    /// A comparison over the message's fields, e.g. `satellites_visible < 6`.
+    pub when: Option<String>,
+    pub color: String,
+}
+
+impl Config {
+    /// A user profile, or a built-in one of the same name.
+    pub fn profile(&self, name: &str) -> Option<Profile> {
+        self.profiles
+            .get(name)
+            .cloned()
+            .or_else(|| profile::builtin(name))
+    }
+
+    pub fn profile_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+        names.extend(profile::BUILTIN);
+        names.sort_unstable();
+        names.dedup();
+        names
+    }
+
+    /// Takes the profile's expected rates where the config has none.
+    pub fn apply_profile(&mut self, profile: &Profile) {
+        for (name, hz) in &profile.expected_rates {
+            self.expected_rates.entry(name.clone()).or_insert(*hz);
+        }
+    }
+
+    /// Parses the display templates, checking each names a real message and
+    /// only its fields.
+    pub fn templates(&self) -> Result<HashMap<String, Template>, String> {
+        self.templates
+            .iter()
+            .map(|(name, text)| {
+                let defaults = message::default_fields(name)
+                    .ok_or_else(|| format!("templates: unknown message type {name:?}"))?;
+                let template =
+                    Template::parse(text).map_err(|e| format!("templates.{name}: {e}"))?;
+                let known = parse_fields(&defaults);
+                let derived = self.derived.get(name);
+                if let Some(field) = template.fields().find(|f| {
+                    !known.iter().any(|(k, _)| k == f)
+                        && !derived.is_some_and(|d| d.contains_key(*f))
+                }) {
+                    return Err(format!("templates.{name}: {name} has no field {field:?}"));
+                }
+                Ok((name.clone(), template))
+            })
+            .collect()
+    }
+
+    /// Parses the derived fields, checking each formula only reads fields the
+    /// message has and no name shadows a native field.
+    pub fn derived(&self) -> Result<DerivedFields, String> {
+        let mut by_message = HashMap::new();
+        for (name, fields) in &self.derived {
+            let defaults = message::default_fields(name)
+                .ok_or_else(|| format!("derived: unknown message type {name:?}"))?;
+            let known = parse_fields(&defaults);
+            let mut formulas = Vec::new();
+            for (field, text) in fields {
+                if known.iter().any(|(k, _)| k == field) {
+                    return Err(format!(
+                        "derived.{name}.{field}: {name} already has this field"
+                    ));
+                }
+                let formula =
+                    Formula::parse(text).map_err(|e| format!("derived.{name}.{field}: {e}"))?;
+                if let Some(input) = formula
+                    .fields()
+                    .into_iter()
+                    .find(|f| !known.iter().any(|(k, _)| k == f))
+                {
+                    return Err(format!(
+                        "derived.{name}.{field}: {name} has no field {input:?}"
+                    ));
+                }
+                formulas.push((field.clone(), formula));
+            }
+            by_message.insert(name.clone(), formulas);
+        }
+        Ok(DerivedFields::new(by_message))
+    }
+
+    /// Parses the highlight rules, checking a rule for one message type
+    /// only reads fields it has.
+    pub fn highlights(&self) -> Result<Highlights, String> {
+        self.highlight
+            .iter()
+            .enumerate()
+            .map(|(i, rule)| {
+                let parsed = Rule::parse(
+                    rule.message.clone(),
+                    rule.sys,
+                    rule.comp,
+                    rule.when.as_deref(),
+                    &rule.color,
+                )
+                .map_err(|e| format!("highlight[{i}]: {e}"))?;
+                if let Some(name) = &rule.message {
+                    let defaults = message::default_fields(name)
+                        .ok_or_else(|| format!("highlight[{i}]: unknown message type {name:?}"))?;
+                    let known = parse_fields(&defaults);
+                    let derived = self.derived.get(name);
+                    if let Some(field) = parsed.fields().into_iter().find(|f| {
+                        !known.iter().any(|(k, _)| k == f)
+                            && !derived.is_some_and(|d| d.contains_key(*f))
+                    }) {
+                        return Err(format!("highlight[{i}]: {name} has no field {field:?}"));
+                    }
+                }
+                Ok(parsed)
+            })
+            .collect::<Result<_, _>>()
+            .map(Highlights::new)
+    }
+
+    /// Applies the `[keys]` table over the default bindings. A key bound
+    /// here leaves the action it had by default; binding one key to two
+    /// actions is an error.
+    pub fn keymap(&self) -> Result<KeyMap, String> {
+        let mut names: Vec<&String> = self.keys.keys().collect();
+        names.sort_unstable();
+        let mut keymap = KeyMap::default();
+        let mut bound: Vec<(Key, &str)> = Vec::new();
+        for name in names {
+            let action =
+                Action::from_name(name).ok_or_else(|| format!("keys: unknown action {name:?}"))?;
+            let mut keys = Vec::new();
+            for text in &self.keys[name] {
+                let key = Key::parse(text).map_err(|e| format!("keys.{name}: {e}"))?;
+                if let Some((_, other)) = bound.iter().find(|(k, _)| *k == key) {
+                    return Err(format!("keys.{name}: {key} is also bound to {other}"));
+                }
+                bound.push((key, name));
+                keys.push(key);
+            }
+            keymap.rebind(action, keys);
+        }
+        Ok(keymap)
+    }
+
+    pub fn classifier(&self) -> Classifier {
+        Classifier::new(self.severity.clone())
+    }
+
+    pub fn command_timeout(&self) -> TimeDelta {
+        TimeDelta::milliseconds((self.command_ack.timeout_secs * 1000.0) as i64)
+    }
+}
+
+fn default_path() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("mavsnark").join("config.toml"))
+}
+
+/// Loads the config. An explicit path must exist; the default one is optional.
+pub fn load(path: Option<PathBuf>) -> io::Result<Config> {
+    let (path, required) = match path {
+        Some(path) => (path, true),
+        None => match default_path() {
+            Some(path) => (path, false),
+            None => return Ok(Config::default()),
+        },
+    };
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if !required && e.kind() == io::ErrorKind::NotFound => {
+            return Ok(Config::default());
+        }
+        Err(e) => return Err(io::Error::other(format!("{}: {e}", path.display()))),
+    };
+    parse(&text).map_err(|e| io::Error::other(format!("{}: {e}", path.display())))
+}
+
+pub fn parse(text: &str) -> Result<Config, toml::de::Error> {
+    toml::from_str(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyModifiers};
+
+    use super::*;
+
+    #[test]
+    fn empty_config_uses_defaults() {
+        let c = parse("").unwrap();
+        assert!(c.expected_rates.is_empty());
+        assert_eq!(c.rate_alert.tolerance, 0.2);
+        assert_eq!(c.gps.divergence_m, 5.0);
+        assert_eq!(c.vibration.warn_level, 30.0);
+        assert_eq!(c.home.max_range_m, None);
+        assert_eq!(c.terrain.min_clearance_m, None);
+        assert_eq!(c.notify.command, None);
+        assert_eq!(c.notify.min_interval_secs, 60.0);
+    }
+
+    #[test]
+    fn parses_expected_rates() {
+        let c = parse(
+            r#"
+            [expected_rates]
+            HEARTBEAT = 1
+            ATTITUDE = 10.0
+
+            [rate_alert]
+            hold_secs = 5
+            "#,
+        )
+        .unwrap();
+        assert_eq!(c.expected_rates["HEARTBEAT"], 1.0);
+        assert_eq!(c.expected_rates["ATTITUDE"], 10.0);
+        assert_eq!(c.rate_alert.hold_secs, 5.0);
+        assert_eq!(c.rate_alert.tolerance, 0.2);
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        assert!(parse("bogus = 1").is_err());
+    }
+
+    #[test]
+    fn missing_explicit_file_is_an_error() {
+        assert!(load(Some(PathBuf::from("/nonexistent/mavsnark.toml"))).is_err());
+    }
+
+    #[test]
+    fn command_timeout_is_configurable() {
+        let c = parse("[command_ack]\ntimeout_secs = 1.5").unwrap();
+        assert_eq!(c.command_timeout(), TimeDelta::milliseconds(1500));
+    }
+
+    #[test]
+    fn palette_is_configurable() {
+        let c = parse("[ui]\npalette = \"protanopia\"").unwrap();
+        assert_eq!(c.ui.palette, Palette::Protanopia);
+        assert!(parse("[ui]\npalette = \"sepia\"").is_err());
+    }
+
+    #[test]
+    fn user_profiles_extend_builtins() {
+        let mut c = parse(
+            r#"
+            [expected_rates]
+            HEARTBEAT = 2
+
+            [profiles.bench]
+            stream_types = ["HEARTBEAT"]
+            expected_rates = { ATTITUDE = 50 }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(c.profile_names(), vec!["ardupilot", "bench", "px4"]);
+        assert!(c.profile("px4").is_some());
+        assert!(c.profile("inav").is_none());
+
+        let bench = c.profile("bench").unwrap();
+        c.apply_profile(&bench);
+        c.apply_profile(&c.profile("px4").unwrap());
+        assert_eq!(c.expected_rates["ATTITUDE"], 50.0);
+        assert_eq!(c.expected_rates["HEARTBEAT"], 2.0);
+    }
+
+    #[test]
+    fn keys_override_the_defaults() {
+        let c = parse(
+            r#"
+            [keys]
+            up = ["up"]
+            down = ["down"]
+            open_docs = ["k"]
+            "#,
+        )
+        .unwrap();
+        let keymap = c.keymap().unwrap();
+        assert_eq!(
+            keymap.action(KeyCode::Char('k'), KeyModifiers::NONE),
+            Some(Action::OpenDocs)
+        );
+        assert_eq!(keymap.action(KeyCode::Char('j'), KeyModifiers::NONE), None);
+
+        let twice = parse("[keys]\nquit = [\"x\"]\nfind = [\"x\"]").unwrap();
+        assert_eq!(
+            twice.keymap().unwrap_err(),
+            "keys.quit: x is also bound to find"
+        );
+        assert!(parse("[keys]\njump = [\"J\"]").unwrap().keymap().is_err());
+        assert!(
+            parse("[keys]\nquit = [\"hyper\"]")
+                .unwrap()
+                .keymap()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn templates_are_checked_against_the_message() {
+        let c = parse(
+            r#"
+            [templates]
+            GPS_RAW_INT = "{lat} {lon} fix {fix_type}"
+            "#,
+        )
+        .unwrap();
+        let templates = c.templates().unwrap();
+        assert_eq!(
+            templates["GPS_RAW_INT"].render("lat: 1, lon: 2, fix_type: GPS_FIX_TYPE_3D_FIX"),
+            "1 2 fix GPS_FIX_TYPE_3D_FIX"
+        );
+
+        let bad_field = parse("[templates]\nGPS_RAW_INT = \"{latitude}\"").unwrap();
+        assert!(bad_field.templates().unwrap_err().contains("latitude"));
+        let bad_name = parse("[templates]\nGPS = \"{lat}\"").unwrap();
+        assert!(bad_name.templates().is_err());
+    }
+
+    #[test]
+    fn derived_fields_are_checked_against_the_message() {
+        let c = parse(
+            r#"
+            [derived.GLOBAL_POSITION_INT]
+            groundspeed = "hypot(vx, vy) / 100"
+            [templates]
+            GLOBAL_POSITION_INT = "{groundspeed} m/s"
+            "#,
+        )
+        .unwrap();
+        let derived = c.derived().unwrap();
+        assert_eq!(
+            derived.apply("GLOBAL_POSITION_INT", "vx: 300, vy: 400".to_string()),
+            "vx: 300, vy: 400, groundspeed: 5"
+        );
+        assert!(c.templates().is_ok());
+
+        for bad in [
+            "[derived.GLOBAL_POSITION_INT]\ngs = \"speed * 2\"",
+            "[derived.GLOBAL_POSITION_INT]\nlat = \"lon\"",
+            "[derived.GLOBAL_POSITION_INT]\ngs = \"vx +\"",
+            "[derived.GPS]\ngs = \"vx\"",
+        ] {
+            assert!(parse(bad).unwrap().derived().is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn highlight_rules_are_checked_against_the_message() {
+        let c = parse(
+            r#"
+            [[highlight]]
+            message = "GPS_RAW_INT"
+            when = "satellites_visible < 6"
+            color = "red"
+
+            [[highlight]]
+            comp = 191
+            color = "yellow"
+            "#,
+        )
+        .unwrap();
+        let highlights = c.highlights().unwrap();
+        assert_eq!(
+            highlights.color(1, 191, "HEARTBEAT", ""),
+            Some(ratatui::style::Color::Yellow)
+        );
+
+        for bad in [
+            "[[highlight]]\nmessage = \"GPS_RAW_INT\"\nwhen = \"sats < 6\"\ncolor = \"red\"",
+            "[[highlight]]\nmessage = \"GPS\"\ncolor = \"red\"",
+            "[[highlight]]\ncolor = \"reddish\"",
+        ] {
+            assert!(parse(bad).unwrap().highlights().is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn parses_severity_overrides() {
+        let c = parse("[severity]\nGPS_DIVERGED = \"critical\"").unwrap();
+        assert_eq!(c.severity["GPS_DIVERGED"], Severity::Critical);
+        assert!(parse("[severity]\nGPS_DIVERGED = \"dire\"").is_err());
+    }
+}
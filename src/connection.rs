@@ -1,24 +1,346 @@
-use std::{io, sync::Arc};
+use std::{
+    io::{self, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs, UdpSocket},
+    path::Path,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU8, Ordering},
+    },
+    thread,
+    time::Duration,
+};
 
 use mavlink::{
-    MavConnection, MavHeader,
+    MAVLinkV2MessageRaw, MavHeader,
     common::{HEARTBEAT_DATA, MavAutopilot, MavMessage, MavModeFlag, MavState, MavType},
 };
+use serialport::SerialPort;
+use tokio::sync::mpsc::Sender;
+
+use crate::{
+    compress, definitions,
+    frame::{self, FrameError, FrameErrorKind, FrameParser, Parsed},
+    message::{Diagnostic, Incoming, MavMsg},
+    pcap::PcapReader,
+    signing,
+};
 
-pub fn connect(uri: &str) -> io::Result<Arc<dyn MavConnection<MavMessage> + Send + Sync>> {
-    let mut connection =
-        mavlink::connect::<MavMessage>(uri).map_err(|e| io::Error::other(format!("{uri}: {e}")))?;
+/// A byte-level MAVLink transport. Framing and decoding happen in
+/// [`FrameParser`] so that frames the `mavlink` crate would silently drop
+/// (bad CRC, foreign dialect) can still be reported.
+pub struct Link {
+    transport: Transport,
+    /// Sequence number of the next frame [`Link::send`] writes.
+    sequence: AtomicU8,
+}
 
-    connection.set_protocol_version(mavlink::MavlinkVersion::V2);
+enum Transport {
+    Udp {
+        socket: UdpSocket,
+        peer: Mutex<Option<SocketAddr>>,
+        /// Reply to whoever sent last (`udpin`) instead of a fixed address.
+        server: bool,
+    },
+    TcpIn {
+        listener: TcpListener,
+        reader: Mutex<Option<TcpStream>>,
+        writer: Mutex<Option<TcpStream>>,
+    },
+    TcpOut {
+        reader: Mutex<TcpStream>,
+        writer: Mutex<TcpStream>,
+    },
+    Serial {
+        reader: Mutex<Box<dyn SerialPort>>,
+        writer: Mutex<Box<dyn SerialPort>>,
+    },
+    /// `file:` and `pcap:`; read to the end, never written to.
+    File(Mutex<Box<dyn Read + Send>>),
+}
 
-    Ok(Arc::new(connection))
+pub fn connect(uri: &str) -> io::Result<Arc<Link>> {
+    match open(uri) {
+        Ok(transport) => {
+            tracing::info!(uri, "link opened");
+            Ok(Arc::new(Link {
+                transport,
+                sequence: AtomicU8::new(0),
+            }))
+        }
+        Err(e) => {
+            tracing::warn!(uri, error = %e, "could not open link");
+            Err(io::Error::other(format!("{uri}: {e}")))
+        }
+    }
+}
+
+fn open(uri: &str) -> io::Result<Transport> {
+    let unsupported = || io::Error::new(io::ErrorKind::AddrNotAvailable, "Protocol unsupported");
+    let (protocol, address) = uri.split_once(':').ok_or_else(unsupported)?;
+    match protocol {
+        "udpin" => Ok(Transport::Udp {
+            socket: UdpSocket::bind(address)?,
+            peer: Mutex::new(None),
+            server: true,
+        }),
+        "udpout" | "udpcast" => {
+            let socket = UdpSocket::bind("0.0.0.0:0")?;
+            socket.set_broadcast(protocol == "udpcast")?;
+            Ok(Transport::Udp {
+                socket,
+                peer: Mutex::new(Some(resolve(address)?)),
+                server: false,
+            })
+        }
+        "tcpin" => Ok(Transport::TcpIn {
+            listener: TcpListener::bind(address)?,
+            reader: Mutex::new(None),
+            writer: Mutex::new(None),
+        }),
+        "tcpout" => {
+            let stream = TcpStream::connect(address)?;
+            Ok(Transport::TcpOut {
+                reader: Mutex::new(stream.try_clone()?),
+                writer: Mutex::new(stream),
+            })
+        }
+        "serial" => {
+            let (port, baud) = address.rsplit_once(':').ok_or_else(|| {
+                io::Error::new(io::ErrorKind::AddrNotAvailable, "Incomplete port settings")
+            })?;
+            let baud = baud.parse().map_err(|_| {
+                io::Error::new(io::ErrorKind::AddrNotAvailable, "Invalid baud rate")
+            })?;
+            let port = serialport::new(port, baud)
+                .timeout(Duration::from_millis(100))
+                .open()?;
+            Ok(Transport::Serial {
+                reader: Mutex::new(port.try_clone()?),
+                writer: Mutex::new(port),
+            })
+        }
+        "file" => {
+            let path = Path::new(address);
+            let mut input = compress::open(path)?;
+            let mut head = Vec::new();
+            input.by_ref().take(9).read_to_end(&mut head)?;
+            if is_tlog(path, &head) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "This is a telemetry log; open it with --tlog",
+                ));
+            }
+            Ok(Transport::File(Mutex::new(Box::new(
+                io::Cursor::new(head).chain(input),
+            ))))
+        }
+        "pcap" => {
+            let capture = PcapReader::new(compress::open(Path::new(address))?)?;
+            Ok(Transport::File(Mutex::new(Box::new(capture))))
+        }
+        _ => Err(unsupported()),
+    }
 }
 
-pub fn spawn_heartbeat(
-    connection: &Arc<dyn MavConnection<MavMessage> + Send + Sync>,
-    system_id: u8,
-) {
-    let conn = Arc::clone(connection);
+/// Whether a `file:` input is a tlog rather than raw frames: named so, or
+/// starting with a timestamp between 2001 and 2100 followed by a frame. Its
+/// timestamps would otherwise be read as garbage between frames.
+fn is_tlog(path: &Path, head: &[u8]) -> bool {
+    let name = path.to_string_lossy();
+    if name.ends_with(".tlog") || name.contains(".tlog.") {
+        return true;
+    }
+    let Some((micros, rest)) = head.split_first_chunk::<8>() else {
+        return false;
+    };
+    (1_000_000_000_000_000..4_102_444_800_000_000).contains(&u64::from_be_bytes(*micros))
+        && matches!(rest.first(), Some(0xFD | 0xFE))
+}
+
+fn resolve(address: &str) -> io::Result<SocketAddr> {
+    address
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::AddrNotAvailable, "Unresolvable address"))
+}
+
+impl Link {
+    /// Blocks for the next chunk of bytes. `Ok(0)` means the link has ended.
+    fn read(&self, buf: &mut [u8]) -> io::Result<usize> {
+        match &self.transport {
+            Transport::Udp {
+                socket,
+                peer,
+                server,
+            } => loop {
+                let (n, from) = socket.recv_from(buf)?;
+                if *server {
+                    *peer.lock().unwrap() = Some(from);
+                }
+                if n > 0 {
+                    return Ok(n);
+                }
+            },
+            Transport::TcpIn {
+                listener,
+                reader,
+                writer,
+            } => {
+                let mut reader = reader.lock().unwrap();
+                loop {
+                    if reader.is_none() {
+                        let (stream, from) = listener.accept()?;
+                        tracing::info!(%from, "tcp client connected");
+                        *writer.lock().unwrap() = Some(stream.try_clone()?);
+                        *reader = Some(stream);
+                    }
+                    let n = reader.as_mut().map_or(Ok(0), |s| s.read(buf))?;
+                    if n > 0 {
+                        return Ok(n);
+                    }
+                    // Client went away; wait for the next one.
+                    tracing::info!("tcp client disconnected");
+                    *reader = None;
+                    *writer.lock().unwrap() = None;
+                }
+            }
+            Transport::TcpOut { reader, .. } => reader.lock().unwrap().read(buf),
+            Transport::Serial { reader, .. } => loop {
+                match reader.lock().unwrap().read(buf) {
+                    Err(e) if e.kind() == io::ErrorKind::TimedOut => continue,
+                    result => return result,
+                }
+            },
+            Transport::File(file) => file.lock().unwrap().read(buf),
+        }
+    }
+
+    fn write(&self, bytes: &[u8]) -> io::Result<()> {
+        match &self.transport {
+            Transport::Udp { socket, peer, .. } => {
+                if let Some(peer) = *peer.lock().unwrap() {
+                    socket.send_to(bytes, peer)?;
+                }
+                Ok(())
+            }
+            Transport::TcpIn { writer, .. } => match writer.lock().unwrap().as_mut() {
+                Some(stream) => stream.write_all(bytes),
+                None => Ok(()),
+            },
+            Transport::TcpOut { writer, .. } => writer.lock().unwrap().write_all(bytes),
+            Transport::Serial { writer, .. } => writer.lock().unwrap().write_all(bytes),
+            Transport::File(_) => Ok(()),
+        }
+    }
+
+    /// Sends `msg` as MAVLink 2, signed if `--signing-link-id` was given.
+    /// The link numbers its frames itself, so `header.sequence` is ignored
+    /// and the receiver's loss counters see an unbroken sequence.
+    pub fn send(&self, header: &MavHeader, msg: &MavMessage) -> io::Result<()> {
+        let header = MavHeader {
+            // Wraps at 255 like the field does.
+            sequence: self.sequence.fetch_add(1, Ordering::Relaxed),
+            ..*header
+        };
+        let mut raw = MAVLinkV2MessageRaw::new();
+        let Some(signer) = signing::signer() else {
+            raw.serialize_message(header, msg);
+            return self.write(raw.raw_bytes());
+        };
+        raw.serialize_message_for_signing(header, msg);
+        let mut frame = raw.raw_bytes().to_vec();
+        signer.sign(&mut frame);
+        self.write(&frame)
+    }
+}
+
+/// Reads frames from `link` on a dedicated thread and forwards them to the UI.
+pub fn spawn_reader(link: Arc<Link>, index: usize, tx: Sender<Incoming>) {
+    spawn(link, index, None, tx);
+}
+
+/// Like [`spawn_reader`], but also writes every byte read to `peer` as it
+/// came, unknown dialects and signatures included, for `--proxy`.
+pub fn spawn_forwarder(link: Arc<Link>, index: usize, peer: Arc<Link>, tx: Sender<Incoming>) {
+    spawn(link, index, Some(peer), tx);
+}
+
+fn spawn(link: Arc<Link>, index: usize, peer: Option<Arc<Link>>, tx: Sender<Incoming>) {
+    thread::spawn(move || {
+        let mut parser = FrameParser::new();
+        let mut buf = vec![0u8; 65536];
+        loop {
+            let n = match link.read(&mut buf) {
+                Ok(0) => {
+                    tracing::info!(link = index, "link ended");
+                    break;
+                }
+                Ok(n) => n,
+                Err(e) => {
+                    // stderr would land on top of the TUI.
+                    tracing::error!(link = index, error = %e, "receive failed");
+                    break;
+                }
+            };
+            // Before decoding, so the capture adds no latency.
+            if let Some(peer) = &peer
+                && let Err(e) = peer.write(&buf[..n])
+            {
+                tracing::warn!(link = index, error = %e, "forward failed");
+            }
+            parser.push(&buf[..n]);
+            while let Some(parsed) = parser.next_frame() {
+                let incoming = decode(parsed, index);
+                if tx.blocking_send(incoming).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+}
+
+/// Turns a parsed frame or error from link `index` into what the UI
+/// receives.
+pub fn decode(parsed: Parsed, index: usize) -> Incoming {
+    match parsed {
+        Parsed::Frame(frame) => match MavMsg::from_frame(&frame, index) {
+            Ok(msg) => Incoming::Message(msg),
+            Err(e) => {
+                tracing::warn!(link = index, error = %e, "could not decode frame");
+                let error = FrameError::new(FrameErrorKind::Decode(e.to_string()), &frame.bytes);
+                Incoming::Diagnostic(Diagnostic::error(&error, index))
+            }
+        },
+        Parsed::Unrecognized(frame, hint) => {
+            if let Some(dialect) = frame::extension()
+                && hint.candidates().contains(&dialect.name)
+                && let Some((name, fields)) = dialect.decode(&frame)
+            {
+                return Incoming::Diagnostic(Diagnostic::new(&frame, name, fields, index));
+            }
+            if let Some(definitions) = definitions::installed()
+                && hint.candidates().contains(&definitions.name)
+                && let Some((name, fields)) = definitions.decode(&frame)
+            {
+                return Incoming::Diagnostic(Diagnostic::new(&frame, name, fields, index));
+            }
+            tracing::debug!(link = index, hint = hint.name(), "unrecognized frame");
+            Incoming::Diagnostic(Diagnostic::new(&frame, hint.name(), hint.describe(), index))
+        }
+        Parsed::Error(error) => {
+            tracing::debug!(
+                link = index,
+                error = error.kind.name(),
+                len = error.len,
+                "bad bytes"
+            );
+            Incoming::Diagnostic(Diagnostic::error(&error, index))
+        }
+    }
+}
+
+pub fn spawn_heartbeat(link: &Arc<Link>, system_id: u8) {
+    let link = Arc::clone(link);
     tokio::spawn(async move {
         let header = MavHeader {
             system_id,
@@ -36,9 +358,53 @@ pub fn spawn_heartbeat(
         let mut interval = tokio::time::interval(std::time::Duration::from_secs(1));
         loop {
             interval.tick().await;
-            if conn.send(&header, &heartbeat).is_err() {
+            if let Err(e) = link.send(&header, &heartbeat) {
+                tracing::warn!(error = %e, "heartbeat send failed; stopping heartbeats");
                 break;
             }
         }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_rejects_tlogs() {
+        let path = std::env::temp_dir().join(format!("mavsnark-file-{}", std::process::id()));
+        let mut tlog = 1_700_000_000_000_000u64.to_be_bytes().to_vec();
+        tlog.extend([0xFE, 9, 0, 1, 1, 0]);
+        std::fs::write(&path, &tlog).unwrap();
+        let error = connect(&format!("file:{}", path.display())).err().unwrap();
+        assert!(error.to_string().contains("--tlog"));
+        assert!(is_tlog(Path::new("flight.tlog.zst"), &[]));
+
+        std::fs::write(&path, &tlog[8..]).unwrap();
+        assert!(connect(&format!("file:{}", path.display())).is_ok());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn send_numbers_frames() {
+        let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+        receiver
+            .set_read_timeout(Some(Duration::from_secs(1)))
+            .unwrap();
+        let link = connect(&format!("udpout:{}", receiver.local_addr().unwrap())).unwrap();
+        let header = MavHeader {
+            system_id: 255,
+            component_id: 0,
+            sequence: 7,
+        };
+        let msg = MavMessage::HEARTBEAT(HEARTBEAT_DATA::default());
+        let mut buf = [0u8; 280];
+        for expected in 0..3 {
+            link.send(&header, &msg).unwrap();
+            let n = receiver.recv(&mut buf).unwrap();
+            assert!(n > 4);
+            // MAVLink 2: magic, len, incompat, compat, then the sequence.
+            assert_eq!(buf[4], expected);
+        }
+    }
+}
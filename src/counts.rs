@@ -0,0 +1,59 @@
+use std::collections::BTreeMap;
+
+use crate::message::MavMsg;
+
+/// Frames one link carried, duplicates included.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LinkTraffic {
+    pub frames: u64,
+    pub bytes: u64,
+}
+
+/// Messages received per type and system since the last clear, duplicates
+/// from other links left out, and the traffic of each link.
+#[derive(Default)]
+pub struct MessageCounts {
+    by_type: BTreeMap<&'static str, u64>,
+    by_system: BTreeMap<u8, u64>,
+    links: BTreeMap<usize, LinkTraffic>,
+    total: u64,
+}
+
+impl MessageCounts {
+    pub fn observe(&mut self, msg: &MavMsg) {
+        *self.by_type.entry(msg.msg_type()).or_default() += 1;
+        *self.by_system.entry(msg.header.system_id).or_default() += 1;
+        self.total += 1;
+    }
+
+    /// Counts `msg` against the link it arrived on, duplicate or not.
+    pub fn observe_link(&mut self, msg: &MavMsg) {
+        let link = self.links.entry(msg.link).or_default();
+        link.frames += 1;
+        link.bytes += msg.len as u64;
+    }
+
+    /// By message name, sorted.
+    pub fn by_type(&self) -> &BTreeMap<&'static str, u64> {
+        &self.by_type
+    }
+
+    pub fn by_system(&self) -> &BTreeMap<u8, u64> {
+        &self.by_system
+    }
+
+    pub fn links(&self) -> &BTreeMap<usize, LinkTraffic> {
+        &self.links
+    }
+
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    pub fn clear(&mut self) {
+        self.by_type.clear();
+        self.by_system.clear();
+        self.links.clear();
+        self.total = 0;
+    }
+}
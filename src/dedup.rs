@@ -0,0 +1,174 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::{DefaultHasher, Hash, Hasher},
+};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use clap::ValueEnum;
+use mavlink::Message;
+
+use crate::message::MavMsg;
+
+/// How long a frame fingerprint is remembered. Sequence numbers wrap every
+/// 256 frames per component, so this must stay short.
+const WINDOW: TimeDelta = TimeDelta::milliseconds(500);
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum DedupMode {
+    /// Drop copies of a frame that already arrived on another link
+    Merge,
+    /// Keep copies in the messages panel, marked as duplicates
+    Tag,
+    /// Treat every frame as unique
+    Off,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Delivery {
+    First,
+    Duplicate { first_link: usize },
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct LinkStats {
+    /// Frames this link delivered before any other link.
+    pub first: u64,
+    /// Frames this link delivered after another link already had.
+    pub duplicate: u64,
+}
+
+pub struct Deduplicator {
+    seen: HashMap<u64, (usize, DateTime<Utc>)>,
+    order: VecDeque<(DateTime<Utc>, u64)>,
+    links: Vec<LinkStats>,
+}
+
+impl Default for Deduplicator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deduplicator {
+    pub fn new() -> Self {
+        Self {
+            seen: HashMap::new(),
+            order: VecDeque::new(),
+            links: Vec::new(),
+        }
+    }
+
+    pub fn observe(&mut self, msg: &MavMsg) -> Delivery {
+        self.expire(msg.timestamp);
+        if self.links.len() <= msg.link {
+            self.links.resize(msg.link + 1, LinkStats::default());
+        }
+
+        let key = fingerprint(msg);
+        match self.seen.get(&key) {
+            Some(&(first_link, _)) if first_link != msg.link => {
+                self.links[msg.link].duplicate += 1;
+                Delivery::Duplicate { first_link }
+            }
+            _ => {
+                self.seen.insert(key, (msg.link, msg.timestamp));
+                self.order.push_back((msg.timestamp, key));
+                self.links[msg.link].first += 1;
+                Delivery::First
+            }
+        }
+    }
+
+    fn expire(&mut self, now: DateTime<Utc>) {
+        while let Some(&(ts, key)) = self.order.front() {
+            if now.signed_duration_since(ts) < WINDOW {
+                break;
+            }
+            self.order.pop_front();
+            if self.seen.get(&key).is_some_and(|&(_, seen)| seen == ts) {
+                self.seen.remove(&key);
+            }
+        }
+    }
+
+    pub fn link_stats(&self) -> &[LinkStats] {
+        &self.links
+    }
+
+    pub fn clear(&mut self) {
+        self.seen.clear();
+        self.order.clear();
+        self.links.clear();
+    }
+}
+
+fn fingerprint(msg: &MavMsg) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    msg.header.system_id.hash(&mut hasher);
+    msg.header.component_id.hash(&mut hasher);
+    msg.header.sequence.hash(&mut hasher);
+    msg.msg.message_id().hash(&mut hasher);
+    msg.payload.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::{
+        MavHeader,
+        common::{HEARTBEAT_DATA, MavMessage},
+    };
+
+    use super::*;
+
+    fn make(seq: u8, link: usize, ms: i64) -> MavMsg {
+        MavMsg {
+            header: MavHeader {
+                system_id: 1,
+                component_id: 1,
+                sequence: seq,
+            },
+            msg: MavMessage::HEARTBEAT(HEARTBEAT_DATA::default()),
+            timestamp: DateTime::from_timestamp_millis(ms).unwrap(),
+            link,
+            signature: None,
+            len: 0,
+            payload: Vec::new(),
+            frame: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn same_frame_on_other_link_is_duplicate() {
+        let mut d = Deduplicator::new();
+        assert_eq!(d.observe(&make(5, 0, 0)), Delivery::First);
+        assert_eq!(
+            d.observe(&make(5, 1, 20)),
+            Delivery::Duplicate { first_link: 0 }
+        );
+        assert_eq!(d.link_stats()[0].first, 1);
+        assert_eq!(d.link_stats()[1].duplicate, 1);
+    }
+
+    #[test]
+    fn different_sequence_is_unique() {
+        let mut d = Deduplicator::new();
+        d.observe(&make(5, 0, 0));
+        assert_eq!(d.observe(&make(6, 1, 10)), Delivery::First);
+    }
+
+    #[test]
+    fn same_link_repeat_is_not_duplicate() {
+        let mut d = Deduplicator::new();
+        d.observe(&make(5, 0, 0));
+        assert_eq!(d.observe(&make(5, 0, 10)), Delivery::First);
+    }
+
+    #[test]
+    fn fingerprint_expires_after_window() {
+        let mut d = Deduplicator::new();
+        d.observe(&make(5, 0, 0));
+        assert_eq!(d.observe(&make(5, 1, 600)), Delivery::First);
+        assert_eq!(d.link_stats()[1].first, 1);
+    }
+}
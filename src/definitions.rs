@@ -0,0 +1,497 @@
+use std::{collections::HashMap, fs, path::Path, sync::OnceLock};
+
+use crate::frame::RawFrame;
+
+/// A MAVLink field type, as written in message definition XML.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Type {
+    Char,
+    U8,
+    I8,
+    U16,
+    I16,
+    U32,
+    I32,
+    U64,
+    I64,
+    F32,
+    F64,
+}
+
+impl Type {
+    fn parse(s: &str) -> Option<Self> {
+        Some(match s {
+            "char" => Self::Char,
+            // HEARTBEAT's version field; a plain byte on the wire.
+            "uint8_t" | "uint8_t_mavlink_version" => Self::U8,
+            "int8_t" => Self::I8,
+            "uint16_t" => Self::U16,
+            "int16_t" => Self::I16,
+            "uint32_t" => Self::U32,
+            "int32_t" => Self::I32,
+            "uint64_t" => Self::U64,
+            "int64_t" => Self::I64,
+            "float" => Self::F32,
+            "double" => Self::F64,
+            _ => return None,
+        })
+    }
+
+    /// Spelling used in the CRC_EXTRA seed.
+    fn c_name(self) -> &'static str {
+        match self {
+            Self::Char => "char",
+            Self::U8 => "uint8_t",
+            Self::I8 => "int8_t",
+            Self::U16 => "uint16_t",
+            Self::I16 => "int16_t",
+            Self::U32 => "uint32_t",
+            Self::I32 => "int32_t",
+            Self::U64 => "uint64_t",
+            Self::I64 => "int64_t",
+            Self::F32 => "float",
+            Self::F64 => "double",
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Self::Char | Self::U8 | Self::I8 => 1,
+            Self::U16 | Self::I16 => 2,
+            Self::U32 | Self::I32 | Self::F32 => 4,
+            Self::U64 | Self::I64 | Self::F64 => 8,
+        }
+    }
+
+    fn format(self, b: &[u8]) -> String {
+        match self {
+            Self::Char | Self::U8 => b[0].to_string(),
+            Self::I8 => (b[0] as i8).to_string(),
+            Self::U16 => u16::from_le_bytes([b[0], b[1]]).to_string(),
+            Self::I16 => i16::from_le_bytes([b[0], b[1]]).to_string(),
+            Self::U32 => u32::from_le_bytes(b[..4].try_into().unwrap()).to_string(),
+            Self::I32 => i32::from_le_bytes(b[..4].try_into().unwrap()).to_string(),
+            Self::U64 => u64::from_le_bytes(b[..8].try_into().unwrap()).to_string(),
+            Self::I64 => i64::from_le_bytes(b[..8].try_into().unwrap()).to_string(),
+            Self::F32 => format!("{:?}", f32::from_le_bytes(b[..4].try_into().unwrap())),
+            Self::F64 => format!("{:?}", f64::from_le_bytes(b[..8].try_into().unwrap())),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Field {
+    name: String,
+    ty: Type,
+    /// Element count of an array field, 0 for a scalar.
+    array: usize,
+}
+
+impl Field {
+    fn len(&self) -> usize {
+        self.ty.size() * self.array.max(1)
+    }
+
+    fn format(&self, bytes: &[u8]) -> String {
+        if self.ty == Type::Char && self.array > 0 {
+            let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+            return format!("{:?}", String::from_utf8_lossy(&bytes[..end]));
+        }
+        if self.array == 0 {
+            return self.ty.format(bytes);
+        }
+        let items: Vec<_> = bytes
+            .chunks(self.ty.size())
+            .map(|b| self.ty.format(b))
+            .collect();
+        format!("[{}]", items.join(", "))
+    }
+}
+
+#[derive(Debug)]
+struct MessageDef {
+    name: &'static str,
+    /// Wire order: base fields largest type first, then extensions as declared.
+    fields: Vec<Field>,
+    extra_crc: u8,
+}
+
+impl MessageDef {
+    fn new(name: &'static str, mut base: Vec<Field>, extensions: Vec<Field>) -> Self {
+        // Stable, so equal sizes keep their declared order.
+        base.sort_by_key(|f| std::cmp::Reverse(f.ty.size()));
+        let mut crc = x25(0xFFFF, format!("{name} ").as_bytes());
+        for field in &base {
+            crc = x25(crc, format!("{} ", field.ty.c_name()).as_bytes());
+            crc = x25(crc, format!("{} ", field.name).as_bytes());
+            if field.array > 0 {
+                crc = x25(crc, &[field.array as u8]);
+            }
+        }
+        let mut fields = base;
+        fields.extend(extensions);
+        Self {
+            name,
+            fields,
+            extra_crc: ((crc & 0xFF) ^ (crc >> 8)) as u8,
+        }
+    }
+
+    /// Fields in the format of [`crate::message::MavMsg::fields`].
+    fn decode(&self, payload: &[u8]) -> String {
+        // MAVLink 2 drops trailing zero bytes.
+        let full: usize = self.fields.iter().map(Field::len).sum();
+        let mut bytes = payload.to_vec();
+        bytes.resize(full.max(bytes.len()), 0);
+        let mut at = 0;
+        let fields: Vec<_> = self
+            .fields
+            .iter()
+            .map(|field| {
+                let value = field.format(&bytes[at..at + field.len()]);
+                at += field.len();
+                format!("{}: {value}", field.name)
+            })
+            .collect();
+        fields.join(", ")
+    }
+}
+
+/// The MAVLink CRC (X.25) over `bytes`, continuing from `crc`.
+fn x25(mut crc: u16, bytes: &[u8]) -> u16 {
+    for &b in bytes {
+        let mut tmp = b ^ crc as u8;
+        tmp ^= tmp << 4;
+        let tmp = u16::from(tmp);
+        crc = (crc >> 8) ^ (tmp << 8) ^ (tmp << 3) ^ (tmp >> 4);
+    }
+    crc
+}
+
+/// Message definitions loaded from a dialect XML file (`--definitions`),
+/// for private dialects the `mavlink` crate was not built with. Fields are
+/// decoded as plain numbers and strings; enums and bitmasks are not named.
+#[derive(Debug)]
+pub struct Definitions {
+    /// The file name without extension, used like a dialect name in
+    /// `UNKNOWN_MESSAGE` candidates.
+    pub name: &'static str,
+    messages: HashMap<u32, MessageDef>,
+}
+
+static INSTALLED: OnceLock<Definitions> = OnceLock::new();
+
+/// Consults `definitions` for frames the compiled dialects cannot decode.
+/// Set once at startup; later calls are ignored.
+pub fn install(definitions: Definitions) {
+    let _ = INSTALLED.set(definitions);
+}
+
+/// The definitions set with [`install`], if any.
+pub fn installed() -> Option<&'static Definitions> {
+    INSTALLED.get()
+}
+
+impl Definitions {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let xml = fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        let name = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        Self::parse(leak(name), &xml).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    /// Reads the `<message>`s of a dialect file. `<include>`s are not
+    /// followed: messages from `common.xml` are decoded already.
+    pub fn parse(name: &'static str, xml: &str) -> Result<Self, String> {
+        let mut messages = HashMap::new();
+        let mut current: Option<(u32, String, Vec<Field>, Option<Vec<Field>>)> = None;
+        for tag in tags(xml)? {
+            match (tag.name, tag.closing, &mut current) {
+                ("message", false, None) => {
+                    let id = tag.attr("id")?;
+                    let id = id.parse().map_err(|_| format!("bad message id {id:?}"))?;
+                    current = Some((id, tag.attr("name")?.to_string(), Vec::new(), None));
+                }
+                ("field", false, Some((_, message, base, extensions))) => {
+                    let ty = tag.attr("type")?;
+                    let (ty, array) = match ty.split_once('[') {
+                        Some((ty, len)) => (ty, len.trim_end_matches(']').parse().ok()),
+                        None => (ty, Some(0)),
+                    };
+                    let field = Field {
+                        name: tag.attr("name")?.to_string(),
+                        ty: Type::parse(ty)
+                            .ok_or_else(|| format!("{message}: unknown type {ty:?}"))?,
+                        array: array.ok_or_else(|| format!("{message}: bad array length"))?,
+                    };
+                    extensions.as_mut().unwrap_or(base).push(field);
+                }
+                ("extensions", false, Some((_, _, _, extensions))) => {
+                    *extensions = Some(Vec::new());
+                }
+                ("message", true, Some(_)) => {
+                    let (id, message, base, extensions) = current.take().unwrap();
+                    let def = MessageDef::new(leak(message), base, extensions.unwrap_or_default());
+                    if messages.insert(id, def).is_some() {
+                        return Err(format!("message id {id} defined twice"));
+                    }
+                }
+                ("message", false, Some((_, message, ..))) => {
+                    return Err(format!("{message}: <message> inside <message>"));
+                }
+                _ => {}
+            }
+        }
+        if let Some((_, message, ..)) = current {
+            return Err(format!("{message}: missing </message>"));
+        }
+        Ok(Self { name, messages })
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// Whether `frame` is a message defined here with a valid CRC.
+    pub fn matches(&self, frame: &RawFrame) -> bool {
+        self.messages
+            .get(&frame.msg_id())
+            .is_some_and(|def| frame.crc_valid(def.extra_crc))
+    }
+
+    /// Name and fields of the message in `frame`, if it is defined here.
+    pub fn decode(&self, frame: &RawFrame) -> Option<(&'static str, String)> {
+        let def = self.messages.get(&frame.msg_id())?;
+        Some((def.name, def.decode(frame.payload())))
+    }
+
+    /// `name` as the definitions spell it, if there is such a message.
+    pub fn message_name(&self, name: &str) -> Option<&'static str> {
+        self.messages
+            .values()
+            .find(|def| def.name == name)
+            .map(|def| def.name)
+    }
+}
+
+/// Message names live as long as the stream rows that show them.
+fn leak(name: String) -> &'static str {
+    Box::leak(name.into_boxed_str())
+}
+
+struct Tag<'a> {
+    name: &'a str,
+    closing: bool,
+    attrs: Vec<(&'a str, String)>,
+}
+
+impl Tag<'_> {
+    fn attr(&self, key: &str) -> Result<&str, String> {
+        self.attrs
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| v.as_str())
+            .ok_or_else(|| format!("<{}> without {key}", self.name))
+    }
+}
+
+/// The element tags of `xml`, in order; text, comments, processing
+/// instructions and declarations are skipped. A self-closing tag is
+/// reported as an opening tag followed by its closing tag.
+fn tags(xml: &str) -> Result<Vec<Tag<'_>>, String> {
+    let mut tags = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start..];
+        let skip = [
+            ("<!--", "-->"),
+            ("<![CDATA[", "]]>"),
+            ("<?", "?>"),
+            ("<!", ">"),
+        ]
+        .into_iter()
+        .find(|(open, _)| rest.starts_with(open));
+        if let Some((_, close)) = skip {
+            let end = rest
+                .find(close)
+                .ok_or_else(|| format!("missing {close:?}"))?;
+            rest = &rest[end + close.len()..];
+            continue;
+        }
+        let end = tag_end(rest).ok_or("unterminated tag")?;
+        let body = &rest[1..end];
+        rest = &rest[end + 1..];
+        let (closing, body) = match body.strip_prefix('/') {
+            Some(body) => (true, body),
+            None => (false, body),
+        };
+        let (self_closing, body) = match body.strip_suffix('/') {
+            Some(body) => (true, body),
+            None => (false, body),
+        };
+        let body = body.trim();
+        let name_end = body.find(char::is_whitespace).unwrap_or(body.len());
+        let name = &body[..name_end];
+        tags.push(Tag {
+            name,
+            closing,
+            attrs: attributes(&body[name_end..])?,
+        });
+        if self_closing {
+            tags.push(Tag {
+                name,
+                closing: true,
+                attrs: Vec::new(),
+            });
+        }
+    }
+    Ok(tags)
+}
+
+/// Index of the `>` ending the tag at `s[0]`, ignoring any inside quotes.
+fn tag_end(s: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in s.char_indices() {
+        match (c, quote) {
+            ('"' | '\'', None) => quote = Some(c),
+            (c, Some(q)) if c == q => quote = None,
+            ('>', None) => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn attributes(mut s: &str) -> Result<Vec<(&str, String)>, String> {
+    let mut attrs = Vec::new();
+    loop {
+        s = s.trim_start();
+        if s.is_empty() {
+            return Ok(attrs);
+        }
+        let (key, rest) = s
+            .split_once('=')
+            .ok_or_else(|| format!("bad attribute {s:?}"))?;
+        let rest = rest.trim_start();
+        let quote = rest
+            .chars()
+            .next()
+            .filter(|&c| c == '"' || c == '\'')
+            .ok_or_else(|| format!("unquoted attribute {}", key.trim()))?;
+        let end = rest[1..]
+            .find(quote)
+            .ok_or_else(|| format!("unterminated attribute {}", key.trim()))?;
+        attrs.push((key.trim(), unescape(&rest[1..1 + end])));
+        s = &rest[end + 2..];
+    }
+}
+
+fn unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::{
+        MAVLinkV2MessageRaw, MavHeader,
+        common::{ATTITUDE_DATA, MavMessage},
+    };
+
+    use super::*;
+
+    const XML: &str = r#"<?xml version="1.0"?>
+<!-- A copy of two common messages, to check against the compiled dialect. -->
+<mavlink>
+  <include>common.xml</include>
+  <messages>
+    <message id="0" name="HEARTBEAT">
+      <description>The heartbeat message.</description>
+      <field type="uint8_t" name="type" enum="MAV_TYPE">Vehicle or component type.</field>
+      <field type="uint8_t" name="autopilot" enum="MAV_AUTOPILOT">Autopilot type.</field>
+      <field type="uint8_t" name="base_mode" enum="MAV_MODE_FLAG" display="bitmask">Mode.</field>
+      <field type="uint32_t" name="custom_mode">Autopilot-specific flags.</field>
+      <field type="uint8_t" name="system_status" enum="MAV_STATE">Status.</field>
+      <field type="uint8_t_mavlink_version" name="mavlink_version">Version, &lt;= 3.</field>
+    </message>
+    <message id="30" name="ATTITUDE">
+      <field type="uint32_t" name="time_boot_ms" units="ms">Timestamp.</field>
+      <field type="float" name="roll" units="rad">Roll angle (-pi..+pi)</field>
+      <field type="float" name="pitch" units="rad">Pitch angle</field>
+      <field type="float" name="yaw" units="rad">Yaw angle</field>
+      <field type="float" name="rollspeed" units="rad/s">Roll rate</field>
+      <field type="float" name="pitchspeed" units="rad/s">Pitch rate</field>
+      <field type="float" name="yawspeed" units="rad/s">Yaw rate</field>
+      <extensions/>
+      <field type="char[8]" name="label">Not in common.</field>
+    </message>
+  </messages>
+</mavlink>
+"#;
+
+    #[test]
+    fn computes_the_crc_extra_of_the_compiled_dialect() {
+        let defs = Definitions::parse("copy", XML).unwrap();
+        assert_eq!(defs.len(), 2);
+        assert_eq!(defs.messages[&0].extra_crc, 50);
+        assert_eq!(defs.messages[&30].extra_crc, 39);
+        assert_eq!(defs.message_name("ATTITUDE"), Some("ATTITUDE"));
+    }
+
+    #[test]
+    fn decodes_frames_generically() {
+        let mut raw = MAVLinkV2MessageRaw::new();
+        raw.serialize_message(
+            MavHeader::default(),
+            &MavMessage::ATTITUDE(ATTITUDE_DATA {
+                time_boot_ms: 10,
+                roll: 0.5,
+                yaw: -1.0,
+                // Nonzero so MAVLink 2 does not truncate the payload.
+                yawspeed: 0.25,
+                ..Default::default()
+            }),
+        );
+        let mut bytes = raw.raw_bytes().to_vec();
+        // Append the extension field, as a sender of the custom dialect would.
+        let label = b"north\0\0\0";
+        let at = 10 + bytes[1] as usize;
+        bytes.splice(at..at, label.iter().copied());
+        bytes[1] += label.len() as u8;
+        let end = bytes.len() - 2;
+        let crc = mavlink::calculate_crc(&bytes[1..end], 39);
+        bytes[end..].copy_from_slice(&crc.to_le_bytes());
+
+        let defs = Definitions::parse("copy", XML).unwrap();
+        let frame = RawFrame { bytes };
+        assert!(defs.matches(&frame));
+        let (name, fields) = defs.decode(&frame).unwrap();
+        assert_eq!(name, "ATTITUDE");
+        assert!(fields.starts_with("time_boot_ms: 10, roll: 0.5, pitch: 0.0, yaw: -1.0"));
+        assert!(fields.ends_with("label: \"north\""), "{fields}");
+    }
+
+    #[test]
+    fn reports_malformed_definitions() {
+        for bad in [
+            r#"<message id="x" name="A"></message>"#,
+            r#"<message id="1" name="A"><field type="uint9_t" name="f"/></message>"#,
+            r#"<message id="1" name="A"><field type="char[" name="f"/></message>"#,
+            r#"<message id="1" name="A">"#,
+            r#"<message id="1" name="A"></message><message id="1" name="B"></message>"#,
+            r#"<message id="1" name="A"><field type="uint8_t" name="f">"#,
+            r#"<message id="1"></message>"#,
+        ] {
+            assert!(Definitions::parse("bad", bad).is_err(), "{bad}");
+        }
+    }
+}
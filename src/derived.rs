@@ -0,0 +1,356 @@
+use std::collections::HashMap;
+
+use crate::entries::parse_fields;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Number(f64),
+    /// A field of the message, optionally one element of an array field.
+    Field(String, Option<usize>),
+    Neg(Box<Expr>),
+    Binary(char, Box<Expr>, Box<Expr>),
+    Call(Function, Vec<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Function {
+    Sqrt,
+    Abs,
+    Hypot,
+    Atan2,
+    Min,
+    Max,
+    Degrees,
+}
+
+impl Function {
+    fn parse(name: &str) -> Option<(Self, usize)> {
+        Some(match name {
+            "sqrt" => (Self::Sqrt, 1),
+            "abs" => (Self::Abs, 1),
+            "hypot" => (Self::Hypot, 2),
+            "atan2" => (Self::Atan2, 2),
+            "min" => (Self::Min, 2),
+            "max" => (Self::Max, 2),
+            "degrees" => (Self::Degrees, 1),
+            _ => return None,
+        })
+    }
+
+    fn call(self, args: &[f64]) -> f64 {
+        match self {
+            Self::Sqrt => args[0].sqrt(),
+            Self::Abs => args[0].abs(),
+            Self::Hypot => args[0].hypot(args[1]),
+            Self::Atan2 => args[0].atan2(args[1]),
+            Self::Min => args[0].min(args[1]),
+            Self::Max => args[0].max(args[1]),
+            Self::Degrees => args[0].to_degrees(),
+        }
+    }
+}
+
+/// An arithmetic formula over a message's fields, e.g.
+/// `hypot(vx, vy) / 100` or `voltages[0] / 1000`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Formula {
+    expr: Expr,
+}
+
+impl Formula {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut parser = Parser {
+            src: s,
+            chars: s.char_indices().peekable(),
+        };
+        let expr = parser.expr()?;
+        parser.skip_space();
+        match parser.chars.peek() {
+            None => Ok(Self { expr }),
+            Some(&(_, c)) => Err(format!("{s:?}: unexpected {c:?}")),
+        }
+    }
+
+    /// Field names the formula reads.
+    pub fn fields(&self) -> Vec<&str> {
+        fn walk<'a>(expr: &'a Expr, out: &mut Vec<&'a str>) {
+            match expr {
+                Expr::Number(_) => {}
+                Expr::Field(name, _) => out.push(name),
+                Expr::Neg(e) => walk(e, out),
+                Expr::Binary(_, l, r) => {
+                    walk(l, out);
+                    walk(r, out);
+                }
+                Expr::Call(_, args) => args.iter().for_each(|a| walk(a, out)),
+            }
+        }
+        let mut out = Vec::new();
+        walk(&self.expr, &mut out);
+        out
+    }
+
+    /// `None` if a field is missing, not a number, or the index is out of
+    /// range.
+    pub fn eval(&self, fields: &[(&str, &str)]) -> Option<f64> {
+        eval(&self.expr, fields)
+    }
+}
+
+fn eval(expr: &Expr, fields: &[(&str, &str)]) -> Option<f64> {
+    Some(match expr {
+        Expr::Number(n) => *n,
+        Expr::Field(name, index) => {
+            let value = fields.iter().find(|(k, _)| k == name)?.1;
+            match index {
+                None => value.parse().ok()?,
+                Some(i) => value
+                    .strip_prefix('[')?
+                    .strip_suffix(']')?
+                    .split(',')
+                    .nth(*i)?
+                    .trim()
+                    .parse()
+                    .ok()?,
+            }
+        }
+        Expr::Neg(e) => -eval(e, fields)?,
+        Expr::Binary(op, l, r) => {
+            let (l, r) = (eval(l, fields)?, eval(r, fields)?);
+            match op {
+                '+' => l + r,
+                '-' => l - r,
+                '*' => l * r,
+                _ => l / r,
+            }
+        }
+        Expr::Call(f, args) => {
+            let args: Option<Vec<f64>> = args.iter().map(|a| eval(a, fields)).collect();
+            f.call(&args?)
+        }
+    })
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    chars: std::iter::Peekable<std::str::CharIndices<'a>>,
+}
+
+impl<'a> Parser<'a> {
+    fn skip_space(&mut self) {
+        while self.chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+    }
+
+    fn eat(&mut self, want: char) -> bool {
+        self.skip_space();
+        self.chars.next_if(|&(_, c)| c == want).is_some()
+    }
+
+    fn error(&self, what: &str) -> String {
+        format!("{:?}: {what}", self.src)
+    }
+
+    fn expr(&mut self) -> Result<Expr, String> {
+        let mut left = self.term()?;
+        loop {
+            let op = if self.eat('+') {
+                '+'
+            } else if self.eat('-') {
+                '-'
+            } else {
+                return Ok(left);
+            };
+            left = Expr::Binary(op, Box::new(left), Box::new(self.term()?));
+        }
+    }
+
+    fn term(&mut self) -> Result<Expr, String> {
+        let mut left = self.unary()?;
+        loop {
+            let op = if self.eat('*') {
+                '*'
+            } else if self.eat('/') {
+                '/'
+            } else {
+                return Ok(left);
+            };
+            left = Expr::Binary(op, Box::new(left), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.eat('-') {
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        self.atom()
+    }
+
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> &'a str {
+        let start = self.chars.peek().map_or(self.src.len(), |&(i, _)| i);
+        while self.chars.next_if(|&(_, c)| f(c)).is_some() {}
+        let end = self.chars.peek().map_or(self.src.len(), |&(i, _)| i);
+        &self.src[start..end]
+    }
+
+    fn atom(&mut self) -> Result<Expr, String> {
+        if self.eat('(') {
+            let inner = self.expr()?;
+            if !self.eat(')') {
+                return Err(self.error("missing ')'"));
+            }
+            return Ok(inner);
+        }
+        self.skip_space();
+        match self.chars.peek() {
+            Some(&(_, c)) if c.is_ascii_digit() || c == '.' => {
+                let text = self.take_while(|c| c.is_ascii_digit() || c == '.');
+                text.parse()
+                    .map(Expr::Number)
+                    .map_err(|_| format!("{:?}: bad number {text:?}", self.src))
+            }
+            Some(&(_, c)) if c.is_ascii_alphabetic() || c == '_' => {
+                let name = self.take_while(|c| c.is_ascii_alphanumeric() || c == '_');
+                if self.eat('(') {
+                    return self.call(name);
+                }
+                let index = if self.eat('[') {
+                    self.skip_space();
+                    let digits = self.take_while(|c| c.is_ascii_digit());
+                    let index = digits.parse().map_err(|_| self.error("bad index"))?;
+                    if !self.eat(']') {
+                        return Err(self.error("missing ']'"));
+                    }
+                    Some(index)
+                } else {
+                    None
+                };
+                Ok(Expr::Field(name.to_string(), index))
+            }
+            Some(&(_, c)) => Err(self.error(&format!("unexpected {c:?}"))),
+            None => Err(self.error("unexpected end")),
+        }
+    }
+
+    fn call(&mut self, name: &str) -> Result<Expr, String> {
+        let (function, arity) =
+            Function::parse(name).ok_or_else(|| self.error(&format!("unknown function {name}")))?;
+        let mut args = Vec::new();
+        if !self.eat(')') {
+            loop {
+                args.push(self.expr()?);
+                if self.eat(')') {
+                    break;
+                }
+                if !self.eat(',') {
+                    return Err(self.error("missing ')'"));
+                }
+            }
+        }
+        if args.len() != arity {
+            return Err(self.error(&format!("{name} takes {arity} argument(s)")));
+        }
+        Ok(Expr::Call(function, args))
+    }
+}
+
+/// User-defined fields per message name, appended to the message's own so
+/// they show, and can be used, wherever native fields can.
+#[derive(Debug, Default)]
+pub struct DerivedFields {
+    by_message: HashMap<String, Vec<(String, Formula)>>,
+}
+
+impl DerivedFields {
+    pub fn new(by_message: HashMap<String, Vec<(String, Formula)>>) -> Self {
+        Self { by_message }
+    }
+
+    /// `fields` (as produced by [`crate::message::MavMsg::fields`]) with the
+    /// derived values of message `name` added; ones that cannot be computed
+    /// from this message are left out.
+    pub fn apply(&self, name: &str, mut fields: String) -> String {
+        let Some(derived) = self.by_message.get(name) else {
+            return fields;
+        };
+        let values: Vec<(String, f64)> = {
+            let parsed = parse_fields(&fields);
+            derived
+                .iter()
+                .filter_map(|(field, formula)| Some((field.clone(), formula.eval(&parsed)?)))
+                .collect()
+        };
+        for (field, value) in values {
+            fields.push_str(&format!(", {field}: {}", format_value(value)));
+        }
+        fields
+    }
+}
+
+fn format_value(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        format!("{value:.0}")
+    } else {
+        let text = format!("{value:.3}");
+        text.trim_end_matches('0').trim_end_matches('.').to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(formula: &str, fields: &str) -> Option<f64> {
+        Formula::parse(formula).unwrap().eval(&parse_fields(fields))
+    }
+
+    #[test]
+    fn evaluates_over_fields() {
+        assert_eq!(eval("hypot(vx, vy) / 100", "vx: 300, vy: -400"), Some(5.0));
+        assert_eq!(
+            eval("voltages[1] / 1000", "voltages: [4200, 4100]"),
+            Some(4.1)
+        );
+        assert_eq!(eval("-a * (b + 1) - 2", "a: 2, b: 3"), Some(-10.0));
+        assert_eq!(eval("voltages[5]", "voltages: [4200, 4100]"), None);
+        assert_eq!(eval("mode", "mode: MAV_MODE_AUTO"), None);
+        assert_eq!(
+            Formula::parse("sqrt(x*x + y[2])").unwrap().fields(),
+            ["x", "x", "y"]
+        );
+    }
+
+    #[test]
+    fn malformed_formulas_are_rejected() {
+        for bad in [
+            "",
+            "1 +",
+            "(a",
+            "a[x]",
+            "nope(a)",
+            "sqrt(a, b)",
+            "a b",
+            "1..2",
+        ] {
+            assert!(Formula::parse(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn values_are_appended_to_the_fields() {
+        let derived = DerivedFields::new(HashMap::from([(
+            "VFR_HUD".to_string(),
+            vec![
+                (
+                    "kmh".to_string(),
+                    Formula::parse("groundspeed * 3.6").unwrap(),
+                ),
+                ("bad".to_string(), Formula::parse("missing").unwrap()),
+            ],
+        )]));
+        assert_eq!(
+            derived.apply("VFR_HUD", "groundspeed: 2.5".to_string()),
+            "groundspeed: 2.5, kmh: 9"
+        );
+        assert_eq!(derived.apply("ATTITUDE", "roll: 1".to_string()), "roll: 1");
+    }
+}
@@ -0,0 +1,167 @@
+use std::collections::HashSet;
+
+use mavlink::{
+    MavHeader, Message,
+    common::{MavComponent, MavMessage, MavType},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::message::MavMsg;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    /// Vehicle → GCS.
+    Downlink,
+    /// GCS → vehicle.
+    Uplink,
+    /// Vehicle → vehicle/component, or GCS → GCS.
+    Peer,
+}
+
+impl Direction {
+    pub fn arrow(self) -> &'static str {
+        match self {
+            Self::Downlink => "\u{2193}",
+            Self::Uplink => "\u{2191}",
+            Self::Peer => "\u{2194}",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Downlink => "downlink",
+            Self::Uplink => "uplink",
+            Self::Peer => "peer",
+        }
+    }
+}
+
+/// Classifies traffic by whether sender and target are ground stations.
+/// A system is a GCS once it sends a `MAV_TYPE_GCS` heartbeat, or if the
+/// sending component is the mission planner component.
+pub struct DirectionClassifier {
+    gcs: HashSet<u8>,
+}
+
+impl Default for DirectionClassifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DirectionClassifier {
+    pub fn new() -> Self {
+        Self {
+            gcs: HashSet::new(),
+        }
+    }
+
+    pub fn classify(&mut self, msg: &MavMsg) -> Direction {
+        let sys_id = msg.header.system_id;
+        if let MavMessage::HEARTBEAT(hb) = &msg.msg
+            && hb.mavtype == MavType::MAV_TYPE_GCS
+        {
+            self.gcs.insert(sys_id);
+        }
+        let from_gcs = self.from_gcs(&msg.header);
+        let to_gcs = match msg.msg.target_system_id() {
+            Some(0) | None => None,
+            Some(target) => Some(self.gcs.contains(&target)),
+        };
+        match (from_gcs, to_gcs) {
+            (true, Some(true)) | (false, Some(false)) => Direction::Peer,
+            (true, _) => Direction::Uplink,
+            (false, _) => Direction::Downlink,
+        }
+    }
+
+    /// Direction of a message whose target is unknown, such as one decoded
+    /// with `--dialect`.
+    pub fn classify_sender(&self, header: &MavHeader) -> Direction {
+        if self.from_gcs(header) {
+            Direction::Uplink
+        } else {
+            Direction::Downlink
+        }
+    }
+
+    fn from_gcs(&self, header: &MavHeader) -> bool {
+        self.gcs.contains(&header.system_id)
+            || header.component_id == MavComponent::MAV_COMP_ID_MISSIONPLANNER as u8
+    }
+
+    pub fn clear(&mut self) {
+        self.gcs.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::{
+        MavHeader,
+        common::{COMMAND_LONG_DATA, HEARTBEAT_DATA},
+    };
+
+    use super::*;
+
+    fn msg(sys_id: u8, comp_id: u8, msg: MavMessage) -> MavMsg {
+        MavMsg::new(
+            MavHeader {
+                system_id: sys_id,
+                component_id: comp_id,
+                sequence: 0,
+            },
+            msg,
+            0,
+        )
+    }
+
+    fn heartbeat(mavtype: MavType) -> MavMessage {
+        MavMessage::HEARTBEAT(HEARTBEAT_DATA {
+            mavtype,
+            ..Default::default()
+        })
+    }
+
+    fn command(target_system: u8) -> MavMessage {
+        MavMessage::COMMAND_LONG(COMMAND_LONG_DATA {
+            target_system,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn vehicle_telemetry_is_downlink() {
+        let mut c = DirectionClassifier::new();
+        let d = c.classify(&msg(1, 1, heartbeat(MavType::MAV_TYPE_QUADROTOR)));
+        assert_eq!(d, Direction::Downlink);
+    }
+
+    #[test]
+    fn gcs_command_is_uplink() {
+        let mut c = DirectionClassifier::new();
+        c.classify(&msg(255, 0, heartbeat(MavType::MAV_TYPE_GCS)));
+        assert_eq!(c.classify(&msg(255, 0, command(1))), Direction::Uplink);
+    }
+
+    #[test]
+    fn mission_planner_component_is_gcs() {
+        let mut c = DirectionClassifier::new();
+        assert_eq!(c.classify(&msg(255, 190, command(1))), Direction::Uplink);
+    }
+
+    #[test]
+    fn vehicle_to_vehicle_is_peer() {
+        let mut c = DirectionClassifier::new();
+        assert_eq!(c.classify(&msg(1, 1, command(2))), Direction::Peer);
+    }
+
+    #[test]
+    fn gcs_to_gcs_is_peer() {
+        let mut c = DirectionClassifier::new();
+        c.classify(&msg(255, 0, heartbeat(MavType::MAV_TYPE_GCS)));
+        c.classify(&msg(254, 0, heartbeat(MavType::MAV_TYPE_GCS)));
+        assert_eq!(c.classify(&msg(255, 0, command(254))), Direction::Peer);
+    }
+}
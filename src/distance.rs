@@ -0,0 +1,213 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use mavlink::common::{MavDistanceSensor, MavMessage, MavSensorOrientation};
+
+use crate::message::MavMsg;
+
+/// A sensor that has not reported for this long is shown as stale.
+const STALE_AFTER: TimeDelta = TimeDelta::seconds(2);
+
+/// One rangefinder's latest DISTANCE_SENSOR and the extremes seen so far.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rangefinder {
+    pub at: DateTime<Utc>,
+    pub kind: MavDistanceSensor,
+    pub orientation: MavSensorOrientation,
+    /// Metres.
+    pub current: f64,
+    /// The sensor's advertised range in metres.
+    pub range: (f64, f64),
+    /// Lowest and highest in-range readings seen, metres.
+    pub seen: Option<(f64, f64)>,
+    /// 1 (invalid) to 100 (perfect); `None` when not reported.
+    pub signal_quality: Option<u8>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Health {
+    Ok,
+    /// The reading is outside the advertised range, which is how most
+    /// sensors say "no return".
+    OutOfRange,
+    /// The sensor says its signal is invalid.
+    NoSignal,
+    /// Another sensor on the vehicle claims the same orientation.
+    Duplicate,
+    Stale,
+}
+
+impl Health {
+    pub fn label(self) -> &'static str {
+        match self {
+            Health::Ok => "ok",
+            Health::OutOfRange => "out of range",
+            Health::NoSignal => "no signal",
+            Health::Duplicate => "duplicate",
+            Health::Stale => "stale",
+        }
+    }
+}
+
+/// DISTANCE_SENSOR readings per vehicle and sensor id.
+#[derive(Default)]
+pub struct DistanceTracker {
+    sensors: BTreeMap<(u8, u8), Rangefinder>,
+}
+
+impl DistanceTracker {
+    pub fn observe(&mut self, msg: &MavMsg) {
+        let MavMessage::DISTANCE_SENSOR(d) = &msg.msg else {
+            return;
+        };
+        let range = (
+            f64::from(d.min_distance) / 100.0,
+            f64::from(d.max_distance) / 100.0,
+        );
+        let current = f64::from(d.current_distance) / 100.0;
+        let key = (msg.header.system_id, d.id);
+        let previous = self.sensors.get(&key).and_then(|s| s.seen);
+        let in_range = (range.0..=range.1).contains(&current);
+        let seen = match previous {
+            Some((lo, hi)) if in_range => Some((lo.min(current), hi.max(current))),
+            None if in_range => Some((current, current)),
+            seen => seen,
+        };
+        self.sensors.insert(
+            key,
+            Rangefinder {
+                at: msg.timestamp,
+                kind: d.mavtype,
+                orientation: d.orientation,
+                current,
+                range,
+                seen,
+                signal_quality: (d.signal_quality != 0).then_some(d.signal_quality),
+            },
+        );
+    }
+
+    /// Sensors of one vehicle by id, with their health at `now`.
+    pub fn vehicle(&self, sys_id: u8, now: DateTime<Utc>) -> Vec<(u8, &Rangefinder, Health)> {
+        let sensors: Vec<(u8, &Rangefinder)> = self
+            .sensors
+            .range((sys_id, 0)..=(sys_id, u8::MAX))
+            .map(|(&(_, id), sensor)| (id, sensor))
+            .collect();
+        sensors
+            .iter()
+            .map(|&(id, sensor)| {
+                let shared = sensors
+                    .iter()
+                    .any(|&(other, s)| other != id && s.orientation == sensor.orientation);
+                let health = if now - sensor.at > STALE_AFTER {
+                    Health::Stale
+                } else if shared {
+                    Health::Duplicate
+                } else if sensor.signal_quality == Some(1) {
+                    Health::NoSignal
+                } else if !(sensor.range.0..=sensor.range.1).contains(&sensor.current) {
+                    Health::OutOfRange
+                } else {
+                    Health::Ok
+                };
+                (id, sensor, health)
+            })
+            .collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.sensors.clear();
+    }
+}
+
+/// Where a sensor points, in words for the common mounts and by rotation
+/// name for the rest.
+pub fn direction(orientation: MavSensorOrientation) -> String {
+    use MavSensorOrientation::*;
+    let name = match orientation {
+        MAV_SENSOR_ROTATION_NONE => "forward",
+        MAV_SENSOR_ROTATION_YAW_45 => "forward-right",
+        MAV_SENSOR_ROTATION_YAW_90 => "right",
+        MAV_SENSOR_ROTATION_YAW_135 => "back-right",
+        MAV_SENSOR_ROTATION_YAW_180 => "back",
+        MAV_SENSOR_ROTATION_YAW_225 => "back-left",
+        MAV_SENSOR_ROTATION_YAW_270 => "left",
+        MAV_SENSOR_ROTATION_YAW_315 => "forward-left",
+        MAV_SENSOR_ROTATION_PITCH_90 => "up",
+        MAV_SENSOR_ROTATION_PITCH_270 => "down",
+        other => {
+            let name = format!("{other:?}");
+            return name
+                .strip_prefix("MAV_SENSOR_ROTATION_")
+                .unwrap_or(&name)
+                .to_lowercase();
+        }
+    };
+    name.to_string()
+}
+
+/// `LASER` for `MAV_DISTANCE_SENSOR_LASER`.
+pub fn kind_name(kind: MavDistanceSensor) -> String {
+    let name = format!("{kind:?}");
+    name.strip_prefix("MAV_DISTANCE_SENSOR_")
+        .unwrap_or(&name)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::{MavHeader, common::DISTANCE_SENSOR_DATA};
+
+    use super::*;
+
+    fn reading(id: u8, orientation: MavSensorOrientation, cm: u16) -> MavMsg {
+        MavMsg::new(
+            MavHeader::default(),
+            MavMessage::DISTANCE_SENSOR(DISTANCE_SENSOR_DATA {
+                id,
+                orientation,
+                min_distance: 20,
+                max_distance: 4_000,
+                current_distance: cm,
+                ..Default::default()
+            }),
+            0,
+        )
+    }
+
+    #[test]
+    fn groups_by_orientation_and_flags_problems() {
+        let mut tracker = DistanceTracker::default();
+        let down = MavSensorOrientation::MAV_SENSOR_ROTATION_PITCH_270;
+        let forward = MavSensorOrientation::MAV_SENSOR_ROTATION_NONE;
+        tracker.observe(&reading(0, down, 150));
+        tracker.observe(&reading(0, down, 120));
+        // No return: reported beyond the maximum, and kept out of min/max.
+        tracker.observe(&reading(0, down, 4_001));
+        tracker.observe(&reading(1, forward, 800));
+
+        let now = Utc::now();
+        let sensors = tracker.vehicle(MavHeader::default().system_id, now);
+        assert_eq!(sensors.len(), 2);
+        let (_, down_sensor, health) = sensors[0];
+        assert_eq!(direction(down_sensor.orientation), "down");
+        assert_eq!(down_sensor.seen, Some((1.2, 1.5)));
+        assert_eq!(health, Health::OutOfRange);
+        assert_eq!(sensors[1].2, Health::Ok);
+
+        // A second sensor configured as forward too.
+        tracker.observe(&reading(2, forward, 790));
+        let sensors = tracker.vehicle(MavHeader::default().system_id, now);
+        assert_eq!(sensors[1].2, Health::Duplicate);
+        assert_eq!(sensors[2].2, Health::Duplicate);
+        assert_eq!(
+            tracker.vehicle(MavHeader::default().system_id, now + TimeDelta::seconds(5))[0].2,
+            Health::Stale
+        );
+        assert_eq!(
+            direction(MavSensorOrientation::MAV_SENSOR_ROTATION_ROLL_180),
+            "roll_180"
+        );
+    }
+}
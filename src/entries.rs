@@ -1,11 +1,18 @@
-use chrono::{DateTime, Utc};
+use std::collections::VecDeque;
+
+use chrono::{DateTime, SecondsFormat, Utc};
 use ratatui::{
-    style::{Color, Style},
+    style::{Color, Style, Stylize},
     text::{Line, Span},
 };
 
-pub(crate) fn parse_fields(s: &str) -> Vec<(&str, &str)> {
-    s.split(',')
+use crate::{
+    direction::Direction, highlight::Highlights, message::id_color, rate::ArrivalStats,
+    severity::Severity, signing::SignatureStatus, template::Template,
+};
+
+pub fn parse_fields(s: &str) -> Vec<(&str, &str)> {
+    split_top_level(s)
         .filter_map(|part| {
             let part = part.trim();
             if part.is_empty() {
@@ -17,6 +24,56 @@ pub(crate) fn parse_fields(s: &str) -> Vec<(&str, &str)> {
         .collect()
 }
 
+/// Splits on commas that are not nested inside brackets, so array values
+/// stay whole.
+fn split_top_level(s: &str) -> impl Iterator<Item = &str> {
+    let mut depth = 0usize;
+    let mut start = 0;
+    let mut parts = Vec::new();
+    for (i, c) in s.char_indices() {
+        match c {
+            '[' | '(' | '{' => depth += 1,
+            ']' | ')' | '}' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                parts.push(&s[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&s[start..]);
+    parts.into_iter()
+}
+
+/// The row text for a message: its name and either every field or the
+/// configured template.
+fn summary(name: &str, fields: &str, template: Option<&Template>) -> String {
+    match template {
+        Some(template) => format!("{name}: {}", template.render(fields)),
+        None => format!("{name}: {fields}"),
+    }
+}
+
+/// Seconds without an update before a row with no rate history is stale.
+const STALE_AFTER: f64 = 5.0;
+
+/// An earlier value of a stream row.
+pub struct Sample {
+    pub timestamp: DateTime<Utc>,
+    pub fields: String,
+    pub payload: Vec<u8>,
+    pub frame: Vec<u8>,
+}
+
+/// A stream row's value at some moment, borrowed from the row or its history.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Snapshot<'a> {
+    pub timestamp: DateTime<Utc>,
+    pub fields: &'a str,
+    pub payload: &'a [u8],
+    pub frame: &'a [u8],
+}
+
 pub struct StreamEntry {
     pub sys_color: Color,
     pub comp_color: Color,
@@ -26,22 +83,104 @@ pub struct StreamEntry {
     pub name: &'static str,
     pub fields: String,
     pub timestamp: DateTime<Utc>,
+    pub link: usize,
+    /// The latest frame arrived as MAVLink 1.
+    pub v1: bool,
+    /// Signature of the latest frame.
+    pub signature: SignatureStatus,
+    pub arrivals: ArrivalStats,
+    pub direction: Direction,
+    /// Payload bytes of the latest frame.
+    pub payload: Vec<u8>,
+    /// The latest frame as received; empty when read back from a session.
+    pub frame: Vec<u8>,
+    /// Earlier values, oldest first, up to
+    /// [`crate::collector::Limits::max_history`].
+    pub history: VecDeque<Sample>,
+}
+
+impl<'a> Snapshot<'a> {
+    pub fn parsed_fields(&self) -> Vec<(&'a str, &'a str)> {
+        parse_fields(self.fields)
+    }
 }
 
 impl StreamEntry {
-    pub fn parsed_fields(&self) -> Vec<(&str, &str)> {
-        parse_fields(&self.fields)
+    /// The value the row held at `as_of`, or the latest one for `None`.
+    /// `None` if the row had not been received yet, or its value then has
+    /// already left the history.
+    pub fn at(&self, as_of: Option<DateTime<Utc>>) -> Option<Snapshot<'_>> {
+        let latest = Snapshot {
+            timestamp: self.timestamp,
+            fields: &self.fields,
+            payload: &self.payload,
+            frame: &self.frame,
+        };
+        let Some(as_of) = as_of else {
+            return Some(latest);
+        };
+        if self.timestamp <= as_of {
+            return Some(latest);
+        }
+        self.history
+            .iter()
+            .rev()
+            .find(|s| s.timestamp <= as_of)
+            .map(|s| Snapshot {
+                timestamp: s.timestamp,
+                fields: &s.fields,
+                payload: &s.payload,
+                frame: &s.frame,
+            })
     }
 
-    pub fn to_line(&self) -> Line<'_> {
+    /// Whether the row had gone quiet by `now`: three expected intervals
+    /// without an update, or five seconds before a rate is known.
+    pub fn is_stale(&self, now: DateTime<Utc>) -> bool {
+        let timestamp = self.at(Some(now)).map_or(self.timestamp, |s| s.timestamp);
+        let age = now.signed_duration_since(timestamp).num_milliseconds() as f64 / 1000.0;
+        match self.arrivals.hz() {
+            Some(hz) if hz > 0.0 => age > (3.0 / hz).max(1.0),
+            _ => age > STALE_AFTER,
+        }
+    }
+
+    /// Smoothed rate next to the age, padded so the rows line up; blank
+    /// before there is one, or for a value taken from the history, which
+    /// the rate (always the current one) does not describe.
+    fn rate_label(&self, now: DateTime<Utc>, shown: DateTime<Utc>) -> String {
+        match self.arrivals.recent_hz(now) {
+            Some(hz) if shown == self.timestamp => format!("{hz:>6.1}Hz "),
+            _ => " ".repeat(9),
+        }
+    }
+
+    /// The row as it stood at `as_of`, or live for `None`.
+    pub fn to_line(
+        &self,
+        template: Option<&Template>,
+        highlights: &Highlights,
+        as_of: Option<DateTime<Utc>>,
+    ) -> Line<'_> {
         let sys_style = Style::default().fg(self.sys_color);
         let comp_style = Style::default().fg(self.comp_color);
-        let ago = Utc::now()
-            .signed_duration_since(self.timestamp)
+        let now = as_of.unwrap_or_else(Utc::now);
+        let snapshot = self.at(as_of).unwrap_or(Snapshot {
+            timestamp: self.timestamp,
+            fields: &self.fields,
+            payload: &self.payload,
+            frame: &self.frame,
+        });
+        let ago = now
+            .signed_duration_since(snapshot.timestamp)
             .num_milliseconds() as f64
             / 1000.0;
         let gray = Style::default().fg(Color::DarkGray);
-        let msg_style = match self.msg_color {
+        // Marked with a symbol as well as dimmed, so it does not rely on colour.
+        let stale = self.is_stale(now);
+        let highlight = highlights.color(self.sys_id, self.comp_id, self.name, snapshot.fields);
+        let msg_style = match highlight.or(self.msg_color) {
+            _ if stale => gray,
             Some(c) => Style::default().fg(c),
             None => Style::default(),
         };
@@ -51,12 +190,48 @@ impl StreamEntry {
             Span::raw(":"),
             Span::styled(format!("{:>3}", self.comp_id), comp_style),
             Span::raw("] "),
+            Span::styled(format!("{} ", self.direction.arrow()), gray),
+            Span::styled(if stale { "!" } else { " " }, Style::default().bold()),
             Span::styled(format!("{ago:>6.1}s "), gray),
-            Span::styled(format!("{}: {}", self.name, self.fields), msg_style),
+            Span::styled(self.rate_label(now, snapshot.timestamp), gray),
+            Span::styled(summary(self.name, snapshot.fields, template), msg_style),
         ])
     }
 }
 
+/// Something a tracker noticed, pushed to the Events tab.
+pub trait Report {
+    fn name(&self) -> &'static str;
+    /// Raised as an alert rather than noted as a diagnostic.
+    fn is_alert(&self) -> bool;
+    /// `(sys_id, comp_id)` the event is shown under.
+    fn source(&self) -> (u8, u8);
+    fn describe(&self) -> String;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EventKind {
+    /// A MAVLink message as received.
+    Message,
+    /// Generated when signed traffic looks replayed or spoofed.
+    Security,
+    /// A frame that could not be decoded with the active dialect.
+    Diagnostic,
+    /// Raised when a stream deviates from its configured expectations.
+    Alert,
+}
+
+impl EventKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            EventKind::Message => "message",
+            EventKind::Security => "security",
+            EventKind::Diagnostic => "diagnostic",
+            EventKind::Alert => "alert",
+        }
+    }
+}
+
 pub struct MessageEntry {
     pub sys_color: Color,
     pub comp_color: Color,
@@ -65,33 +240,148 @@ pub struct MessageEntry {
     pub comp_id: u8,
     pub name: &'static str,
     pub fields: String,
+    pub link: usize,
+    /// Arrived as a MAVLink 1 frame.
+    pub v1: bool,
+    pub signature: SignatureStatus,
+    /// Copy of a frame that already arrived on another link.
+    pub duplicate: bool,
+    pub kind: EventKind,
+    /// `None` for events mavsnark generated itself.
+    pub direction: Option<Direction>,
+    /// Payload bytes; empty for generated events.
+    pub payload: Vec<u8>,
+    /// The frame as received; empty for generated events and events read
+    /// back from a session.
+    pub frame: Vec<u8>,
+    /// When it was received or raised.
+    pub timestamp: DateTime<Utc>,
 }
 
 impl MessageEntry {
+    /// An event with no frame behind it, coloured by its source: unsigned,
+    /// without direction or bytes. Override the rest, such as `msg_color`,
+    /// with struct update syntax.
+    pub fn event(
+        kind: EventKind,
+        name: &'static str,
+        fields: String,
+        (sys_id, comp_id): (u8, u8),
+        link: usize,
+        at: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            sys_color: id_color(sys_id),
+            comp_color: id_color(comp_id),
+            msg_color: None,
+            sys_id,
+            comp_id,
+            name,
+            fields,
+            link,
+            v1: false,
+            signature: SignatureStatus::Unsigned,
+            duplicate: false,
+            kind,
+            direction: None,
+            payload: Vec::new(),
+            frame: Vec::new(),
+            timestamp: at,
+        }
+    }
+
     pub fn parsed_fields(&self) -> Vec<(&str, &str)> {
         parse_fields(&self.fields)
     }
 
-    pub fn to_line(&self) -> Line<'_> {
+    /// A highlight rule's colour wins over the severity's, which wins over
+    /// the message category's.
+    pub fn to_line(
+        &self,
+        template: Option<&Template>,
+        highlights: &Highlights,
+        severity: Severity,
+    ) -> Line<'_> {
         let sys_style = Style::default().fg(self.sys_color);
         let comp_style = Style::default().fg(self.comp_color);
-        let msg_style = match self.msg_color {
+        let highlight = highlights.color(self.sys_id, self.comp_id, self.name, &self.fields);
+        let mut msg_style = match highlight.or(severity.color()).or(self.msg_color) {
             Some(c) => Style::default().fg(c),
             None => Style::default(),
         };
-        Line::from(vec![
+        if severity == Severity::Critical {
+            msg_style = msg_style.bold();
+        }
+        let mut spans = vec![
             Span::raw("["),
             Span::styled(format!("{:>3}", self.sys_id), sys_style),
             Span::raw(":"),
             Span::styled(format!("{:>3}", self.comp_id), comp_style),
             Span::raw("] "),
-            Span::styled(format!("{}: {}", self.name, self.fields), msg_style),
-        ])
+            Span::styled(
+                format!("{} ", self.direction.map_or(" ", Direction::arrow)),
+                Style::default().fg(Color::DarkGray),
+            ),
+        ];
+        if self.duplicate {
+            spans.push(Span::styled("DUP ", Style::default().fg(Color::DarkGray)));
+        }
+        match self.kind {
+            EventKind::Message => {}
+            EventKind::Security => spans.push(Span::styled(
+                "SECURITY ",
+                Style::default().fg(Color::Red).bold(),
+            )),
+            EventKind::Diagnostic => spans.push(Span::styled(
+                "DIAG ",
+                Style::default().fg(Color::Yellow).bold(),
+            )),
+            EventKind::Alert => spans.push(Span::styled(
+                "ALERT ",
+                Style::default().fg(Color::LightRed).bold(),
+            )),
+        }
+        spans.push(Span::styled(
+            summary(self.name, &self.fields, template),
+            msg_style,
+        ));
+        Line::from(spans)
+    }
+
+    /// One JSON object, without a trailing newline.
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"event\":\"{}\",\"kind\":\"{}\",\"sys\":{},\"comp\":{},\"details\":\"{}\",\"time\":\"{}\"}}",
+            json_escape(self.name),
+            self.kind.name(),
+            self.sys_id,
+            self.comp_id,
+            json_escape(&self.fields),
+            self.timestamp.to_rfc3339_opts(SecondsFormat::Millis, true)
+        )
+    }
+}
+
+pub(crate) fn json_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c < ' ' => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
     }
+    out
 }
 
 #[cfg(test)]
 mod tests {
+    use chrono::TimeDelta;
+
     use super::*;
 
     #[test]
@@ -128,9 +418,17 @@ mod tests {
             comp_id: 1,
             name: "TEST",
             fields: "x: 10, y: 20".to_string(),
+            v1: false,
+            signature: SignatureStatus::Unsigned,
             timestamp: Utc::now(),
+            link: 0,
+            arrivals: ArrivalStats::default(),
+            direction: Direction::Downlink,
+            payload: Vec::new(),
+            frame: Vec::new(),
+            history: VecDeque::new(),
         };
-        let fields = entry.parsed_fields();
+        let fields = entry.at(None).unwrap().parsed_fields();
         assert_eq!(fields, vec![("x", "10"), ("y", "20")]);
     }
 
@@ -144,8 +442,163 @@ mod tests {
             comp_id: 1,
             name: "TEST",
             fields: "cmd: 42".to_string(),
+            link: 0,
+            duplicate: false,
+            kind: EventKind::Message,
+            direction: None,
+            payload: Vec::new(),
+            frame: Vec::new(),
+            v1: false,
+            signature: SignatureStatus::Unsigned,
+            timestamp: Utc::now(),
         };
         let fields = entry.parsed_fields();
         assert_eq!(fields, vec![("cmd", "42")]);
     }
+
+    #[test]
+    fn message_entry_as_json() {
+        use chrono::TimeZone;
+
+        let entry = MessageEntry {
+            sys_color: Color::Red,
+            comp_color: Color::Cyan,
+            msg_color: None,
+            sys_id: 1,
+            comp_id: 1,
+            name: "VIBRATION_HIGH",
+            fields: "level: 42.5, note: \"x\\y\"\n".to_string(),
+            link: 0,
+            duplicate: false,
+            kind: EventKind::Alert,
+            direction: None,
+            payload: Vec::new(),
+            frame: Vec::new(),
+            v1: false,
+            signature: SignatureStatus::Unsigned,
+            timestamp: Utc.with_ymd_and_hms(2024, 5, 1, 12, 30, 0).unwrap(),
+        };
+        assert_eq!(
+            entry.to_json(),
+            "{\"event\":\"VIBRATION_HIGH\",\"kind\":\"alert\",\"sys\":1,\"comp\":1,\
+             \"details\":\"level: 42.5, note: \\\"x\\\\y\\\"\\n\",\
+             \"time\":\"2024-05-01T12:30:00.000Z\"}"
+        );
+    }
+
+    #[test]
+    fn parse_keeps_arrays_whole() {
+        let result = parse_fields("len: 2, data: [1, 2, 0], seq: 3");
+        assert_eq!(
+            result,
+            vec![("len", "2"), ("data", "[1, 2, 0]"), ("seq", "3")]
+        );
+    }
+
+    #[test]
+    fn stream_entry_goes_stale() {
+        let t0 = DateTime::from_timestamp(100, 0).unwrap();
+        let mut entry = StreamEntry {
+            sys_color: Color::Red,
+            comp_color: Color::Cyan,
+            msg_color: None,
+            sys_id: 1,
+            comp_id: 1,
+            name: "TEST",
+            fields: String::new(),
+            v1: false,
+            signature: SignatureStatus::Unsigned,
+            timestamp: t0,
+            link: 0,
+            arrivals: ArrivalStats::default(),
+            direction: Direction::Downlink,
+            payload: Vec::new(),
+            frame: Vec::new(),
+            history: VecDeque::new(),
+        };
+        assert!(!entry.is_stale(t0 + TimeDelta::seconds(4)));
+        assert!(entry.is_stale(t0 + TimeDelta::seconds(6)));
+        for i in 0..=10 {
+            entry.arrivals.record(t0 + TimeDelta::milliseconds(i * 100));
+        }
+        entry.timestamp = t0 + TimeDelta::seconds(1);
+        assert!(!entry.is_stale(t0 + TimeDelta::milliseconds(1500)));
+        assert!(entry.is_stale(t0 + TimeDelta::milliseconds(2500)));
+    }
+
+    #[test]
+    fn rows_show_their_rate_next_to_the_age() {
+        let t0 = DateTime::from_timestamp(100, 0).unwrap();
+        let mut entry = StreamEntry {
+            sys_color: Color::Red,
+            comp_color: Color::Cyan,
+            msg_color: None,
+            sys_id: 1,
+            comp_id: 1,
+            name: "ATTITUDE",
+            fields: "roll: 1".to_string(),
+            v1: false,
+            signature: SignatureStatus::Unsigned,
+            timestamp: t0,
+            link: 0,
+            arrivals: ArrivalStats::default(),
+            direction: Direction::Downlink,
+            payload: Vec::new(),
+            frame: Vec::new(),
+            history: VecDeque::new(),
+        };
+        let text = |entry: &StreamEntry, ms| {
+            let at = Some(t0 + TimeDelta::milliseconds(ms));
+            entry.to_line(None, &Highlights::default(), at).to_string()
+        };
+        entry.arrivals.record(t0);
+        assert!(!text(&entry, 100).contains("Hz"));
+
+        for i in 1..=20 {
+            entry.arrivals.record(t0 + TimeDelta::milliseconds(i * 250));
+        }
+        entry.timestamp = t0 + TimeDelta::seconds(5);
+        assert!(text(&entry, 5100).contains("   4.0Hz ATTITUDE"));
+        // Decays once the stream goes quiet.
+        assert!(text(&entry, 7000).contains("   0.5Hz ATTITUDE"));
+    }
+
+    #[test]
+    fn value_as_of_comes_from_history() {
+        let t0 = Utc::now();
+        let sample = |secs, roll: &str| Sample {
+            timestamp: t0 + TimeDelta::seconds(secs),
+            fields: format!("roll: {roll}"),
+            payload: Vec::new(),
+            frame: Vec::new(),
+        };
+        let entry = StreamEntry {
+            sys_color: Color::Red,
+            comp_color: Color::Cyan,
+            msg_color: None,
+            sys_id: 1,
+            comp_id: 1,
+            name: "ATTITUDE",
+            fields: "roll: 3".to_string(),
+            v1: false,
+            signature: SignatureStatus::Unsigned,
+            timestamp: t0 + TimeDelta::seconds(3),
+            link: 0,
+            arrivals: ArrivalStats::default(),
+            direction: Direction::Downlink,
+            payload: Vec::new(),
+            frame: Vec::new(),
+            history: VecDeque::from([sample(1, "1"), sample(2, "2")]),
+        };
+        let fields = |secs| {
+            entry
+                .at(Some(t0 + TimeDelta::milliseconds(secs)))
+                .map(|s| s.fields)
+        };
+        assert_eq!(entry.at(None).unwrap().fields, "roll: 3");
+        assert_eq!(fields(5000), Some("roll: 3"));
+        assert_eq!(fields(2500), Some("roll: 2"));
+        assert_eq!(fields(1000), Some("roll: 1"));
+        assert_eq!(fields(500), None);
+    }
 }
@@ -0,0 +1,310 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+
+use crate::{
+    clock::ClockEstimate,
+    entries::{MessageEntry, StreamEntry, json_escape, parse_fields},
+    message::{Diagnostic, Incoming, MavMsg},
+    signing::SignatureStatus,
+};
+
+/// One stream value or event, as written by [`write`].
+pub struct Record<'a> {
+    pub name: &'a str,
+    /// `stream`, or the event kind.
+    pub kind: &'a str,
+    pub sys_id: u8,
+    pub comp_id: u8,
+    pub link: usize,
+    pub v1: bool,
+    pub signature: SignatureStatus,
+    pub timestamp: DateTime<Utc>,
+    /// When the vehicle sent it, by its clock; see [`Record::with_clock`].
+    pub sent: Option<DateTime<Utc>>,
+    pub fields: &'a str,
+}
+
+impl<'a> Record<'a> {
+    /// The row's value at `as_of`, or the latest for `None`; `None` if the
+    /// row had no value yet.
+    pub fn stream(entry: &'a StreamEntry, as_of: Option<DateTime<Utc>>) -> Option<Self> {
+        let snapshot = entry.at(as_of)?;
+        Some(Self {
+            name: entry.name,
+            kind: "stream",
+            sys_id: entry.sys_id,
+            comp_id: entry.comp_id,
+            link: entry.link,
+            v1: entry.v1,
+            signature: entry.signature,
+            timestamp: snapshot.timestamp,
+            sent: None,
+            fields: snapshot.fields,
+        })
+    }
+
+    pub fn event(entry: &'a MessageEntry) -> Self {
+        Self {
+            name: entry.name,
+            kind: entry.kind.name(),
+            sys_id: entry.sys_id,
+            comp_id: entry.comp_id,
+            link: entry.link,
+            v1: entry.v1,
+            signature: entry.signature,
+            timestamp: entry.timestamp,
+            sent: None,
+            fields: &entry.fields,
+        }
+    }
+
+    /// A message as it arrives, with its fields as rendered by
+    /// [`MavMsg::fields`].
+    pub fn message(msg: &MavMsg, fields: &'a str) -> Self {
+        Self {
+            name: msg.msg_type(),
+            kind: "message",
+            sys_id: msg.header.system_id,
+            comp_id: msg.header.component_id,
+            link: msg.link,
+            v1: msg.is_v1(),
+            signature: msg.signature_status(),
+            timestamp: msg.timestamp,
+            sent: None,
+            fields,
+        }
+    }
+
+    /// A message only the `--dialect` extension or `--definitions` decode.
+    pub fn decoded(diag: &'a Diagnostic) -> Self {
+        Self {
+            name: diag.name,
+            kind: "message",
+            sys_id: diag.header.system_id,
+            comp_id: diag.header.component_id,
+            link: diag.link,
+            v1: false,
+            signature: SignatureStatus::Unsigned,
+            timestamp: diag.timestamp,
+            sent: None,
+            fields: &diag.fields,
+        }
+    }
+
+    /// Adds when the vehicle sent the record, from its `time_boot_ms` and
+    /// the vehicle's clock estimate, so records from links captured on
+    /// different machines line up.
+    pub fn with_clock(mut self, clock: Option<&ClockEstimate>) -> Self {
+        self.sent = clock.and_then(|est| est.sent(self.fields));
+        self
+    }
+
+    fn fields_json(&self) -> String {
+        let fields: Vec<String> = parse_fields(self.fields)
+            .into_iter()
+            .map(|(k, v)| format!("\"{}\":{}", json_escape(k), json_value(v)))
+            .collect();
+        format!("{{{}}}", fields.join(","))
+    }
+
+    pub(crate) fn to_json(&self) -> String {
+        let sent = match self.sent {
+            Some(at) => format!(",\"sent\":\"{}\"", time(at)),
+            None => String::new(),
+        };
+        format!(
+            "{{\"header\":{{\"sys\":{},\"comp\":{},\"link\":{},\"mavlink\":{},\"signature\":\"{}\"}},\
+             \"time\":\"{}\"{sent},\"message\":\"{}\",\"kind\":\"{}\",\"fields\":{}}}",
+            self.sys_id,
+            self.comp_id,
+            self.link,
+            if self.v1 { 1 } else { 2 },
+            self.signature.label(),
+            time(self.timestamp),
+            json_escape(self.name),
+            self.kind,
+            self.fields_json()
+        )
+    }
+
+    /// The record as one flat line, for `--headless` output piped into jq.
+    pub fn to_ndjson(&self) -> String {
+        format!(
+            "{{\"timestamp\":\"{}\",\"sysid\":{},\"compid\":{},\"type\":\"{}\",\"link\":{},\"fields\":{}}}",
+            time(self.timestamp),
+            self.sys_id,
+            self.comp_id,
+            json_escape(self.name),
+            self.link,
+            self.fields_json()
+        )
+    }
+}
+
+/// A decoded message as one line of JSON, for `--headless` and
+/// `--serve-ws`; `None` for hints and errors.
+pub fn ndjson(incoming: &Incoming) -> Option<String> {
+    match incoming {
+        Incoming::Message(msg) => Some(Record::message(msg, &msg.fields()).to_ndjson()),
+        Incoming::Diagnostic(diag) if !diag.is_hint() => Some(Record::decoded(diag).to_ndjson()),
+        Incoming::Diagnostic(_) => None,
+    }
+}
+
+/// A field value as JSON: numbers, booleans and arrays typed, anything else
+/// (enum names, flags, text) a string.
+fn json_value(value: &str) -> String {
+    let value = value.trim();
+    if let Some(items) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+        let items: Vec<String> = items
+            .split(',')
+            .map(str::trim)
+            .filter(|i| !i.is_empty())
+            .map(json_value)
+            .collect();
+        return format!("[{}]", items.join(","));
+    }
+    if matches!(value, "true" | "false") {
+        return value.to_string();
+    }
+    // Rust also parses forms JSON lacks, like `+1`, `.5` and `inf`.
+    let plain = value.starts_with(|c: char| c.is_ascii_digit() || c == '-')
+        && value.ends_with(|c: char| c.is_ascii_digit());
+    if plain && value.parse::<f64>().is_ok() {
+        return value.to_string();
+    }
+    let text = value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value);
+    format!("\"{}\"", json_escape(text))
+}
+
+pub(crate) fn time(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+/// Writes `records` as one JSON document, one record per line, for
+/// attaching to bug reports.
+pub fn write(out: &mut impl Write, records: &[Record], at: DateTime<Utc>) -> io::Result<()> {
+    writeln!(
+        out,
+        "{{\"exported\":\"{}\",\"tool\":\"mavsnark {}\",\"records\":[",
+        time(at),
+        env!("CARGO_PKG_VERSION")
+    )?;
+    for (i, record) in records.iter().enumerate() {
+        let comma = if i + 1 < records.len() { "," } else { "" };
+        writeln!(out, "{}{comma}", record.to_json())?;
+    }
+    writeln!(out, "]}}")
+}
+
+pub fn save(path: &Path, records: &[Record], at: DateTime<Utc>) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create_new(path)?);
+    write(&mut out, records, at)?;
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeDelta, TimeZone};
+    use mavlink::{
+        MavHeader,
+        common::{MavMessage, SYSTEM_TIME_DATA},
+    };
+
+    use super::*;
+    use crate::clock::ClockTracker;
+
+    #[test]
+    fn values_are_typed() {
+        assert_eq!(json_value("0.25"), "0.25");
+        assert_eq!(json_value("-3"), "-3");
+        assert_eq!(json_value("true"), "true");
+        assert_eq!(json_value("[1, 2, 0]"), "[1,2,0]");
+        assert_eq!(json_value("[]"), "[]");
+        assert_eq!(json_value("MAV_TYPE_QUADROTOR"), "\"MAV_TYPE_QUADROTOR\"");
+        assert_eq!(json_value("\"PreArm\""), "\"PreArm\"");
+        assert_eq!(json_value("NaN"), "\"NaN\"");
+        assert_eq!(json_value(".5"), "\".5\"");
+    }
+
+    #[test]
+    fn writes_a_document() {
+        let at = Utc.with_ymd_and_hms(2024, 5, 1, 12, 30, 0).unwrap();
+        let record = Record {
+            name: "ATTITUDE",
+            kind: "stream",
+            sys_id: 1,
+            comp_id: 1,
+            link: 0,
+            v1: false,
+            signature: SignatureStatus::Unsigned,
+            timestamp: at,
+            sent: None,
+            fields: "time_boot_ms: 1000, roll: 0.5",
+        };
+        let mut out = Vec::new();
+        write(&mut out, std::slice::from_ref(&record), at).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("{\"exported\":\"2024-05-01T12:30:00.000Z\""));
+        assert_eq!(
+            lines[1],
+            "{\"header\":{\"sys\":1,\"comp\":1,\"link\":0,\"mavlink\":2,\"signature\":\"unsigned\"},\
+             \"time\":\"2024-05-01T12:30:00.000Z\",\"message\":\"ATTITUDE\",\"kind\":\"stream\",\
+             \"fields\":{\"time_boot_ms\":1000,\"roll\":0.5}}"
+        );
+        assert_eq!(lines[2], "]}");
+        assert_eq!(
+            record.to_ndjson(),
+            "{\"timestamp\":\"2024-05-01T12:30:00.000Z\",\"sysid\":1,\"compid\":1,\
+             \"type\":\"ATTITUDE\",\"link\":0,\"fields\":{\"time_boot_ms\":1000,\"roll\":0.5}}"
+        );
+    }
+
+    #[test]
+    fn sent_time_from_the_clock_estimate() {
+        let at = Utc.with_ymd_and_hms(2024, 5, 1, 12, 30, 0).unwrap();
+        let mut clock = ClockTracker::new();
+        let system_time = MavMessage::SYSTEM_TIME(SYSTEM_TIME_DATA {
+            time_unix_usec: 0,
+            time_boot_ms: 500,
+        });
+        clock.observe(&MavMsg {
+            timestamp: at,
+            ..MavMsg::new(
+                MavHeader {
+                    system_id: 1,
+                    component_id: 1,
+                    sequence: 0,
+                },
+                system_time,
+                0,
+            )
+        });
+        let record = Record {
+            name: "ATTITUDE",
+            kind: "stream",
+            sys_id: 1,
+            comp_id: 1,
+            link: 0,
+            v1: false,
+            signature: SignatureStatus::Unsigned,
+            timestamp: at + TimeDelta::seconds(1),
+            sent: None,
+            fields: "time_boot_ms: 1000, roll: 0.5",
+        }
+        .with_clock(clock.get(1, 1));
+        assert!(record.to_json().contains(
+            "\"time\":\"2024-05-01T12:30:01.000Z\",\"sent\":\"2024-05-01T12:30:00.500Z\""
+        ));
+    }
+}
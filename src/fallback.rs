@@ -0,0 +1,135 @@
+use std::env;
+
+use ratatui::{buffer::Buffer, style::Color};
+
+/// Terminals known to lack unicode glyphs, or to show only the eight basic
+/// colours.
+const LIMITED_TERMS: &[&str] = &["dumb", "ansi", "vt100", "vt102", "vt220"];
+
+/// How far the drawn frame is degraded for the terminal it is shown on.
+///
+/// Applied to the finished buffer rather than at each widget, so the UI code
+/// keeps drawing with box characters and the full palette.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Fallback {
+    /// Replace non-ASCII symbols (borders, arrows, chart markers).
+    pub ascii: bool,
+    /// Map the bright colours onto the eight basic ones.
+    pub basic_colors: bool,
+}
+
+impl Fallback {
+    /// Inspects `TERM` and the locale; `force_ascii` (`--ascii`) turns on both.
+    pub fn detect(force_ascii: bool) -> Self {
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .into_iter()
+            .find_map(|var| env::var(var).ok().filter(|v| !v.is_empty()));
+        let term = env::var("TERM").ok();
+        Self::from_env(force_ascii, term.as_deref(), locale.as_deref())
+    }
+
+    fn from_env(force_ascii: bool, term: Option<&str>, locale: Option<&str>) -> Self {
+        let limited_term = term.is_some_and(|t| LIMITED_TERMS.contains(&t));
+        // An unset locale is common in containers whose terminal is fine.
+        let non_utf8_locale = locale.is_some_and(|l| {
+            let l = l.to_ascii_lowercase();
+            !l.contains("utf-8") && !l.contains("utf8")
+        });
+        Self {
+            ascii: force_ascii || limited_term || non_utf8_locale || term == Some("linux"),
+            basic_colors: force_ascii || limited_term,
+        }
+    }
+
+    pub fn apply(self, buf: &mut Buffer) {
+        if self == Self::default() {
+            return;
+        }
+        for cell in &mut buf.content {
+            if self.ascii && !cell.symbol().is_ascii() {
+                let c = cell.symbol().chars().next().map_or(' ', ascii_symbol);
+                cell.set_char(c);
+            }
+            if self.basic_colors {
+                cell.fg = basic_color(cell.fg);
+                cell.bg = basic_color(cell.bg);
+            }
+        }
+    }
+}
+
+fn ascii_symbol(c: char) -> char {
+    match c {
+        '─' | '━' | '═' => '-',
+        '│' | '┃' | '║' | '▏' => '|',
+        '┌' | '┐' | '└' | '┘' | '├' | '┤' | '┬' | '┴' | '┼' | '╭' | '╮' | '╰' | '╯' => {
+            '+'
+        }
+        '↑' | '▲' => '^',
+        '↓' | '▼' => 'v',
+        '←' | '◄' => '<',
+        '→' | '►' => '>',
+        '↔' => '=',
+        '…' => '~',
+        '×' => 'x',
+        'σ' => 's',
+        '•' | '·' => '.',
+        '█' | '▇' | '▆' | '▅' | '▄' | '▃' | '▂' | '▁' => '#',
+        '\u{2800}' => ' ',
+        '\u{2801}'..='\u{28ff}' => '.',
+        _ => '?',
+    }
+}
+
+fn basic_color(c: Color) -> Color {
+    match c {
+        Color::LightRed => Color::Red,
+        Color::LightGreen => Color::Green,
+        Color::LightYellow => Color::Yellow,
+        Color::LightBlue => Color::Blue,
+        Color::LightMagenta => Color::Magenta,
+        Color::LightCyan => Color::Cyan,
+        Color::DarkGray => Color::Gray,
+        Color::Rgb(..) | Color::Indexed(_) => Color::Reset,
+        c => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{layout::Rect, style::Style};
+
+    use super::*;
+
+    #[test]
+    fn modern_terminals_are_untouched() {
+        let f = Fallback::from_env(false, Some("xterm-256color"), Some("en_US.UTF-8"));
+        assert_eq!(f, Fallback::default());
+        let f = Fallback::from_env(false, Some("xterm-256color"), None);
+        assert_eq!(f, Fallback::default());
+    }
+
+    #[test]
+    fn limited_terminals_degrade() {
+        let f = Fallback::from_env(false, Some("vt100"), Some("en_US.UTF-8"));
+        assert!(f.ascii && f.basic_colors);
+        let f = Fallback::from_env(false, Some("xterm"), Some("C"));
+        assert!(f.ascii && !f.basic_colors);
+        let f = Fallback::from_env(true, Some("xterm-256color"), Some("C.UTF-8"));
+        assert!(f.ascii && f.basic_colors);
+    }
+
+    #[test]
+    fn apply_rewrites_symbols_and_colors() {
+        let mut buf = Buffer::empty(Rect::new(0, 0, 4, 1));
+        buf.set_string(0, 0, "┌↓a…", Style::default().fg(Color::LightRed));
+        Fallback {
+            ascii: true,
+            basic_colors: true,
+        }
+        .apply(&mut buf);
+        let symbols: String = buf.content.iter().map(|c| c.symbol()).collect();
+        assert_eq!(symbols, "+va~");
+        assert_eq!(buf.content[0].fg, Color::Red);
+    }
+}
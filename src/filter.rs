@@ -0,0 +1,349 @@
+use std::str::FromStr;
+
+use crate::{entries::parse_fields, message};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+}
+
+impl Op {
+    /// Numbers compare by value; anything else ignoring case, and only for
+    /// (in)equality and `contains`.
+    fn holds(self, value: &str, expected: &str) -> bool {
+        let value = value.trim_matches('"');
+        if let (Ok(v), Ok(e)) = (value.parse::<f64>(), expected.parse::<f64>()) {
+            return match self {
+                Op::Eq => v == e,
+                Op::Ne => v != e,
+                Op::Lt => v < e,
+                Op::Le => v <= e,
+                Op::Gt => v > e,
+                Op::Ge => v >= e,
+                Op::Contains => value.contains(expected),
+            };
+        }
+        match self {
+            Op::Eq => value.eq_ignore_ascii_case(expected),
+            Op::Ne => !value.eq_ignore_ascii_case(expected),
+            Op::Contains => value.to_lowercase().contains(&expected.to_lowercase()),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+    Op(Op),
+    Word(String),
+    /// A quoted value.
+    Text(String),
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = s.char_indices().peekable();
+    while let Some((at, c)) = chars.next() {
+        let next = chars.peek().map(|&(_, c)| c);
+        let token = match (c, next) {
+            (c, _) if c.is_whitespace() => continue,
+            ('(', _) => Token::Open,
+            (')', _) => Token::Close,
+            ('&', Some('&'))
+            | ('|', Some('|'))
+            | ('=', Some('='))
+            | ('!', Some('='))
+            | ('<', Some('='))
+            | ('>', Some('=')) => {
+                chars.next();
+                match c {
+                    '&' => Token::And,
+                    '|' => Token::Or,
+                    '=' => Token::Op(Op::Eq),
+                    '!' => Token::Op(Op::Ne),
+                    '<' => Token::Op(Op::Le),
+                    _ => Token::Op(Op::Ge),
+                }
+            }
+            ('!', _) => Token::Not,
+            ('<', _) => Token::Op(Op::Lt),
+            ('>', _) => Token::Op(Op::Gt),
+            ('"', _) => {
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, '\\')) => text.extend(chars.next().map(|(_, c)| c)),
+                        Some((_, c)) => text.push(c),
+                        None => return Err(format!("unterminated string at {at}")),
+                    }
+                }
+                Token::Text(text)
+            }
+            (c, _) if is_word_char(c) => {
+                let mut word = c.to_string();
+                while let Some(&(_, c)) = chars.peek()
+                    && is_word_char(c)
+                {
+                    word.push(c);
+                    chars.next();
+                }
+                match word.to_ascii_lowercase().as_str() {
+                    "and" => Token::And,
+                    "or" => Token::Or,
+                    "not" => Token::Not,
+                    "contains" => Token::Op(Op::Contains),
+                    _ => Token::Word(word),
+                }
+            }
+            (c, _) => return Err(format!("unexpected {c:?} at {at}")),
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.' | '-' | '+')
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Or(Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    /// A bare message name, or a field that must be present.
+    Has(String),
+    Compare(String, Op, String),
+}
+
+/// Recursive descent, loosest binding first: `||`, `&&`, `!`.
+struct Parser {
+    tokens: Vec<Token>,
+    at: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.at)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.at).cloned();
+        self.at += 1;
+        token
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.not()?;
+        while self.peek() == Some(&Token::And) {
+            self.next();
+            expr = Expr::And(Box::new(expr), Box::new(self.not()?));
+        }
+        Ok(expr)
+    }
+
+    fn not(&mut self) -> Result<Expr, String> {
+        if self.peek() == Some(&Token::Not) {
+            self.next();
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        let subject = match self.next() {
+            Some(Token::Open) => {
+                let expr = self.or()?;
+                return match self.next() {
+                    Some(Token::Close) => Ok(expr),
+                    _ => Err("missing ')'".to_string()),
+                };
+            }
+            Some(Token::Word(word)) => word,
+            Some(token) => return Err(format!("unexpected {}", describe(&token))),
+            None => return Err("incomplete expression".to_string()),
+        };
+        let Some(&Token::Op(op)) = self.peek() else {
+            return Ok(Expr::Has(subject));
+        };
+        self.next();
+        match self.next() {
+            Some(Token::Word(value) | Token::Text(value)) => Ok(Expr::Compare(subject, op, value)),
+            _ => Err(format!("expected a value after {subject}")),
+        }
+    }
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Open => "'('".to_string(),
+        Token::Close => "')'".to_string(),
+        Token::And => "'&&'".to_string(),
+        Token::Or => "'||'".to_string(),
+        Token::Not => "'!'".to_string(),
+        Token::Op(op) => format!("{op:?}").to_lowercase(),
+        Token::Word(word) => format!("{word:?}"),
+        Token::Text(text) => format!("{text:?}"),
+    }
+}
+
+/// What a filter is checked against: one stream row or event.
+pub struct Row<'a> {
+    pub sys_id: u8,
+    pub comp_id: u8,
+    pub name: &'a str,
+    pub fields: &'a str,
+    pub link: usize,
+}
+
+impl Row<'_> {
+    /// The value of `sysid`, `compid`, `msg` and `link`, or of a field,
+    /// optionally qualified with its message (`ATTITUDE.roll`).
+    fn value(&self, subject: &str) -> Option<String> {
+        match subject.to_ascii_lowercase().as_str() {
+            "sysid" | "sys" => return Some(self.sys_id.to_string()),
+            "compid" | "comp" => return Some(self.comp_id.to_string()),
+            "msg" | "name" => return Some(self.name.to_string()),
+            "link" => return Some(self.link.to_string()),
+            _ => {}
+        }
+        let field = match subject.split_once('.') {
+            Some((name, field)) if name.eq_ignore_ascii_case(self.name) => field,
+            Some(_) => return None,
+            None => subject,
+        };
+        parse_fields(self.fields)
+            .into_iter()
+            .find(|(k, _)| *k == field)
+            .map(|(_, v)| v.to_string())
+    }
+}
+
+/// A display filter (`f`): `sysid==1 && msg=="ATTITUDE" && roll > 0.5`.
+/// Comparisons are `==`, `!=`, `<`, `<=`, `>`, `>=` and `contains`, joined
+/// with `&&`/`and`, `||`/`or`, `!`/`not` and parentheses. Besides `sysid`,
+/// `compid`, `msg` and `link`, any field name can be compared; a row
+/// without that field does not match. A bare message name matches that
+/// message, any other bare name rows that have such a field.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter {
+    text: String,
+    expr: Expr,
+}
+
+impl FromStr for Filter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            tokens: tokenize(s)?,
+            at: 0,
+        };
+        let expr = parser.or()?;
+        if let Some(token) = parser.peek() {
+            return Err(format!("unexpected {}", describe(token)));
+        }
+        Ok(Self {
+            text: s.trim().to_string(),
+            expr,
+        })
+    }
+}
+
+impl Filter {
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn matches(&self, row: &Row) -> bool {
+        eval(&self.expr, row)
+    }
+}
+
+fn eval(expr: &Expr, row: &Row) -> bool {
+    match expr {
+        Expr::Or(a, b) => eval(a, row) || eval(b, row),
+        Expr::And(a, b) => eval(a, row) && eval(b, row),
+        Expr::Not(e) => !eval(e, row),
+        Expr::Has(name) if message::is_message_name(name) => row.name == name.as_str(),
+        Expr::Has(subject) => row.value(subject).is_some(),
+        Expr::Compare(subject, op, expected) => row
+            .value(subject)
+            .is_some_and(|value| op.holds(&value, expected)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row<'a>(sys_id: u8, name: &'a str, fields: &'a str) -> Row<'a> {
+        Row {
+            sys_id,
+            comp_id: 1,
+            name,
+            fields,
+            link: 0,
+        }
+    }
+
+    fn matches(filter: &str, row: &Row) -> bool {
+        filter.parse::<Filter>().unwrap().matches(row)
+    }
+
+    #[test]
+    fn evaluates_expressions() {
+        let attitude = row(1, "ATTITUDE", "time_boot_ms: 10, roll: 0.6, pitch: -0.1");
+        let heartbeat = row(2, "HEARTBEAT", "system_status: MAV_STATE_ACTIVE");
+        let filter = "sysid==1 && msg==\"ATTITUDE\" && roll > 0.5";
+        assert!(matches(filter, &attitude));
+        assert!(!matches(filter, &heartbeat));
+        assert!(matches("ATTITUDE.pitch < 0 or sys == 2", &heartbeat));
+        assert!(matches("!(roll > 0.7)", &attitude));
+        assert!(!matches("HEARTBEAT.roll > 0", &attitude));
+        assert!(matches("system_status == mav_state_active", &heartbeat));
+        assert!(matches("system_status contains ACTIVE", &heartbeat));
+        assert!(matches("HEARTBEAT || roll", &attitude));
+        assert!(!matches("roll", &heartbeat));
+        // Missing fields never match, even with `!=`.
+        assert!(!matches("roll != 1", &heartbeat));
+        // `&&` binds tighter than `||`.
+        assert!(matches("sys == 2 && msg == X || roll >= 0.6", &attitude));
+    }
+
+    #[test]
+    fn reports_syntax_errors() {
+        for bad in [
+            "roll >",
+            "(sys == 1",
+            "sys == 1)",
+            "roll > 1 &&",
+            "\"text",
+            "a # b",
+        ] {
+            assert!(bad.parse::<Filter>().is_err(), "{bad}");
+        }
+        assert_eq!(" sys==1 ".parse::<Filter>().unwrap().text(), "sys==1");
+    }
+}
@@ -0,0 +1,112 @@
+use std::collections::BTreeMap;
+
+use mavlink::Message;
+
+use crate::message::MavMsg;
+
+/// A `(sys_id, comp_id)` endpoint. Component 0 on a target means "any
+/// component"; `(0, 0)` means broadcast.
+pub type Node = (u8, u8);
+
+/// Who-talks-to-whom, built from the sender header and the message's
+/// `target_system`/`target_component` fields.
+pub struct FlowGraph {
+    edges: BTreeMap<(Node, Node), BTreeMap<&'static str, u64>>,
+}
+
+impl Default for FlowGraph {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FlowGraph {
+    pub fn new() -> Self {
+        Self {
+            edges: BTreeMap::new(),
+        }
+    }
+
+    pub fn observe(&mut self, msg: &MavMsg) {
+        let from = (msg.header.system_id, msg.header.component_id);
+        let to = (
+            msg.msg.target_system_id().unwrap_or(0),
+            msg.msg.target_component_id().unwrap_or(0),
+        );
+        *self
+            .edges
+            .entry((from, to))
+            .or_default()
+            .entry(msg.msg_type())
+            .or_insert(0) += 1;
+    }
+
+    /// Edges in `(from, to)` order with their message counts, busiest first.
+    pub fn edges(&self) -> impl Iterator<Item = (Node, Node, Vec<(&'static str, u64)>)> + '_ {
+        self.edges.iter().map(|(&(from, to), counts)| {
+            let mut counts: Vec<_> = counts.iter().map(|(&n, &c)| (n, c)).collect();
+            counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+            (from, to, counts)
+        })
+    }
+
+    pub fn clear(&mut self) {
+        self.edges.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::{
+        MavHeader,
+        common::{COMMAND_LONG_DATA, HEARTBEAT_DATA, MavMessage, PARAM_REQUEST_LIST_DATA},
+    };
+
+    use super::*;
+
+    fn msg(sys_id: u8, comp_id: u8, msg: MavMessage) -> MavMsg {
+        MavMsg::new(
+            MavHeader {
+                system_id: sys_id,
+                component_id: comp_id,
+                sequence: 0,
+            },
+            msg,
+            0,
+        )
+    }
+
+    #[test]
+    fn untargeted_messages_are_broadcast() {
+        let mut g = FlowGraph::new();
+        g.observe(&msg(1, 1, MavMessage::HEARTBEAT(HEARTBEAT_DATA::default())));
+        let edges: Vec<_> = g.edges().collect();
+        assert_eq!(edges, vec![((1, 1), (0, 0), vec![("HEARTBEAT", 1)])]);
+    }
+
+    #[test]
+    fn targeted_messages_are_counted_per_edge() {
+        let mut g = FlowGraph::new();
+        let cmd = MavMessage::COMMAND_LONG(COMMAND_LONG_DATA {
+            target_system: 1,
+            target_component: 1,
+            ..Default::default()
+        });
+        let params = MavMessage::PARAM_REQUEST_LIST(PARAM_REQUEST_LIST_DATA {
+            target_system: 1,
+            target_component: 1,
+        });
+        g.observe(&msg(255, 190, cmd.clone()));
+        g.observe(&msg(255, 190, params));
+        g.observe(&msg(255, 190, cmd));
+        let edges: Vec<_> = g.edges().collect();
+        assert_eq!(
+            edges,
+            vec![(
+                (255, 190),
+                (1, 1),
+                vec![("COMMAND_LONG", 2), ("PARAM_REQUEST_LIST", 1)]
+            )]
+        );
+    }
+}
@@ -0,0 +1,160 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use mavlink::common::MavMessage;
+
+use crate::{geo, message::MavMsg, radio::HISTORY};
+
+/// A FOLLOW_TARGET older than this is not paired with vehicle positions.
+const STALE: TimeDelta = TimeDelta::seconds(3);
+
+/// The last FOLLOW_TARGET position.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Target {
+    pub sender: u8,
+    /// Degrees * 1e7.
+    pub position: (i32, i32),
+    /// Metres AMSL.
+    pub alt: f32,
+    pub at: DateTime<Utc>,
+}
+
+/// Where the target was as seen from one vehicle position report.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Separation {
+    pub at: DateTime<Utc>,
+    /// Horizontal metres.
+    pub distance: f64,
+    /// Degrees from the vehicle to the target, 0 north, clockwise.
+    pub bearing: f64,
+    /// Metres the target is above the vehicle.
+    pub alt_diff: f64,
+}
+
+/// Pairs FOLLOW_TARGET with each vehicle's GLOBAL_POSITION_INT so a
+/// follow-me run can be judged by separation rather than raw coordinates.
+#[derive(Default)]
+pub struct FollowTracker {
+    target: Option<Target>,
+    /// Per vehicle system ID, over the last [`HISTORY`].
+    vehicles: BTreeMap<u8, VecDeque<Separation>>,
+}
+
+impl FollowTracker {
+    pub fn observe(&mut self, msg: &MavMsg) {
+        let sys = msg.header.system_id;
+        match &msg.msg {
+            MavMessage::FOLLOW_TARGET(d) if (d.lat, d.lon) != (0, 0) => {
+                self.target = Some(Target {
+                    sender: sys,
+                    position: (d.lat, d.lon),
+                    alt: d.alt,
+                    at: msg.timestamp,
+                });
+            }
+            MavMessage::GLOBAL_POSITION_INT(d) if (d.lat, d.lon) != (0, 0) => {
+                let Some(target) = self.target else {
+                    return;
+                };
+                if target.sender == sys || msg.timestamp - target.at > STALE {
+                    return;
+                }
+                let (distance, bearing) = geo::distance_bearing((d.lat, d.lon), target.position);
+                let samples = self.vehicles.entry(sys).or_default();
+                samples.push_back(Separation {
+                    at: msg.timestamp,
+                    distance,
+                    bearing,
+                    alt_diff: f64::from(target.alt) - f64::from(d.alt) / 1000.0,
+                });
+                while samples
+                    .front()
+                    .is_some_and(|s| msg.timestamp - s.at > HISTORY)
+                {
+                    samples.pop_front();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn target(&self) -> Option<&Target> {
+        self.target.as_ref()
+    }
+
+    pub fn vehicles(&self) -> impl Iterator<Item = (&u8, &VecDeque<Separation>)> {
+        self.vehicles.iter()
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// `N`, `NE`, ... for a bearing in degrees.
+pub fn compass(bearing: f64) -> &'static str {
+    const POINTS: [&str; 8] = ["N", "NE", "E", "SE", "S", "SW", "W", "NW"];
+    POINTS[((bearing.rem_euclid(360.0) + 22.5) / 45.0) as usize % 8]
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::{
+        MavHeader,
+        common::{FOLLOW_TARGET_DATA, GLOBAL_POSITION_INT_DATA},
+    };
+
+    use super::*;
+
+    fn at(sys_id: u8, msg: MavMessage, secs: i64) -> MavMsg {
+        let header = MavHeader {
+            system_id: sys_id,
+            component_id: 1,
+            sequence: 0,
+        };
+        MavMsg {
+            timestamp: DateTime::from_timestamp(1_700_000_000 + secs, 0).unwrap(),
+            ..MavMsg::new(header, msg, 0)
+        }
+    }
+
+    fn vehicle(lat: i32, secs: i64) -> MavMsg {
+        at(
+            1,
+            MavMessage::GLOBAL_POSITION_INT(GLOBAL_POSITION_INT_DATA {
+                lat,
+                lon: 85_455_940,
+                alt: 500_000,
+                ..Default::default()
+            }),
+            secs,
+        )
+    }
+
+    #[test]
+    fn separation_from_each_vehicle_position() {
+        let mut follow = FollowTracker::default();
+        // No target yet.
+        follow.observe(&vehicle(473_977_420, 0));
+        follow.observe(&at(
+            255,
+            MavMessage::FOLLOW_TARGET(FOLLOW_TARGET_DATA {
+                lat: 473_987_420,
+                lon: 85_455_940,
+                alt: 480.0,
+                ..Default::default()
+            }),
+            1,
+        ));
+        follow.observe(&vehicle(473_977_420, 2));
+        // The target went quiet.
+        follow.observe(&vehicle(473_977_420, 10));
+
+        let (sys, samples) = follow.vehicles().next().unwrap();
+        assert_eq!((*sys, samples.len()), (1, 1));
+        let s = samples[0];
+        assert!((s.distance - 111.2).abs() < 0.5);
+        assert_eq!(compass(s.bearing), "N");
+        assert_eq!(s.alt_diff, -20.0);
+    }
+}
@@ -0,0 +1,798 @@
+use std::{collections::VecDeque, mem, ops::Range, sync::OnceLock};
+
+use mavlink::{MavlinkVersion, Message, calculate_crc};
+
+use crate::definitions;
+
+const STX_V1: u8 = 0xFE;
+const STX_V2: u8 = 0xFD;
+const HEADER_V1: usize = 6;
+const HEADER_V2: usize = 10;
+const SIGNATURE_LEN: usize = 13;
+const IFLAG_SIGNED: u8 = 0x01;
+/// Bytes an error keeps of what it skipped; a whole frame fits.
+const ERROR_BYTES: usize = 280;
+
+/// A message set the frame CRC can be checked against.
+pub struct Dialect {
+    pub name: &'static str,
+    /// Full (untruncated) payload length of a message id, if the id exists.
+    encoded_len: fn(u32) -> Option<usize>,
+    extra_crc: fn(u32) -> u8,
+    parse: fn(MavlinkVersion, u32, &[u8]) -> Option<(&'static str, String)>,
+    message_name: fn(&str) -> Option<&'static str>,
+}
+
+impl Dialect {
+    fn matches(&self, frame: &RawFrame) -> bool {
+        (self.encoded_len)(frame.msg_id()).is_some()
+            && frame.crc_valid((self.extra_crc)(frame.msg_id()))
+    }
+
+    /// Name and fields (as in [`crate::message::MavMsg::fields`]) of the
+    /// message in `frame`, if it decodes in this dialect.
+    pub fn decode(&self, frame: &RawFrame) -> Option<(&'static str, String)> {
+        let version = if frame.is_v2() {
+            MavlinkVersion::V2
+        } else {
+            MavlinkVersion::V1
+        };
+        (self.parse)(version, frame.msg_id(), frame.payload())
+    }
+
+    /// `name` as the dialect spells it, if it has such a message.
+    pub fn message_name(&self, name: &str) -> Option<&'static str> {
+        (self.message_name)(name)
+    }
+}
+
+macro_rules! dialect {
+    ($name:literal, $module:ident) => {
+        Dialect {
+            name: $name,
+            encoded_len: |id| {
+                let msg = mavlink::$module::MavMessage::default_message_from_id(id)?;
+                Some(msg.ser(MavlinkVersion::V1, &mut [0u8; 255]))
+            },
+            extra_crc: mavlink::$module::MavMessage::extra_crc,
+            parse: |version, id, payload| {
+                let msg = mavlink::$module::MavMessage::parse(version, id, payload).ok()?;
+                Some((msg.message_name(), crate::message::debug_fields(&msg)))
+            },
+            message_name: |name| {
+                let id = mavlink::$module::MavMessage::message_id_from_name(name)?;
+                let msg = mavlink::$module::MavMessage::default_message_from_id(id)?;
+                Some(msg.message_name())
+            },
+        }
+    };
+}
+
+/// The dialect messages are decoded with.
+pub const ACTIVE: Dialect = dialect!("common", common);
+
+/// Other compiled dialects consulted when a frame fails the active CRC check.
+pub const CANDIDATES: &[Dialect] = &[dialect!("ardupilotmega", ardupilotmega)];
+
+static EXTENSION: OnceLock<&'static Dialect> = OnceLock::new();
+
+/// Looks up `--dialect`: the active dialect or one of the candidates.
+pub fn parse_dialect(name: &str) -> Result<&'static Dialect, String> {
+    std::iter::once(&ACTIVE)
+        .chain(CANDIDATES)
+        .find(|d| d.name.eq_ignore_ascii_case(name))
+        .ok_or_else(|| {
+            let names: Vec<_> = std::iter::once(&ACTIVE)
+                .chain(CANDIDATES)
+                .map(|d| d.name)
+                .collect();
+            format!("unknown dialect {name:?} (expected {})", names.join(", "))
+        })
+}
+
+/// Decodes messages that only `dialect` has (`--dialect`) instead of
+/// reporting them as diagnostics. Set once at startup; later calls and the
+/// active dialect itself are ignored.
+pub fn extend(dialect: &'static Dialect) {
+    if dialect.name != ACTIVE.name {
+        let _ = EXTENSION.set(dialect);
+    }
+}
+
+/// The dialect set with [`extend`], if any.
+pub fn extension() -> Option<&'static Dialect> {
+    EXTENSION.get().copied()
+}
+
+/// One complete MAVLink 1 or 2 frame, from start marker to signature.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawFrame {
+    pub bytes: Vec<u8>,
+}
+
+impl RawFrame {
+    pub fn is_v2(&self) -> bool {
+        self.bytes[0] == STX_V2
+    }
+
+    fn header_len(&self) -> usize {
+        if self.is_v2() { HEADER_V2 } else { HEADER_V1 }
+    }
+
+    pub fn payload_len(&self) -> usize {
+        self.bytes[1] as usize
+    }
+
+    pub fn incompat_flags(&self) -> u8 {
+        if self.is_v2() { self.bytes[2] } else { 0 }
+    }
+
+    pub fn sequence(&self) -> u8 {
+        self.bytes[if self.is_v2() { 4 } else { 2 }]
+    }
+
+    pub fn system_id(&self) -> u8 {
+        self.bytes[if self.is_v2() { 5 } else { 3 }]
+    }
+
+    pub fn component_id(&self) -> u8 {
+        self.bytes[if self.is_v2() { 6 } else { 4 }]
+    }
+
+    pub fn msg_id(&self) -> u32 {
+        if self.is_v2() {
+            u32::from_le_bytes([self.bytes[7], self.bytes[8], self.bytes[9], 0])
+        } else {
+            u32::from(self.bytes[5])
+        }
+    }
+
+    pub fn payload(&self) -> &[u8] {
+        let start = self.header_len();
+        &self.bytes[start..start + self.payload_len()]
+    }
+
+    pub fn checksum(&self) -> u16 {
+        let at = self.header_len() + self.payload_len();
+        u16::from_le_bytes([self.bytes[at], self.bytes[at + 1]])
+    }
+
+    /// `(link_id, timestamp)` of the signature trailer, if signed.
+    pub fn signature(&self) -> Option<(u8, u64)> {
+        if self.incompat_flags() & IFLAG_SIGNED == 0 {
+            return None;
+        }
+        let at = self.header_len() + self.payload_len() + 2;
+        let mut ts = [0u8; 8];
+        ts[..6].copy_from_slice(&self.bytes[at + 1..at + 7]);
+        Some((self.bytes[at], u64::from_le_bytes(ts)))
+    }
+
+    pub fn crc_valid(&self, extra_crc: u8) -> bool {
+        let end = self.header_len() + self.payload_len();
+        calculate_crc(&self.bytes[1..end], extra_crc) == self.checksum()
+    }
+
+    /// Brute-forces the CRC_EXTRA byte the sender must have used.
+    fn sender_extra_crc(&self) -> Option<u8> {
+        (0..=u8::MAX).find(|&extra| self.crc_valid(extra))
+    }
+}
+
+/// Why a well-formed frame could not be decoded with the active dialect.
+#[derive(Debug, PartialEq)]
+pub enum DialectHint {
+    /// The message id exists in the active dialect but its CRC_EXTRA differs.
+    Mismatch {
+        msg_id: u32,
+        expected_extra: u8,
+        sender_extra: Option<u8>,
+        candidates: Vec<&'static str>,
+    },
+    /// The message id only exists in other compiled dialects.
+    Unknown {
+        msg_id: u32,
+        candidates: Vec<&'static str>,
+    },
+}
+
+impl DialectHint {
+    /// Other compiled dialects the frame's CRC is valid in.
+    pub fn candidates(&self) -> &[&'static str] {
+        match self {
+            Self::Mismatch { candidates, .. } | Self::Unknown { candidates, .. } => candidates,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Mismatch { .. } => "DIALECT_MISMATCH",
+            Self::Unknown { .. } => "UNKNOWN_MESSAGE",
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Mismatch {
+                msg_id,
+                expected_extra,
+                sender_extra,
+                candidates,
+            } => {
+                let sender = match sender_extra {
+                    Some(extra) => format!("{extra:#04x}"),
+                    None => "?".to_string(),
+                };
+                format!(
+                    "msg_id: {msg_id}, dialect: {}, crc_extra: {expected_extra:#04x}, sender_crc_extra: {sender}, candidates: {}",
+                    ACTIVE.name,
+                    candidate_list(candidates)
+                )
+            }
+            Self::Unknown { msg_id, candidates } => format!(
+                "msg_id: {msg_id}, dialect: {}, candidates: {}",
+                ACTIVE.name,
+                candidate_list(candidates)
+            ),
+        }
+    }
+}
+
+fn candidate_list(candidates: &[&str]) -> String {
+    if candidates.is_empty() {
+        "none".to_string()
+    } else {
+        candidates.join(" ")
+    }
+}
+
+/// What went wrong with bytes that did not make a frame.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FrameErrorKind {
+    /// A frame of a known message whose checksum does not match.
+    Crc,
+    /// A MAVLink 1 frame with a valid checksum but a payload length the
+    /// message does not have.
+    Length,
+    /// Bytes between frames that start no frame at all.
+    Framing,
+    /// A frame with a valid checksum whose payload still did not decode.
+    Decode(String),
+}
+
+impl FrameErrorKind {
+    pub const NAMES: [&str; 4] = ["CRC_ERROR", "LENGTH_ERROR", "FRAMING_ERROR", "DECODE_ERROR"];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Crc => Self::NAMES[0],
+            Self::Length => Self::NAMES[1],
+            Self::Framing => Self::NAMES[2],
+            Self::Decode(_) => Self::NAMES[3],
+        }
+    }
+}
+
+/// Bytes the parser could not turn into a message.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameError {
+    pub kind: FrameErrorKind,
+    /// The offending bytes, at most [`ERROR_BYTES`] of them.
+    pub bytes: Vec<u8>,
+    /// How many bytes there were.
+    pub len: usize,
+}
+
+impl FrameError {
+    pub fn new(kind: FrameErrorKind, bytes: &[u8]) -> Self {
+        Self {
+            kind,
+            bytes: bytes[..bytes.len().min(ERROR_BYTES)].to_vec(),
+            len: bytes.len(),
+        }
+    }
+
+    /// The header of the frame the bytes claim to be, if they are one.
+    pub fn frame(&self) -> Option<RawFrame> {
+        if self.kind == FrameErrorKind::Framing {
+            return None;
+        }
+        Some(RawFrame {
+            bytes: self.bytes.clone(),
+        })
+    }
+
+    /// `len: 21, msg_id: 30, bytes: fd 0b …`, as event fields.
+    pub fn describe(&self) -> String {
+        let mut hex: Vec<String> = self.bytes.iter().map(|b| format!("{b:02x}")).collect();
+        if self.len > self.bytes.len() {
+            hex.push("…".to_string());
+        }
+        let bytes = hex.join(" ");
+        match (&self.kind, self.frame()) {
+            (FrameErrorKind::Framing, _) | (_, None) => {
+                format!("skipped: {}, bytes: {bytes}", self.len)
+            }
+            (FrameErrorKind::Decode(error), Some(frame)) => format!(
+                "len: {}, msg_id: {}, error: {error}, bytes: {bytes}",
+                self.len,
+                frame.msg_id()
+            ),
+            (_, Some(frame)) => format!(
+                "len: {}, msg_id: {}, bytes: {bytes}",
+                self.len,
+                frame.msg_id()
+            ),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Parsed {
+    Frame(RawFrame),
+    Unrecognized(RawFrame, DialectHint),
+    Error(FrameError),
+}
+
+/// Splits a byte stream into MAVLink frames, resynchronising on noise and
+/// reporting what it had to skip.
+pub struct FrameParser {
+    buf: Vec<u8>,
+    /// Found but not returned yet: a frame waits behind the error reporting
+    /// the bytes before it.
+    ready: VecDeque<Parsed>,
+    /// Bytes skipped since the last frame, not reported yet: the first
+    /// [`ERROR_BYTES`], and how many.
+    skipped: Vec<u8>,
+    skipped_len: usize,
+    /// Upcoming bytes a CRC error already reported, not to be reported
+    /// again as skipped.
+    covered: usize,
+}
+
+impl Default for FrameParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrameParser {
+    pub fn new() -> Self {
+        Self {
+            buf: Vec::new(),
+            ready: VecDeque::new(),
+            skipped: Vec::new(),
+            skipped_len: 0,
+            covered: 0,
+        }
+    }
+
+    pub fn push(&mut self, data: &[u8]) {
+        self.buf.extend_from_slice(data);
+    }
+
+    /// Returns the next frame or error, or `None` once more bytes are
+    /// needed.
+    pub fn next_frame(&mut self) -> Option<Parsed> {
+        loop {
+            if let Some(parsed) = self.ready.pop_front() {
+                return Some(parsed);
+            }
+            let start = self.buf.iter().position(|&b| b == STX_V1 || b == STX_V2);
+            match start {
+                Some(start) => self.skip(start),
+                None => {
+                    self.skip(self.buf.len());
+                    return self.flush();
+                }
+            }
+
+            let Some(len) = frame_len(&self.buf) else {
+                return self.flush();
+            };
+            if len == 0 {
+                self.skip(1);
+                continue;
+            }
+            if self.buf.len() < len {
+                return self.flush();
+            }
+
+            let frame = RawFrame {
+                bytes: self.buf[..len].to_vec(),
+            };
+            let parsed = match classify(&frame) {
+                Verdict::Valid => Parsed::Frame(frame),
+                Verdict::Hint(hint) => Parsed::Unrecognized(frame, hint),
+                Verdict::Length => {
+                    Parsed::Error(FrameError::new(FrameErrorKind::Length, &frame.bytes))
+                }
+                // Still skipped a byte at a time: a good frame may start
+                // inside, if only the start marker was a corrupted byte.
+                Verdict::BadCrc if self.covered == 0 => {
+                    self.flush_skipped();
+                    self.ready.push_back(Parsed::Error(FrameError::new(
+                        FrameErrorKind::Crc,
+                        &frame.bytes,
+                    )));
+                    self.buf.drain(..1);
+                    self.covered = len - 1;
+                    continue;
+                }
+                // Not a frame after all: a stray start marker inside noise.
+                Verdict::BadCrc | Verdict::Noise => {
+                    self.skip(1);
+                    continue;
+                }
+            };
+            self.buf.drain(..len);
+            self.covered = 0;
+            self.flush_skipped();
+            self.ready.push_back(parsed);
+        }
+    }
+
+    /// Drops `n` bytes from the front, keeping those not reported yet.
+    fn skip(&mut self, n: usize) {
+        let reported = n.min(self.covered);
+        self.covered -= reported;
+        let keep = (ERROR_BYTES - self.skipped.len()).min(n - reported);
+        self.skipped
+            .extend_from_slice(&self.buf[reported..reported + keep]);
+        self.skipped_len += n - reported;
+        self.buf.drain(..n);
+    }
+
+    fn flush_skipped(&mut self) {
+        if self.skipped_len > 0 {
+            let mut error = FrameError::new(FrameErrorKind::Framing, &mem::take(&mut self.skipped));
+            error.len = mem::take(&mut self.skipped_len);
+            self.ready.push_back(Parsed::Error(error));
+        }
+    }
+
+    /// Reports what was skipped before waiting for more bytes.
+    fn flush(&mut self) -> Option<Parsed> {
+        self.flush_skipped();
+        self.ready.pop_front()
+    }
+}
+
+/// Total length of the frame starting at `buf[0]` (a start marker), `Some(0)`
+/// if the header is invalid, `None` if the header is not complete yet.
+pub fn frame_len(buf: &[u8]) -> Option<usize> {
+    let v2 = buf[0] == STX_V2;
+    let header = if v2 { HEADER_V2 } else { HEADER_V1 };
+    if buf.len() < header {
+        return None;
+    }
+    let payload = buf[1] as usize;
+    if !v2 {
+        return Some(header + payload + 2);
+    }
+    let flags = buf[2];
+    if flags & !IFLAG_SIGNED != 0 {
+        return Some(0);
+    }
+    let signature = if flags & IFLAG_SIGNED != 0 {
+        SIGNATURE_LEN
+    } else {
+        0
+    };
+    Some(header + payload + 2 + signature)
+}
+
+/// The part of a frame a byte belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Region {
+    Header,
+    Payload,
+    Checksum,
+    Signature,
+}
+
+impl Region {
+    pub fn name(self) -> &'static str {
+        match self {
+            Region::Header => "header",
+            Region::Payload => "payload",
+            Region::Checksum => "crc",
+            Region::Signature => "signature",
+        }
+    }
+}
+
+/// The regions of a whole frame, in order, cut short where `bytes` ends.
+pub fn regions(bytes: &[u8]) -> Vec<(Region, Range<usize>)> {
+    let (header, signed) = match bytes.first() {
+        Some(&STX_V2) => (
+            HEADER_V2,
+            bytes.get(2).is_some_and(|f| f & IFLAG_SIGNED != 0),
+        ),
+        Some(&STX_V1) => (HEADER_V1, false),
+        _ => return Vec::new(),
+    };
+    let payload = bytes.get(1).map_or(0, |&len| len as usize);
+    let signature = if signed { SIGNATURE_LEN } else { 0 };
+    let mut start = 0;
+    let mut regions = Vec::new();
+    for (region, len) in [
+        (Region::Header, header),
+        (Region::Payload, payload),
+        (Region::Checksum, 2),
+        (Region::Signature, signature),
+    ] {
+        let end = (start + len).min(bytes.len());
+        if end > start {
+            regions.push((region, start..end));
+        }
+        start = end;
+    }
+    regions
+}
+
+enum Verdict {
+    Valid,
+    Hint(DialectHint),
+    /// Valid checksum, but the wrong payload length for the message.
+    Length,
+    /// A known message of a plausible length, but the checksum is wrong.
+    BadCrc,
+    Noise,
+}
+
+fn classify(frame: &RawFrame) -> Verdict {
+    if ACTIVE.matches(frame) {
+        // MAVLink 2 may truncate the payload and add extensions, so only
+        // MAVLink 1 has one right length.
+        if !frame.is_v2() && (ACTIVE.encoded_len)(frame.msg_id()) != Some(frame.payload_len()) {
+            return Verdict::Length;
+        }
+        return Verdict::Valid;
+    }
+    let msg_id = frame.msg_id();
+    let mut candidates: Vec<&'static str> = CANDIDATES
+        .iter()
+        .filter(|d| d.matches(frame))
+        .map(|d| d.name)
+        .collect();
+    if let Some(definitions) = definitions::installed()
+        && definitions.matches(frame)
+    {
+        candidates.push(definitions.name);
+    }
+    if let Some(full_len) = (ACTIVE.encoded_len)(msg_id) {
+        // MAVLink 2 truncates trailing zeros, MAVLink 1 never does.
+        let plausible_len = if frame.is_v2() {
+            frame.payload_len() <= full_len
+        } else {
+            frame.payload_len() == full_len
+        };
+        // Brute-forcing is 256 CRCs; noise full of start markers would
+        // otherwise pay that at every one of them.
+        let sender_extra = plausible_len.then(|| frame.sender_extra_crc()).flatten();
+        if candidates.is_empty() && sender_extra.is_none() {
+            return if plausible_len {
+                Verdict::BadCrc
+            } else {
+                Verdict::Noise
+            };
+        }
+        return Verdict::Hint(DialectHint::Mismatch {
+            msg_id,
+            expected_extra: (ACTIVE.extra_crc)(msg_id),
+            sender_extra,
+            candidates,
+        });
+    }
+    if candidates.is_empty() {
+        return Verdict::Noise;
+    }
+    Verdict::Hint(DialectHint::Unknown { msg_id, candidates })
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::{
+        MAVLinkV2MessageRaw, MavHeader,
+        common::{HEARTBEAT_DATA, MavMessage},
+    };
+
+    use super::*;
+
+    fn encode<M: Message>(msg: &M) -> Vec<u8> {
+        let mut raw = MAVLinkV2MessageRaw::new();
+        raw.serialize_message(
+            MavHeader {
+                system_id: 1,
+                component_id: 2,
+                sequence: 3,
+            },
+            msg,
+        );
+        raw.raw_bytes().to_vec()
+    }
+
+    fn heartbeat() -> Vec<u8> {
+        encode(&MavMessage::HEARTBEAT(HEARTBEAT_DATA::default()))
+    }
+
+    fn reseal(bytes: &mut [u8], extra: u8) {
+        let end = bytes.len() - 2;
+        let crc = calculate_crc(&bytes[1..end], extra);
+        bytes[end..].copy_from_slice(&crc.to_le_bytes());
+    }
+
+    #[test]
+    fn parses_valid_frame() {
+        let mut p = FrameParser::new();
+        p.push(&heartbeat());
+        let Some(Parsed::Frame(frame)) = p.next_frame() else {
+            panic!("expected frame");
+        };
+        assert_eq!(frame.msg_id(), 0);
+        assert_eq!(frame.system_id(), 1);
+        assert_eq!(frame.component_id(), 2);
+        assert_eq!(frame.sequence(), 3);
+        assert!(p.next_frame().is_none());
+    }
+
+    #[test]
+    fn skips_leading_noise() {
+        let mut p = FrameParser::new();
+        p.push(&[0x00, 0xFD, 0x42, 0x13]);
+        p.push(&heartbeat());
+        // The noise is reported before the frame behind it.
+        let Some(Parsed::Error(error)) = p.next_frame() else {
+            panic!("expected error");
+        };
+        assert_eq!(error.kind, FrameErrorKind::Framing);
+        assert_eq!(error.bytes, [0x00, 0xFD, 0x42, 0x13]);
+        assert_eq!(error.describe(), "skipped: 4, bytes: 00 fd 42 13");
+        assert!(matches!(p.next_frame(), Some(Parsed::Frame(_))));
+        assert!(p.next_frame().is_none());
+    }
+
+    #[test]
+    fn corrupted_frame_is_a_crc_error() {
+        let mut bytes = heartbeat();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xFF;
+        let mut p = FrameParser::new();
+        p.push(&bytes);
+        p.push(&heartbeat());
+        let Some(Parsed::Error(error)) = p.next_frame() else {
+            panic!("expected error");
+        };
+        assert_eq!(error.kind, FrameErrorKind::Crc);
+        assert_eq!(error.bytes, bytes);
+        assert!(error.describe().starts_with("len: "));
+        // The rest of the bad frame is not reported again as noise.
+        assert!(matches!(p.next_frame(), Some(Parsed::Frame(_))));
+        assert!(p.next_frame().is_none());
+    }
+
+    #[test]
+    fn wrong_mavlink1_length_is_a_length_error() {
+        // HEARTBEAT is 9 bytes; this one claims 1.
+        let mut bytes = vec![STX_V1, 1, 0, 1, 1, 0, 0, 0, 0];
+        reseal(&mut bytes, 50);
+        let mut p = FrameParser::new();
+        p.push(&bytes);
+        let Some(Parsed::Error(error)) = p.next_frame() else {
+            panic!("expected error");
+        };
+        assert_eq!(error.kind, FrameErrorKind::Length);
+        assert_eq!(error.kind.name(), "LENGTH_ERROR");
+    }
+
+    #[test]
+    fn waits_for_partial_frame() {
+        let bytes = heartbeat();
+        let mut p = FrameParser::new();
+        p.push(&bytes[..5]);
+        assert!(p.next_frame().is_none());
+        p.push(&bytes[5..]);
+        assert!(matches!(p.next_frame(), Some(Parsed::Frame(_))));
+    }
+
+    #[test]
+    fn known_id_with_wrong_crc_extra_is_mismatch() {
+        let mut bytes = heartbeat();
+        reseal(&mut bytes, 0x42);
+        let mut p = FrameParser::new();
+        p.push(&bytes);
+        let Some(Parsed::Unrecognized(_, hint)) = p.next_frame() else {
+            panic!("expected hint");
+        };
+        assert_eq!(
+            hint,
+            DialectHint::Mismatch {
+                msg_id: 0,
+                expected_extra: 50,
+                sender_extra: Some(0x42),
+                candidates: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn unknown_id_names_candidate_dialect() {
+        let bytes = encode(&mavlink::ardupilotmega::MavMessage::MEMINFO(
+            mavlink::ardupilotmega::MEMINFO_DATA::default(),
+        ));
+        let mut p = FrameParser::new();
+        p.push(&bytes);
+        let Some(Parsed::Unrecognized(_, hint)) = p.next_frame() else {
+            panic!("expected hint");
+        };
+        assert_eq!(
+            hint,
+            DialectHint::Unknown {
+                msg_id: 152,
+                candidates: vec!["ardupilotmega"],
+            }
+        );
+    }
+
+    #[test]
+    fn candidate_dialect_decodes_its_own_messages() {
+        let bytes = encode(&mavlink::ardupilotmega::MavMessage::MEMINFO(
+            mavlink::ardupilotmega::MEMINFO_DATA {
+                brkval: 1,
+                freemem: 2048,
+                ..Default::default()
+            },
+        ));
+        let ardupilot = parse_dialect("ArduPilotMega").unwrap();
+        let (name, fields) = ardupilot.decode(&RawFrame { bytes }).unwrap();
+        assert_eq!(name, "MEMINFO");
+        assert!(fields.contains("freemem: 2048"), "{fields}");
+        assert_eq!(ardupilot.message_name("AHRS2"), Some("AHRS2"));
+        assert_eq!(ACTIVE.message_name("AHRS2"), None);
+        assert!(parse_dialect("px4").is_err());
+    }
+
+    #[test]
+    fn splits_a_frame_into_regions() {
+        let bytes = heartbeat();
+        let end = 10 + bytes[1] as usize;
+        assert_eq!(
+            regions(&bytes),
+            [
+                (Region::Header, 0..10),
+                (Region::Payload, 10..end),
+                (Region::Checksum, end..end + 2),
+            ]
+        );
+        let mut signed = bytes.clone();
+        signed[2] = IFLAG_SIGNED;
+        signed.extend([0; SIGNATURE_LEN]);
+        assert_eq!(
+            regions(&signed)[3],
+            (Region::Signature, end + 2..end + 2 + SIGNATURE_LEN)
+        );
+        // A cut-off frame.
+        assert_eq!(regions(&bytes[..end]).len(), 2);
+        assert!(regions(&[]).is_empty());
+    }
+
+    #[test]
+    fn signed_frame_includes_trailer() {
+        let mut raw = MAVLinkV2MessageRaw::new();
+        raw.serialize_message_for_signing(
+            MavHeader::default(),
+            &MavMessage::HEARTBEAT(HEARTBEAT_DATA::default()),
+        );
+        let mut bytes = raw.raw_bytes().to_vec();
+        let trailer = bytes.len() - SIGNATURE_LEN;
+        bytes[trailer] = 7;
+        bytes[trailer + 1] = 0x10;
+        let mut p = FrameParser::new();
+        p.push(&bytes);
+        let Some(Parsed::Frame(frame)) = p.next_frame() else {
+            panic!("expected frame");
+        };
+        assert_eq!(frame.signature(), Some((7, 0x10)));
+    }
+}
@@ -0,0 +1,35 @@
+/// Mean Earth radius in metres.
+const EARTH_RADIUS: f64 = 6_371_000.0;
+
+/// Great-circle distance in metres and initial bearing in degrees (0 north,
+/// clockwise) from the first position to the second, both in degrees * 1e7.
+pub fn distance_bearing(from: (i32, i32), to: (i32, i32)) -> (f64, f64) {
+    let rad = |e7: i32| (f64::from(e7) / 1e7).to_radians();
+    let (lat1, lon1, lat2, lon2) = (rad(from.0), rad(from.1), rad(to.0), rad(to.1));
+    let dlat = lat2 - lat1;
+    let dlon = lon2 - lon1;
+    let a = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+    let distance = 2.0 * EARTH_RADIUS * a.sqrt().asin();
+    let bearing = (dlon.sin() * lat2.cos())
+        .atan2(lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * dlon.cos())
+        .to_degrees()
+        .rem_euclid(360.0);
+    (distance, bearing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_distances_and_bearings() {
+        let origin = (473_977_420, 85_455_940);
+        // 0.001 degrees of latitude is about 111 m, due north.
+        let (d, b) = distance_bearing(origin, (473_987_420, 85_455_940));
+        assert!((d - 111.2).abs() < 0.5, "{d}");
+        assert!(b.abs() < 1e-6, "{b}");
+        let (_, b) = distance_bearing(origin, (473_977_420, 85_445_940));
+        assert!((b - 270.0).abs() < 0.01, "{b}");
+        assert_eq!(distance_bearing(origin, origin).0, 0.0);
+    }
+}
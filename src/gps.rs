@@ -0,0 +1,264 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use mavlink::common::{GpsFixType, MavMessage};
+
+use crate::{entries::Report, geo, message::MavMsg};
+
+/// Readings further apart in time than this are not compared.
+const MAX_SKEW: TimeDelta = TimeDelta::seconds(2);
+
+/// One receiver's latest GPS_RAW_INT or GPS2_RAW.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GpsFix {
+    pub at: DateTime<Utc>,
+    pub fix_type: GpsFixType,
+    /// `None` when the receiver does not report it.
+    pub satellites: Option<u8>,
+    pub hdop: Option<f64>,
+    /// Degrees * 1e7.
+    pub lat: i32,
+    pub lon: i32,
+    /// Metres above MSL.
+    pub alt: f64,
+}
+
+impl GpsFix {
+    /// A 3D fix or better, so the position is worth comparing.
+    pub fn has_position(&self) -> bool {
+        self.fix_type as u8 >= GpsFixType::GPS_FIX_TYPE_3D_FIX as u8
+    }
+}
+
+/// How far apart the two receivers put the vehicle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Delta {
+    pub horizontal: f64,
+    pub vertical: f64,
+}
+
+#[derive(Debug, Default)]
+pub struct Vehicle {
+    /// GPS_RAW_INT, then GPS2_RAW.
+    pub receivers: [Option<GpsFix>; 2],
+    /// Whether the last comparison was beyond the threshold.
+    pub diverged: bool,
+}
+
+impl Vehicle {
+    /// `None` unless both receivers have a 3D fix from about the same time.
+    pub fn delta(&self) -> Option<Delta> {
+        let [Some(a), Some(b)] = &self.receivers else {
+            return None;
+        };
+        if !a.has_position() || !b.has_position() || (a.at - b.at).abs() > MAX_SKEW {
+            return None;
+        }
+        let (horizontal, _) = geo::distance_bearing((a.lat, a.lon), (b.lat, b.lon));
+        Some(Delta {
+            horizontal,
+            vertical: (a.alt - b.alt).abs(),
+        })
+    }
+}
+
+/// The receivers drifting apart beyond the threshold, or coming back.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GpsReport {
+    pub vehicle: u8,
+    pub delta: Delta,
+    pub threshold: f64,
+    pub diverged: bool,
+}
+
+impl Report for GpsReport {
+    fn name(&self) -> &'static str {
+        if self.diverged {
+            "GPS_DIVERGED"
+        } else {
+            "GPS_AGREE"
+        }
+    }
+
+    fn is_alert(&self) -> bool {
+        self.diverged
+    }
+
+    fn source(&self) -> (u8, u8) {
+        (self.vehicle, 0)
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "horizontal: {:.1} m, vertical: {:.1} m, threshold: {:.1} m",
+            self.delta.horizontal, self.delta.vertical, self.threshold
+        )
+    }
+}
+
+/// Pairs GPS_RAW_INT with GPS2_RAW per vehicle for the GPS comparison panel.
+pub struct GpsTracker {
+    vehicles: BTreeMap<u8, Vehicle>,
+    /// Horizontal distance in metres beyond which the receivers disagree.
+    threshold: f64,
+}
+
+impl Default for GpsTracker {
+    fn default() -> Self {
+        Self::new(5.0)
+    }
+}
+
+impl GpsTracker {
+    pub fn new(threshold: f64) -> Self {
+        Self {
+            vehicles: BTreeMap::new(),
+            threshold,
+        }
+    }
+
+    pub fn observe(&mut self, msg: &MavMsg) -> Option<GpsReport> {
+        let known = |value: u16| (value != u16::MAX).then(|| f64::from(value) / 100.0);
+        let (receiver, fix) = match &msg.msg {
+            MavMessage::GPS_RAW_INT(d) => (
+                0,
+                GpsFix {
+                    at: msg.timestamp,
+                    fix_type: d.fix_type,
+                    satellites: (d.satellites_visible != u8::MAX).then_some(d.satellites_visible),
+                    hdop: known(d.eph),
+                    lat: d.lat,
+                    lon: d.lon,
+                    alt: f64::from(d.alt) / 1000.0,
+                },
+            ),
+            MavMessage::GPS2_RAW(d) => (
+                1,
+                GpsFix {
+                    at: msg.timestamp,
+                    fix_type: d.fix_type,
+                    satellites: (d.satellites_visible != u8::MAX).then_some(d.satellites_visible),
+                    hdop: known(d.eph),
+                    lat: d.lat,
+                    lon: d.lon,
+                    alt: f64::from(d.alt) / 1000.0,
+                },
+            ),
+            _ => return None,
+        };
+        let vehicle = self.vehicles.entry(msg.header.system_id).or_default();
+        vehicle.receivers[receiver] = Some(fix);
+        let delta = vehicle.delta()?;
+        let diverged = delta.horizontal > self.threshold;
+        if diverged == vehicle.diverged {
+            return None;
+        }
+        vehicle.diverged = diverged;
+        Some(GpsReport {
+            vehicle: msg.header.system_id,
+            delta,
+            threshold: self.threshold,
+            diverged,
+        })
+    }
+
+    pub fn threshold(&self) -> f64 {
+        self.threshold
+    }
+
+    pub fn vehicle(&self, sys_id: u8) -> Option<&Vehicle> {
+        self.vehicles.get(&sys_id)
+    }
+
+    pub fn clear(&mut self) {
+        self.vehicles.clear();
+    }
+}
+
+/// `3D_FIX` for `GPS_FIX_TYPE_3D_FIX`.
+pub fn fix_name(fix_type: GpsFixType) -> String {
+    let name = format!("{fix_type:?}");
+    name.strip_prefix("GPS_FIX_TYPE_")
+        .unwrap_or(&name)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::{
+        MavHeader,
+        common::{GPS_RAW_INT_DATA, GPS2_RAW_DATA},
+    };
+
+    use super::*;
+
+    fn gps1(lat: i32) -> MavMsg {
+        MavMsg::new(
+            MavHeader::default(),
+            MavMessage::GPS_RAW_INT(GPS_RAW_INT_DATA {
+                fix_type: GpsFixType::GPS_FIX_TYPE_3D_FIX,
+                lat,
+                lon: 85_455_940,
+                alt: 488_000,
+                eph: 80,
+                satellites_visible: 14,
+                ..Default::default()
+            }),
+            0,
+        )
+    }
+
+    fn gps2(lat: i32) -> MavMsg {
+        MavMsg::new(
+            MavHeader::default(),
+            MavMessage::GPS2_RAW(GPS2_RAW_DATA {
+                fix_type: GpsFixType::GPS_FIX_TYPE_RTK_FIXED,
+                lat,
+                lon: 85_455_940,
+                alt: 489_500,
+                eph: u16::MAX,
+                satellites_visible: 20,
+                ..Default::default()
+            }),
+            0,
+        )
+    }
+
+    #[test]
+    fn compares_receivers_and_flags_divergence() {
+        let mut gps = GpsTracker::new(5.0);
+        assert_eq!(gps.observe(&gps1(473_977_420)), None);
+        // About 1.1 m north of the first receiver.
+        assert_eq!(gps.observe(&gps2(473_977_520)), None);
+        let vehicle = gps.vehicle(MavHeader::default().system_id).unwrap();
+        let delta = vehicle.delta().unwrap();
+        assert!((delta.horizontal - 1.1).abs() < 0.1, "{delta:?}");
+        assert!((delta.vertical - 1.5).abs() < 1e-9);
+        assert_eq!(vehicle.receivers[0].unwrap().hdop, Some(0.8));
+        assert_eq!(vehicle.receivers[1].unwrap().hdop, None);
+
+        // About 11 m: diverged, raised once, then back.
+        let report = gps.observe(&gps2(473_978_420)).unwrap();
+        assert!(report.is_alert());
+        assert_eq!(report.name(), "GPS_DIVERGED");
+        assert_eq!(gps.observe(&gps1(473_978_420)).unwrap().name(), "GPS_AGREE");
+    }
+
+    #[test]
+    fn no_comparison_without_a_fix() {
+        let mut gps = GpsTracker::default();
+        let mut no_fix = gps1(0);
+        if let MavMessage::GPS_RAW_INT(d) = &mut no_fix.msg {
+            d.fix_type = GpsFixType::GPS_FIX_TYPE_NO_FIX;
+        }
+        gps.observe(&no_fix);
+        assert_eq!(gps.observe(&gps2(473_977_420)), None);
+        assert!(
+            gps.vehicle(MavHeader::default().system_id)
+                .unwrap()
+                .delta()
+                .is_none()
+        );
+        assert_eq!(fix_name(GpsFixType::GPS_FIX_TYPE_RTK_FIXED), "RTK_FIXED");
+    }
+}
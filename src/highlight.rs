@@ -0,0 +1,197 @@
+use std::str::FromStr;
+
+use ratatui::style::Color;
+
+use crate::{derived::Formula, entries::parse_fields};
+
+/// Comparison operators, longest first so `<=` is not read as `<`.
+const OPERATORS: [&str; 6] = ["<=", ">=", "==", "!=", "<", ">"];
+
+/// `lhs op rhs`, compared as numbers. A bare name or quoted text on the
+/// right is compared as text with `==` and `!=`, so enum fields can be
+/// matched by name, e.g. `fix_type == GPS_FIX_TYPE_NO_FIX`.
+#[derive(Debug, Clone, PartialEq)]
+struct Condition {
+    lhs: Formula,
+    /// Set when the left side is a bare field name.
+    field: Option<String>,
+    op: &'static str,
+    /// `None` when the right side is text.
+    rhs: Option<Formula>,
+    text: String,
+}
+
+impl Condition {
+    fn parse(s: &str) -> Result<Self, String> {
+        let (at, op) = OPERATORS
+            .iter()
+            .filter_map(|op| Some((s.find(op)?, *op)))
+            .min_by_key(|&(at, op)| (at, usize::MAX - op.len()))
+            .ok_or_else(|| format!("{s:?}: expected a comparison such as \"alt > 100\""))?;
+        let (left, right) = (s[..at].trim(), s[at + op.len()..].trim());
+        let lhs = Formula::parse(left)?;
+        let field = (lhs.fields() == [left]).then(|| left.to_string());
+        let text = right.trim_matches('"').to_string();
+        let rhs = Formula::parse(right).ok().filter(|f| f.fields() != [right]);
+        if rhs.is_none() && !matches!(op, "==" | "!=") {
+            return Err(format!("{s:?}: {op} needs a number on the right"));
+        }
+        if rhs.is_none() && field.is_none() {
+            return Err(format!("{s:?}: text can only be compared with a field"));
+        }
+        Ok(Self {
+            lhs,
+            field,
+            op,
+            rhs,
+            text,
+        })
+    }
+
+    fn fields(&self) -> impl Iterator<Item = &str> {
+        self.lhs
+            .fields()
+            .into_iter()
+            .chain(self.rhs.iter().flat_map(Formula::fields))
+    }
+
+    fn holds(&self, fields: &[(&str, &str)]) -> bool {
+        let numbers = self
+            .rhs
+            .as_ref()
+            .and_then(|rhs| Some((self.lhs.eval(fields)?, rhs.eval(fields)?)));
+        if let Some((l, r)) = numbers {
+            return match self.op {
+                "<=" => l <= r,
+                ">=" => l >= r,
+                "==" => l == r,
+                "!=" => l != r,
+                "<" => l < r,
+                _ => l > r,
+            };
+        }
+        let Some(value) = self
+            .field
+            .as_deref()
+            .and_then(|f| fields.iter().find(|(k, _)| *k == f))
+            .map(|(_, v)| v.trim_matches('"'))
+        else {
+            return false;
+        };
+        match self.op {
+            "==" => value == self.text,
+            "!=" => value != self.text,
+            _ => false,
+        }
+    }
+}
+
+/// One `[[highlight]]` rule: rows that match every given criterion take
+/// the colour.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    pub message: Option<String>,
+    pub sys_id: Option<u8>,
+    pub comp_id: Option<u8>,
+    when: Option<Condition>,
+    pub color: Color,
+}
+
+impl Rule {
+    pub fn parse(
+        message: Option<String>,
+        sys_id: Option<u8>,
+        comp_id: Option<u8>,
+        when: Option<&str>,
+        color: &str,
+    ) -> Result<Self, String> {
+        let color = Color::from_str(color).map_err(|_| format!("unknown colour {color:?}"))?;
+        Ok(Self {
+            message,
+            sys_id,
+            comp_id,
+            when: when.map(Condition::parse).transpose()?,
+            color,
+        })
+    }
+
+    /// Field names the condition reads.
+    pub fn fields(&self) -> Vec<&str> {
+        self.when.iter().flat_map(Condition::fields).collect()
+    }
+
+    fn matches(&self, sys_id: u8, comp_id: u8, name: &str, fields: &str) -> bool {
+        self.message.as_deref().is_none_or(|m| m == name)
+            && self.sys_id.is_none_or(|id| id == sys_id)
+            && self.comp_id.is_none_or(|id| id == comp_id)
+            && self
+                .when
+                .as_ref()
+                .is_none_or(|c| c.holds(&parse_fields(fields)))
+    }
+}
+
+/// Config-defined row colours for the Stream and Events panels; the first
+/// matching rule wins.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Highlights {
+    rules: Vec<Rule>,
+}
+
+impl Highlights {
+    pub fn new(rules: Vec<Rule>) -> Self {
+        Self { rules }
+    }
+
+    pub fn color(&self, sys_id: u8, comp_id: u8, name: &str, fields: &str) -> Option<Color> {
+        self.rules
+            .iter()
+            .find(|r| r.matches(sys_id, comp_id, name, fields))
+            .map(|r| r.color)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_matching_rule_wins() {
+        let highlights = Highlights::new(vec![
+            Rule::parse(
+                Some("GPS_RAW_INT".to_string()),
+                None,
+                None,
+                Some("fix_type == GPS_FIX_TYPE_NO_FIX"),
+                "red",
+            )
+            .unwrap(),
+            Rule::parse(None, None, Some(191), None, "yellow").unwrap(),
+            Rule::parse(None, None, None, Some("alt / 1000 >= 120"), "#ff8800").unwrap(),
+        ]);
+        let gps = "fix_type: GPS_FIX_TYPE_NO_FIX, satellites_visible: 3";
+        assert_eq!(highlights.color(1, 1, "GPS_RAW_INT", gps), Some(Color::Red));
+        assert_eq!(
+            highlights.color(1, 191, "GPS_RAW_INT", gps),
+            Some(Color::Red)
+        );
+        assert_eq!(
+            highlights.color(1, 191, "HEARTBEAT", "type: 2"),
+            Some(Color::Yellow)
+        );
+        assert_eq!(
+            highlights.color(1, 1, "GLOBAL_POSITION_INT", "alt: 150000"),
+            Some(Color::Rgb(0xff, 0x88, 0x00))
+        );
+        // A missing field never matches.
+        assert_eq!(highlights.color(1, 1, "HEARTBEAT", "type: 2"), None);
+    }
+
+    #[test]
+    fn rejects_bad_rules() {
+        assert!(Rule::parse(None, None, None, Some("alt"), "red").is_err());
+        assert!(Rule::parse(None, None, None, Some("fix_type < NO_FIX"), "red").is_err());
+        assert!(Rule::parse(None, None, None, Some("abs(roll) == \"x\""), "red").is_err());
+        assert!(Rule::parse(None, None, None, None, "reddish").is_err());
+    }
+}
@@ -0,0 +1,202 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use mavlink::common::MavMessage;
+
+use crate::{entries::Report, geo, message::MavMsg};
+
+/// Where the vehicle is relative to its home.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Range {
+    pub at: DateTime<Utc>,
+    /// Horizontal distance, metres.
+    pub distance: f64,
+    /// Degrees from home to the vehicle, 0 north, clockwise.
+    pub bearing: f64,
+    /// Height above home, metres.
+    pub height: f64,
+}
+
+#[derive(Debug, Default)]
+pub struct Vehicle {
+    /// Degrees * 1e7 and metres MSL, from HOME_POSITION.
+    home: Option<(i32, i32, f64)>,
+    pub range: Option<Range>,
+    beyond: bool,
+}
+
+/// A vehicle going beyond the configured range from home, or coming back.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HomeReport {
+    pub vehicle: u8,
+    pub range: Range,
+    pub limit: f64,
+    pub beyond: bool,
+}
+
+impl Report for HomeReport {
+    fn name(&self) -> &'static str {
+        if self.beyond {
+            "HOME_RANGE_EXCEEDED"
+        } else {
+            "HOME_RANGE_OK"
+        }
+    }
+
+    fn is_alert(&self) -> bool {
+        self.beyond
+    }
+
+    fn source(&self) -> (u8, u8) {
+        (self.vehicle, 0)
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "distance: {:.0} m, bearing: {:.0}°, limit: {:.0} m",
+            self.range.distance, self.range.bearing, self.limit
+        )
+    }
+}
+
+/// Distance and bearing from HOME_POSITION to GLOBAL_POSITION_INT per
+/// vehicle.
+#[derive(Default)]
+pub struct HomeTracker {
+    vehicles: BTreeMap<u8, Vehicle>,
+    /// Metres; `None` never alerts.
+    limit: Option<f64>,
+}
+
+impl HomeTracker {
+    pub fn new(limit: Option<f64>) -> Self {
+        Self {
+            vehicles: BTreeMap::new(),
+            limit,
+        }
+    }
+
+    pub fn observe(&mut self, msg: &MavMsg) -> Option<HomeReport> {
+        let sys_id = msg.header.system_id;
+        let (lat, lon, alt) = match &msg.msg {
+            MavMessage::HOME_POSITION(d) => {
+                let vehicle = self.vehicles.entry(sys_id).or_default();
+                vehicle.home = Some((d.latitude, d.longitude, f64::from(d.altitude) / 1000.0));
+                return None;
+            }
+            MavMessage::GLOBAL_POSITION_INT(d) if d.lat != 0 || d.lon != 0 => {
+                (d.lat, d.lon, f64::from(d.alt) / 1000.0)
+            }
+            _ => return None,
+        };
+        let vehicle = self.vehicles.get_mut(&sys_id)?;
+        let (home_lat, home_lon, home_alt) = vehicle.home?;
+        let (distance, bearing) = geo::distance_bearing((home_lat, home_lon), (lat, lon));
+        let range = Range {
+            at: msg.timestamp,
+            distance,
+            bearing,
+            height: alt - home_alt,
+        };
+        vehicle.range = Some(range);
+        let limit = self.limit?;
+        let beyond = distance > limit;
+        if beyond == vehicle.beyond {
+            return None;
+        }
+        vehicle.beyond = beyond;
+        Some(HomeReport {
+            vehicle: sys_id,
+            range,
+            limit,
+            beyond,
+        })
+    }
+
+    pub fn limit(&self) -> Option<f64> {
+        self.limit
+    }
+
+    /// Vehicles with a home and a position, by system id.
+    pub fn ranges(&self) -> impl Iterator<Item = (u8, &Range)> {
+        self.vehicles
+            .iter()
+            .filter_map(|(&id, v)| Some((id, v.range.as_ref()?)))
+    }
+
+    pub fn clear(&mut self) {
+        self.vehicles.clear();
+    }
+}
+
+/// `NE` for 45°: the nearest of the eight compass points.
+pub fn compass_point(bearing: f64) -> &'static str {
+    const POINTS: [&str; 8] = ["N", "NE", "E", "SE", "S", "SW", "W", "NW"];
+    POINTS[((bearing.rem_euclid(360.0) + 22.5) / 45.0) as usize % 8]
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::{
+        MavHeader,
+        common::{GLOBAL_POSITION_INT_DATA, HOME_POSITION_DATA},
+    };
+
+    use super::*;
+
+    fn position(lat: i32) -> MavMsg {
+        MavMsg::new(
+            MavHeader::default(),
+            MavMessage::GLOBAL_POSITION_INT(GLOBAL_POSITION_INT_DATA {
+                lat,
+                lon: 85_455_940,
+                alt: 520_000,
+                ..Default::default()
+            }),
+            0,
+        )
+    }
+
+    #[test]
+    fn ranges_from_home_and_alerts_beyond_the_limit() {
+        let mut home = HomeTracker::new(Some(100.0));
+        // No home yet: nothing to measure from.
+        assert_eq!(home.observe(&position(473_977_420)), None);
+        assert_eq!(home.ranges().count(), 0);
+
+        home.observe(&MavMsg::new(
+            MavHeader::default(),
+            MavMessage::HOME_POSITION(HOME_POSITION_DATA {
+                latitude: 473_977_420,
+                longitude: 85_455_940,
+                altitude: 488_000,
+                ..Default::default()
+            }),
+            0,
+        ));
+        // About 55 m north.
+        assert_eq!(home.observe(&position(473_982_420)), None);
+        let (_, range) = home.ranges().next().unwrap();
+        assert!((range.distance - 55.6).abs() < 0.5, "{range:?}");
+        assert_eq!(compass_point(range.bearing), "N");
+        assert!((range.height - 32.0).abs() < 1e-9);
+
+        // About 111 m: beyond, raised once, then back.
+        let report = home.observe(&position(473_987_420)).unwrap();
+        assert_eq!(report.name(), "HOME_RANGE_EXCEEDED");
+        assert!(report.is_alert());
+        assert_eq!(home.observe(&position(473_987_520)), None);
+        assert_eq!(
+            home.observe(&position(473_977_420)).unwrap().name(),
+            "HOME_RANGE_OK"
+        );
+    }
+
+    #[test]
+    fn compass_points() {
+        assert_eq!(compass_point(0.0), "N");
+        assert_eq!(compass_point(350.0), "N");
+        assert_eq!(compass_point(44.0), "NE");
+        assert_eq!(compass_point(225.0), "SW");
+    }
+}
@@ -0,0 +1,390 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    collector::Collector,
+    entries::json_escape,
+    export::{self, Record},
+    message, metrics,
+};
+
+/// How long a client may take to send its request, and to read the answer.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long a request waits for the UI to answer before getting a 503.
+const ANSWER_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Bytes of request line and headers read before answering 400, so a
+/// client cannot make a request grow without end.
+const MAX_HEAD: u64 = 8 * 1024;
+
+/// Events of one type listed by `/messages/<type>`, newest kept.
+const MAX_EVENTS: usize = 100;
+
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+impl Response {
+    pub fn json(body: String) -> Self {
+        Self {
+            status: 200,
+            content_type: "application/json",
+            body,
+        }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body: format!("{{\"error\":\"{}\"}}", json_escape(message)),
+        }
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            503 => "Service Unavailable",
+            _ => "Bad Request",
+        }
+    }
+}
+
+/// A GET request waiting for the UI to answer it.
+pub struct Request {
+    pub path: String,
+    reply: Sender<Response>,
+}
+
+impl Request {
+    pub fn respond(self, response: Response) {
+        // The client may have given up waiting.
+        let _ = self.reply.send(response);
+    }
+}
+
+/// `--serve-http`: answers GET requests for capture statistics while the TUI
+/// runs. Connections are read on their own threads; the requests are handed
+/// to the UI, which answers them from the collector on its next tick.
+pub struct HttpServer {
+    addr: SocketAddr,
+    requests: Receiver<Request>,
+}
+
+impl HttpServer {
+    pub fn bind(listen: &str) -> io::Result<Self> {
+        let listener = TcpListener::bind(listen)?;
+        let addr = listener.local_addr()?;
+        let (tx, requests) = mpsc::channel();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        tracing::warn!(error = %e, "http accept failed");
+                        continue;
+                    }
+                };
+                let tx = tx.clone();
+                thread::spawn(move || {
+                    if let Err(e) = serve(stream, &tx) {
+                        tracing::debug!(error = %e, "http request failed");
+                    }
+                });
+            }
+        });
+        tracing::info!(%addr, "http server listening");
+        Ok(Self { addr, requests })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Requests waiting for an answer, without blocking.
+    pub fn pending(&self) -> impl Iterator<Item = Request> + '_ {
+        self.requests.try_iter()
+    }
+}
+
+/// Reads one request from `stream`, waits for its answer and writes it back.
+fn serve(stream: TcpStream, tx: &Sender<Request>) -> io::Result<()> {
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+    stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+    let mut reader = BufReader::new(&stream).take(MAX_HEAD);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 || line.trim_end().is_empty() {
+            break;
+        }
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let target = parts.next().unwrap_or_default();
+    let response = if reader.limit() == 0 {
+        Response::error(400, "request head too long")
+    } else if method != "GET" {
+        Response::error(405, "only GET is supported")
+    } else {
+        let path = target.split(['?', '#']).next().unwrap_or_default();
+        let (reply, answer) = mpsc::channel();
+        let request = Request {
+            path: path.to_string(),
+            reply,
+        };
+        if tx.send(request).is_err() {
+            Response::error(503, "shutting down")
+        } else {
+            answer
+                .recv_timeout(ANSWER_TIMEOUT)
+                .unwrap_or_else(|_| Response::error(503, "no answer from the capture"))
+        }
+    };
+
+    let mut stream = &stream;
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\n\
+         Content-Type: {}\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\r\n",
+        response.status,
+        response.reason(),
+        response.content_type,
+        response.body.len()
+    )?;
+    stream.write_all(response.body.as_bytes())?;
+    stream.flush()
+}
+
+/// Answers a GET of `path` from what `collector` holds at `now`.
+pub fn route(collector: &Collector, path: &str, now: DateTime<Utc>) -> Response {
+    let path = path.trim_end_matches('/');
+    match path {
+        "/stats" => Response::json(stats(collector, now)),
+        "/vehicles" => Response::json(vehicles(collector)),
+        "/metrics" => Response {
+            status: 200,
+            content_type: metrics::CONTENT_TYPE,
+            body: metrics::render(collector, now),
+        },
+        _ => match path.strip_prefix("/messages/") {
+            Some(name) if message::is_message_name(name) => {
+                Response::json(messages(collector, name))
+            }
+            Some(_) => Response::error(404, "unknown message type"),
+            None => Response::error(404, "not found"),
+        },
+    }
+}
+
+/// Totals since the last clear: messages by type, events, memory and link
+/// errors.
+fn stats(collector: &Collector, now: DateTime<Utc>) -> String {
+    let counts = collector.counts();
+    let types: Vec<String> = counts
+        .by_type()
+        .iter()
+        .map(|(name, count)| format!("\"{}\":{count}", json_escape(name)))
+        .collect();
+    let errors: Vec<String> = collector
+        .link_errors()
+        .iter()
+        .map(|(link, e)| {
+            format!(
+                "\"{link}\":{{\"crc\":{},\"length\":{},\"framing\":{},\"decode\":{}}}",
+                e.crc, e.length, e.framing, e.decode
+            )
+        })
+        .collect();
+    let usage = collector.usage();
+    format!(
+        "{{\"time\":\"{}\",\"messages\":{},\"stream_rows\":{},\"events\":{},\"alerts\":{},\
+         \"vehicles\":{},\"memory_bytes\":{},\"types\":{{{}}},\"link_errors\":{{{}}}}}",
+        export::time(now),
+        counts.total(),
+        collector.stream().len(),
+        collector.event_total(),
+        collector.alert_count(),
+        collector.vehicle_count(),
+        usage.bytes,
+        types.join(","),
+        errors.join(",")
+    )
+}
+
+/// Every system seen, with its components and their latest HEARTBEAT.
+fn vehicles(collector: &Collector) -> String {
+    let mut systems: BTreeMap<u8, BTreeMap<u8, Option<String>>> = BTreeMap::new();
+    for entry in collector.stream() {
+        let heartbeat = systems
+            .entry(entry.sys_id)
+            .or_default()
+            .entry(entry.comp_id)
+            .or_default();
+        if entry.name == "HEARTBEAT"
+            && let Some(record) = Record::stream(entry, None)
+        {
+            *heartbeat = Some(record.to_json());
+        }
+    }
+    let systems: Vec<String> = systems
+        .into_iter()
+        .map(|(sys_id, components)| {
+            let components: Vec<String> = components
+                .into_iter()
+                .map(|(comp_id, heartbeat)| {
+                    format!(
+                        "{{\"compid\":{comp_id},\"heartbeat\":{}}}",
+                        heartbeat.as_deref().unwrap_or("null")
+                    )
+                })
+                .collect();
+            format!(
+                "{{\"sysid\":{sys_id},\"components\":[{}]}}",
+                components.join(",")
+            )
+        })
+        .collect();
+    format!("{{\"vehicles\":[{}]}}", systems.join(","))
+}
+
+/// The stream rows of `name` with their rates, and its latest events.
+fn messages(collector: &Collector, name: &str) -> String {
+    let received = collector.counts().by_type().get(name).copied().unwrap_or(0);
+    let rows: Vec<String> = collector
+        .stream()
+        .iter()
+        .filter(|e| e.name == name)
+        .filter_map(|e| {
+            let record = Record::stream(e, None)?;
+            let hz = e
+                .arrivals
+                .hz()
+                .map_or("null".to_string(), |hz| format!("{hz:.2}"));
+            Some(format!("{{\"hz\":{hz},\"value\":{}}}", record.to_json()))
+        })
+        .collect();
+    let mut events: Vec<String> = collector
+        .messages()
+        .iter()
+        .rev()
+        .filter(|e| e.name == name)
+        .take(MAX_EVENTS)
+        .map(|e| Record::event(e).to_json())
+        .collect();
+    events.reverse();
+    format!(
+        "{{\"type\":\"{}\",\"received\":{received},\"rows\":[{}],\"events\":[{}]}}",
+        json_escape(name),
+        rows.join(","),
+        events.join(",")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::{
+        MavHeader,
+        common::{HEARTBEAT_DATA, MavMessage},
+    };
+
+    use super::*;
+    use crate::message::MavMsg;
+
+    fn collector() -> Collector {
+        let mut c = Collector::new();
+        c.push(MavMsg::new(
+            MavHeader {
+                system_id: 1,
+                component_id: 1,
+                sequence: 0,
+            },
+            MavMessage::HEARTBEAT(HEARTBEAT_DATA::default()),
+            0,
+        ));
+        c
+    }
+
+    #[test]
+    fn routes_requests() {
+        let c = collector();
+        let now = Utc::now();
+
+        let stats = route(&c, "/stats", now);
+        assert_eq!(stats.status, 200);
+        assert!(stats.body.contains("\"messages\":1"));
+        assert!(stats.body.contains("\"types\":{\"HEARTBEAT\":1}"));
+
+        let vehicles = route(&c, "/vehicles/", now);
+        assert!(vehicles.body.starts_with(
+            "{\"vehicles\":[{\"sysid\":1,\"components\":[{\"compid\":1,\"heartbeat\":{"
+        ));
+
+        let messages = route(&c, "/messages/HEARTBEAT", now);
+        assert!(messages.body.contains("\"received\":1"));
+        assert!(messages.body.contains("\"message\":\"HEARTBEAT\""));
+
+        assert_eq!(route(&c, "/messages/NOT_A_MESSAGE", now).status, 404);
+        let metrics = route(&c, "/metrics", now);
+        assert_eq!(metrics.content_type, metrics::CONTENT_TYPE);
+        assert_eq!(route(&c, "/", now).status, 404);
+    }
+
+    #[test]
+    fn answers_over_tcp() {
+        let server = HttpServer::bind("127.0.0.1:0").unwrap();
+        let mut client = TcpStream::connect(server.local_addr()).unwrap();
+        client
+            .write_all(b"GET /stats?pretty HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .unwrap();
+
+        let c = collector();
+        let request = loop {
+            if let Some(request) = server.pending().next() {
+                break request;
+            }
+            thread::sleep(Duration::from_millis(10));
+        };
+        assert_eq!(request.path, "/stats");
+        let response = route(&c, &request.path, Utc::now());
+        request.respond(response);
+
+        let mut text = String::new();
+        client.read_to_string(&mut text).unwrap();
+        assert!(text.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(text.contains("Content-Type: application/json\r\n"));
+        assert!(text.ends_with('}'));
+    }
+
+    #[test]
+    fn refuses_endless_request_lines() {
+        let server = HttpServer::bind("127.0.0.1:0").unwrap();
+        let mut client = TcpStream::connect(server.local_addr()).unwrap();
+        let mut request = b"GET /".to_vec();
+        request.resize(MAX_HEAD as usize, b'a');
+        client.write_all(&request).unwrap();
+
+        let mut text = String::new();
+        client.read_to_string(&mut text).unwrap();
+        assert!(text.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+        assert!(server.pending().next().is_none());
+    }
+}
@@ -0,0 +1,194 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use mavlink::common::MavMessage;
+
+use crate::message::MavMsg;
+
+/// IMUs compared: RAW_IMU or SCALED_IMU, SCALED_IMU2 and SCALED_IMU3.
+pub const IMUS: usize = 3;
+
+/// Acceleration, rotation rate and magnetic field, x/y/z each.
+pub const AXES: [&str; 9] = [
+    "acc x", "acc y", "acc z", "gyro x", "gyro y", "gyro z", "mag x", "mag y", "mag z",
+];
+
+/// How far an axis may be from the other IMUs' before it is highlighted:
+/// 150 mG, 50 mrad/s (about 3°/s) and 100 mgauss.
+const TOLERANCE: [f64; 3] = [150.0, 50.0, 100.0];
+
+/// An IMU that has not reported for this long is shown as stale.
+const STALE_AFTER: TimeDelta = TimeDelta::seconds(2);
+
+/// One IMU's latest reading, in the units of SCALED_IMU: mG, mrad/s and
+/// mgauss, in [`AXES`] order. RAW_IMU is taken to be in the same units,
+/// which holds for ArduPilot, the autopilot that sends it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImuReading {
+    pub at: DateTime<Utc>,
+    pub values: [f64; 9],
+}
+
+/// How one axis of one IMU compares with the others.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Agreement {
+    /// Within tolerance of the median of all IMUs, or the only one.
+    Agrees,
+    /// Further than tolerance from the median; with two IMUs both are.
+    Differs,
+}
+
+/// Latest reading of each IMU per vehicle, for the IMU comparison panel.
+#[derive(Default)]
+pub struct ImuTracker {
+    vehicles: BTreeMap<u8, [Option<ImuReading>; IMUS]>,
+}
+
+impl ImuTracker {
+    pub fn observe(&mut self, msg: &MavMsg) {
+        let (imu, acc, gyro, mag) = match &msg.msg {
+            MavMessage::RAW_IMU(d) => (
+                0,
+                [d.xacc, d.yacc, d.zacc],
+                [d.xgyro, d.ygyro, d.zgyro],
+                [d.xmag, d.ymag, d.zmag],
+            ),
+            MavMessage::SCALED_IMU(d) => (
+                0,
+                [d.xacc, d.yacc, d.zacc],
+                [d.xgyro, d.ygyro, d.zgyro],
+                [d.xmag, d.ymag, d.zmag],
+            ),
+            MavMessage::SCALED_IMU2(d) => (
+                1,
+                [d.xacc, d.yacc, d.zacc],
+                [d.xgyro, d.ygyro, d.zgyro],
+                [d.xmag, d.ymag, d.zmag],
+            ),
+            MavMessage::SCALED_IMU3(d) => (
+                2,
+                [d.xacc, d.yacc, d.zacc],
+                [d.xgyro, d.ygyro, d.zgyro],
+                [d.xmag, d.ymag, d.zmag],
+            ),
+            _ => return,
+        };
+        let mut values = [0.0; 9];
+        for (slot, value) in values
+            .iter_mut()
+            .zip(acc.into_iter().chain(gyro).chain(mag))
+        {
+            *slot = f64::from(value);
+        }
+        self.vehicles.entry(msg.header.system_id).or_default()[imu] = Some(ImuReading {
+            at: msg.timestamp,
+            values,
+        });
+    }
+
+    pub fn readings(&self, sys_id: u8) -> Option<&[Option<ImuReading>; IMUS]> {
+        self.vehicles.get(&sys_id)
+    }
+
+    pub fn clear(&mut self) {
+        self.vehicles.clear();
+    }
+}
+
+pub fn is_stale(reading: &ImuReading, now: DateTime<Utc>) -> bool {
+    now - reading.at > STALE_AFTER
+}
+
+/// Compares each axis of the IMUs that are reporting against their median.
+/// `None` for IMUs that are absent or stale.
+pub fn compare(
+    readings: &[Option<ImuReading>; IMUS],
+    now: DateTime<Utc>,
+) -> [[Option<Agreement>; IMUS]; 9] {
+    let live: Vec<(usize, &ImuReading)> = readings
+        .iter()
+        .enumerate()
+        .filter_map(|(i, r)| Some((i, r.as_ref().filter(|r| !is_stale(r, now))?)))
+        .collect();
+    let mut result = [[None; IMUS]; 9];
+    for (axis, row) in result.iter_mut().enumerate() {
+        let mut values: Vec<f64> = live.iter().map(|(_, r)| r.values[axis]).collect();
+        values.sort_by(f64::total_cmp);
+        let median = match values.len() {
+            0 => continue,
+            n if n % 2 == 0 => (values[n / 2 - 1] + values[n / 2]) / 2.0,
+            n => values[n / 2],
+        };
+        let tolerance = TOLERANCE[axis / 3];
+        for (imu, reading) in &live {
+            row[*imu] = Some(if (reading.values[axis] - median).abs() > tolerance {
+                Agreement::Differs
+            } else {
+                Agreement::Agrees
+            });
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::{
+        MavHeader,
+        common::{SCALED_IMU_DATA, SCALED_IMU2_DATA, SCALED_IMU3_DATA},
+    };
+
+    use super::*;
+
+    fn push(tracker: &mut ImuTracker, msg: MavMessage, at: DateTime<Utc>) {
+        let mut msg = MavMsg::new(MavHeader::default(), msg, 0);
+        msg.timestamp = at;
+        tracker.observe(&msg);
+    }
+
+    #[test]
+    fn flags_the_imu_that_drifts() {
+        let mut tracker = ImuTracker::default();
+        let now = Utc::now();
+        push(
+            &mut tracker,
+            MavMessage::SCALED_IMU(SCALED_IMU_DATA {
+                zacc: -1000,
+                ..Default::default()
+            }),
+            now,
+        );
+        push(
+            &mut tracker,
+            MavMessage::SCALED_IMU2(SCALED_IMU2_DATA {
+                zacc: -990,
+                ..Default::default()
+            }),
+            now,
+        );
+        push(
+            &mut tracker,
+            MavMessage::SCALED_IMU3(SCALED_IMU3_DATA {
+                zacc: -1400,
+                xgyro: 20,
+                ..Default::default()
+            }),
+            now,
+        );
+        let readings = tracker.readings(MavHeader::default().system_id).unwrap();
+        let compared = compare(readings, now);
+        let (differs, agrees) = (Some(Agreement::Differs), Some(Agreement::Agrees));
+        assert_eq!(compared[2], [agrees, agrees, differs]);
+        assert_eq!(compared[3], [agrees, agrees, agrees]);
+
+        // A silent IMU drops out of the comparison.
+        let later = now + TimeDelta::seconds(3);
+        push(
+            &mut tracker,
+            MavMessage::SCALED_IMU(SCALED_IMU_DATA::default()),
+            later,
+        );
+        let readings = tracker.readings(MavHeader::default().system_id).unwrap();
+        assert_eq!(compare(readings, later)[2], [agrees, None, None]);
+    }
+}
@@ -0,0 +1,291 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+use mavlink::{Message, common::MavMessage};
+
+use crate::{compress, frame};
+
+const MAGIC: &[u8; 8] = b"MAVSNIDX";
+const VERSION: u8 = 1;
+
+/// Microseconds between seek points.
+const SEEK_EVERY: i64 = 1_000_000;
+
+/// Sidecar index of a telemetry log: where each second of it starts and how
+/// many frames of each message id it holds, so a large log can be opened part
+/// way in or summarised without reading it all. Kept next to the log as
+/// `<log>.idx` (see [`sidecar`]).
+///
+/// Offsets are into the uncompressed log, so they also work for a
+/// zstd-compressed one, just not instantly.
+#[derive(Debug, Default, PartialEq)]
+pub struct Index {
+    /// Size of the log file when indexed; the index is rebuilt if it changes.
+    pub log_len: u64,
+    /// (receive time in µs, byte offset) of the first frame in each second,
+    /// in file order.
+    pub seek: Vec<(i64, u64)>,
+    /// Receive time of the last frame, in µs.
+    pub last: i64,
+    /// Frames per message id.
+    pub counts: BTreeMap<u32, u64>,
+}
+
+impl Index {
+    /// Scans a log as [`crate::tlog::read`] takes it, reading frame headers
+    /// only.
+    pub fn build(input: &mut impl Read) -> io::Result<Self> {
+        let mut index = Self::default();
+        let mut offset = 0u64;
+        let mut next_seek = i64::MIN;
+        loop {
+            let mut stamp = [0u8; 8];
+            match input.read_exact(&mut stamp) {
+                Ok(()) => {}
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+            let micros = i64::try_from(u64::from_be_bytes(stamp)).unwrap_or(i64::MAX);
+            let mut header = [0u8; 10];
+            input.read_exact(&mut header[..1])?;
+            let (header_len, msg_id) = match header[0] {
+                0xFE => {
+                    input.read_exact(&mut header[1..6])?;
+                    (6, u32::from(header[5]))
+                }
+                0xFD => {
+                    input.read_exact(&mut header[1..10])?;
+                    (10, u32::from_le_bytes([header[7], header[8], header[9], 0]))
+                }
+                _ => return Err(invalid("not a tlog (no frame after timestamp)")),
+            };
+            let len = match frame::frame_len(&header[..header_len]) {
+                Some(0) | None => return Err(invalid("bad frame header")),
+                Some(len) => len,
+            };
+            io::copy(
+                &mut input.by_ref().take((len - header_len) as u64),
+                &mut io::sink(),
+            )?;
+
+            if micros >= next_seek {
+                index.seek.push((micros, offset));
+                next_seek = micros - micros.rem_euclid(SEEK_EVERY) + SEEK_EVERY;
+            }
+            index.last = index.last.max(micros);
+            *index.counts.entry(msg_id).or_default() += 1;
+            offset += 8 + len as u64;
+        }
+        Ok(index)
+    }
+
+    /// Receive time of the first frame; `None` for an empty log.
+    pub fn first(&self) -> Option<DateTime<Utc>> {
+        self.seek
+            .first()
+            .and_then(|&(micros, _)| DateTime::from_timestamp_micros(micros))
+    }
+
+    pub fn last(&self) -> Option<DateTime<Utc>> {
+        self.first()?;
+        DateTime::from_timestamp_micros(self.last)
+    }
+
+    pub fn frames(&self) -> u64 {
+        self.counts.values().sum()
+    }
+
+    /// Where to start reading to get every frame from `at` on: the last seek
+    /// point at or before it.
+    pub fn offset_at(&self, at: DateTime<Utc>) -> u64 {
+        let micros = at.timestamp_micros();
+        let after = self.seek.partition_point(|&(t, _)| t <= micros);
+        after.checked_sub(1).map_or(0, |i| self.seek[i].1)
+    }
+
+    /// Frame counts by message name, most frequent first. Ids the `common`
+    /// dialect does not know are shown as `#<id>`.
+    pub fn type_counts(&self) -> Vec<(String, u64)> {
+        let mut counts: Vec<(String, u64)> = self
+            .counts
+            .iter()
+            .map(|(&id, &count)| {
+                let name = MavMessage::default_message_from_id(id)
+                    .map_or_else(|| format!("#{id}"), |m| m.message_name().to_string());
+                (name, count)
+            })
+            .collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        counts
+    }
+
+    pub fn write(&self, out: &mut impl Write) -> io::Result<()> {
+        out.write_all(MAGIC)?;
+        out.write_all(&[VERSION])?;
+        out.write_all(&self.log_len.to_le_bytes())?;
+        out.write_all(&self.last.to_le_bytes())?;
+        out.write_all(&(self.counts.len() as u32).to_le_bytes())?;
+        for (id, count) in &self.counts {
+            out.write_all(&id.to_le_bytes())?;
+            out.write_all(&count.to_le_bytes())?;
+        }
+        out.write_all(&(self.seek.len() as u32).to_le_bytes())?;
+        for (micros, offset) in &self.seek {
+            out.write_all(&micros.to_le_bytes())?;
+            out.write_all(&offset.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    pub fn read(input: &mut impl Read) -> io::Result<Self> {
+        if &read_array::<8>(input)? != MAGIC {
+            return Err(invalid("not a mavsnark index"));
+        }
+        let [version] = read_array(input)?;
+        if version != VERSION {
+            return Err(invalid(format!("unsupported index version {version}")));
+        }
+        let log_len = u64::from_le_bytes(read_array(input)?);
+        let last = i64::from_le_bytes(read_array(input)?);
+        let mut counts = BTreeMap::new();
+        for _ in 0..u32::from_le_bytes(read_array(input)?) {
+            let id = u32::from_le_bytes(read_array(input)?);
+            counts.insert(id, u64::from_le_bytes(read_array(input)?));
+        }
+        let mut seek = Vec::new();
+        for _ in 0..u32::from_le_bytes(read_array(input)?) {
+            let micros = i64::from_le_bytes(read_array(input)?);
+            seek.push((micros, u64::from_le_bytes(read_array(input)?)));
+        }
+        Ok(Self {
+            log_len,
+            seek,
+            last,
+            counts,
+        })
+    }
+}
+
+/// Where the index of `log` is kept: `<log>.idx`.
+pub fn sidecar(log: &Path) -> PathBuf {
+    let mut path = log.as_os_str().to_owned();
+    path.push(".idx");
+    PathBuf::from(path)
+}
+
+/// The index of `log`, from its sidecar if that is current, otherwise built
+/// and saved for next time. Not being able to save it is only logged.
+pub fn load_or_build(log: &Path) -> io::Result<Index> {
+    let log_len = fs::metadata(log)?.len();
+    let sidecar = sidecar(log);
+    if let Ok(file) = File::open(&sidecar) {
+        match Index::read(&mut BufReader::new(file)) {
+            Ok(index) if index.log_len == log_len => return Ok(index),
+            Ok(_) => tracing::info!(path = %sidecar.display(), "index out of date; rebuilding"),
+            Err(e) => tracing::warn!(path = %sidecar.display(), error = %e, "unreadable index"),
+        }
+    }
+    let mut index = Index::build(&mut compress::open(log)?)
+        .map_err(|e| io::Error::new(e.kind(), format!("{}: {e}", log.display())))?;
+    index.log_len = log_len;
+    let saved = File::create(&sidecar).and_then(|file| {
+        let mut out = BufWriter::new(file);
+        index.write(&mut out)?;
+        out.flush()
+    });
+    match saved {
+        Ok(()) => tracing::info!(path = %sidecar.display(), "index written"),
+        Err(e) => tracing::warn!(path = %sidecar.display(), error = %e, "could not write index"),
+    }
+    Ok(index)
+}
+
+fn read_array<const N: usize>(input: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut buf = [0u8; N];
+    input.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+fn invalid(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::{
+        MAVLinkV2MessageRaw, MavHeader,
+        common::{ATTITUDE_DATA, HEARTBEAT_DATA},
+    };
+
+    use super::*;
+
+    fn entry(out: &mut Vec<u8>, micros: u64, msg: &MavMessage) {
+        let mut raw = MAVLinkV2MessageRaw::new();
+        raw.serialize_message(MavHeader::default(), msg);
+        out.extend_from_slice(&micros.to_be_bytes());
+        out.extend_from_slice(raw.raw_bytes());
+    }
+
+    fn log() -> Vec<u8> {
+        let mut log = Vec::new();
+        let heartbeat = MavMessage::HEARTBEAT(HEARTBEAT_DATA::default());
+        let attitude = MavMessage::ATTITUDE(ATTITUDE_DATA::default());
+        for tenth in 0..30u64 {
+            entry(&mut log, 5_000_000 + tenth * 100_000, &attitude);
+            if tenth % 10 == 0 {
+                entry(&mut log, 5_000_000 + tenth * 100_000, &heartbeat);
+            }
+        }
+        log
+    }
+
+    #[test]
+    fn indexes_seconds_and_types() {
+        let log = log();
+        let index = Index::build(&mut log.as_slice()).unwrap();
+        assert_eq!(index.frames(), 33);
+        assert_eq!(
+            index.type_counts(),
+            vec![("ATTITUDE".to_string(), 30), ("HEARTBEAT".to_string(), 3)]
+        );
+        let starts: Vec<i64> = index.seek.iter().map(|&(t, _)| t).collect();
+        assert_eq!(starts, vec![5_000_000, 6_000_000, 7_000_000]);
+        assert_eq!(index.last().unwrap().timestamp_micros(), 7_900_000);
+
+        // Reading from a seek point gives whole frames from there on.
+        let at = DateTime::from_timestamp_micros(6_500_000).unwrap();
+        let offset = index.offset_at(at) as usize;
+        assert_eq!(offset, index.seek[1].1 as usize);
+        let rest = crate::tlog::read(&mut &log[offset..], 0).unwrap();
+        assert_eq!(rest.len(), 22);
+        assert_eq!(index.offset_at(DateTime::UNIX_EPOCH), 0);
+    }
+
+    #[test]
+    fn sidecar_is_reused_until_the_log_changes() {
+        let dir = std::env::temp_dir().join(format!("mavsnark-index-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("flight.tlog");
+        fs::write(&path, log()).unwrap();
+
+        let built = load_or_build(&path).unwrap();
+        assert!(sidecar(&path).exists());
+        assert_eq!(load_or_build(&path).unwrap(), built);
+
+        let mut longer = log();
+        entry(
+            &mut longer,
+            9_000_000,
+            &MavMessage::HEARTBEAT(HEARTBEAT_DATA::default()),
+        );
+        fs::write(&path, longer).unwrap();
+        let rebuilt = load_or_build(&path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(rebuilt.frames(), 34);
+    }
+}
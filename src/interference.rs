@@ -0,0 +1,293 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use mavlink::common::MavMessage;
+
+use crate::{message::MavMsg, radio::HISTORY};
+
+/// A current or throttle reading older than this is not paired with the
+/// compass.
+const MAX_AGE: TimeDelta = TimeDelta::seconds(2);
+
+/// Paired samples needed before an estimate is made.
+const MIN_SAMPLES: usize = 20;
+
+/// The spread in current (A) or throttle (%) needed for a meaningful fit.
+const MIN_SPREAD: [f64; 2] = [2.0, 20.0];
+
+/// Interference below this share of the field is fine, above the second
+/// it is not; in between is marginal. ArduPilot's compassmot guidance.
+pub const RATING: (f64, f64) = (30.0, 60.0);
+
+/// What the field is fitted against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Basis {
+    /// Battery current in A, from BATTERY_STATUS or SYS_STATUS.
+    Current,
+    /// VFR_HUD throttle in %, when no current is reported.
+    Throttle,
+}
+
+impl Basis {
+    pub fn unit(self) -> &'static str {
+        match self {
+            Basis::Current => "A",
+            Basis::Throttle => "%",
+        }
+    }
+}
+
+/// The compass field strength with the current and throttle at the time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MagSample {
+    pub at: DateTime<Utc>,
+    /// Field magnitude in mgauss, from SCALED_IMU.
+    pub field: f64,
+    pub current: Option<f64>,
+    pub throttle: Option<f64>,
+}
+
+impl MagSample {
+    pub fn load(&self, basis: Basis) -> Option<f64> {
+        match basis {
+            Basis::Current => self.current,
+            Basis::Throttle => self.throttle,
+        }
+    }
+}
+
+/// A straight-line fit of field strength against load.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Estimate {
+    pub basis: Basis,
+    /// mgauss per A or per % throttle.
+    pub slope: f64,
+    /// Field at no load, mgauss.
+    pub baseline: f64,
+    /// Pearson correlation of field and load.
+    pub correlation: f64,
+    /// Highest load seen.
+    pub max_load: f64,
+    /// Field change at the highest load as a share of the baseline, %.
+    pub interference: f64,
+    pub samples: usize,
+}
+
+#[derive(Debug, Default)]
+pub struct Vehicle {
+    /// Over the last [`HISTORY`], oldest first.
+    pub samples: VecDeque<MagSample>,
+    /// BATTERY_STATUS current wins over SYS_STATUS's once seen.
+    battery_status: bool,
+    current: Option<(DateTime<Utc>, f64)>,
+    throttle: Option<(DateTime<Utc>, f64)>,
+}
+
+impl Vehicle {
+    /// Fitted against current when the vehicle reports it, otherwise
+    /// against throttle. `None` until there are enough samples spread over
+    /// enough of the load range.
+    pub fn estimate(&self) -> Option<Estimate> {
+        let basis = if self.samples.iter().any(|s| s.current.is_some()) {
+            Basis::Current
+        } else {
+            Basis::Throttle
+        };
+        let points: Vec<(f64, f64)> = self
+            .samples
+            .iter()
+            .filter_map(|s| Some((s.load(basis)?, s.field)))
+            .collect();
+        if points.len() < MIN_SAMPLES {
+            return None;
+        }
+        let n = points.len() as f64;
+        let (mean_x, mean_y) = points
+            .iter()
+            .fold((0.0, 0.0), |(x, y), p| (x + p.0 / n, y + p.1 / n));
+        let (mut sxx, mut syy, mut sxy) = (0.0, 0.0, 0.0);
+        let (mut min_x, mut max_x) = (f64::MAX, f64::MIN);
+        for &(x, y) in &points {
+            sxx += (x - mean_x).powi(2);
+            syy += (y - mean_y).powi(2);
+            sxy += (x - mean_x) * (y - mean_y);
+            min_x = min_x.min(x);
+            max_x = max_x.max(x);
+        }
+        if max_x - min_x < MIN_SPREAD[basis as usize] {
+            return None;
+        }
+        let slope = sxy / sxx;
+        let baseline = mean_y - slope * mean_x;
+        let correlation = if syy > 0.0 {
+            sxy / (sxx * syy).sqrt()
+        } else {
+            0.0
+        };
+        let interference = if baseline > 0.0 {
+            (slope * max_x).abs() / baseline * 100.0
+        } else {
+            0.0
+        };
+        Some(Estimate {
+            basis,
+            slope,
+            baseline,
+            correlation,
+            max_load: max_x,
+            interference,
+            samples: points.len(),
+        })
+    }
+}
+
+/// Pairs the first compass with battery current and throttle per vehicle,
+/// the terminal version of a compass/motor interference test.
+#[derive(Default)]
+pub struct InterferenceTracker {
+    vehicles: BTreeMap<u8, Vehicle>,
+}
+
+impl InterferenceTracker {
+    pub fn observe(&mut self, msg: &MavMsg) {
+        let vehicle = match &msg.msg {
+            MavMessage::SCALED_IMU(_)
+            | MavMessage::SYS_STATUS(_)
+            | MavMessage::BATTERY_STATUS(_)
+            | MavMessage::VFR_HUD(_) => self.vehicles.entry(msg.header.system_id).or_default(),
+            _ => return,
+        };
+        let at = msg.timestamp;
+        match &msg.msg {
+            // Centiamps, -1 when not measured.
+            MavMessage::SYS_STATUS(d) if !vehicle.battery_status && d.current_battery >= 0 => {
+                vehicle.current = Some((at, f64::from(d.current_battery) / 100.0));
+            }
+            MavMessage::BATTERY_STATUS(d) if d.id == 0 && d.current_battery >= 0 => {
+                vehicle.battery_status = true;
+                vehicle.current = Some((at, f64::from(d.current_battery) / 100.0));
+            }
+            MavMessage::VFR_HUD(d) => vehicle.throttle = Some((at, f64::from(d.throttle))),
+            MavMessage::SCALED_IMU(d) => {
+                let field = [d.xmag, d.ymag, d.zmag]
+                    .iter()
+                    .map(|&v| f64::from(v).powi(2))
+                    .sum::<f64>()
+                    .sqrt();
+                if field == 0.0 {
+                    // No compass on this IMU.
+                    return;
+                }
+                let fresh = |reading: Option<(DateTime<Utc>, f64)>| {
+                    reading.filter(|(t, _)| at - *t <= MAX_AGE).map(|(_, v)| v)
+                };
+                vehicle.samples.push_back(MagSample {
+                    at,
+                    field,
+                    current: fresh(vehicle.current),
+                    throttle: fresh(vehicle.throttle),
+                });
+                while vehicle.samples.front().is_some_and(|s| at - s.at > HISTORY) {
+                    vehicle.samples.pop_front();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn vehicle(&self, sys_id: u8) -> Option<&Vehicle> {
+        self.vehicles.get(&sys_id)
+    }
+
+    pub fn clear(&mut self) {
+        self.vehicles.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::{
+        MavHeader,
+        common::{SCALED_IMU_DATA, SYS_STATUS_DATA, VFR_HUD_DATA},
+    };
+
+    use super::*;
+
+    fn push(tracker: &mut InterferenceTracker, msg: MavMessage, at: DateTime<Utc>) {
+        let mut msg = MavMsg::new(MavHeader::default(), msg, 0);
+        msg.timestamp = at;
+        tracker.observe(&msg);
+    }
+
+    fn compass(x: i16) -> MavMessage {
+        MavMessage::SCALED_IMU(SCALED_IMU_DATA {
+            xmag: x,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn fits_field_against_current() {
+        let mut tracker = InterferenceTracker::default();
+        let t0 = Utc::now();
+        // 500 mgauss at rest, 5 mgauss more per A up to 30 A.
+        for i in 0..31 {
+            let at = t0 + TimeDelta::milliseconds(i * 200);
+            let status = SYS_STATUS_DATA {
+                current_battery: (i * 100) as i16,
+                ..Default::default()
+            };
+            push(&mut tracker, MavMessage::SYS_STATUS(status), at);
+            push(&mut tracker, compass((500 + 5 * i) as i16), at);
+        }
+        let estimate = tracker
+            .vehicle(MavHeader::default().system_id)
+            .unwrap()
+            .estimate()
+            .unwrap();
+        assert_eq!(estimate.basis, Basis::Current);
+        assert!((estimate.slope - 5.0).abs() < 1e-9);
+        assert!((estimate.baseline - 500.0).abs() < 1e-6);
+        assert!((estimate.correlation - 1.0).abs() < 1e-9);
+        assert!((estimate.interference - 30.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn falls_back_to_throttle_and_needs_spread() {
+        let mut tracker = InterferenceTracker::default();
+        let t0 = Utc::now();
+        let hud = |throttle| {
+            MavMessage::VFR_HUD(VFR_HUD_DATA {
+                throttle,
+                ..Default::default()
+            })
+        };
+        for i in 0..25 {
+            let at = t0 + TimeDelta::milliseconds(i * 200);
+            push(&mut tracker, hud(50), at);
+            push(&mut tracker, compass(400), at);
+        }
+        // Hovering at one throttle says nothing about interference.
+        assert_eq!(
+            tracker
+                .vehicle(MavHeader::default().system_id)
+                .unwrap()
+                .estimate(),
+            None
+        );
+
+        for i in 25..50 {
+            let at = t0 + TimeDelta::milliseconds(i * 200);
+            push(&mut tracker, hud(80), at);
+            push(&mut tracker, compass(460), at);
+        }
+        let estimate = tracker
+            .vehicle(MavHeader::default().system_id)
+            .unwrap()
+            .estimate()
+            .unwrap();
+        assert_eq!(estimate.basis, Basis::Throttle);
+        assert!((estimate.slope - 2.0).abs() < 1e-9);
+        assert!((estimate.baseline - 300.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,295 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use mavlink::{
+    Message,
+    common::{MavCmd, MavMessage},
+};
+
+use crate::{entries::StreamEntry, message::MavMsg};
+
+/// How long a MESSAGE_INTERVAL answer may take before the query is given
+/// up on.
+const ANSWER_TIMEOUT: TimeDelta = TimeDelta::seconds(3);
+
+/// Allowed relative difference between the configured and observed rate.
+const TOLERANCE: f64 = 0.25;
+
+/// Below this a stream counts as not arriving.
+const MIN_HZ: f64 = 0.05;
+
+/// What MESSAGE_INTERVAL says about a stream.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Interval {
+    /// `interval_us` -1: the stream is turned off.
+    Disabled,
+    /// `interval_us` 0: the autopilot cannot send it.
+    Unavailable,
+    /// Microseconds between messages.
+    Every(i32),
+}
+
+impl Interval {
+    fn from_us(interval_us: i32) -> Self {
+        match interval_us {
+            0 => Interval::Unavailable,
+            us if us < 0 => Interval::Disabled,
+            us => Interval::Every(us),
+        }
+    }
+
+    pub fn hz(self) -> Option<f64> {
+        match self {
+            Interval::Every(us) => Some(1e6 / f64::from(us)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// Asked and waiting for the answer.
+    Pending,
+    /// Asked, and no MESSAGE_INTERVAL came back in time.
+    Unanswered,
+    /// Arriving at the configured rate.
+    Ok,
+    /// Configured, but arriving faster or slower, or arriving while
+    /// disabled.
+    Mismatch,
+    /// Configured with a rate but not arriving.
+    Missing,
+    Disabled,
+    Unavailable,
+}
+
+impl Status {
+    pub fn label(self) -> &'static str {
+        match self {
+            Status::Pending => "asking",
+            Status::Unanswered => "no answer",
+            Status::Ok => "ok",
+            Status::Mismatch => "mismatch",
+            Status::Missing => "missing",
+            Status::Disabled => "disabled",
+            Status::Unavailable => "unavailable",
+        }
+    }
+
+    /// Needs a look: the stream is not doing what the autopilot says.
+    pub fn is_problem(self) -> bool {
+        matches!(
+            self,
+            Status::Mismatch | Status::Missing | Status::Unanswered
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+struct Query {
+    asked: Option<DateTime<Utc>>,
+    answer: Option<(DateTime<Utc>, Interval)>,
+}
+
+/// One message of one component, configured against observed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditRow {
+    pub sys_id: u8,
+    pub comp_id: u8,
+    pub name: &'static str,
+    pub configured: Option<Interval>,
+    /// Current rate of the stream row, Hz.
+    pub observed: Option<f64>,
+    pub status: Status,
+}
+
+/// MESSAGE_INTERVAL answers and the MAV_CMD_GET_MESSAGE_INTERVAL queries
+/// behind them, from any GCS on the link or from mavsnark itself.
+#[derive(Default)]
+pub struct IntervalTracker {
+    queries: BTreeMap<(u8, u8, &'static str), Query>,
+}
+
+impl IntervalTracker {
+    pub fn observe(&mut self, msg: &MavMsg) {
+        match &msg.msg {
+            MavMessage::COMMAND_LONG(d) if d.command == MavCmd::MAV_CMD_GET_MESSAGE_INTERVAL => {
+                if let Some(name) = message_name(d.param1 as u32) {
+                    self.asked(d.target_system, d.target_component, name, msg.timestamp);
+                }
+            }
+            MavMessage::MESSAGE_INTERVAL(d) => {
+                let Some(name) = message_name(u32::from(d.message_id)) else {
+                    return;
+                };
+                let key = (msg.header.system_id, msg.header.component_id, name);
+                self.queries.entry(key).or_default().answer =
+                    Some((msg.timestamp, Interval::from_us(d.interval_us)));
+            }
+            _ => {}
+        }
+    }
+
+    /// Records a query sent for `name` to `sys_id`/`comp_id`.
+    pub fn asked(&mut self, sys_id: u8, comp_id: u8, name: &'static str, at: DateTime<Utc>) {
+        self.queries
+            .entry((sys_id, comp_id, name))
+            .or_default()
+            .asked = Some(at);
+    }
+
+    /// Every queried or answered stream, with the rate its stream row is
+    /// arriving at.
+    pub fn audit(&self, stream: &[StreamEntry], now: DateTime<Utc>) -> Vec<AuditRow> {
+        self.queries
+            .iter()
+            .map(|(&(sys_id, comp_id, name), query)| {
+                let observed = stream
+                    .iter()
+                    .find(|e| (e.sys_id, e.comp_id, e.name) == (sys_id, comp_id, name))
+                    .and_then(|e| e.arrivals.recent_hz(now));
+                let configured = query.answer.map(|(_, interval)| interval);
+                AuditRow {
+                    sys_id,
+                    comp_id,
+                    name,
+                    configured,
+                    observed,
+                    status: status(query, observed, now),
+                }
+            })
+            .collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.queries.clear();
+    }
+}
+
+fn status(query: &Query, observed: Option<f64>, now: DateTime<Utc>) -> Status {
+    let arriving = observed.is_some_and(|hz| hz >= MIN_HZ);
+    // A query newer than the answer is still out.
+    let waiting = match (query.asked, query.answer) {
+        (Some(asked), Some((answered, _))) => asked > answered,
+        (Some(_), None) => true,
+        (None, _) => false,
+    };
+    match query.answer {
+        Some((_, interval)) if !waiting => judge(interval, observed, arriving),
+        _ if query.asked.is_some_and(|at| now - at <= ANSWER_TIMEOUT) => Status::Pending,
+        // A repeated query going unanswered leaves the last answer standing.
+        Some((_, interval)) => judge(interval, observed, arriving),
+        None => Status::Unanswered,
+    }
+}
+
+fn judge(interval: Interval, observed: Option<f64>, arriving: bool) -> Status {
+    match (interval, interval.hz(), observed) {
+        (Interval::Disabled, ..) if arriving => Status::Mismatch,
+        (Interval::Disabled, ..) => Status::Disabled,
+        (Interval::Unavailable, ..) => Status::Unavailable,
+        (_, Some(_), _) if !arriving => Status::Missing,
+        (_, Some(expected), Some(hz)) if ((hz - expected) / expected).abs() > TOLERANCE => {
+            Status::Mismatch
+        }
+        _ => Status::Ok,
+    }
+}
+
+fn message_name(id: u32) -> Option<&'static str> {
+    MavMessage::default_message_from_id(id).map(|m| m.message_name())
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::{
+        MavHeader,
+        common::{COMMAND_LONG_DATA, MESSAGE_INTERVAL_DATA},
+    };
+
+    use super::*;
+
+    fn msg(system_id: u8, msg: MavMessage, at: DateTime<Utc>) -> MavMsg {
+        let header = MavHeader {
+            system_id,
+            component_id: 1,
+            sequence: 0,
+        };
+        let mut msg = MavMsg::new(header, msg, 0);
+        msg.timestamp = at;
+        msg
+    }
+
+    fn answer(message_id: u16, interval_us: i32, at: DateTime<Utc>) -> MavMsg {
+        msg(
+            1,
+            MavMessage::MESSAGE_INTERVAL(MESSAGE_INTERVAL_DATA {
+                message_id,
+                interval_us,
+            }),
+            at,
+        )
+    }
+
+    fn query(param1: f32, at: DateTime<Utc>) -> MavMsg {
+        msg(
+            255,
+            MavMessage::COMMAND_LONG(COMMAND_LONG_DATA {
+                command: MavCmd::MAV_CMD_GET_MESSAGE_INTERVAL,
+                target_system: 1,
+                target_component: 1,
+                param1,
+                ..Default::default()
+            }),
+            at,
+        )
+    }
+
+    fn statuses(tracker: &IntervalTracker, now: DateTime<Utc>) -> Vec<(&'static str, Status)> {
+        tracker
+            .audit(&[], now)
+            .into_iter()
+            .map(|r| (r.name, r.status))
+            .collect()
+    }
+
+    #[test]
+    fn queries_wait_for_an_answer() {
+        let mut tracker = IntervalTracker::default();
+        let t0 = Utc::now();
+        // ATTITUDE is 30, GPS_RAW_INT 24.
+        tracker.observe(&query(30.0, t0));
+        tracker.observe(&query(24.0, t0));
+        assert_eq!(
+            statuses(&tracker, t0),
+            [
+                ("ATTITUDE", Status::Pending),
+                ("GPS_RAW_INT", Status::Pending)
+            ]
+        );
+        tracker.observe(&answer(30, -1, t0 + TimeDelta::milliseconds(100)));
+        assert_eq!(
+            statuses(&tracker, t0 + TimeDelta::seconds(4)),
+            [
+                ("ATTITUDE", Status::Disabled),
+                ("GPS_RAW_INT", Status::Unanswered)
+            ]
+        );
+    }
+
+    #[test]
+    fn judges_observed_against_configured() {
+        let (ok, slow, off) = (Some(9.0), Some(4.0), Some(0.01));
+        let every = Interval::from_us(100_000);
+        assert_eq!(every.hz(), Some(10.0));
+        assert_eq!(judge(every, ok, true), Status::Ok);
+        assert_eq!(judge(every, slow, true), Status::Mismatch);
+        assert_eq!(judge(every, off, false), Status::Missing);
+        assert_eq!(judge(every, None, false), Status::Missing);
+        assert_eq!(judge(Interval::from_us(-1), ok, true), Status::Mismatch);
+        assert_eq!(
+            judge(Interval::from_us(0), None, false),
+            Status::Unavailable
+        );
+    }
+}
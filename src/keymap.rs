@@ -0,0 +1,420 @@
+use std::fmt;
+
+use crossterm::event::{KeyCode, KeyModifiers};
+
+/// Something a key does in the main view, named as in the `[keys]` config
+/// table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    NextTab,
+    PrevTab,
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Top,
+    Bottom,
+    FocusDetail,
+    JumpToName,
+    SearchHistory,
+    OpenDocs,
+    MoveCategory,
+    Direction,
+    Severity,
+    Filter,
+    Pause,
+    Params,
+    StatusText,
+    Plot,
+    ClosePlot,
+    ExportRow,
+    ExportEvents,
+    Find,
+    FindNext,
+    FindPrev,
+    OnlySystem,
+    OnlyMessage,
+    QueryIntervals,
+    WrapMode,
+    HideDefaults,
+    SaveSession,
+    SaveSanitized,
+    Snapshot,
+    SavePcapng,
+    ByteMap,
+    RawFrame,
+    Intervals,
+    Back,
+    Forward,
+    BackFar,
+    ForwardFar,
+    Faster,
+    Slower,
+    Performance,
+    Clear,
+}
+
+impl Action {
+    pub const ALL: [Action; 47] = [
+        Action::Quit,
+        Action::NextTab,
+        Action::PrevTab,
+        Action::Up,
+        Action::Down,
+        Action::PageUp,
+        Action::PageDown,
+        Action::Top,
+        Action::Bottom,
+        Action::FocusDetail,
+        Action::JumpToName,
+        Action::SearchHistory,
+        Action::OpenDocs,
+        Action::MoveCategory,
+        Action::Direction,
+        Action::Severity,
+        Action::Filter,
+        Action::Pause,
+        Action::Params,
+        Action::StatusText,
+        Action::Plot,
+        Action::ClosePlot,
+        Action::ExportRow,
+        Action::ExportEvents,
+        Action::Find,
+        Action::FindNext,
+        Action::FindPrev,
+        Action::OnlySystem,
+        Action::OnlyMessage,
+        Action::QueryIntervals,
+        Action::WrapMode,
+        Action::HideDefaults,
+        Action::SaveSession,
+        Action::SaveSanitized,
+        Action::Snapshot,
+        Action::SavePcapng,
+        Action::ByteMap,
+        Action::RawFrame,
+        Action::Intervals,
+        Action::Back,
+        Action::Forward,
+        Action::BackFar,
+        Action::ForwardFar,
+        Action::Faster,
+        Action::Slower,
+        Action::Performance,
+        Action::Clear,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::NextTab => "next_tab",
+            Action::PrevTab => "prev_tab",
+            Action::Up => "up",
+            Action::Down => "down",
+            Action::PageUp => "page_up",
+            Action::PageDown => "page_down",
+            Action::Top => "top",
+            Action::Bottom => "bottom",
+            Action::FocusDetail => "focus_detail",
+            Action::JumpToName => "jump_to_name",
+            Action::SearchHistory => "search_history",
+            Action::OpenDocs => "open_docs",
+            Action::MoveCategory => "move_category",
+            Action::Direction => "direction",
+            Action::Severity => "severity",
+            Action::Filter => "filter",
+            Action::Pause => "pause",
+            Action::Params => "params",
+            Action::StatusText => "status_text",
+            Action::Plot => "plot",
+            Action::ClosePlot => "close_plot",
+            Action::ExportRow => "export_row",
+            Action::ExportEvents => "export_events",
+            Action::Find => "find",
+            Action::FindNext => "find_next",
+            Action::FindPrev => "find_prev",
+            Action::OnlySystem => "only_system",
+            Action::OnlyMessage => "only_message",
+            Action::QueryIntervals => "query_intervals",
+            Action::WrapMode => "wrap_mode",
+            Action::HideDefaults => "hide_defaults",
+            Action::SaveSession => "save_session",
+            Action::SaveSanitized => "save_sanitized",
+            Action::Snapshot => "snapshot",
+            Action::SavePcapng => "save_pcapng",
+            Action::ByteMap => "byte_map",
+            Action::RawFrame => "raw_frame",
+            Action::Intervals => "intervals",
+            Action::Back => "back",
+            Action::Forward => "forward",
+            Action::BackFar => "back_far",
+            Action::ForwardFar => "forward_far",
+            Action::Faster => "faster",
+            Action::Slower => "slower",
+            Action::Performance => "performance",
+            Action::Clear => "clear",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|a| a.name() == name)
+    }
+
+    /// The keys bound when the config leaves the action alone.
+    fn default_keys(self) -> &'static [Key] {
+        match self {
+            Action::Quit => &[key('q'), code(KeyCode::Esc)],
+            Action::NextTab => &[code(KeyCode::Tab), code(KeyCode::Right), key('l')],
+            Action::PrevTab => &[code(KeyCode::BackTab), code(KeyCode::Left), key('h')],
+            Action::Up => &[code(KeyCode::Up), key('k')],
+            Action::Down => &[code(KeyCode::Down), key('j')],
+            Action::PageUp => &[code(KeyCode::PageUp)],
+            Action::PageDown => &[code(KeyCode::PageDown)],
+            Action::Top => &[key('g')],
+            Action::Bottom => &[key('G')],
+            Action::FocusDetail => &[code(KeyCode::Enter)],
+            Action::JumpToName => &[key('\'')],
+            Action::SearchHistory => &[ctrl('f')],
+            Action::OpenDocs => &[ctrl('o')],
+            Action::MoveCategory => &[ctrl('t')],
+            Action::Direction => &[ctrl('d')],
+            Action::Severity => &[key('s')],
+            Action::Filter => &[key('f')],
+            Action::Pause => &[key(' ')],
+            Action::Params => &[key('p')],
+            Action::StatusText => &[key('c')],
+            Action::Plot => &[key('w')],
+            Action::ClosePlot => &[key('W')],
+            Action::ExportRow => &[key('e')],
+            Action::ExportEvents => &[key('E')],
+            Action::Find => &[key('/')],
+            Action::FindNext => &[key('n')],
+            Action::FindPrev => &[key('N')],
+            Action::OnlySystem => &[key('v')],
+            Action::OnlyMessage => &[key('m')],
+            Action::QueryIntervals => &[ctrl('g')],
+            Action::WrapMode => &[ctrl('w')],
+            Action::HideDefaults => &[ctrl('e')],
+            Action::SaveSession => &[ctrl('s')],
+            Action::SaveSanitized => &[ctrl('p')],
+            Action::Snapshot => &[ctrl('n')],
+            Action::SavePcapng => &[ctrl('k')],
+            Action::ByteMap => &[ctrl('x')],
+            Action::RawFrame => &[key('x')],
+            Action::Intervals => &[key('i')],
+            Action::Back => &[key('[')],
+            Action::Forward => &[key(']')],
+            Action::BackFar => &[key('{')],
+            Action::ForwardFar => &[key('}')],
+            Action::Faster => &[key('+'), key('=')],
+            Action::Slower => &[key('-')],
+            Action::Performance => &[code(KeyCode::F(12))],
+            Action::Clear => &[ctrl('r')],
+        }
+    }
+}
+
+/// A key press, with or without Ctrl. Shift is part of the character.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Key {
+    pub code: KeyCode,
+    pub ctrl: bool,
+}
+
+const fn key(c: char) -> Key {
+    code(KeyCode::Char(c))
+}
+
+const fn ctrl(c: char) -> Key {
+    Key {
+        code: KeyCode::Char(c),
+        ctrl: true,
+    }
+}
+
+const fn code(code: KeyCode) -> Key {
+    Key { code, ctrl: false }
+}
+
+impl Key {
+    /// Parses a key as written in the config: a character (`g`, `G`, `/`),
+    /// a name (`up`, `pgdn`, `enter`, `space`, `f12`), either prefixed with
+    /// `ctrl+`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let (ctrl, rest) = match text.get(..5) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("ctrl+") && text.len() > 5 => {
+                (true, &text[5..])
+            }
+            _ => (false, text),
+        };
+        let mut chars = rest.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            return Ok(Key {
+                code: KeyCode::Char(c),
+                ctrl,
+            });
+        }
+        let code = match rest.to_ascii_lowercase().as_str() {
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "pageup" | "pgup" => KeyCode::PageUp,
+            "pagedown" | "pgdn" => KeyCode::PageDown,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "enter" => KeyCode::Enter,
+            "esc" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "backtab" | "shift+tab" => KeyCode::BackTab,
+            "space" => KeyCode::Char(' '),
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            name => match name.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                Some(n @ 1..=12) => KeyCode::F(n),
+                _ => return Err(format!("unknown key {text:?}")),
+            },
+        };
+        Ok(Key { code, ctrl })
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.ctrl {
+            f.write_str("Ctrl+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("Space"),
+            KeyCode::Char(c) => write!(f, "{c}"),
+            KeyCode::Up => f.write_str("\u{2191}"),
+            KeyCode::Down => f.write_str("\u{2193}"),
+            KeyCode::Left => f.write_str("\u{2190}"),
+            KeyCode::Right => f.write_str("\u{2192}"),
+            KeyCode::PageUp => f.write_str("PgUp"),
+            KeyCode::PageDown => f.write_str("PgDn"),
+            KeyCode::BackTab => f.write_str("Shift+Tab"),
+            KeyCode::F(n) => write!(f, "F{n}"),
+            KeyCode::Delete => f.write_str("Del"),
+            code => write!(f, "{code:?}"),
+        }
+    }
+}
+
+/// Which keys trigger which [`Action`]: the defaults, with the `[keys]`
+/// config table applied over them.
+#[derive(Debug, Clone)]
+pub struct KeyMap {
+    bindings: Vec<(Action, Vec<Key>)>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        Self {
+            bindings: Action::ALL
+                .into_iter()
+                .map(|a| (a, a.default_keys().to_vec()))
+                .collect(),
+        }
+    }
+}
+
+impl KeyMap {
+    /// The action bound to a key press, if any.
+    pub fn action(&self, code: KeyCode, modifiers: KeyModifiers) -> Option<Action> {
+        let pressed = Key {
+            code,
+            ctrl: modifiers.contains(KeyModifiers::CONTROL),
+        };
+        self.bindings
+            .iter()
+            .find(|(_, keys)| keys.contains(&pressed))
+            .map(|&(action, _)| action)
+    }
+
+    pub fn keys(&self, action: Action) -> &[Key] {
+        self.bindings
+            .iter()
+            .find(|(a, _)| *a == action)
+            .map(|(_, keys)| keys.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Binds `action` to `keys` alone, taking them from any other action
+    /// they were bound to by default.
+    pub fn rebind(&mut self, action: Action, keys: Vec<Key>) {
+        for (other, bound) in &mut self.bindings {
+            if *other == action {
+                bound.clone_from(&keys);
+            } else {
+                bound.retain(|k| !keys.contains(k));
+            }
+        }
+    }
+
+    /// The keys of `actions` as the footer shows them, e.g. `g/G`.
+    pub fn label(&self, actions: &[Action]) -> String {
+        actions
+            .iter()
+            .flat_map(|&a| self.keys(a))
+            .map(Key::to_string)
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_shows_keys() {
+        assert_eq!(Key::parse("G").unwrap(), key('G'));
+        assert_eq!(Key::parse("Ctrl+o").unwrap(), ctrl('o'));
+        assert_eq!(Key::parse("pgdn").unwrap(), code(KeyCode::PageDown));
+        assert_eq!(Key::parse("f12").unwrap(), code(KeyCode::F(12)));
+        assert_eq!(Key::parse("space").unwrap().to_string(), "Space");
+        assert_eq!(Key::parse("ctrl+x").unwrap().to_string(), "Ctrl+x");
+        assert_eq!(Key::parse("shift+tab").unwrap().to_string(), "Shift+Tab");
+        assert!(Key::parse("hyper").is_err());
+        assert!(Key::parse("f13").is_err());
+    }
+
+    #[test]
+    fn every_action_has_a_distinct_name_and_key() {
+        let map = KeyMap::default();
+        for a in Action::ALL {
+            assert_eq!(Action::from_name(a.name()), Some(a));
+            for &k in map.keys(a) {
+                let modifiers = if k.ctrl {
+                    KeyModifiers::CONTROL
+                } else {
+                    KeyModifiers::NONE
+                };
+                assert_eq!(map.action(k.code, modifiers), Some(a), "{k} is bound twice");
+            }
+        }
+    }
+
+    #[test]
+    fn rebinding_takes_keys_from_defaults() {
+        let mut map = KeyMap::default();
+        map.rebind(Action::Up, vec![code(KeyCode::Up)]);
+        map.rebind(Action::OpenDocs, vec![key('k')]);
+        assert_eq!(
+            map.action(KeyCode::Char('k'), KeyModifiers::NONE),
+            Some(Action::OpenDocs)
+        );
+        assert_eq!(map.action(KeyCode::Char('o'), KeyModifiers::CONTROL), None);
+        assert_eq!(
+            map.action(KeyCode::Char('G'), KeyModifiers::SHIFT),
+            Some(Action::Bottom)
+        );
+        assert_eq!(
+            map.label(&[Action::Up, Action::Down]),
+            "\u{2191}/\u{2193}/j"
+        );
+    }
+}
@@ -0,0 +1,149 @@
+//! MAVLink capture and classification behind the mavsnark TUI, for tools
+//! that want the same view of a link without the terminal.
+//!
+//! [`connection`] opens a link and feeds [`message::Incoming`] items into a
+//! channel; [`collector::Collector`] sorts them into one row per periodic
+//! stream ([`collector::StreamEntry`]) and a log of everything else
+//! ([`collector::MessageEntry`]), and runs the trackers (clock drift,
+//! signing, mission transfers, radio, ...) that raise alerts along the way.
+//! [`frame`] splits raw bytes into MAVLink frames, and [`session`] reads and
+//! writes the files the TUI saves with `Ctrl+s`.
+//!
+//! ```
+//! use mavlink::{
+//!     MavHeader,
+//!     common::{HEARTBEAT_DATA, MavMessage, STATUSTEXT_DATA},
+//! };
+//! use mavsnark::{collector::Collector, message::MavMsg};
+//!
+//! let mut collector = Collector::new();
+//! let header = MavHeader {
+//!     system_id: 1,
+//!     component_id: 1,
+//!     sequence: 0,
+//! };
+//! for msg in [
+//!     MavMessage::HEARTBEAT(HEARTBEAT_DATA::default()),
+//!     MavMessage::HEARTBEAT(HEARTBEAT_DATA::default()),
+//!     MavMessage::STATUSTEXT(STATUSTEXT_DATA::default()),
+//! ] {
+//!     collector.push(MavMsg::new(header, msg, 0));
+//! }
+//!
+//! // Both heartbeats update the same stream row; the text is an event.
+//! assert_eq!(collector.stream().len(), 1);
+//! assert_eq!(collector.stream()[0].name, "HEARTBEAT");
+//! assert_eq!(collector.messages().len(), 1);
+//! ```
+//!
+//! Reading a live link:
+//!
+//! ```no_run
+//! use mavsnark::{collector::Collector, connection, message::Incoming};
+//!
+//! # async fn run() -> std::io::Result<()> {
+//! let (tx, mut rx) = tokio::sync::mpsc::channel(1024);
+//! let link = connection::connect("udpin:0.0.0.0:14550")?;
+//! connection::spawn_reader(link, 0, tx);
+//!
+//! let mut collector = Collector::new();
+//! while let Some(incoming) = rx.recv().await {
+//!     match incoming {
+//!         Incoming::Message(msg) => collector.push(msg),
+//!         Incoming::Diagnostic(diag) => collector.push_diagnostic(diag),
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+mod agent;
+mod alerts;
+mod app;
+mod autosave;
+mod battery;
+mod bytemap;
+mod capabilities;
+mod cellular;
+mod cli;
+mod clock;
+pub mod collector;
+mod commands;
+mod compress;
+mod config;
+pub mod connection;
+mod counts;
+mod dedup;
+mod definitions;
+mod derived;
+mod direction;
+mod distance;
+mod entries;
+mod export;
+mod fallback;
+mod filter;
+mod flow;
+mod follow;
+pub mod frame;
+mod geo;
+mod gps;
+mod highlight;
+mod home;
+mod http;
+mod imu;
+mod index;
+mod interference;
+mod intervals;
+mod keymap;
+// Synthetic traffic for `benches/ingest.rs`, not part of the API.
+#[doc(hidden)]
+pub mod loadgen;
+mod logging;
+mod map;
+pub mod message;
+mod metrics;
+mod mirror;
+mod mission;
+mod modes;
+mod navigation;
+mod notify;
+mod palette;
+mod params;
+mod pcap;
+mod perf;
+mod plot;
+mod power;
+mod profile;
+mod radio;
+mod rate;
+mod replay;
+mod routing;
+mod safety;
+mod sanitize;
+mod scroll;
+mod search;
+mod security;
+pub mod session;
+mod severity;
+mod signing;
+mod snapshot;
+mod state;
+mod statustext;
+mod talkers;
+mod template;
+mod terrain;
+mod throughput;
+mod tlog;
+mod trigger;
+mod uavcan;
+mod vibration;
+mod vtol;
+mod watchdog;
+mod websocket;
+mod winch;
+mod wizard;
+
+#[doc(hidden)]
+pub use cli::run;
+#[doc(hidden)]
+pub use perf::CountingAllocator;
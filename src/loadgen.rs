@@ -0,0 +1,323 @@
+use chrono::{DateTime, TimeDelta, Utc};
+use mavlink::{
+    MavHeader,
+    common::{
+        ATTITUDE_DATA, BATTERY_STATUS_DATA, COMMAND_ACK_DATA, COMMAND_LONG_DATA,
+        GLOBAL_POSITION_INT_DATA, GPS_RAW_INT_DATA, GpsFixType, HEARTBEAT_DATA, HIGHRES_IMU_DATA,
+        LOCAL_POSITION_NED_DATA, MavAutopilot, MavCmd, MavMessage, MavModeFlag, MavResult,
+        MavSeverity, MavState, MavType, STATUSTEXT_DATA, SYS_STATUS_DATA, VFR_HUD_DATA,
+    },
+};
+
+use crate::message::MavMsg;
+
+/// What one autopilot streams by default, with rates in Hz.
+pub const TELEMETRY: &[(&str, u32)] = &[
+    ("ATTITUDE", 50),
+    ("HIGHRES_IMU", 50),
+    ("LOCAL_POSITION_NED", 30),
+    ("GLOBAL_POSITION_INT", 10),
+    ("VFR_HUD", 10),
+    ("GPS_RAW_INT", 5),
+    ("SYS_STATUS", 2),
+    ("BATTERY_STATUS", 1),
+    ("HEARTBEAT", 1),
+];
+
+/// The ground station's system ID, which commands come from.
+const GCS: u8 = 255;
+
+/// A 4S pack in millivolts, unused cells marked as such.
+const CELLS: [u16; 10] = [
+    3950,
+    3950,
+    3940,
+    3960,
+    u16::MAX,
+    u16::MAX,
+    u16::MAX,
+    u16::MAX,
+    u16::MAX,
+    u16::MAX,
+];
+
+/// Synthetic traffic for benchmarking the [`crate::collector::Collector`]
+/// without a link: vehicles streaming [`TELEMETRY`] in its real proportions,
+/// interleaved with commands, acks and status texts from the ground station.
+/// Timestamps advance as if the messages arrived at `rate` per second, so
+/// rates, staleness and history eviction behave as they would live.
+pub struct LoadGenerator {
+    rate: f64,
+    vehicles: u8,
+    /// Share of messages that are events rather than telemetry.
+    event_share: f64,
+    start: DateTime<Utc>,
+    sent: u64,
+    /// Telemetry messages sent, to take turns between vehicles.
+    streamed: u64,
+    /// Smooth weighted round robin credit per vehicle and [`TELEMETRY`]
+    /// entry.
+    credit: Vec<Vec<i64>>,
+    /// Events owed so far, fractional.
+    events_due: f64,
+    events: u64,
+    /// Next sequence number per system ID.
+    sequence: [u8; 256],
+}
+
+impl LoadGenerator {
+    /// Messages per second of simulated time, from one vehicle.
+    pub fn new(rate: f64) -> Self {
+        Self {
+            rate,
+            vehicles: 1,
+            event_share: 0.0,
+            start: DateTime::from_timestamp(1_700_000_000, 0).unwrap_or_default(),
+            sent: 0,
+            streamed: 0,
+            credit: vec![vec![0; TELEMETRY.len()]],
+            events_due: 0.0,
+            events: 0,
+            sequence: [0; 256],
+        }
+    }
+
+    /// Spreads the telemetry over system IDs `1..=vehicles`.
+    pub fn with_vehicles(mut self, vehicles: u8) -> Self {
+        self.vehicles = vehicles.max(1);
+        self.credit = vec![vec![0; TELEMETRY.len()]; usize::from(self.vehicles)];
+        self
+    }
+
+    /// Makes `share` (0 to 1) of the messages events.
+    pub fn with_event_share(mut self, share: f64) -> Self {
+        self.event_share = share.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_start(mut self, start: DateTime<Utc>) -> Self {
+        self.start = start;
+        self
+    }
+
+    fn now(&self) -> DateTime<Utc> {
+        let micros = self.sent as f64 * 1e6 / self.rate.max(f64::MIN_POSITIVE);
+        self.start + TimeDelta::microseconds(micros as i64)
+    }
+
+    /// The [`TELEMETRY`] entry `vehicle` sends next, in proportion to the
+    /// rates.
+    fn next_stream(&mut self, vehicle: usize) -> &'static str {
+        let total: i64 = TELEMETRY.iter().map(|&(_, hz)| i64::from(hz)).sum();
+        let credit = &mut self.credit[vehicle];
+        for (credit, &(_, hz)) in credit.iter_mut().zip(TELEMETRY) {
+            *credit += i64::from(hz);
+        }
+        let index = credit
+            .iter()
+            .enumerate()
+            .max_by_key(|&(i, credit)| (*credit, std::cmp::Reverse(i)))
+            .map_or(0, |(i, _)| i);
+        credit[index] -= total;
+        TELEMETRY[index].0
+    }
+
+    fn telemetry(&mut self, t: f64) -> (u8, MavMessage) {
+        let vehicle = (self.streamed % u64::from(self.vehicles)) as usize;
+        self.streamed += 1;
+        // Slowly changing values, so rows keep being reformatted.
+        let wave = (t * 0.5).sin() as f32;
+        let msg = match self.next_stream(vehicle) {
+            "ATTITUDE" => MavMessage::ATTITUDE(ATTITUDE_DATA {
+                time_boot_ms: (t * 1000.0) as u32,
+                roll: 0.1 * wave,
+                pitch: -0.05 * wave,
+                yaw: 1.5 + wave,
+                ..Default::default()
+            }),
+            "HIGHRES_IMU" => MavMessage::HIGHRES_IMU(HIGHRES_IMU_DATA {
+                time_usec: (t * 1e6) as u64,
+                xacc: 0.2 * wave,
+                zacc: -9.81,
+                abs_pressure: 1013.0 + wave,
+                temperature: 35.0,
+                ..Default::default()
+            }),
+            "LOCAL_POSITION_NED" => MavMessage::LOCAL_POSITION_NED(LOCAL_POSITION_NED_DATA {
+                time_boot_ms: (t * 1000.0) as u32,
+                x: 10.0 * wave,
+                y: 5.0 * wave,
+                z: -20.0,
+                ..Default::default()
+            }),
+            "GLOBAL_POSITION_INT" => MavMessage::GLOBAL_POSITION_INT(GLOBAL_POSITION_INT_DATA {
+                time_boot_ms: (t * 1000.0) as u32,
+                lat: 473_977_420 + (wave * 1000.0) as i32,
+                lon: 85_455_940,
+                alt: 508_000,
+                relative_alt: 20_000,
+                hdg: 9000,
+                ..Default::default()
+            }),
+            "VFR_HUD" => MavMessage::VFR_HUD(VFR_HUD_DATA {
+                airspeed: 5.0 + wave,
+                groundspeed: 5.0 + wave,
+                alt: 508.0,
+                heading: 90,
+                throttle: 55,
+                ..Default::default()
+            }),
+            "GPS_RAW_INT" => MavMessage::GPS_RAW_INT(GPS_RAW_INT_DATA {
+                time_usec: (t * 1e6) as u64,
+                fix_type: GpsFixType::GPS_FIX_TYPE_3D_FIX,
+                lat: 473_977_420,
+                lon: 85_455_940,
+                satellites_visible: 14,
+                eph: 80,
+                ..Default::default()
+            }),
+            "SYS_STATUS" => MavMessage::SYS_STATUS(SYS_STATUS_DATA {
+                voltage_battery: 15_800,
+                current_battery: 1_200,
+                battery_remaining: 80,
+                load: 350,
+                ..Default::default()
+            }),
+            "BATTERY_STATUS" => MavMessage::BATTERY_STATUS(BATTERY_STATUS_DATA {
+                voltages: CELLS,
+                current_battery: 1_200,
+                battery_remaining: 80,
+                ..Default::default()
+            }),
+            _ => MavMessage::HEARTBEAT(HEARTBEAT_DATA {
+                custom_mode: 0,
+                mavtype: MavType::MAV_TYPE_QUADROTOR,
+                autopilot: MavAutopilot::MAV_AUTOPILOT_PX4,
+                base_mode: MavModeFlag::MAV_MODE_FLAG_CUSTOM_MODE_ENABLED,
+                system_status: MavState::MAV_STATE_ACTIVE,
+                mavlink_version: 3,
+            }),
+        };
+        (vehicle as u8 + 1, msg)
+    }
+
+    /// A command from the ground station, its ack, or a status text, in turn.
+    fn event(&mut self) -> (u8, MavMessage) {
+        let turn = self.events;
+        self.events += 1;
+        let vehicle = (turn / 3 % u64::from(self.vehicles)) as u8 + 1;
+        match turn % 3 {
+            0 => (
+                GCS,
+                MavMessage::COMMAND_LONG(COMMAND_LONG_DATA {
+                    command: MavCmd::MAV_CMD_REQUEST_MESSAGE,
+                    param1: 148.0,
+                    target_system: vehicle,
+                    target_component: 1,
+                    ..Default::default()
+                }),
+            ),
+            1 => (
+                vehicle,
+                MavMessage::COMMAND_ACK(COMMAND_ACK_DATA {
+                    command: MavCmd::MAV_CMD_REQUEST_MESSAGE,
+                    result: MavResult::MAV_RESULT_ACCEPTED,
+                }),
+            ),
+            _ => (
+                vehicle,
+                MavMessage::STATUSTEXT(STATUSTEXT_DATA {
+                    severity: MavSeverity::MAV_SEVERITY_INFO,
+                    text: "Takeoff detected".into(),
+                }),
+            ),
+        }
+    }
+}
+
+impl Iterator for LoadGenerator {
+    type Item = MavMsg;
+
+    fn next(&mut self) -> Option<MavMsg> {
+        let at = self.now();
+        let t = at
+            .signed_duration_since(self.start)
+            .num_microseconds()
+            .unwrap_or(0) as f64
+            / 1e6;
+        self.events_due += self.event_share;
+        let (system_id, msg) = if self.events_due >= 1.0 {
+            self.events_due -= 1.0;
+            self.event()
+        } else {
+            self.telemetry(t)
+        };
+        let sequence = &mut self.sequence[usize::from(system_id)];
+        let header = MavHeader {
+            system_id,
+            component_id: 1,
+            sequence: *sequence,
+        };
+        *sequence = sequence.wrapping_add(1);
+        self.sent += 1;
+        Some(MavMsg {
+            timestamp: at,
+            ..MavMsg::new(header, msg, 0)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use mavlink::Message;
+
+    use super::*;
+
+    #[test]
+    fn telemetry_follows_the_stream_rates() {
+        let total: u32 = TELEMETRY.iter().map(|&(_, hz)| hz).sum();
+        let counts = LoadGenerator::new(1000.0).take(total as usize * 4).fold(
+            HashMap::new(),
+            |mut counts, msg| {
+                *counts.entry(msg.msg.message_name()).or_insert(0) += 1;
+                counts
+            },
+        );
+        for &(name, hz) in TELEMETRY {
+            assert_eq!(counts[name], hz * 4, "{name}");
+        }
+    }
+
+    #[test]
+    fn rate_vehicles_and_event_share() {
+        let msgs: Vec<_> = LoadGenerator::new(100.0)
+            .with_vehicles(3)
+            .with_event_share(0.25)
+            .take(400)
+            .collect();
+        let span = msgs[399].timestamp.signed_duration_since(msgs[0].timestamp);
+        assert_eq!(span.num_milliseconds(), 3990);
+        let events = msgs
+            .iter()
+            .filter(|m| {
+                matches!(
+                    m.msg,
+                    MavMessage::COMMAND_LONG(_)
+                        | MavMessage::COMMAND_ACK(_)
+                        | MavMessage::STATUSTEXT(_)
+                )
+            })
+            .count();
+        assert_eq!(events, 100);
+        let mut vehicles: Vec<_> = msgs
+            .iter()
+            .map(|m| m.header.system_id)
+            .filter(|&sys| sys != GCS)
+            .collect();
+        vehicles.sort();
+        vehicles.dedup();
+        assert_eq!(vehicles, [1, 2, 3]);
+    }
+}
@@ -0,0 +1,17 @@
+use std::{fs::OpenOptions, io, path::Path, sync::Mutex};
+
+use tracing::Level;
+
+/// Sends internal diagnostics to `path` for `--log-file`, since stderr is
+/// hidden behind the TUI. Appends, so repeated runs share one file.
+pub fn init(path: &Path, level: Level) -> io::Result<()> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    tracing_subscriber::fmt()
+        .with_writer(Mutex::new(file))
+        .with_ansi(false)
+        .with_max_level(level)
+        .with_target(false)
+        .init();
+    tracing::info!(version = env!("CARGO_PKG_VERSION"), "mavsnark started");
+    Ok(())
+}
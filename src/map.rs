@@ -0,0 +1,143 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use chrono::{DateTime, Utc};
+use mavlink::common::MavMessage;
+
+use crate::message::MavMsg;
+
+/// Positions kept per vehicle for its track on the Map tab.
+const TRACK: usize = 600;
+
+/// Where one vehicle is and has been, in degrees.
+#[derive(Debug, Default)]
+pub struct Vehicle {
+    /// `(lat, lon)` of the latest GLOBAL_POSITION_INTs, oldest first;
+    /// repeats of the same position are kept once.
+    pub track: VecDeque<(f64, f64)>,
+    /// Metres MSL of the latest position.
+    pub alt: f64,
+    pub at: Option<DateTime<Utc>>,
+    /// `(lat, lon)` from HOME_POSITION.
+    pub home: Option<(f64, f64)>,
+}
+
+impl Vehicle {
+    pub fn position(&self) -> Option<(f64, f64)> {
+        self.track.back().copied()
+    }
+}
+
+/// Tracks and homes per vehicle from GLOBAL_POSITION_INT and HOME_POSITION.
+#[derive(Default)]
+pub struct MapTracker {
+    vehicles: BTreeMap<u8, Vehicle>,
+}
+
+impl MapTracker {
+    pub fn observe(&mut self, msg: &MavMsg) {
+        let deg = |e7: i32| f64::from(e7) / 1e7;
+        let sys_id = msg.header.system_id;
+        match &msg.msg {
+            // 0, 0 is what autopilots send before a fix.
+            MavMessage::GLOBAL_POSITION_INT(d) if d.lat != 0 || d.lon != 0 => {
+                let vehicle = self.vehicles.entry(sys_id).or_default();
+                let position = (deg(d.lat), deg(d.lon));
+                if vehicle.position() != Some(position) {
+                    if vehicle.track.len() == TRACK {
+                        vehicle.track.pop_front();
+                    }
+                    vehicle.track.push_back(position);
+                }
+                vehicle.alt = f64::from(d.alt) / 1000.0;
+                vehicle.at = Some(msg.timestamp);
+            }
+            MavMessage::HOME_POSITION(d) => {
+                let vehicle = self.vehicles.entry(sys_id).or_default();
+                vehicle.home = Some((deg(d.latitude), deg(d.longitude)));
+            }
+            _ => {}
+        }
+    }
+
+    /// Vehicles by system id.
+    pub fn vehicles(&self) -> impl Iterator<Item = (u8, &Vehicle)> {
+        self.vehicles.iter().map(|(&id, v)| (id, v))
+    }
+
+    /// `((south, west), (north, east))` around every track and home;
+    /// `None` until something has a position.
+    pub fn bounds(&self) -> Option<((f64, f64), (f64, f64))> {
+        self.vehicles
+            .values()
+            .flat_map(|v| v.track.iter().chain(&v.home))
+            .fold(None, |bounds, &(lat, lon)| {
+                let ((south, west), (north, east)) = bounds.unwrap_or(((lat, lon), (lat, lon)));
+                Some((
+                    (south.min(lat), west.min(lon)),
+                    (north.max(lat), east.max(lon)),
+                ))
+            })
+    }
+
+    pub fn clear(&mut self) {
+        self.vehicles.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::{
+        MavHeader,
+        common::{GLOBAL_POSITION_INT_DATA, HOME_POSITION_DATA},
+    };
+
+    use super::*;
+
+    fn msg(msg: MavMessage) -> MavMsg {
+        let header = MavHeader {
+            system_id: 1,
+            component_id: 1,
+            sequence: 0,
+        };
+        MavMsg::new(header, msg, 0)
+    }
+
+    fn position(lat: i32, lon: i32) -> MavMsg {
+        msg(MavMessage::GLOBAL_POSITION_INT(GLOBAL_POSITION_INT_DATA {
+            lat,
+            lon,
+            alt: 488_000,
+            ..Default::default()
+        }))
+    }
+
+    #[test]
+    fn tracks_positions_once_each() {
+        let mut map = MapTracker::default();
+        map.observe(&position(0, 0));
+        assert!(map.bounds().is_none());
+        map.observe(&position(473_977_420, 85_455_940));
+        map.observe(&position(473_977_420, 85_455_940));
+        map.observe(&position(473_987_420, 85_455_940));
+        let (id, vehicle) = map.vehicles().next().unwrap();
+        assert_eq!(id, 1);
+        assert_eq!(vehicle.track.len(), 2);
+        assert_eq!(vehicle.position(), Some((47.398742, 8.545594)));
+        assert_eq!(vehicle.alt, 488.0);
+    }
+
+    #[test]
+    fn bounds_include_home() {
+        let mut map = MapTracker::default();
+        map.observe(&position(473_977_420, 85_455_940));
+        map.observe(&msg(MavMessage::HOME_POSITION(HOME_POSITION_DATA {
+            latitude: 473_967_420,
+            longitude: 85_465_940,
+            ..Default::default()
+        })));
+        assert_eq!(
+            map.bounds(),
+            Some(((47.396742, 8.545594), (47.397742, 8.546594)))
+        );
+    }
+}
@@ -1,7 +1,15 @@
 use chrono::{DateTime, Utc};
-use mavlink::{MavHeader, Message, common::MavMessage};
+use mavlink::{
+    MAVLinkV2MessageRaw, MavHeader, MavlinkVersion, Message, common::MavMessage, error::ParserError,
+};
 use ratatui::style::Color;
 
+use crate::{
+    definitions,
+    frame::{self, FrameError, FrameErrorKind, RawFrame},
+    signing::{self, SignatureStatus},
+};
+
 const COLORS: &[Color] = &[
     Color::Red,
     Color::Green,
@@ -11,27 +19,179 @@ const COLORS: &[Color] = &[
     Color::Cyan,
 ];
 
+pub fn id_color(id: u8) -> Color {
+    COLORS[id as usize % COLORS.len()]
+}
+
+/// Item sent from the link reader threads to the UI.
+#[allow(clippy::large_enum_variant)] // messages are the hot path; don't box them
+pub enum Incoming {
+    Message(MavMsg),
+    Diagnostic(Diagnostic),
+}
+
+/// A frame that was recognisably MAVLink but could not be decoded in the
+/// `common` dialect: a `DIALECT_MISMATCH`/`UNKNOWN_MESSAGE` hint, or the
+/// message itself when the `--dialect` extension or `--definitions`
+/// decode it. Also bytes that made no message at all, such as a frame with
+/// a bad CRC.
+pub struct Diagnostic {
+    pub header: MavHeader,
+    pub name: &'static str,
+    pub fields: String,
+    pub link: usize,
+    pub timestamp: DateTime<Utc>,
+    /// The bytes it is about; empty when read back from a session.
+    pub frame: Vec<u8>,
+}
+
+impl Diagnostic {
+    pub fn new(frame: &RawFrame, name: &'static str, fields: String, link: usize) -> Self {
+        Self {
+            header: MavHeader {
+                system_id: frame.system_id(),
+                component_id: frame.component_id(),
+                sequence: frame.sequence(),
+            },
+            name,
+            fields,
+            link,
+            timestamp: Utc::now(),
+            frame: frame.bytes.clone(),
+        }
+    }
+
+    /// Reports bytes the parser could not turn into a message.
+    pub fn error(error: &FrameError, link: usize) -> Self {
+        let header = match error.frame() {
+            Some(frame) => MavHeader {
+                system_id: frame.system_id(),
+                component_id: frame.component_id(),
+                sequence: frame.sequence(),
+            },
+            None => MavHeader {
+                system_id: 0,
+                component_id: 0,
+                sequence: 0,
+            },
+        };
+        Self {
+            header,
+            name: error.kind.name(),
+            fields: error.describe(),
+            link,
+            timestamp: Utc::now(),
+            frame: error.bytes.clone(),
+        }
+    }
+
+    /// A hint at why the frame was not decoded, rather than a message
+    /// decoded with [`frame::extension`] or [`definitions::installed`].
+    pub fn is_hint(&self) -> bool {
+        matches!(self.name, "DIALECT_MISMATCH" | "UNKNOWN_MESSAGE") || self.is_error()
+    }
+
+    /// Bytes that made no message, as reported by [`Diagnostic::error`].
+    pub fn is_error(&self) -> bool {
+        FrameErrorKind::NAMES.contains(&self.name)
+    }
+}
+
+/// MAVLink 2 signature trailer of a signed frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Signature {
+    pub link_id: u8,
+    /// Units of 10 microseconds since 2015-01-01.
+    pub timestamp: u64,
+    /// Whether it matched `--signing-key`; `None` without a key.
+    pub verified: Option<bool>,
+}
+
 pub struct MavMsg {
     pub header: MavHeader,
     pub msg: MavMessage,
     pub timestamp: DateTime<Utc>,
+    /// Index of the connection this message arrived on.
+    pub link: usize,
+    pub signature: Option<Signature>,
+    /// Size of the frame on the wire, in bytes.
+    pub len: usize,
+    /// Payload as received; MAVLink 2 drops trailing zero bytes.
+    pub payload: Vec<u8>,
+    /// The whole frame as received, signature included; empty for messages
+    /// read back from a session.
+    pub frame: Vec<u8>,
 }
 
 impl MavMsg {
-    pub fn new(header: MavHeader, msg: MavMessage) -> Self {
+    pub fn new(header: MavHeader, msg: MavMessage, link: usize) -> Self {
+        let mut payload = [0u8; 255];
+        // Unsigned MAVLink 2: 10 byte header, payload, 2 byte checksum.
+        let payload_len = msg.ser(MavlinkVersion::V2, &mut payload);
+        let mut raw = MAVLinkV2MessageRaw::new();
+        raw.serialize_message(header, &msg);
         Self {
             header,
             msg,
             timestamp: Utc::now(),
+            link,
+            signature: None,
+            len: 12 + payload_len,
+            payload: payload[..payload_len].to_vec(),
+            frame: raw.raw_bytes().to_vec(),
         }
     }
 
+    pub fn from_frame(frame: &RawFrame, link: usize) -> Result<Self, ParserError> {
+        let header = MavHeader {
+            system_id: frame.system_id(),
+            component_id: frame.component_id(),
+            sequence: frame.sequence(),
+        };
+        let version = if frame.is_v2() {
+            MavlinkVersion::V2
+        } else {
+            MavlinkVersion::V1
+        };
+        let msg = MavMessage::parse(version, frame.msg_id(), frame.payload())?;
+        let signature = frame.signature().map(|(link_id, timestamp)| Signature {
+            link_id,
+            timestamp,
+            verified: signing::key().map(|key| signing::verify(&frame.bytes, key)),
+        });
+        Ok(Self {
+            header,
+            msg,
+            timestamp: Utc::now(),
+            link,
+            signature,
+            len: frame.bytes.len(),
+            payload: frame.payload().to_vec(),
+            frame: frame.bytes.clone(),
+        })
+    }
+
+    pub fn signature_status(&self) -> SignatureStatus {
+        match self.signature.map(|s| s.verified) {
+            None => SignatureStatus::Unsigned,
+            Some(None) => SignatureStatus::Unchecked,
+            Some(Some(true)) => SignatureStatus::Valid,
+            Some(Some(false)) => SignatureStatus::Invalid,
+        }
+    }
+
+    /// Received as a MAVLink 1 frame: 6 byte header and 2 byte checksum
+    /// around the payload.
+    pub fn is_v1(&self) -> bool {
+        self.len == self.payload.len() + 8
+    }
+
     pub fn sys_color(&self) -> Color {
-        COLORS[self.header.system_id as usize % COLORS.len()]
+        id_color(self.header.system_id)
     }
 
     pub fn comp_color(&self) -> Color {
-        COLORS[self.header.component_id as usize % COLORS.len()]
+        id_color(self.header.component_id)
     }
 
     pub fn msg_color(&self) -> Option<Color> {
@@ -48,13 +208,32 @@ impl MavMsg {
     }
 
     pub fn fields(&self) -> String {
-        let debug = format!("{:?}", self.msg);
-        let start = debug.find('{').map(|i| i + 1).unwrap_or(0);
-        let end = debug.rfind('}').unwrap_or(debug.len());
-        debug[start..end].trim().to_string()
+        debug_fields(&self.msg)
     }
 }
 
+pub fn debug_fields(msg: &impl std::fmt::Debug) -> String {
+    let debug = format!("{msg:?}");
+    let start = debug.find('{').map(|i| i + 1).unwrap_or(0);
+    let end = debug.rfind('}').unwrap_or(debug.len());
+    debug[start..end].trim().to_string()
+}
+
+/// Whether `name` is a message in `common`, the `--dialect` extension or
+/// the `--definitions` file.
+pub fn is_message_name(name: &str) -> bool {
+    MavMessage::message_id_from_name(name).is_some()
+        || frame::extension().is_some_and(|d| d.message_name(name).is_some())
+        || definitions::installed().is_some_and(|d| d.message_name(name).is_some())
+}
+
+/// Fields of a default-constructed `name` message, in the same format as
+/// [`MavMsg::fields`]. `None` for names that are not MAVLink messages.
+pub fn default_fields(name: &str) -> Option<String> {
+    let id = MavMessage::message_id_from_name(name)?;
+    MavMessage::default_message_from_id(id).map(|msg| debug_fields(&msg))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -68,6 +247,11 @@ mod tests {
             },
             msg,
             timestamp: chrono::Utc::now(),
+            link: 0,
+            signature: None,
+            len: 0,
+            payload: Vec::new(),
+            frame: Vec::new(),
         }
     }
 
@@ -155,4 +339,11 @@ mod tests {
         );
         assert_eq!(m.msg_type(), "HEARTBEAT");
     }
+
+    #[test]
+    fn default_fields_for_known_messages() {
+        let fields = default_fields("COMMAND_ACK").unwrap();
+        assert!(fields.contains("result: MAV_RESULT_ACCEPTED"));
+        assert_eq!(default_fields("RATE_ANOMALY"), None);
+    }
 }
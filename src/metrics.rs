@@ -0,0 +1,187 @@
+use std::{collections::BTreeMap, fmt::Write};
+
+use chrono::{DateTime, Utc};
+
+use crate::collector::Collector;
+
+/// Content type of [`render`]'s output, Prometheus text format 0.0.4.
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// Everything `collector` counts, in the Prometheus text format, for
+/// `GET /metrics`. Counters start again from zero when the capture is
+/// cleared, which Prometheus treats as a restart.
+pub fn render(collector: &Collector, now: DateTime<Utc>) -> String {
+    let mut out = String::new();
+    let counts = collector.counts();
+
+    family(
+        &mut out,
+        "mavsnark_messages_total",
+        "counter",
+        "Messages received, by type.",
+        counts
+            .by_type()
+            .iter()
+            .map(|(name, n)| (format!("type=\"{name}\""), *n as f64)),
+    );
+    family(
+        &mut out,
+        "mavsnark_system_messages_total",
+        "counter",
+        "Messages received, by system.",
+        counts
+            .by_system()
+            .iter()
+            .map(|(sys_id, n)| (format!("sysid=\"{sys_id}\""), *n as f64)),
+    );
+
+    // Talkers rank by component; summed per system here.
+    let mut rates: BTreeMap<u8, (f64, f64)> = BTreeMap::new();
+    for talker in collector.talkers().by_source(now) {
+        let rate = rates.entry(talker.key.0).or_default();
+        rate.0 += talker.hz;
+        rate.1 += talker.bytes_per_sec;
+    }
+    family(
+        &mut out,
+        "mavsnark_system_rate_hz",
+        "gauge",
+        "Messages per second by system, over the last 10 seconds.",
+        rates
+            .iter()
+            .map(|(sys_id, (hz, _))| (format!("sysid=\"{sys_id}\""), *hz)),
+    );
+    family(
+        &mut out,
+        "mavsnark_system_bytes_per_second",
+        "gauge",
+        "Bytes per second by system, over the last 10 seconds.",
+        rates
+            .iter()
+            .map(|(sys_id, (_, bytes))| (format!("sysid=\"{sys_id}\""), *bytes)),
+    );
+
+    family(
+        &mut out,
+        "mavsnark_packets_lost_total",
+        "counter",
+        "Packets missing from sequence numbers, across all systems.",
+        [(String::new(), collector.radio().lost() as f64)],
+    );
+
+    family(
+        &mut out,
+        "mavsnark_link_frames_total",
+        "counter",
+        "Frames received, by link, duplicates included.",
+        counts
+            .links()
+            .iter()
+            .map(|(link, t)| (format!("link=\"{link}\""), t.frames as f64)),
+    );
+    family(
+        &mut out,
+        "mavsnark_link_bytes_total",
+        "counter",
+        "Bytes received on the wire, by link, duplicates included.",
+        counts
+            .links()
+            .iter()
+            .map(|(link, t)| (format!("link=\"{link}\""), t.bytes as f64)),
+    );
+    family(
+        &mut out,
+        "mavsnark_link_errors_total",
+        "counter",
+        "Frame errors, by link and kind.",
+        collector.link_errors().iter().flat_map(|(link, e)| {
+            [
+                ("crc", e.crc),
+                ("length", e.length),
+                ("framing", e.framing),
+                ("decode", e.decode),
+            ]
+            .map(|(kind, n)| (format!("link=\"{link}\",kind=\"{kind}\""), n as f64))
+        }),
+    );
+
+    family(
+        &mut out,
+        "mavsnark_vehicles",
+        "gauge",
+        "Systems sending heartbeats that are not ground stations.",
+        [(String::new(), collector.vehicle_count() as f64)],
+    );
+    family(
+        &mut out,
+        "mavsnark_alerts",
+        "gauge",
+        "Alerts raised since the capture was cleared.",
+        [(String::new(), collector.alert_count() as f64)],
+    );
+    family(
+        &mut out,
+        "mavsnark_memory_bytes",
+        "gauge",
+        "Approximate bytes held by events and history.",
+        [(String::new(), collector.usage().bytes as f64)],
+    );
+    out
+}
+
+/// Writes one metric family; samples are `(labels, value)`, labels already
+/// formatted and empty for none.
+fn family(
+    out: &mut String,
+    name: &str,
+    kind: &str,
+    help: &str,
+    samples: impl IntoIterator<Item = (String, f64)>,
+) {
+    // Writing to a String cannot fail.
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+    for (labels, value) in samples {
+        if labels.is_empty() {
+            let _ = writeln!(out, "{name} {value}");
+        } else {
+            let _ = writeln!(out, "{name}{{{labels}}} {value}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::{
+        MavHeader,
+        common::{HEARTBEAT_DATA, MavMessage},
+    };
+
+    use super::*;
+    use crate::message::MavMsg;
+
+    #[test]
+    fn renders_counters_and_gauges() {
+        let mut c = Collector::new();
+        for sequence in [0, 3] {
+            c.push(MavMsg::new(
+                MavHeader {
+                    system_id: 1,
+                    component_id: 1,
+                    sequence,
+                },
+                MavMessage::HEARTBEAT(HEARTBEAT_DATA::default()),
+                0,
+            ));
+        }
+        let text = render(&c, Utc::now());
+
+        assert!(text.contains("# TYPE mavsnark_messages_total counter\n"));
+        assert!(text.contains("mavsnark_messages_total{type=\"HEARTBEAT\"} 2\n"));
+        assert!(text.contains("mavsnark_system_messages_total{sysid=\"1\"} 2\n"));
+        assert!(text.contains("mavsnark_system_rate_hz{sysid=\"1\"} 0.2\n"));
+        assert!(text.contains("mavsnark_packets_lost_total 2\n"));
+        assert!(text.contains("mavsnark_link_frames_total{link=\"0\"} 2\n"));
+        assert!(text.contains("mavsnark_vehicles 1\n"));
+    }
+}
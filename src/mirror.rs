@@ -0,0 +1,151 @@
+use std::{
+    collections::VecDeque,
+    fs::{File, OpenOptions},
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use chrono::Local;
+use clap::ValueEnum;
+
+use crate::entries::{EventKind, MessageEntry};
+
+/// How `--mirror-events` writes each row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum MirrorFormat {
+    /// One line per event, as in the Events panel, with the time in front.
+    #[default]
+    Text,
+    /// One JSON object per line.
+    Json,
+}
+
+/// Appends every event row to a file as it arrives, for `tail -f` or a
+/// later grep next to the TUI.
+pub struct EventMirror {
+    out: BufWriter<File>,
+    format: MirrorFormat,
+    /// Events the collector had raised when the mirror last caught up.
+    written: usize,
+}
+
+impl EventMirror {
+    /// Opens `path` for appending, so restarts add to the same file.
+    pub fn new(path: &Path, format: MirrorFormat) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            out: BufWriter::new(file),
+            format,
+            written: 0,
+        })
+    }
+
+    /// Writes the events raised since the last call. `total` counts every
+    /// event the collector has held, including those it has since dropped
+    /// from the front of `events`; it going down means the view was cleared.
+    pub fn sync(&mut self, events: &VecDeque<MessageEntry>, total: usize) -> io::Result<()> {
+        if total < self.written {
+            self.written = 0;
+        }
+        let new = (total - self.written).min(events.len());
+        for entry in events.range(events.len() - new..) {
+            match self.format {
+                MirrorFormat::Text => writeln!(self.out, "{}", text_line(entry))?,
+                MirrorFormat::Json => writeln!(self.out, "{}", entry.to_json())?,
+            }
+        }
+        self.written = total;
+        self.out.flush()
+    }
+}
+
+fn text_line(entry: &MessageEntry) -> String {
+    let kind = match entry.kind {
+        EventKind::Message => "",
+        EventKind::Security => "SECURITY ",
+        EventKind::Diagnostic => "DIAG ",
+        EventKind::Alert => "ALERT ",
+    };
+    format!(
+        "{} [{:>3}:{:>3}] {}{}{} {}",
+        entry
+            .timestamp
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M:%S%.3f"),
+        entry.sys_id,
+        entry.comp_id,
+        if entry.duplicate { "DUP " } else { "" },
+        kind,
+        entry.name,
+        entry.fields
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use chrono::Utc;
+    use ratatui::style::Color;
+
+    use super::*;
+    use crate::signing::SignatureStatus;
+
+    fn entry(name: &'static str, kind: EventKind) -> MessageEntry {
+        MessageEntry {
+            sys_color: Color::Red,
+            comp_color: Color::Cyan,
+            msg_color: None,
+            sys_id: 1,
+            comp_id: 1,
+            name,
+            fields: "distance: 12.0".to_string(),
+            link: 0,
+            duplicate: false,
+            kind,
+            direction: None,
+            payload: Vec::new(),
+            frame: Vec::new(),
+            v1: false,
+            signature: SignatureStatus::Unsigned,
+            timestamp: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn appends_only_new_events() {
+        let path = std::env::temp_dir().join(format!("mavsnark-mirror-{}", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let mut mirror = EventMirror::new(&path, MirrorFormat::Text).unwrap();
+        let mut events = VecDeque::from([entry("COMMAND_ACK", EventKind::Message)]);
+        mirror.sync(&events, 1).unwrap();
+        mirror.sync(&events, 1).unwrap();
+        // One more raised while the oldest was dropped.
+        events.pop_front();
+        events.push_back(entry("GPS_DIVERGED", EventKind::Alert));
+        mirror.sync(&events, 2).unwrap();
+        // Cleared, then one more.
+        events.clear();
+        events.push_back(entry("STATUSTEXT", EventKind::Message));
+        mirror.sync(&events, 1).unwrap();
+
+        let text = fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3, "{text}");
+        assert!(lines[0].ends_with("[  1:  1] COMMAND_ACK distance: 12.0"));
+        assert!(lines[1].ends_with("ALERT GPS_DIVERGED distance: 12.0"));
+        assert!(lines[2].contains("STATUSTEXT"));
+
+        // A second run appends.
+        let mut mirror = EventMirror::new(&path, MirrorFormat::Json).unwrap();
+        mirror.sync(&events, 1).unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        assert!(
+            text.lines()
+                .nth(3)
+                .unwrap()
+                .starts_with("{\"event\":\"STATUSTEXT\"")
+        );
+        fs::remove_file(&path).unwrap();
+    }
+}
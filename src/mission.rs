@@ -0,0 +1,319 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use mavlink::common::MavMessage;
+
+use crate::message::MavMsg;
+
+/// How long a transfer may go without a new item being requested.
+const STALL: TimeDelta = TimeDelta::seconds(5);
+
+#[derive(Debug, PartialEq)]
+pub enum MissionReport {
+    /// A finished transfer in which some items had to be requested again.
+    Retried {
+        provider: u8,
+        requester: u8,
+        count: u16,
+        /// Item sequence number and how many extra requests it took.
+        retries: Vec<(u16, u32)>,
+    },
+    /// A transfer that stopped making progress.
+    Stalled {
+        provider: u8,
+        requester: u8,
+        count: u16,
+        seq: u16,
+        retries: u32,
+    },
+}
+
+impl MissionReport {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Retried { .. } => "MISSION_RETRIES",
+            Self::Stalled { .. } => "MISSION_STALLED",
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Retried {
+                provider,
+                requester,
+                count,
+                retries,
+            } => {
+                let items: Vec<String> = retries
+                    .iter()
+                    .map(|(seq, n)| format!("item {seq} retried {n}x"))
+                    .collect();
+                format!(
+                    "from: {provider}, to: {requester}, count: {count}, {}",
+                    items.join(", ")
+                )
+            }
+            Self::Stalled {
+                provider,
+                requester,
+                count,
+                seq,
+                retries,
+            } => format!(
+                "from: {provider}, to: {requester}, count: {count}, waiting_on: {seq}, retries: {retries}"
+            ),
+        }
+    }
+}
+
+/// What the tracker made of one message.
+#[derive(Debug, PartialEq)]
+pub enum MissionUpdate {
+    None,
+    /// A repeat request for an item already asked for; counted, not listed.
+    Retry,
+    Report(MissionReport),
+}
+
+struct Transfer {
+    count: u16,
+    /// Times each item was requested.
+    requests: BTreeMap<u16, u32>,
+    last_seq: Option<u16>,
+    last_progress: DateTime<Utc>,
+    stalled: bool,
+    /// Link the last progress arrived on.
+    link: usize,
+}
+
+impl Transfer {
+    fn retries(&self) -> Vec<(u16, u32)> {
+        self.requests
+            .iter()
+            .filter(|&(_, &n)| n > 1)
+            .map(|(&seq, &n)| (seq, n - 1))
+            .collect()
+    }
+}
+
+/// Follows mission transfers (MISSION_COUNT → MISSION_REQUEST(_INT)* →
+/// MISSION_ACK) in both directions, keyed by `(provider, requester)`.
+pub struct MissionTracker {
+    transfers: HashMap<(u8, u8), Transfer>,
+}
+
+impl Default for MissionTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MissionTracker {
+    pub fn new() -> Self {
+        Self {
+            transfers: HashMap::new(),
+        }
+    }
+
+    pub fn observe(&mut self, msg: &MavMsg) -> MissionUpdate {
+        let sys = msg.header.system_id;
+        match &msg.msg {
+            MavMessage::MISSION_COUNT(d) => {
+                self.transfers.insert(
+                    (sys, d.target_system),
+                    Transfer {
+                        count: d.count,
+                        requests: BTreeMap::new(),
+                        last_seq: None,
+                        last_progress: msg.timestamp,
+                        stalled: false,
+                        link: msg.link,
+                    },
+                );
+                MissionUpdate::None
+            }
+            // Deprecated, but still sent by older autopilots.
+            #[allow(deprecated)]
+            MavMessage::MISSION_REQUEST(d) => self.request(d.target_system, sys, d.seq, msg),
+            MavMessage::MISSION_REQUEST_INT(d) => self.request(d.target_system, sys, d.seq, msg),
+            MavMessage::MISSION_ACK(d) => {
+                let Some(transfer) = self.transfers.remove(&(d.target_system, sys)) else {
+                    return MissionUpdate::None;
+                };
+                let retries = transfer.retries();
+                if retries.is_empty() {
+                    return MissionUpdate::None;
+                }
+                MissionUpdate::Report(MissionReport::Retried {
+                    provider: d.target_system,
+                    requester: sys,
+                    count: transfer.count,
+                    retries,
+                })
+            }
+            _ => MissionUpdate::None,
+        }
+    }
+
+    fn request(&mut self, provider: u8, requester: u8, seq: u16, msg: &MavMsg) -> MissionUpdate {
+        let Some(transfer) = self.transfers.get_mut(&(provider, requester)) else {
+            return MissionUpdate::None;
+        };
+        let n = transfer.requests.entry(seq).or_insert(0);
+        *n += 1;
+        if *n > 1 {
+            return MissionUpdate::Retry;
+        }
+        transfer.last_seq = Some(seq);
+        transfer.last_progress = msg.timestamp;
+        transfer.stalled = false;
+        transfer.link = msg.link;
+        MissionUpdate::None
+    }
+
+    /// Reports each transfer once when it stops making progress, with the
+    /// link it was last heard on.
+    pub fn stalled(&mut self, now: DateTime<Utc>) -> Vec<(MissionReport, usize)> {
+        let mut reports = Vec::new();
+        for (&(provider, requester), transfer) in &mut self.transfers {
+            if transfer.stalled || now.signed_duration_since(transfer.last_progress) < STALL {
+                continue;
+            }
+            transfer.stalled = true;
+            let seq = transfer.last_seq.unwrap_or(0);
+            let report = MissionReport::Stalled {
+                provider,
+                requester,
+                count: transfer.count,
+                seq,
+                retries: transfer.requests.get(&seq).map_or(0, |n| n - 1),
+            };
+            reports.push((report, transfer.link));
+        }
+        reports
+    }
+
+    pub fn clear(&mut self) {
+        self.transfers.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::{
+        MavHeader,
+        common::{MISSION_ACK_DATA, MISSION_COUNT_DATA, MISSION_REQUEST_INT_DATA},
+    };
+
+    use super::*;
+
+    fn msg(sys_id: u8, msg: MavMessage, ms: i64) -> MavMsg {
+        let mut m = MavMsg::new(
+            MavHeader {
+                system_id: sys_id,
+                component_id: 1,
+                sequence: 0,
+            },
+            msg,
+            0,
+        );
+        m.timestamp = DateTime::from_timestamp_millis(ms).unwrap();
+        m
+    }
+
+    /// GCS 255 uploads `count` items to vehicle 1.
+    fn count(count: u16) -> MavMsg {
+        msg(
+            255,
+            MavMessage::MISSION_COUNT(MISSION_COUNT_DATA {
+                count,
+                target_system: 1,
+                target_component: 1,
+            }),
+            0,
+        )
+    }
+
+    fn request(seq: u16, ms: i64) -> MavMsg {
+        msg(
+            1,
+            MavMessage::MISSION_REQUEST_INT(MISSION_REQUEST_INT_DATA {
+                seq,
+                target_system: 255,
+                target_component: 190,
+            }),
+            ms,
+        )
+    }
+
+    fn ack() -> MavMsg {
+        msg(
+            1,
+            MavMessage::MISSION_ACK(MISSION_ACK_DATA {
+                target_system: 255,
+                target_component: 190,
+                ..Default::default()
+            }),
+            0,
+        )
+    }
+
+    fn at(ms: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp_millis(ms).unwrap()
+    }
+
+    #[test]
+    fn clean_transfer_reports_nothing() {
+        let mut t = MissionTracker::new();
+        t.observe(&count(2));
+        assert_eq!(t.observe(&request(0, 0)), MissionUpdate::None);
+        assert_eq!(t.observe(&request(1, 10)), MissionUpdate::None);
+        assert_eq!(t.observe(&ack()), MissionUpdate::None);
+    }
+
+    #[test]
+    fn repeats_are_summarised_on_ack() {
+        let mut t = MissionTracker::new();
+        t.observe(&count(20));
+        t.observe(&request(17, 0));
+        for _ in 0..5 {
+            assert_eq!(t.observe(&request(17, 0)), MissionUpdate::Retry);
+        }
+        let MissionUpdate::Report(report) = t.observe(&ack()) else {
+            panic!("expected a report");
+        };
+        assert_eq!(report.name(), "MISSION_RETRIES");
+        assert!(report.describe().contains("item 17 retried 5x"));
+    }
+
+    #[test]
+    fn stall_is_reported_once() {
+        let mut t = MissionTracker::new();
+        t.observe(&count(20));
+        t.observe(&request(3, 1000));
+        t.observe(&request(3, 2000));
+        assert!(t.stalled(at(4000)).is_empty());
+        let reports = t.stalled(at(7000));
+        assert_eq!(
+            reports,
+            vec![(
+                MissionReport::Stalled {
+                    provider: 255,
+                    requester: 1,
+                    count: 20,
+                    seq: 3,
+                    retries: 1,
+                },
+                0
+            )]
+        );
+        assert!(t.stalled(at(9000)).is_empty());
+    }
+
+    #[test]
+    fn requests_without_count_are_ignored() {
+        let mut t = MissionTracker::new();
+        t.observe(&request(0, 0));
+        assert_eq!(t.observe(&request(0, 0)), MissionUpdate::None);
+    }
+}
@@ -0,0 +1,189 @@
+use serde::Deserialize;
+
+/// How an autopilot packs its flight mode into `HEARTBEAT.custom_mode`.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ModeScheme {
+    Px4,
+    Ardupilot,
+}
+
+const PX4_MAIN: &[(u32, &str)] = &[
+    (1, "MANUAL"),
+    (2, "ALTCTL"),
+    (3, "POSCTL"),
+    (4, "AUTO"),
+    (5, "ACRO"),
+    (6, "OFFBOARD"),
+    (7, "STABILIZED"),
+    (8, "RATTITUDE"),
+    (9, "SIMPLE"),
+    (10, "TERMINATION"),
+];
+
+const PX4_AUTO: &[(u32, &str)] = &[
+    (1, "READY"),
+    (2, "TAKEOFF"),
+    (3, "LOITER"),
+    (4, "MISSION"),
+    (5, "RTL"),
+    (6, "LAND"),
+    (8, "FOLLOW_TARGET"),
+    (9, "PRECLAND"),
+    (10, "VTOL_TAKEOFF"),
+];
+
+const PX4_POSCTL: &[(u32, &str)] = &[(1, "ORBIT"), (2, "SLOW")];
+
+const COPTER: &[(u32, &str)] = &[
+    (0, "STABILIZE"),
+    (1, "ACRO"),
+    (2, "ALT_HOLD"),
+    (3, "AUTO"),
+    (4, "GUIDED"),
+    (5, "LOITER"),
+    (6, "RTL"),
+    (7, "CIRCLE"),
+    (9, "LAND"),
+    (11, "DRIFT"),
+    (13, "SPORT"),
+    (14, "FLIP"),
+    (15, "AUTOTUNE"),
+    (16, "POSHOLD"),
+    (17, "BRAKE"),
+    (18, "THROW"),
+    (19, "AVOID_ADSB"),
+    (20, "GUIDED_NOGPS"),
+    (21, "SMART_RTL"),
+    (22, "FLOWHOLD"),
+    (23, "FOLLOW"),
+    (24, "ZIGZAG"),
+    (25, "SYSTEMID"),
+    (26, "AUTOROTATE"),
+    (27, "AUTO_RTL"),
+    (28, "TURTLE"),
+];
+
+const PLANE: &[(u32, &str)] = &[
+    (0, "MANUAL"),
+    (1, "CIRCLE"),
+    (2, "STABILIZE"),
+    (3, "TRAINING"),
+    (4, "ACRO"),
+    (5, "FLY_BY_WIRE_A"),
+    (6, "FLY_BY_WIRE_B"),
+    (7, "CRUISE"),
+    (8, "AUTOTUNE"),
+    (10, "AUTO"),
+    (11, "RTL"),
+    (12, "LOITER"),
+    (13, "TAKEOFF"),
+    (14, "AVOID_ADSB"),
+    (15, "GUIDED"),
+    (17, "QSTABILIZE"),
+    (18, "QHOVER"),
+    (19, "QLOITER"),
+    (20, "QLAND"),
+    (21, "QRTL"),
+    (22, "QAUTOTUNE"),
+    (23, "QACRO"),
+    (24, "THERMAL"),
+    (25, "LOITER_ALT_QLAND"),
+];
+
+const ROVER: &[(u32, &str)] = &[
+    (0, "MANUAL"),
+    (1, "ACRO"),
+    (3, "STEERING"),
+    (4, "HOLD"),
+    (5, "LOITER"),
+    (6, "FOLLOW"),
+    (7, "SIMPLE"),
+    (8, "DOCK"),
+    (9, "CIRCLE"),
+    (10, "AUTO"),
+    (11, "RTL"),
+    (12, "SMART_RTL"),
+    (15, "GUIDED"),
+];
+
+const SUB: &[(u32, &str)] = &[
+    (0, "STABILIZE"),
+    (1, "ACRO"),
+    (2, "ALT_HOLD"),
+    (3, "AUTO"),
+    (4, "GUIDED"),
+    (7, "CIRCLE"),
+    (9, "SURFACE"),
+    (16, "POSHOLD"),
+    (19, "MANUAL"),
+    (20, "MOTOR_DETECT"),
+];
+
+fn lookup(table: &[(u32, &'static str)], value: u32) -> Option<&'static str> {
+    table
+        .iter()
+        .find(|&&(v, _)| v == value)
+        .map(|&(_, name)| name)
+}
+
+/// Names the flight mode in a heartbeat. `mavtype` is the `MAV_TYPE_*` name,
+/// which picks the ArduPilot firmware (Copter, Plane, Rover or Sub).
+pub fn decode(scheme: ModeScheme, mavtype: &str, custom_mode: u32) -> Option<String> {
+    match scheme {
+        ModeScheme::Px4 => {
+            let main = (custom_mode >> 16) & 0xff;
+            let sub = (custom_mode >> 24) & 0xff;
+            let main_name = lookup(PX4_MAIN, main)?;
+            let sub_table = match main {
+                4 => PX4_AUTO,
+                3 => PX4_POSCTL,
+                _ => &[],
+            };
+            Some(match lookup(sub_table, sub) {
+                Some(sub_name) => format!("{main_name}.{sub_name}"),
+                None => main_name.to_string(),
+            })
+        }
+        ModeScheme::Ardupilot => {
+            let kind = mavtype.trim_start_matches("MAV_TYPE_");
+            let table = match kind {
+                "FIXED_WING" => PLANE,
+                k if k.starts_with("VTOL") => PLANE,
+                "GROUND_ROVER" | "SURFACE_BOAT" => ROVER,
+                "SUBMARINE" => SUB,
+                _ => COPTER,
+            };
+            lookup(table, custom_mode).map(str::to_string)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn px4_main_and_sub_modes() {
+        let mode = (4 << 16) | (4 << 24);
+        assert_eq!(
+            decode(ModeScheme::Px4, "MAV_TYPE_QUADROTOR", mode).as_deref(),
+            Some("AUTO.MISSION")
+        );
+        assert_eq!(
+            decode(ModeScheme::Px4, "MAV_TYPE_QUADROTOR", 3 << 16).as_deref(),
+            Some("POSCTL")
+        );
+        assert_eq!(decode(ModeScheme::Px4, "MAV_TYPE_QUADROTOR", 0), None);
+    }
+
+    #[test]
+    fn ardupilot_table_follows_vehicle_type() {
+        let copter = decode(ModeScheme::Ardupilot, "MAV_TYPE_QUADROTOR", 5);
+        let plane = decode(ModeScheme::Ardupilot, "MAV_TYPE_FIXED_WING", 5);
+        let rover = decode(ModeScheme::Ardupilot, "MAV_TYPE_GROUND_ROVER", 4);
+        assert_eq!(copter.as_deref(), Some("LOITER"));
+        assert_eq!(plane.as_deref(), Some("FLY_BY_WIRE_A"));
+        assert_eq!(rover.as_deref(), Some("HOLD"));
+    }
+}
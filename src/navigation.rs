@@ -0,0 +1,164 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use mavlink::common::MavMessage;
+
+use crate::{message::MavMsg, radio::HISTORY};
+
+/// A heading older than this is not paired with the controller output.
+const MAX_AGE: TimeDelta = TimeDelta::seconds(2);
+
+/// One NAV_CONTROLLER_OUTPUT with the vehicle's heading at the time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NavSample {
+    pub at: DateTime<Utc>,
+    /// Bearing the controller is steering to, degrees.
+    pub desired: f64,
+    /// Heading from VFR_HUD, degrees; `None` without a recent one.
+    pub actual: Option<f64>,
+    /// Bearing to the current waypoint, degrees.
+    pub target: f64,
+    /// Metres off the track between waypoints.
+    pub crosstrack: f64,
+    /// Metres to the current waypoint.
+    pub wp_dist: f64,
+    pub alt_error: f64,
+}
+
+impl NavSample {
+    /// Actual minus desired heading in -180..180 degrees.
+    pub fn bearing_error(&self) -> Option<f64> {
+        let diff = self.actual? - self.desired;
+        Some((diff + 180.0).rem_euclid(360.0) - 180.0)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Navigation {
+    /// Over the last [`HISTORY`], oldest first.
+    pub samples: VecDeque<NavSample>,
+    heading: Option<(DateTime<Utc>, f64)>,
+}
+
+impl Navigation {
+    pub fn latest(&self) -> Option<&NavSample> {
+        self.samples.back()
+    }
+
+    /// Largest crosstrack error over the history, metres.
+    pub fn worst_crosstrack(&self) -> Option<f64> {
+        self.samples
+            .iter()
+            .map(|s| s.crosstrack.abs())
+            .max_by(f64::total_cmp)
+    }
+}
+
+/// NAV_CONTROLLER_OUTPUT history per vehicle, paired with VFR_HUD heading.
+#[derive(Default)]
+pub struct NavigationTracker {
+    vehicles: BTreeMap<u8, Navigation>,
+}
+
+impl NavigationTracker {
+    pub fn observe(&mut self, msg: &MavMsg) {
+        let at = msg.timestamp;
+        match &msg.msg {
+            MavMessage::VFR_HUD(d) => {
+                self.vehicles
+                    .entry(msg.header.system_id)
+                    .or_default()
+                    .heading = Some((at, f64::from(d.heading)));
+            }
+            MavMessage::NAV_CONTROLLER_OUTPUT(d) => {
+                let nav = self.vehicles.entry(msg.header.system_id).or_default();
+                let actual = nav
+                    .heading
+                    .filter(|(t, _)| at - *t <= MAX_AGE)
+                    .map(|(_, h)| h);
+                nav.samples.push_back(NavSample {
+                    at,
+                    desired: f64::from(d.nav_bearing).rem_euclid(360.0),
+                    actual,
+                    target: f64::from(d.target_bearing).rem_euclid(360.0),
+                    crosstrack: f64::from(d.xtrack_error),
+                    wp_dist: f64::from(d.wp_dist),
+                    alt_error: f64::from(d.alt_error),
+                });
+                while nav.samples.front().is_some_and(|s| at - s.at > HISTORY) {
+                    nav.samples.pop_front();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn vehicle(&self, sys_id: u8) -> Option<&Navigation> {
+        self.vehicles.get(&sys_id)
+    }
+
+    pub fn clear(&mut self) {
+        self.vehicles.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::{
+        MavHeader,
+        common::{NAV_CONTROLLER_OUTPUT_DATA, VFR_HUD_DATA},
+    };
+
+    use super::*;
+
+    fn push(tracker: &mut NavigationTracker, msg: MavMessage, at: DateTime<Utc>) {
+        let mut msg = MavMsg::new(MavHeader::default(), msg, 0);
+        msg.timestamp = at;
+        tracker.observe(&msg);
+    }
+
+    fn output(nav_bearing: i16, xtrack_error: f32) -> MavMessage {
+        MavMessage::NAV_CONTROLLER_OUTPUT(NAV_CONTROLLER_OUTPUT_DATA {
+            nav_bearing,
+            target_bearing: 10,
+            xtrack_error,
+            wp_dist: 240,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn pairs_desired_and_actual_bearing() {
+        let mut tracker = NavigationTracker::default();
+        let t0 = Utc::now();
+        push(&mut tracker, output(-10, 1.5), t0);
+        let sys_id = MavHeader::default().system_id;
+        assert_eq!(
+            tracker.vehicle(sys_id).unwrap().latest().unwrap().actual,
+            None
+        );
+
+        push(
+            &mut tracker,
+            MavMessage::VFR_HUD(VFR_HUD_DATA {
+                heading: 5,
+                ..Default::default()
+            }),
+            t0,
+        );
+        push(&mut tracker, output(-10, -4.0), t0 + TimeDelta::seconds(1));
+        let nav = tracker.vehicle(sys_id).unwrap();
+        let latest = nav.latest().unwrap();
+        assert_eq!(latest.desired, 350.0);
+        // 5° against 350° is 15° right of the wanted bearing, not 345° left.
+        assert_eq!(latest.bearing_error(), Some(15.0));
+        assert_eq!(nav.worst_crosstrack(), Some(4.0));
+
+        // A heading gone stale is not used.
+        push(&mut tracker, output(-10, 0.0), t0 + TimeDelta::seconds(4));
+        assert_eq!(
+            tracker.vehicle(sys_id).unwrap().latest().unwrap().actual,
+            None
+        );
+    }
+}
@@ -0,0 +1,201 @@
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+    process::{Command, Stdio},
+    thread,
+};
+
+use chrono::{DateTime, SecondsFormat, TimeDelta, Utc};
+
+use crate::{
+    config::NotifyConfig,
+    entries::{EventKind, MessageEntry},
+};
+
+/// Runs the `[notify]` hooks for alerts and security events, so an
+/// unattended soak test can page someone.
+pub struct Notifier {
+    command: Option<String>,
+    desktop: bool,
+    /// Event names to notify for; empty for every alert.
+    events: Vec<String>,
+    /// Per event name, so one flapping alert cannot drown out the rest.
+    min_interval: TimeDelta,
+    last: HashMap<&'static str, DateTime<Utc>>,
+}
+
+impl Notifier {
+    /// `None` when neither a command nor desktop notifications are set up.
+    pub fn new(config: &NotifyConfig) -> Option<Self> {
+        if config.command.is_none() && !config.desktop {
+            return None;
+        }
+        Some(Self {
+            command: config.command.clone(),
+            desktop: config.desktop,
+            events: config.events.clone(),
+            min_interval: TimeDelta::milliseconds((config.min_interval_secs * 1000.0) as i64),
+            last: HashMap::new(),
+        })
+    }
+
+    /// Starts the hooks for `entry` if it is one to notify for and its name
+    /// has not notified within the interval. The hooks run in the
+    /// background; only failing to start them is an error.
+    pub fn notify(&mut self, entry: &MessageEntry) -> io::Result<()> {
+        if !self.wants(entry) {
+            return Ok(());
+        }
+        if let Some(command) = &self.command {
+            run(command, entry)?;
+        }
+        if self.desktop {
+            desktop(entry)?;
+        }
+        Ok(())
+    }
+
+    fn wants(&mut self, entry: &MessageEntry) -> bool {
+        if !matches!(entry.kind, EventKind::Alert | EventKind::Security)
+            || !(self.events.is_empty() || self.events.iter().any(|e| e == entry.name))
+        {
+            return false;
+        }
+        if self
+            .last
+            .get(entry.name)
+            .is_some_and(|&at| entry.timestamp - at < self.min_interval)
+        {
+            return false;
+        }
+        self.last.insert(entry.name, entry.timestamp);
+        true
+    }
+}
+
+/// `sh -c command` (`cmd /C command` on Windows) with the event in
+/// `MAVSNARK_*` variables and as a JSON object on stdin.
+fn run(command: &str, entry: &MessageEntry) -> io::Result<()> {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    let mut child = shell
+        .arg(command)
+        .env("MAVSNARK_EVENT", entry.name)
+        .env("MAVSNARK_KIND", entry.kind.name())
+        .env("MAVSNARK_SYS", entry.sys_id.to_string())
+        .env("MAVSNARK_COMP", entry.comp_id.to_string())
+        .env("MAVSNARK_DETAILS", &entry.fields)
+        .env("MAVSNARK_TIME", timestamp(entry))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    let json = format!("{}\n", entry.to_json());
+    // A hook that never reads stdin or never exits must not stall the UI.
+    thread::spawn(move || {
+        if let Some(mut stdin) = child.stdin.take() {
+            let _ = stdin.write_all(json.as_bytes());
+        }
+        let _ = child.wait();
+    });
+    Ok(())
+}
+
+fn desktop(entry: &MessageEntry) -> io::Result<()> {
+    let title = format!(
+        "mavsnark: {} from {}/{}",
+        entry.name, entry.sys_id, entry.comp_id
+    );
+    let mut command = if cfg!(target_os = "macos") {
+        let script = format!(
+            "display notification \"{}\" with title \"{}\"",
+            applescript_escape(&entry.fields),
+            applescript_escape(&title)
+        );
+        let mut command = Command::new("osascript");
+        command.arg("-e").arg(script);
+        command
+    } else {
+        let mut command = Command::new("notify-send");
+        command.arg(title).arg(&entry.fields);
+        command
+    };
+    let mut child = command
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    thread::spawn(move || child.wait());
+    Ok(())
+}
+
+fn timestamp(entry: &MessageEntry) -> String {
+    entry.timestamp.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+fn applescript_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::style::Color;
+
+    use super::*;
+    use crate::signing::SignatureStatus;
+
+    fn entry(name: &'static str, kind: EventKind, at: DateTime<Utc>) -> MessageEntry {
+        MessageEntry {
+            sys_color: Color::Red,
+            comp_color: Color::Cyan,
+            msg_color: None,
+            sys_id: 1,
+            comp_id: 1,
+            name,
+            fields: "level: 42.5, note: \"x\\y\"\n".to_string(),
+            link: 0,
+            duplicate: false,
+            kind,
+            direction: None,
+            payload: Vec::new(),
+            frame: Vec::new(),
+            v1: false,
+            signature: SignatureStatus::Unsigned,
+            timestamp: at,
+        }
+    }
+
+    #[test]
+    fn needs_a_hook() {
+        assert!(Notifier::new(&NotifyConfig::default()).is_none());
+    }
+
+    #[test]
+    fn throttles_per_event_and_filters_by_name() {
+        let mut notifier = Notifier::new(&NotifyConfig {
+            desktop: true,
+            events: vec!["VIBRATION_HIGH".to_string(), "GPS_DIVERGED".to_string()],
+            min_interval_secs: 60.0,
+            ..Default::default()
+        })
+        .unwrap();
+        let t0 = Utc::now();
+        assert!(notifier.wants(&entry("VIBRATION_HIGH", EventKind::Alert, t0)));
+        assert!(!notifier.wants(&entry("VIBRATION_HIGH", EventKind::Alert, t0)));
+        assert!(notifier.wants(&entry("GPS_DIVERGED", EventKind::Alert, t0)));
+        assert!(notifier.wants(&entry(
+            "VIBRATION_HIGH",
+            EventKind::Alert,
+            t0 + TimeDelta::seconds(61)
+        )));
+        // Not listed, or not an alert.
+        assert!(!notifier.wants(&entry("HOME_RANGE_EXCEEDED", EventKind::Alert, t0)));
+        assert!(!notifier.wants(&entry("GPS_DIVERGED", EventKind::Diagnostic, t0)));
+    }
+}
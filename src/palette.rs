@@ -0,0 +1,73 @@
+use ratatui::{buffer::Buffer, style::Color};
+use serde::Deserialize;
+
+/// Colour scheme, chosen with `[ui] palette` in the config.
+///
+/// The alternatives swap the red/green hues the UI draws with for colours
+/// from the Okabe-Ito set, which stay distinct under red-green colour
+/// blindness. Like [`crate::fallback::Fallback`] they are applied to the
+/// finished buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Palette {
+    #[default]
+    Default,
+    Deuteranopia,
+    Protanopia,
+}
+
+const ORANGE: Color = Color::Rgb(230, 159, 0);
+const SKY_BLUE: Color = Color::Rgb(86, 180, 233);
+const BLUISH_GREEN: Color = Color::Rgb(0, 158, 115);
+const YELLOW: Color = Color::Rgb(240, 228, 66);
+const BLUE: Color = Color::Rgb(0, 114, 178);
+const VERMILLION: Color = Color::Rgb(213, 94, 0);
+const REDDISH_PURPLE: Color = Color::Rgb(204, 121, 167);
+
+impl Palette {
+    fn map(self, c: Color) -> Color {
+        match (self, c) {
+            (Palette::Default, c) => c,
+            // Reds read as dark brown to protanopes, so use the lighter orange.
+            (Palette::Protanopia, Color::Red | Color::LightRed) => ORANGE,
+            (Palette::Deuteranopia, Color::Red | Color::LightRed) => VERMILLION,
+            (_, Color::Green | Color::LightGreen) => SKY_BLUE,
+            (_, Color::Blue | Color::LightBlue) => BLUE,
+            (_, Color::Cyan | Color::LightCyan) => BLUISH_GREEN,
+            (_, Color::Yellow | Color::LightYellow) => YELLOW,
+            (_, Color::Magenta | Color::LightMagenta) => REDDISH_PURPLE,
+            (_, c) => c,
+        }
+    }
+
+    pub fn apply(self, buf: &mut Buffer) {
+        if self == Palette::Default {
+            return;
+        }
+        for cell in &mut buf.content {
+            cell.fg = self.map(cell.fg);
+            cell.bg = self.map(cell.bg);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn red_and_green_stay_apart() {
+        for palette in [Palette::Deuteranopia, Palette::Protanopia] {
+            let red = palette.map(Color::Red);
+            let green = palette.map(Color::Green);
+            assert_ne!(red, green);
+            assert_ne!(red, Color::Red);
+            assert_eq!(palette.map(Color::Gray), Color::Gray);
+        }
+    }
+
+    #[test]
+    fn default_palette_is_untouched() {
+        assert_eq!(Palette::Default.map(Color::LightRed), Color::LightRed);
+    }
+}
@@ -0,0 +1,201 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use mavlink::common::{MavMessage, MavParamType};
+
+use crate::message::MavMsg;
+
+/// The latest PARAM_VALUE of one parameter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Param {
+    pub value: f32,
+    pub kind: MavParamType,
+    pub index: u16,
+    pub at: DateTime<Utc>,
+}
+
+impl Param {
+    /// The value as the autopilot means it: integer types without a
+    /// fraction, since they travel cast to a float.
+    pub fn display(&self) -> String {
+        use MavParamType::*;
+        match self.kind {
+            MAV_PARAM_TYPE_REAL32 | MAV_PARAM_TYPE_REAL64 => self.value.to_string(),
+            _ => (self.value as i64).to_string(),
+        }
+    }
+}
+
+/// `INT32` for `MAV_PARAM_TYPE_INT32`.
+pub fn kind_name(kind: MavParamType) -> String {
+    let name = format!("{kind:?}");
+    name.strip_prefix("MAV_PARAM_TYPE_")
+        .unwrap_or(&name)
+        .to_string()
+}
+
+/// How the parameter table is ordered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParamOrder {
+    #[default]
+    Name,
+    Index,
+}
+
+impl ParamOrder {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Name => Self::Index,
+            Self::Index => Self::Name,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Name => "name",
+            Self::Index => "index",
+        }
+    }
+}
+
+/// The parameters one component has sent, and how many it says it has.
+#[derive(Default)]
+pub struct ParamSet {
+    params: BTreeMap<String, Param>,
+    /// `param_count` of the latest PARAM_VALUE.
+    pub count: u16,
+}
+
+impl ParamSet {
+    pub fn len(&self) -> usize {
+        self.params.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.params.is_empty()
+    }
+
+    /// Parameters whose name contains `query` (ignoring case), in `order`.
+    pub fn matching(&self, query: &str, order: ParamOrder) -> Vec<(&str, &Param)> {
+        let query = query.to_ascii_uppercase();
+        let mut rows: Vec<(&str, &Param)> = self
+            .params
+            .iter()
+            .filter(|(name, _)| name.to_ascii_uppercase().contains(&query))
+            .map(|(name, param)| (name.as_str(), param))
+            .collect();
+        if order == ParamOrder::Index {
+            rows.sort_by_key(|(_, p)| p.index);
+        }
+        rows
+    }
+}
+
+/// PARAM_VALUE collected per `(system, component)`, so a full parameter
+/// download can be browsed. Later values of a parameter replace earlier ones.
+#[derive(Default)]
+pub struct ParamTracker {
+    components: BTreeMap<(u8, u8), ParamSet>,
+}
+
+impl ParamTracker {
+    pub fn observe(&mut self, msg: &MavMsg) {
+        let MavMessage::PARAM_VALUE(d) = &msg.msg else {
+            return;
+        };
+        let Ok(name) = d.param_id.to_str() else {
+            return;
+        };
+        let set = self
+            .components
+            .entry((msg.header.system_id, msg.header.component_id))
+            .or_default();
+        set.count = d.param_count;
+        set.params.insert(
+            name.to_string(),
+            Param {
+                value: d.param_value,
+                kind: d.param_type,
+                index: d.param_index,
+                at: msg.timestamp,
+            },
+        );
+    }
+
+    /// Components that sent parameters, by `(system, component)`.
+    pub fn components(&self) -> impl Iterator<Item = (&(u8, u8), &ParamSet)> {
+        self.components.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.components.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::{MavHeader, common::PARAM_VALUE_DATA};
+
+    use super::*;
+
+    fn value(name: &str, value: f32, kind: MavParamType, index: u16) -> MavMsg {
+        let mut id = [0u8; 16];
+        id[..name.len()].copy_from_slice(name.as_bytes());
+        let header = MavHeader {
+            system_id: 1,
+            component_id: 1,
+            sequence: 0,
+        };
+        MavMsg::new(
+            header,
+            MavMessage::PARAM_VALUE(PARAM_VALUE_DATA {
+                param_value: value,
+                param_count: 3,
+                param_index: index,
+                param_id: id.into(),
+                param_type: kind,
+            }),
+            0,
+        )
+    }
+
+    #[test]
+    fn collects_sorts_and_searches() {
+        let mut tracker = ParamTracker::default();
+        let real = MavParamType::MAV_PARAM_TYPE_REAL32;
+        let int = MavParamType::MAV_PARAM_TYPE_INT8;
+        tracker.observe(&value("SYSID_THISMAV", 1.0, int, 2));
+        tracker.observe(&value("ARMING_CHECK", 1.0, int, 1));
+        tracker.observe(&value("ATC_RAT_RLL_P", 0.135, real, 0));
+        // A later value replaces the earlier one.
+        tracker.observe(&value("ARMING_CHECK", 0.0, int, 1));
+
+        let (&key, set) = tracker.components().next().unwrap();
+        assert_eq!(key, (1, 1));
+        assert_eq!((set.len(), set.count), (3, 3));
+
+        let names = |order| -> Vec<&str> {
+            set.matching("", order)
+                .into_iter()
+                .map(|(n, _)| n)
+                .collect()
+        };
+        assert_eq!(
+            names(ParamOrder::Name),
+            ["ARMING_CHECK", "ATC_RAT_RLL_P", "SYSID_THISMAV"]
+        );
+        assert_eq!(
+            names(ParamOrder::Index),
+            ["ATC_RAT_RLL_P", "ARMING_CHECK", "SYSID_THISMAV"]
+        );
+
+        let found = set.matching("arm", ParamOrder::Name);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].1.display(), "0");
+        assert_eq!(kind_name(found[0].1.kind), "INT8");
+        assert_eq!(
+            set.matching("rll", ParamOrder::Name)[0].1.display(),
+            "0.135"
+        );
+    }
+}
@@ -0,0 +1,528 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Cursor, Read, Write},
+    path::Path,
+};
+
+use chrono::{DateTime, Utc};
+use mavlink::{MAVLinkV1MessageRaw, MAVLinkV2MessageRaw};
+
+use crate::{
+    message::{Incoming, MavMsg},
+    session,
+};
+
+/// BSD loopback: a 4-byte address family in host order.
+const LINKTYPE_NULL: u16 = 0;
+const LINKTYPE_ETHERNET: u16 = 1;
+/// Raw IPv4 packets, no link layer.
+const LINKTYPE_RAW: u16 = 101;
+/// OpenBSD loopback: like [`LINKTYPE_NULL`], in network order.
+const LINKTYPE_LOOP: u16 = 108;
+/// Linux "any" device cooked capture, as `tcpdump -i any` writes.
+const LINKTYPE_LINUX_SLL: u16 = 113;
+const LINKTYPE_IPV4: u16 = 228;
+const LINKTYPE_IPV6: u16 = 229;
+const LINKTYPE_LINUX_SLL2: u16 = 276;
+const PCAP_MAGIC_MICROS: u32 = 0xA1B2_C3D4;
+const PCAP_MAGIC_NANOS: u32 = 0xA1B2_3C4D;
+const BLOCK_SHB: u32 = 0x0A0D_0D0A;
+const BLOCK_IDB: u32 = 1;
+const BLOCK_SPB: u32 = 3;
+const BLOCK_EPB: u32 = 6;
+const BYTE_ORDER_MAGIC: u32 = 0x1A2B_3C4D;
+const OPT_END: u16 = 0;
+const OPT_IF_NAME: u16 = 2;
+/// Wireshark's MAVLink dissector listens here.
+pub const MAVLINK_PORT: u16 = 14550;
+const SOURCE_PORT: u16 = 14555;
+
+/// Writes MAVLink frames as a pcapng capture, each in a UDP datagram to
+/// [`MAVLINK_PORT`] so Wireshark's MAVLink dissector picks them up. Every
+/// link gets its own interface, named after its URI.
+pub struct PcapngWriter<W: Write> {
+    out: W,
+    interfaces: usize,
+}
+
+impl<W: Write> PcapngWriter<W> {
+    /// Writes the section and interface headers.
+    pub fn new(mut out: W, links: &[String]) -> io::Result<Self> {
+        let mut body = Vec::new();
+        body.extend_from_slice(&BYTE_ORDER_MAGIC.to_le_bytes());
+        body.extend_from_slice(&1u16.to_le_bytes());
+        body.extend_from_slice(&0u16.to_le_bytes());
+        // Section length not known up front.
+        body.extend_from_slice(&(-1i64).to_le_bytes());
+        write_block(&mut out, BLOCK_SHB, &body)?;
+        let names: Vec<&str> = match links {
+            [] => vec!["mavsnark"],
+            links => links.iter().map(String::as_str).collect(),
+        };
+        for name in &names {
+            let mut body = Vec::new();
+            body.extend_from_slice(&LINKTYPE_RAW.to_le_bytes());
+            body.extend_from_slice(&0u16.to_le_bytes());
+            // No snapshot length limit.
+            body.extend_from_slice(&0u32.to_le_bytes());
+            push_option(&mut body, OPT_IF_NAME, name.as_bytes());
+            push_option(&mut body, OPT_END, &[]);
+            write_block(&mut out, BLOCK_IDB, &body)?;
+        }
+        Ok(Self {
+            out,
+            interfaces: names.len(),
+        })
+    }
+
+    /// One frame, on the interface of the link it arrived on.
+    pub fn write(&mut self, at: DateTime<Utc>, msg: &MavMsg) -> io::Result<()> {
+        let packet = udp_packet(&frame_bytes(msg));
+        let micros = at.timestamp_micros() as u64;
+        let mut body = Vec::with_capacity(20 + packet.len() + 3);
+        let interface = msg.link.min(self.interfaces - 1) as u32;
+        body.extend_from_slice(&interface.to_le_bytes());
+        body.extend_from_slice(&((micros >> 32) as u32).to_le_bytes());
+        body.extend_from_slice(&(micros as u32).to_le_bytes());
+        body.extend_from_slice(&(packet.len() as u32).to_le_bytes());
+        body.extend_from_slice(&(packet.len() as u32).to_le_bytes());
+        body.extend_from_slice(&packet);
+        pad(&mut body);
+        write_block(&mut self.out, BLOCK_EPB, &body)
+    }
+
+    /// Writes a received item; frames that could not be decoded are not
+    /// kept, so there is nothing to write for them.
+    pub fn record(&mut self, at: DateTime<Utc>, incoming: &Incoming) -> io::Result<()> {
+        match incoming {
+            Incoming::Message(msg) => self.write(at, msg),
+            Incoming::Diagnostic(_) => Ok(()),
+        }
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// `--pcapng`: a capture file written as traffic arrives.
+pub fn create(path: &Path, links: &[String]) -> io::Result<PcapngWriter<BufWriter<File>>> {
+    PcapngWriter::new(BufWriter::new(File::create(path)?), links)
+}
+
+/// Writes the messages in a session record log to `path`; returns how many.
+pub fn export(path: &Path, links: &[String], log: &[u8]) -> io::Result<usize> {
+    let mut writer = create(path, links)?;
+    let mut input = Cursor::new(log);
+    let mut frames = 0;
+    while let Some((at, incoming)) = session::read_record(&mut input)? {
+        if let Incoming::Message(msg) = &incoming {
+            writer.write(at, msg)?;
+            frames += 1;
+        }
+    }
+    writer.flush()?;
+    Ok(frames)
+}
+
+/// `pcap:` links: the UDP and TCP payloads of a tcpdump or Wireshark
+/// capture (pcap or pcapng), back to back, for the frame parser to pick the
+/// MAVLink out of. Packets that are not IP, are fragments, or carry no
+/// payload are skipped.
+pub struct PcapReader<R> {
+    input: R,
+    pcapng: bool,
+    big_endian: bool,
+    /// By interface; classic pcap has one for the whole file.
+    link_types: Vec<u16>,
+    /// Payload bytes not yet handed out.
+    pending: Vec<u8>,
+    consumed: usize,
+}
+
+impl<R: Read> PcapReader<R> {
+    /// Reads the file header; fails if `input` is neither format.
+    pub fn new(mut input: R) -> io::Result<Self> {
+        let magic: [u8; 4] = read_array(&mut input)?;
+        let mut reader = Self {
+            input,
+            pcapng: u32::from_le_bytes(magic) == BLOCK_SHB,
+            big_endian: false,
+            link_types: Vec::new(),
+            pending: Vec::new(),
+            consumed: 0,
+        };
+        if reader.pcapng {
+            let len = read_array(&mut reader.input)?;
+            reader.read_section_header(len)?;
+            return Ok(reader);
+        }
+        reader.big_endian = match (u32::from_le_bytes(magic), u32::from_be_bytes(magic)) {
+            (PCAP_MAGIC_MICROS | PCAP_MAGIC_NANOS, _) => false,
+            (_, PCAP_MAGIC_MICROS | PCAP_MAGIC_NANOS) => true,
+            _ => return Err(invalid("not a pcap or pcapng capture")),
+        };
+        // Version, time zone, accuracy and snapshot length.
+        read_bytes(&mut reader.input, 16)?;
+        let link_type = reader.u32(&read_array::<4>(&mut reader.input)?) as u16;
+        reader.link_types.push(link_type);
+        Ok(reader)
+    }
+
+    fn u32(&self, bytes: &[u8]) -> u32 {
+        let bytes = bytes.try_into().unwrap();
+        if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        }
+    }
+
+    /// The rest of a section header block after its type and `len`, still
+    /// in the byte order the block itself declares. Each section numbers
+    /// its interfaces afresh.
+    fn read_section_header(&mut self, len: [u8; 4]) -> io::Result<()> {
+        let magic: [u8; 4] = read_array(&mut self.input)?;
+        self.big_endian = match u32::from_le_bytes(magic) {
+            BYTE_ORDER_MAGIC => false,
+            m if m.swap_bytes() == BYTE_ORDER_MAGIC => true,
+            _ => return Err(invalid("bad pcapng byte order")),
+        };
+        let len = self.u32(&len) as usize;
+        if len < 28 || !len.is_multiple_of(4) {
+            return Err(invalid("bad pcapng block length"));
+        }
+        read_bytes(&mut self.input, len - 12)?;
+        self.link_types.clear();
+        Ok(())
+    }
+
+    /// The next packet's link type and bytes, `None` at the end.
+    fn next_packet(&mut self) -> io::Result<Option<(u16, Vec<u8>)>> {
+        loop {
+            let Some(head) = read_or_end::<_, 8>(&mut self.input)? else {
+                return Ok(None);
+            };
+            if !self.pcapng {
+                // `head` is the timestamp; then captured and original length.
+                let lengths: [u8; 8] = read_array(&mut self.input)?;
+                let captured = self.u32(&lengths[..4]) as usize;
+                let packet = read_bytes(&mut self.input, captured)?;
+                return Ok(Some((self.link_types[0], packet)));
+            }
+            // The same in either byte order.
+            if u32::from_le_bytes(head[..4].try_into().unwrap()) == BLOCK_SHB {
+                self.read_section_header(head[4..].try_into().unwrap())?;
+                continue;
+            }
+            let kind = self.u32(&head[..4]);
+            let len = self.u32(&head[4..]) as usize;
+            if len < 12 || !len.is_multiple_of(4) {
+                return Err(invalid("bad pcapng block length"));
+            }
+            let block = read_bytes(&mut self.input, len - 8)?;
+            let body = &block[..len - 12];
+            match kind {
+                BLOCK_IDB if body.len() >= 2 => {
+                    let link_type = if self.big_endian {
+                        u16::from_be_bytes([body[0], body[1]])
+                    } else {
+                        u16::from_le_bytes([body[0], body[1]])
+                    };
+                    self.link_types.push(link_type);
+                }
+                BLOCK_EPB if body.len() >= 20 => {
+                    let interface = self.u32(&body[..4]) as usize;
+                    let captured = self.u32(&body[12..16]) as usize;
+                    let packet = body
+                        .get(20..20 + captured)
+                        .ok_or_else(|| invalid("bad pcapng packet length"))?;
+                    if let Some(&link_type) = self.link_types.get(interface) {
+                        return Ok(Some((link_type, packet.to_vec())));
+                    }
+                }
+                BLOCK_SPB if body.len() >= 4 => {
+                    if let Some(&link_type) = self.link_types.first() {
+                        return Ok(Some((link_type, body[4..].to_vec())));
+                    }
+                }
+                // Name resolution, statistics, custom blocks, ...
+                _ => {}
+            }
+        }
+    }
+}
+
+impl<R: Read> Read for PcapReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.consumed == self.pending.len() {
+            let Some((link_type, packet)) = self.next_packet()? else {
+                return Ok(0);
+            };
+            if let Some(payload) = transport_payload(link_type, &packet) {
+                self.pending = payload.to_vec();
+                self.consumed = 0;
+            }
+        }
+        let n = buf.len().min(self.pending.len() - self.consumed);
+        buf[..n].copy_from_slice(&self.pending[self.consumed..self.consumed + n]);
+        self.consumed += n;
+        Ok(n)
+    }
+}
+
+/// The message re-encoded in the MAVLink version it arrived in. Signed
+/// frames come out unsigned: the signature itself is not kept.
+pub fn frame_bytes(msg: &MavMsg) -> Vec<u8> {
+    if msg.is_v1() {
+        let mut raw = MAVLinkV1MessageRaw::new();
+        raw.serialize_message(msg.header, &msg.msg);
+        raw.raw_bytes().to_vec()
+    } else {
+        let mut raw = MAVLinkV2MessageRaw::new();
+        raw.serialize_message(msg.header, &msg.msg);
+        raw.raw_bytes().to_vec()
+    }
+}
+
+/// `payload` in an IPv4/UDP datagram between two loopback ports.
+fn udp_packet(payload: &[u8]) -> Vec<u8> {
+    let total = (20 + 8 + payload.len()) as u16;
+    let mut packet = Vec::with_capacity(total as usize);
+    packet.extend_from_slice(&[0x45, 0]);
+    packet.extend_from_slice(&total.to_be_bytes());
+    // Identification, don't fragment, TTL 64, UDP, checksum filled below.
+    packet.extend_from_slice(&[0, 0, 0x40, 0, 64, 17, 0, 0]);
+    packet.extend_from_slice(&[127, 0, 0, 1, 127, 0, 0, 1]);
+    let checksum = ipv4_checksum(&packet);
+    packet[10..12].copy_from_slice(&checksum.to_be_bytes());
+    packet.extend_from_slice(&SOURCE_PORT.to_be_bytes());
+    packet.extend_from_slice(&MAVLINK_PORT.to_be_bytes());
+    packet.extend_from_slice(&(total - 20).to_be_bytes());
+    // An IPv4 UDP checksum of zero means none.
+    packet.extend_from_slice(&[0, 0]);
+    packet.extend_from_slice(payload);
+    packet
+}
+
+fn ipv4_checksum(header: &[u8]) -> u16 {
+    let sum: u32 = header
+        .chunks(2)
+        .map(|w| u32::from(u16::from_be_bytes([w[0], w[1]])))
+        .sum();
+    let folded = (sum & 0xFFFF) + (sum >> 16);
+    !((folded & 0xFFFF) + (folded >> 16)) as u16
+}
+
+/// The UDP or TCP payload of a captured packet, `None` for anything else.
+fn transport_payload(link_type: u16, packet: &[u8]) -> Option<&[u8]> {
+    let ip = match link_type {
+        LINKTYPE_RAW | LINKTYPE_IPV4 | LINKTYPE_IPV6 => packet,
+        LINKTYPE_NULL | LINKTYPE_LOOP => packet.get(4..)?,
+        LINKTYPE_ETHERNET => {
+            let mut at = 12;
+            // 802.1Q and 802.1ad VLAN tags.
+            while let [0x81, 0x00] | [0x88, 0xA8] = packet.get(at..at + 2)? {
+                at += 4;
+            }
+            packet.get(at + 2..)?
+        }
+        LINKTYPE_LINUX_SLL => packet.get(16..)?,
+        LINKTYPE_LINUX_SLL2 => packet.get(20..)?,
+        _ => return None,
+    };
+    let (protocol, transport) = match ip.first()? >> 4 {
+        4 => {
+            let header = usize::from(ip.first()? & 0x0F) * 4;
+            let total = usize::from(u16::from_be_bytes([*ip.get(2)?, *ip.get(3)?]));
+            // More fragments, or not the first.
+            if u16::from_be_bytes([*ip.get(6)?, *ip.get(7)?]) & 0x3FFF != 0 {
+                return None;
+            }
+            (*ip.get(9)?, ip.get(header..total.min(ip.len()))?)
+        }
+        6 => {
+            let total = 40 + usize::from(u16::from_be_bytes([*ip.get(4)?, *ip.get(5)?]));
+            (*ip.get(6)?, ip.get(40..total.min(ip.len()))?)
+        }
+        _ => return None,
+    };
+    let payload = match protocol {
+        17 => transport.get(8..)?,
+        6 => transport.get(usize::from(transport.get(12)? >> 4) * 4..)?,
+        _ => return None,
+    };
+    (!payload.is_empty()).then_some(payload)
+}
+
+fn read_array<const N: usize>(input: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut bytes = [0u8; N];
+    input.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Like [`read_array`], but `None` at a clean end of input.
+fn read_or_end<R: Read, const N: usize>(input: &mut R) -> io::Result<Option<[u8; N]>> {
+    let mut bytes = [0u8; N];
+    let mut filled = 0;
+    while filled < N {
+        match input.read(&mut bytes[filled..])? {
+            0 if filled == 0 => return Ok(None),
+            0 => return Err(io::ErrorKind::UnexpectedEof.into()),
+            n => filled += n,
+        }
+    }
+    Ok(Some(bytes))
+}
+
+fn read_bytes(input: &mut impl Read, len: usize) -> io::Result<Vec<u8>> {
+    let mut bytes = vec![0u8; len];
+    input.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn push_option(body: &mut Vec<u8>, code: u16, value: &[u8]) {
+    body.extend_from_slice(&code.to_le_bytes());
+    body.extend_from_slice(&(value.len() as u16).to_le_bytes());
+    body.extend_from_slice(value);
+    pad(body);
+}
+
+/// Blocks and options are 32-bit aligned.
+fn pad(body: &mut Vec<u8>) {
+    body.resize(body.len().next_multiple_of(4), 0);
+}
+
+fn write_block(out: &mut impl Write, kind: u32, body: &[u8]) -> io::Result<()> {
+    let len = (12 + body.len()) as u32;
+    out.write_all(&kind.to_le_bytes())?;
+    out.write_all(&len.to_le_bytes())?;
+    out.write_all(body)?;
+    out.write_all(&len.to_le_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::{
+        MavHeader,
+        common::{HEARTBEAT_DATA, MavMessage},
+    };
+
+    use super::*;
+
+    fn u32_at(bytes: &[u8], at: usize) -> u32 {
+        u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn writes_frames_in_udp_to_the_mavlink_port() {
+        let header = MavHeader {
+            system_id: 1,
+            component_id: 1,
+            sequence: 7,
+        };
+        let mut msg = MavMsg::new(header, MavMessage::HEARTBEAT(HEARTBEAT_DATA::default()), 1);
+        msg.timestamp = DateTime::from_timestamp_micros(0x1_0000_0002).unwrap();
+        let links = [
+            "udpin:0.0.0.0:14550".to_string(),
+            "serial:/dev/ttyUSB0".to_string(),
+        ];
+        let mut writer = PcapngWriter::new(Vec::new(), &links).unwrap();
+        writer.write(msg.timestamp, &msg).unwrap();
+        let bytes = writer.out;
+
+        assert_eq!(u32_at(&bytes, 0), BLOCK_SHB);
+        assert_eq!(u32_at(&bytes, 8), BYTE_ORDER_MAGIC);
+        let mut at = u32_at(&bytes, 4) as usize;
+        for link in &links {
+            assert_eq!(u32_at(&bytes, at), BLOCK_IDB);
+            let len = u32_at(&bytes, at + 4) as usize;
+            assert_eq!(&bytes[at + 8..at + 10], &LINKTYPE_RAW.to_le_bytes());
+            let name = &bytes[at + 20..at + 20 + link.len()];
+            assert_eq!(name, link.as_bytes());
+            assert_eq!(u32_at(&bytes, at + len - 4) as usize, len);
+            at += len;
+        }
+
+        assert_eq!(u32_at(&bytes, at), BLOCK_EPB);
+        assert_eq!(u32_at(&bytes, at + 8), 1, "second link's interface");
+        assert_eq!((u32_at(&bytes, at + 12), u32_at(&bytes, at + 16)), (1, 2));
+        let frame = frame_bytes(&msg);
+        assert_eq!(frame[0], 0xFD);
+        assert_eq!(frame.len(), msg.len);
+        let captured = u32_at(&bytes, at + 20) as usize;
+        assert_eq!(captured, 28 + frame.len());
+        let packet = &bytes[at + 28..at + 28 + captured];
+        assert_eq!(ipv4_checksum(&packet[..20]), 0);
+        assert_eq!(&packet[22..24], &MAVLINK_PORT.to_be_bytes());
+        assert_eq!(&packet[28..], frame.as_slice());
+        assert_eq!(at + u32_at(&bytes, at + 4) as usize, bytes.len());
+    }
+
+    #[test]
+    fn reads_back_what_it_writes() {
+        let frames: Vec<MavMsg> = (0..3)
+            .map(|sequence| {
+                let header = MavHeader {
+                    system_id: 1,
+                    component_id: 1,
+                    sequence,
+                };
+                MavMsg::new(header, MavMessage::HEARTBEAT(HEARTBEAT_DATA::default()), 0)
+            })
+            .collect();
+        let mut writer = PcapngWriter::new(Vec::new(), &[]).unwrap();
+        for msg in &frames {
+            writer.write(msg.timestamp, msg).unwrap();
+        }
+        let mut payloads = Vec::new();
+        PcapReader::new(writer.out.as_slice())
+            .unwrap()
+            .read_to_end(&mut payloads)
+            .unwrap();
+        assert_eq!(
+            payloads,
+            frames.iter().flat_map(frame_bytes).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn reads_tcp_over_tagged_ethernet_from_classic_pcap() {
+        let payload = b"\xFDframe";
+        let mut ip = udp_packet(payload);
+        // Make it TCP: protocol, and a 20-byte header in place of UDP's 8.
+        ip[9] = 6;
+        let mut tcp = [0u8; 20];
+        tcp[12] = 5 << 4;
+        ip.splice(20..28, tcp);
+        let total = ip.len() as u16;
+        ip[2..4].copy_from_slice(&total.to_be_bytes());
+        let mut packet = vec![0u8; 12];
+        packet.extend_from_slice(&[0x81, 0x00, 0, 1, 0x08, 0x00]);
+        packet.extend_from_slice(&ip);
+
+        // Big-endian header, as written on some capture hardware.
+        let mut capture = Vec::new();
+        capture.extend_from_slice(&PCAP_MAGIC_MICROS.to_be_bytes());
+        capture.extend_from_slice(&[0, 2, 0, 4]);
+        capture.extend_from_slice(&[0; 12]);
+        capture.extend_from_slice(&u32::from(LINKTYPE_ETHERNET).to_be_bytes());
+        for packet in [&packet, &vec![0u8; 20]] {
+            capture.extend_from_slice(&[0; 8]);
+            capture.extend_from_slice(&(packet.len() as u32).to_be_bytes());
+            capture.extend_from_slice(&(packet.len() as u32).to_be_bytes());
+            capture.extend_from_slice(packet);
+        }
+        let mut read = Vec::new();
+        PcapReader::new(capture.as_slice())
+            .unwrap()
+            .read_to_end(&mut read)
+            .unwrap();
+        assert_eq!(read, payload);
+        assert!(PcapReader::new(&b"not a capture"[..]).is_err());
+    }
+}
@@ -0,0 +1,154 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    collections::VecDeque,
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+/// Frames kept for the render time figures.
+const FRAME_WINDOW: Duration = Duration::from_secs(5);
+
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static LIVE_BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// The system allocator, counting as it goes. Install it with
+/// `#[global_allocator]` to get allocation figures in [`PerfMeter`].
+pub struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        INSTALLED.store(true, Ordering::Relaxed);
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        LIVE_BYTES.fetch_add(layout.size() as u64, Ordering::Relaxed);
+        // SAFETY: forwarded unchanged from the caller.
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE_BYTES.fetch_sub(layout.size() as u64, Ordering::Relaxed);
+        // SAFETY: forwarded unchanged from the caller.
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        LIVE_BYTES.fetch_add(new_size as u64, Ordering::Relaxed);
+        LIVE_BYTES.fetch_sub(layout.size() as u64, Ordering::Relaxed);
+        // SAFETY: forwarded unchanged from the caller.
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+/// `(allocations so far, bytes live)`, or `None` without
+/// [`CountingAllocator`].
+pub fn allocations() -> Option<(u64, u64)> {
+    INSTALLED.load(Ordering::Relaxed).then(|| {
+        (
+            ALLOCATIONS.load(Ordering::Relaxed),
+            LIVE_BYTES.load(Ordering::Relaxed),
+        )
+    })
+}
+
+/// Processing and drawing figures for the diagnostics panel, updated once
+/// per received item and once per drawn frame.
+pub struct PerfMeter {
+    received: u64,
+    /// `received` and the allocation count at the start of the current
+    /// second.
+    mark: (Instant, u64, u64),
+    /// Items and allocations per second over the last full second.
+    pub received_per_sec: f64,
+    pub allocations_per_sec: Option<f64>,
+    /// Items waiting in the link channel at the last frame.
+    pub backlog: usize,
+    frames: VecDeque<(Instant, Duration)>,
+}
+
+impl Default for PerfMeter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PerfMeter {
+    pub fn new() -> Self {
+        Self {
+            received: 0,
+            mark: (Instant::now(), 0, allocations().map_or(0, |(n, _)| n)),
+            received_per_sec: 0.0,
+            allocations_per_sec: None,
+            backlog: 0,
+            frames: VecDeque::new(),
+        }
+    }
+
+    pub fn received(&mut self) {
+        self.received += 1;
+    }
+
+    pub fn frame(&mut self, at: Instant, draw: Duration, backlog: usize) {
+        self.backlog = backlog;
+        self.frames.push_back((at, draw));
+        while self
+            .frames
+            .front()
+            .is_some_and(|&(t, _)| at.duration_since(t) > FRAME_WINDOW)
+        {
+            self.frames.pop_front();
+        }
+        let (since, received, allocated) = self.mark;
+        let elapsed = at.duration_since(since).as_secs_f64();
+        if elapsed >= 1.0 {
+            self.received_per_sec = (self.received - received) as f64 / elapsed;
+            let now_allocated = allocations().map(|(n, _)| n);
+            self.allocations_per_sec =
+                now_allocated.map(|n| n.saturating_sub(allocated) as f64 / elapsed);
+            self.mark = (at, self.received, now_allocated.unwrap_or(0));
+        }
+    }
+
+    /// Time the last frame took to draw.
+    pub fn last_frame(&self) -> Option<Duration> {
+        self.frames.back().map(|&(_, d)| d)
+    }
+
+    /// Slowest frame over the last few seconds.
+    pub fn slowest_frame(&self) -> Option<Duration> {
+        self.frames.iter().map(|&(_, d)| d).max()
+    }
+
+    pub fn total_received(&self) -> u64 {
+        self.received
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rates_over_each_second_and_recent_frames() {
+        let mut meter = PerfMeter::new();
+        let t0 = meter.mark.0;
+        for _ in 0..300 {
+            meter.received();
+        }
+        meter.frame(t0, Duration::from_millis(9), 4);
+        assert_eq!(meter.received_per_sec, 0.0);
+        meter.frame(
+            t0 + Duration::from_millis(1500),
+            Duration::from_millis(2),
+            0,
+        );
+        assert_eq!(meter.received_per_sec, 200.0);
+        assert_eq!(meter.backlog, 0);
+        assert_eq!(meter.last_frame(), Some(Duration::from_millis(2)));
+        assert_eq!(meter.slowest_frame(), Some(Duration::from_millis(9)));
+
+        meter.frame(t0 + Duration::from_secs(10), Duration::from_millis(3), 0);
+        assert_eq!(meter.slowest_frame(), Some(Duration::from_millis(3)));
+        assert_eq!(meter.total_received(), 300);
+    }
+}
@@ -0,0 +1,174 @@
+use std::collections::VecDeque;
+
+use chrono::{DateTime, TimeDelta, Utc};
+
+use crate::entries::{StreamEntry, parse_fields};
+
+/// How far back the plot reaches.
+pub const WINDOW: TimeDelta = TimeDelta::seconds(60);
+
+/// Samples kept at most, whatever the rate: a minute at 50 Hz and then some.
+const MAX_SAMPLES: usize = 4000;
+
+/// One numeric field of one stream row, followed over time for the plot
+/// panel (`w`).
+pub struct FieldPlot {
+    pub sys_id: u8,
+    pub comp_id: u8,
+    pub name: &'static str,
+    pub field: String,
+    samples: VecDeque<(DateTime<Utc>, f64)>,
+}
+
+impl FieldPlot {
+    /// Starts plotting `field` of `entry`, with what its history still holds.
+    pub fn new(entry: &StreamEntry, field: &str) -> Self {
+        let mut plot = Self {
+            sys_id: entry.sys_id,
+            comp_id: entry.comp_id,
+            name: entry.name,
+            field: field.to_string(),
+            samples: VecDeque::new(),
+        };
+        for sample in &entry.history {
+            plot.push(sample.timestamp, &sample.fields);
+        }
+        plot.observe(entry);
+        plot
+    }
+
+    /// `ATTITUDE.roll [1:1]`.
+    pub fn label(&self) -> String {
+        format!(
+            "{}.{} [{}:{}]",
+            self.name, self.field, self.sys_id, self.comp_id
+        )
+    }
+
+    pub fn is_for(&self, entry: &StreamEntry) -> bool {
+        (entry.sys_id, entry.comp_id, entry.name) == (self.sys_id, self.comp_id, self.name)
+    }
+
+    /// Takes the row's latest value, unless it was taken already.
+    pub fn observe(&mut self, entry: &StreamEntry) {
+        if self.is_for(entry) {
+            self.push(entry.timestamp, &entry.fields);
+        }
+    }
+
+    fn push(&mut self, at: DateTime<Utc>, fields: &str) {
+        if self.samples.back().is_some_and(|&(last, _)| last >= at) {
+            return;
+        }
+        let Some(value) = value(fields, &self.field) else {
+            return;
+        };
+        self.samples.push_back((at, value));
+        while self.samples.len() > MAX_SAMPLES
+            || self.samples.front().is_some_and(|&(t, _)| at - t > WINDOW)
+        {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Oldest first.
+    pub fn samples(&self) -> &VecDeque<(DateTime<Utc>, f64)> {
+        &self.samples
+    }
+
+    /// Lowest and highest value kept.
+    pub fn range(&self) -> Option<(f64, f64)> {
+        let mut values = self.samples.iter().map(|&(_, v)| v);
+        let first = values.next()?;
+        Some(values.fold((first, first), |(lo, hi), v| (lo.min(v), hi.max(v))))
+    }
+}
+
+/// `field` of a row's `fields` as a number; `None` if it is missing or not
+/// numeric.
+pub fn value(fields: &str, field: &str) -> Option<f64> {
+    parse_fields(fields)
+        .into_iter()
+        .find(|(k, _)| *k == field)
+        .and_then(|(_, v)| v.parse::<f64>().ok())
+        .filter(|v| v.is_finite())
+}
+
+/// Names of the fields that can be plotted, in message order.
+pub fn numeric_fields(fields: &str) -> Vec<&str> {
+    parse_fields(fields)
+        .into_iter()
+        .filter(|(_, v)| v.parse::<f64>().is_ok_and(f64::is_finite))
+        .map(|(k, _)| k)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::style::Color;
+
+    use super::*;
+    use crate::{
+        direction::Direction, entries::Sample, rate::ArrivalStats, signing::SignatureStatus,
+    };
+
+    fn attitude(secs: i64, roll: &str) -> StreamEntry {
+        StreamEntry {
+            sys_color: Color::Red,
+            comp_color: Color::Cyan,
+            msg_color: None,
+            sys_id: 1,
+            comp_id: 1,
+            name: "ATTITUDE",
+            fields: format!("time_boot_ms: 0, roll: {roll}, mode: STABILIZE"),
+            v1: false,
+            signature: SignatureStatus::Unsigned,
+            timestamp: DateTime::from_timestamp(secs, 0).unwrap(),
+            link: 0,
+            arrivals: ArrivalStats::default(),
+            direction: Direction::Downlink,
+            payload: Vec::new(),
+            frame: Vec::new(),
+            history: VecDeque::new(),
+        }
+    }
+
+    #[test]
+    fn follows_a_field_over_time() {
+        let mut entry = attitude(100, "0.5");
+        entry.history.push_back(Sample {
+            timestamp: DateTime::from_timestamp(10, 0).unwrap(),
+            fields: "roll: 9".to_string(),
+            payload: Vec::new(),
+            frame: Vec::new(),
+        });
+        entry.history.push_back(Sample {
+            timestamp: DateTime::from_timestamp(99, 0).unwrap(),
+            fields: "roll: -0.5".to_string(),
+            payload: Vec::new(),
+            frame: Vec::new(),
+        });
+        let mut plot = FieldPlot::new(&entry, "roll");
+        assert_eq!(plot.label(), "ATTITUDE.roll [1:1]");
+        // The sample from 90 s before the latest is outside the window.
+        assert_eq!(plot.samples().len(), 2);
+
+        // Unchanged rows and other rows add nothing.
+        plot.observe(&entry);
+        let mut other = attitude(101, "3");
+        other.sys_id = 2;
+        plot.observe(&other);
+        plot.observe(&attitude(101, "1.5"));
+        assert_eq!(plot.samples().len(), 3);
+        assert_eq!(plot.range(), Some((-0.5, 1.5)));
+    }
+
+    #[test]
+    fn only_numbers_are_plotted() {
+        let fields = "time_boot_ms: 0, roll: 0.5, mode: STABILIZE, data: [1, 2]";
+        assert_eq!(numeric_fields(fields), ["time_boot_ms", "roll"]);
+        assert_eq!(value(fields, "roll"), Some(0.5));
+        assert_eq!(value(fields, "mode"), None);
+        assert_eq!(value(fields, "yaw"), None);
+    }
+}
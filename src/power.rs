@@ -0,0 +1,197 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use chrono::{DateTime, Utc};
+use mavlink::common::{MavMessage, MavPowerStatus};
+
+use crate::{entries::Report, message::MavMsg, radio::HISTORY};
+
+/// Flags shown as indicators on the power panel, in order, with labels.
+pub const INDICATORS: [(MavPowerStatus, &str); 5] = [
+    (MavPowerStatus::MAV_POWER_STATUS_BRICK_VALID, "brick"),
+    (MavPowerStatus::MAV_POWER_STATUS_SERVO_VALID, "servo"),
+    (MavPowerStatus::MAV_POWER_STATUS_USB_CONNECTED, "usb"),
+    (
+        MavPowerStatus::MAV_POWER_STATUS_PERIPH_OVERCURRENT,
+        "periph overcurrent",
+    ),
+    (
+        MavPowerStatus::MAV_POWER_STATUS_PERIPH_HIPOWER_OVERCURRENT,
+        "hipower overcurrent",
+    ),
+];
+
+/// Flags that mean trouble when set.
+pub const OVERCURRENT: MavPowerStatus = MavPowerStatus::MAV_POWER_STATUS_PERIPH_OVERCURRENT
+    .union(MavPowerStatus::MAV_POWER_STATUS_PERIPH_HIPOWER_OVERCURRENT);
+
+/// Healthy range of the 5 V rail in volts.
+pub const VCC_RANGE: (f32, f32) = (4.5, 5.5);
+
+/// One POWER_STATUS.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PowerSample {
+    pub at: DateTime<Utc>,
+    /// Flight controller 5 V rail in volts.
+    pub vcc: f32,
+    /// Servo rail in volts; 0 when not measured.
+    pub vservo: f32,
+    pub flags: MavPowerStatus,
+}
+
+#[derive(Debug, Default)]
+pub struct Power {
+    /// Over the last [`HISTORY`], oldest first.
+    pub samples: VecDeque<PowerSample>,
+}
+
+impl Power {
+    pub fn latest(&self) -> Option<&PowerSample> {
+        self.samples.back()
+    }
+}
+
+/// A power flag turning on or off.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PowerReport {
+    pub vehicle: u8,
+    pub from: MavPowerStatus,
+    pub to: MavPowerStatus,
+}
+
+impl Report for PowerReport {
+    fn name(&self) -> &'static str {
+        "POWER_STATUS_CHANGE"
+    }
+
+    fn is_alert(&self) -> bool {
+        in_trouble(self.to)
+    }
+
+    fn source(&self) -> (u8, u8) {
+        (self.vehicle, 0)
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "flags: {} -> {}",
+            flag_names(self.from).join("|"),
+            flag_names(self.to).join("|")
+        )
+    }
+}
+
+/// POWER_STATUS history per vehicle.
+#[derive(Default)]
+pub struct PowerTracker {
+    vehicles: BTreeMap<u8, Power>,
+}
+
+impl PowerTracker {
+    pub fn observe(&mut self, msg: &MavMsg) -> Option<PowerReport> {
+        let MavMessage::POWER_STATUS(d) = &msg.msg else {
+            return None;
+        };
+        let power = self.vehicles.entry(msg.header.system_id).or_default();
+        let previous = power.latest().map(|s| s.flags);
+        power.samples.push_back(PowerSample {
+            at: msg.timestamp,
+            vcc: f32::from(d.Vcc) / 1000.0,
+            vservo: f32::from(d.Vservo) / 1000.0,
+            flags: d.flags,
+        });
+        while power
+            .samples
+            .front()
+            .is_some_and(|s| msg.timestamp - s.at > HISTORY)
+        {
+            power.samples.pop_front();
+        }
+        // CHANGED only says something changed since boot; the rest is news.
+        let watched = MavPowerStatus::all() - MavPowerStatus::MAV_POWER_STATUS_CHANGED;
+        let from = previous?;
+        (from & watched != d.flags & watched).then_some(PowerReport {
+            vehicle: msg.header.system_id,
+            from,
+            to: d.flags,
+        })
+    }
+
+    pub fn vehicle(&self, sys_id: u8) -> Option<&Power> {
+        self.vehicles.get(&sys_id)
+    }
+
+    pub fn clear(&mut self) {
+        self.vehicles.clear();
+    }
+}
+
+/// An overcurrent flag is set or the power brick stopped being valid.
+pub fn in_trouble(flags: MavPowerStatus) -> bool {
+    flags.intersects(OVERCURRENT) || !flags.contains(MavPowerStatus::MAV_POWER_STATUS_BRICK_VALID)
+}
+
+pub fn vcc_ok(vcc: f32) -> bool {
+    (VCC_RANGE.0..=VCC_RANGE.1).contains(&vcc)
+}
+
+pub fn flag_names(flags: MavPowerStatus) -> Vec<String> {
+    flags
+        .iter_names()
+        .map(|(name, _)| {
+            name.trim_start_matches("MAV_POWER_STATUS_")
+                .to_lowercase()
+                .replace('_', " ")
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::{MavHeader, common::POWER_STATUS_DATA};
+
+    use super::*;
+
+    fn status(vcc: u16, flags: MavPowerStatus) -> MavMsg {
+        MavMsg::new(
+            MavHeader::default(),
+            MavMessage::POWER_STATUS(POWER_STATUS_DATA {
+                Vcc: vcc,
+                Vservo: 0,
+                flags,
+            }),
+            0,
+        )
+    }
+
+    #[test]
+    fn overcurrent_is_an_alert() {
+        let mut power = PowerTracker::default();
+        let brick = MavPowerStatus::MAV_POWER_STATUS_BRICK_VALID;
+        assert!(power.observe(&status(5_020, brick)).is_none());
+        // Only CHANGED toggling is not news.
+        let changed = brick | MavPowerStatus::MAV_POWER_STATUS_CHANGED;
+        assert!(power.observe(&status(5_010, changed)).is_none());
+
+        let overcurrent = changed | MavPowerStatus::MAV_POWER_STATUS_PERIPH_OVERCURRENT;
+        let report = power.observe(&status(4_700, overcurrent)).unwrap();
+        assert!(report.is_alert());
+        assert!(
+            report
+                .describe()
+                .ends_with("brick valid|periph overcurrent|changed")
+        );
+
+        let usb = brick | MavPowerStatus::MAV_POWER_STATUS_USB_CONNECTED;
+        assert!(!power.observe(&status(5_000, usb)).unwrap().is_alert());
+
+        let latest = power
+            .vehicle(MavHeader::default().system_id)
+            .unwrap()
+            .latest()
+            .unwrap();
+        assert_eq!(latest.vcc, 5.0);
+        assert!(vcc_ok(latest.vcc));
+        assert!(!vcc_ok(4.3));
+        assert!(in_trouble(MavPowerStatus::empty()));
+    }
+}
@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::modes::ModeScheme;
+
+/// Docs page used when a profile does not set its own; `{name}` is replaced
+/// with the message name.
+pub const DEFAULT_DOCS_URL: &str = "https://mavlink.io/en/messages/common.html#{name}";
+
+/// Presets for one autopilot stack, chosen with `--profile`. Built in for
+/// `px4` and `ardupilot`; more can be defined under `[profiles.<name>]`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    /// Message names shown as stream rows; unset keeps the current set.
+    pub stream_types: Option<Vec<String>>,
+    /// Expected rates, used where the config does not set one.
+    pub expected_rates: HashMap<String, f64>,
+    /// How to name the flight mode in HEARTBEAT.
+    pub modes: Option<ModeScheme>,
+    /// Docs link template for `Ctrl+o`, with `{name}` for the message.
+    pub docs_url: Option<String>,
+}
+
+const PX4_STREAM: &[&str] = &[
+    "HEARTBEAT",
+    "SYS_STATUS",
+    "SYSTEM_TIME",
+    "EXTENDED_SYS_STATE",
+    "ATTITUDE",
+    "ATTITUDE_QUATERNION",
+    "ATTITUDE_TARGET",
+    "LOCAL_POSITION_NED",
+    "GLOBAL_POSITION_INT",
+    "POSITION_TARGET_LOCAL_NED",
+    "POSITION_TARGET_GLOBAL_INT",
+    "GPS_RAW_INT",
+    "HIGHRES_IMU",
+    "ALTITUDE",
+    "VFR_HUD",
+    "ESTIMATOR_STATUS",
+    "ODOMETRY",
+    "BATTERY_STATUS",
+    "VIBRATION",
+    "HOME_POSITION",
+    "MISSION_CURRENT",
+    "RC_CHANNELS",
+    "SERVO_OUTPUT_RAW",
+    "ACTUATOR_OUTPUT_STATUS",
+    "UTM_GLOBAL_POSITION",
+    "LINK_NODE_STATUS",
+    "TIMESYNC",
+    "RADIO_STATUS",
+];
+
+const ARDUPILOT_STREAM: &[&str] = &[
+    "HEARTBEAT",
+    "SYS_STATUS",
+    "SYSTEM_TIME",
+    "EXTENDED_SYS_STATE",
+    "ATTITUDE",
+    "GLOBAL_POSITION_INT",
+    "LOCAL_POSITION_NED",
+    "GPS_RAW_INT",
+    "GPS2_RAW",
+    "RAW_IMU",
+    "SCALED_IMU2",
+    "SCALED_IMU3",
+    "SCALED_PRESSURE",
+    "SCALED_PRESSURE2",
+    "VFR_HUD",
+    "NAV_CONTROLLER_OUTPUT",
+    "MISSION_CURRENT",
+    "BATTERY_STATUS",
+    "POWER_STATUS",
+    "VIBRATION",
+    "HOME_POSITION",
+    "TERRAIN_REPORT",
+    "DISTANCE_SENSOR",
+    "RC_CHANNELS",
+    "SERVO_OUTPUT_RAW",
+    "TIMESYNC",
+    "RADIO_STATUS",
+];
+
+pub const BUILTIN: &[&str] = &["ardupilot", "px4"];
+
+pub fn builtin(name: &str) -> Option<Profile> {
+    let (stream, modes) = match name {
+        "px4" => (PX4_STREAM, ModeScheme::Px4),
+        "ardupilot" => (ARDUPILOT_STREAM, ModeScheme::Ardupilot),
+        _ => return None,
+    };
+    Some(Profile {
+        stream_types: Some(stream.iter().map(|s| s.to_string()).collect()),
+        // Both stacks send heartbeats at 1 Hz on every link; other rates
+        // depend on the link's stream settings.
+        expected_rates: HashMap::from([("HEARTBEAT".to_string(), 1.0)]),
+        modes: Some(modes),
+        docs_url: None,
+    })
+}
+
+/// The docs link for message `name`.
+pub fn docs_url(template: &str, name: &str) -> String {
+    template.replace("{name}", name)
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::{Message, common::MavMessage};
+
+    use super::*;
+
+    #[test]
+    fn builtin_stream_types_are_known_messages() {
+        for name in BUILTIN {
+            let profile = builtin(name).unwrap();
+            for msg in profile.stream_types.unwrap() {
+                assert!(
+                    MavMessage::message_id_from_name(&msg).is_some(),
+                    "{name}: {msg}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn docs_url_fills_in_name() {
+        assert_eq!(
+            docs_url(DEFAULT_DOCS_URL, "HEARTBEAT"),
+            "https://mavlink.io/en/messages/common.html#HEARTBEAT"
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use std::collections::{HashMap, VecDeque};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use mavlink::common::MavMessage;
+
+use crate::message::MavMsg;
+
+/// How far back the RSSI and loss history reaches.
+pub const HISTORY: TimeDelta = TimeDelta::minutes(5);
+
+/// Sequence jumps this large are treated as a restart or reorder, not loss.
+const MAX_GAP: u8 = 128;
+
+pub struct RadioSample {
+    pub timestamp: DateTime<Utc>,
+    pub rssi: u8,
+    pub remrssi: u8,
+    pub noise: u8,
+    pub remnoise: u8,
+}
+
+/// RADIO_STATUS history per radio, alongside packets lost per second across
+/// all systems (from sequence gaps) so the two can be compared.
+pub struct RadioTracker {
+    history: HashMap<(u8, u8), VecDeque<RadioSample>>,
+    last_seq: HashMap<(u8, u8), u8>,
+    /// Lost packets per whole second (unix time).
+    loss: VecDeque<(i64, u32)>,
+    /// Lost packets since the last clear, beyond [`HISTORY`] too.
+    lost: u64,
+}
+
+impl Default for RadioTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RadioTracker {
+    pub fn new() -> Self {
+        Self {
+            history: HashMap::new(),
+            last_seq: HashMap::new(),
+            loss: VecDeque::new(),
+            lost: 0,
+        }
+    }
+
+    pub fn observe(&mut self, msg: &MavMsg) {
+        let key = (msg.header.system_id, msg.header.component_id);
+        let seq = msg.header.sequence;
+        if let Some(prev) = self.last_seq.insert(key, seq) {
+            let gap = seq.wrapping_sub(prev).wrapping_sub(1);
+            if gap > 0 && gap < MAX_GAP {
+                self.record_loss(msg.timestamp, gap as u32);
+            }
+        }
+
+        if let MavMessage::RADIO_STATUS(d) = &msg.msg {
+            let samples = self.history.entry(key).or_default();
+            samples.push_back(RadioSample {
+                timestamp: msg.timestamp,
+                rssi: d.rssi,
+                remrssi: d.remrssi,
+                noise: d.noise,
+                remnoise: d.remnoise,
+            });
+            while samples
+                .front()
+                .is_some_and(|s| msg.timestamp.signed_duration_since(s.timestamp) > HISTORY)
+            {
+                samples.pop_front();
+            }
+        }
+    }
+
+    fn record_loss(&mut self, ts: DateTime<Utc>, lost: u32) {
+        self.lost += lost as u64;
+        let second = ts.timestamp();
+        match self.loss.back_mut() {
+            Some((s, n)) if *s == second => *n += lost,
+            _ => self.loss.push_back((second, lost)),
+        }
+        let oldest = second - HISTORY.num_seconds();
+        while self.loss.front().is_some_and(|&(s, _)| s < oldest) {
+            self.loss.pop_front();
+        }
+    }
+
+    pub fn history(&self, sys_id: u8, comp_id: u8) -> Option<&VecDeque<RadioSample>> {
+        self.history.get(&(sys_id, comp_id))
+    }
+
+    pub fn loss(&self) -> &VecDeque<(i64, u32)> {
+        &self.loss
+    }
+
+    pub fn lost(&self) -> u64 {
+        self.lost
+    }
+
+    pub fn clear(&mut self) {
+        self.history.clear();
+        self.last_seq.clear();
+        self.loss.clear();
+        self.lost = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::{
+        MavHeader,
+        common::{HEARTBEAT_DATA, RADIO_STATUS_DATA},
+    };
+
+    use super::*;
+
+    fn msg(sequence: u8, msg: MavMessage, secs: i64) -> MavMsg {
+        let mut m = MavMsg::new(
+            MavHeader {
+                system_id: 1,
+                component_id: 1,
+                sequence,
+            },
+            msg,
+            0,
+        );
+        m.timestamp = DateTime::from_timestamp(secs, 0).unwrap();
+        m
+    }
+
+    fn heartbeat(sequence: u8, secs: i64) -> MavMsg {
+        msg(
+            sequence,
+            MavMessage::HEARTBEAT(HEARTBEAT_DATA::default()),
+            secs,
+        )
+    }
+
+    #[test]
+    fn sequence_gaps_count_as_loss() {
+        let mut r = RadioTracker::new();
+        r.observe(&heartbeat(0, 10));
+        r.observe(&heartbeat(1, 10));
+        r.observe(&heartbeat(4, 10));
+        r.observe(&heartbeat(6, 11));
+        assert_eq!(r.loss(), &VecDeque::from([(10, 2), (11, 1)]));
+        assert_eq!(r.lost(), 3);
+    }
+
+    #[test]
+    fn sequence_wraps_without_loss() {
+        let mut r = RadioTracker::new();
+        r.observe(&heartbeat(255, 10));
+        r.observe(&heartbeat(0, 10));
+        assert!(r.loss().is_empty());
+    }
+
+    #[test]
+    fn radio_status_history_is_bounded() {
+        let mut r = RadioTracker::new();
+        let status = |rssi| {
+            MavMessage::RADIO_STATUS(RADIO_STATUS_DATA {
+                rssi,
+                ..Default::default()
+            })
+        };
+        r.observe(&msg(0, status(100), 0));
+        r.observe(&msg(1, status(90), 200));
+        r.observe(&msg(2, status(80), 400));
+        let history = r.history(1, 1).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].rssi, 90);
+    }
+}
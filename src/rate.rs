@@ -0,0 +1,192 @@
+use std::collections::VecDeque;
+
+use chrono::{DateTime, Utc};
+
+/// Number of inter-arrival intervals kept per stream.
+const WINDOW: usize = 100;
+
+/// Number of most recent intervals used for the current rate.
+const RECENT: usize = 10;
+
+/// Inter-arrival timing of one stream, for rate and jitter.
+#[derive(Default)]
+pub struct ArrivalStats {
+    last: Option<DateTime<Utc>>,
+    intervals_ms: VecDeque<f64>,
+}
+
+impl ArrivalStats {
+    pub fn record(&mut self, ts: DateTime<Utc>) {
+        if let Some(last) = self.last {
+            let ms = ts
+                .signed_duration_since(last)
+                .num_microseconds()
+                .unwrap_or(0) as f64
+                / 1000.0;
+            if self.intervals_ms.len() == WINDOW {
+                self.intervals_ms.pop_front();
+            }
+            self.intervals_ms.push_back(ms.max(0.0));
+        }
+        self.last = Some(ts);
+    }
+
+    fn mean_ms(&self) -> Option<f64> {
+        if self.intervals_ms.is_empty() {
+            return None;
+        }
+        Some(self.intervals_ms.iter().sum::<f64>() / self.intervals_ms.len() as f64)
+    }
+
+    pub fn hz(&self) -> Option<f64> {
+        self.mean_ms().filter(|&m| m > 0.0).map(|m| 1000.0 / m)
+    }
+
+    /// Rate over the last few intervals, decaying once the stream goes quiet:
+    /// a stream can't be faster than one message per time since the last one.
+    pub fn recent_hz(&self, now: DateTime<Utc>) -> Option<f64> {
+        let last = self.last?;
+        let age_ms = now
+            .signed_duration_since(last)
+            .num_microseconds()
+            .unwrap_or(0) as f64
+            / 1000.0;
+        let quiet = (age_ms > 0.0).then(|| 1000.0 / age_ms);
+        let n = self.intervals_ms.len().min(RECENT);
+        let mean = self.intervals_ms.iter().rev().take(n).sum::<f64>() / n as f64;
+        let hz = (n > 0 && mean > 0.0).then(|| 1000.0 / mean);
+        match (hz, quiet) {
+            (Some(hz), Some(quiet)) => Some(hz.min(quiet)),
+            (Some(hz), None) => Some(hz),
+            // A lone arrival says nothing until it is clearly stale.
+            (None, Some(quiet)) if age_ms >= 1000.0 => Some(quiet),
+            _ => None,
+        }
+    }
+
+    /// Standard deviation of the inter-arrival interval.
+    pub fn stddev_ms(&self) -> Option<f64> {
+        let mean = self.mean_ms()?;
+        let n = self.intervals_ms.len() as f64;
+        let var = self
+            .intervals_ms
+            .iter()
+            .map(|i| (i - mean).powi(2))
+            .sum::<f64>()
+            / n;
+        Some(var.sqrt())
+    }
+
+    /// 95th percentile inter-arrival interval.
+    pub fn p95_ms(&self) -> Option<f64> {
+        if self.intervals_ms.is_empty() {
+            return None;
+        }
+        let mut sorted: Vec<f64> = self.intervals_ms.iter().copied().collect();
+        sorted.sort_by(f64::total_cmp);
+        let idx = ((sorted.len() as f64 * 0.95).ceil() as usize).clamp(1, sorted.len()) - 1;
+        Some(sorted[idx])
+    }
+
+    /// The kept intervals counted into `bins` equal-width buckets from the
+    /// shortest to the longest, as `(bucket start in ms, count)`.
+    pub fn histogram(&self, bins: usize) -> Vec<(f64, u64)> {
+        let Some(min) = self.intervals_ms.iter().copied().reduce(f64::min) else {
+            return Vec::new();
+        };
+        let max = self.intervals_ms.iter().copied().fold(min, f64::max);
+        let bins = bins.max(1);
+        let width = (max - min) / bins as f64;
+        let mut counts = vec![0; bins];
+        for &ms in &self.intervals_ms {
+            let bin = if width > 0.0 {
+                (((ms - min) / width) as usize).min(bins - 1)
+            } else {
+                0
+            };
+            counts[bin] += 1;
+        }
+        if width == 0.0 {
+            counts.truncate(1);
+        }
+        counts
+            .into_iter()
+            .enumerate()
+            .map(|(i, n)| (min + i as f64 * width, n))
+            .collect()
+    }
+
+    pub fn summary(&self) -> Option<String> {
+        Some(format!(
+            "{:.1} Hz, jitter \u{03c3} {:.1} ms, p95 {:.1} ms",
+            self.hz()?,
+            self.stddev_ms()?,
+            self.p95_ms()?
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(intervals: &[i64]) -> ArrivalStats {
+        let mut s = ArrivalStats::default();
+        let mut t = 0;
+        s.record(DateTime::from_timestamp_millis(t).unwrap());
+        for i in intervals {
+            t += i;
+            s.record(DateTime::from_timestamp_millis(t).unwrap());
+        }
+        s
+    }
+
+    #[test]
+    fn single_arrival_has_no_rate() {
+        let s = stats(&[]);
+        assert!(s.hz().is_none());
+        assert!(s.summary().is_none());
+    }
+
+    #[test]
+    fn steady_stream_has_no_jitter() {
+        let s = stats(&[100; 10]);
+        assert!((s.hz().unwrap() - 10.0).abs() < 1e-9);
+        assert_eq!(s.stddev_ms(), Some(0.0));
+        assert_eq!(s.p95_ms(), Some(100.0));
+    }
+
+    #[test]
+    fn bursty_stream_has_jitter() {
+        let s = stats(&[10, 190, 10, 190]);
+        assert!((s.hz().unwrap() - 10.0).abs() < 1e-9);
+        assert!((s.stddev_ms().unwrap() - 90.0).abs() < 1e-9);
+        assert_eq!(s.p95_ms(), Some(190.0));
+    }
+
+    #[test]
+    fn histogram_separates_bursts_from_gaps() {
+        let s = stats(&[10, 190, 10, 190, 100]);
+        assert_eq!(s.histogram(3), [(10.0, 2), (70.0, 1), (130.0, 2)]);
+        assert_eq!(stats(&[100; 4]).histogram(5), [(100.0, 4)]);
+        assert!(stats(&[]).histogram(5).is_empty());
+    }
+
+    #[test]
+    fn window_is_bounded() {
+        let mut intervals = vec![1000; 10];
+        intervals.extend([100; WINDOW]);
+        let s = stats(&intervals);
+        assert!((s.hz().unwrap() - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn recent_rate_decays_when_quiet() {
+        let s = stats(&[1000; 5]);
+        let last = DateTime::from_timestamp_millis(5000).unwrap();
+        let now = last + chrono::TimeDelta::milliseconds(500);
+        assert!((s.recent_hz(now).unwrap() - 1.0).abs() < 1e-9);
+        let now = last + chrono::TimeDelta::seconds(4);
+        assert!((s.recent_hz(now).unwrap() - 0.25).abs() < 1e-9);
+    }
+}
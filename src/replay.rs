@@ -0,0 +1,127 @@
+use std::{collections::VecDeque, time::Instant};
+
+use chrono::{DateTime, TimeDelta, Utc};
+
+use crate::message::Incoming;
+
+/// Playback speeds `+`/`-` step through.
+pub const SPEEDS: [f64; 7] = [0.25, 0.5, 1.0, 2.0, 5.0, 10.0, 100.0];
+
+const NORMAL: usize = 2;
+
+/// `--replay`: hands out recorded traffic as the recorded time catches up
+/// with it, instead of all at once.
+pub struct Replay {
+    records: VecDeque<(DateTime<Utc>, Incoming)>,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    /// Recorded time played up to.
+    position: DateTime<Utc>,
+    speed: usize,
+    /// Wall clock at the last [`Replay::advance`].
+    last: Option<Instant>,
+}
+
+impl Replay {
+    /// `None` for an empty recording. `records` must be in time order.
+    pub fn new(records: Vec<(DateTime<Utc>, Incoming)>) -> Option<Self> {
+        let start = records.first()?.0;
+        let end = records.last()?.0;
+        Some(Self {
+            records: records.into(),
+            start,
+            end,
+            position: start,
+            speed: NORMAL,
+            last: None,
+        })
+    }
+
+    /// Moves the recorded time on by the wall time since the last call,
+    /// scaled by the speed, and returns what was recorded up to it.
+    pub fn advance(&mut self, now: Instant) -> Vec<(DateTime<Utc>, Incoming)> {
+        if let Some(last) = self.last {
+            let elapsed = now.duration_since(last).as_secs_f64() * self.speed();
+            let step = TimeDelta::microseconds((elapsed * 1e6) as i64);
+            self.position = (self.position + step).min(self.end);
+        }
+        self.last = Some(now);
+        let due = self
+            .records
+            .iter()
+            .take_while(|(at, _)| *at <= self.position)
+            .count();
+        self.records.drain(..due).collect()
+    }
+
+    pub fn faster(&mut self) {
+        self.speed = (self.speed + 1).min(SPEEDS.len() - 1);
+    }
+
+    pub fn slower(&mut self) {
+        self.speed = self.speed.saturating_sub(1);
+    }
+
+    pub fn speed(&self) -> f64 {
+        SPEEDS[self.speed]
+    }
+
+    pub fn position(&self) -> DateTime<Utc> {
+        self.position
+    }
+
+    /// Played and total length of the recording.
+    pub fn progress(&self) -> (TimeDelta, TimeDelta) {
+        (self.position - self.start, self.end - self.start)
+    }
+
+    pub fn finished(&self) -> bool {
+        self.records.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use mavlink::{MavHeader, common::MavMessage};
+
+    use super::*;
+    use crate::message::MavMsg;
+
+    fn record(at: DateTime<Utc>) -> (DateTime<Utc>, Incoming) {
+        let msg = MavMessage::HEARTBEAT(Default::default());
+        (
+            at,
+            Incoming::Message(MavMsg::new(MavHeader::default(), msg, 0)),
+        )
+    }
+
+    #[test]
+    fn plays_at_the_recorded_pace() {
+        let t0 = Utc::now();
+        let records = [0, 500, 1500, 4000]
+            .map(|ms| record(t0 + TimeDelta::milliseconds(ms)))
+            .into();
+        let mut replay = Replay::new(records).unwrap();
+        let wall = Instant::now();
+        assert_eq!(replay.advance(wall).len(), 1);
+        assert_eq!(replay.advance(wall + Duration::from_secs(1)).len(), 1);
+        replay.faster();
+        assert_eq!(replay.speed(), 2.0);
+        // Half a second at 2x reaches 2 s in.
+        assert_eq!(replay.advance(wall + Duration::from_millis(1500)).len(), 1);
+        assert!(!replay.finished());
+        replay.slower();
+        replay.slower();
+        assert_eq!(replay.speed(), 0.5);
+        assert!(replay.advance(wall + Duration::from_secs(5)).is_empty());
+        assert_eq!(
+            replay.progress(),
+            (TimeDelta::milliseconds(3750), TimeDelta::milliseconds(4000))
+        );
+        replay.advance(wall + Duration::from_secs(6));
+        assert!(replay.finished());
+        assert_eq!(replay.position(), t0 + TimeDelta::milliseconds(4000));
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, TimeDelta, Utc};
+
+use crate::message::MavMsg;
+
+/// How long a system may be silent on its link before traffic on another
+/// link counts as a migration rather than redundancy.
+const STALE: TimeDelta = TimeDelta::seconds(3);
+
+#[derive(Debug, PartialEq)]
+pub struct Migration {
+    pub sys_id: u8,
+    pub from: usize,
+    pub to: usize,
+}
+
+impl Migration {
+    pub fn name(&self) -> &'static str {
+        "LINK_MIGRATION"
+    }
+
+    pub fn describe(&self) -> String {
+        format!("from_link: {}, to_link: {}", self.from, self.to)
+    }
+}
+
+struct Route {
+    current: usize,
+    last_seen: BTreeMap<usize, DateTime<Utc>>,
+}
+
+/// Infers which link each system is reachable on.
+pub struct RouteTracker {
+    systems: HashMap<u8, Route>,
+}
+
+impl Default for RouteTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RouteTracker {
+    pub fn new() -> Self {
+        Self {
+            systems: HashMap::new(),
+        }
+    }
+
+    /// Call for every delivery, duplicates included, so redundant links are seen.
+    pub fn observe(&mut self, msg: &MavMsg) -> Option<Migration> {
+        let sys_id = msg.header.system_id;
+        let route = self.systems.entry(sys_id).or_insert(Route {
+            current: msg.link,
+            last_seen: BTreeMap::new(),
+        });
+        route.last_seen.insert(msg.link, msg.timestamp);
+        if msg.link == route.current {
+            return None;
+        }
+        let silent = route
+            .last_seen
+            .get(&route.current)
+            .is_none_or(|&t| msg.timestamp.signed_duration_since(t) > STALE);
+        if !silent {
+            return None;
+        }
+        let from = std::mem::replace(&mut route.current, msg.link);
+        Some(Migration {
+            sys_id,
+            from,
+            to: msg.link,
+        })
+    }
+
+    /// Systems heard on `link` recently.
+    pub fn systems_on(&self, link: usize, now: DateTime<Utc>) -> Vec<u8> {
+        let mut ids: Vec<u8> = self
+            .systems
+            .iter()
+            .filter(|(_, route)| {
+                route
+                    .last_seen
+                    .get(&link)
+                    .is_some_and(|&t| now.signed_duration_since(t) <= STALE)
+            })
+            .map(|(&id, _)| id)
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    pub fn clear(&mut self) {
+        self.systems.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::{
+        MavHeader,
+        common::{HEARTBEAT_DATA, MavMessage},
+    };
+
+    use super::*;
+
+    fn msg(sys_id: u8, link: usize, ms: i64) -> MavMsg {
+        let mut m = MavMsg::new(
+            MavHeader {
+                system_id: sys_id,
+                component_id: 1,
+                sequence: 0,
+            },
+            MavMessage::HEARTBEAT(HEARTBEAT_DATA::default()),
+            link,
+        );
+        m.timestamp = DateTime::from_timestamp_millis(ms).unwrap();
+        m
+    }
+
+    fn at(ms: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp_millis(ms).unwrap()
+    }
+
+    #[test]
+    fn redundant_links_are_not_a_migration() {
+        let mut r = RouteTracker::new();
+        assert_eq!(r.observe(&msg(1, 0, 0)), None);
+        assert_eq!(r.observe(&msg(1, 1, 10)), None);
+        assert_eq!(r.observe(&msg(1, 0, 1000)), None);
+        assert_eq!(r.observe(&msg(1, 1, 1010)), None);
+        assert_eq!(r.systems_on(0, at(1010)), vec![1]);
+        assert_eq!(r.systems_on(1, at(1010)), vec![1]);
+    }
+
+    #[test]
+    fn falling_back_to_another_link_is_a_migration() {
+        let mut r = RouteTracker::new();
+        r.observe(&msg(1, 0, 0));
+        assert_eq!(
+            r.observe(&msg(1, 1, 5000)),
+            Some(Migration {
+                sys_id: 1,
+                from: 0,
+                to: 1
+            })
+        );
+        assert_eq!(r.observe(&msg(1, 1, 6000)), None);
+        assert!(r.systems_on(0, at(6000)).is_empty());
+    }
+}
@@ -0,0 +1,411 @@
+use std::collections::VecDeque;
+
+use chrono::{DateTime, Utc};
+use mavlink::common::{MavCmd, MavFrame, MavMessage, MavResult};
+
+use crate::{entries::Report, message::MavMsg};
+
+/// Requests and area changes kept for the Safety panel.
+const KEPT: usize = 16;
+
+/// How an arm authorization request was answered.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArmOutcome {
+    Pending,
+    /// Authorized for this many seconds (0 if the authorizer did not say).
+    Granted {
+        valid_secs: u8,
+    },
+    Denied {
+        reason: &'static str,
+    },
+    /// Any other COMMAND_ACK result, e.g. temporarily rejected.
+    Other(MavResult),
+}
+
+/// `MAV_CMD_ARM_AUTHORIZATION_REQUEST` from a vehicle and its answer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArmRequest {
+    pub vehicle: u8,
+    /// Who was asked, `(system, component)`; 0 means broadcast.
+    pub authorizer: (u8, u8),
+    pub requested: DateTime<Utc>,
+    pub outcome: ArmOutcome,
+}
+
+/// Whether a vehicle reports the allowed area it was sent.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AreaStatus {
+    Pending,
+    Confirmed,
+    Differs,
+}
+
+/// A box as sent in SAFETY_SET_ALLOWED_AREA or reported in
+/// SAFETY_ALLOWED_AREA.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Area {
+    pub frame: MavFrame,
+    pub p1: [f32; 3],
+    pub p2: [f32; 3],
+}
+
+impl Area {
+    fn matches(&self, other: &Area) -> bool {
+        self.frame == other.frame
+            && self
+                .p1
+                .iter()
+                .chain(&self.p2)
+                .zip(other.p1.iter().chain(&other.p2))
+                .all(|(a, b)| (a - b).abs() <= 1e-4 * a.abs().max(1.0))
+    }
+
+    pub fn describe(&self) -> String {
+        let [x1, y1, z1] = self.p1;
+        let [x2, y2, z2] = self.p2;
+        let frame = format!("{:?}", self.frame);
+        let frame = frame.strip_prefix("MAV_FRAME_").unwrap_or(&frame);
+        format!("{frame} ({x1}, {y1}, {z1}) to ({x2}, {y2}, {z2})")
+    }
+}
+
+/// SAFETY_SET_ALLOWED_AREA sent to a vehicle.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AreaRequest {
+    pub sender: u8,
+    pub target: u8,
+    pub sent: DateTime<Utc>,
+    pub area: Area,
+    pub status: AreaStatus,
+}
+
+/// An outcome worth raising as an event.
+#[derive(Debug, PartialEq)]
+pub enum SafetyReport {
+    Arm(ArmRequest),
+    Area {
+        request: AreaRequest,
+        reported: Area,
+    },
+}
+
+impl Report for SafetyReport {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Arm(r) => match r.outcome {
+                ArmOutcome::Granted { .. } => "ARM_AUTH_GRANTED",
+                ArmOutcome::Denied { .. } => "ARM_AUTH_DENIED",
+                _ => "ARM_AUTH_REJECTED",
+            },
+            Self::Area { request, .. } => match request.status {
+                AreaStatus::Differs => "SAFETY_AREA_DIFFERS",
+                _ => "SAFETY_AREA_CONFIRMED",
+            },
+        }
+    }
+
+    /// Denials and areas the vehicle did not take are alerts.
+    fn is_alert(&self) -> bool {
+        match self {
+            Self::Arm(r) => !matches!(r.outcome, ArmOutcome::Granted { .. }),
+            Self::Area { request, .. } => request.status == AreaStatus::Differs,
+        }
+    }
+
+    /// `(sys_id, comp_id)` the event is shown under.
+    fn source(&self) -> (u8, u8) {
+        match self {
+            Self::Arm(r) => (r.vehicle, 0),
+            Self::Area { request, .. } => (request.target, 0),
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Self::Arm(r) => format!(
+                "vehicle: {}, authorizer: {}:{}, {}",
+                r.vehicle,
+                r.authorizer.0,
+                r.authorizer.1,
+                describe_outcome(r.outcome)
+            ),
+            Self::Area { request, reported } => format!(
+                "vehicle: {}, sent: {}, reported: {}",
+                request.target,
+                request.area.describe(),
+                reported.describe()
+            ),
+        }
+    }
+}
+
+pub fn describe_outcome(outcome: ArmOutcome) -> String {
+    match outcome {
+        ArmOutcome::Pending => "pending".to_string(),
+        ArmOutcome::Granted { valid_secs: 0 } => "granted".to_string(),
+        ArmOutcome::Granted { valid_secs } => format!("granted for {valid_secs}s"),
+        ArmOutcome::Denied { reason } => format!("denied: {reason}"),
+        ArmOutcome::Other(result) => format!("{result:?}"),
+    }
+}
+
+/// Follows arm authorization (MAV_CMD_ARM_AUTHORIZATION_REQUEST answered by
+/// COMMAND_ACK) and allowed-area changes (SAFETY_SET_ALLOWED_AREA confirmed
+/// by SAFETY_ALLOWED_AREA) for the Safety panel.
+#[derive(Default)]
+pub struct SafetyTracker {
+    arm: VecDeque<ArmRequest>,
+    areas: VecDeque<AreaRequest>,
+    /// Last area each vehicle reported.
+    reported: Vec<(u8, Area, DateTime<Utc>)>,
+}
+
+impl SafetyTracker {
+    pub fn observe(&mut self, msg: &MavMsg) -> Option<SafetyReport> {
+        let sys = msg.header.system_id;
+        match &msg.msg {
+            MavMessage::COMMAND_LONG(d)
+                if d.command == MavCmd::MAV_CMD_ARM_AUTHORIZATION_REQUEST =>
+            {
+                push_bounded(
+                    &mut self.arm,
+                    ArmRequest {
+                        vehicle: sys,
+                        authorizer: (d.target_system, d.target_component),
+                        requested: msg.timestamp,
+                        outcome: ArmOutcome::Pending,
+                    },
+                );
+                None
+            }
+            MavMessage::COMMAND_ACK(d)
+                if d.command == MavCmd::MAV_CMD_ARM_AUTHORIZATION_REQUEST =>
+            {
+                let from = (sys, msg.header.component_id);
+                let request = self.arm.iter_mut().rev().find(|r| {
+                    r.outcome == ArmOutcome::Pending
+                        && (r.authorizer.0 == 0 || r.authorizer.0 == from.0)
+                        && (r.authorizer.1 == 0 || r.authorizer.1 == from.1)
+                })?;
+                let (progress, result_param2) = ack_extensions(&msg.payload);
+                request.outcome = match d.result {
+                    MavResult::MAV_RESULT_ACCEPTED => ArmOutcome::Granted {
+                        valid_secs: progress,
+                    },
+                    MavResult::MAV_RESULT_DENIED => ArmOutcome::Denied {
+                        reason: denied_reason(result_param2),
+                    },
+                    other => ArmOutcome::Other(other),
+                };
+                Some(SafetyReport::Arm(request.clone()))
+            }
+            MavMessage::SAFETY_SET_ALLOWED_AREA(d) => {
+                push_bounded(
+                    &mut self.areas,
+                    AreaRequest {
+                        sender: sys,
+                        target: d.target_system,
+                        sent: msg.timestamp,
+                        area: Area {
+                            frame: d.frame,
+                            p1: [d.p1x, d.p1y, d.p1z],
+                            p2: [d.p2x, d.p2y, d.p2z],
+                        },
+                        status: AreaStatus::Pending,
+                    },
+                );
+                None
+            }
+            MavMessage::SAFETY_ALLOWED_AREA(d) => {
+                let reported = Area {
+                    frame: d.frame,
+                    p1: [d.p1x, d.p1y, d.p1z],
+                    p2: [d.p2x, d.p2y, d.p2z],
+                };
+                self.reported.retain(|(s, ..)| *s != sys);
+                self.reported.push((sys, reported, msg.timestamp));
+                let request = self.areas.iter_mut().rev().find(|r| {
+                    r.status == AreaStatus::Pending && (r.target == 0 || r.target == sys)
+                })?;
+                request.status = if request.area.matches(&reported) {
+                    AreaStatus::Confirmed
+                } else {
+                    AreaStatus::Differs
+                };
+                Some(SafetyReport::Area {
+                    request: request.clone(),
+                    reported,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Arm authorization requests, oldest first.
+    pub fn arm_requests(&self) -> &VecDeque<ArmRequest> {
+        &self.arm
+    }
+
+    /// Allowed areas sent, oldest first.
+    pub fn area_requests(&self) -> &VecDeque<AreaRequest> {
+        &self.areas
+    }
+
+    /// The area each vehicle last reported.
+    pub fn reported_areas(&self) -> &[(u8, Area, DateTime<Utc>)] {
+        &self.reported
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+fn push_bounded<T>(list: &mut VecDeque<T>, item: T) {
+    if list.len() == KEPT {
+        list.pop_front();
+    }
+    list.push_back(item);
+}
+
+/// COMMAND_ACK `progress` and `result_param2`, read from the payload since
+/// they are MAVLink 2 extensions; zero when the sender truncated them.
+fn ack_extensions(payload: &[u8]) -> (u8, i32) {
+    let byte = |i: usize| payload.get(i).copied().unwrap_or(0);
+    let progress = byte(3);
+    let result_param2 = i32::from_le_bytes([byte(4), byte(5), byte(6), byte(7)]);
+    (progress, result_param2)
+}
+
+fn denied_reason(code: i32) -> &'static str {
+    match code {
+        1 => "none given",
+        2 => "invalid waypoint",
+        3 => "timeout",
+        4 => "airspace in use",
+        5 => "bad weather",
+        _ => "generic",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::{
+        MavHeader,
+        common::{
+            COMMAND_ACK_DATA, COMMAND_LONG_DATA, SAFETY_ALLOWED_AREA_DATA,
+            SAFETY_SET_ALLOWED_AREA_DATA,
+        },
+    };
+
+    use super::*;
+
+    fn msg(sys_id: u8, comp_id: u8, msg: MavMessage) -> MavMsg {
+        MavMsg::new(
+            MavHeader {
+                system_id: sys_id,
+                component_id: comp_id,
+                sequence: 0,
+            },
+            msg,
+            0,
+        )
+    }
+
+    fn request() -> MavMsg {
+        msg(
+            1,
+            1,
+            MavMessage::COMMAND_LONG(COMMAND_LONG_DATA {
+                command: MavCmd::MAV_CMD_ARM_AUTHORIZATION_REQUEST,
+                param1: 1.0,
+                target_system: 200,
+                target_component: 190,
+                ..Default::default()
+            }),
+        )
+    }
+
+    fn ack(result: MavResult, progress: u8, reason: i32) -> MavMsg {
+        let mut ack = msg(
+            200,
+            190,
+            MavMessage::COMMAND_ACK(COMMAND_ACK_DATA {
+                command: MavCmd::MAV_CMD_ARM_AUTHORIZATION_REQUEST,
+                result,
+            }),
+        );
+        ack.payload.resize(3, 0);
+        ack.payload.push(progress);
+        ack.payload.extend(reason.to_le_bytes());
+        ack
+    }
+
+    #[test]
+    fn arm_authorization_outcomes() {
+        let mut tracker = SafetyTracker::default();
+        assert_eq!(tracker.observe(&request()), None);
+        assert_eq!(tracker.arm_requests()[0].outcome, ArmOutcome::Pending,);
+        let report = tracker
+            .observe(&ack(MavResult::MAV_RESULT_DENIED, 0, 4))
+            .unwrap();
+        assert_eq!(report.name(), "ARM_AUTH_DENIED");
+        assert!(report.is_alert());
+        assert!(report.describe().contains("airspace in use"));
+
+        tracker.observe(&request());
+        let report = tracker
+            .observe(&ack(MavResult::MAV_RESULT_ACCEPTED, 60, 0))
+            .unwrap();
+        assert_eq!(report.name(), "ARM_AUTH_GRANTED");
+        assert!(!report.is_alert());
+        assert_eq!(
+            tracker.arm_requests()[1].outcome,
+            ArmOutcome::Granted { valid_secs: 60 }
+        );
+        // Nothing left to answer.
+        assert_eq!(
+            tracker.observe(&ack(MavResult::MAV_RESULT_ACCEPTED, 60, 0)),
+            None
+        );
+    }
+
+    #[test]
+    fn allowed_area_is_confirmed_or_differs() {
+        let set = |p2x| {
+            msg(
+                255,
+                190,
+                MavMessage::SAFETY_SET_ALLOWED_AREA(SAFETY_SET_ALLOWED_AREA_DATA {
+                    p1x: 47.0,
+                    p2x,
+                    target_system: 1,
+                    ..Default::default()
+                }),
+            )
+        };
+        let reported = |p2x| {
+            msg(
+                1,
+                1,
+                MavMessage::SAFETY_ALLOWED_AREA(SAFETY_ALLOWED_AREA_DATA {
+                    p1x: 47.0,
+                    p2x,
+                    ..Default::default()
+                }),
+            )
+        };
+        let mut tracker = SafetyTracker::default();
+        tracker.observe(&set(48.0));
+        let report = tracker.observe(&reported(48.0)).unwrap();
+        assert_eq!(report.name(), "SAFETY_AREA_CONFIRMED");
+
+        tracker.observe(&set(49.0));
+        let report = tracker.observe(&reported(48.0)).unwrap();
+        assert_eq!(report.name(), "SAFETY_AREA_DIFFERS");
+        assert!(report.is_alert());
+        assert_eq!(tracker.reported_areas().len(), 1);
+        assert_eq!(tracker.area_requests()[1].status, AreaStatus::Differs);
+    }
+}
@@ -0,0 +1,354 @@
+use std::{
+    io,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use mavlink::{
+    MavlinkVersion, Message,
+    common::{MavFrame, MavMessage},
+};
+
+use crate::{
+    message::{Incoming, MavMsg},
+    session::{self, SessionMeta},
+};
+
+const MAX_LAT: i64 = 900_000_000;
+const FULL_TURN: i64 = 3_600_000_000;
+
+/// Makes a capture safe to attach to a public bug report: positions are
+/// moved by one fixed, random offset (so tracks, distances and fix states
+/// survive but not where they were), and serial numbers, operator and
+/// aircraft IDs, SIM PINs and link addresses are blanked. Frame errors and
+/// messages only a dialect extension decodes lose their fields. Timing, IDs,
+/// sequence numbers and every other field are left alone.
+pub struct Sanitizer {
+    /// Offsets in degrees * 1e7.
+    lat: i64,
+    lon: i64,
+}
+
+impl Sanitizer {
+    pub fn new(seed: u64) -> Self {
+        let a = splitmix(seed);
+        let b = splitmix(a);
+        // 10-40 degrees either way, so the shift is never negligible.
+        let offset = |r: u64, sign: u64| {
+            let deg = 100_000_000 + (r % 300_000_000) as i64;
+            if sign & 1 == 0 { deg } else { -deg }
+        };
+        Self {
+            lat: offset(a, b >> 32),
+            lon: offset(b, a >> 32),
+        }
+    }
+
+    /// A sanitizer with an offset nobody can reconstruct from the export.
+    pub fn random() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64);
+        Self::new(nanos ^ u64::from(std::process::id()) << 32)
+    }
+
+    pub fn apply(&self, incoming: &mut Incoming) {
+        match incoming {
+            Incoming::Message(m) => {
+                if self.message(&mut m.msg) {
+                    reencode(m);
+                }
+            }
+            // Messages decoded with `--dialect` or `--definitions` carry their
+            // fields as text, and frame errors a hex dump of the frame, so
+            // either may hold a position. Only the name and header are kept.
+            Incoming::Diagnostic(d) if d.is_error() || !d.is_hint() => {
+                d.fields.clear();
+                d.frame.clear();
+            }
+            Incoming::Diagnostic(_) => {}
+        }
+    }
+
+    /// Rewrites sensitive fields in place; `true` if it changed anything.
+    #[allow(deprecated)] // older autopilots still send MISSION_ITEM and friends
+    fn message(&self, msg: &mut MavMessage) -> bool {
+        match msg {
+            MavMessage::GLOBAL_POSITION_INT(d) => self.shift(&mut d.lat, &mut d.lon),
+            MavMessage::GPS_RAW_INT(d) => self.shift(&mut d.lat, &mut d.lon),
+            MavMessage::GPS2_RAW(d) => self.shift(&mut d.lat, &mut d.lon),
+            MavMessage::GPS_INPUT(d) => self.shift(&mut d.lat, &mut d.lon),
+            MavMessage::HOME_POSITION(d) => self.shift(&mut d.latitude, &mut d.longitude),
+            MavMessage::GPS_GLOBAL_ORIGIN(d) => self.shift(&mut d.latitude, &mut d.longitude),
+            MavMessage::SET_GPS_GLOBAL_ORIGIN(d) => self.shift(&mut d.latitude, &mut d.longitude),
+            MavMessage::HIGH_LATENCY2(d) => self.shift(&mut d.latitude, &mut d.longitude),
+            MavMessage::FOLLOW_TARGET(d) => self.shift(&mut d.lat, &mut d.lon),
+            MavMessage::CAMERA_IMAGE_CAPTURED(d) => self.shift(&mut d.lat, &mut d.lon),
+            MavMessage::POSITION_TARGET_GLOBAL_INT(d) => self.shift(&mut d.lat_int, &mut d.lon_int),
+            MavMessage::SET_POSITION_TARGET_GLOBAL_INT(d) => {
+                self.shift(&mut d.lat_int, &mut d.lon_int)
+            }
+            MavMessage::MISSION_ITEM_INT(d) if is_global(d.frame) => self.shift(&mut d.x, &mut d.y),
+            MavMessage::COMMAND_INT(d) if is_global(d.frame) => self.shift(&mut d.x, &mut d.y),
+            MavMessage::MISSION_ITEM(d) if is_global(d.frame) => {
+                self.shift_degrees(&mut d.x, &mut d.y)
+            }
+            MavMessage::ADSB_VEHICLE(d) => {
+                self.shift(&mut d.lat, &mut d.lon);
+                d.ICAO_address = 0;
+                d.callsign = [0; 9].into();
+                true
+            }
+            MavMessage::OPEN_DRONE_ID_LOCATION(d) => {
+                self.shift(&mut d.latitude, &mut d.longitude);
+                d.id_or_mac = [0; 20];
+                true
+            }
+            MavMessage::OPEN_DRONE_ID_SYSTEM(d) => {
+                self.shift(&mut d.operator_latitude, &mut d.operator_longitude);
+                d.id_or_mac = [0; 20];
+                true
+            }
+            MavMessage::OPEN_DRONE_ID_BASIC_ID(d) => {
+                d.id_or_mac = [0; 20];
+                d.uas_id = [0; 20];
+                true
+            }
+            MavMessage::OPEN_DRONE_ID_OPERATOR_ID(d) => {
+                d.id_or_mac = [0; 20];
+                d.operator_id = [0; 20].into();
+                true
+            }
+            MavMessage::CELLULAR_CONFIG(d) => {
+                d.pin = [0; 16].into();
+                d.new_pin = [0; 16].into();
+                d.puk = [0; 16].into();
+                true
+            }
+            MavMessage::AUTOPILOT_VERSION(d) => {
+                d.uid = 0;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Moves a degE7 position; 0,0 means "no fix" and is kept as such.
+    fn shift(&self, lat: &mut i32, lon: &mut i32) -> bool {
+        if *lat == 0 && *lon == 0 {
+            return false;
+        }
+        *lat = (i64::from(*lat) + self.lat).clamp(-MAX_LAT, MAX_LAT) as i32;
+        *lon = ((i64::from(*lon) + self.lon + FULL_TURN / 2).rem_euclid(FULL_TURN) - FULL_TURN / 2)
+            as i32;
+        true
+    }
+
+    fn shift_degrees(&self, lat: &mut f32, lon: &mut f32) -> bool {
+        let mut lat_e7 = (*lat * 1e7) as i32;
+        let mut lon_e7 = (*lon * 1e7) as i32;
+        let changed = self.shift(&mut lat_e7, &mut lon_e7);
+        *lat = lat_e7 as f32 / 1e7;
+        *lon = lon_e7 as f32 / 1e7;
+        changed
+    }
+
+    /// Link names without hosts, ports, devices or file paths.
+    pub fn meta(&self, meta: SessionMeta) -> SessionMeta {
+        let links = meta
+            .links
+            .iter()
+            .enumerate()
+            .map(|(i, link)| match link.split_once(':') {
+                Some((scheme, _)) if scheme.chars().all(|c| c.is_ascii_alphabetic()) => {
+                    format!("{scheme}:redacted")
+                }
+                _ => format!("link{i}"),
+            })
+            .collect();
+        SessionMeta { links, ..meta }
+    }
+}
+
+#[allow(deprecated)]
+fn is_global(frame: MavFrame) -> bool {
+    matches!(
+        frame,
+        MavFrame::MAV_FRAME_GLOBAL
+            | MavFrame::MAV_FRAME_GLOBAL_RELATIVE_ALT
+            | MavFrame::MAV_FRAME_GLOBAL_TERRAIN_ALT
+            | MavFrame::MAV_FRAME_GLOBAL_INT
+            | MavFrame::MAV_FRAME_GLOBAL_RELATIVE_ALT_INT
+            | MavFrame::MAV_FRAME_GLOBAL_TERRAIN_ALT_INT
+    )
+}
+
+/// Re-encodes the payload after its message changed, keeping the original
+/// protocol version so frame sizes stay comparable.
+fn reencode(m: &mut MavMsg) {
+    let overhead = m.len - m.payload.len();
+    // MAVLink 1: 6 byte header and 2 byte checksum, no truncation.
+    let version = if overhead == 8 {
+        MavlinkVersion::V1
+    } else {
+        MavlinkVersion::V2
+    };
+    let mut payload = [0u8; 255];
+    let len = m.msg.ser(version, &mut payload);
+    m.payload = payload[..len].to_vec();
+    m.len = overhead + len;
+}
+
+fn splitmix(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Writes `log` (records as kept for [`session::save`]) to `path` with
+/// every record passed through `sanitizer`.
+pub fn export(
+    path: &Path,
+    meta: SessionMeta,
+    mut log: &[u8],
+    sanitizer: &Sanitizer,
+) -> io::Result<()> {
+    let mut out = Vec::with_capacity(log.len());
+    while let Some((at, mut incoming)) = session::read_record(&mut log)? {
+        sanitizer.apply(&mut incoming);
+        session::write_record(&mut out, at, &incoming)?;
+    }
+    session::save(path, &sanitizer.meta(meta), &out)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use mavlink::{
+        MAVLinkV2MessageRaw, MavHeader,
+        common::{AUTOPILOT_VERSION_DATA, GLOBAL_POSITION_INT_DATA, GPS_RAW_INT_DATA},
+    };
+
+    use super::*;
+    use crate::{connection, frame::FrameParser};
+
+    fn position(lat: i32, lon: i32) -> Incoming {
+        Incoming::Message(MavMsg::new(
+            MavHeader::default(),
+            MavMessage::GLOBAL_POSITION_INT(GLOBAL_POSITION_INT_DATA {
+                lat,
+                lon,
+                alt: 488_000,
+                ..Default::default()
+            }),
+            0,
+        ))
+    }
+
+    fn lat_lon(incoming: &Incoming) -> (i32, i32) {
+        let Incoming::Message(m) = incoming else {
+            panic!("expected a message");
+        };
+        // Decode the payload so the re-encoding is checked too.
+        match MavMessage::parse(MavlinkVersion::V2, m.msg.message_id(), &m.payload).unwrap() {
+            MavMessage::GLOBAL_POSITION_INT(d) => (d.lat, d.lon),
+            _ => panic!("expected GLOBAL_POSITION_INT"),
+        }
+    }
+
+    #[test]
+    fn positions_move_together() {
+        let sanitizer = Sanitizer::new(7);
+        let mut a = position(473_977_420, 85_455_940);
+        let mut b = position(473_987_420, 85_465_940);
+        sanitizer.apply(&mut a);
+        sanitizer.apply(&mut b);
+        let (a, b) = (lat_lon(&a), lat_lon(&b));
+        assert!((a.0 - 473_977_420).abs() >= 100_000_000);
+        assert_eq!((b.0 - a.0, b.1 - a.1), (10_000, 10_000));
+    }
+
+    #[test]
+    fn no_fix_and_unrelated_fields_survive() {
+        let sanitizer = Sanitizer::new(1);
+        let mut none = position(0, 0);
+        sanitizer.apply(&mut none);
+        assert_eq!(lat_lon(&none), (0, 0));
+
+        let mut gps = MavMessage::GPS_RAW_INT(GPS_RAW_INT_DATA {
+            lat: 899_999_999,
+            lon: 1_799_999_999,
+            satellites_visible: 12,
+            ..Default::default()
+        });
+        assert!(sanitizer.message(&mut gps));
+        let MavMessage::GPS_RAW_INT(d) = gps else {
+            unreachable!()
+        };
+        assert!((-MAX_LAT as i32..=MAX_LAT as i32).contains(&d.lat));
+        assert_eq!(d.satellites_visible, 12);
+
+        let mut version = MavMessage::AUTOPILOT_VERSION(AUTOPILOT_VERSION_DATA {
+            uid: 0xDEAD_BEEF,
+            flight_sw_version: 42,
+            ..Default::default()
+        });
+        sanitizer.message(&mut version);
+        let MavMessage::AUTOPILOT_VERSION(d) = version else {
+            unreachable!()
+        };
+        assert_eq!((d.uid, d.flight_sw_version), (0, 42));
+    }
+
+    #[test]
+    fn link_addresses_are_redacted() {
+        let meta = SessionMeta {
+            links: vec![
+                "tcpout:10.0.0.5:5760".to_string(),
+                "/home/pilot/flight.tlog (+1.000s)".to_string(),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(
+            Sanitizer::new(0).meta(meta).links,
+            ["tcpout:redacted", "link1"]
+        );
+    }
+
+    #[test]
+    fn bad_crc_frames_lose_their_bytes() {
+        let Incoming::Message(m) = position(473_977_420, 85_455_940) else {
+            unreachable!()
+        };
+        let mut raw = MAVLinkV2MessageRaw::new();
+        raw.serialize_message(m.header, &m.msg);
+        let mut bytes = raw.raw_bytes().to_vec();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xFF;
+        let mut parser = FrameParser::new();
+        parser.push(&bytes);
+        // A good frame after it, so the parser knows the bad one ended.
+        parser.push(raw.raw_bytes());
+        let error = connection::decode(parser.next_frame().unwrap(), 0);
+        let Incoming::Diagnostic(d) = &error else {
+            panic!("expected a diagnostic");
+        };
+        assert_eq!(d.name, "CRC_ERROR");
+        assert!(d.fields.contains("bytes: fd"));
+
+        let mut log = Vec::new();
+        session::write_record(&mut log, Utc::now(), &error).unwrap();
+        let path =
+            std::env::temp_dir().join(format!("mavsnark-sanitize-{}.session", std::process::id()));
+        export(&path, SessionMeta::default(), &log, &Sanitizer::new(3)).unwrap();
+        let session = session::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let [(_, Incoming::Diagnostic(d))] = &session.records[..] else {
+            panic!("expected one diagnostic");
+        };
+        assert_eq!(d.name, "CRC_ERROR");
+        assert_eq!(d.header.system_id, m.header.system_id);
+        assert!(d.fields.is_empty());
+    }
+}
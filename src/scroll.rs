@@ -47,6 +47,17 @@ impl ScrollState {
         self.offset = total.saturating_sub(visible);
     }
 
+    /// Selects `index`, scrolling just enough to bring it into view.
+    pub(crate) fn select(&mut self, index: usize, visible: usize) {
+        self.auto_scroll = false;
+        self.selected = index;
+        if index < self.offset {
+            self.offset = index;
+        } else if index >= self.offset + visible {
+            self.offset = index.saturating_sub(visible.saturating_sub(1));
+        }
+    }
+
     pub(crate) fn clamp(&mut self, total: usize, visible: usize) {
         if total == 0 {
             self.selected = 0;
@@ -137,4 +148,15 @@ mod tests {
         assert_eq!(s.selected, 0);
         assert_eq!(s.offset, 0);
     }
+
+    #[test]
+    fn select_brings_row_into_view() {
+        let mut s = ScrollState::new();
+        s.select(8, 3);
+        assert_eq!(s.selected, 8);
+        assert_eq!(s.offset, 6);
+        assert!(!s.auto_scroll);
+        s.select(2, 3);
+        assert_eq!(s.offset, 2);
+    }
 }
@@ -0,0 +1,153 @@
+use std::collections::VecDeque;
+
+use chrono::{DateTime, Utc};
+
+use crate::entries::{MessageEntry, StreamEntry};
+
+/// Hits past this many are dropped, oldest first.
+pub const MAX_HITS: usize = 1000;
+
+/// Which panel a hit lives in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Target {
+    /// A stream row's value at the hit's time; `latest` when that is still
+    /// its current value.
+    Stream {
+        latest: bool,
+    },
+    Event,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hit {
+    pub timestamp: DateTime<Utc>,
+    pub sys_id: u8,
+    pub comp_id: u8,
+    pub name: &'static str,
+    pub fields: String,
+    pub target: Target,
+}
+
+/// Every stream value still in a row's history and every event the
+/// collector holds whose name or fields contain `query`, ignoring case and
+/// the panel filters; oldest first.
+pub fn search(stream: &[StreamEntry], events: &VecDeque<MessageEntry>, query: &str) -> Vec<Hit> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let matches = |name: &str, fields: &str| {
+        name.to_lowercase().contains(&query) || fields.to_lowercase().contains(&query)
+    };
+    let mut hits = Vec::new();
+    for entry in stream {
+        let values = entry
+            .history
+            .iter()
+            .map(|s| (s.timestamp, s.fields.as_str(), false))
+            .chain([(entry.timestamp, entry.fields.as_str(), true)]);
+        for (at, fields, latest) in values {
+            if matches(entry.name, fields) {
+                hits.push(Hit {
+                    timestamp: at,
+                    sys_id: entry.sys_id,
+                    comp_id: entry.comp_id,
+                    name: entry.name,
+                    fields: fields.to_string(),
+                    target: Target::Stream { latest },
+                });
+            }
+        }
+    }
+    for entry in events {
+        if matches(entry.name, &entry.fields) {
+            hits.push(Hit {
+                timestamp: entry.timestamp,
+                sys_id: entry.sys_id,
+                comp_id: entry.comp_id,
+                name: entry.name,
+                fields: entry.fields.clone(),
+                target: Target::Event,
+            });
+        }
+    }
+    hits.sort_by_key(|h| h.timestamp);
+    if hits.len() > MAX_HITS {
+        hits.drain(..hits.len() - MAX_HITS);
+    }
+    hits
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeDelta;
+    use ratatui::style::Color;
+
+    use super::*;
+    use crate::{
+        direction::Direction,
+        entries::{EventKind, Sample},
+        rate::ArrivalStats,
+        signing::SignatureStatus,
+    };
+
+    #[test]
+    fn finds_history_and_events_in_order() {
+        let t0 = Utc::now();
+        let at = |s| t0 + TimeDelta::seconds(s);
+        let stream = [StreamEntry {
+            sys_color: Color::Red,
+            comp_color: Color::Cyan,
+            msg_color: None,
+            sys_id: 1,
+            comp_id: 1,
+            name: "GPS_RAW_INT",
+            fields: "fix_type: GPS_FIX_TYPE_3D_FIX".to_string(),
+            v1: false,
+            signature: SignatureStatus::Unsigned,
+            timestamp: at(3),
+            link: 0,
+            arrivals: ArrivalStats::default(),
+            direction: Direction::Downlink,
+            payload: Vec::new(),
+            frame: Vec::new(),
+            history: VecDeque::from([Sample {
+                timestamp: at(1),
+                fields: "fix_type: GPS_FIX_TYPE_NO_FIX".to_string(),
+                payload: Vec::new(),
+                frame: Vec::new(),
+            }]),
+        }];
+        let event = MessageEntry {
+            sys_color: Color::Red,
+            comp_color: Color::Cyan,
+            msg_color: None,
+            sys_id: 1,
+            comp_id: 1,
+            name: "STATUSTEXT",
+            fields: "text: \"GPS 1: no fix\"".to_string(),
+            link: 0,
+            duplicate: false,
+            kind: EventKind::Message,
+            direction: None,
+            payload: Vec::new(),
+            frame: Vec::new(),
+            v1: false,
+            signature: SignatureStatus::Unsigned,
+            timestamp: at(2),
+        };
+        let events = VecDeque::from([event]);
+
+        let hits = search(&stream, &events, "No_Fix");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].timestamp, at(1));
+        assert_eq!(hits[0].target, Target::Stream { latest: false });
+
+        let hits = search(&stream, &events, "gps");
+        let names: Vec<_> = hits.iter().map(|h| h.name).collect();
+        assert_eq!(names, ["GPS_RAW_INT", "STATUSTEXT", "GPS_RAW_INT"]);
+        assert_eq!(hits[1].target, Target::Event);
+        assert_eq!(hits[2].target, Target::Stream { latest: true });
+        assert!(search(&stream, &events, "  ").is_empty());
+    }
+}
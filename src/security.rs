@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+
+use crate::message::MavMsg;
+
+/// Forward jump (in 10 µs signature units) treated as suspicious: one minute,
+/// the same tolerance the MAVLink signing spec allows against the local clock.
+const MAX_JUMP: u64 = 60 * 100_000;
+
+#[derive(Debug, PartialEq)]
+pub enum SigningAnomaly {
+    /// Timestamp did not increase: replayed or reordered traffic.
+    Regression {
+        link_id: u8,
+        previous: u64,
+        current: u64,
+    },
+    /// Timestamp leapt forward further than any real clock would.
+    Jump {
+        link_id: u8,
+        previous: u64,
+        current: u64,
+    },
+}
+
+impl SigningAnomaly {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Regression { .. } => "SIGNING_TIMESTAMP_REGRESSION",
+            Self::Jump { .. } => "SIGNING_TIMESTAMP_JUMP",
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match *self {
+            Self::Regression {
+                link_id,
+                previous,
+                current,
+            } => format!(
+                "link_id: {link_id}, previous: {previous}, current: {current}, delta_ms: -{:.2}",
+                (previous - current) as f64 / 100.0
+            ),
+            Self::Jump {
+                link_id,
+                previous,
+                current,
+            } => format!(
+                "link_id: {link_id}, previous: {previous}, current: {current}, delta_ms: {:.2}",
+                (current - previous) as f64 / 100.0
+            ),
+        }
+    }
+}
+
+/// Tracks signature timestamps per `(sys_id, comp_id, link_id)` signing stream.
+pub struct SigningMonitor {
+    last: HashMap<(u8, u8, u8), u64>,
+}
+
+impl Default for SigningMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SigningMonitor {
+    pub fn new() -> Self {
+        Self {
+            last: HashMap::new(),
+        }
+    }
+
+    pub fn observe(&mut self, msg: &MavMsg) -> Option<SigningAnomaly> {
+        let sig = msg.signature?;
+        let key = (msg.header.system_id, msg.header.component_id, sig.link_id);
+        let Some(&previous) = self.last.get(&key) else {
+            self.last.insert(key, sig.timestamp);
+            return None;
+        };
+        if sig.timestamp <= previous {
+            // Keep the high-water mark so a replay doesn't hide the next one.
+            return Some(SigningAnomaly::Regression {
+                link_id: sig.link_id,
+                previous,
+                current: sig.timestamp,
+            });
+        }
+        self.last.insert(key, sig.timestamp);
+        if sig.timestamp - previous > MAX_JUMP {
+            return Some(SigningAnomaly::Jump {
+                link_id: sig.link_id,
+                previous,
+                current: sig.timestamp,
+            });
+        }
+        None
+    }
+
+    pub fn clear(&mut self) {
+        self.last.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::{
+        MavHeader,
+        common::{HEARTBEAT_DATA, MavMessage},
+    };
+
+    use super::*;
+    use crate::message::Signature;
+
+    fn signed(link_id: u8, timestamp: u64) -> MavMsg {
+        let mut msg = MavMsg::new(
+            MavHeader {
+                system_id: 1,
+                component_id: 1,
+                sequence: 0,
+            },
+            MavMessage::HEARTBEAT(HEARTBEAT_DATA::default()),
+            0,
+        );
+        msg.signature = Some(Signature {
+            link_id,
+            timestamp,
+            verified: None,
+        });
+        msg
+    }
+
+    #[test]
+    fn unsigned_is_ignored() {
+        let mut m = SigningMonitor::new();
+        let mut msg = signed(0, 0);
+        msg.signature = None;
+        assert_eq!(m.observe(&msg), None);
+    }
+
+    #[test]
+    fn increasing_timestamps_are_fine() {
+        let mut m = SigningMonitor::new();
+        assert_eq!(m.observe(&signed(0, 1000)), None);
+        assert_eq!(m.observe(&signed(0, 1001)), None);
+    }
+
+    #[test]
+    fn regression_is_flagged() {
+        let mut m = SigningMonitor::new();
+        m.observe(&signed(0, 1000));
+        assert_eq!(
+            m.observe(&signed(0, 1000)),
+            Some(SigningAnomaly::Regression {
+                link_id: 0,
+                previous: 1000,
+                current: 1000
+            })
+        );
+    }
+
+    #[test]
+    fn regression_keeps_high_water_mark() {
+        let mut m = SigningMonitor::new();
+        m.observe(&signed(0, 1000));
+        m.observe(&signed(0, 500));
+        assert!(m.observe(&signed(0, 900)).is_some());
+    }
+
+    #[test]
+    fn large_jump_is_flagged() {
+        let mut m = SigningMonitor::new();
+        m.observe(&signed(0, 1000));
+        assert!(matches!(
+            m.observe(&signed(0, 1000 + MAX_JUMP + 1)),
+            Some(SigningAnomaly::Jump { .. })
+        ));
+    }
+
+    #[test]
+    fn link_ids_are_tracked_separately() {
+        let mut m = SigningMonitor::new();
+        m.observe(&signed(0, 1000));
+        assert_eq!(m.observe(&signed(1, 10)), None);
+    }
+}
@@ -0,0 +1,454 @@
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{self, BufWriter, Read, Write},
+    path::Path,
+};
+
+use chrono::{DateTime, Utc};
+use mavlink::{MavHeader, MavlinkVersion, Message, common::MavMessage};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    compress,
+    frame::FrameErrorKind,
+    message::{Diagnostic, Incoming, MavMsg, Signature},
+    state::UiState,
+};
+
+const MAGIC: &[u8; 8] = b"MAVSNARK";
+const VERSION: u8 = 1;
+
+const KIND_MESSAGE: u8 = 0;
+const KIND_DIAGNOSTIC: u8 = 1;
+
+/// Signature flags of a message record; files written before signatures
+/// were checked only have `SIGNED`.
+const SIGNED: u8 = 1;
+const SIGNED_VALID: u8 = 2;
+const SIGNED_INVALID: u8 = 3;
+
+/// Everything about a session except the traffic itself.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionMeta {
+    pub links: Vec<String>,
+    /// Selected row per tab, in tab order.
+    pub selected: Vec<usize>,
+    pub ui: UiState,
+}
+
+/// A saved session: the UI state plus every frame received, in arrival
+/// order. Loading replays the frames, so all derived views come back too.
+///
+/// Layout: `MAVSNARK`, a version byte, a length-prefixed TOML
+/// [`SessionMeta`], then records as written by [`write_record`].
+pub struct Session {
+    pub meta: SessionMeta,
+    pub records: Vec<(DateTime<Utc>, Incoming)>,
+}
+
+/// What [`SessionLog`] keeps unless `--max-memory` says otherwise: a few
+/// hours of a busy telemetry link.
+pub const DEFAULT_LOG_BYTES: u64 = 256 * 1024 * 1024;
+
+/// Everything received, encoded for saving, with the oldest records dropped
+/// once it outgrows its cap ([`DEFAULT_LOG_BYTES`] or `--max-memory`).
+pub struct SessionLog {
+    bytes: VecDeque<u8>,
+    /// Encoded size of each record, oldest first.
+    sizes: VecDeque<usize>,
+    max_bytes: u64,
+    /// Records dropped since the last clear.
+    pub dropped: usize,
+}
+
+impl Default for SessionLog {
+    fn default() -> Self {
+        Self {
+            bytes: VecDeque::new(),
+            sizes: VecDeque::new(),
+            max_bytes: DEFAULT_LOG_BYTES,
+            dropped: 0,
+        }
+    }
+}
+
+impl SessionLog {
+    pub fn push(&mut self, at: DateTime<Utc>, incoming: &Incoming) {
+        let before = self.bytes.len();
+        // Writing to memory cannot fail.
+        let _ = write_record(&mut self.bytes, at, incoming);
+        self.sizes.push_back(self.bytes.len() - before);
+        self.trim_to(self.max_bytes);
+    }
+
+    /// Replaces the cap, dropping the oldest records if already over it.
+    pub fn set_max_bytes(&mut self, max: u64) {
+        self.max_bytes = max;
+        self.trim_to(max);
+    }
+
+    pub fn max_bytes(&self) -> u64 {
+        self.max_bytes
+    }
+
+    pub fn byte_len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Drops the oldest records until the log fits in `max` bytes.
+    pub fn trim_to(&mut self, max: u64) {
+        while self.bytes.len() as u64 > max {
+            let Some(size) = self.sizes.pop_front() else {
+                break;
+            };
+            self.bytes.drain(..size);
+            self.dropped += 1;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self {
+            max_bytes: self.max_bytes,
+            ..Self::default()
+        };
+    }
+
+    /// The records in order, as [`save`] takes them.
+    pub fn as_bytes(&mut self) -> &[u8] {
+        self.bytes.make_contiguous()
+    }
+}
+
+/// Appends one received item to a record log.
+pub fn write_record(
+    out: &mut impl Write,
+    at: DateTime<Utc>,
+    incoming: &Incoming,
+) -> io::Result<()> {
+    let (kind, header, link) = match incoming {
+        Incoming::Message(m) => (KIND_MESSAGE, m.header, m.link),
+        Incoming::Diagnostic(d) => (KIND_DIAGNOSTIC, d.header, d.link),
+    };
+    out.write_all(&[kind])?;
+    out.write_all(&at.timestamp_micros().to_le_bytes())?;
+    out.write_all(&(link as u16).to_le_bytes())?;
+    out.write_all(&[header.system_id, header.component_id, header.sequence])?;
+    match incoming {
+        Incoming::Message(m) => {
+            out.write_all(&m.msg.message_id().to_le_bytes())?;
+            out.write_all(&(m.len as u16).to_le_bytes())?;
+            match m.signature {
+                Some(sig) => {
+                    let flag = match sig.verified {
+                        None => SIGNED,
+                        Some(true) => SIGNED_VALID,
+                        Some(false) => SIGNED_INVALID,
+                    };
+                    out.write_all(&[flag, sig.link_id])?;
+                    out.write_all(&sig.timestamp.to_le_bytes())?;
+                }
+                None => out.write_all(&[0])?,
+            }
+            out.write_all(&[m.payload.len() as u8])?;
+            out.write_all(&m.payload)?;
+        }
+        Incoming::Diagnostic(d) => {
+            out.write_all(&[d.name.len() as u8])?;
+            out.write_all(d.name.as_bytes())?;
+            out.write_all(&(d.fields.len() as u32).to_le_bytes())?;
+            out.write_all(d.fields.as_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+fn read_array<const N: usize>(input: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut buf = [0u8; N];
+    input.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+fn read_bytes(input: &mut impl Read, len: usize) -> io::Result<Vec<u8>> {
+    let mut buf = vec![0u8; len];
+    input.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+fn invalid(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+/// Diagnostic names are `&'static str`; the known ones are reused rather
+/// than leaked.
+fn diagnostic_name(name: String) -> &'static str {
+    match name.as_str() {
+        "DIALECT_MISMATCH" => "DIALECT_MISMATCH",
+        "UNKNOWN_MESSAGE" => "UNKNOWN_MESSAGE",
+        _ => match FrameErrorKind::NAMES.into_iter().find(|&n| n == name) {
+            Some(known) => known,
+            None => Box::leak(name.into_boxed_str()),
+        },
+    }
+}
+
+/// Reads the next record, or `None` at a clean end of input.
+pub fn read_record(input: &mut impl Read) -> io::Result<Option<(DateTime<Utc>, Incoming)>> {
+    let mut kind = [0u8; 1];
+    if input.read(&mut kind)? == 0 {
+        return Ok(None);
+    }
+    let micros = i64::from_le_bytes(read_array(input)?);
+    let at = DateTime::from_timestamp_micros(micros).ok_or_else(|| invalid("bad timestamp"))?;
+    let link = u16::from_le_bytes(read_array(input)?) as usize;
+    let [system_id, component_id, sequence] = read_array(input)?;
+    let header = MavHeader {
+        system_id,
+        component_id,
+        sequence,
+    };
+    let incoming = match kind[0] {
+        KIND_MESSAGE => {
+            let msg_id = u32::from_le_bytes(read_array(input)?);
+            let len = u16::from_le_bytes(read_array(input)?) as usize;
+            let signature = match read_array::<1>(input)? {
+                [0] => None,
+                [flag] => {
+                    let [link_id] = read_array(input)?;
+                    let timestamp = u64::from_le_bytes(read_array(input)?);
+                    Some(Signature {
+                        link_id,
+                        timestamp,
+                        verified: match flag {
+                            SIGNED_VALID => Some(true),
+                            SIGNED_INVALID => Some(false),
+                            _ => None,
+                        },
+                    })
+                }
+            };
+            let [payload_len] = read_array(input)?;
+            let payload = read_bytes(input, payload_len as usize)?;
+            let msg = MavMessage::parse(MavlinkVersion::V2, msg_id, &payload)
+                .map_err(|e| invalid(format!("message {msg_id}: {e}")))?;
+            Incoming::Message(MavMsg {
+                timestamp: at,
+                signature,
+                len,
+                payload,
+                // The session keeps the payload only.
+                frame: Vec::new(),
+                ..MavMsg::new(header, msg, link)
+            })
+        }
+        KIND_DIAGNOSTIC => {
+            let [name_len] = read_array(input)?;
+            let name = String::from_utf8(read_bytes(input, name_len as usize)?)
+                .map_err(|_| invalid("bad diagnostic name"))?;
+            let fields_len = u32::from_le_bytes(read_array(input)?) as usize;
+            let fields = String::from_utf8(read_bytes(input, fields_len)?)
+                .map_err(|_| invalid("bad diagnostic fields"))?;
+            Incoming::Diagnostic(Diagnostic {
+                header,
+                name: diagnostic_name(name),
+                fields,
+                link,
+                timestamp: at,
+                frame: Vec::new(),
+            })
+        }
+        other => return Err(invalid(format!("unknown record kind {other}"))),
+    };
+    Ok(Some((at, incoming)))
+}
+
+/// Starts a session file; records follow with [`write_record`].
+pub fn write_header(out: &mut impl Write, meta: &SessionMeta) -> io::Result<()> {
+    let meta = toml::to_string(meta).map_err(io::Error::other)?;
+    out.write_all(MAGIC)?;
+    out.write_all(&[VERSION])?;
+    out.write_all(&(meta.len() as u32).to_le_bytes())?;
+    out.write_all(meta.as_bytes())
+}
+
+/// Writes a session; `log` holds records made with [`write_record`].
+pub fn save(path: &Path, meta: &SessionMeta, log: &[u8]) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    write_header(&mut out, meta)?;
+    out.write_all(log)?;
+    out.flush()
+}
+
+/// Reads a session file, plain or zstd-compressed.
+pub fn load(path: &Path) -> io::Result<Session> {
+    let mut input = compress::open(path)?;
+    read(&mut input).map_err(|e| io::Error::new(e.kind(), format!("{}: {e}", path.display())))
+}
+
+fn read(input: &mut impl Read) -> io::Result<Session> {
+    let meta = read_header(input)?;
+    let mut records = Vec::new();
+    while let Some(record) = read_record(input)? {
+        records.push(record);
+    }
+    Ok(Session { meta, records })
+}
+
+/// Reads what [`write_header`] wrote; records follow.
+pub fn read_header(input: &mut impl Read) -> io::Result<SessionMeta> {
+    if &read_array::<8>(input)? != MAGIC {
+        return Err(invalid("not a mavsnark session"));
+    }
+    let [version] = read_array(input)?;
+    if version != VERSION {
+        return Err(invalid(format!("unsupported session version {version}")));
+    }
+    let meta_len = u32::from_le_bytes(read_array(input)?) as usize;
+    let meta = String::from_utf8(read_bytes(input, meta_len)?)
+        .map_err(|_| invalid("bad session header"))?;
+    toml::from_str(&meta).map_err(|e| invalid(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::common::{ATTITUDE_DATA, HEARTBEAT_DATA};
+
+    use super::*;
+    use crate::{app::Tab, signing::SignatureStatus};
+
+    fn header(system_id: u8) -> MavHeader {
+        MavHeader {
+            system_id,
+            component_id: 1,
+            sequence: 7,
+        }
+    }
+
+    #[test]
+    fn records_round_trip() {
+        let at = DateTime::from_timestamp_micros(1_700_000_000_123_456).unwrap();
+        let mut msg = MavMsg::new(
+            header(1),
+            MavMessage::ATTITUDE(ATTITUDE_DATA {
+                roll: 0.5,
+                ..Default::default()
+            }),
+            2,
+        );
+        msg.signature = Some(Signature {
+            link_id: 3,
+            timestamp: 42,
+            verified: Some(false),
+        });
+        let diag = Diagnostic {
+            header: header(9),
+            name: "UNKNOWN_MESSAGE",
+            fields: "msg_id: 999".to_string(),
+            link: 0,
+            timestamp: at,
+            frame: Vec::new(),
+        };
+
+        let mut log = Vec::new();
+        write_record(&mut log, at, &Incoming::Message(msg)).unwrap();
+        write_record(&mut log, at, &Incoming::Diagnostic(diag)).unwrap();
+        let mut input = log.as_slice();
+
+        let (t, first) = read_record(&mut input).unwrap().unwrap();
+        assert_eq!(t, at);
+        let Incoming::Message(m) = first else {
+            panic!("expected a message");
+        };
+        assert_eq!(m.link, 2);
+        assert_eq!(m.header.sequence, 7);
+        assert_eq!(m.timestamp, at);
+        assert_eq!(m.signature.map(|s| s.timestamp), Some(42));
+        assert_eq!(m.signature_status(), SignatureStatus::Invalid);
+        assert!(m.fields().contains("roll: 0.5"));
+
+        let (_, second) = read_record(&mut input).unwrap().unwrap();
+        let Incoming::Diagnostic(d) = second else {
+            panic!("expected a diagnostic");
+        };
+        assert_eq!(d.name, "UNKNOWN_MESSAGE");
+        assert_eq!(d.fields, "msg_id: 999");
+        assert!(read_record(&mut input).unwrap().is_none());
+    }
+
+    #[test]
+    fn session_file_round_trips() {
+        let meta = SessionMeta {
+            links: vec!["udpin:0.0.0.0:14445".to_string()],
+            selected: vec![3, 0, 0],
+            ui: UiState {
+                tab: Tab::Stream,
+                ..Default::default()
+            },
+        };
+        let mut log = Vec::new();
+        let heartbeat = MavMsg::new(
+            header(1),
+            MavMessage::HEARTBEAT(HEARTBEAT_DATA::default()),
+            0,
+        );
+        write_record(&mut log, Utc::now(), &Incoming::Message(heartbeat)).unwrap();
+
+        let path =
+            std::env::temp_dir().join(format!("mavsnark-test-{}.session", std::process::id()));
+        save(&path, &meta, &log).unwrap();
+        let session = load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(session.meta, meta);
+        assert_eq!(session.records.len(), 1);
+    }
+
+    #[test]
+    fn other_files_are_rejected() {
+        let err = read(&mut b"not a session file".as_slice()).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn log_drops_oldest_whole_records() {
+        let mut log = SessionLog::default();
+        for sys in 1..=3 {
+            let msg = MavMsg::new(
+                header(sys),
+                MavMessage::HEARTBEAT(HEARTBEAT_DATA::default()),
+                0,
+            );
+            log.push(Utc::now(), &Incoming::Message(msg));
+        }
+        let record = log.byte_len() / 3;
+        log.trim_to((record * 2) as u64);
+        assert_eq!(log.dropped, 1);
+
+        let mut input = log.as_bytes();
+        let mut systems = Vec::new();
+        while let Some((_, Incoming::Message(m))) = read_record(&mut input).unwrap() {
+            systems.push(m.header.system_id);
+        }
+        assert_eq!(systems, vec![2, 3]);
+    }
+
+    #[test]
+    fn log_stays_within_its_cap() {
+        let mut log = SessionLog::default();
+        assert_eq!(log.max_bytes(), DEFAULT_LOG_BYTES);
+        let heartbeat = || {
+            let msg = MavMessage::HEARTBEAT(HEARTBEAT_DATA::default());
+            Incoming::Message(MavMsg::new(header(1), msg, 0))
+        };
+        log.push(Utc::now(), &heartbeat());
+        let record = log.byte_len() as u64;
+        log.set_max_bytes(record * 3);
+        for _ in 0..10 {
+            log.push(Utc::now(), &heartbeat());
+        }
+        assert_eq!(log.byte_len() as u64, record * 3);
+        assert_eq!(log.dropped, 8);
+        log.clear();
+        assert_eq!(log.max_bytes(), record * 3);
+    }
+}
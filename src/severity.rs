@@ -0,0 +1,207 @@
+use std::collections::HashMap;
+
+use ratatui::style::Color;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    entries::{EventKind, MessageEntry},
+    frame::FrameErrorKind,
+};
+
+/// How much an event row matters, lowest first.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    #[default]
+    Info,
+    Warning,
+    Error,
+    Critical,
+}
+
+impl Severity {
+    pub const ALL: [Severity; 4] = [
+        Severity::Info,
+        Severity::Warning,
+        Severity::Error,
+        Severity::Critical,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+            Severity::Critical => "critical",
+        }
+    }
+
+    /// Row colour; `None` leaves info rows in their category colour.
+    pub fn color(self) -> Option<Color> {
+        match self {
+            Severity::Info => None,
+            Severity::Warning => Some(Color::Yellow),
+            Severity::Error => Some(Color::LightRed),
+            Severity::Critical => Some(Color::Red),
+        }
+    }
+
+    /// The next minimum severity for the filter key: all, then warnings
+    /// and up, errors and up, critical only, and back to all.
+    pub fn next_filter(filter: Option<Severity>) -> Option<Severity> {
+        match filter {
+            None => Some(Severity::Warning),
+            Some(Severity::Info | Severity::Warning) => Some(Severity::Error),
+            Some(Severity::Error) => Some(Severity::Critical),
+            Some(Severity::Critical) => None,
+        }
+    }
+}
+
+/// Assigns event rows a severity: `[severity]` config entries by name
+/// first, then the built-in rules.
+#[derive(Debug, Clone, Default)]
+pub struct Classifier {
+    overrides: HashMap<String, Severity>,
+}
+
+impl Classifier {
+    pub fn new(overrides: HashMap<String, Severity>) -> Self {
+        Self { overrides }
+    }
+
+    pub fn classify(&self, entry: &MessageEntry) -> Severity {
+        if let Some(&severity) = self.overrides.get(entry.name) {
+            return severity;
+        }
+        match entry.kind {
+            EventKind::Security => Severity::Critical,
+            EventKind::Alert => Severity::Error,
+            // Bytes lost on the link, such as a frame with a bad CRC.
+            EventKind::Diagnostic if FrameErrorKind::NAMES.contains(&entry.name) => {
+                Severity::Warning
+            }
+            EventKind::Diagnostic => Severity::Info,
+            EventKind::Message => message_severity(entry),
+        }
+    }
+}
+
+/// STATUSTEXT by its own severity, refused commands as warnings.
+fn message_severity(entry: &MessageEntry) -> Severity {
+    let fields = entry.parsed_fields();
+    let field = |name: &str| fields.iter().find(|(k, _)| *k == name).map(|(_, v)| *v);
+    match entry.name {
+        "STATUSTEXT" => match field("severity") {
+            Some("MAV_SEVERITY_EMERGENCY" | "MAV_SEVERITY_ALERT" | "MAV_SEVERITY_CRITICAL") => {
+                Severity::Critical
+            }
+            Some("MAV_SEVERITY_ERROR") => Severity::Error,
+            Some("MAV_SEVERITY_WARNING") => Severity::Warning,
+            _ => Severity::Info,
+        },
+        "COMMAND_ACK" => match field("result") {
+            None | Some("MAV_RESULT_ACCEPTED" | "MAV_RESULT_IN_PROGRESS") => Severity::Info,
+            Some(_) => Severity::Warning,
+        },
+        _ => Severity::Info,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+    use crate::signing::SignatureStatus;
+
+    fn entry(name: &'static str, kind: EventKind, fields: &str) -> MessageEntry {
+        MessageEntry {
+            sys_color: Color::Red,
+            comp_color: Color::Cyan,
+            msg_color: None,
+            sys_id: 1,
+            comp_id: 1,
+            name,
+            fields: fields.to_string(),
+            link: 0,
+            duplicate: false,
+            kind,
+            direction: None,
+            payload: Vec::new(),
+            frame: Vec::new(),
+            v1: false,
+            signature: SignatureStatus::Unsigned,
+            timestamp: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn built_in_rules() {
+        let classifier = Classifier::default();
+        let text = |severity| {
+            classifier.classify(&entry(
+                "STATUSTEXT",
+                EventKind::Message,
+                &format!("severity: {severity}, text: \"x\""),
+            ))
+        };
+        assert_eq!(text("MAV_SEVERITY_EMERGENCY"), Severity::Critical);
+        assert_eq!(text("MAV_SEVERITY_ERROR"), Severity::Error);
+        assert_eq!(text("MAV_SEVERITY_WARNING"), Severity::Warning);
+        assert_eq!(text("MAV_SEVERITY_NOTICE"), Severity::Info);
+        assert_eq!(
+            classifier.classify(&entry(
+                "COMMAND_ACK",
+                EventKind::Message,
+                "command: MAV_CMD_COMPONENT_ARM_DISARM, result: MAV_RESULT_DENIED"
+            )),
+            Severity::Warning
+        );
+        assert_eq!(
+            classifier.classify(&entry("GPS_DIVERGED", EventKind::Alert, "")),
+            Severity::Error
+        );
+        assert_eq!(
+            classifier.classify(&entry("SIGNATURE_REPLAY", EventKind::Security, "")),
+            Severity::Critical
+        );
+    }
+
+    #[test]
+    fn config_overrides_by_name() {
+        let classifier = Classifier::new(HashMap::from([
+            ("GPS_DIVERGED".to_string(), Severity::Critical),
+            ("MISSION_ACK".to_string(), Severity::Warning),
+        ]));
+        assert_eq!(
+            classifier.classify(&entry("GPS_DIVERGED", EventKind::Alert, "")),
+            Severity::Critical
+        );
+        assert_eq!(
+            classifier.classify(&entry("MISSION_ACK", EventKind::Message, "")),
+            Severity::Warning
+        );
+    }
+
+    #[test]
+    fn filter_cycles_back_to_all() {
+        let mut filter = None;
+        let mut seen = Vec::new();
+        for _ in 0..4 {
+            filter = Severity::next_filter(filter);
+            seen.push(filter);
+        }
+        assert_eq!(
+            seen,
+            [
+                Some(Severity::Warning),
+                Some(Severity::Error),
+                Some(Severity::Critical),
+                None
+            ]
+        );
+    }
+}
@@ -0,0 +1,235 @@
+use std::sync::{
+    OnceLock,
+    atomic::{AtomicU64, Ordering},
+};
+
+use chrono::{DateTime, Utc};
+use ratatui::style::Color;
+use sha2::{Digest, Sha256};
+
+/// Bytes of the SHA-256 digest kept as the frame signature.
+const SIGNATURE_LEN: usize = 6;
+/// Link id, timestamp and signature after the checksum.
+const TRAILER_LEN: usize = 1 + 6 + SIGNATURE_LEN;
+/// Signature timestamps count from 2015-01-01 00:00:00 UTC.
+const EPOCH_SECS: i64 = 1_420_070_400;
+
+/// What checking a frame's MAVLink 2 signature against `--signing-key` found.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SignatureStatus {
+    #[default]
+    Unsigned,
+    /// Signed, but there is no key to check it with.
+    Unchecked,
+    Valid,
+    Invalid,
+}
+
+impl SignatureStatus {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Unsigned => "unsigned",
+            Self::Unchecked => "signed?",
+            Self::Valid => "signed",
+            Self::Invalid => "bad sig",
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            Self::Unsigned => Color::DarkGray,
+            Self::Unchecked => Color::Gray,
+            Self::Valid => Color::Green,
+            Self::Invalid => Color::Red,
+        }
+    }
+}
+
+static KEY: OnceLock<[u8; 32]> = OnceLock::new();
+
+/// Reads `--signing-key`: 64 hex digits are the secret key itself, anything
+/// else a passphrase hashed with SHA-256, as MAVProxy and QGroundControl do.
+pub fn parse_key(s: &str) -> Result<[u8; 32], String> {
+    if s.is_empty() {
+        return Err("empty signing key".to_string());
+    }
+    if s.len() == 64 && s.bytes().all(|b| b.is_ascii_hexdigit()) {
+        let mut key = [0u8; 32];
+        for (i, byte) in key.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&s[2 * i..2 * i + 2], 16).unwrap();
+        }
+        return Ok(key);
+    }
+    Ok(Sha256::digest(s.as_bytes()).into())
+}
+
+/// Checks signed frames against `key` from now on. Set once at startup;
+/// later calls are ignored.
+pub fn install(key: [u8; 32]) {
+    let _ = KEY.set(key);
+}
+
+/// The key set with [`install`], if any.
+pub fn key() -> Option<&'static [u8; 32]> {
+    KEY.get()
+}
+
+/// Whether the last six bytes of a signed frame are the first six of
+/// SHA-256 over the key and everything before them.
+pub fn verify(bytes: &[u8], key: &[u8; 32]) -> bool {
+    let Some(end) = bytes.len().checked_sub(SIGNATURE_LEN) else {
+        return false;
+    };
+    constant_time_eq(&signature(&bytes[..end], key), &bytes[end..])
+}
+
+/// Signs what mavsnark sends (`--signing-link-id`), so it can take part on
+/// networks that reject unsigned traffic.
+pub struct Signer {
+    key: [u8; 32],
+    link_id: u8,
+    /// Last timestamp used; the spec requires them to increase.
+    last: AtomicU64,
+}
+
+static SIGNER: OnceLock<Signer> = OnceLock::new();
+
+/// Signs outgoing frames with `key` and `link_id` from now on. Set once at
+/// startup; later calls are ignored.
+pub fn sign_outgoing(key: [u8; 32], link_id: u8) {
+    let _ = SIGNER.set(Signer {
+        key,
+        link_id,
+        last: AtomicU64::new(0),
+    });
+}
+
+/// The signer set with [`sign_outgoing`], if any.
+pub fn signer() -> Option<&'static Signer> {
+    SIGNER.get()
+}
+
+impl Signer {
+    /// Fills in the trailer of a frame serialized for signing, stamped with
+    /// the current time, or just after the previous frame's stamp.
+    pub fn sign(&self, frame: &mut [u8]) {
+        let now = timestamp(Utc::now());
+        let previous = self
+            .last
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |last| {
+                Some(now.max(last + 1))
+            })
+            .unwrap();
+        sign(frame, &self.key, self.link_id, now.max(previous + 1));
+    }
+}
+
+/// `at` in signature timestamp units: 10 µs since 2015-01-01.
+fn timestamp(at: DateTime<Utc>) -> u64 {
+    let micros = at.timestamp_micros() - EPOCH_SECS * 1_000_000;
+    (micros.max(0) / 10) as u64
+}
+
+/// Writes link id, timestamp and signature into the last 13 bytes of
+/// `frame`, which must have the signed flag set already.
+pub fn sign(frame: &mut [u8], key: &[u8; 32], link_id: u8, timestamp: u64) {
+    let at = frame.len() - TRAILER_LEN;
+    frame[at] = link_id;
+    frame[at + 1..at + 7].copy_from_slice(&timestamp.to_le_bytes()[..6]);
+    let end = frame.len() - SIGNATURE_LEN;
+    let signature = signature(&frame[..end], key);
+    frame[end..].copy_from_slice(&signature);
+}
+
+fn signature(signed: &[u8], key: &[u8; 32]) -> [u8; SIGNATURE_LEN] {
+    let digest = Sha256::new()
+        .chain_update(key)
+        .chain_update(signed)
+        .finalize();
+    let mut signature = [0u8; SIGNATURE_LEN];
+    signature.copy_from_slice(&digest[..SIGNATURE_LEN]);
+    signature
+}
+
+/// Compares without stopping at the first difference, so how long a
+/// check takes does not tell a forger how much of a signature was right.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len()
+        && a.iter()
+            .zip(b)
+            .fold(0, |diff, (x, y)| std::hint::black_box(diff | (x ^ y)))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A HEARTBEAT signed with link id 5 under the passphrase "secret".
+    const SIGNED: [u8; 34] = [
+        0xfd, 0x09, 0x01, 0x00, 0x07, 0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02,
+        0x03, 0x51, 0x04, 0x03, 0x43, 0x54, 0x05, 0x15, 0xcd, 0x5b, 0x07, 0x00, 0x00, 0xa5, 0x08,
+        0xca, 0xa6, 0x99, 0xe7,
+    ];
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    #[test]
+    fn compares_whole_slices() {
+        assert!(constant_time_eq(b"abcdef", b"abcdef"));
+        assert!(!constant_time_eq(b"abcdef", b"abcdeg"));
+        assert!(!constant_time_eq(b"abcdef", b"abcde"));
+        assert!(constant_time_eq(b"", b""));
+    }
+
+    #[test]
+    fn keys_are_hex_or_passphrases() {
+        let key = parse_key("secret").unwrap();
+        assert_eq!(
+            hex(&key),
+            "2bb80d537b1da3e38bd30361aa855686bde0eacd7162fef6a25fe97bf527a25b"
+        );
+        assert_eq!(parse_key(&hex(&key)).unwrap(), key);
+        assert!(parse_key("").is_err());
+    }
+
+    #[test]
+    fn verifies_signatures() {
+        let key = parse_key("secret").unwrap();
+        assert!(verify(&SIGNED, &key));
+        assert!(!verify(&SIGNED, &parse_key("guess").unwrap()));
+        let mut tampered = SIGNED;
+        tampered[16] ^= 0x01;
+        assert!(!verify(&tampered, &key));
+        assert!(!verify(&SIGNED[..4], &key));
+    }
+
+    #[test]
+    fn signs_with_increasing_timestamps() {
+        let key = parse_key("secret").unwrap();
+        let mut frame = SIGNED;
+        frame[21..].fill(0);
+        sign(&mut frame, &key, 5, 123_456_789);
+        assert_eq!(frame, SIGNED);
+
+        let signer = Signer {
+            key,
+            link_id: 1,
+            last: AtomicU64::new(0),
+        };
+        let stamp = |frame: &[u8]| {
+            let mut ts = [0u8; 8];
+            ts[..6].copy_from_slice(&frame[22..28]);
+            u64::from_le_bytes(ts)
+        };
+        let (mut first, mut second) = (SIGNED, SIGNED);
+        signer.sign(&mut first);
+        signer.sign(&mut second);
+        assert!(verify(&first, &key) && verify(&second, &key));
+        assert_eq!(first[21], 1);
+        assert!(stamp(&second) > stamp(&first));
+        assert!(stamp(&first) > timestamp("2025-01-01T00:00:00Z".parse().unwrap()));
+    }
+}
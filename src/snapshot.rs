@@ -0,0 +1,342 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use chrono::{DateTime, Local, Utc};
+
+use crate::{
+    battery, capabilities,
+    collector::Collector,
+    entries::{EventKind, StreamEntry, parse_fields},
+    modes::{self, ModeScheme},
+};
+
+/// Alerts listed, newest last.
+const RECENT_ALERTS: usize = 20;
+
+/// What a snapshot shows besides the collector's contents.
+pub struct Context<'a> {
+    pub links: &'a [String],
+    /// For decoding flight modes; `None` leaves the mode column empty.
+    pub modes: Option<ModeScheme>,
+    /// The moment shown: now, or the time cursor.
+    pub at: DateTime<Utc>,
+}
+
+/// Writes the state at `context.at` as markdown: links, one row per vehicle,
+/// the stream table and the latest alerts. Meant for pasting into a flight
+/// test log.
+pub fn write(out: &mut impl Write, collector: &Collector, context: &Context) -> io::Result<()> {
+    let at = context.at;
+    writeln!(
+        out,
+        "# mavsnark snapshot {}\n",
+        at.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S%.3f")
+    )?;
+
+    writeln!(out, "## Links\n")?;
+    writeln!(out, "| # | Link | First | Duplicate |")?;
+    writeln!(out, "|---|------|------:|----------:|")?;
+    let stats = collector.link_stats();
+    for (i, link) in context.links.iter().enumerate() {
+        let (first, duplicate) = stats.get(i).map_or((0, 0), |s| (s.first, s.duplicate));
+        writeln!(out, "| {i} | {} | {first} | {duplicate} |", cell(link))?;
+    }
+
+    let rows: Vec<(&StreamEntry, &str)> = collector
+        .stream()
+        .iter()
+        .filter_map(|entry| Some((entry, entry.at(Some(at))?.fields)))
+        .collect();
+
+    writeln!(out, "\n## Vehicles\n")?;
+    writeln!(
+        out,
+        "| System | Type | Autopilot | Mode | Armed | State | Battery | GPS |"
+    )?;
+    writeln!(
+        out,
+        "|-------:|------|-----------|------|-------|-------|---------|-----|"
+    )?;
+    for &(heartbeat, fields) in rows.iter().filter(|(e, _)| e.name == "HEARTBEAT") {
+        let sys_id = heartbeat.sys_id;
+        let field = |name: &str, key: &str| {
+            let fields = if name == "HEARTBEAT" {
+                fields
+            } else {
+                rows.iter()
+                    .find(|(e, _)| e.sys_id == sys_id && e.name == name)?
+                    .1
+            };
+            parse_fields(fields)
+                .into_iter()
+                .find(|(k, _)| *k == key)
+                .map(|(_, v)| v.to_string())
+        };
+        let mavtype = field("HEARTBEAT", "mavtype").unwrap_or_default();
+        let mode = context
+            .modes
+            .zip(field("HEARTBEAT", "custom_mode").and_then(|m| m.parse().ok()))
+            .and_then(|(scheme, custom)| modes::decode(scheme, &mavtype, custom))
+            .unwrap_or_default();
+        let armed = field("HEARTBEAT", "base_mode").is_some_and(|m| m.contains("SAFETY_ARMED"));
+        let battery = match (
+            field("SYS_STATUS", "voltage_battery").and_then(|v| v.parse::<f64>().ok()),
+            field("SYS_STATUS", "battery_remaining"),
+        ) {
+            (Some(mv), Some(pct)) if pct != "-1" => format!("{:.2} V, {pct}%", mv / 1000.0),
+            (Some(mv), _) => format!("{:.2} V", mv / 1000.0),
+            (None, _) => String::new(),
+        };
+        let gps = match (
+            field("GPS_RAW_INT", "fix_type"),
+            field("GPS_RAW_INT", "satellites_visible"),
+        ) {
+            (Some(fix), Some(sats)) => format!("{}, {sats} sats", short(&fix, "GPS_FIX_TYPE_")),
+            _ => String::new(),
+        };
+        writeln!(
+            out,
+            "| {sys_id} | {} | {} | {mode} | {} | {} | {battery} | {gps} |",
+            short(&mavtype, "MAV_TYPE_"),
+            short(
+                &field("HEARTBEAT", "autopilot").unwrap_or_default(),
+                "MAV_AUTOPILOT_"
+            ),
+            if armed { "yes" } else { "no" },
+            short(
+                &field("HEARTBEAT", "system_status").unwrap_or_default(),
+                "MAV_STATE_"
+            ),
+        )?;
+    }
+
+    write_batteries(out, collector, context)?;
+    write_components(out, collector)?;
+
+    writeln!(out, "\n## Stream\n")?;
+    writeln!(out, "| Source | Message | Age | Rate | Fields |")?;
+    writeln!(out, "|--------|---------|----:|-----:|--------|")?;
+    for &(entry, fields) in &rows {
+        let timestamp = entry.at(Some(at)).map_or(entry.timestamp, |s| s.timestamp);
+        let age = (at - timestamp).num_milliseconds() as f64 / 1000.0;
+        let rate = entry
+            .arrivals
+            .hz()
+            .map_or_else(String::new, |hz| format!("{hz:.1} Hz"));
+        writeln!(
+            out,
+            "| {}:{} | {} | {age:.1}s | {rate} | {} |",
+            entry.sys_id,
+            entry.comp_id,
+            entry.name,
+            cell(fields)
+        )?;
+    }
+
+    writeln!(out, "\n## Recent alerts\n")?;
+    let alerts: Vec<_> = collector
+        .messages()
+        .iter()
+        .filter(|e| matches!(e.kind, EventKind::Alert | EventKind::Security) && e.timestamp <= at)
+        .collect();
+    if alerts.is_empty() {
+        writeln!(out, "None.")?;
+        return Ok(());
+    }
+    writeln!(out, "| Time | Source | Alert | Details |")?;
+    writeln!(out, "|------|--------|-------|---------|")?;
+    for alert in &alerts[alerts.len().saturating_sub(RECENT_ALERTS)..] {
+        writeln!(
+            out,
+            "| {} | {}:{} | {} | {} |",
+            alert.timestamp.with_timezone(&Local).format("%H:%M:%S%.3f"),
+            alert.sys_id,
+            alert.comp_id,
+            alert.name,
+            cell(&alert.fields)
+        )?;
+    }
+    Ok(())
+}
+
+/// Draw, time left and consumption per flight mode phase for each battery;
+/// nothing when no BATTERY_STATUS was seen.
+fn write_batteries(
+    out: &mut impl Write,
+    collector: &Collector,
+    context: &Context,
+) -> io::Result<()> {
+    let batteries: Vec<_> = collector.battery().batteries().collect();
+    if batteries.is_empty() {
+        return Ok(());
+    }
+    let value = |v: Option<String>| v.unwrap_or_default();
+    writeln!(out, "\n## Batteries\n")?;
+    writeln!(
+        out,
+        "| System | Battery | Voltage | Consumed | Remaining | Avg draw | Recent draw | Time left |"
+    )?;
+    writeln!(
+        out,
+        "|-------:|--------:|--------:|---------:|----------:|---------:|------------:|----------:|"
+    )?;
+    for (&(sys_id, id), battery) in &batteries {
+        let Some(latest) = battery.latest() else {
+            continue;
+        };
+        writeln!(
+            out,
+            "| {sys_id} | {id} | {} | {} | {} | {} | {} | {} |",
+            value(latest.voltage.map(|v| format!("{v:.2} V"))),
+            value(latest.consumed.map(|c| format!("{c:.0} mAh"))),
+            value(latest.remaining.map(|p| format!("{p}%"))),
+            value(battery.average_draw().map(|a| format!("{a:.1} A"))),
+            value(battery.recent_draw().map(|a| format!("{a:.1} A"))),
+            value(battery.endurance().map(battery::format_duration)),
+        )?;
+    }
+    for (&(sys_id, id), battery) in &batteries {
+        if battery.phases.is_empty() {
+            continue;
+        }
+        writeln!(out, "\n### System {sys_id} battery {id} by phase\n")?;
+        writeln!(out, "| Phase | Start | Duration | Consumed |")?;
+        writeln!(out, "|-------|-------|---------:|---------:|")?;
+        for phase in &battery.phases {
+            writeln!(
+                out,
+                "| {} | {} | {} | {:.0} mAh |",
+                cell(&phase.label(context.modes)),
+                phase.start.with_timezone(&Local).format("%H:%M:%S"),
+                battery::format_duration(phase.duration()),
+                phase.consumed
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Wire protocol, firmware, declared capabilities and the microservices
+/// seen per component.
+fn write_components(out: &mut impl Write, collector: &Collector) -> io::Result<()> {
+    let components: Vec<_> = collector.capabilities().components().collect();
+    if components.is_empty() {
+        return Ok(());
+    }
+    writeln!(out, "\n## Components\n")?;
+    writeln!(
+        out,
+        "| Source | Type | Autopilot | MAVLink | Firmware | Board | Capabilities | Services |"
+    )?;
+    writeln!(
+        out,
+        "|--------|------|-----------|---------|----------|-------|--------------|----------|"
+    )?;
+    for ((sys_id, comp_id), component) in components {
+        let version = component.version.as_ref();
+        writeln!(
+            out,
+            "| {sys_id}:{comp_id} | {} | {} | {} | {} | {} | {} | {} |",
+            component.mavtype.as_deref().unwrap_or_default(),
+            component.autopilot.as_deref().unwrap_or_default(),
+            component.wire_label(),
+            version.map_or_else(String::new, |v| capabilities::format_version(v.flight_sw)),
+            version.map_or_else(String::new, |v| format!("{:#x}", v.board)),
+            component.capability_names().join(", "),
+            component
+                .services
+                .iter()
+                .copied()
+                .collect::<Vec<_>>()
+                .join(", "),
+        )?;
+    }
+    Ok(())
+}
+
+/// Writes a snapshot to a new file at `path`.
+pub fn save(path: &Path, collector: &Collector, context: &Context) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create_new(path)?);
+    write(&mut out, collector, context)?;
+    out.flush()
+}
+
+/// An enum value without its common prefix, e.g. `QUADROTOR` for
+/// `MAV_TYPE_QUADROTOR`.
+fn short<'a>(value: &'a str, prefix: &str) -> &'a str {
+    value.strip_prefix(prefix).unwrap_or(value)
+}
+
+/// Keeps a value from breaking out of its table cell.
+fn cell(value: &str) -> String {
+    value.replace('|', "\\|").replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::{
+        MavHeader,
+        common::{
+            GPS_RAW_INT_DATA, GpsFixType, HEARTBEAT_DATA, MavMessage, MavModeFlag, MavState,
+            MavType, SYS_STATUS_DATA,
+        },
+    };
+
+    use super::*;
+    use crate::message::MavMsg;
+
+    #[test]
+    fn lists_vehicles_and_stream() {
+        let mut collector = Collector::new();
+        let header = MavHeader {
+            system_id: 1,
+            component_id: 1,
+            sequence: 0,
+        };
+        for msg in [
+            MavMessage::HEARTBEAT(HEARTBEAT_DATA {
+                mavtype: MavType::MAV_TYPE_QUADROTOR,
+                base_mode: MavModeFlag::MAV_MODE_FLAG_SAFETY_ARMED,
+                system_status: MavState::MAV_STATE_ACTIVE,
+                ..Default::default()
+            }),
+            MavMessage::SYS_STATUS(SYS_STATUS_DATA {
+                voltage_battery: 15_800,
+                battery_remaining: 76,
+                ..Default::default()
+            }),
+            MavMessage::GPS_RAW_INT(GPS_RAW_INT_DATA {
+                fix_type: GpsFixType::GPS_FIX_TYPE_3D_FIX,
+                satellites_visible: 14,
+                ..Default::default()
+            }),
+        ] {
+            collector.push(MavMsg::new(header, msg, 0));
+        }
+        let links = ["udpin:0.0.0.0:14445".to_string()];
+        let context = Context {
+            links: &links,
+            modes: None,
+            at: Utc::now(),
+        };
+        let mut out = Vec::new();
+        write(&mut out, &collector, &context).unwrap();
+        let text = String::from_utf8(out).unwrap();
+
+        assert!(text.contains("| 0 | udpin:0.0.0.0:14445 | 3 | 0 |"));
+        assert!(text.contains(
+            "| 1 | QUADROTOR | GENERIC |  | yes | ACTIVE | 15.80 V, 76% | 3D_FIX, 14 sats |"
+        ));
+        assert!(text.contains("| 1:1 | GPS_RAW_INT |"));
+        assert!(text.contains("| 1:1 | QUADROTOR | GENERIC | MAVLink 2 |  |  |  |  |"));
+        assert!(text.ends_with("## Recent alerts\n\nNone.\n"));
+    }
+
+    #[test]
+    fn cells_cannot_break_the_table() {
+        assert_eq!(cell("a | b\nc"), "a \\| b c");
+    }
+}
@@ -0,0 +1,105 @@
+use std::{collections::BTreeMap, env, fs, io, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::{LayoutMode, Overflow, Tab},
+    direction::Direction,
+    severity::Severity,
+};
+
+/// UI choices restored at startup, saved on exit.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiState {
+    /// Message names shown as stream rows; `None` keeps the built-in set.
+    pub stream_types: Option<Vec<String>>,
+    pub direction: Option<Direction>,
+    /// Lowest severity shown in the Events tab; `None` shows all.
+    pub severity: Option<Severity>,
+    pub layout: LayoutMode,
+    pub tab: Tab,
+    pub overflow: Overflow,
+    pub hide_defaults: bool,
+}
+
+/// Saved states keyed by connection preset, so a bench setup and a field
+/// radio each come back the way they were left.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct StateFile {
+    presets: BTreeMap<String, UiState>,
+}
+
+/// The preset a set of `--uri` arguments is saved under.
+pub fn preset_key(uris: &[String]) -> String {
+    uris.join(" ")
+}
+
+fn path() -> Option<PathBuf> {
+    let base = env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state")))?;
+    Some(base.join("mavsnark").join("state.toml"))
+}
+
+fn read(path: &PathBuf) -> StateFile {
+    // The file is only a convenience; a missing or damaged one starts fresh.
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|text| toml::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+pub fn load(preset: &str) -> UiState {
+    path()
+        .map(|path| read(&path).presets.remove(preset).unwrap_or_default())
+        .unwrap_or_default()
+}
+
+pub fn save(preset: &str, state: UiState) -> io::Result<()> {
+    let Some(path) = path() else {
+        return Ok(());
+    };
+    let mut file = read(&path);
+    file.presets.insert(preset.to_string(), state);
+    let text = toml::to_string(&file).map_err(io::Error::other)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_round_trips() {
+        let mut file = StateFile::default();
+        file.presets.insert(
+            "udpin:0.0.0.0:14445".to_string(),
+            UiState {
+                stream_types: Some(vec!["HEARTBEAT".to_string()]),
+                direction: Some(Direction::Uplink),
+                severity: Some(Severity::Error),
+                layout: LayoutMode::Vertical,
+                tab: Tab::Stream,
+                overflow: Overflow::Truncate,
+                hide_defaults: true,
+            },
+        );
+        let text = toml::to_string(&file).unwrap();
+        let back: StateFile = toml::from_str(&text).unwrap();
+        assert_eq!(back.presets, file.presets);
+    }
+
+    #[test]
+    fn missing_keys_use_defaults() {
+        let file: StateFile = toml::from_str("[presets.a]\ntab = \"stats\"").unwrap();
+        let state = &file.presets["a"];
+        assert_eq!(state.tab, Tab::Stats);
+        assert_eq!(state.stream_types, None);
+        assert_eq!(state.layout, LayoutMode::Auto);
+    }
+}
@@ -0,0 +1,188 @@
+use std::collections::{HashMap, VecDeque};
+
+use chrono::{DateTime, Utc};
+use mavlink::common::{MavMessage, MavSeverity};
+
+use crate::{message::MavMsg, severity::Severity};
+
+/// Lines kept before the oldest are dropped.
+const MAX_LINES: usize = 1000;
+
+/// Characters in one STATUSTEXT; a chunk this long is followed by another.
+const CHUNK_LEN: usize = 50;
+
+/// One STATUSTEXT, or several chunks of one joined back together.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatusLine {
+    /// When the first chunk arrived.
+    pub at: DateTime<Utc>,
+    pub sys_id: u8,
+    pub comp_id: u8,
+    pub severity: MavSeverity,
+    pub text: String,
+    /// Some chunks never arrived; marked with `…` where they were.
+    pub gap: bool,
+}
+
+impl StatusLine {
+    /// `WARNING` for `MAV_SEVERITY_WARNING`.
+    pub fn severity_name(&self) -> String {
+        let name = format!("{:?}", self.severity);
+        name.strip_prefix("MAV_SEVERITY_")
+            .unwrap_or(&name)
+            .to_string()
+    }
+}
+
+/// The severity the Events tab gives the same STATUSTEXT.
+pub fn severity(severity: MavSeverity) -> Severity {
+    use MavSeverity::*;
+    match severity {
+        MAV_SEVERITY_EMERGENCY | MAV_SEVERITY_ALERT | MAV_SEVERITY_CRITICAL => Severity::Critical,
+        MAV_SEVERITY_ERROR => Severity::Error,
+        MAV_SEVERITY_WARNING => Severity::Warning,
+        _ => Severity::Info,
+    }
+}
+
+/// STATUSTEXT of every component in arrival order, with the chunks of long
+/// texts (sent with the same non-zero `id`, counting up `chunk_seq`) joined
+/// into one line.
+#[derive(Default)]
+pub struct StatusConsole {
+    lines: VecDeque<StatusLine>,
+    /// Lines dropped from the front, so `open` can keep stable positions.
+    dropped: usize,
+    /// Lines still waiting for chunks, by `(system, component, id)`: their
+    /// position counted from the first line ever, and the chunk expected.
+    open: HashMap<(u8, u8, u16), (usize, u8)>,
+}
+
+impl StatusConsole {
+    pub fn observe(&mut self, msg: &MavMsg) {
+        let MavMessage::STATUSTEXT(d) = &msg.msg else {
+            return;
+        };
+        let text = d.text.to_str().unwrap_or_default();
+        let last = text.len() < CHUNK_LEN;
+        let (sys_id, comp_id) = (msg.header.system_id, msg.header.component_id);
+        let key = (sys_id, comp_id, d.id);
+
+        if d.id != 0
+            && let Some(&(position, expected)) = self.open.get(&key)
+            && let Some(line) = position
+                .checked_sub(self.dropped)
+                .and_then(|i| self.lines.get_mut(i))
+        {
+            if d.chunk_seq != expected {
+                line.gap = true;
+                line.text.push('…');
+            }
+            line.text.push_str(text);
+            if last {
+                self.open.remove(&key);
+            } else {
+                self.open
+                    .insert(key, (position, d.chunk_seq.wrapping_add(1)));
+            }
+            return;
+        }
+
+        let gap = d.id != 0 && d.chunk_seq != 0;
+        let mut line = StatusLine {
+            at: msg.timestamp,
+            sys_id,
+            comp_id,
+            severity: d.severity,
+            text: String::new(),
+            gap,
+        };
+        if gap {
+            line.text.push('…');
+        }
+        line.text.push_str(text);
+        if d.id != 0 && !last {
+            let position = self.dropped + self.lines.len();
+            self.open
+                .insert(key, (position, d.chunk_seq.wrapping_add(1)));
+        }
+        if self.lines.len() == MAX_LINES {
+            self.lines.pop_front();
+            self.dropped += 1;
+        }
+        self.lines.push_back(line);
+    }
+
+    /// Oldest first.
+    pub fn lines(&self) -> &VecDeque<StatusLine> {
+        &self.lines
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.open.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::{MavHeader, common::STATUSTEXT_DATA};
+
+    use super::*;
+
+    fn chunk(severity: MavSeverity, text: &str, id: u16, chunk_seq: u8) -> MavMsg {
+        let mut bytes = [0u8; 50];
+        bytes[..text.len()].copy_from_slice(text.as_bytes());
+        MavMsg::new(
+            MavHeader::default(),
+            MavMessage::STATUSTEXT(STATUSTEXT_DATA {
+                severity,
+                text: bytes.into(),
+                id,
+                chunk_seq,
+            }),
+            0,
+        )
+    }
+
+    #[test]
+    fn joins_chunks_into_one_line() {
+        let mut console = StatusConsole::default();
+        let info = MavSeverity::MAV_SEVERITY_INFO;
+        let warning = MavSeverity::MAV_SEVERITY_WARNING;
+        let first = "PreArm: Battery 1 voltage 10.2 below the minimum o";
+        console.observe(&chunk(warning, first, 7, 0));
+        // Another text in between does not break the reassembly.
+        console.observe(&chunk(info, "EKF3 IMU0 is using GPS", 0, 0));
+        console.observe(&chunk(warning, "f 10.5", 7, 1));
+
+        let lines = console.lines();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].text, format!("{first}f 10.5"));
+        assert_eq!(lines[0].severity_name(), "WARNING");
+        assert_eq!(severity(lines[0].severity), Severity::Warning);
+        assert!(!lines[0].gap);
+        assert_eq!(lines[1].text, "EKF3 IMU0 is using GPS");
+
+        // The same id again starts a new line once the last one finished.
+        console.observe(&chunk(warning, "short", 7, 0));
+        assert_eq!(console.lines().len(), 3);
+    }
+
+    #[test]
+    fn marks_missing_chunks() {
+        let mut console = StatusConsole::default();
+        let error = MavSeverity::MAV_SEVERITY_ERROR;
+        let full = "x".repeat(50);
+        console.observe(&chunk(error, &full, 3, 0));
+        console.observe(&chunk(error, "end", 3, 2));
+        // A chunk whose start was missed.
+        console.observe(&chunk(error, "tail", 4, 1));
+
+        let lines = console.lines();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].text, format!("{full}…end"));
+        assert!(lines[0].gap);
+        assert_eq!(lines[1].text, "…tail");
+    }
+}
@@ -0,0 +1,162 @@
+use std::collections::{HashMap, VecDeque};
+
+use chrono::{DateTime, TimeDelta, Utc};
+
+use crate::message::MavMsg;
+
+/// Sliding window the rankings are computed over.
+pub const WINDOW: TimeDelta = TimeDelta::seconds(10);
+
+struct Arrival {
+    timestamp: DateTime<Utc>,
+    source: (u8, u8),
+    name: &'static str,
+    len: usize,
+}
+
+/// One ranked row: a source or a message type with its share of the traffic.
+#[derive(Debug, PartialEq)]
+pub struct Talker<K> {
+    pub key: K,
+    pub hz: f64,
+    pub bytes_per_sec: f64,
+}
+
+/// Ranks sources and message types by bandwidth over the last [`WINDOW`].
+pub struct TopTalkers {
+    arrivals: VecDeque<Arrival>,
+}
+
+impl Default for TopTalkers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TopTalkers {
+    pub fn new() -> Self {
+        Self {
+            arrivals: VecDeque::new(),
+        }
+    }
+
+    pub fn observe(&mut self, msg: &MavMsg) {
+        self.arrivals.push_back(Arrival {
+            timestamp: msg.timestamp,
+            source: (msg.header.system_id, msg.header.component_id),
+            name: msg.msg_type(),
+            len: msg.len,
+        });
+        self.expire(msg.timestamp);
+    }
+
+    fn expire(&mut self, now: DateTime<Utc>) {
+        while self
+            .arrivals
+            .front()
+            .is_some_and(|a| now.signed_duration_since(a.timestamp) > WINDOW)
+        {
+            self.arrivals.pop_front();
+        }
+    }
+
+    fn rank<K: Copy + Eq + std::hash::Hash + Ord>(
+        &self,
+        now: DateTime<Utc>,
+        key: impl Fn(&Arrival) -> K,
+    ) -> Vec<Talker<K>> {
+        let mut totals: HashMap<K, (usize, usize)> = HashMap::new();
+        for a in &self.arrivals {
+            if now.signed_duration_since(a.timestamp) > WINDOW {
+                continue;
+            }
+            let t = totals.entry(key(a)).or_default();
+            t.0 += 1;
+            t.1 += a.len;
+        }
+        let secs = WINDOW.num_milliseconds() as f64 / 1000.0;
+        let mut ranked: Vec<Talker<K>> = totals
+            .into_iter()
+            .map(|(key, (count, bytes))| Talker {
+                key,
+                hz: count as f64 / secs,
+                bytes_per_sec: bytes as f64 / secs,
+            })
+            .collect();
+        ranked.sort_by(|a, b| {
+            b.bytes_per_sec
+                .total_cmp(&a.bytes_per_sec)
+                .then(a.key.cmp(&b.key))
+        });
+        ranked
+    }
+
+    pub fn by_source(&self, now: DateTime<Utc>) -> Vec<Talker<(u8, u8)>> {
+        self.rank(now, |a| a.source)
+    }
+
+    pub fn by_type(&self, now: DateTime<Utc>) -> Vec<Talker<&'static str>> {
+        self.rank(now, |a| a.name)
+    }
+
+    pub fn clear(&mut self) {
+        self.arrivals.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::{
+        MavHeader,
+        common::{ATTITUDE_DATA, HEARTBEAT_DATA, MavMessage},
+    };
+
+    use super::*;
+
+    fn msg(sys_id: u8, msg: MavMessage, ms: i64) -> MavMsg {
+        let mut m = MavMsg::new(
+            MavHeader {
+                system_id: sys_id,
+                component_id: 1,
+                sequence: 0,
+            },
+            msg,
+            0,
+        );
+        m.timestamp = DateTime::from_timestamp_millis(ms).unwrap();
+        m
+    }
+
+    fn at(ms: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp_millis(ms).unwrap()
+    }
+
+    #[test]
+    fn ranks_by_bandwidth() {
+        let mut t = TopTalkers::new();
+        for i in 0..10 {
+            t.observe(&msg(1, MavMessage::HEARTBEAT(HEARTBEAT_DATA::default()), i));
+        }
+        for i in 0..10 {
+            let attitude = ATTITUDE_DATA {
+                yawspeed: 1.0,
+                ..Default::default()
+            };
+            t.observe(&msg(2, MavMessage::ATTITUDE(attitude), i));
+        }
+        let sources = t.by_source(at(10));
+        assert_eq!(sources[0].key, (2, 1));
+        assert_eq!(sources[1].key, (1, 1));
+        assert!((sources[0].hz - 1.0).abs() < 1e-9);
+        let types = t.by_type(at(10));
+        assert_eq!(types[0].key, "ATTITUDE");
+    }
+
+    #[test]
+    fn old_traffic_falls_out_of_the_window() {
+        let mut t = TopTalkers::new();
+        t.observe(&msg(1, MavMessage::HEARTBEAT(HEARTBEAT_DATA::default()), 0));
+        assert_eq!(t.by_source(at(5_000)).len(), 1);
+        assert!(t.by_source(at(20_000)).is_empty());
+    }
+}
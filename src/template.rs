@@ -0,0 +1,115 @@
+use crate::entries::parse_fields;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Text(String),
+    Field(String),
+}
+
+/// A compact row format for one message type, e.g.
+/// `"{lat} {lon} alt {alt} fix {fix_type}"`. `{{` and `}}` are literal braces.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+impl Template {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let mut field = String::new();
+                    let mut closed = false;
+                    for c in chars.by_ref() {
+                        if c == '}' {
+                            closed = true;
+                            break;
+                        }
+                        field.push(c);
+                    }
+                    let field = field.trim();
+                    if !closed || field.is_empty() || field.contains('{') {
+                        return Err(format!("{s:?}: bad field reference"));
+                    }
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(Part::Field(field.to_string()));
+                }
+                '}' => return Err(format!("{s:?}: unmatched '}}'")),
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        Ok(Self { parts })
+    }
+
+    /// Field names the template refers to.
+    pub fn fields(&self) -> impl Iterator<Item = &str> {
+        self.parts.iter().filter_map(|part| match part {
+            Part::Field(name) => Some(name.as_str()),
+            Part::Text(_) => None,
+        })
+    }
+
+    /// Fills in values from a `fields` string as produced by
+    /// [`crate::message::MavMsg::fields`]; missing ones show as `?`.
+    pub fn render(&self, fields: &str) -> String {
+        let values = parse_fields(fields);
+        let mut out = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => out.push_str(text),
+                Part::Field(name) => out.push_str(
+                    values
+                        .iter()
+                        .find(|(k, _)| k == name)
+                        .map_or("?", |&(_, v)| v),
+                ),
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_selected_fields() {
+        let template = Template::parse("{lat},{lon} alt {alt}m").unwrap();
+        assert_eq!(
+            template.render("time_usec: 0, lat: 473977420, lon: 85455940, alt: 488000"),
+            "473977420,85455940 alt 488000m"
+        );
+        assert_eq!(template.fields().collect::<Vec<_>>(), ["lat", "lon", "alt"]);
+    }
+
+    #[test]
+    fn missing_fields_and_escapes() {
+        let template = Template::parse("{{{ x }}} {nope}").unwrap();
+        assert_eq!(template.render("x: 1"), "{1} ?");
+    }
+
+    #[test]
+    fn malformed_templates_are_rejected() {
+        assert!(Template::parse("{}").is_err());
+        assert!(Template::parse("a } b").is_err());
+        assert!(Template::parse("{a{b}").is_err());
+        assert!(Template::parse("{lat").is_err());
+    }
+}
@@ -0,0 +1,259 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use chrono::{DateTime, Utc};
+use mavlink::common::{MavMessage, MavModeFlag};
+
+use crate::{entries::Report, message::MavMsg, radio::HISTORY};
+
+/// Where a clearance figure came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    /// ALTITUDE altitude_terrain.
+    Altitude,
+    /// TERRAIN_REPORT current_height.
+    TerrainReport,
+}
+
+impl Source {
+    pub fn name(self) -> &'static str {
+        match self {
+            Source::Altitude => "ALTITUDE",
+            Source::TerrainReport => "TERRAIN_REPORT",
+        }
+    }
+}
+
+/// Height above the terrain below the vehicle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Clearance {
+    pub at: DateTime<Utc>,
+    /// Metres.
+    pub height: f32,
+    pub source: Source,
+}
+
+#[derive(Debug, Default)]
+pub struct Terrain {
+    /// Over the last [`HISTORY`], oldest first.
+    pub samples: VecDeque<Clearance>,
+    /// Terrain height AMSL under the vehicle from TERRAIN_REPORT, metres.
+    pub terrain_height: Option<f32>,
+    /// Vehicle altitude AMSL from ALTITUDE, metres.
+    pub amsl: Option<f32>,
+    /// Terrain tiles still to load and loaded, from TERRAIN_REPORT.
+    pub tiles: Option<(u16, u16)>,
+    armed: bool,
+    low: bool,
+}
+
+impl Terrain {
+    pub fn latest(&self) -> Option<&Clearance> {
+        self.samples.back()
+    }
+
+    pub fn lowest(&self) -> Option<f32> {
+        self.samples.iter().map(|s| s.height).reduce(f32::min)
+    }
+}
+
+/// Clearance falling below the margin while armed, or recovering.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TerrainReport {
+    pub vehicle: u8,
+    pub clearance: Clearance,
+    pub margin: f32,
+    pub low: bool,
+}
+
+impl Report for TerrainReport {
+    fn name(&self) -> &'static str {
+        if self.low {
+            "TERRAIN_CLEARANCE_LOW"
+        } else {
+            "TERRAIN_CLEARANCE_OK"
+        }
+    }
+
+    fn is_alert(&self) -> bool {
+        self.low
+    }
+
+    fn source(&self) -> (u8, u8) {
+        (self.vehicle, 0)
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "clearance: {:.1} m, margin: {:.1} m, from: {}",
+            self.clearance.height,
+            self.margin,
+            self.clearance.source.name()
+        )
+    }
+}
+
+/// Terrain clearance per vehicle from ALTITUDE and TERRAIN_REPORT. ALTITUDE
+/// is preferred once a vehicle sends a terrain altitude in it, since it
+/// comes from the same estimate the autopilot flies on.
+#[derive(Default)]
+pub struct TerrainTracker {
+    vehicles: BTreeMap<u8, Terrain>,
+    /// Metres; `None` never alerts.
+    margin: Option<f32>,
+}
+
+impl TerrainTracker {
+    pub fn new(margin: Option<f32>) -> Self {
+        Self {
+            vehicles: BTreeMap::new(),
+            margin,
+        }
+    }
+
+    pub fn observe(&mut self, msg: &MavMsg) -> Option<TerrainReport> {
+        let sys_id = msg.header.system_id;
+        let height = match &msg.msg {
+            MavMessage::HEARTBEAT(d) if msg.header.component_id == 1 => {
+                self.vehicles.entry(sys_id).or_default().armed = d
+                    .base_mode
+                    .contains(MavModeFlag::MAV_MODE_FLAG_SAFETY_ARMED);
+                return None;
+            }
+            MavMessage::ALTITUDE(d) => {
+                let terrain = self.vehicles.entry(sys_id).or_default();
+                terrain.amsl = Some(d.altitude_amsl);
+                // -1000 when there is no terrain estimate.
+                (d.altitude_terrain > -1000.0).then_some((d.altitude_terrain, Source::Altitude))
+            }
+            MavMessage::TERRAIN_REPORT(d) => {
+                let terrain = self.vehicles.entry(sys_id).or_default();
+                terrain.terrain_height = Some(d.terrain_height);
+                terrain.tiles = Some((d.pending, d.loaded));
+                let preferred = terrain.samples.iter().any(|s| s.source == Source::Altitude);
+                (!preferred).then_some((d.current_height, Source::TerrainReport))
+            }
+            _ => return None,
+        };
+        let (height, source) = height?;
+        let terrain = self.vehicles.get_mut(&sys_id)?;
+        let clearance = Clearance {
+            at: msg.timestamp,
+            height,
+            source,
+        };
+        terrain.samples.push_back(clearance);
+        while terrain
+            .samples
+            .front()
+            .is_some_and(|s| clearance.at - s.at > HISTORY)
+        {
+            terrain.samples.pop_front();
+        }
+
+        let margin = self.margin?;
+        // On the ground clearance is zero; that is not news.
+        let low = terrain.armed && height < margin;
+        if low == terrain.low {
+            return None;
+        }
+        terrain.low = low;
+        Some(TerrainReport {
+            vehicle: sys_id,
+            clearance,
+            margin,
+            low,
+        })
+    }
+
+    pub fn margin(&self) -> Option<f32> {
+        self.margin
+    }
+
+    pub fn vehicle(&self, sys_id: u8) -> Option<&Terrain> {
+        self.vehicles.get(&sys_id)
+    }
+
+    pub fn clear(&mut self) {
+        self.vehicles.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::{
+        MavHeader,
+        common::{ALTITUDE_DATA, HEARTBEAT_DATA, TERRAIN_REPORT_DATA},
+    };
+
+    use super::*;
+
+    fn msg(msg: MavMessage) -> MavMsg {
+        let header = MavHeader {
+            system_id: 1,
+            component_id: 1,
+            sequence: 0,
+        };
+        MavMsg::new(header, msg, 0)
+    }
+
+    fn report(current_height: f32) -> MavMsg {
+        msg(MavMessage::TERRAIN_REPORT(TERRAIN_REPORT_DATA {
+            terrain_height: 488.0,
+            current_height,
+            loaded: 12,
+            ..Default::default()
+        }))
+    }
+
+    fn armed(armed: bool) -> MavMsg {
+        msg(MavMessage::HEARTBEAT(HEARTBEAT_DATA {
+            base_mode: if armed {
+                MavModeFlag::MAV_MODE_FLAG_SAFETY_ARMED
+            } else {
+                MavModeFlag::empty()
+            },
+            ..Default::default()
+        }))
+    }
+
+    #[test]
+    fn warns_below_the_margin_only_while_armed() {
+        let mut terrain = TerrainTracker::new(Some(10.0));
+        // Sitting on the ground, disarmed.
+        assert_eq!(terrain.observe(&report(0.2)), None);
+        terrain.observe(&armed(true));
+        let low = terrain.observe(&report(0.3)).unwrap();
+        assert_eq!(low.name(), "TERRAIN_CLEARANCE_LOW");
+        assert!(low.is_alert());
+        assert_eq!(
+            terrain.observe(&report(25.0)).unwrap().name(),
+            "TERRAIN_CLEARANCE_OK"
+        );
+        assert_eq!(terrain.observe(&report(30.0)), None);
+        terrain.observe(&armed(false));
+        assert_eq!(terrain.observe(&report(0.0)), None);
+
+        let vehicle = terrain.vehicle(1).unwrap();
+        assert_eq!(vehicle.lowest(), Some(0.0));
+        assert_eq!(vehicle.terrain_height, Some(488.0));
+        assert_eq!(vehicle.tiles, Some((0, 12)));
+    }
+
+    #[test]
+    fn prefers_altitude_once_seen() {
+        let mut terrain = TerrainTracker::new(None);
+        terrain.observe(&report(40.0));
+        terrain.observe(&msg(MavMessage::ALTITUDE(ALTITUDE_DATA {
+            altitude_amsl: 530.0,
+            altitude_terrain: 41.5,
+            ..Default::default()
+        })));
+        terrain.observe(&report(40.0));
+        let vehicle = terrain.vehicle(1).unwrap();
+        let latest = vehicle.latest().unwrap();
+        assert_eq!(latest.source, Source::Altitude);
+        assert_eq!(latest.height, 41.5);
+        assert_eq!(vehicle.samples.len(), 2);
+        assert_eq!(vehicle.amsl, Some(530.0));
+    }
+}
@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+
+use crate::message::MavMsg;
+
+/// Messages per second over the whole session, in total and per message
+/// type, one count per second since the first message.
+pub struct RateHistory {
+    /// Unix second of the first bucket.
+    start: Option<i64>,
+    total: Vec<u32>,
+    by_name: HashMap<&'static str, Vec<u32>>,
+}
+
+impl Default for RateHistory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RateHistory {
+    pub fn new() -> Self {
+        Self {
+            start: None,
+            total: Vec::new(),
+            by_name: HashMap::new(),
+        }
+    }
+
+    pub fn observe(&mut self, msg: &MavMsg) {
+        let second = msg.timestamp.timestamp();
+        let start = *self.start.get_or_insert(second);
+        // Anything older than the first message (merged logs) counts there.
+        let index = second.saturating_sub(start).max(0) as usize;
+        bump(&mut self.total, index);
+        bump(self.by_name.entry(msg.msg_type()).or_default(), index);
+    }
+
+    pub fn start(&self) -> Option<DateTime<Utc>> {
+        DateTime::from_timestamp(self.start?, 0)
+    }
+
+    /// Seconds covered so far.
+    pub fn span(&self) -> usize {
+        self.total.len()
+    }
+
+    /// At most `points` `(seconds since start, messages/s)` pairs covering
+    /// the whole session, for all messages or just `name`. Each point is the
+    /// average over its share of the session, so short dips stay visible
+    /// only as far as the resolution allows.
+    pub fn series(&self, name: Option<&str>, points: usize) -> Vec<(f64, f64)> {
+        let counts: &[u32] = match name {
+            None => &self.total,
+            Some(name) => self.by_name.get(name).map_or(&[], Vec::as_slice),
+        };
+        let span = self.span();
+        let per_point = span.div_ceil(points.max(1)).max(1);
+        (0..span)
+            .step_by(per_point)
+            .map(|first| {
+                let end = (first + per_point).min(span);
+                let sum: u32 = (first..end)
+                    .map(|i| counts.get(i).copied().unwrap_or(0))
+                    .sum();
+                (first as f64, f64::from(sum) / (end - first) as f64)
+            })
+            .collect()
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+}
+
+fn bump(counts: &mut Vec<u32>, index: usize) {
+    if counts.len() <= index {
+        counts.resize(index + 1, 0);
+    }
+    counts[index] += 1;
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeDelta;
+    use mavlink::{
+        MavHeader,
+        common::{ATTITUDE_DATA, HEARTBEAT_DATA, MavMessage},
+    };
+
+    use super::*;
+
+    fn at(msg: MavMessage, t: DateTime<Utc>) -> MavMsg {
+        MavMsg {
+            timestamp: t,
+            ..MavMsg::new(MavHeader::default(), msg, 0)
+        }
+    }
+
+    #[test]
+    fn counts_per_second_and_type() {
+        let t0 = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let mut history = RateHistory::new();
+        for s in 0..4 {
+            let t = t0 + TimeDelta::seconds(s);
+            history.observe(&at(MavMessage::HEARTBEAT(HEARTBEAT_DATA::default()), t));
+            // A dropout in second 2.
+            if s != 2 {
+                for _ in 0..10 {
+                    history.observe(&at(MavMessage::ATTITUDE(ATTITUDE_DATA::default()), t));
+                }
+            }
+        }
+        assert_eq!(history.start(), Some(t0));
+        assert_eq!(
+            history.series(Some("ATTITUDE"), 10),
+            [(0.0, 10.0), (1.0, 10.0), (2.0, 0.0), (3.0, 10.0)]
+        );
+        assert_eq!(history.series(None, 2), [(0.0, 11.0), (2.0, 6.0)]);
+        assert_eq!(history.series(Some("GPS_RAW_INT"), 1), [(0.0, 0.0)]);
+    }
+}
@@ -0,0 +1,247 @@
+use std::{
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use mavlink::common::MavMessage;
+
+use crate::{
+    compress, connection,
+    frame::{self, FrameParser},
+    index,
+    message::Incoming,
+    session::{Session, SessionMeta},
+};
+
+/// Reads a telemetry log as written by QGroundControl and MAVProxy: each
+/// frame is preceded by its receive time, in big-endian microseconds since
+/// the Unix epoch. Frames are tagged with `link`.
+pub fn read(input: &mut impl Read, link: usize) -> io::Result<Vec<(DateTime<Utc>, Incoming)>> {
+    let mut records = Vec::new();
+    let mut parser = FrameParser::new();
+    loop {
+        let mut stamp = [0u8; 8];
+        match input.read_exact(&mut stamp) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e),
+        }
+        let micros = i64::try_from(u64::from_be_bytes(stamp)).unwrap_or(i64::MAX);
+        let at = DateTime::from_timestamp_micros(micros).ok_or_else(|| invalid("bad timestamp"))?;
+
+        // Enough of the header to know how long the frame is.
+        let mut stx = [0u8; 1];
+        input.read_exact(&mut stx)?;
+        let header = match stx[0] {
+            0xFE => 6,
+            0xFD => 10,
+            _ => return Err(invalid("not a tlog (no frame after timestamp)")),
+        };
+        let mut bytes = vec![0u8; header];
+        bytes[0] = stx[0];
+        input.read_exact(&mut bytes[1..])?;
+        let len = match frame::frame_len(&bytes) {
+            Some(0) | None => return Err(invalid("bad frame header")),
+            Some(len) => len,
+        };
+        bytes.resize(len, 0);
+        input.read_exact(&mut bytes[header..])?;
+
+        parser.push(&bytes);
+        while let Some(parsed) = parser.next_frame() {
+            let mut incoming = connection::decode(parsed, link);
+            set_time(&mut incoming, at);
+            records.push((at, incoming));
+        }
+    }
+    Ok(records)
+}
+
+/// Reads a telemetry log file, plain or zstd-compressed.
+pub fn load(path: &Path, link: usize) -> io::Result<Vec<(DateTime<Utc>, Incoming)>> {
+    let mut input = compress::open(path)?;
+    read(&mut input, link).map_err(|e| io::Error::new(e.kind(), format!("{}: {e}", path.display())))
+}
+
+/// Like [`load`], but starting `skip` after the log's first frame, jumping
+/// there with its [`index::Index`] (built and saved beside the log if needed).
+pub fn load_from(
+    path: &Path,
+    link: usize,
+    skip: TimeDelta,
+) -> io::Result<Vec<(DateTime<Utc>, Incoming)>> {
+    let index = index::load_or_build(path)?;
+    let Some(first) = index.first() else {
+        return Ok(Vec::new());
+    };
+    let from = first + skip;
+    let mut input = compress::open_at(path, index.offset_at(from))?;
+    let mut records = read(&mut input, link)
+        .map_err(|e| io::Error::new(e.kind(), format!("{}: {e}", path.display())))?;
+    records.retain(|(at, _)| *at >= from);
+    Ok(records)
+}
+
+/// How far the capturing machine's clock was behind the vehicle's GPS time,
+/// from the median over the log's `SYSTEM_TIME` messages. `None` if the
+/// vehicle never reported a time.
+pub fn clock_offset(records: &[(DateTime<Utc>, Incoming)]) -> Option<TimeDelta> {
+    let mut offsets: Vec<i64> = records
+        .iter()
+        .filter_map(|(at, incoming)| match incoming {
+            Incoming::Message(m) => match &m.msg {
+                MavMessage::SYSTEM_TIME(data) if data.time_unix_usec != 0 => {
+                    Some(data.time_unix_usec as i64 - at.timestamp_micros())
+                }
+                _ => None,
+            },
+            Incoming::Diagnostic(_) => None,
+        })
+        .collect();
+    if offsets.is_empty() {
+        return None;
+    }
+    offsets.sort_unstable();
+    Some(TimeDelta::microseconds(offsets[offsets.len() / 2]))
+}
+
+/// Loads several logs of the same flight (e.g. GCS and companion side),
+/// each from `skip` in (see [`load_from`]), shifts each onto vehicle time
+/// with [`clock_offset`] and interleaves them as one session, one link per
+/// file.
+pub fn merge(paths: &[PathBuf], skip: TimeDelta) -> io::Result<Session> {
+    let mut links = Vec::new();
+    let mut records = Vec::new();
+    for (index, path) in paths.iter().enumerate() {
+        let mut log = load_from(path, index, skip)?;
+        let label = match clock_offset(&log) {
+            Some(offset) => {
+                for (at, incoming) in &mut log {
+                    *at += offset;
+                    set_time(incoming, *at);
+                }
+                let secs = offset.num_microseconds().unwrap_or(0) as f64 / 1e6;
+                tracing::info!(path = %path.display(), offset = secs, "tlog aligned");
+                format!("{} ({secs:+.3}s)", path.display())
+            }
+            None => {
+                tracing::warn!(path = %path.display(), "tlog has no SYSTEM_TIME; not aligned");
+                format!("{} (unaligned)", path.display())
+            }
+        };
+        links.push(label);
+        records.append(&mut log);
+    }
+    // Stable, so frames with equal times keep their order within a file.
+    records.sort_by_key(|(at, _)| *at);
+    Ok(Session {
+        meta: SessionMeta {
+            links,
+            ..Default::default()
+        },
+        records,
+    })
+}
+
+fn set_time(incoming: &mut Incoming, at: DateTime<Utc>) {
+    match incoming {
+        Incoming::Message(m) => m.timestamp = at,
+        Incoming::Diagnostic(d) => d.timestamp = at,
+    }
+}
+
+fn invalid(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::{
+        MAVLinkV2MessageRaw, MavHeader,
+        common::{HEARTBEAT_DATA, SYSTEM_TIME_DATA},
+    };
+
+    use super::*;
+
+    fn entry(out: &mut Vec<u8>, micros: u64, msg: &MavMessage) {
+        let mut raw = MAVLinkV2MessageRaw::new();
+        raw.serialize_message(MavHeader::default(), msg);
+        out.extend_from_slice(&micros.to_be_bytes());
+        out.extend_from_slice(raw.raw_bytes());
+    }
+
+    fn system_time(unix_usec: u64) -> MavMessage {
+        MavMessage::SYSTEM_TIME(SYSTEM_TIME_DATA {
+            time_unix_usec: unix_usec,
+            time_boot_ms: 0,
+        })
+    }
+
+    #[test]
+    fn reads_timestamped_frames() {
+        let mut log = Vec::new();
+        entry(&mut log, 1_700_000_000_000_000, &system_time(0));
+        entry(
+            &mut log,
+            1_700_000_000_500_000,
+            &MavMessage::HEARTBEAT(HEARTBEAT_DATA::default()),
+        );
+        let records = read(&mut log.as_slice(), 3).unwrap();
+        assert_eq!(records.len(), 2);
+        let (at, Incoming::Message(m)) = &records[1] else {
+            panic!("expected a message");
+        };
+        assert_eq!(at.timestamp_micros(), 1_700_000_000_500_000);
+        assert_eq!(m.timestamp, *at);
+        assert_eq!(m.link, 3);
+        assert!(read(&mut &b"not a tlog at all"[..], 0).is_err());
+    }
+
+    #[test]
+    fn offset_is_the_median_against_vehicle_time() {
+        let mut log = Vec::new();
+        // The capturing clock runs 2 s behind; one sample is an outlier.
+        entry(&mut log, 1_000_000, &system_time(3_000_000));
+        entry(&mut log, 2_000_000, &system_time(4_000_000));
+        entry(&mut log, 3_000_000, &system_time(90_000_000));
+        let records = read(&mut log.as_slice(), 0).unwrap();
+        assert_eq!(clock_offset(&records), Some(TimeDelta::seconds(2)));
+        assert_eq!(clock_offset(&records[..0]), None);
+    }
+
+    #[test]
+    fn merges_logs_onto_one_timeline() {
+        let dir = std::env::temp_dir().join(format!("mavsnark-tlog-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let heartbeat = MavMessage::HEARTBEAT(HEARTBEAT_DATA::default());
+        // GCS clock is right; the companion's is 10 s behind.
+        let mut gcs = Vec::new();
+        entry(&mut gcs, 100_000_000, &system_time(100_000_000));
+        entry(&mut gcs, 101_000_000, &heartbeat);
+        let mut companion = Vec::new();
+        entry(&mut companion, 90_500_000, &system_time(100_500_000));
+        entry(&mut companion, 91_500_000, &heartbeat);
+        let paths = [dir.join("gcs.tlog"), dir.join("companion.tlog")];
+        std::fs::write(&paths[0], gcs).unwrap();
+        std::fs::write(&paths[1], companion).unwrap();
+
+        let session = merge(&paths, TimeDelta::zero()).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        let order: Vec<(i64, usize)> = session
+            .records
+            .iter()
+            .map(|(at, incoming)| {
+                let Incoming::Message(m) = incoming else {
+                    panic!("expected a message");
+                };
+                (at.timestamp_millis(), m.link)
+            })
+            .collect();
+        assert_eq!(
+            order,
+            vec![(100_000, 0), (100_500, 1), (101_000, 0), (101_500, 1)]
+        );
+        assert!(session.meta.links[1].ends_with("companion.tlog (+10.000s)"));
+    }
+}
@@ -0,0 +1,275 @@
+use std::{
+    collections::VecDeque,
+    fs::{self, File},
+    io::{self, Write},
+    path::PathBuf,
+    str::FromStr,
+};
+
+use chrono::{DateTime, Local, TimeDelta, Utc};
+
+use crate::{
+    compress::{self, Output},
+    message::{self, Incoming},
+    session::{self, SessionMeta},
+};
+
+/// What starts a `--trigger` capture.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Trigger {
+    /// A message of this type arriving.
+    Message(String),
+    /// Any alert or security event being raised.
+    Alert,
+}
+
+impl FromStr for Trigger {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("alert") {
+            Ok(Self::Alert)
+        } else if message::is_message_name(s) {
+            Ok(Self::Message(s.to_string()))
+        } else {
+            Err(format!("{s:?}: expected a message type or \"alert\""))
+        }
+    }
+}
+
+struct Capture {
+    out: Output,
+    until: DateTime<Utc>,
+}
+
+/// Keeps the last `pre` of traffic in memory and, when a [`Trigger`] fires,
+/// writes it plus the following `post` to a session file, like the pre- and
+/// post-trigger buffer of an oscilloscope. Triggers during a capture extend
+/// it.
+pub struct RingCapture {
+    dir: PathBuf,
+    pre: TimeDelta,
+    post: TimeDelta,
+    triggers: Vec<Trigger>,
+    links: Vec<String>,
+    compress: bool,
+    /// Encoded records from the last `pre`, oldest first.
+    ring: VecDeque<(DateTime<Utc>, Vec<u8>)>,
+    capture: Option<Capture>,
+    alerts_seen: usize,
+}
+
+impl RingCapture {
+    pub fn new(
+        dir: PathBuf,
+        pre: TimeDelta,
+        post: TimeDelta,
+        triggers: Vec<Trigger>,
+        links: Vec<String>,
+        compress: bool,
+    ) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            pre,
+            post,
+            triggers,
+            links,
+            compress,
+            ring: VecDeque::new(),
+            capture: None,
+            alerts_seen: 0,
+        })
+    }
+
+    /// Buffers or writes one record. Returns the new file if it started a
+    /// capture.
+    pub fn record(
+        &mut self,
+        at: DateTime<Utc>,
+        incoming: &Incoming,
+    ) -> io::Result<Option<PathBuf>> {
+        let mut bytes = Vec::new();
+        session::write_record(&mut bytes, at, incoming)?;
+        self.close_if_done(at)?;
+        match &mut self.capture {
+            Some(capture) => capture.out.write_all(&bytes)?,
+            None => {
+                self.ring.push_back((at, bytes));
+                while self.ring.front().is_some_and(|(t, _)| at - *t > self.pre) {
+                    self.ring.pop_front();
+                }
+            }
+        }
+        let fired = match incoming {
+            Incoming::Message(m) => self
+                .triggers
+                .iter()
+                .any(|t| matches!(t, Trigger::Message(name) if name == m.msg_type())),
+            Incoming::Diagnostic(_) => false,
+        };
+        if fired { self.fire(at) } else { Ok(None) }
+    }
+
+    /// Fires an [`Trigger::Alert`] capture if `alert_count` grew since the
+    /// last call.
+    pub fn check_alerts(
+        &mut self,
+        at: DateTime<Utc>,
+        alert_count: usize,
+    ) -> io::Result<Option<PathBuf>> {
+        let raised = alert_count > self.alerts_seen;
+        self.alerts_seen = alert_count;
+        if raised && self.triggers.contains(&Trigger::Alert) {
+            self.fire(at)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn fire(&mut self, at: DateTime<Utc>) -> io::Result<Option<PathBuf>> {
+        if let Some(capture) = &mut self.capture {
+            capture.until = capture.until.max(at + self.post);
+            return Ok(None);
+        }
+        let stamp = at.with_timezone(&Local).format("%Y%m%d-%H%M%S%.3f");
+        let suffix = compress::suffix(self.compress);
+        let path = self
+            .dir
+            .join(format!("mavsnark-trigger-{stamp}.session{suffix}"));
+        let mut out = Output::new(File::create_new(&path)?, self.compress)?;
+        let meta = SessionMeta {
+            links: self.links.clone(),
+            ..Default::default()
+        };
+        session::write_header(&mut out, &meta)?;
+        for (_, bytes) in self.ring.drain(..) {
+            out.write_all(&bytes)?;
+        }
+        self.capture = Some(Capture {
+            out,
+            until: at + self.post,
+        });
+        Ok(Some(path))
+    }
+
+    fn close_if_done(&mut self, now: DateTime<Utc>) -> io::Result<()> {
+        if self.capture.as_ref().is_some_and(|c| now > c.until)
+            && let Some(capture) = self.capture.take()
+        {
+            capture.out.finish()?;
+        }
+        Ok(())
+    }
+
+    /// Finishes a capture whose post-trigger time is up and pushes the rest
+    /// to disk; called every UI tick.
+    pub fn flush(&mut self, now: DateTime<Utc>) -> io::Result<()> {
+        self.close_if_done(now)?;
+        match &mut self.capture {
+            Some(capture) => capture.out.flush(),
+            None => Ok(()),
+        }
+    }
+
+    /// Ends a capture still in progress, e.g. on exit.
+    pub fn finish(&mut self) -> io::Result<()> {
+        match self.capture.take() {
+            Some(capture) => capture.out.finish(),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::{
+        MavHeader,
+        common::{HEARTBEAT_DATA, MavMessage, STATUSTEXT_DATA},
+    };
+
+    use super::*;
+    use crate::message::MavMsg;
+
+    fn incoming(msg: MavMessage) -> Incoming {
+        Incoming::Message(MavMsg::new(MavHeader::default(), msg, 0))
+    }
+
+    fn capture(triggers: &[&str]) -> (PathBuf, RingCapture) {
+        let dir = std::env::temp_dir().join(format!(
+            "mavsnark-trigger-{}-{}",
+            std::process::id(),
+            triggers.join("-")
+        ));
+        let triggers = triggers.iter().map(|t| t.parse().unwrap()).collect();
+        let ring = RingCapture::new(
+            dir.clone(),
+            TimeDelta::seconds(10),
+            TimeDelta::seconds(5),
+            triggers,
+            vec![],
+            false,
+        )
+        .unwrap();
+        (dir, ring)
+    }
+
+    fn saved(dir: &PathBuf) -> Vec<usize> {
+        let mut files: Vec<_> = fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .collect();
+        files.sort();
+        let counts = files
+            .iter()
+            .map(|f| session::load(f).unwrap().records.len())
+            .collect();
+        fs::remove_dir_all(dir).unwrap();
+        counts
+    }
+
+    #[test]
+    fn parses_triggers() {
+        assert_eq!("alert".parse(), Ok(Trigger::Alert));
+        assert_eq!(
+            "STATUSTEXT".parse(),
+            Ok(Trigger::Message("STATUSTEXT".to_string()))
+        );
+        assert!("BOGUS".parse::<Trigger>().is_err());
+    }
+
+    #[test]
+    fn saves_lead_up_and_tail() {
+        let (dir, mut ring) = capture(&["STATUSTEXT"]);
+        let t0 = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let heartbeat = || incoming(MavMessage::HEARTBEAT(HEARTBEAT_DATA::default()));
+        for s in 0..60 {
+            let at = t0 + TimeDelta::seconds(s);
+            assert_eq!(ring.record(at, &heartbeat()).unwrap(), None);
+            if s == 30 || s == 33 {
+                let text = incoming(MavMessage::STATUSTEXT(STATUSTEXT_DATA::default()));
+                let started = ring.record(at, &text).unwrap();
+                assert_eq!(started.is_some(), s == 30);
+            }
+        }
+        ring.flush(t0 + TimeDelta::seconds(60)).unwrap();
+        // 20..=30 before, the retrigger at 33 carries on to 38.
+        assert_eq!(saved(&dir), vec![11 + 1 + 8 + 1]);
+    }
+
+    #[test]
+    fn alerts_fire_once_per_new_alert() {
+        let (dir, mut ring) = capture(&["alert"]);
+        let t0 = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let heartbeat = incoming(MavMessage::HEARTBEAT(HEARTBEAT_DATA::default()));
+        ring.record(t0, &heartbeat).unwrap();
+        assert!(ring.check_alerts(t0, 1).unwrap().is_some());
+        assert!(ring.check_alerts(t0, 1).unwrap().is_none());
+        let later = t0 + TimeDelta::seconds(30);
+        ring.record(later, &heartbeat).unwrap();
+        assert!(ring.check_alerts(later, 1).unwrap().is_none());
+        assert!(ring.check_alerts(later, 2).unwrap().is_some());
+        ring.flush(later + TimeDelta::seconds(6)).unwrap();
+        assert_eq!(saved(&dir).len(), 2);
+    }
+}
@@ -0,0 +1,230 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use mavlink::common::{MavMessage, UavcanNodeHealth, UavcanNodeMode};
+
+use crate::{entries::Report, message::MavMsg};
+
+/// One DroneCAN/UAVCAN node as bridged onto MAVLink, keyed by the
+/// `(system, component)` the autopilot sends its status under.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    /// From UAVCAN_NODE_INFO; empty until it arrives.
+    pub name: String,
+    /// Hardware and software versions, e.g. `hw 1.0 sw 2.3`.
+    pub version: Option<String>,
+    pub uptime_sec: u32,
+    pub health: UavcanNodeHealth,
+    pub mode: UavcanNodeMode,
+    pub vendor_status: u16,
+    pub last_status: Option<DateTime<Utc>>,
+}
+
+/// Something about a node worth an event.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NodeReport {
+    Health {
+        node: (u8, u8),
+        from: UavcanNodeHealth,
+        to: UavcanNodeHealth,
+    },
+    /// Uptime went backwards: the node rebooted.
+    Restarted { node: (u8, u8), uptime_sec: u32 },
+}
+
+impl Report for NodeReport {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Health { .. } => "UAVCAN_HEALTH",
+            Self::Restarted { .. } => "UAVCAN_RESTART",
+        }
+    }
+
+    /// Worse health or a reboot; recovering to OK is only noted.
+    fn is_alert(&self) -> bool {
+        match self {
+            Self::Health { to, .. } => *to != UavcanNodeHealth::UAVCAN_NODE_HEALTH_OK,
+            Self::Restarted { .. } => true,
+        }
+    }
+
+    fn source(&self) -> (u8, u8) {
+        match self {
+            Self::Health { node, .. } | Self::Restarted { node, .. } => *node,
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Self::Health { from, to, .. } => {
+                format!("health: {} -> {}", health_name(*from), health_name(*to))
+            }
+            Self::Restarted { uptime_sec, .. } => format!("uptime reset to {uptime_sec}s"),
+        }
+    }
+}
+
+/// UAVCAN_NODE_STATUS and UAVCAN_NODE_INFO folded into one row per node.
+#[derive(Default)]
+pub struct UavcanNodes {
+    nodes: BTreeMap<(u8, u8), Node>,
+}
+
+impl UavcanNodes {
+    pub fn observe(&mut self, msg: &MavMsg) -> Option<NodeReport> {
+        let key = (msg.header.system_id, msg.header.component_id);
+        let entry = || Node {
+            name: String::new(),
+            version: None,
+            uptime_sec: 0,
+            health: UavcanNodeHealth::UAVCAN_NODE_HEALTH_OK,
+            mode: UavcanNodeMode::UAVCAN_NODE_MODE_OPERATIONAL,
+            vendor_status: 0,
+            last_status: None,
+        };
+        match &msg.msg {
+            MavMessage::UAVCAN_NODE_STATUS(d) => {
+                let node = self.nodes.entry(key).or_insert_with(entry);
+                let first = node.last_status.is_none();
+                let report = if !first && d.uptime_sec < node.uptime_sec {
+                    Some(NodeReport::Restarted {
+                        node: key,
+                        uptime_sec: d.uptime_sec,
+                    })
+                } else if !first && d.health != node.health {
+                    Some(NodeReport::Health {
+                        node: key,
+                        from: node.health,
+                        to: d.health,
+                    })
+                } else {
+                    None
+                };
+                node.uptime_sec = d.uptime_sec;
+                node.health = d.health;
+                node.mode = d.mode;
+                node.vendor_status = d.vendor_specific_status_code;
+                node.last_status = Some(msg.timestamp);
+                report
+            }
+            MavMessage::UAVCAN_NODE_INFO(d) => {
+                let node = self.nodes.entry(key).or_insert_with(entry);
+                node.name = d.name.to_str().unwrap_or_default().to_string();
+                node.version = Some(format!(
+                    "hw {}.{} sw {}.{}",
+                    d.hw_version_major, d.hw_version_minor, d.sw_version_major, d.sw_version_minor
+                ));
+                None
+            }
+            _ => None,
+        }
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = (&(u8, u8), &Node)> {
+        self.nodes.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+    }
+}
+
+pub fn health_name(health: UavcanNodeHealth) -> &'static str {
+    match health {
+        UavcanNodeHealth::UAVCAN_NODE_HEALTH_OK => "ok",
+        UavcanNodeHealth::UAVCAN_NODE_HEALTH_WARNING => "warning",
+        UavcanNodeHealth::UAVCAN_NODE_HEALTH_ERROR => "error",
+        UavcanNodeHealth::UAVCAN_NODE_HEALTH_CRITICAL => "critical",
+    }
+}
+
+pub fn mode_name(mode: UavcanNodeMode) -> &'static str {
+    match mode {
+        UavcanNodeMode::UAVCAN_NODE_MODE_OPERATIONAL => "operational",
+        UavcanNodeMode::UAVCAN_NODE_MODE_INITIALIZATION => "initialization",
+        UavcanNodeMode::UAVCAN_NODE_MODE_MAINTENANCE => "maintenance",
+        UavcanNodeMode::UAVCAN_NODE_MODE_SOFTWARE_UPDATE => "software update",
+        UavcanNodeMode::UAVCAN_NODE_MODE_OFFLINE => "offline",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::{
+        MavHeader,
+        common::{UAVCAN_NODE_INFO_DATA, UAVCAN_NODE_STATUS_DATA},
+    };
+
+    use super::*;
+
+    fn from(comp: u8, msg: MavMessage) -> MavMsg {
+        let header = MavHeader {
+            system_id: 1,
+            component_id: comp,
+            sequence: 0,
+        };
+        MavMsg::new(header, msg, 0)
+    }
+
+    fn status(comp: u8, uptime_sec: u32, health: UavcanNodeHealth) -> MavMsg {
+        from(
+            comp,
+            MavMessage::UAVCAN_NODE_STATUS(UAVCAN_NODE_STATUS_DATA {
+                uptime_sec,
+                health,
+                ..Default::default()
+            }),
+        )
+    }
+
+    #[test]
+    fn status_and_info_share_a_row() {
+        let mut nodes = UavcanNodes::default();
+        assert_eq!(
+            nodes.observe(&status(125, 10, UavcanNodeHealth::UAVCAN_NODE_HEALTH_OK)),
+            None
+        );
+        let mut name = [0u8; 80];
+        name[..12].copy_from_slice(b"org.ardu.gps");
+        nodes.observe(&from(
+            125,
+            MavMessage::UAVCAN_NODE_INFO(UAVCAN_NODE_INFO_DATA {
+                name: name.into(),
+                hw_version_major: 1,
+                sw_version_major: 2,
+                sw_version_minor: 3,
+                ..Default::default()
+            }),
+        ));
+        nodes.observe(&status(126, 5, UavcanNodeHealth::UAVCAN_NODE_HEALTH_OK));
+        let rows: Vec<_> = nodes.nodes().collect();
+        assert_eq!(rows.len(), 2);
+        let (key, node) = rows[0];
+        assert_eq!(*key, (1, 125));
+        assert_eq!(node.name, "org.ardu.gps");
+        assert_eq!(node.version.as_deref(), Some("hw 1.0 sw 2.3"));
+        assert_eq!(node.uptime_sec, 10);
+    }
+
+    #[test]
+    fn reports_health_changes_and_reboots() {
+        let mut nodes = UavcanNodes::default();
+        nodes.observe(&status(125, 10, UavcanNodeHealth::UAVCAN_NODE_HEALTH_OK));
+        let worse = nodes
+            .observe(&status(125, 11, UavcanNodeHealth::UAVCAN_NODE_HEALTH_ERROR))
+            .unwrap();
+        assert!(worse.is_alert());
+        assert_eq!(worse.describe(), "health: ok -> error");
+        let better = nodes
+            .observe(&status(125, 12, UavcanNodeHealth::UAVCAN_NODE_HEALTH_OK))
+            .unwrap();
+        assert!(!better.is_alert());
+        assert_eq!(
+            nodes.observe(&status(125, 1, UavcanNodeHealth::UAVCAN_NODE_HEALTH_OK)),
+            Some(NodeReport::Restarted {
+                node: (1, 125),
+                uptime_sec: 1
+            })
+        );
+    }
+}
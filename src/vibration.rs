@@ -0,0 +1,233 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use mavlink::common::MavMessage;
+
+use crate::{entries::Report, message::MavMsg, radio::HISTORY};
+
+/// Clipping is reported at most this often per vehicle.
+const CLIPPING_EVERY: TimeDelta = TimeDelta::seconds(10);
+
+/// One VIBRATION.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VibrationSample {
+    pub at: DateTime<Utc>,
+    /// Vibration levels in m/s², x/y/z.
+    pub levels: [f32; 3],
+    /// Accelerometer clipping counts since boot, per IMU.
+    pub clipping: [u32; 3],
+}
+
+impl VibrationSample {
+    pub fn peak(&self) -> f32 {
+        self.levels.iter().copied().fold(0.0, f32::max)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Vehicle {
+    /// Over the last [`HISTORY`], oldest first.
+    pub samples: VecDeque<VibrationSample>,
+    high: bool,
+    clipping_reported: Option<DateTime<Utc>>,
+}
+
+impl Vehicle {
+    pub fn latest(&self) -> Option<&VibrationSample> {
+        self.samples.back()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VibrationEvent {
+    /// An axis went above the warning level.
+    High { level: f32 },
+    /// All axes back below it.
+    Normal { level: f32 },
+    /// An accelerometer clipped since the last sample.
+    Clipping { imu: usize, total: u32 },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VibrationReport {
+    pub vehicle: u8,
+    pub event: VibrationEvent,
+    pub warn: f32,
+}
+
+impl Report for VibrationReport {
+    fn name(&self) -> &'static str {
+        match self.event {
+            VibrationEvent::High { .. } => "VIBRATION_HIGH",
+            VibrationEvent::Normal { .. } => "VIBRATION_NORMAL",
+            VibrationEvent::Clipping { .. } => "VIBRATION_CLIPPING",
+        }
+    }
+
+    fn is_alert(&self) -> bool {
+        !matches!(self.event, VibrationEvent::Normal { .. })
+    }
+
+    fn source(&self) -> (u8, u8) {
+        (self.vehicle, 0)
+    }
+
+    fn describe(&self) -> String {
+        match self.event {
+            VibrationEvent::High { level } | VibrationEvent::Normal { level } => {
+                format!("peak: {level:.1} m/s², warn: {:.1} m/s²", self.warn)
+            }
+            VibrationEvent::Clipping { imu, total } => format!("imu: {imu}, clipped: {total}"),
+        }
+    }
+}
+
+/// VIBRATION history per vehicle, with an alert when a level crosses `warn`
+/// or an accelerometer clips.
+pub struct VibrationTracker {
+    vehicles: BTreeMap<u8, Vehicle>,
+    /// Warning level in m/s².
+    warn: f32,
+}
+
+impl Default for VibrationTracker {
+    fn default() -> Self {
+        Self::new(30.0)
+    }
+}
+
+impl VibrationTracker {
+    pub fn new(warn: f32) -> Self {
+        Self {
+            vehicles: BTreeMap::new(),
+            warn,
+        }
+    }
+
+    pub fn observe(&mut self, msg: &MavMsg) -> Option<VibrationReport> {
+        let MavMessage::VIBRATION(d) = &msg.msg else {
+            return None;
+        };
+        let sample = VibrationSample {
+            at: msg.timestamp,
+            levels: [d.vibration_x, d.vibration_y, d.vibration_z],
+            clipping: [d.clipping_0, d.clipping_1, d.clipping_2],
+        };
+        let vehicle = self.vehicles.entry(msg.header.system_id).or_default();
+        let previous = vehicle.latest().copied();
+        vehicle.samples.push_back(sample);
+        while vehicle
+            .samples
+            .front()
+            .is_some_and(|s| msg.timestamp - s.at > HISTORY)
+        {
+            vehicle.samples.pop_front();
+        }
+
+        let level = sample.peak();
+        let high = level > self.warn;
+        let event = if high != vehicle.high {
+            vehicle.high = high;
+            Some(if high {
+                VibrationEvent::High { level }
+            } else {
+                VibrationEvent::Normal { level }
+            })
+        } else {
+            let clipped =
+                previous.and_then(|p| (0..3).find(|&i| sample.clipping[i] > p.clipping[i]));
+            let quiet = vehicle
+                .clipping_reported
+                .is_none_or(|at| msg.timestamp - at >= CLIPPING_EVERY);
+            clipped.filter(|_| quiet).map(|imu| {
+                vehicle.clipping_reported = Some(msg.timestamp);
+                VibrationEvent::Clipping {
+                    imu,
+                    total: sample.clipping[imu],
+                }
+            })
+        };
+        event.map(|event| VibrationReport {
+            vehicle: msg.header.system_id,
+            event,
+            warn: self.warn,
+        })
+    }
+
+    pub fn warn(&self) -> f32 {
+        self.warn
+    }
+
+    pub fn vehicle(&self, sys_id: u8) -> Option<&Vehicle> {
+        self.vehicles.get(&sys_id)
+    }
+
+    pub fn clear(&mut self) {
+        self.vehicles.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::{MavHeader, common::VIBRATION_DATA};
+
+    use super::*;
+
+    fn vibration(at: DateTime<Utc>, z: f32, clipping_1: u32) -> MavMsg {
+        let mut msg = MavMsg::new(
+            MavHeader::default(),
+            MavMessage::VIBRATION(VIBRATION_DATA {
+                vibration_x: 5.0,
+                vibration_y: 6.0,
+                vibration_z: z,
+                clipping_1,
+                ..Default::default()
+            }),
+            0,
+        );
+        msg.timestamp = at;
+        msg
+    }
+
+    #[test]
+    fn warns_on_crossing_the_level() {
+        let mut tracker = VibrationTracker::new(30.0);
+        let t0 = Utc::now();
+        let names: Vec<Option<&str>> = [10.0, 35.0, 40.0, 12.0]
+            .iter()
+            .enumerate()
+            .map(|(i, &z)| {
+                let at = t0 + TimeDelta::seconds(i as i64);
+                tracker.observe(&vibration(at, z, 0)).map(|r| r.name())
+            })
+            .collect();
+        assert_eq!(
+            names,
+            vec![None, Some("VIBRATION_HIGH"), None, Some("VIBRATION_NORMAL")]
+        );
+        let vehicle = tracker.vehicle(MavHeader::default().system_id).unwrap();
+        assert_eq!(vehicle.samples.len(), 4);
+        assert_eq!(vehicle.latest().unwrap().peak(), 12.0);
+    }
+
+    #[test]
+    fn clipping_is_reported_at_most_every_ten_seconds() {
+        let mut tracker = VibrationTracker::default();
+        let t0 = Utc::now();
+        assert!(tracker.observe(&vibration(t0, 5.0, 0)).is_none());
+        let report = tracker
+            .observe(&vibration(t0 + TimeDelta::seconds(1), 5.0, 3))
+            .unwrap();
+        assert_eq!(report.event, VibrationEvent::Clipping { imu: 1, total: 3 });
+        assert!(
+            tracker
+                .observe(&vibration(t0 + TimeDelta::seconds(2), 5.0, 9))
+                .is_none()
+        );
+        assert!(
+            tracker
+                .observe(&vibration(t0 + TimeDelta::seconds(12), 5.0, 12))
+                .is_some()
+        );
+    }
+}
@@ -0,0 +1,359 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use mavlink::common::{MavCmd, MavMessage, MavResult, MavVtolState};
+
+use crate::{entries::Report, message::MavMsg};
+
+/// Transitions kept per vehicle for the VTOL panel.
+const KEPT: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TransitionOutcome {
+    InProgress,
+    Completed,
+    /// Ended in the configuration it started from, or another one.
+    Aborted {
+        ended_in: MavVtolState,
+    },
+    /// MAV_CMD_DO_VTOL_TRANSITION was refused before anything moved.
+    Rejected(MavResult),
+}
+
+/// One transition towards multicopter or fixed-wing flight.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transition {
+    /// `MAV_VTOL_STATE_MC` or `MAV_VTOL_STATE_FW`.
+    pub to: MavVtolState,
+    pub started: DateTime<Utc>,
+    pub ended: Option<DateTime<Utc>>,
+    /// Whether a MAV_CMD_DO_VTOL_TRANSITION asked for it, as opposed to the
+    /// autopilot (mission item, failsafe, RC switch).
+    pub commanded: bool,
+    pub outcome: TransitionOutcome,
+}
+
+impl Transition {
+    pub fn duration(&self) -> Option<TimeDelta> {
+        Some(self.ended? - self.started)
+    }
+}
+
+#[derive(Debug)]
+pub struct Vehicle {
+    pub state: MavVtolState,
+    pub since: DateTime<Utc>,
+    /// Oldest first.
+    pub transitions: VecDeque<Transition>,
+}
+
+/// A transition starting, finishing or failing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VtolReport {
+    pub vehicle: u8,
+    pub transition: Transition,
+}
+
+impl Report for VtolReport {
+    fn name(&self) -> &'static str {
+        match self.transition.outcome {
+            TransitionOutcome::InProgress => "VTOL_TRANSITION_START",
+            TransitionOutcome::Completed => "VTOL_TRANSITION_DONE",
+            TransitionOutcome::Aborted { .. } | TransitionOutcome::Rejected(_) => {
+                "VTOL_TRANSITION_FAILED"
+            }
+        }
+    }
+
+    fn source(&self) -> (u8, u8) {
+        (self.vehicle, 0)
+    }
+
+    fn is_alert(&self) -> bool {
+        matches!(
+            self.transition.outcome,
+            TransitionOutcome::Aborted { .. } | TransitionOutcome::Rejected(_)
+        )
+    }
+
+    fn describe(&self) -> String {
+        let t = &self.transition;
+        let mut text = format!("to: {}", state_name(t.to));
+        if t.commanded {
+            text.push_str(", commanded");
+        }
+        match t.outcome {
+            TransitionOutcome::InProgress | TransitionOutcome::Completed => {}
+            TransitionOutcome::Aborted { ended_in } => {
+                text.push_str(&format!(", ended in: {}", state_name(ended_in)))
+            }
+            TransitionOutcome::Rejected(result) => text.push_str(&format!(", result: {result:?}")),
+        }
+        if let Some(took) = t.duration() {
+            text.push_str(&format!(
+                ", took: {:.1}s",
+                took.num_milliseconds() as f64 / 1000.0
+            ));
+        }
+        text
+    }
+}
+
+/// Follows EXTENDED_SYS_STATE `vtol_state` and MAV_CMD_DO_VTOL_TRANSITION
+/// per vehicle.
+#[derive(Default)]
+pub struct VtolTracker {
+    vehicles: BTreeMap<u8, Vehicle>,
+    /// Transitions commanded but not yet visible in `vtol_state`, by target.
+    commanded: BTreeMap<u8, (MavVtolState, DateTime<Utc>)>,
+}
+
+impl VtolTracker {
+    pub fn observe(&mut self, msg: &MavMsg) -> Option<VtolReport> {
+        match &msg.msg {
+            MavMessage::COMMAND_LONG(d) if d.command == MavCmd::MAV_CMD_DO_VTOL_TRANSITION => {
+                self.command(d.target_system, d.param1, msg.timestamp);
+                None
+            }
+            MavMessage::COMMAND_INT(d) if d.command == MavCmd::MAV_CMD_DO_VTOL_TRANSITION => {
+                self.command(d.target_system, d.param1, msg.timestamp);
+                None
+            }
+            MavMessage::COMMAND_ACK(d) if d.command == MavCmd::MAV_CMD_DO_VTOL_TRANSITION => {
+                let sys = msg.header.system_id;
+                if matches!(
+                    d.result,
+                    MavResult::MAV_RESULT_ACCEPTED | MavResult::MAV_RESULT_IN_PROGRESS
+                ) {
+                    return None;
+                }
+                let (to, started) = self.commanded.remove(&sys)?;
+                let transition = Transition {
+                    to,
+                    started,
+                    ended: Some(msg.timestamp),
+                    commanded: true,
+                    outcome: TransitionOutcome::Rejected(d.result),
+                };
+                if let Some(vehicle) = self.vehicles.get_mut(&sys) {
+                    push_bounded(&mut vehicle.transitions, transition.clone());
+                }
+                Some(VtolReport {
+                    vehicle: sys,
+                    transition,
+                })
+            }
+            MavMessage::EXTENDED_SYS_STATE(d) => {
+                let sys = msg.header.system_id;
+                let state = d.vtol_state;
+                if state == MavVtolState::MAV_VTOL_STATE_UNDEFINED {
+                    return None;
+                }
+                let Some(vehicle) = self.vehicles.get_mut(&sys) else {
+                    self.vehicles.insert(
+                        sys,
+                        Vehicle {
+                            state,
+                            since: msg.timestamp,
+                            transitions: VecDeque::new(),
+                        },
+                    );
+                    return None;
+                };
+                if vehicle.state == state {
+                    return None;
+                }
+                vehicle.state = state;
+                vehicle.since = msg.timestamp;
+                let target = match state {
+                    MavVtolState::MAV_VTOL_STATE_TRANSITION_TO_FW => {
+                        Some(MavVtolState::MAV_VTOL_STATE_FW)
+                    }
+                    MavVtolState::MAV_VTOL_STATE_TRANSITION_TO_MC => {
+                        Some(MavVtolState::MAV_VTOL_STATE_MC)
+                    }
+                    _ => None,
+                };
+                let open = vehicle
+                    .transitions
+                    .back_mut()
+                    .filter(|t| t.outcome == TransitionOutcome::InProgress);
+                let transition = match (open, target) {
+                    // Reaching a steady state ends the transition under way.
+                    (Some(t), None) => {
+                        t.ended = Some(msg.timestamp);
+                        t.outcome = if t.to == state {
+                            TransitionOutcome::Completed
+                        } else {
+                            TransitionOutcome::Aborted { ended_in: state }
+                        };
+                        t.clone()
+                    }
+                    // Reversing mid-transition aborts it and starts another.
+                    (open, Some(to)) => {
+                        let mut aborted = None;
+                        if let Some(t) = open {
+                            t.ended = Some(msg.timestamp);
+                            t.outcome = TransitionOutcome::Aborted { ended_in: state };
+                            aborted = Some(t.clone());
+                        }
+                        let commanded = self
+                            .commanded
+                            .remove(&sys)
+                            .is_some_and(|(wanted, _)| wanted == to);
+                        let started = Transition {
+                            to,
+                            started: msg.timestamp,
+                            ended: None,
+                            commanded,
+                            outcome: TransitionOutcome::InProgress,
+                        };
+                        push_bounded(&mut vehicle.transitions, started.clone());
+                        aborted.unwrap_or(started)
+                    }
+                    // A jump between steady states without a transition
+                    // state in between is just the new configuration.
+                    (None, None) => return None,
+                };
+                Some(VtolReport {
+                    vehicle: sys,
+                    transition,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    fn command(&mut self, target: u8, param1: f32, at: DateTime<Utc>) {
+        let to = match param1 as u32 {
+            3 => MavVtolState::MAV_VTOL_STATE_MC,
+            4 => MavVtolState::MAV_VTOL_STATE_FW,
+            _ => return,
+        };
+        self.commanded.insert(target, (to, at));
+    }
+
+    pub fn vehicles(&self) -> impl Iterator<Item = (&u8, &Vehicle)> {
+        self.vehicles.iter()
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+fn push_bounded<T>(list: &mut VecDeque<T>, item: T) {
+    if list.len() == KEPT {
+        list.pop_front();
+    }
+    list.push_back(item);
+}
+
+pub fn state_name(state: MavVtolState) -> &'static str {
+    match state {
+        MavVtolState::MAV_VTOL_STATE_UNDEFINED => "undefined",
+        MavVtolState::MAV_VTOL_STATE_TRANSITION_TO_FW => "transition to FW",
+        MavVtolState::MAV_VTOL_STATE_TRANSITION_TO_MC => "transition to MC",
+        MavVtolState::MAV_VTOL_STATE_MC => "MC",
+        MavVtolState::MAV_VTOL_STATE_FW => "FW",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::{
+        MavHeader,
+        common::{COMMAND_ACK_DATA, COMMAND_LONG_DATA, EXTENDED_SYS_STATE_DATA},
+    };
+
+    use super::*;
+
+    fn at(sys_id: u8, msg: MavMessage, secs: i64) -> MavMsg {
+        let header = MavHeader {
+            system_id: sys_id,
+            component_id: 1,
+            sequence: 0,
+        };
+        MavMsg {
+            timestamp: DateTime::from_timestamp(1_700_000_000 + secs, 0).unwrap(),
+            ..MavMsg::new(header, msg, 0)
+        }
+    }
+
+    fn state(vtol_state: MavVtolState, secs: i64) -> MavMsg {
+        at(
+            1,
+            MavMessage::EXTENDED_SYS_STATE(EXTENDED_SYS_STATE_DATA {
+                vtol_state,
+                ..Default::default()
+            }),
+            secs,
+        )
+    }
+
+    fn transition(to: f32, secs: i64) -> MavMsg {
+        at(
+            255,
+            MavMessage::COMMAND_LONG(COMMAND_LONG_DATA {
+                command: MavCmd::MAV_CMD_DO_VTOL_TRANSITION,
+                target_system: 1,
+                param1: to,
+                ..Default::default()
+            }),
+            secs,
+        )
+    }
+
+    #[test]
+    fn commanded_transition_starts_and_completes() {
+        let mut vtol = VtolTracker::default();
+        assert_eq!(
+            vtol.observe(&state(MavVtolState::MAV_VTOL_STATE_MC, 0)),
+            None
+        );
+        vtol.observe(&transition(4.0, 10));
+        let start = vtol
+            .observe(&state(MavVtolState::MAV_VTOL_STATE_TRANSITION_TO_FW, 11))
+            .unwrap();
+        assert_eq!(start.name(), "VTOL_TRANSITION_START");
+        assert!(start.transition.commanded);
+        let done = vtol
+            .observe(&state(MavVtolState::MAV_VTOL_STATE_FW, 16))
+            .unwrap();
+        assert_eq!(done.name(), "VTOL_TRANSITION_DONE");
+        assert_eq!(done.describe(), "to: FW, commanded, took: 5.0s");
+        let (_, vehicle) = vtol.vehicles().next().unwrap();
+        assert_eq!(vehicle.state, MavVtolState::MAV_VTOL_STATE_FW);
+        assert_eq!(vehicle.transitions.len(), 1);
+    }
+
+    #[test]
+    fn back_transitions_and_rejections_fail() {
+        let mut vtol = VtolTracker::default();
+        vtol.observe(&state(MavVtolState::MAV_VTOL_STATE_MC, 0));
+        vtol.observe(&state(MavVtolState::MAV_VTOL_STATE_TRANSITION_TO_FW, 1));
+        let back = vtol
+            .observe(&state(MavVtolState::MAV_VTOL_STATE_MC, 3))
+            .unwrap();
+        assert!(back.is_alert());
+        assert_eq!(
+            back.transition.outcome,
+            TransitionOutcome::Aborted {
+                ended_in: MavVtolState::MAV_VTOL_STATE_MC
+            }
+        );
+
+        vtol.observe(&transition(4.0, 10));
+        let refused = vtol
+            .observe(&at(
+                1,
+                MavMessage::COMMAND_ACK(COMMAND_ACK_DATA {
+                    command: MavCmd::MAV_CMD_DO_VTOL_TRANSITION,
+                    result: MavResult::MAV_RESULT_TEMPORARILY_REJECTED,
+                }),
+                10,
+            ))
+            .unwrap();
+        assert_eq!(refused.name(), "VTOL_TRANSITION_FAILED");
+    }
+}
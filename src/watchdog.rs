@@ -0,0 +1,293 @@
+use std::{collections::BTreeSet, str::FromStr};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+
+use crate::{
+    entries::{json_escape, parse_fields},
+    message::{self, MavMsg},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Op {
+    /// Longest first, so `>=` is not read as `>`.
+    const ALL: [(&'static str, Op); 6] = [
+        ("==", Op::Eq),
+        ("!=", Op::Ne),
+        ("<=", Op::Le),
+        (">=", Op::Ge),
+        ("<", Op::Lt),
+        (">", Op::Gt),
+    ];
+
+    /// Numbers compare by value; anything else, e.g. an enum name, only
+    /// with `==`/`!=`, against any of `|`-separated alternatives.
+    fn holds(self, value: &str, expected: &str) -> bool {
+        let value = value.trim_matches('"');
+        if let (Ok(v), Ok(e)) = (value.parse::<f64>(), expected.parse::<f64>()) {
+            return match self {
+                Op::Eq => v == e,
+                Op::Ne => v != e,
+                Op::Lt => v < e,
+                Op::Le => v <= e,
+                Op::Gt => v > e,
+                Op::Ge => v >= e,
+            };
+        }
+        let any = expected.split('|').any(|e| e == value);
+        match self {
+            Op::Eq => any,
+            Op::Ne => !any,
+            _ => false,
+        }
+    }
+}
+
+/// A `--watch` condition: `NAME` for a message arriving at all, or
+/// `NAME.field OP value` with OP one of `==`, `!=`, `<`, `<=`, `>`, `>=`,
+/// e.g. `GPS_RAW_INT.satellites_visible >= 6`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Condition {
+    text: String,
+    name: String,
+    test: Option<(String, Op, String)>,
+}
+
+impl FromStr for Condition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let text = s.trim().to_string();
+        let (subject, test) = match Op::ALL
+            .iter()
+            .find_map(|&(op, o)| Some((s.find(op)?, op, o)))
+        {
+            Some((at, op, o)) => (&s[..at], Some((o, s[at + op.len()..].trim()))),
+            None => (s, None),
+        };
+        let (name, field) = match subject.trim().split_once('.') {
+            Some((name, field)) => (name, Some(field.trim())),
+            None => (subject.trim(), None),
+        };
+        if !message::is_message_name(name) {
+            return Err(format!("{s:?}: unknown message type {name:?}"));
+        }
+        let test = match (field, test) {
+            (None, None) => None,
+            (Some(field), Some((op, value))) if !field.is_empty() && !value.is_empty() => {
+                Some((field.to_string(), op, value.to_string()))
+            }
+            _ => {
+                return Err(format!(
+                    "{s:?}: expected NAME or NAME.field OP value (e.g. GPS_RAW_INT.satellites_visible >= 6)"
+                ));
+            }
+        };
+        Ok(Self {
+            text,
+            name: name.to_string(),
+            test,
+        })
+    }
+}
+
+impl Condition {
+    /// The value that satisfies the condition in `msg`, empty when only the
+    /// message was asked for.
+    fn check(&self, msg: &MavMsg) -> Option<String> {
+        if msg.msg_type() != self.name {
+            return None;
+        }
+        let Some((field, op, expected)) = &self.test else {
+            return Some(String::new());
+        };
+        let fields = msg.fields();
+        let value = parse_fields(&fields)
+            .into_iter()
+            .find(|(k, _)| *k == field.as_str())?
+            .1;
+        op.holds(value, expected).then(|| value.to_string())
+    }
+}
+
+/// How a watchdog run ended, and its exit status.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
+    Met,
+    TimedOut,
+    /// Every link ended first.
+    Closed,
+}
+
+impl Outcome {
+    pub fn code(self) -> i32 {
+        match self {
+            Outcome::Met => 0,
+            Outcome::TimedOut => 1,
+            Outcome::Closed => 2,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Outcome::Met => "met",
+            Outcome::TimedOut => "timeout",
+            Outcome::Closed => "closed",
+        }
+    }
+}
+
+struct Met {
+    at: DateTime<Utc>,
+    sys_id: u8,
+    comp_id: u8,
+    value: String,
+}
+
+/// `--watch`: headless, until every condition has held at least once or
+/// the time runs out, for hardware-in-the-loop rigs that need a pass or
+/// fail from the link.
+pub struct Watchdog {
+    conditions: Vec<(Condition, Option<Met>)>,
+    started: DateTime<Utc>,
+    messages: u64,
+    systems: BTreeSet<u8>,
+}
+
+impl Watchdog {
+    pub fn new(conditions: Vec<Condition>, started: DateTime<Utc>) -> Self {
+        Self {
+            conditions: conditions.into_iter().map(|c| (c, None)).collect(),
+            started,
+            messages: 0,
+            systems: BTreeSet::new(),
+        }
+    }
+
+    /// Checks the conditions still open against `msg`; `true` once all
+    /// have been met.
+    pub fn observe(&mut self, msg: &MavMsg) -> bool {
+        self.messages += 1;
+        self.systems.insert(msg.header.system_id);
+        for (condition, met) in &mut self.conditions {
+            if met.is_some() {
+                continue;
+            }
+            if let Some(value) = condition.check(msg) {
+                *met = Some(Met {
+                    at: msg.timestamp,
+                    sys_id: msg.header.system_id,
+                    comp_id: msg.header.component_id,
+                    value,
+                });
+            }
+        }
+        self.conditions.iter().all(|(_, met)| met.is_some())
+    }
+
+    /// One JSON object: the outcome, and when and by what each condition
+    /// was met.
+    pub fn summary(&self, outcome: Outcome, now: DateTime<Utc>) -> String {
+        let secs = |t: DateTime<Utc>| (t - self.started).num_milliseconds() as f64 / 1000.0;
+        let conditions: Vec<String> = self
+            .conditions
+            .iter()
+            .map(|(condition, met)| match met {
+                Some(met) => format!(
+                    "{{\"condition\":\"{}\",\"met\":true,\"after_secs\":{:.3},\"sys\":{},\"comp\":{},\"value\":\"{}\"}}",
+                    json_escape(&condition.text),
+                    secs(met.at),
+                    met.sys_id,
+                    met.comp_id,
+                    json_escape(&met.value)
+                ),
+                None => format!(
+                    "{{\"condition\":\"{}\",\"met\":false}}",
+                    json_escape(&condition.text)
+                ),
+            })
+            .collect();
+        let systems: Vec<String> = self.systems.iter().map(u8::to_string).collect();
+        format!(
+            "{{\"result\":\"{}\",\"started\":\"{}\",\"elapsed_secs\":{:.3},\"messages\":{},\"systems\":[{}],\"conditions\":[{}]}}",
+            outcome.label(),
+            self.started.to_rfc3339_opts(SecondsFormat::Millis, true),
+            secs(now),
+            self.messages,
+            systems.join(","),
+            conditions.join(",")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeDelta;
+    use mavlink::{
+        MavHeader,
+        common::{GPS_RAW_INT_DATA, GpsFixType, HEARTBEAT_DATA, MavMessage},
+    };
+
+    use super::*;
+
+    fn msg(msg: MavMessage, at: DateTime<Utc>) -> MavMsg {
+        let mut msg = MavMsg::new(MavHeader::default(), msg, 0);
+        msg.timestamp = at;
+        msg
+    }
+
+    #[test]
+    fn parses_conditions() {
+        let c: Condition = "GPS_RAW_INT.satellites_visible >= 6".parse().unwrap();
+        assert_eq!(
+            c.test,
+            Some(("satellites_visible".to_string(), Op::Ge, "6".to_string()))
+        );
+        assert_eq!("HEARTBEAT".parse::<Condition>().unwrap().test, None);
+        assert!("NOT_A_MESSAGE".parse::<Condition>().is_err());
+        assert!("GPS_RAW_INT.fix_type".parse::<Condition>().is_err());
+        assert!("GPS_RAW_INT >= 3".parse::<Condition>().is_err());
+    }
+
+    #[test]
+    fn waits_for_every_condition() {
+        let t0 = Utc::now();
+        let conditions = [
+            "HEARTBEAT",
+            "GPS_RAW_INT.fix_type == GPS_FIX_TYPE_3D_FIX|GPS_FIX_TYPE_RTK_FIXED",
+            "GPS_RAW_INT.satellites_visible > 5",
+        ]
+        .map(|c| c.parse().unwrap())
+        .to_vec();
+        let mut watchdog = Watchdog::new(conditions, t0);
+        let gps = |fix_type, satellites_visible| {
+            MavMessage::GPS_RAW_INT(GPS_RAW_INT_DATA {
+                fix_type,
+                satellites_visible,
+                ..Default::default()
+            })
+        };
+        assert!(!watchdog.observe(&msg(MavMessage::HEARTBEAT(HEARTBEAT_DATA::default()), t0)));
+        assert!(!watchdog.observe(&msg(gps(GpsFixType::GPS_FIX_TYPE_2D_FIX, 8), t0)));
+        let later = t0 + TimeDelta::milliseconds(1500);
+        assert!(watchdog.observe(&msg(gps(GpsFixType::GPS_FIX_TYPE_RTK_FIXED, 4), later)));
+
+        let summary = watchdog.summary(Outcome::Met, later);
+        assert!(summary.starts_with("{\"result\":\"met\""), "{summary}");
+        assert!(summary.contains("\"messages\":3,\"systems\":[255]"));
+        assert!(summary.contains(
+            "\"met\":true,\"after_secs\":1.500,\"sys\":255,\"comp\":0,\"value\":\"GPS_FIX_TYPE_RTK_FIXED\""
+        ));
+        assert!(summary.contains(
+            "\"condition\":\"GPS_RAW_INT.satellites_visible > 5\",\"met\":true,\"after_secs\":0.000"
+        ));
+    }
+}
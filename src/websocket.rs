@@ -0,0 +1,259 @@
+use std::{
+    io::{self, ErrorKind, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        Arc, Mutex,
+        mpsc::{self, Receiver, RecvTimeoutError, SyncSender, TrySendError},
+    },
+    thread,
+    time::Duration,
+};
+
+use tungstenite::{Message, Utf8Bytes, WebSocket};
+
+use crate::{export, message::Incoming};
+
+/// A client that stops reading for this long is dropped, so it cannot hold
+/// up the others.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long a new connection may take to send its upgrade request.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Messages waiting for a client; one that falls further behind than this
+/// is dropped rather than queueing without bound.
+const CLIENT_QUEUE: usize = 4096;
+
+/// How often a client's thread looks for pings and close frames while
+/// there is nothing to send.
+const POLL: Duration = Duration::from_millis(50);
+
+type Clients = Mutex<Vec<(SocketAddr, SyncSender<Utf8Bytes>)>>;
+
+/// `--serve-ws`: pushes every decoded message to WebSocket clients as a text
+/// frame holding the same JSON object `--headless` prints, while the TUI
+/// runs. Pings are answered and close frames honoured; any other message a
+/// client sends is ignored.
+pub struct WsServer {
+    addr: SocketAddr,
+    clients: Arc<Clients>,
+}
+
+impl WsServer {
+    pub fn bind(listen: &str) -> io::Result<Self> {
+        let listener = TcpListener::bind(listen)?;
+        let addr = listener.local_addr()?;
+        let clients = Arc::<Clients>::default();
+
+        let accepting = Arc::clone(&clients);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        tracing::warn!(error = %e, "websocket accept failed");
+                        continue;
+                    }
+                };
+                let accepting = Arc::clone(&accepting);
+                // Each client is read and written from its own thread, so a
+                // slow one cannot hold up the next.
+                thread::spawn(move || serve_client(stream, &accepting));
+            }
+        });
+
+        tracing::info!(%addr, "websocket server listening");
+        Ok(Self { addr, clients })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Queues `incoming` for every client, if it is a decoded message.
+    pub fn publish(&self, incoming: &Incoming) {
+        let Some(line) = export::ndjson(incoming) else {
+            return;
+        };
+        let text = Utf8Bytes::from(line);
+        self.clients
+            .lock()
+            .unwrap()
+            .retain(|(peer, queue)| match queue.try_send(text.clone()) {
+                Ok(()) => true,
+                Err(TrySendError::Full(_)) => {
+                    tracing::warn!(%peer, "websocket client fell behind, dropped");
+                    false
+                }
+                Err(TrySendError::Disconnected(_)) => false,
+            });
+    }
+}
+
+/// Upgrades the connection, then sends the client its queued messages and
+/// answers its control frames until either side closes it.
+fn serve_client(stream: TcpStream, clients: &Clients) {
+    let peer = match stream.peer_addr() {
+        Ok(peer) => peer,
+        Err(e) => {
+            tracing::warn!(error = %e, "websocket accept failed");
+            return;
+        }
+    };
+    let mut ws = match handshake(stream) {
+        Ok(ws) => ws,
+        Err(e) => {
+            tracing::warn!(%peer, error = %e, "websocket handshake failed");
+            return;
+        }
+    };
+    let (tx, queue) = mpsc::sync_channel(CLIENT_QUEUE);
+    clients.lock().unwrap().push((peer, tx));
+    tracing::info!(%peer, "websocket client connected");
+    match run(&mut ws, &queue) {
+        Ok(()) => tracing::info!(%peer, "websocket client disconnected"),
+        Err(e) => tracing::info!(%peer, error = %e, "websocket client dropped"),
+    }
+}
+
+/// Accepts a WebSocket upgrade; anything else gets a 400 and `Err`.
+fn handshake(stream: TcpStream) -> io::Result<WebSocket<TcpStream>> {
+    stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+    let mut reply = stream.try_clone()?;
+    let ws = tungstenite::accept(stream).map_err(|e| {
+        // Best effort: the client may already be gone.
+        let _ = reply.write_all(b"HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\n\r\n");
+        io::Error::new(ErrorKind::InvalidData, e.to_string())
+    })?;
+    // From here reads only wait long enough to notice control frames.
+    ws.get_ref().set_read_timeout(Some(POLL))?;
+    Ok(ws)
+}
+
+fn run(ws: &mut WebSocket<TcpStream>, queue: &Receiver<Utf8Bytes>) -> tungstenite::Result<()> {
+    loop {
+        match queue.recv_timeout(POLL) {
+            Ok(text) => {
+                ws.write(Message::Text(text))?;
+                while let Ok(text) = queue.try_recv() {
+                    ws.write(Message::Text(text))?;
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            // Dropped for falling behind, or the server is gone.
+            Err(RecvTimeoutError::Disconnected) => {
+                return ws.close(None).and_then(|()| ws.flush());
+            }
+        }
+        // Pongs and the reply to a close frame are queued by `read` and go
+        // out with this flush.
+        match ws.read().and_then(|_| ws.flush()) {
+            Ok(()) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) =>
+            {
+                ws.flush()?;
+            }
+            Err(tungstenite::Error::ConnectionClosed) => return Ok(()),
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Read};
+
+    use mavlink::{
+        MavHeader,
+        common::{HEARTBEAT_DATA, MavMessage},
+    };
+
+    use super::*;
+    use crate::message::MavMsg;
+
+    #[test]
+    fn pushes_messages_to_connected_clients() {
+        let server = WsServer::bind("127.0.0.1:0").unwrap();
+        let mut client = TcpStream::connect(server.local_addr()).unwrap();
+        client
+            .write_all(
+                b"GET / HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\n\
+                  Connection: Upgrade\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\
+                  Sec-WebSocket-Version: 13\r\n\r\n",
+            )
+            .unwrap();
+        client
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let mut reader = BufReader::new(client);
+        let mut response = String::new();
+        while !response.ends_with("\r\n\r\n") {
+            reader.read_line(&mut response).unwrap();
+        }
+        assert!(response.starts_with("HTTP/1.1 101"));
+
+        // The client is registered just after the response goes out.
+        let heartbeat = Incoming::Message(MavMsg::new(
+            MavHeader::default(),
+            MavMessage::HEARTBEAT(HEARTBEAT_DATA::default()),
+            0,
+        ));
+        let client = reader.get_ref();
+        client
+            .set_read_timeout(Some(Duration::from_millis(100)))
+            .unwrap();
+        let mut header = [0u8; 4];
+        for _ in 0..50 {
+            server.publish(&heartbeat);
+            if client.peek(&mut header).is_ok_and(|n| n > 0) {
+                break;
+            }
+        }
+        client
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        reader.read_exact(&mut header).unwrap();
+        assert_eq!(header[..2], [0x81, 126]);
+        let mut text = vec![0u8; u16::from_be_bytes([header[2], header[3]]) as usize];
+        reader.read_exact(&mut text).unwrap();
+        let text = String::from_utf8(text).unwrap();
+        assert!(text.contains("\"type\":\"HEARTBEAT\""));
+    }
+
+    #[test]
+    fn rejects_requests_that_are_not_upgrades() {
+        let server = WsServer::bind("127.0.0.1:0").unwrap();
+        let mut client = TcpStream::connect(server.local_addr()).unwrap();
+        client
+            .write_all(
+                b"GET / HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\n\
+                  Connection: Upgrade\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n",
+            )
+            .unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 400"), "{response}");
+    }
+
+    #[test]
+    fn answers_pings_and_closes() {
+        let server = WsServer::bind("127.0.0.1:0").unwrap();
+        let stream = TcpStream::connect(server.local_addr()).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let (mut client, _) = tungstenite::client("ws://localhost/", stream).unwrap();
+        client.send(Message::Ping("hi".into())).unwrap();
+        assert_eq!(client.read().unwrap(), Message::Pong("hi".into()));
+        client.close(None).unwrap();
+        loop {
+            match client.read() {
+                Ok(_) => {}
+                Err(tungstenite::Error::ConnectionClosed) => break,
+                Err(e) => panic!("{e}"),
+            }
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use chrono::{DateTime, Utc};
+use mavlink::common::{MavMessage, MavWinchStatusFlag};
+
+use crate::{entries::Report, message::MavMsg, radio::HISTORY};
+
+/// Failover states the winch only enters when a delivery goes wrong.
+const FAILOVER: MavWinchStatusFlag = MavWinchStatusFlag::MAV_WINCH_STATUS_REDELIVER
+    .union(MavWinchStatusFlag::MAV_WINCH_STATUS_ABANDON_LINE);
+
+/// One WINCH_STATUS. Readings the winch does not report are NaN (or `None`
+/// for the temperature).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WinchSample {
+    pub at: DateTime<Utc>,
+    /// Metres of line out.
+    pub line_length: f32,
+    /// m/s, positive paying out.
+    pub speed: f32,
+    /// kg.
+    pub tension: f32,
+    pub voltage: f32,
+    pub current: f32,
+    pub temperature: Option<i16>,
+    pub status: MavWinchStatusFlag,
+}
+
+#[derive(Debug, Default)]
+pub struct Winch {
+    /// Over the last [`HISTORY`], oldest first.
+    pub samples: VecDeque<WinchSample>,
+}
+
+impl Winch {
+    pub fn latest(&self) -> Option<&WinchSample> {
+        self.samples.back()
+    }
+}
+
+/// The winch turning unhealthy or into a failover state, or recovering.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WinchReport {
+    pub winch: (u8, u8),
+    pub from: MavWinchStatusFlag,
+    pub to: MavWinchStatusFlag,
+}
+
+impl Report for WinchReport {
+    fn name(&self) -> &'static str {
+        "WINCH_STATE"
+    }
+
+    fn is_alert(&self) -> bool {
+        in_trouble(self.to)
+    }
+
+    fn source(&self) -> (u8, u8) {
+        self.winch
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "status: {} -> {}",
+            flag_names(self.from).join("|"),
+            flag_names(self.to).join("|")
+        )
+    }
+}
+
+/// WINCH_STATUS history per winch.
+#[derive(Default)]
+pub struct WinchTracker {
+    winches: BTreeMap<(u8, u8), Winch>,
+}
+
+impl WinchTracker {
+    pub fn observe(&mut self, msg: &MavMsg) -> Option<WinchReport> {
+        let MavMessage::WINCH_STATUS(d) = &msg.msg else {
+            return None;
+        };
+        let key = (msg.header.system_id, msg.header.component_id);
+        let winch = self.winches.entry(key).or_default();
+        let previous = winch.latest().map(|s| s.status);
+        winch.samples.push_back(WinchSample {
+            at: msg.timestamp,
+            line_length: d.line_length,
+            speed: d.speed,
+            tension: d.tension,
+            voltage: d.voltage,
+            current: d.current,
+            temperature: (d.temperature != i16::MAX).then_some(d.temperature),
+            status: d.status,
+        });
+        while winch
+            .samples
+            .front()
+            .is_some_and(|s| msg.timestamp - s.at > HISTORY)
+        {
+            winch.samples.pop_front();
+        }
+        let watched = FAILOVER | MavWinchStatusFlag::MAV_WINCH_STATUS_HEALTHY;
+        let from = previous?;
+        (from & watched != d.status & watched).then_some(WinchReport {
+            winch: key,
+            from,
+            to: d.status,
+        })
+    }
+
+    pub fn winches(&self) -> impl Iterator<Item = (&(u8, u8), &Winch)> {
+        self.winches.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.winches.clear();
+    }
+}
+
+/// Unhealthy, or redelivering or abandoning the line.
+pub fn in_trouble(status: MavWinchStatusFlag) -> bool {
+    !status.contains(MavWinchStatusFlag::MAV_WINCH_STATUS_HEALTHY) || status.intersects(FAILOVER)
+}
+
+/// `MAV_WINCH_STATUS_FULLY_RETRACTED` as `fully retracted`.
+pub fn flag_names(status: MavWinchStatusFlag) -> Vec<String> {
+    status
+        .iter_names()
+        .map(|(name, _)| {
+            name.trim_start_matches("MAV_WINCH_STATUS_")
+                .to_lowercase()
+                .replace('_', " ")
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::{MavHeader, common::WINCH_STATUS_DATA};
+
+    use super::*;
+
+    fn status(status: MavWinchStatusFlag, line_length: f32) -> MavMsg {
+        MavMsg::new(
+            MavHeader::default(),
+            MavMessage::WINCH_STATUS(WINCH_STATUS_DATA {
+                line_length,
+                tension: 1.5,
+                voltage: f32::NAN,
+                temperature: i16::MAX,
+                status,
+                ..Default::default()
+            }),
+            0,
+        )
+    }
+
+    #[test]
+    fn keeps_readings_and_flags() {
+        let mut winches = WinchTracker::default();
+        let healthy = MavWinchStatusFlag::MAV_WINCH_STATUS_HEALTHY;
+        let dropping = healthy | MavWinchStatusFlag::MAV_WINCH_STATUS_DROPPING;
+        assert_eq!(winches.observe(&status(healthy, 0.0)), None);
+        // Ordinary operation changes no watched flag.
+        assert_eq!(winches.observe(&status(dropping, 4.5)), None);
+        let (_, winch) = winches.winches().next().unwrap();
+        let latest = winch.latest().unwrap();
+        assert_eq!((latest.line_length, latest.temperature), (4.5, None));
+        assert!(latest.voltage.is_nan());
+        assert_eq!(flag_names(latest.status), ["healthy", "dropping"]);
+        assert_eq!(winch.samples.len(), 2);
+    }
+
+    #[test]
+    fn failover_is_an_alert() {
+        let mut winches = WinchTracker::default();
+        let healthy = MavWinchStatusFlag::MAV_WINCH_STATUS_HEALTHY;
+        winches.observe(&status(healthy, 10.0));
+        let redeliver = winches
+            .observe(&status(
+                healthy | MavWinchStatusFlag::MAV_WINCH_STATUS_REDELIVER,
+                10.0,
+            ))
+            .unwrap();
+        assert!(redeliver.is_alert());
+        assert_eq!(redeliver.describe(), "status: healthy -> healthy|redeliver");
+        let recovered = winches.observe(&status(healthy, 0.0)).unwrap();
+        assert!(!recovered.is_alert());
+    }
+}
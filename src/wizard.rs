@@ -0,0 +1,463 @@
+use std::{collections::BTreeSet, io, sync::Arc, time::Duration};
+
+use crossterm::event::{Event, EventStream, KeyCode, KeyEventKind, KeyModifiers};
+use futures::StreamExt;
+use ratatui::{
+    DefaultTerminal, Frame,
+    layout::{Constraint, Flex, Layout},
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Paragraph},
+};
+use tokio::sync::mpsc::{self, Receiver};
+
+use crate::{
+    app,
+    connection::{self, Link},
+    fallback::Fallback,
+    message::Incoming,
+};
+
+/// Transports offered, with the address each starts from.
+const TRANSPORTS: &[(&str, &str)] = &[
+    ("udpin", "0.0.0.0:14445"),
+    ("udpout", "127.0.0.1:14550"),
+    ("tcpin", "0.0.0.0:5760"),
+    ("tcpout", "127.0.0.1:5760"),
+    ("serial", "/dev/ttyUSB0"),
+    ("file", "capture.bin"),
+    ("pcap", "capture.pcapng"),
+];
+
+const BAUDS: &[u32] = &[
+    9600, 19200, 38400, 57600, 115200, 230400, 460800, 921600, 1500000,
+];
+
+/// The only dialect compiled in; shown so mismatched traffic is explained.
+const DIALECT: &str = "common";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Transport,
+    Address,
+    Baud,
+}
+
+enum Step {
+    Continue,
+    Quit,
+    Start,
+}
+
+/// A connection opened to see whether anything is talking on it.
+struct Probe {
+    uri: String,
+    link: Arc<Link>,
+    rx: Receiver<Incoming>,
+    frames: usize,
+    /// Frames that parsed but are not in [`DIALECT`].
+    mismatched: usize,
+    systems: BTreeSet<u8>,
+}
+
+/// The connection picked in the wizard, with its reader already running.
+pub struct Chosen {
+    pub uri: String,
+    pub link: Arc<Link>,
+    pub rx: Receiver<Incoming>,
+}
+
+/// Picks a connection interactively when none was given or the given one
+/// failed. Each choice is opened on Enter and shows what it receives before
+/// a second Enter starts the capture with it.
+pub struct Wizard {
+    transport: usize,
+    address: String,
+    baud: usize,
+    field: Field,
+    /// Serial devices found at startup, cycled with Left/Right.
+    ports: Vec<String>,
+    error: Option<String>,
+    /// Every connection opened so far. Kept open, since a socket stays bound
+    /// while its reader waits, so going back to one reuses it.
+    probes: Vec<Probe>,
+    /// Link number frames are tagged with.
+    index: usize,
+}
+
+impl Wizard {
+    /// Starts from `uri` if given, showing `error` from connecting to it.
+    pub fn new(uri: Option<&str>, error: Option<String>, index: usize) -> Self {
+        let ports = serialport::available_ports()
+            .map(|ports| ports.into_iter().map(|p| p.port_name).collect())
+            .unwrap_or_default();
+        let mut wizard = Self {
+            transport: 0,
+            address: TRANSPORTS[0].1.to_string(),
+            baud: BAUDS.iter().position(|&b| b == 57600).unwrap_or(0),
+            field: Field::Transport,
+            ports,
+            error,
+            probes: Vec::new(),
+            index,
+        };
+        if let Some(uri) = uri {
+            wizard.set_uri(uri);
+        }
+        wizard
+    }
+
+    fn set_uri(&mut self, uri: &str) {
+        let (protocol, mut address) = uri.split_once(':').unwrap_or((uri, ""));
+        let Some(transport) = TRANSPORTS.iter().position(|&(t, _)| t == protocol) else {
+            return;
+        };
+        self.transport = transport;
+        if self.is_serial()
+            && let Some((port, baud)) = address.rsplit_once(':')
+            && let Some(baud) = BAUDS.iter().position(|b| b.to_string() == baud)
+        {
+            self.baud = baud;
+            address = port;
+        }
+        self.address = address.to_string();
+    }
+
+    fn is_serial(&self) -> bool {
+        TRANSPORTS[self.transport].0 == "serial"
+    }
+
+    fn uri(&self) -> String {
+        let transport = TRANSPORTS[self.transport].0;
+        if self.is_serial() {
+            format!("{transport}:{}:{}", self.address, BAUDS[self.baud])
+        } else {
+            format!("{transport}:{}", self.address)
+        }
+    }
+
+    fn fields(&self) -> &'static [Field] {
+        if self.is_serial() {
+            &[Field::Transport, Field::Address, Field::Baud]
+        } else {
+            &[Field::Transport, Field::Address]
+        }
+    }
+
+    fn probe(&self) -> Option<&Probe> {
+        let uri = self.uri();
+        self.probes.iter().find(|p| p.uri == uri)
+    }
+
+    fn move_field(&mut self, down: bool) {
+        let fields = self.fields();
+        let i = fields.iter().position(|&f| f == self.field).unwrap_or(0);
+        let next = if down {
+            (i + 1) % fields.len()
+        } else {
+            (i + fields.len() - 1) % fields.len()
+        };
+        self.field = fields[next];
+    }
+
+    fn change(&mut self, forward: bool) {
+        let step = |i: usize, len: usize| {
+            if forward {
+                (i + 1) % len
+            } else {
+                (i + len - 1) % len
+            }
+        };
+        match self.field {
+            Field::Transport => {
+                self.transport = step(self.transport, TRANSPORTS.len());
+                self.address = match self.ports.first() {
+                    Some(port) if self.is_serial() => port.clone(),
+                    _ => TRANSPORTS[self.transport].1.to_string(),
+                };
+            }
+            Field::Address if self.is_serial() && !self.ports.is_empty() => {
+                let i = self
+                    .ports
+                    .iter()
+                    .position(|p| *p == self.address)
+                    .map_or(0, |i| step(i, self.ports.len()));
+                self.address = self.ports[i].clone();
+            }
+            Field::Address => {}
+            Field::Baud => self.baud = step(self.baud, BAUDS.len()),
+        }
+    }
+
+    fn handle_key(&mut self, code: KeyCode, modifiers: KeyModifiers) -> Step {
+        match code {
+            KeyCode::Esc => return Step::Quit,
+            KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => return Step::Quit,
+            KeyCode::Enter if self.probe().is_some() => return Step::Start,
+            KeyCode::Enter => self.open(),
+            KeyCode::Up | KeyCode::BackTab => self.move_field(false),
+            KeyCode::Down | KeyCode::Tab => self.move_field(true),
+            KeyCode::Left => self.change(false),
+            KeyCode::Right => self.change(true),
+            KeyCode::Backspace if self.field == Field::Address => {
+                self.address.pop();
+                self.error = None;
+            }
+            KeyCode::Char(c) if self.field == Field::Address => {
+                self.address.push(c);
+                self.error = None;
+            }
+            _ => {}
+        }
+        Step::Continue
+    }
+
+    fn open(&mut self) {
+        let uri = self.uri();
+        match connection::connect(&uri) {
+            Ok(link) => {
+                let (tx, rx) = mpsc::channel(app::CHANNEL_CAPACITY);
+                connection::spawn_reader(Arc::clone(&link), self.index, tx);
+                self.probes.push(Probe {
+                    uri,
+                    link,
+                    rx,
+                    frames: 0,
+                    mismatched: 0,
+                    systems: BTreeSet::new(),
+                });
+                self.error = None;
+            }
+            Err(e) => self.error = Some(e.to_string()),
+        }
+    }
+
+    /// Counts what the open probes have received since the last tick.
+    fn poll(&mut self) {
+        for probe in &mut self.probes {
+            while let Ok(incoming) = probe.rx.try_recv() {
+                match incoming {
+                    Incoming::Message(msg) => {
+                        probe.frames += 1;
+                        probe.systems.insert(msg.header.system_id);
+                    }
+                    // Garbage at a wrong baud rate is not a frame.
+                    Incoming::Diagnostic(diag) if diag.is_error() => {}
+                    Incoming::Diagnostic(diag) => {
+                        probe.frames += 1;
+                        if diag.name == "DIALECT_MISMATCH" {
+                            probe.mismatched += 1;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Hands over the connection on screen; the others are dropped.
+    fn into_chosen(self) -> Option<Chosen> {
+        let uri = self.uri();
+        let probe = self.probes.into_iter().find(|p| p.uri == uri)?;
+        Some(Chosen {
+            uri: probe.uri,
+            link: probe.link,
+            rx: probe.rx,
+        })
+    }
+
+    /// Runs until a connection is started, or returns `None` if the user
+    /// quits instead.
+    pub async fn run(
+        mut self,
+        terminal: &mut DefaultTerminal,
+        fallback: Fallback,
+    ) -> io::Result<Option<Chosen>> {
+        let mut events = EventStream::new();
+        let mut tick = tokio::time::interval(Duration::from_millis(100));
+        loop {
+            tokio::select! {
+                Some(Ok(event)) = events.next() => {
+                    if let Event::Key(key) = event && key.kind == KeyEventKind::Press {
+                        match self.handle_key(key.code, key.modifiers) {
+                            Step::Continue => {}
+                            Step::Quit => return Ok(None),
+                            Step::Start => return Ok(self.into_chosen()),
+                        }
+                    }
+                }
+                _ = tick.tick() => {
+                    self.poll();
+                    terminal.draw(|frame| {
+                        self.draw(frame);
+                        fallback.apply(frame.buffer_mut());
+                    })?;
+                }
+            }
+        }
+    }
+
+    fn status(&self) -> Line<'_> {
+        if let Some(error) = &self.error {
+            return Line::from(Span::styled(error.clone(), Style::default().fg(Color::Red)));
+        }
+        let Some(probe) = self.probe() else {
+            return Line::from(Span::styled(
+                "Press Enter to open this connection",
+                Style::default().fg(Color::DarkGray),
+            ));
+        };
+        if probe.frames == 0 {
+            return Line::from(Span::styled(
+                "Open, waiting for frames...",
+                Style::default().fg(Color::Yellow),
+            ));
+        }
+        let systems: Vec<String> = probe.systems.iter().map(u8::to_string).collect();
+        let mut spans = vec![Span::styled(
+            format!("{} frames detected", probe.frames),
+            Style::default().fg(Color::Green),
+        )];
+        if !systems.is_empty() {
+            spans.push(Span::raw(format!(" from system {}", systems.join(", "))));
+        }
+        if probe.mismatched > 0 {
+            spans.push(Span::styled(
+                format!(" ({} not in the {DIALECT} dialect)", probe.mismatched),
+                Style::default().fg(Color::Yellow),
+            ));
+        }
+        Line::from(spans)
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let key = Style::default().fg(Color::Cyan).bold();
+        let row = |field: Field, label: &'static str, value: String| {
+            let selected = self.field == field;
+            let marker = if selected { "> " } else { "  " };
+            let value = match (selected, field) {
+                (true, Field::Address) => format!("{value}_"),
+                (true, _) => format!("< {value} >"),
+                _ => value,
+            };
+            let style = if selected { key } else { Style::default() };
+            Line::from(vec![
+                Span::styled(marker, key),
+                Span::raw(format!("{label:<11}")),
+                Span::styled(value, style),
+            ])
+        };
+
+        let mut lines = vec![
+            row(
+                Field::Transport,
+                "Transport",
+                TRANSPORTS[self.transport].0.to_string(),
+            ),
+            row(Field::Address, "Address", self.address.clone()),
+        ];
+        if self.is_serial() {
+            lines.push(row(Field::Baud, "Baud", BAUDS[self.baud].to_string()));
+        }
+        lines.push(Line::from(vec![
+            Span::raw(format!("  {:<11}", "Dialect")),
+            Span::styled(DIALECT, Style::default().fg(Color::DarkGray)),
+        ]));
+        if self.is_serial() {
+            let ports = if self.ports.is_empty() {
+                "none found".to_string()
+            } else {
+                self.ports.join(", ")
+            };
+            lines.push(Line::from(Span::styled(
+                format!("  {:<11}{ports}", "Detected"),
+                Style::default().fg(Color::DarkGray),
+            )));
+        }
+        lines.push(Line::default());
+        lines.push(Line::from(format!("  {}", self.uri())));
+        let mut status = self.status();
+        status.spans.insert(0, Span::raw("  "));
+        lines.push(status);
+        lines.push(Line::default());
+        let start = if self.probe().is_some() {
+            " Start  "
+        } else {
+            " Open  "
+        };
+        lines.push(Line::from(vec![
+            Span::styled("  Enter", key),
+            Span::raw(start),
+            Span::styled("\u{2191}\u{2193}", key),
+            Span::raw(" Field  "),
+            Span::styled("\u{2190}\u{2192}", key),
+            Span::raw(" Change  "),
+            Span::styled("Esc", key),
+            Span::raw(" Quit"),
+        ]));
+
+        let [area] = Layout::vertical([Constraint::Length(lines.len() as u16 + 2)])
+            .flex(Flex::Center)
+            .areas(frame.area());
+        let [area] = Layout::horizontal([Constraint::Max(72)])
+            .flex(Flex::Center)
+            .areas(area);
+        let block = Block::bordered().title(" Connect ");
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(wizard: &mut Wizard, code: KeyCode) -> Step {
+        wizard.handle_key(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn starts_from_the_failed_uri() {
+        let wizard = Wizard::new(Some("serial:/dev/ttyACM0:115200"), None, 0);
+        assert!(wizard.is_serial());
+        assert_eq!(wizard.address, "/dev/ttyACM0");
+        assert_eq!(wizard.uri(), "serial:/dev/ttyACM0:115200");
+        let wizard = Wizard::new(Some("tcpout:10.0.0.2:5760"), None, 0);
+        assert_eq!(wizard.uri(), "tcpout:10.0.0.2:5760");
+    }
+
+    #[test]
+    fn editing_builds_the_uri() {
+        let mut wizard = Wizard::new(None, None, 0);
+        assert_eq!(wizard.uri(), "udpin:0.0.0.0:14445");
+        press(&mut wizard, KeyCode::Right);
+        assert_eq!(wizard.uri(), "udpout:127.0.0.1:14550");
+        press(&mut wizard, KeyCode::Down);
+        for _ in 0..5 {
+            press(&mut wizard, KeyCode::Backspace);
+        }
+        for c in "14551".chars() {
+            press(&mut wizard, KeyCode::Char(c));
+        }
+        assert_eq!(wizard.uri(), "udpout:127.0.0.1:14551");
+        // Only serial has a baud field, so Down wraps back to the transport.
+        press(&mut wizard, KeyCode::Down);
+        assert_eq!(wizard.field, Field::Transport);
+    }
+
+    #[test]
+    fn enter_opens_then_starts() {
+        let mut wizard = Wizard::new(Some("udpin:127.0.0.1:0"), None, 2);
+        assert!(matches!(press(&mut wizard, KeyCode::Enter), Step::Continue));
+        assert!(wizard.error.is_none());
+        assert!(matches!(press(&mut wizard, KeyCode::Enter), Step::Start));
+        let chosen = wizard.into_chosen().unwrap();
+        assert_eq!(chosen.uri, "udpin:127.0.0.1:0");
+    }
+
+    #[test]
+    fn failed_open_shows_the_error() {
+        let mut wizard = Wizard::new(Some("file:/nonexistent/mavsnark"), None, 0);
+        assert!(matches!(press(&mut wizard, KeyCode::Enter), Step::Continue));
+        assert!(wizard.error.is_some());
+        assert!(matches!(press(&mut wizard, KeyCode::Esc), Step::Quit));
+    }
+}